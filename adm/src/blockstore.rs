@@ -19,7 +19,7 @@ use protobuf;
 use protobuf::Message;
 
 use crate::database::error::DatabaseError;
-use crate::database::lmdb::LmdbDatabase;
+use crate::database::lmdb::{DatabaseSize, LmdbDatabase};
 use crate::proto::block::{Block, BlockHeader};
 
 pub struct Blockstore<'a> {
@@ -210,6 +210,22 @@ impl<'a> Blockstore<'a> {
         let reader = self.db.reader()?;
         reader.index_count("index_batch")
     }
+
+    /// Get the space used by the blocks and by each index.
+    pub fn get_sizes(&self) -> Result<Vec<(&'static str, DatabaseSize)>, DatabaseError> {
+        let reader = self.db.reader()?;
+        let mut sizes = vec![("blocks", reader.size()?)];
+        for index in &["index_batch", "index_transaction", "index_block_num"] {
+            sizes.push((*index, reader.index_size(index)?));
+        }
+        Ok(sizes)
+    }
+
+    /// Get the number of pages in the blockstore file not used by any records.
+    pub fn get_free_pages(&self) -> Result<usize, DatabaseError> {
+        let reader = self.db.reader()?;
+        reader.free_pages()
+    }
 }
 
 #[cfg(test)]
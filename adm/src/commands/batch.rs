@@ -12,22 +12,32 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::fs::File;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
 use clap::ArgMatches;
+use openssl::base64;
+use protobuf::{CodedInputStream, Message, RepeatedField};
 use sawtooth::client::{
     rest::{RestApiSawtoothClient, RestApiSawtoothClientBuilder},
     Batch, Header, InvalidTransaction, SawtoothClient, SawtoothClientError as ClientError, Status,
     Transaction, TransactionHeader,
 };
 use serde::Serialize;
-use std::time::Duration;
 
 use crate::err::CliError;
+use crate::proto::batch::{Batch as BatchProto, BatchList};
+
+const DEFAULT_URL: &str = "http://localhost:8008";
 
 pub fn run(args: &ArgMatches) -> Result<(), CliError> {
     match args.subcommand() {
         ("list", Some(args)) => run_list(args),
         ("show", Some(args)) => run_show(args),
         ("status", Some(args)) => run_status(args),
+        ("submit", Some(args)) => run_submit(args),
         _ => {
             println!("Invalid subcommand; Pass --help for usage.");
             Ok(())
@@ -36,10 +46,7 @@ pub fn run(args: &ArgMatches) -> Result<(), CliError> {
 }
 
 fn create_client(args: &ArgMatches) -> Result<RestApiSawtoothClient, CliError> {
-    let mut url = args
-        .value_of("url")
-        .unwrap_or("http://localhost:8008")
-        .to_string();
+    let mut url = args.value_of("url").unwrap_or(DEFAULT_URL).to_string();
     if !url.contains("http://") {
         url = format!("http://{}", url);
     }
@@ -193,6 +200,176 @@ fn run_status(args: &ArgMatches) -> Result<(), CliError> {
     Ok(())
 }
 
+fn run_submit(args: &ArgMatches) -> Result<(), CliError> {
+    let filename = args.value_of("filename").unwrap();
+    let wait: Option<Duration> = match args.value_of("wait") {
+        Some(w) => Some(Duration::from_secs(w.parse::<u64>().map_err(|err| {
+            CliError::ArgumentError(format!("Failed to parse wait: {}", err))
+        })?)),
+        None => None,
+    };
+
+    let mut packed = Vec::new();
+    File::open(filename)
+        .and_then(|mut file| file.read_to_end(&mut packed))
+        .map_err(|err| {
+            CliError::EnvironmentError(format!("Failed to read {}: {}", filename, err))
+        })?;
+    let batch_list = read_batch_list(&packed).ok_or_else(|| {
+        CliError::ParseError(format!(
+            "{} does not contain a BatchList or a Batch",
+            filename
+        ))
+    })?;
+
+    let batch_ids = batch_list
+        .get_batches()
+        .iter()
+        .map(|batch| batch.header_signature.clone())
+        .collect::<Vec<String>>();
+    let body = batch_list
+        .write_to_bytes()
+        .map_err(|err| CliError::ParseError(format!("Failed to serialize batches: {}", err)))?;
+
+    post_batches(args, body)?;
+    println!("Submitted {} batch(es)", batch_ids.len());
+
+    let client = create_client(args)?;
+    let statuses = client
+        .list_batch_status(batch_ids.iter().map(|id| &**id).collect(), wait)
+        .map_err(|err| CliError::EnvironmentError(format!("Failed to get batch status: {}", err)))?
+        .unwrap_or_default();
+
+    let mut data = vec![vec![
+        "BATCH_ID".to_string(),
+        "STATUS".to_string(),
+        "MESSAGE".to_string(),
+    ]];
+    for status in statuses {
+        let message = status
+            .invalid_transactions
+            .iter()
+            .map(|txn| txn.message.clone())
+            .collect::<Vec<String>>()
+            .join("; ");
+        data.push(vec![status.id, status.status, message]);
+    }
+    print_table(data);
+
+    Ok(())
+}
+
+/// Reads the batches in a file, which may hold a BatchList, a BatchList
+/// prefixed with its length, or a single Batch.
+fn read_batch_list(packed: &[u8]) -> Option<BatchList> {
+    let is_valid = |batch_list: &BatchList| {
+        !batch_list.get_batches().is_empty() && batch_list.get_batches().iter().all(is_valid_batch)
+    };
+
+    if let Ok(batch_list) = BatchList::parse_from_bytes(packed) {
+        if is_valid(&batch_list) {
+            return Some(batch_list);
+        }
+    }
+
+    let mut input = CodedInputStream::from_bytes(packed);
+    if let Ok(len) = input.read_raw_varint64() {
+        let start = input.pos() as usize;
+        if start as u64 + len == packed.len() as u64 {
+            if let Ok(batch_list) = BatchList::parse_from_bytes(&packed[start..]) {
+                if is_valid(&batch_list) {
+                    return Some(batch_list);
+                }
+            }
+        }
+    }
+
+    match BatchProto::parse_from_bytes(packed) {
+        Ok(ref batch) if is_valid_batch(batch) => {
+            let mut batch_list = BatchList::new();
+            batch_list.set_batches(RepeatedField::from_vec(vec![batch.clone()]));
+            Some(batch_list)
+        }
+        _ => None,
+    }
+}
+
+/// A batch parsed from bytes of some other message will not have a
+/// signature; header signatures are 128 hex characters.
+fn is_valid_batch(batch: &BatchProto) -> bool {
+    batch.header_signature.len() == 128
+        && batch
+            .header_signature
+            .chars()
+            .all(|c| c.is_ascii_hexdigit())
+}
+
+/// Posts a serialized BatchList to the REST API's /batches endpoint.
+fn post_batches(args: &ArgMatches, body: Vec<u8>) -> Result<(), CliError> {
+    let url = args.value_of("url").unwrap_or(DEFAULT_URL);
+    let url = url.trim_start_matches("http://").trim_end_matches('/');
+    let (address, base_path) = match url.find('/') {
+        Some(i) => (&url[..i], &url[i..]),
+        None => (url, ""),
+    };
+    let address = if address.contains(':') {
+        address.to_string()
+    } else {
+        format!("{}:80", address)
+    };
+
+    let mut request = format!(
+        "POST {}/batches HTTP/1.1\r\n\
+         Host: {}\r\n\
+         Content-Type: application/octet-stream\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n",
+        base_path,
+        address,
+        body.len()
+    );
+    if let Some(auth) = args.value_of("username") {
+        let credentials = get_credentials(auth)?;
+        request += &format!(
+            "Authorization: Basic {}\r\n",
+            base64::encode_block(credentials.join(":").as_bytes())
+        );
+    }
+    request += "\r\n";
+
+    let mut response = Vec::new();
+    TcpStream::connect(&address)
+        .and_then(|mut stream| {
+            stream.write_all(request.as_bytes())?;
+            stream.write_all(&body)?;
+            stream.read_to_end(&mut response)
+        })
+        .map_err(|err| CliError::EnvironmentError(format!("Failed to submit batches: {}", err)))?;
+
+    let response = String::from_utf8_lossy(&response);
+    let status = response.lines().next().unwrap_or_default();
+    if status.split(' ').nth(1) == Some("202") {
+        return Ok(());
+    }
+
+    // The REST API describes a rejected submission in the body, e.g.
+    // {"error": {"code": 30, "title": "Submitted Batches Invalid", ...}}
+    let message = response
+        .splitn(2, "\r\n\r\n")
+        .nth(1)
+        .and_then(|body| serde_json::from_str::<serde_json::Value>(body).ok())
+        .and_then(|json| {
+            json["error"]["message"]
+                .as_str()
+                .map(|message| message.to_string())
+        })
+        .unwrap_or_else(|| status.to_string());
+    Err(CliError::EnvironmentError(format!(
+        "Failed to submit batches: {}",
+        message
+    )))
+}
+
 /// Parse batches into rows containing batch id, number of transactions, and signer pulic key
 fn parse_batches_into_rows(
     batches: Box<dyn Iterator<Item = Result<Batch, ClientError>>>,
@@ -419,3 +596,39 @@ impl From<&InvalidTransaction> for DisplayInvalidTransaction {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_batch(signature: &str) -> BatchProto {
+        let mut batch = BatchProto::new();
+        batch.set_header(b"header".to_vec());
+        batch.set_header_signature(signature.repeat(128));
+        batch
+    }
+
+    #[test]
+    fn test_read_batch_list() {
+        let mut batch_list = BatchList::new();
+        batch_list.set_batches(RepeatedField::from_vec(vec![
+            make_batch("a"),
+            make_batch("b"),
+        ]));
+
+        let packed = batch_list.write_to_bytes().unwrap();
+        assert_eq!(Some(batch_list.clone()), read_batch_list(&packed));
+
+        let packed = batch_list.write_length_delimited_to_bytes().unwrap();
+        assert_eq!(Some(batch_list.clone()), read_batch_list(&packed));
+
+        let packed = make_batch("c").write_to_bytes().unwrap();
+        assert_eq!(
+            vec![make_batch("c")],
+            read_batch_list(&packed).unwrap().get_batches()
+        );
+
+        assert_eq!(None, read_batch_list(b"not a batch"));
+        assert_eq!(None, read_batch_list(&[]));
+    }
+}
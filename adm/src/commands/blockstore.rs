@@ -16,8 +16,9 @@
  */
 
 use std::collections::HashMap;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::{self, Read, Write};
+use std::path::Path;
 
 use clap::ArgMatches;
 use protobuf;
@@ -46,6 +47,7 @@ pub fn run<'a>(args: &ArgMatches<'a>) -> Result<(), CliError> {
         ("export", Some(args)) => run_export_command(args),
         ("import", Some(args)) => run_import_command(args),
         ("stats", Some(args)) => run_stats_command(args),
+        ("compact", Some(args)) => run_compact_command(args),
         _ => {
             println!("Invalid subcommand; Pass --help for usage.");
             Ok(())
@@ -409,12 +411,27 @@ fn run_stats_command<'a>(args: &ArgMatches<'a>) -> Result<(), CliError> {
             })?;
         }
 
+        let sizes = blockstore.get_sizes().map_err(|err| {
+            CliError::EnvironmentError(format!("failed to read database sizes: {}", err))
+        })?;
+        let free_pages = blockstore.get_free_pages().map_err(|err| {
+            CliError::EnvironmentError(format!("failed to read free pages: {}", err))
+        })?;
+
         println!("Blocks:       {}", block_count);
         println!("Batches:      {}", batch_count);
         println!("Transactions: {}", txn_count);
         for (family, count) in &txn_family_counts {
             println!("  {}: {}", family, count);
         }
+        println!("Database sizes:");
+        for (name, size) in &sizes {
+            println!(
+                "  {}: {} entries, {} pages, {} bytes",
+                name, size.entries, size.pages, size.bytes
+            );
+        }
+        println!("Free pages:   {}", free_pages);
     } else {
         println!("Blocks:       {}", block_count);
         println!("Batches:      {}", batch_count);
@@ -424,6 +441,51 @@ fn run_stats_command<'a>(args: &ArgMatches<'a>) -> Result<(), CliError> {
     Ok(())
 }
 
+fn run_compact_command<'a>(args: &ArgMatches<'a>) -> Result<(), CliError> {
+    let path_config = config::get_path_config();
+    let blockstore_path = path_config.data_dir.join(config::get_blockstore_filename());
+    let compact_path = match args.value_of("output") {
+        Some(output) => Path::new(output).to_path_buf(),
+        None => blockstore_path.with_extension("lmdb.compact"),
+    };
+
+    if compact_path.exists() {
+        return Err(CliError::EnvironmentError(format!(
+            "{} already exists",
+            compact_path.display()
+        )));
+    }
+
+    {
+        let ctx = create_context()?;
+        ctx.compact(&compact_path).map_err(|err| {
+            CliError::EnvironmentError(format!("failed to compact block store: {}", err))
+        })?;
+    }
+
+    let size_of = |path: &Path| {
+        fs::metadata(path)
+            .map(|metadata| metadata.len())
+            .map_err(|err| {
+                CliError::EnvironmentError(format!("failed to read {}: {}", path.display(), err))
+            })
+    };
+    let original_size = size_of(&blockstore_path)?;
+    let compact_size = size_of(&compact_path)?;
+
+    if args.value_of("output").is_none() {
+        fs::rename(&compact_path, &blockstore_path).map_err(|err| {
+            CliError::EnvironmentError(format!("failed to replace block store: {}", err))
+        })?;
+    }
+
+    println!(
+        "Compacted block store from {} bytes to {} bytes",
+        original_size, compact_size
+    );
+    Ok(())
+}
+
 fn create_context() -> Result<lmdb::LmdbContext, CliError> {
     let path_config = config::get_path_config();
     let blockstore_path = &path_config.data_dir.join(config::get_blockstore_filename());
@@ -0,0 +1,314 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+use std::fmt;
+use std::fs::{metadata, read_to_string};
+use std::iter::repeat;
+use std::os::unix::fs::PermissionsExt;
+
+use clap::ArgMatches;
+use openssl::bn::BigNumContext;
+use openssl::ec::{EcGroup, EcKey, EcPoint, PointConversionForm};
+use openssl::nid::Nid;
+use openssl::pkey::{PKey, Public};
+use openssl::sha::sha256;
+use sawtooth_sdk::signing;
+use sawtooth_sdk::signing::secp256k1::Secp256k1PrivateKey;
+
+use crate::err::CliError;
+
+/// The settings whose values list the public keys allowed to sign
+/// transactions that change settings, roles and policies.
+const AUTHORIZING_SETTINGS: &[&str] = &[
+    "sawtooth.settings.vote.authorized_keys",
+    "sawtooth.identity.allowed_keys",
+];
+
+const SETTINGS_NAMESPACE: &str = "000000";
+const SETTINGS_KEY_PARTS: usize = 4;
+const SETTINGS_PART_SIZE: usize = 16;
+
+pub fn run<'a>(args: &ArgMatches<'a>) -> Result<(), CliError> {
+    match args.subcommand() {
+        ("inspect", Some(args)) => run_inspect(args),
+        _ => {
+            println!("Invalid subcommand; Pass --help for usage.");
+            Ok(())
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum KeyFormat {
+    Hex,
+    Pem,
+}
+
+impl fmt::Display for KeyFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeyFormat::Hex => write!(f, "hex"),
+            KeyFormat::Pem => write!(f, "PEM"),
+        }
+    }
+}
+
+/// The contents of a key file, as hex-encoded secp256k1 keys
+#[derive(Debug, PartialEq)]
+enum KeyFile {
+    Private { format: KeyFormat, key: String },
+    Public { format: KeyFormat, key: String },
+}
+
+fn run_inspect(args: &ArgMatches) -> Result<(), CliError> {
+    let filename = args.value_of("filename").unwrap();
+    let contents = read_to_string(filename).map_err(|err| {
+        CliError::EnvironmentError(format!("Failed to read {}: {}", filename, err))
+    })?;
+
+    let (description, public_key) = match read_key(&contents)? {
+        KeyFile::Private { format, key } => {
+            let context = signing::create_context("secp256k1")
+                .map_err(|err| CliError::EnvironmentError(format!("{}", err)))?;
+            let private_key = Secp256k1PrivateKey::from_hex(&key)
+                .map_err(|err| CliError::ParseError(format!("Invalid private key: {}", err)))?;
+            let public_key = context
+                .get_public_key(&private_key)
+                .map_err(|err| CliError::ParseError(format!("Invalid private key: {}", err)))?;
+
+            check_permissions(filename)?;
+
+            (format!("private key ({})", format), public_key.as_hex())
+        }
+        KeyFile::Public { format, key } => (format!("public key ({})", format), key),
+    };
+
+    println!("file: {}", filename);
+    println!("type: {}", description);
+    println!("public key: {}", public_key);
+    println!();
+    println!("This key may sign settings, role and policy changes if it is listed in:");
+    for setting in AUTHORIZING_SETTINGS {
+        println!("  {} (address {})", setting, setting_address(setting));
+    }
+
+    Ok(())
+}
+
+/// Reads a secp256k1 key from either a hex string, as written by keygen, or
+/// a PEM block, as written by openssl. Public keys are returned compressed.
+fn read_key(contents: &str) -> Result<KeyFile, CliError> {
+    let contents = contents.trim();
+
+    if contents.starts_with("-----BEGIN") {
+        // An encrypted key would make openssl prompt for its passphrase
+        if contents.contains("ENCRYPTED") {
+            return Err(CliError::ParseError(
+                "Encrypted PEM keys are not supported".into(),
+            ));
+        }
+
+        if contents.contains("PUBLIC KEY") {
+            let ec_key = PKey::public_key_from_pem(contents.as_bytes())
+                .and_then(|pkey| pkey.ec_key())
+                .map_err(|err| CliError::ParseError(format!("Invalid PEM public key: {}", err)))?;
+            check_curve(&ec_key.group().curve_name())?;
+            return Ok(KeyFile::Public {
+                format: KeyFormat::Pem,
+                key: compress(ec_key.public_key())?,
+            });
+        }
+
+        let ec_key = PKey::private_key_from_pem(contents.as_bytes())
+            .and_then(|pkey| pkey.ec_key())
+            .map_err(|err| CliError::ParseError(format!("Invalid PEM private key: {}", err)))?;
+        check_curve(&ec_key.group().curve_name())?;
+
+        let mut key = ec_key.private_key().to_vec();
+        while key.len() < 32 {
+            key.insert(0, 0);
+        }
+        return Ok(KeyFile::Private {
+            format: KeyFormat::Pem,
+            key: to_hex(&key),
+        });
+    }
+
+    if !contents.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(CliError::ParseError(
+            "Key is neither hex nor PEM encoded".into(),
+        ));
+    }
+
+    match contents.len() {
+        64 => Ok(KeyFile::Private {
+            format: KeyFormat::Hex,
+            key: contents.to_lowercase(),
+        }),
+        66 | 130 => {
+            let point = public_key_point(contents)?;
+            Ok(KeyFile::Public {
+                format: KeyFormat::Hex,
+                key: compress(&point)?,
+            })
+        }
+        len => Err(CliError::ParseError(format!(
+            "A hex key of {} characters is neither a private nor a public key",
+            len
+        ))),
+    }
+}
+
+fn check_curve(curve: &Option<Nid>) -> Result<(), CliError> {
+    match curve {
+        Some(Nid::SECP256K1) => Ok(()),
+        _ => Err(CliError::ParseError("Key is not a secp256k1 key".into())),
+    }
+}
+
+fn public_key_point(hex: &str) -> Result<EcPoint, CliError> {
+    let bytes = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+        .collect::<Result<Vec<u8>, _>>()
+        .map_err(|err| CliError::ParseError(format!("Invalid public key: {}", err)))?;
+    let group = secp256k1_group()?;
+    let mut ctx = BigNumContext::new().map_err(openssl_error)?;
+    let point = EcPoint::from_bytes(&group, &bytes, &mut ctx)
+        .map_err(|_| CliError::ParseError("Public key is not a point on secp256k1".into()))?;
+    // Check the point is valid by building a key from it
+    EcKey::<Public>::from_public_key(&group, &point).map_err(openssl_error)?;
+    Ok(point)
+}
+
+fn compress(point: &openssl::ec::EcPointRef) -> Result<String, CliError> {
+    let group = secp256k1_group()?;
+    let mut ctx = BigNumContext::new().map_err(openssl_error)?;
+    point
+        .to_bytes(&group, PointConversionForm::COMPRESSED, &mut ctx)
+        .map(|bytes| to_hex(&bytes))
+        .map_err(openssl_error)
+}
+
+fn secp256k1_group() -> Result<EcGroup, CliError> {
+    EcGroup::from_curve_name(Nid::SECP256K1).map_err(openssl_error)
+}
+
+fn openssl_error(err: openssl::error::ErrorStack) -> CliError {
+    CliError::EnvironmentError(format!("{}", err))
+}
+
+/// Warns if a private key file can be read or written by any user; keygen
+/// creates them readable only by their owner and group.
+fn check_permissions(filename: &str) -> Result<(), CliError> {
+    let mode = metadata(filename)
+        .map_err(|err| CliError::EnvironmentError(format!("{}", err)))?
+        .permissions()
+        .mode();
+    if mode & 0o007 != 0 {
+        eprintln!(
+            "warning: {} is accessible by all users (mode {:o}); restrict it with \
+             `chmod 640 {}`",
+            filename,
+            mode & 0o777,
+            filename
+        );
+    }
+    Ok(())
+}
+
+/// The state address of a setting, as computed by the settings family
+fn setting_address(key: &str) -> String {
+    let parts = key
+        .splitn(SETTINGS_KEY_PARTS, '.')
+        .chain(repeat(""))
+        .take(SETTINGS_KEY_PARTS)
+        .map(|part| to_hex(&sha256(part.as_bytes()))[..SETTINGS_PART_SIZE].to_string())
+        .collect::<Vec<_>>();
+    format!("{}{}", SETTINGS_NAMESPACE, parts.join(""))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_hex_keys() {
+        let private_key = "2f1e7b7a130d7ba9da0068b3bb0ba1d79e7e77110302c9f746c3c2a63fe40088";
+        assert_eq!(
+            KeyFile::Private {
+                format: KeyFormat::Hex,
+                key: private_key.into(),
+            },
+            read_key(&format!("{}\n", private_key)).unwrap()
+        );
+
+        let public_key = "026a2c795a9776f75464aa3bda3534c3154a6e91b357b1181d3f515110f84b67c5";
+        assert_eq!(
+            KeyFile::Public {
+                format: KeyFormat::Hex,
+                key: public_key.into(),
+            },
+            read_key(public_key).unwrap()
+        );
+
+        assert!(read_key(&"00".repeat(33)).is_err());
+        assert!(read_key("not a key").is_err());
+        assert!(read_key("abcd").is_err());
+    }
+
+    #[test]
+    fn test_read_pem_keys() {
+        let group = EcGroup::from_curve_name(Nid::SECP256K1).unwrap();
+        let ec_key = EcKey::generate(&group).unwrap();
+        let pem = String::from_utf8(ec_key.private_key_to_pem().unwrap()).unwrap();
+
+        match read_key(&pem).unwrap() {
+            KeyFile::Private {
+                format: KeyFormat::Pem,
+                key,
+            } => assert_eq!(64, key.len()),
+            key => panic!("Expected a PEM private key, got {:?}", key),
+        }
+
+        let pem = String::from_utf8(ec_key.public_key_to_pem().unwrap()).unwrap();
+        assert_eq!(
+            KeyFile::Public {
+                format: KeyFormat::Pem,
+                key: compress(ec_key.public_key()).unwrap(),
+            },
+            read_key(&pem).unwrap()
+        );
+
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let ec_key = EcKey::generate(&group).unwrap();
+        let pem = String::from_utf8(ec_key.private_key_to_pem().unwrap()).unwrap();
+        assert!(read_key(&pem).is_err());
+    }
+
+    #[test]
+    fn test_setting_address() {
+        assert_eq!(
+            "000000a87cb5eafdcca6a8cde0fb0dec1400c5ab274474a6aa82c12840f169a04216b7",
+            setting_address("sawtooth.settings.vote.authorized_keys")
+        );
+    }
+}
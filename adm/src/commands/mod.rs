@@ -20,7 +20,10 @@ pub mod batch;
 
 pub mod blockstore;
 pub mod genesis;
+pub mod key;
 pub mod keygen;
+pub mod state;
+pub mod validator;
 
 #[cfg(feature = "client-cli")]
 pub use sawtooth;
@@ -0,0 +1,137 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+
+use clap::ArgMatches;
+use protobuf::Message;
+
+use crate::blockstore::Blockstore;
+use crate::config;
+use crate::database::lmdb;
+use crate::err::CliError;
+use crate::proto::block::BlockHeader;
+use crate::state::snapshot;
+
+const STATE_INDEXES: [&str; 2] = ["change_log", "duplicate_log"];
+
+pub fn run<'a>(args: &ArgMatches<'a>) -> Result<(), CliError> {
+    match args.subcommand() {
+        ("snapshot", Some(args)) => match args.subcommand() {
+            ("create", Some(args)) => run_snapshot_create_command(args),
+            ("restore", Some(args)) => run_snapshot_restore_command(args),
+            _ => {
+                println!("Invalid subcommand; Pass --help for usage.");
+                Ok(())
+            }
+        },
+        _ => {
+            println!("Invalid subcommand; Pass --help for usage.");
+            Ok(())
+        }
+    }
+}
+
+fn run_snapshot_create_command<'a>(args: &ArgMatches<'a>) -> Result<(), CliError> {
+    let state_root = match args.value_of("state_root") {
+        Some(state_root) => state_root.to_string(),
+        None => chain_head_state_root()?,
+    };
+
+    let ctx = create_context()?;
+    let state_db = open_state_database(&ctx)?;
+    let reader = state_db.reader().map_err(|err| {
+        CliError::EnvironmentError(format!("failed to read state database: {}", err))
+    })?;
+
+    let filepath = args
+        .value_of("output")
+        .ok_or_else(|| CliError::ArgumentError("No output file".into()))?;
+    let file = File::create(filepath)
+        .map_err(|err| CliError::EnvironmentError(format!("Failed to create file: {}", err)))?;
+
+    let count = snapshot::create_snapshot(&reader, &state_root, &mut BufWriter::new(file))
+        .map_err(|err| CliError::EnvironmentError(format!("Failed to create snapshot: {}", err)))?;
+
+    println!("Wrote {} trie nodes at state root {}", count, state_root);
+    Ok(())
+}
+
+fn run_snapshot_restore_command<'a>(args: &ArgMatches<'a>) -> Result<(), CliError> {
+    let ctx = create_context()?;
+    let state_db = open_state_database(&ctx)?;
+
+    let filepath = args
+        .value_of("input")
+        .ok_or_else(|| CliError::ArgumentError("No input file".into()))?;
+    let file = File::open(filepath)
+        .map_err(|err| CliError::EnvironmentError(format!("Failed to open file: {}", err)))?;
+
+    let mut writer = state_db.writer().map_err(|err| {
+        CliError::EnvironmentError(format!("failed to write state database: {}", err))
+    })?;
+    let (state_root, count) = snapshot::restore_snapshot(&mut BufReader::new(file), &mut writer)
+        .map_err(|err| {
+            CliError::EnvironmentError(format!("Failed to restore snapshot: {}", err))
+        })?;
+    writer
+        .commit()
+        .map_err(|err| CliError::EnvironmentError(format!("Failed to commit snapshot: {}", err)))?;
+
+    println!("Restored {} trie nodes at state root {}", count, state_root);
+    Ok(())
+}
+
+fn chain_head_state_root() -> Result<String, CliError> {
+    let path_config = config::get_path_config();
+    let blockstore_path = &path_config.data_dir.join(config::get_blockstore_filename());
+    let ctx = lmdb::LmdbContext::new(blockstore_path, 3, None).map_err(|err| {
+        CliError::EnvironmentError(format!("failed to create block store context: {}", err))
+    })?;
+    let blockstore_db = lmdb::LmdbDatabase::new(
+        &ctx,
+        &["index_batch", "index_transaction", "index_block_num"],
+    )
+    .map_err(|err| CliError::EnvironmentError(format!("failed to open block store DB: {}", err)))?;
+    let blockstore = Blockstore::new(blockstore_db);
+
+    let head_id = blockstore
+        .get_chain_head()
+        .map_err(|err| CliError::EnvironmentError(format!("unable to read chain head: {}", err)))?;
+    let block = blockstore.get(&head_id).map_err(|err| {
+        CliError::EnvironmentError(format!("failed to read block {}: {}", head_id, err))
+    })?;
+    let block_header: BlockHeader = Message::parse_from_bytes(&block.header)
+        .map_err(|err| CliError::ParseError(format!("Unable to read block header: {}", err)))?;
+
+    Ok(block_header.state_root_hash)
+}
+
+fn create_context() -> Result<lmdb::LmdbContext, CliError> {
+    let path_config = config::get_path_config();
+    let state_path = &path_config.data_dir.join(config::get_state_filename());
+
+    lmdb::LmdbContext::new(state_path, STATE_INDEXES.len() as u32, None).map_err(|err| {
+        CliError::EnvironmentError(format!("failed to create state database context: {}", err))
+    })
+}
+
+fn open_state_database(ctx: &lmdb::LmdbContext) -> Result<lmdb::LmdbDatabase, CliError> {
+    lmdb::LmdbDatabase::new(ctx, &STATE_INDEXES)
+        .map_err(|err| CliError::EnvironmentError(format!("failed to open state DB: {}", err)))
+}
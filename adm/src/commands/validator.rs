@@ -0,0 +1,406 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+//! Administrative requests sent to a running validator over its component
+//! endpoint. The validator does not authenticate these, so any process that
+//! can reach the component endpoint may send them.
+
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::ArgMatches;
+use protobuf::Message as M;
+
+use crate::err::CliError;
+use crate::proto::admin::{
+    AdminConsensusEnginesGetRequest, AdminConsensusEnginesGetResponse,
+    AdminConsensusEnginesGetResponse_Engine, AdminConsensusEnginesGetResponse_Status,
+    AdminForksGetRequest, AdminForksGetResponse, AdminForksGetResponse_Fork,
+    AdminForksGetResponse_Status, AdminLogLevelSetRequest, AdminLogLevelSetResponse,
+    AdminLogLevelSetResponse_Status, AdminMetricsGetRequest, AdminMetricsGetResponse,
+    AdminMetricsGetResponse_Status, AdminPublishingPauseRequest, AdminPublishingPauseResponse,
+    AdminPublishingPauseResponse_Status, AdminPublishingResumeRequest,
+    AdminPublishingResumeResponse, AdminPublishingResumeResponse_Status,
+};
+use crate::proto::validator::{Message, Message_MessageType};
+
+const DEFAULT_CONNECT: &str = "tcp://localhost:4004";
+const DEFAULT_TIMEOUT_MS: i32 = 10_000;
+/// The longest the validator lets publishing be paused for, in seconds
+const MAX_PAUSE_TIMEOUT_SECS: u64 = 7 * 24 * 3600;
+
+pub fn run<'a>(args: &ArgMatches<'a>) -> Result<(), CliError> {
+    match args.subcommand() {
+        ("log-level", Some(args)) => run_log_level(args),
+        ("metrics", Some(args)) => run_metrics(args),
+        ("pause-publishing", Some(args)) => run_pause_publishing(args),
+        ("resume-publishing", Some(args)) => run_resume_publishing(args),
+        ("consensus-engines", Some(args)) => run_consensus_engines(args),
+        ("forks", Some(args)) => run_forks(args),
+        _ => {
+            println!("Invalid subcommand; Pass --help for usage.");
+            Ok(())
+        }
+    }
+}
+
+fn run_log_level(args: &ArgMatches) -> Result<(), CliError> {
+    let mut client = AdminClient::new(args.value_of("connect").unwrap_or(DEFAULT_CONNECT))?;
+
+    let mut request = AdminLogLevelSetRequest::new();
+    request.set_logger(args.value_of("logger").unwrap_or("").into());
+    request.set_level(args.value_of("level").unwrap().into());
+
+    let response: AdminLogLevelSetResponse = client.send(
+        Message_MessageType::ADMIN_LOG_LEVEL_SET_REQUEST,
+        Message_MessageType::ADMIN_LOG_LEVEL_SET_RESPONSE,
+        &request,
+    )?;
+
+    match response.get_status() {
+        AdminLogLevelSetResponse_Status::OK => Ok(()),
+        AdminLogLevelSetResponse_Status::INVALID_LEVEL => Err(CliError::ArgumentError(format!(
+            "Invalid log level {}; expected one of TRACE, DEBUG, INFO, WARNING, ERROR or \
+             CRITICAL",
+            request.get_level()
+        ))),
+        status => Err(CliError::EnvironmentError(format!(
+            "Unable to set log level: {:?}",
+            status
+        ))),
+    }
+}
+
+fn run_metrics(args: &ArgMatches) -> Result<(), CliError> {
+    let mut client = AdminClient::new(args.value_of("connect").unwrap_or(DEFAULT_CONNECT))?;
+
+    let mut response: AdminMetricsGetResponse = client.send(
+        Message_MessageType::ADMIN_METRICS_GET_REQUEST,
+        Message_MessageType::ADMIN_METRICS_GET_RESPONSE,
+        &AdminMetricsGetRequest::new(),
+    )?;
+
+    match response.get_status() {
+        AdminMetricsGetResponse_Status::OK => (),
+        AdminMetricsGetResponse_Status::NOT_ENABLED => {
+            return Err(CliError::EnvironmentError(
+                "The validator is not configured to report metrics".into(),
+            ));
+        }
+        status => {
+            return Err(CliError::EnvironmentError(format!(
+                "Unable to get metrics: {:?}",
+                status
+            )));
+        }
+    }
+
+    for mut metric in response.take_metrics() {
+        println!(
+            "{}",
+            format_metric(metric.get_name(), &metric.take_fields())
+        );
+    }
+
+    Ok(())
+}
+
+fn run_pause_publishing(args: &ArgMatches) -> Result<(), CliError> {
+    let mut request = AdminPublishingPauseRequest::new();
+    if let Some(timeout) = args.value_of("timeout") {
+        let secs = timeout.parse::<u64>().map_err(|err| {
+            CliError::ArgumentError(format!("Invalid timeout {}: {}", timeout, err))
+        })?;
+        if secs > MAX_PAUSE_TIMEOUT_SECS {
+            return Err(CliError::ArgumentError(format!(
+                "Invalid timeout {}: at most {} seconds",
+                timeout, MAX_PAUSE_TIMEOUT_SECS
+            )));
+        }
+        request.set_timeout(secs);
+    }
+
+    let mut client = AdminClient::new(args.value_of("connect").unwrap_or(DEFAULT_CONNECT))?;
+    let response: AdminPublishingPauseResponse = client.send(
+        Message_MessageType::ADMIN_PUBLISHING_PAUSE_REQUEST,
+        Message_MessageType::ADMIN_PUBLISHING_PAUSE_RESPONSE,
+        &request,
+    )?;
+
+    match response.get_status() {
+        AdminPublishingPauseResponse_Status::OK => Ok(()),
+        status => Err(CliError::EnvironmentError(format!(
+            "Unable to pause publishing: {:?}",
+            status
+        ))),
+    }
+}
+
+fn run_resume_publishing(args: &ArgMatches) -> Result<(), CliError> {
+    let mut client = AdminClient::new(args.value_of("connect").unwrap_or(DEFAULT_CONNECT))?;
+    let response: AdminPublishingResumeResponse = client.send(
+        Message_MessageType::ADMIN_PUBLISHING_RESUME_REQUEST,
+        Message_MessageType::ADMIN_PUBLISHING_RESUME_RESPONSE,
+        &AdminPublishingResumeRequest::new(),
+    )?;
+
+    match response.get_status() {
+        AdminPublishingResumeResponse_Status::OK => Ok(()),
+        status => Err(CliError::EnvironmentError(format!(
+            "Unable to resume publishing: {:?}",
+            status
+        ))),
+    }
+}
+
+fn run_consensus_engines(args: &ArgMatches) -> Result<(), CliError> {
+    let mut client = AdminClient::new(args.value_of("connect").unwrap_or(DEFAULT_CONNECT))?;
+    let response: AdminConsensusEnginesGetResponse = client.send(
+        Message_MessageType::ADMIN_CONSENSUS_ENGINES_GET_REQUEST,
+        Message_MessageType::ADMIN_CONSENSUS_ENGINES_GET_RESPONSE,
+        &AdminConsensusEnginesGetRequest::new(),
+    )?;
+
+    match response.get_status() {
+        AdminConsensusEnginesGetResponse_Status::OK => (),
+        status => {
+            return Err(CliError::EnvironmentError(format!(
+                "Unable to get consensus engines: {:?}",
+                status
+            )));
+        }
+    }
+
+    for engine in response.get_engines() {
+        println!("{}", format_engine(engine));
+    }
+
+    Ok(())
+}
+
+fn run_forks(args: &ArgMatches) -> Result<(), CliError> {
+    let mut client = AdminClient::new(args.value_of("connect").unwrap_or(DEFAULT_CONNECT))?;
+    let response: AdminForksGetResponse = client.send(
+        Message_MessageType::ADMIN_FORKS_GET_REQUEST,
+        Message_MessageType::ADMIN_FORKS_GET_RESPONSE,
+        &AdminForksGetRequest::new(),
+    )?;
+
+    match response.get_status() {
+        AdminForksGetResponse_Status::OK => (),
+        AdminForksGetResponse_Status::NOT_READY => {
+            return Err(CliError::EnvironmentError(
+                "The validator has no chain head yet".into(),
+            ));
+        }
+        status => {
+            return Err(CliError::EnvironmentError(format!(
+                "Unable to get forks: {:?}",
+                status
+            )));
+        }
+    }
+
+    println!("chain head {}", response.get_chain_head_id());
+    for fork in response.get_forks() {
+        println!("{}", format_fork(fork));
+    }
+
+    Ok(())
+}
+
+/// Formats a fork as its head, length and where it branches from, e.g.
+/// "3 d2f5 Valid length=2 fork_point=a7c0"
+fn format_fork(fork: &AdminForksGetResponse_Fork) -> String {
+    format!(
+        "{} {} {} length={} fork_point={}",
+        fork.get_head_block_num(),
+        fork.get_head_id(),
+        fork.get_head_status(),
+        fork.get_length(),
+        fork.get_fork_point_id()
+    )
+}
+
+/// Formats a consensus engine as its name, version and status, e.g.
+/// "pbft 1.0 active responsive missed_pings=0 connection=7a3b"
+fn format_engine(engine: &AdminConsensusEnginesGetResponse_Engine) -> String {
+    format!(
+        "{} {} {} {} missed_pings={} connection={}",
+        engine.get_name(),
+        engine.get_version(),
+        if engine.get_active() {
+            "active"
+        } else {
+            "inactive"
+        },
+        if engine.get_responsive() {
+            "responsive"
+        } else {
+            "unresponsive"
+        },
+        engine.get_missed_pings(),
+        engine.get_connection_id()
+    )
+}
+
+/// Formats a metric as its name followed by its fields in name order, e.g.
+/// "gossip.peers,host=validator-0 value=3"
+fn format_metric(name: &str, fields: &HashMap<String, f64>) -> String {
+    let mut fields = fields.iter().collect::<Vec<_>>();
+    fields.sort_by(|a, b| a.0.cmp(b.0));
+
+    let mut line = name.to_string();
+    for (field, value) in fields {
+        line.push_str(&format!(" {}={}", field, value));
+    }
+    line
+}
+
+/// Sends requests to a validator's component endpoint, answering any pings
+/// from the validator while waiting for a response.
+struct AdminClient {
+    socket: zmq::Socket,
+    // The socket must not outlive its context
+    _context: zmq::Context,
+}
+
+impl AdminClient {
+    fn new(url: &str) -> Result<AdminClient, CliError> {
+        let context = zmq::Context::new();
+        let socket = context.socket(zmq::DEALER).map_err(zmq_error)?;
+        socket.set_linger(0).map_err(zmq_error)?;
+        socket.set_rcvtimeo(DEFAULT_TIMEOUT_MS).map_err(zmq_error)?;
+        socket.connect(url).map_err(zmq_error)?;
+
+        Ok(AdminClient {
+            socket,
+            _context: context,
+        })
+    }
+
+    fn send<Req: M, Resp: M>(
+        &mut self,
+        request_type: Message_MessageType,
+        response_type: Message_MessageType,
+        request: &Req,
+    ) -> Result<Resp, CliError> {
+        let correlation_id = generate_correlation_id();
+
+        let mut msg = Message::new();
+        msg.set_message_type(request_type);
+        msg.set_correlation_id(correlation_id.clone());
+        msg.set_content(request.write_to_bytes().map_err(protobuf_error)?);
+        self.socket
+            .send(&msg.write_to_bytes().map_err(protobuf_error)?, 0)
+            .map_err(zmq_error)?;
+
+        loop {
+            let bytes = self.socket.recv_bytes(0).map_err(|err| match err {
+                zmq::Error::EAGAIN => CliError::EnvironmentError(
+                    "Timed out waiting for the validator to respond".into(),
+                ),
+                err => zmq_error(err),
+            })?;
+            let mut reply: Message = M::parse_from_bytes(&bytes).map_err(protobuf_error)?;
+
+            if reply.get_message_type() == Message_MessageType::PING_REQUEST {
+                let mut pong = Message::new();
+                pong.set_message_type(Message_MessageType::PING_RESPONSE);
+                pong.set_correlation_id(reply.take_correlation_id());
+                self.socket
+                    .send(&pong.write_to_bytes().map_err(protobuf_error)?, 0)
+                    .map_err(zmq_error)?;
+                continue;
+            }
+
+            if reply.get_correlation_id() != correlation_id {
+                continue;
+            }
+
+            if reply.get_message_type() != response_type {
+                return Err(CliError::EnvironmentError(format!(
+                    "Expected {:?}, received {:?}",
+                    response_type,
+                    reply.get_message_type()
+                )));
+            }
+
+            return M::parse_from_bytes(reply.get_content()).map_err(protobuf_error);
+        }
+    }
+}
+
+fn generate_correlation_id() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    format!("sawadm-{:x}", nanos)
+}
+
+fn zmq_error(err: zmq::Error) -> CliError {
+    CliError::EnvironmentError(format!("Unable to reach the validator: {}", err))
+}
+
+fn protobuf_error(err: protobuf::ProtobufError) -> CliError {
+    CliError::ParseError(format!("{}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_metric() {
+        let mut fields = HashMap::new();
+        fields.insert("mean".to_string(), 0.5);
+        fields.insert("count".to_string(), 3.0);
+
+        assert_eq!(
+            "block_validator.validation,host=test count=3 mean=0.5",
+            format_metric("block_validator.validation,host=test", &fields)
+        );
+        assert_eq!("empty", format_metric("empty", &HashMap::new()));
+    }
+
+    #[test]
+    fn test_format_fork() {
+        let mut fork = AdminForksGetResponse_Fork::new();
+        fork.set_head_id("d2".into());
+        fork.set_head_block_num(3);
+        fork.set_fork_point_id("a".into());
+        fork.set_length(2);
+        fork.set_head_status("Valid".into());
+
+        assert_eq!("3 d2 Valid length=2 fork_point=a", format_fork(&fork));
+    }
+
+    #[test]
+    fn test_format_engine() {
+        let mut engine = AdminConsensusEnginesGetResponse_Engine::new();
+        engine.set_connection_id("abc".into());
+        engine.set_name("pbft".into());
+        engine.set_version("1.0".into());
+        engine.set_missed_pings(4);
+
+        assert_eq!(
+            "pbft 1.0 inactive unresponsive missed_pings=4 connection=abc",
+            format_engine(&engine)
+        );
+    }
+}
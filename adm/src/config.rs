@@ -25,6 +25,7 @@ const DEFAULT_KEY_DIR: &str = "/etc/sawtooth/keys";
 const DEFAULT_POLICY_DIR: &str = "/etc/sawtooth/policy";
 
 const DEFAULT_BLOCKSTORE_FILENAME: &str = "block-00.lmdb";
+const DEFAULT_STATE_FILENAME: &str = "merkle-00.lmdb";
 
 pub struct PathConfig {
     pub config_dir: PathBuf,
@@ -56,3 +57,7 @@ pub fn get_path_config() -> PathConfig {
 pub fn get_blockstore_filename() -> String {
     String::from(DEFAULT_BLOCKSTORE_FILENAME)
 }
+
+pub fn get_state_filename() -> String {
+    String::from(DEFAULT_STATE_FILENAME)
+}
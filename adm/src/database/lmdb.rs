@@ -53,6 +53,38 @@ impl LmdbContext {
         }?;
         Ok(LmdbContext { env })
     }
+
+    /// Writes a compacted copy of the environment to `filepath`. Free pages
+    /// are omitted and records are renumbered sequentially, so the copy is
+    /// usually much smaller than the original.
+    pub fn compact(&self, filepath: &Path) -> Result<(), DatabaseError> {
+        let filepath_str = filepath
+            .to_str()
+            .ok_or_else(|| DatabaseError::InitError(format!("Invalid filepath: {:?}", filepath)))?;
+
+        self.env
+            .copy(filepath_str, lmdb::copy::COMPACT)
+            .map_err(|err| DatabaseError::WriterError(format!("Failed to compact: {}", err)))
+    }
+}
+
+/// The space used by a single database within an environment.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DatabaseSize {
+    pub entries: usize,
+    pub pages: usize,
+    pub bytes: usize,
+}
+
+impl From<lmdb::Stat> for DatabaseSize {
+    fn from(stat: lmdb::Stat) -> Self {
+        let pages = stat.branch_pages + stat.leaf_pages + stat.overflow_pages;
+        DatabaseSize {
+            entries: stat.entries,
+            pages,
+            bytes: pages * stat.psize as usize,
+        }
+    }
 }
 
 pub struct LmdbDatabase<'e> {
@@ -163,6 +195,49 @@ impl<'a> LmdbDatabaseReader<'a> {
             })
             .map(|stat| stat.entries)
     }
+
+    pub fn size(&self) -> Result<DatabaseSize, DatabaseError> {
+        self.txn
+            .db_stat(&self.db.main)
+            .map_err(|err| {
+                DatabaseError::CorruptionError(format!("Failed to get database stats: {}", err))
+            })
+            .map(DatabaseSize::from)
+    }
+
+    pub fn index_size(&self, index: &str) -> Result<DatabaseSize, DatabaseError> {
+        let index = self
+            .db
+            .indexes
+            .get(index)
+            .ok_or_else(|| DatabaseError::ReaderError(format!("Not an index: {}", index)))?;
+        self.txn
+            .db_stat(index)
+            .map_err(|err| {
+                DatabaseError::CorruptionError(format!("Failed to get database stats: {}", err))
+            })
+            .map(DatabaseSize::from)
+    }
+
+    /// Returns the number of pages in the file that are not used by any
+    /// database. These are only reclaimed by compacting the file.
+    pub fn free_pages(&self) -> Result<usize, DatabaseError> {
+        let env = &self.db.ctx.env;
+        let info = env.info().map_err(|err| {
+            DatabaseError::CorruptionError(format!("Failed to get environment info: {}", err))
+        })?;
+        let root = env.stat().map_err(|err| {
+            DatabaseError::CorruptionError(format!("Failed to get environment stats: {}", err))
+        })?;
+
+        // The first two pages of the file hold the environment's meta data
+        let mut used = 2 + DatabaseSize::from(root).pages + self.size()?.pages;
+        for name in self.db.indexes.keys() {
+            used += self.index_size(name)?.pages;
+        }
+
+        Ok((info.last_pgno + 1).saturating_sub(used))
+    }
 }
 
 pub struct LmdbDatabaseReaderCursor<'a> {
@@ -379,4 +454,69 @@ mod tests {
         assert_key_value(5, 6, &database);
         assert_not_in_database(3, &database);
     }
+
+    /// Removes an environment's file and lock file, if they exist.
+    fn remove_environment(path: &Path) {
+        let lock_path = format!("{}-lock", path.display());
+        for file in [path, Path::new(&lock_path)].iter() {
+            if file.exists() {
+                std::fs::remove_file(file).unwrap();
+            }
+        }
+    }
+
+    /// Fills a database, deletes most of it and checks that compacting the
+    /// file leaves only the live records behind.
+    #[test]
+    fn test_lmdb_compact() {
+        let path_config = config::get_path_config();
+
+        let original_path = &path_config.data_dir.join("unit-lmdb-compact.lmdb");
+        let compact_path = &path_config.data_dir.join("unit-lmdb-compacted.lmdb");
+
+        // Compacting refuses to overwrite an existing file, so clear out any
+        // left by an earlier run
+        remove_environment(original_path);
+        remove_environment(compact_path);
+
+        {
+            let ctx = LmdbContext::new(original_path, 3, None).unwrap();
+            let database = LmdbDatabase::new(&ctx, &["a"]).unwrap();
+
+            let mut writer = database.writer().unwrap();
+            for i in 0..1000u32 {
+                writer.put(&i.to_be_bytes(), &[0; 512]).unwrap();
+            }
+            writer.commit().unwrap();
+
+            let mut writer = database.writer().unwrap();
+            for i in 1..1000u32 {
+                writer.delete(&i.to_be_bytes()).unwrap();
+            }
+            writer.commit().unwrap();
+
+            {
+                let reader = database.reader().unwrap();
+                assert_eq!(1, reader.size().unwrap().entries);
+                assert_eq!(0, reader.index_size("a").unwrap().entries);
+                assert!(reader.free_pages().unwrap() > 0);
+            }
+
+            // The copy takes its own read transaction, so no other may be
+            // open on this thread
+            ctx.compact(compact_path).unwrap();
+        }
+
+        {
+            let ctx = LmdbContext::new(compact_path, 3, None).unwrap();
+            let database = LmdbDatabase::new(&ctx, &["a"]).unwrap();
+            let reader = database.reader().unwrap();
+            assert_eq!(1, reader.count().unwrap());
+            assert_eq!(Some(vec![0; 512]), reader.get(&0u32.to_be_bytes()));
+            assert_eq!(0, reader.free_pages().unwrap());
+        }
+
+        remove_environment(original_path);
+        remove_environment(compact_path);
+    }
 }
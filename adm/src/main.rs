@@ -24,9 +24,11 @@ mod config;
 mod database;
 mod err;
 mod proto;
+mod state;
 mod wrappers;
 
 use clap::{clap_app, ArgMatches};
+use sawtooth_logging::{level_from_verbosity, LoggingConfig};
 
 #[cfg(feature = "client-cli")]
 use clap::{AppSettings, Arg, SubCommand};
@@ -36,10 +38,21 @@ const VERSION: &str = env!("CARGO_PKG_VERSION");
 fn main() {
     let args = parse_args();
 
+    let log_level = level_from_verbosity(args.occurrences_of("verbose"));
+    if let Err(err) = LoggingConfig::from_matches(&args, log_level)
+        .and_then(|config| sawtooth_logging::init(&config))
+    {
+        eprintln!("Error: {}", err);
+        std::process::exit(1);
+    }
+
     let result = match args.subcommand() {
         ("blockstore", Some(args)) => commands::blockstore::run(args),
         ("keygen", Some(args)) => commands::keygen::run(args),
+        ("key", Some(args)) => commands::key::run(args),
         ("genesis", Some(args)) => commands::genesis::run(args),
+        ("state", Some(args)) => commands::state::run(args),
+        ("validator", Some(args)) => commands::validator::run(args),
         #[cfg(feature = "client-cli")]
         ("batch", Some(args)) => commands::batch::run(args),
         _ => {
@@ -96,12 +109,23 @@ fn parse_args<'a>() -> ArgMatches<'a> {
                 (@arg blockfile: +required "a protobuf file containing the block to add"))
             (@subcommand stats =>
                 (about: "print out database stats")
-                (@arg extended: -x --extended "show extended stats about the blockstore")))
+                (@arg extended: -x --extended
+                    "show extended stats about the blockstore, including database sizes"))
+            (@subcommand compact =>
+                (about: "rewrite the blockstore into a densely packed file; the validator must be stopped")
+                (@arg output: -o --output +takes_value
+                    "write the compacted copy to this file instead of replacing the blockstore")))
         (@subcommand keygen =>
             (about: "generates keys for the validator to use when signing blocks")
             (@arg key_name: +takes_value "name of the key to create")
             (@arg force: --force "overwrite files if they exist")
             (@arg quiet: -q --quiet "do not display output"))
+        (@subcommand key =>
+            (about: "inspects keys")
+            (@setting SubcommandRequiredElseHelp)
+            (@subcommand inspect =>
+                (about: "validates a hex or PEM key file and displays its public key")
+                (@arg filename: +required +takes_value "the key file to inspect")))
         (@subcommand genesis =>
             (about: "creates the genesis.batch file for initializing the validator")
             (@arg input_file:
@@ -110,6 +134,51 @@ fn parse_args<'a>() -> ArgMatches<'a> {
             (@arg ignore_required_settings: --("ignore-required-settings")
              "skip the check for settings that are required at genesis (necessary if using a
               settings transaction family other than sawtooth_settings)"))
+        (@subcommand state =>
+            (about: "manage the state database directly")
+            (@subcommand snapshot =>
+                (about: "export or import the merkle trie at a single state root")
+                (@subcommand create =>
+                    (about: "write the merkle trie at a state root to a file")
+                    (@arg output: +required "the file to write the snapshot to")
+                    (@arg state_root: --("state-root") +takes_value
+                        "the state root to snapshot (default: the chain head's state root)"))
+                (@subcommand restore =>
+                    (about: "load a snapshot file into the state database")
+                    (@arg input: +required "the file to restore the snapshot from"))))
+        (@subcommand validator =>
+            (about: "sends administrative requests to a running validator")
+            (@setting SubcommandRequiredElseHelp)
+            (@subcommand ("log-level") =>
+                (about: "changes the level of one of the validator's loggers")
+                (@arg level: +required +takes_value
+                    "one of TRACE, DEBUG, INFO, WARNING, ERROR or CRITICAL")
+                (@arg logger: --logger +takes_value
+                    "the logger to change, e.g. sawtooth_validator.journal (default: root logger)")
+                (@arg connect: -C --connect +takes_value
+                    "the validator's component endpoint (default: tcp://localhost:4004)"))
+            (@subcommand metrics =>
+                (about: "displays the current values of the validator's metrics")
+                (@arg connect: -C --connect +takes_value
+                    "the validator's component endpoint (default: tcp://localhost:4004)"))
+            (@subcommand ("pause-publishing") =>
+                (about: "stops the validator from publishing blocks until resumed")
+                (@arg timeout: --timeout +takes_value
+                    "the most seconds to pause for, at most 604800 (default: 3600)")
+                (@arg connect: -C --connect +takes_value
+                    "the validator's component endpoint (default: tcp://localhost:4004)"))
+            (@subcommand ("resume-publishing") =>
+                (about: "lets the validator publish blocks again after pause-publishing")
+                (@arg connect: -C --connect +takes_value
+                    "the validator's component endpoint (default: tcp://localhost:4004)"))
+            (@subcommand ("consensus-engines") =>
+                (about: "lists the registered consensus engines and whether they respond")
+                (@arg connect: -C --connect +takes_value
+                    "the validator's component endpoint (default: tcp://localhost:4004)"))
+            (@subcommand forks =>
+                (about: "lists the forks off the current chain that the validator holds")
+                (@arg connect: -C --connect +takes_value
+                    "the validator's component endpoint (default: tcp://localhost:4004)")))
         (@arg verbose: -v... "increase the logging level.")
     );
 
@@ -231,7 +300,38 @@ fn parse_args<'a>() -> ArgMatches<'a> {
                             .possible_values(&["json", "yaml"])
                             .help("choose the output format (default: yaml)"),
                     ),
+                SubCommand::with_name("submit")
+                    .about(
+                        "Submits the batches in a file to the validator and \
+                            displays their status.",
+                    )
+                    .arg(
+                        Arg::with_name("filename")
+                            .required(true)
+                            .takes_value(true)
+                            .help("file containing a BatchList or a single Batch"),
+                    )
+                    .arg(Arg::with_name("url").long("url").takes_value(true).help(
+                        "identify the URL of the validator's \
+                            REST API (default: http://localhost:8008)",
+                    ))
+                    .arg(
+                        Arg::with_name("username")
+                            .long("user")
+                            .short("u")
+                            .takes_value(true)
+                            .help(
+                                "specify the user to authorize request; \
+                                format: USERNAME[:PASSWORD]",
+                            ),
+                    )
+                    .arg(
+                        Arg::with_name("wait")
+                            .long("wait")
+                            .takes_value(true)
+                            .help("set time, in seconds, to wait for the batches to commit"),
+                    ),
             ]),
     );
-    app.get_matches()
+    sawtooth_logging::add_args(app).get_matches()
 }
@@ -0,0 +1,307 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+//! Streamed snapshots of the merkle trie at a single state root.
+//!
+//! A snapshot is a `StateSnapshotHeader` followed by a `StateSnapshotNode` for
+//! every node reachable from the state root, each written length-delimited,
+//! parents before children.
+
+use std::collections::HashMap;
+use std::io::{Cursor, Read, Write};
+
+use cbor::decoder::GenericDecoder;
+use cbor::value::{Key, Text, Value};
+use openssl::sha::sha512;
+use protobuf::{CodedInputStream, Message};
+
+use crate::database::error::DatabaseError;
+use crate::database::lmdb::{LmdbDatabaseReader, LmdbDatabaseWriter};
+use crate::proto::merkle::{StateSnapshotHeader, StateSnapshotNode};
+
+/// Writes every node reachable from `state_root` to `output`, returning the
+/// number of nodes written.
+pub fn create_snapshot<W: Write>(
+    reader: &LmdbDatabaseReader,
+    state_root: &str,
+    output: &mut W,
+) -> Result<usize, DatabaseError> {
+    let mut header = StateSnapshotHeader::new();
+    header.set_state_root(state_root.into());
+    header
+        .write_length_delimited_to_writer(output)
+        .map_err(|err| DatabaseError::WriterError(format!("{}", err)))?;
+
+    let mut count = 0;
+    let mut pending = vec![state_root.to_string()];
+    while let Some(node_hash) = pending.pop() {
+        let node = reader.get(node_hash.as_bytes()).ok_or_else(|| {
+            DatabaseError::NotFoundError(format!("Trie node not found: {}", node_hash))
+        })?;
+        pending.extend(children(&node)?);
+
+        let mut entry = StateSnapshotNode::new();
+        entry.set_node(node);
+        entry
+            .write_length_delimited_to_writer(output)
+            .map_err(|err| DatabaseError::WriterError(format!("{}", err)))?;
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+/// Reads a snapshot from `input` into the state database, returning the
+/// snapshot's state root and the number of nodes restored.
+///
+/// Each node is checked against the hash its parent refers to it by, and the
+/// snapshot must contain the complete trie, so a truncated or altered file is
+/// rejected. A node referred to more than once, such as two leaves holding
+/// the same value, is expected once per reference. The caller decides whether
+/// to commit `writer`.
+pub fn restore_snapshot<R: Read>(
+    input: &mut R,
+    writer: &mut LmdbDatabaseWriter,
+) -> Result<(String, usize), DatabaseError> {
+    let mut source = CodedInputStream::new(input);
+
+    let header: StateSnapshotHeader = source.read_message().map_err(|err| {
+        DatabaseError::CorruptionError(format!("Unable to read snapshot header: {}", err))
+    })?;
+    let state_root = header.state_root;
+
+    let mut count = 0;
+    // The hashes of the nodes still to be read, with how many times each is
+    // referred to
+    let mut expected: HashMap<String, usize> = HashMap::new();
+    expected.insert(state_root.clone(), 1);
+
+    while !source
+        .eof()
+        .map_err(|err| DatabaseError::ReaderError(format!("Failed to check EOF: {}", err)))?
+    {
+        let entry: StateSnapshotNode = source.read_message().map_err(|err| {
+            DatabaseError::CorruptionError(format!("Unable to read snapshot node: {}", err))
+        })?;
+
+        let node_hash = hash(&entry.node);
+        match expected.get_mut(&node_hash) {
+            Some(references) if *references > 1 => *references -= 1,
+            Some(_) => {
+                expected.remove(&node_hash);
+            }
+            None => {
+                return Err(DatabaseError::CorruptionError(format!(
+                    "Unexpected trie node in snapshot: {}",
+                    node_hash
+                )));
+            }
+        }
+        for child_hash in children(&entry.node)? {
+            *expected.entry(child_hash).or_insert(0) += 1;
+        }
+
+        writer.put(node_hash.as_bytes(), &entry.node)?;
+        count += 1;
+    }
+
+    if !expected.is_empty() {
+        return Err(DatabaseError::CorruptionError(format!(
+            "Snapshot is missing {} trie nodes",
+            expected.values().sum::<usize>()
+        )));
+    }
+
+    Ok((state_root, count))
+}
+
+/// Returns the hashes of a serialized node's children.
+fn children(node: &[u8]) -> Result<Vec<String>, DatabaseError> {
+    let mut decoder = GenericDecoder::new(cbor::Config::default(), Cursor::new(node));
+    let mut root_map = match decoder.value() {
+        Ok(Value::Map(root_map)) => root_map,
+        _ => {
+            return Err(DatabaseError::CorruptionError(
+                "Trie node is not a map".into(),
+            ))
+        }
+    };
+
+    match root_map.remove(&Key::Text(Text::Text("c".into()))) {
+        Some(Value::Map(children)) => children
+            .into_values()
+            .map(|child| match child {
+                Value::Text(Text::Text(child_hash)) => Ok(child_hash),
+                _ => Err(DatabaseError::CorruptionError(
+                    "Invalid trie node child".into(),
+                )),
+            })
+            .collect(),
+        None => Ok(vec![]),
+        Some(_) => Err(DatabaseError::CorruptionError(
+            "Invalid trie node children".into(),
+        )),
+    }
+}
+
+fn hash(bytes: &[u8]) -> String {
+    sha512(bytes)[..32]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::BTreeMap;
+
+    use cbor::Encoder;
+
+    use crate::config;
+    use crate::database::lmdb::{LmdbContext, LmdbDatabase};
+
+    /// Builds a two level trie, snapshots it from one database and restores
+    /// it into another, then checks that a tampered snapshot is rejected.
+    #[test]
+    fn snapshot_round_trip() {
+        let path_config = config::get_path_config();
+
+        let source_ctx = LmdbContext::new(
+            &path_config.data_dir.join("unit-snapshot-source.lmdb"),
+            2,
+            None,
+        )
+        .unwrap();
+        let source = LmdbDatabase::new(&source_ctx, &["change_log", "duplicate_log"]).unwrap();
+
+        let leaf = encode_node(Some(b"value"), &BTreeMap::new());
+        let leaf_hash = hash(&leaf);
+        let mut children = BTreeMap::new();
+        children.insert("ab".to_string(), leaf_hash.clone());
+        let root = encode_node(None, &children);
+        let root_hash = hash(&root);
+
+        let mut writer = source.writer().unwrap();
+        writer.put(leaf_hash.as_bytes(), &leaf).unwrap();
+        writer.put(root_hash.as_bytes(), &root).unwrap();
+        writer.commit().unwrap();
+
+        let mut snapshot = vec![];
+        let count = create_snapshot(&source.reader().unwrap(), &root_hash, &mut snapshot).unwrap();
+        assert_eq!(2, count);
+
+        let target_ctx = LmdbContext::new(
+            &path_config.data_dir.join("unit-snapshot-target.lmdb"),
+            2,
+            None,
+        )
+        .unwrap();
+        let target = LmdbDatabase::new(&target_ctx, &["change_log", "duplicate_log"]).unwrap();
+
+        let mut writer = target.writer().unwrap();
+        let (restored_root, count) =
+            restore_snapshot(&mut Cursor::new(&snapshot), &mut writer).unwrap();
+        writer.commit().unwrap();
+
+        assert_eq!(root_hash, restored_root);
+        assert_eq!(2, count);
+        assert_eq!(
+            Some(leaf),
+            target.reader().unwrap().get(leaf_hash.as_bytes())
+        );
+
+        // Dropping the last byte truncates the leaf node
+        snapshot.pop();
+        let mut writer = target.writer().unwrap();
+        assert!(restore_snapshot(&mut Cursor::new(&snapshot), &mut writer).is_err());
+    }
+
+    /// Snapshots and restores a trie whose root has two leaves holding the
+    /// same value, so both children refer to the same node.
+    #[test]
+    fn snapshot_round_trip_with_equal_siblings() {
+        let path_config = config::get_path_config();
+
+        let source_ctx = LmdbContext::new(
+            &path_config
+                .data_dir
+                .join("unit-snapshot-siblings-source.lmdb"),
+            2,
+            None,
+        )
+        .unwrap();
+        let source = LmdbDatabase::new(&source_ctx, &["change_log", "duplicate_log"]).unwrap();
+
+        let leaf = encode_node(Some(b"value"), &BTreeMap::new());
+        let leaf_hash = hash(&leaf);
+        let mut children = BTreeMap::new();
+        children.insert("ab".to_string(), leaf_hash.clone());
+        children.insert("cd".to_string(), leaf_hash.clone());
+        let root = encode_node(None, &children);
+        let root_hash = hash(&root);
+
+        let mut writer = source.writer().unwrap();
+        writer.put(leaf_hash.as_bytes(), &leaf).unwrap();
+        writer.put(root_hash.as_bytes(), &root).unwrap();
+        writer.commit().unwrap();
+
+        let mut snapshot = vec![];
+        let count = create_snapshot(&source.reader().unwrap(), &root_hash, &mut snapshot).unwrap();
+        assert_eq!(3, count);
+
+        let target_ctx = LmdbContext::new(
+            &path_config
+                .data_dir
+                .join("unit-snapshot-siblings-target.lmdb"),
+            2,
+            None,
+        )
+        .unwrap();
+        let target = LmdbDatabase::new(&target_ctx, &["change_log", "duplicate_log"]).unwrap();
+
+        let mut writer = target.writer().unwrap();
+        let (restored_root, count) =
+            restore_snapshot(&mut Cursor::new(&snapshot), &mut writer).unwrap();
+        writer.commit().unwrap();
+
+        assert_eq!(root_hash, restored_root);
+        assert_eq!(3, count);
+        assert_eq!(
+            Some(leaf),
+            target.reader().unwrap().get(leaf_hash.as_bytes())
+        );
+    }
+
+    fn encode_node(value: Option<&[u8]>, children: &BTreeMap<String, String>) -> Vec<u8> {
+        let mut encoder = Encoder::new(Cursor::new(Vec::new()));
+        encoder.object(2).unwrap();
+        encoder.text("c").unwrap();
+        encoder.object(children.len()).unwrap();
+        for (token, child_hash) in children {
+            encoder.text(token).unwrap();
+            encoder.text(child_hash).unwrap();
+        }
+        encoder.text("v").unwrap();
+        match value {
+            Some(value) => encoder.bytes(value).unwrap(),
+            None => encoder.null().unwrap(),
+        }
+        encoder.into_writer().into_inner()
+    }
+}
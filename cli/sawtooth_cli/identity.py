@@ -151,6 +151,31 @@ def add_identity_parser(subparsers, parent_parser):
         choices=['default', 'csv', 'json', 'yaml'],
         help='choose the output format')
 
+    # policy test
+    test_parser = policy_parsers.add_parser(
+        'test',
+        help='Shows whether a public key is permitted by each role',
+        description='Evaluates the policies currently set in state against '
+        'a public key, showing for each role whether the key is permitted '
+        'and which policy entry decided it.')
+
+    test_parser.add_argument(
+        '--url',
+        type=str,
+        help="identify the URL of a validator's REST API",
+        default='http://localhost:8008')
+
+    test_parser.add_argument(
+        '--format',
+        default='default',
+        choices=['default', 'csv', 'json', 'yaml'],
+        help='choose the output format')
+
+    test_parser.add_argument(
+        'public_key',
+        type=str,
+        help='the public key to evaluate')
+
     # role
     role_parser = identity_parsers.add_parser(
         'role',
@@ -233,6 +258,8 @@ def do_identity(args):
         _do_identity_policy_create(args)
     elif args.subcommand == 'policy' and args.policy_cmd == 'list':
         _do_identity_policy_list(args)
+    elif args.subcommand == 'policy' and args.policy_cmd == 'test':
+        _do_identity_policy_test(args)
     elif args.subcommand == 'role' and args.role_cmd == 'create':
         _do_identity_role_create(args)
     elif args.subcommand == 'role' and args.role_cmd == 'list':
@@ -296,20 +323,7 @@ def _do_identity_policy_create(args):
 
 def _do_identity_policy_list(args):
     rest_client = RestClient(args.url)
-    state = rest_client.list_state(subtree=IDENTITY_NAMESPACE + _POLICY_PREFIX)
-
-    head = state['head']
-    state_values = state['data']
-    printable_policies = []
-    for state_value in state_values:
-        policies_list = PolicyList()
-        decoded = b64decode(state_value['data'])
-        policies_list.ParseFromString(decoded)
-
-        for policy in policies_list.policies:
-            printable_policies.append(policy)
-
-    printable_policies.sort(key=lambda p: p.name)
+    head, printable_policies = _list_policies(rest_client)
 
     if args.format == 'default':
         tty_width = tty.width()
@@ -359,6 +373,120 @@ def _do_identity_policy_list(args):
         raise AssertionError('Unknown format {}'.format(args.format))
 
 
+def _do_identity_policy_test(args):
+    """Executes the 'policy test' subcommand. Evaluates the policy of each
+    role in state, and the default policy, against a public key the same way
+    the validator does: the first entry whose key matches decides, and a key
+    no entry matches is denied.
+    """
+    rest_client = RestClient(args.url)
+    head, policies = _list_policies(rest_client)
+    _, roles = _list_roles(rest_client)
+
+    policies = {policy.name: policy for policy in policies}
+    role_policies = [(role.name, role.policy_name) for role in roles]
+    if 'default' in policies:
+        role_policies.append(('(default)', 'default'))
+
+    results = []
+    for role_name, policy_name in role_policies:
+        policy = policies.get(policy_name)
+        if policy is None:
+            # The validator does not enforce roles with an unset policy
+            decision, reason = 'PERMIT', 'policy not set'
+        else:
+            entry = _evaluate_policy(args.public_key, policy)
+            if entry is None:
+                decision, reason = 'DENY', 'no matching entry'
+            else:
+                decision = 'PERMIT' \
+                    if entry.type == Policy.PERMIT_KEY else 'DENY'
+                reason = Policy.EntryType.Name(entry.type) + ' ' + entry.key
+        results.append((role_name, policy_name, decision, reason))
+
+    if args.format == 'default':
+        if not results:
+            print('No roles or default policy are set; all keys are '
+                  'permitted')
+        for role_name, policy_name, decision, reason in results:
+            print('{}: {} ({}: {})'.format(
+                role_name, decision, policy_name, reason))
+    elif args.format == 'csv':
+        try:
+            writer = csv.writer(sys.stdout, quoting=csv.QUOTE_ALL)
+            writer.writerow(['ROLE', 'POLICY NAME', 'DECISION', 'REASON'])
+            for result in results:
+                writer.writerow(result)
+        except csv.Error:
+            raise CliException('Error writing CSV') from CliException
+    elif args.format == 'json' or args.format == 'yaml':
+        test_snapshot = {
+            'head': head,
+            'public_key': args.public_key,
+            'roles': {
+                role_name: {
+                    'policy': policy_name,
+                    'decision': decision,
+                    'reason': reason,
+                }
+                for role_name, policy_name, decision, reason in results
+            }
+        }
+        if args.format == 'json':
+            print(json.dumps(test_snapshot, indent=2, sort_keys=True))
+        else:
+            print(yaml.dump(test_snapshot, default_flow_style=False)[0:-1])
+    else:
+        raise AssertionError('Unknown format {}'.format(args.format))
+
+
+def _evaluate_policy(public_key, policy):
+    """Returns the first entry of the policy whose key matches the public
+    key, or None if no entry matches.
+    """
+    for entry in policy.entries:
+        if entry.type not in (Policy.PERMIT_KEY, Policy.DENY_KEY):
+            continue
+        if entry.key in (public_key, "*"):
+            return entry
+
+    return None
+
+
+def _list_policies(rest_client):
+    state = rest_client.list_state(subtree=IDENTITY_NAMESPACE + _POLICY_PREFIX)
+
+    policies = []
+    for state_value in state['data']:
+        policies_list = PolicyList()
+        decoded = b64decode(state_value['data'])
+        policies_list.ParseFromString(decoded)
+
+        for policy in policies_list.policies:
+            policies.append(policy)
+
+    policies.sort(key=lambda p: p.name)
+
+    return state['head'], policies
+
+
+def _list_roles(rest_client):
+    state = rest_client.list_state(subtree=IDENTITY_NAMESPACE + _ROLE_PREFIX)
+
+    roles = []
+    for state_value in state['data']:
+        role_list = RoleList()
+        decoded = b64decode(state_value['data'])
+        role_list.ParseFromString(decoded)
+
+        for role in role_list.roles:
+            roles.append(role)
+
+    roles.sort(key=lambda r: r.name)
+
+    return state['head'], roles
+
+
 def _do_identity_role_create(args):
     """Executes the 'role create' subcommand.  Given a key file, a role name,
     and a policy name it generates a batch of sawtooth_identity
@@ -415,20 +543,7 @@ def _do_identity_role_list(args):
     """Lists the current on-chain configuration values.
     """
     rest_client = RestClient(args.url)
-    state = rest_client.list_state(subtree=IDENTITY_NAMESPACE + _ROLE_PREFIX)
-
-    head = state['head']
-    state_values = state['data']
-    printable_roles = []
-    for state_value in state_values:
-        role_list = RoleList()
-        decoded = b64decode(state_value['data'])
-        role_list.ParseFromString(decoded)
-
-        for role in role_list.roles:
-            printable_roles.append(role)
-
-    printable_roles.sort(key=lambda r: r.name)
+    head, printable_roles = _list_roles(rest_client)
 
     if args.format == 'default':
         tty_width = tty.width()
@@ -39,6 +39,7 @@ from sawtooth_cli.rest_client import RestClient
 
 from sawtooth_cli.protobuf.settings_pb2 import SettingsPayload
 from sawtooth_cli.protobuf.settings_pb2 import SettingProposal
+from sawtooth_cli.protobuf.settings_pb2 import SettingMultiProposal
 from sawtooth_cli.protobuf.settings_pb2 import SettingVote
 from sawtooth_cli.protobuf.settings_pb2 import SettingCandidates
 from sawtooth_cli.protobuf.setting_pb2 import Setting
@@ -53,6 +54,9 @@ DISTRIBUTION_NAME = 'sawset'
 
 SETTINGS_NAMESPACE = '000000'
 
+# The settings family version that accepts PROPOSE_MULTIPLE payloads
+MULTI_PROPOSAL_FAMILY_VERSION = '1.1'
+
 _MIN_PRINT_WIDTH = 15
 _MAX_KEY_PARTS = 4
 _ADDRESS_PART_SIZE = 16
@@ -80,13 +84,17 @@ def _do_config_proposal_create(args):
     series of key/value pairs, it generates batches of sawtooth_settings
     transactions in a BatchList instance.  The BatchList is either stored to a
     file or submitted to a validator, depending on the supplied CLI arguments.
+    With --multiple, the settings are proposed together in one transaction.
     """
     settings = [s.split('=', 1) for s in args.setting]
 
     signer = _read_signer(args.key)
 
-    txns = [_create_propose_txn(signer, setting)
-            for setting in settings]
+    if args.multiple:
+        txns = [_create_multi_propose_txn(signer, settings)]
+    else:
+        txns = [_create_propose_txn(signer, setting)
+                for setting in settings]
 
     batch = _create_batch(signer, txns)
 
@@ -123,7 +131,9 @@ def _do_config_proposal_list(args):
         # created it.
         has_pub_key = (not public_key
                        or candidate.votes[0].public_key == public_key)
-        has_prefix = candidate.proposal.setting.startswith(prefix)
+        has_prefix = any(
+            setting.startswith(prefix)
+            for setting, _ in _candidate_settings(candidate))
         return has_prefix and has_pub_key
 
     candidates_payload = _get_proposals(RestClient(args.url))
@@ -134,21 +144,18 @@ def _do_config_proposal_list(args):
 
     if args.format == 'default':
         for candidate in candidates:
-            print('{}: {} => {}'.format(
-                candidate.proposal_id,
-                candidate.proposal.setting,
-                candidate.proposal.value))
+            for setting, value in _candidate_settings(candidate):
+                print('{}: {} => {}'.format(
+                    candidate.proposal_id, setting, value))
     elif args.format == 'csv':
         writer = csv.writer(sys.stdout, quoting=csv.QUOTE_ALL)
         writer.writerow(['PROPOSAL_ID', 'KEY', 'VALUE'])
         for candidate in candidates:
-            writer.writerow([
-                candidate.proposal_id,
-                candidate.proposal.setting,
-                candidate.proposal.value])
+            for setting, value in _candidate_settings(candidate):
+                writer.writerow([candidate.proposal_id, setting, value])
     elif args.format == 'json' or args.format == 'yaml':
         candidates_snapshot = \
-            {c.proposal_id: {c.proposal.setting: c.proposal.value}
+            {c.proposal_id: dict(_candidate_settings(c))
              for c in candidates}
 
         if args.format == 'json':
@@ -188,7 +195,7 @@ def _do_config_proposal_vote(args):
     txn = _create_vote_txn(
         signer,
         args.proposal_id,
-        proposal.proposal.setting,
+        [setting for setting, _ in _candidate_settings(proposal)],
         args.vote_value)
     batch = _create_batch(signer, [txn])
 
@@ -206,12 +213,8 @@ def _do_config_genesis(args):
     if public_key not in authorized_keys:
         authorized_keys.append(public_key)
 
-    txns = []
-
-    txns.append(_create_propose_txn(
-        signer,
-        ('sawtooth.settings.vote.authorized_keys',
-         ','.join(authorized_keys))))
+    settings = [('sawtooth.settings.vote.authorized_keys',
+                 ','.join(authorized_keys))]
 
     if args.approval_threshold is not None:
         if args.approval_threshold < 1:
@@ -222,10 +225,13 @@ def _do_config_genesis(args):
                 'approval threshold must not be greater than the number of '
                 'authorized keys')
 
-        txns.append(_create_propose_txn(
-            signer,
-            ('sawtooth.settings.vote.approval_threshold',
-             str(args.approval_threshold))))
+        settings.append(('sawtooth.settings.vote.approval_threshold',
+                         str(args.approval_threshold)))
+
+    if args.multiple:
+        txns = [_create_multi_propose_txn(signer, settings)]
+    else:
+        txns = [_create_propose_txn(signer, setting) for setting in settings]
 
     batch = _create_batch(signer, txns)
     batch_list = BatchList(batches=[batch])
@@ -262,6 +268,16 @@ def _get_proposals(rest_client):
     return config_candidates
 
 
+def _candidate_settings(candidate):
+    """Returns the (setting, value) pairs that a candidate proposes.
+    """
+    if candidate.HasField('multi_proposal'):
+        return [(proposal.setting, proposal.value)
+                for proposal in candidate.multi_proposal.proposals]
+
+    return [(candidate.proposal.setting, candidate.proposal.value)]
+
+
 def _read_signer(key_filename):
     """Reads the given file as a hex key.
 
@@ -335,12 +351,33 @@ def _create_propose_txn(signer, setting_key_value):
     payload = SettingsPayload(data=proposal.SerializeToString(),
                               action=SettingsPayload.PROPOSE)
 
-    return _make_txn(signer, setting_key, payload)
+    return _make_txn(signer, [setting_key], payload)
+
+
+def _create_multi_propose_txn(signer, settings):
+    """Creates a single sawtooth_settings transaction proposing all of the
+    given (key, value) pairs, which are voted on and changed together.
+    """
+    nonce = hex(random.randint(0, 2**64))
+    proposal = SettingMultiProposal(
+        proposals=[
+            SettingProposal(setting=setting_key, value=setting_value)
+            for setting_key, setting_value in settings
+        ],
+        nonce=nonce)
+    payload = SettingsPayload(data=proposal.SerializeToString(),
+                              action=SettingsPayload.PROPOSE_MULTIPLE)
+
+    return _make_txn(
+        signer,
+        [setting_key for setting_key, _ in settings],
+        payload,
+        family_version=MULTI_PROPOSAL_FAMILY_VERSION)
 
 
-def _create_vote_txn(signer, proposal_id, setting_key, vote_value):
+def _create_vote_txn(signer, proposal_id, setting_keys, vote_value):
     """Creates an individual sawtooth_settings transaction for voting on a
-    proposal for a particular setting key.
+    proposal for the given setting keys.
     """
     if vote_value == 'accept':
         vote_id = SettingVote.ACCEPT
@@ -351,19 +388,19 @@ def _create_vote_txn(signer, proposal_id, setting_key, vote_value):
     payload = SettingsPayload(data=vote.SerializeToString(),
                               action=SettingsPayload.VOTE)
 
-    return _make_txn(signer, setting_key, payload)
+    return _make_txn(signer, setting_keys, payload)
 
 
-def _make_txn(signer, setting_key, payload):
+def _make_txn(signer, setting_keys, payload, family_version='1.0'):
     """Creates and signs a sawtooth_settings transaction with with a payload.
     """
     serialized_payload = payload.SerializeToString()
     header = TransactionHeader(
         signer_public_key=signer.get_public_key().as_hex(),
         family_name='sawtooth_settings',
-        family_version='1.0',
-        inputs=_config_inputs(setting_key),
-        outputs=_config_outputs(setting_key),
+        family_version=family_version,
+        inputs=_config_inputs(setting_keys),
+        outputs=_config_outputs(setting_keys),
         dependencies=[],
         payload_sha512=hashlib.sha512(serialized_payload).hexdigest(),
         batcher_public_key=signer.get_public_key().as_hex()
@@ -375,26 +412,24 @@ def _make_txn(signer, setting_key, payload):
         payload=serialized_payload)
 
 
-def _config_inputs(key):
-    """Creates the list of inputs for a sawtooth_settings transaction, for a
-    given setting key.
+def _config_inputs(keys):
+    """Creates the list of inputs for a sawtooth_settings transaction, for the
+    given setting keys.
     """
     return [
         _key_to_address('sawtooth.settings.vote.proposals'),
         _key_to_address('sawtooth.settings.vote.authorized_keys'),
         _key_to_address('sawtooth.settings.vote.approval_threshold'),
-        _key_to_address(key)
-    ]
+    ] + [_key_to_address(key) for key in keys]
 
 
-def _config_outputs(key):
-    """Creates the list of outputs for a sawtooth_settings transaction, for a
-    given setting key.
+def _config_outputs(keys):
+    """Creates the list of outputs for a sawtooth_settings transaction, for the
+    given setting keys.
     """
     return [
         _key_to_address('sawtooth.settings.vote.proposals'),
-        _key_to_address(key)
-    ]
+    ] + [_key_to_address(key) for key in keys]
 
 
 def _short_hash(in_str):
@@ -514,6 +549,12 @@ def create_parser(prog_name):
         help='specify a public key for the user authorized to submit '
              'config transactions')
 
+    genesis_parser.add_argument(
+        '--multiple',
+        action='store_true',
+        help='propose the settings in a single transaction, which requires '
+             'version 1.1 of the settings transaction processor')
+
     # The following parser is for the `proposal` subcommand group. These
     # commands allow the user to create proposals which may be applied
     # immediately or placed in ballot mode, depending on the current on-chain
@@ -561,6 +602,13 @@ def create_parser(prog_name):
              'for the sabre cli'
     )
 
+    prop_parser.add_argument(
+        '--multiple',
+        action='store_true',
+        help='propose the settings together in a single transaction, so that '
+             'they are voted on and changed together; requires version 1.1 '
+             'of the settings transaction processor')
+
     prop_parser.add_argument(
         'setting',
         type=str,
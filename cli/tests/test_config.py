@@ -22,6 +22,9 @@ import unittest
 
 from sawtooth_cli.protobuf.batch_pb2 import BatchHeader
 from sawtooth_cli.protobuf.batch_pb2 import BatchList
+from sawtooth_cli.protobuf.settings_pb2 import SettingMultiProposal
+from sawtooth_cli.protobuf.settings_pb2 import SettingsPayload
+from sawtooth_cli.protobuf.transaction_pb2 import TransactionHeader
 
 
 PRIV_HEX = \
@@ -69,3 +72,33 @@ class TestConfigBatchlist(unittest.TestCase):
         batch_header = BatchHeader()
         batch_header.ParseFromString(batch_list.batches[0].header)
         self.assertEqual(2, len(batch_header.transaction_ids))
+
+    def test_set_multiple_values_creates_one_transaction(self):
+        subprocess.run(
+            shlex.split(
+                'sawset proposal create --multiple -k {} -o {} x=1 y=2'.format(
+                    self._priv_file,
+                    os.path.join(self._temp_dir, 'myconfig.batch')
+                )
+            ), check=True
+        )
+
+        batch_list = self._read_target_file_as(BatchList)
+
+        self.assertEqual(1, len(batch_list.batches))
+        self.assertEqual(1, len(batch_list.batches[0].transactions))
+
+        txn = batch_list.batches[0].transactions[0]
+        txn_header = TransactionHeader()
+        txn_header.ParseFromString(txn.header)
+        self.assertEqual('1.1', txn_header.family_version)
+
+        payload = SettingsPayload()
+        payload.ParseFromString(txn.payload)
+        self.assertEqual(SettingsPayload.PROPOSE_MULTIPLE, payload.action)
+
+        proposal = SettingMultiProposal()
+        proposal.ParseFromString(payload.data)
+        self.assertEqual(
+            [('x', '1'), ('y', '2')],
+            [(p.setting, p.value) for p in proposal.proposals])
@@ -23,7 +23,7 @@ extern crate sawtooth_sdk;
 extern crate users;
 
 use battleship::client::BattleshipClient;
-use battleship::game::Board;
+use battleship::game::{Board, GameStatus};
 use clap::ArgMatches;
 use failure::Error;
 use prettytable::format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR;
@@ -50,6 +50,19 @@ fn parse_wait_flag(matches: &ArgMatches) -> Result<Option<u64>, Error> {
     }
 }
 
+/// Renders a board, with unknown spaces as dots
+fn board_to_str(board: &[Vec<char>]) -> String {
+    board
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|&i| if i == '?' { '.' } else { i })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 fn run() -> Result<(), Error> {
     let matches = clap_app!(myapp =>
         (name: crate_name!())
@@ -81,11 +94,18 @@ fn run() -> Result<(), Error> {
         )
         (@subcommand list =>
             (about: "List Battleship games")
+            (@arg status: -s --status +takes_value possible_values(&["open", "in-progress", "finished"])
+                "Only list games with this status")
         )
         (@subcommand show =>
             (about: "Show Battleship games")
             (@arg name: +required "Name of the game to show")
         )
+        (@subcommand watch =>
+            (about: "Watch a Battleship game as it is played")
+            (@arg name: +required "Name of the game to watch")
+            (@arg connect: -C --connect +takes_value "Validator endpoint to receive game events from")
+        )
     )
     .get_matches();
 
@@ -129,10 +149,7 @@ fn run() -> Result<(), Error> {
             let name = join_matches.value_of("name").expect("Name is required!");
             let wait = parse_wait_flag(join_matches)?;
 
-            let games = client.list()?;
-            let game = games
-                .get(name)
-                .ok_or_else(|| format_err!("Couldn't get game `{}`!", name))?;
+            let game = client.get_game(name)?;
             let board = Board::load_or_generate(format!("{}-{}", name, key), &game.ships)?;
 
             let link = client.join(name, board.render_hashed())?;
@@ -171,8 +188,12 @@ fn run() -> Result<(), Error> {
                 ),
             }
         }
-        ("list", _) => {
-            let games = client.list()?;
+        ("list", Some(list_matches)) => {
+            let status = match list_matches.value_of("status") {
+                Some(status) => Some(status.parse::<GameStatus>()?),
+                None => None,
+            };
+            let games = client.list_games(status)?;
 
             let mut table = Table::new();
             table.set_format(*FORMAT_NO_BORDER_LINE_SEPARATOR);
@@ -193,25 +214,10 @@ fn run() -> Result<(), Error> {
         ("show", Some(show_matches)) => {
             let name = show_matches.value_of("name").expect("Name is required!");
 
-            let games = client.list()?;
-            let game = games
-                .get(name)
-                .ok_or_else(|| format_err!("Couldn't get game `{}`!", name))?;
+            let game = client.get_game(name)?;
             let board = Board::load(&format!("{}-{}", name, key)).ok();
             let pub_key = client.pub_key()?;
 
-            fn board_to_str(board: &[Vec<char>]) -> String {
-                board
-                    .iter()
-                    .map(|row| {
-                        row.iter()
-                            .map(|&i| if i == '?' { '.' } else { i })
-                            .collect::<String>()
-                    })
-                    .collect::<Vec<_>>()
-                    .join("\n")
-            }
-
             // Shows your board, but with any hits and misses overlaid
             fn overlay_boards(board1: &[Vec<char>], board2: &[Vec<char>]) -> Vec<Vec<char>> {
                 board1
@@ -271,6 +277,26 @@ fn run() -> Result<(), Error> {
                 }
             }
         }
+        ("watch", Some(watch_matches)) => {
+            let name = watch_matches.value_of("name").expect("Name is required!");
+            let client = match watch_matches.value_of("connect") {
+                Some(connect) => client.validator_url(connect),
+                None => client,
+            };
+
+            println!("Watching game `{}`.", name);
+            for update in client.watch_game(name)? {
+                let update = update?;
+                println!("\n{} -> {}", update.action, update.game.state);
+                if let (Some(row), Some(col)) =
+                    (&update.game.last_fire_row, &update.game.last_fire_column)
+                {
+                    println!("Last shot: {}{}", row, col);
+                }
+                println!("Board #1:\n{}\n", board_to_str(&update.game.target_board_1));
+                println!("Board #2:\n{}", board_to_str(&update.game.target_board_2));
+            }
+        }
         _ => println!("other"),
     }
 
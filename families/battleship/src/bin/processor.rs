@@ -17,13 +17,10 @@ extern crate clap;
 extern crate battleship;
 #[macro_use]
 extern crate log;
-extern crate log4rs;
+extern crate sawtooth_logging;
 extern crate sawtooth_sdk;
 
-use log::LevelFilter;
-use log4rs::append::console::ConsoleAppender;
-use log4rs::config::{Appender, Config, Root};
-use log4rs::encode::pattern::PatternEncoder;
+use sawtooth_logging::{level_from_verbosity, LoggingConfig};
 use std::process;
 
 use sawtooth_sdk::processor::TransactionProcessor;
@@ -31,42 +28,25 @@ use sawtooth_sdk::processor::TransactionProcessor;
 use battleship::handler::BattleshipTransactionHandler;
 
 fn main() {
-    let matches = clap_app!(battleship =>
+    let matches = sawtooth_logging::add_args(clap_app!(battleship =>
         (version: crate_version!())
         (about: "Battleship Transaction Processor (Rust)")
         (@arg connect: -C --connect +takes_value "connection endpoint for validator")
         (@arg verbose: -v --verbose +multiple "increase output verbosity")
-    )
+    ))
     .get_matches();
 
     let endpoint = matches
         .value_of("connect")
         .unwrap_or("tcp://localhost:4004");
 
-    let console_log_level = match matches.occurrences_of("verbose") {
-        0 => LevelFilter::Warn,
-        1 => LevelFilter::Info,
-        2 => LevelFilter::Debug,
-        3 | _ => LevelFilter::Trace,
-    };
+    let console_log_level = level_from_verbosity(matches.occurrences_of("verbose"));
 
-    let stdout = ConsoleAppender::builder()
-        .encoder(Box::new(PatternEncoder::new(
-            "{h({l:5.5})} | {({M}:{L}):20.20} | {m}{n}",
-        )))
-        .build();
-
-    let config = match Config::builder()
-        .appender(Appender::builder().build("stdout", Box::new(stdout)))
-        .build(Root::builder().appender("stdout").build(console_log_level))
+    if let Err(err) = LoggingConfig::from_matches(&matches, console_log_level)
+        .and_then(|config| sawtooth_logging::init(&config))
     {
-        Ok(x) => x,
-        Err(_) => process::exit(1),
-    };
-
-    match log4rs::init_config(config) {
-        Ok(_) => (),
-        Err(_) => process::exit(1),
+        eprintln!("{}", err);
+        process::exit(1);
     }
 
     info!("Console logging level: {}", console_log_level);
@@ -15,19 +15,45 @@
 use base64::decode;
 use dirs::home_dir;
 use failure::Error;
-use game::{Action, Game};
-use reqwest::{Client, Url};
+use game::{
+    get_battleship_address, get_battleship_prefix, Action, Game, GameStatus, GAME_UPDATED_EVENT,
+};
+use protobuf::{Message as ProtobufMessage, RepeatedField};
+use rand::distributions::Alphanumeric;
+use rand::{thread_rng, Rng};
+use reqwest::{Client, StatusCode, Url};
+use sawtooth_sdk::messages::batch::BatchList;
+use sawtooth_sdk::messages::client_event::{
+    ClientEventsSubscribeRequest, ClientEventsSubscribeResponse,
+    ClientEventsSubscribeResponse_Status, ClientEventsUnsubscribeRequest,
+};
+use sawtooth_sdk::messages::events::{
+    EventFilter, EventFilter_FilterType, EventList, EventSubscription,
+};
+use sawtooth_sdk::messages::validator::{Message, Message_MessageType};
+use sawtooth_sdk::messaging::stream::{MessageConnection, MessageSender, ReceiveError};
+use sawtooth_sdk::messaging::zmq_stream::{ZmqMessageConnection, ZmqMessageSender};
 use sawtooth_sdk::signing::secp256k1::Secp256k1PrivateKey;
 use sawtooth_sdk::signing::{create_context, PrivateKey, Signer};
 use serde_json::{from_slice, to_vec, Value};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::fs::File;
 use std::io::Read;
 use std::path::PathBuf;
+use std::sync::mpsc::Receiver;
 use std::thread::sleep;
 use std::time::Duration;
 use transaction_builder::TransactionBuilder;
 
+/// The number of state entries to request in each page of a listing
+const PAGE_SIZE: usize = 100;
+
+/// The number of times a request which fails transiently is retried
+const MAX_RETRIES: u32 = 5;
+
+/// The wait before the first retry of a request, which doubles with each retry
+const INITIAL_BACKOFF: Duration = Duration::from_millis(250);
+
 #[derive(Deserialize, Debug)]
 enum TransactionState {
     COMMITTED,
@@ -55,11 +81,96 @@ struct TransactionStatus {
     link: String,
 }
 
+/// The validator endpoint games are watched through, unless another is set
+const DEFAULT_VALIDATOR_URL: &str = "tcp://localhost:4004";
+
+/// An update to a game being watched
+#[derive(Debug, Clone)]
+pub struct GameUpdate {
+    /// The action that updated the game: `CREATE`, `JOIN` or `FIRE`
+    pub action: String,
+    /// The game, as updated
+    pub game: Game,
+}
+
+/// Iterates over the updates to a game as they are committed
+///
+/// Unsubscribes from the game's events when dropped.
+pub struct GameWatcher {
+    sender: ZmqMessageSender,
+    receiver: Receiver<Result<Message, ReceiveError>>,
+    updates: VecDeque<GameUpdate>,
+}
+
+impl GameWatcher {
+    fn receive_events(&mut self) -> Result<bool, Error> {
+        let message = match self.receiver.recv() {
+            Ok(message) => {
+                message.map_err(|err| format_err!("Couldn't receive game events: {:?}", err))?
+            }
+            // The connection to the validator has closed
+            Err(_) => return Ok(false),
+        };
+
+        if message.get_message_type() != Message_MessageType::CLIENT_EVENTS {
+            return Ok(true);
+        }
+
+        let events = EventList::parse_from_bytes(message.get_content())?;
+        for event in events.get_events() {
+            let action = event
+                .get_attributes()
+                .iter()
+                .find(|attribute| attribute.get_key() == "action")
+                .map(|attribute| attribute.get_value().to_string())
+                .unwrap_or_default();
+            let game = from_slice(event.get_data())?;
+
+            self.updates.push_back(GameUpdate { action, game });
+        }
+
+        Ok(true)
+    }
+}
+
+impl Iterator for GameWatcher {
+    type Item = Result<GameUpdate, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.updates.is_empty() {
+            match self.receive_events() {
+                Ok(true) => {}
+                Ok(false) => return None,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+
+        self.updates.pop_front().map(Ok)
+    }
+}
+
+impl Drop for GameWatcher {
+    fn drop(&mut self) {
+        let request = ClientEventsUnsubscribeRequest::new();
+        if let Ok(bytes) = request.write_to_bytes() {
+            if let Err(err) = self.sender.send(
+                Message_MessageType::CLIENT_EVENTS_UNSUBSCRIBE_REQUEST,
+                &generate_correlation_id(),
+                &bytes,
+            ) {
+                debug!("Couldn't unsubscribe from game events: {:?}", err);
+            }
+        }
+        self.sender.close();
+    }
+}
+
 /// Client for the Battleship transaction family
 ///
-/// Handles talking to the REST API
+/// Handles talking to the REST API, and to the validator to watch games
 pub struct BattleshipClient<'a> {
     url: String,
+    validator_url: String,
     key: Box<dyn PrivateKey>,
     client: Client,
     builder: TransactionBuilder<'a>,
@@ -90,6 +201,7 @@ impl<'a> BattleshipClient<'a> {
 
         Ok(BattleshipClient {
             url: url.into(),
+            validator_url: DEFAULT_VALIDATOR_URL.into(),
             key,
             client: Client::new(),
             builder: Self::generate_builder(),
@@ -103,12 +215,19 @@ impl<'a> BattleshipClient<'a> {
     ) -> Result<BattleshipClient<'a>, Error> {
         Ok(BattleshipClient {
             url: url.into(),
+            validator_url: DEFAULT_VALIDATOR_URL.into(),
             key,
             client: Client::new(),
             builder: Self::generate_builder(),
         })
     }
 
+    /// Sets the validator endpoint that games are watched through
+    pub fn validator_url<S: Into<String>>(mut self, validator_url: S) -> Self {
+        self.validator_url = validator_url.into();
+        self
+    }
+
     fn generate_builder() -> TransactionBuilder<'a> {
         TransactionBuilder::new()
             .family_name("battleship")
@@ -134,13 +253,22 @@ impl<'a> BattleshipClient<'a> {
         let context = create_context("secp256k1").map_err(|e| format_err!("{}", e))?;
         let signer = Signer::new(&*context, &*self.key);
 
-        let request_bytes = self
+        let batch_list = self
             .builder
             .clone()
             .payload(to_vec(&action)?)
             .signer(&signer)
-            .build_request_bytes()?;
+            .build_batch_list()?;
+
+        let submitted = self.find_submitted_transactions(&batch_list)?;
+        if !submitted.is_empty() {
+            return Err(format_err!(
+                "Transactions were already submitted: {}",
+                submitted.join(", ")
+            ));
+        }
 
+        let request_bytes = batch_list.write_to_bytes()?;
         let response: Value = self
             .client
             .post(&(self.url.clone() + "batches"))
@@ -158,6 +286,39 @@ impl<'a> BattleshipClient<'a> {
             .into())
     }
 
+    /// Returns the ids of the transactions in the batch list which the REST API
+    /// already has committed
+    ///
+    /// Checked before submitting, so that a client retrying a submission with
+    /// the same nonces does not create duplicate transactions.
+    pub fn find_submitted_transactions(
+        &self,
+        batch_list: &BatchList,
+    ) -> Result<Vec<String>, Error> {
+        let mut submitted = vec![];
+        for batch in batch_list.get_batches() {
+            for transaction in batch.get_transactions() {
+                if self.transaction_exists(transaction.get_header_signature())? {
+                    submitted.push(transaction.get_header_signature().to_string());
+                }
+            }
+        }
+
+        Ok(submitted)
+    }
+
+    /// Whether the REST API has committed the transaction with the given id
+    pub fn transaction_exists(&self, transaction_id: &str) -> Result<bool, Error> {
+        let url = self.get_path(&format!("/transactions/{}", transaction_id))?;
+        match self.get_json(&url) {
+            Ok(_) => Ok(true),
+            Err(err) => match err.downcast_ref::<reqwest::Error>() {
+                Some(err) if err.status() == Some(StatusCode::NOT_FOUND) => Ok(false),
+                _ => Err(err),
+            },
+        }
+    }
+
     /// Creates a game
     pub fn create(&self, name: &str, ships: Vec<String>) -> Result<String, Error> {
         self.send_action(&Action::Create {
@@ -194,33 +355,137 @@ impl<'a> BattleshipClient<'a> {
 
     /// Lists games
     pub fn list(&self) -> Result<HashMap<String, Game>, Error> {
-        let response: Value = self
-            .client
-            .get(&self.get_path("/state?address=6e10df")?)
-            .send()?
-            .json()?;
+        self.list_games(None)
+    }
+
+    /// Lists games, optionally only those with the given status
+    ///
+    /// Fetches every page of the battleship namespace in turn, so that listings
+    /// aren't cut short on chains with many games.
+    pub fn list_games(
+        &self,
+        status_filter: Option<GameStatus>,
+    ) -> Result<HashMap<String, Game>, Error> {
+        let mut games = HashMap::new();
+        let mut next = Some(self.get_path(&format!(
+            "/state?address={}&limit={}",
+            get_battleship_prefix(),
+            PAGE_SIZE
+        ))?);
 
-        Ok(response["data"]
-            .as_array()
-            .unwrap()
-            .iter()
-            .map(|json| {
-                let entry: HashMap<String, Game> =
-                    from_slice(&decode(json["data"].as_str().unwrap()).unwrap()).unwrap();
-                let key = entry.keys().next().unwrap();
-                (key.clone(), entry[key].clone())
-            })
-            .collect())
+        while let Some(url) = next {
+            let response = self.get_json(&url)?;
+
+            for entry in response["data"]
+                .as_array()
+                .ok_or_else(|| format_err!("State listing is missing its data"))?
+            {
+                let data = entry["data"]
+                    .as_str()
+                    .ok_or_else(|| format_err!("State entry is missing its data"))?;
+                games.extend(decode_games(data)?.into_iter().filter(|(_, game)| {
+                    status_filter.map_or(true, |status| game.status() == status)
+                }));
+            }
+
+            next = response["paging"]["next"].as_str().map(String::from);
+        }
+
+        Ok(games)
     }
 
     /// Gets a particular game from the list
     pub fn get_game(&self, name: &str) -> Result<Game, Error> {
-        let games = self.list()?;
+        let url = self.get_path(&format!("/state/{}", get_battleship_address(name)))?;
+        let response = match self.get_json(&url) {
+            Ok(response) => response,
+            Err(err) => match err.downcast_ref::<reqwest::Error>() {
+                Some(err) if err.status() == Some(StatusCode::NOT_FOUND) => {
+                    return Err(format_err!("Game `{}` not found!", name));
+                }
+                _ => return Err(err),
+            },
+        };
+
+        let data = response["data"]
+            .as_str()
+            .ok_or_else(|| format_err!("State entry is missing its data"))?;
+
+        decode_games(data)?
+            .remove(name)
+            .ok_or_else(|| format_err!("Game `{}` not found!", name))
+    }
 
-        Ok(games
-            .get(name)
-            .ok_or_else(|| format_err!("Game `{}` not found!", name))?
-            .clone())
+    /// Gets a JSON response, retrying with backoff if the request fails
+    /// transiently, such as when the REST API is unreachable or overloaded
+    fn get_json(&self, url: &str) -> Result<Value, Error> {
+        let mut backoff = INITIAL_BACKOFF;
+        let mut retries = 0;
+
+        loop {
+            let result = self
+                .client
+                .get(url)
+                .send()
+                .and_then(|response| response.error_for_status());
+
+            match result {
+                Ok(mut response) => return Ok(response.json()?),
+                Err(ref err) if retries < MAX_RETRIES && is_transient(err) => {
+                    debug!("Retrying {} in {:?}: {}", url, backoff, err);
+                    sleep(backoff);
+                    backoff *= 2;
+                    retries += 1;
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+
+    /// Watches a game, yielding each update to it as it is committed
+    ///
+    /// Subscribes to the game's events through the validator's event system, so
+    /// that spectators see each action without polling state.
+    pub fn watch_game(&self, name: &str) -> Result<GameWatcher, Error> {
+        let connection = ZmqMessageConnection::new(&self.validator_url);
+        let (sender, receiver) = connection.create();
+
+        let mut filter = EventFilter::new();
+        filter.set_key("name".into());
+        filter.set_match_string(name.into());
+        filter.set_filter_type(EventFilter_FilterType::SIMPLE_ALL);
+
+        let mut subscription = EventSubscription::new();
+        subscription.set_event_type(GAME_UPDATED_EVENT.into());
+        subscription.set_filters(RepeatedField::from_vec(vec![filter]));
+
+        let mut request = ClientEventsSubscribeRequest::new();
+        request.set_subscriptions(RepeatedField::from_vec(vec![subscription]));
+
+        let mut future = sender
+            .send(
+                Message_MessageType::CLIENT_EVENTS_SUBSCRIBE_REQUEST,
+                &generate_correlation_id(),
+                &request.write_to_bytes()?,
+            )
+            .map_err(|err| format_err!("Couldn't subscribe to game events: {:?}", err))?;
+        let response = future
+            .get()
+            .map_err(|err| format_err!("Couldn't subscribe to game events: {:?}", err))?;
+        let response = ClientEventsSubscribeResponse::parse_from_bytes(response.get_content())?;
+
+        match response.get_status() {
+            ClientEventsSubscribeResponse_Status::OK => Ok(GameWatcher {
+                sender,
+                receiver,
+                updates: VecDeque::new(),
+            }),
+            status => Err(format_err!(
+                "Couldn't subscribe to game events: {:?} {}",
+                status,
+                response.get_response_message()
+            )),
+        }
     }
 
     /// Waits for transaction to complete
@@ -244,3 +509,21 @@ impl<'a> BattleshipClient<'a> {
         Err(format_err!("Waited too long for transaction to complete!"))
     }
 }
+
+/// Decodes a battleship state entry, which maps game names to games so that
+/// games whose addresses collide can share an entry
+fn decode_games(data: &str) -> Result<HashMap<String, Game>, Error> {
+    Ok(from_slice(&decode(data)?)?)
+}
+
+/// Whether a failed request may succeed if it is retried
+fn is_transient(err: &reqwest::Error) -> bool {
+    match err.status() {
+        Some(status) => status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS,
+        None => err.is_timeout() || err.is_http(),
+    }
+}
+
+fn generate_correlation_id() -> String {
+    thread_rng().sample_iter(&Alphanumeric).take(16).collect()
+}
@@ -19,8 +19,17 @@ use failure::Error;
 use rand::distributions::Alphanumeric;
 use rand::{thread_rng, Rng};
 use serde_json;
+use std::fmt;
 use std::fs::File;
 use std::path::PathBuf;
+use std::str::FromStr;
+
+/// The type of the event emitted whenever a game is created, joined or fired on
+///
+/// The event's `name`, `action` and `state` attributes are the game's name, the
+/// action that updated it (`CREATE`, `JOIN` or `FIRE`) and its new state. Its
+/// data is the game, serialized as it is in state.
+pub const GAME_UPDATED_EVENT: &str = "battleship/game-updated";
 
 // Convenience functions
 
@@ -133,6 +142,15 @@ impl Game {
             (Some(_), None) | (None, Some(_)) => Err(format_err!("Game is in invalid state!"))?,
         })
     }
+
+    /// Gets whether the game is open to players, in progress, or finished
+    pub fn status(&self) -> GameStatus {
+        match self.state.as_str() {
+            "NEW" => GameStatus::Open,
+            "P1-WIN" | "P2-WIN" => GameStatus::Finished,
+            _ => GameStatus::InProgress,
+        }
+    }
 }
 
 impl Default for Game {
@@ -152,6 +170,43 @@ impl Default for Game {
     }
 }
 
+/// The stage a `Game` is at, as used to filter listings of games.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameStatus {
+    /// Waiting for players to join
+    Open,
+    /// Both players have joined, and neither has won
+    InProgress,
+    /// A player has won
+    Finished,
+}
+
+impl FromStr for GameStatus {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "open" => Ok(GameStatus::Open),
+            "in-progress" => Ok(GameStatus::InProgress),
+            "finished" => Ok(GameStatus::Finished),
+            _ => Err(format_err!(
+                "Unknown game status `{}`, expected open, in-progress or finished",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for GameStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            GameStatus::Open => "open",
+            GameStatus::InProgress => "in-progress",
+            GameStatus::Finished => "finished",
+        })
+    }
+}
+
 /// An action that updates a `Game`.
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(tag = "Action")]
@@ -337,3 +392,32 @@ impl Board {
             .join("\n")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn game_status() {
+        let mut game = Game::default();
+        assert_eq!(game.status(), GameStatus::Open);
+
+        game.state = "P2-NEXT".into();
+        assert_eq!(game.status(), GameStatus::InProgress);
+
+        game.state = "P1-WIN".into();
+        assert_eq!(game.status(), GameStatus::Finished);
+    }
+
+    #[test]
+    fn parse_game_status() {
+        for status in &[
+            GameStatus::Open,
+            GameStatus::InProgress,
+            GameStatus::Finished,
+        ] {
+            assert_eq!(status.to_string().parse::<GameStatus>().unwrap(), *status);
+        }
+        assert!("won".parse::<GameStatus>().is_err());
+    }
+}
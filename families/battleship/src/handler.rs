@@ -59,10 +59,12 @@ impl BattleshipTransactionHandler {
         }
     }
 
-    /// Stores a single game
+    /// Stores a single game, and emits an event so that spectators see the
+    /// action that updated it
     fn store_game(
         context: &mut dyn TransactionContext,
         name: &str,
+        action: &str,
         game: &game::Game,
     ) -> Result<(), ApplyError> {
         let address = game::get_battleship_address(name);
@@ -72,9 +74,23 @@ impl BattleshipTransactionHandler {
         })?;
 
         context
-            .set_state_entry(address, serialized.into())
+            .set_state_entry(address, serialized.clone().into())
             .map_err(|err| {
                 ApplyError::InvalidTransaction(format!("Couldn't set new game state: {}", err))
+            })?;
+
+        context
+            .add_event(
+                game::GAME_UPDATED_EVENT.to_string(),
+                vec![
+                    ("name".to_string(), name.to_string()),
+                    ("action".to_string(), action.to_string()),
+                    ("state".to_string(), game.state.clone()),
+                ],
+                serialized.as_bytes(),
+            )
+            .map_err(|err| {
+                ApplyError::InternalError(format!("Couldn't add game updated event: {}", err))
             })
     }
 
@@ -109,6 +125,7 @@ impl BattleshipTransactionHandler {
         BattleshipTransactionHandler::store_game(
             context,
             name,
+            "CREATE",
             &game::Game {
                 ships,
                 ..Default::default()
@@ -183,7 +200,7 @@ impl BattleshipTransactionHandler {
             (true, true) => Err(ApplyError::InvalidTransaction(String::from("Game is full")))?,
         }
 
-        BattleshipTransactionHandler::store_game(context, name, &game)
+        BattleshipTransactionHandler::store_game(context, name, "JOIN", &game)
     }
 
     /// Handles FIRE action
@@ -320,7 +337,7 @@ impl BattleshipTransactionHandler {
             )))?,
         };
 
-        BattleshipTransactionHandler::store_game(context, name, &game)
+        BattleshipTransactionHandler::store_game(context, name, "FIRE", &game)
     }
 }
 
@@ -16,10 +16,15 @@ use crypto::digest::Digest;
 use crypto::sha2::Sha512;
 use failure::{Backtrace, Context, Fail};
 use protobuf::{Message, ProtobufError, RepeatedField};
+use rand::distributions::Alphanumeric;
+use rand::{thread_rng, Rng};
 use sawtooth_sdk::messages::batch::{Batch, BatchHeader, BatchList};
 use sawtooth_sdk::messages::transaction::{Transaction, TransactionHeader};
 use sawtooth_sdk::signing::{Error as SigningError, Signer};
 use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 #[derive(Debug)]
@@ -37,6 +42,9 @@ pub enum ErrorKind {
 
     #[fail(display = "{}", _0)]
     SerializationError(String),
+
+    #[fail(display = "Unable to generate nonce: {}", _0)]
+    NonceError(String),
 }
 
 impl Fail for Error {
@@ -91,12 +99,81 @@ impl From<ProtobufError> for Error {
     }
 }
 
+/// Generates the nonces of transactions, which distinguish otherwise
+/// identical transactions
+pub trait NonceStrategy {
+    fn next_nonce(&self) -> Result<String, Error>;
+}
+
+/// Nonces of random alphanumeric characters
+pub struct RandomNonce;
+
+impl NonceStrategy for RandomNonce {
+    fn next_nonce(&self) -> Result<String, Error> {
+        Ok(thread_rng().sample_iter(&Alphanumeric).take(32).collect())
+    }
+}
+
+/// Nonces of the current time in seconds since the epoch; transactions with
+/// the same contents built within the same second are identical
+pub struct TimestampNonce;
+
+impl NonceStrategy for TimestampNonce {
+    fn next_nonce(&self) -> Result<String, Error> {
+        Ok(SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|err| ErrorKind::NonceError(err.to_string()))?
+            .as_secs()
+            .to_string())
+    }
+}
+
+/// Nonces of a counter which is kept in a file, so that it keeps counting up
+/// across runs of a client
+///
+/// The file is read and rewritten for each nonce, so it must not be shared by
+/// clients running at the same time.
+pub struct CounterFileNonce {
+    path: PathBuf,
+}
+
+impl CounterFileNonce {
+    /// Counts from the value in the file at path, or from zero if there is no
+    /// such file
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        CounterFileNonce { path: path.into() }
+    }
+}
+
+impl NonceStrategy for CounterFileNonce {
+    fn next_nonce(&self) -> Result<String, Error> {
+        let counter = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents.trim().parse::<u64>().map_err(|err| {
+                ErrorKind::NonceError(format!("{}: {}", self.path.display(), err))
+            })?,
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => 0,
+            Err(err) => {
+                return Err(
+                    ErrorKind::NonceError(format!("{}: {}", self.path.display(), err)).into(),
+                );
+            }
+        };
+
+        let next = counter + 1;
+        fs::write(&self.path, next.to_string())
+            .map_err(|err| ErrorKind::NonceError(format!("{}: {}", self.path.display(), err)))?;
+
+        Ok(next.to_string())
+    }
+}
+
 #[derive(Clone, Default)]
 pub struct TransactionBuilder<'a> {
     family_name: Option<String>,
     family_version: Option<String>,
     inputs: Option<Vec<String>>,
     nonce: Option<u64>,
+    nonce_strategy: Option<&'a dyn NonceStrategy>,
     outputs: Option<Vec<String>>,
     payload: Option<Vec<u8>>,
     signer: Option<&'a Signer<'a>>,
@@ -124,6 +201,13 @@ impl<'a> TransactionBuilder<'a> {
         self
     }
 
+    /// Sets how the nonce is generated if none is given with `nonce`;
+    /// defaults to `TimestampNonce`
+    pub fn nonce_strategy(mut self, strategy: &'a dyn NonceStrategy) -> Self {
+        self.nonce_strategy = Some(strategy);
+        self
+    }
+
     pub fn input<S: Into<String>>(mut self, input: S) -> Self {
         self.inputs.get_or_insert_with(Vec::new).push(input.into());
         self
@@ -186,14 +270,15 @@ impl<'a> TransactionBuilder<'a> {
             .family_version
             .ok_or_else(|| ErrorKind::MissingInfo("Family Version".into()))?;
 
-        let default_nonce = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("Time went backwards!")
-            .as_secs();
+        let nonce = match (self.nonce, self.nonce_strategy) {
+            (Some(nonce), _) => nonce.to_string(),
+            (None, Some(strategy)) => strategy.next_nonce()?,
+            (None, None) => TimestampNonce.next_nonce()?,
+        };
 
         txn_header.set_family_name(family_name.clone());
         txn_header.set_family_version(family_version.clone());
-        txn_header.set_nonce(self.nonce.unwrap_or(default_nonce).to_string());
+        txn_header.set_nonce(nonce);
         txn_header.set_signer_public_key(signer.get_public_key()?.as_hex());
 
         txn_header.set_inputs(RepeatedField::from_vec(
@@ -379,9 +464,17 @@ impl<'a> BatchListBuilder<'a> {
 
 #[cfg(test)]
 mod tests {
-    use super::{BatchBuilder, BatchListBuilder, TransactionBuilder};
+    use super::{
+        BatchBuilder, BatchListBuilder, CounterFileNonce, NonceStrategy, RandomNonce,
+        TransactionBuilder,
+    };
+    use protobuf::Message;
+    use sawtooth_sdk::messages::transaction::TransactionHeader;
     use sawtooth_sdk::signing::secp256k1::Secp256k1PrivateKey;
     use sawtooth_sdk::signing::{create_context, Signer};
+    use std::env;
+    use std::fs;
+    use std::process;
 
     fn get_key() -> Secp256k1PrivateKey {
         Secp256k1PrivateKey::from_hex(
@@ -516,4 +609,46 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn nonce_strategies() {
+        let context = create_context("secp256k1").unwrap();
+        let key = get_key();
+        let signer = Signer::new(&*context, &key);
+        let builder = TransactionBuilder::new()
+            .family_name("foo")
+            .family_version("1.0")
+            .addresses(vec!["000000"])
+            .payload(vec![1, 2, 3])
+            .signer(&signer);
+
+        let nonce_of = |builder: TransactionBuilder| {
+            let batch = builder.build_batch().unwrap();
+            TransactionHeader::parse_from_bytes(&batch.transactions[0].header)
+                .unwrap()
+                .nonce
+        };
+
+        assert_eq!(nonce_of(builder.clone().nonce(7)), "7");
+
+        let random = RandomNonce;
+        assert_ne!(
+            nonce_of(builder.clone().nonce_strategy(&random)),
+            nonce_of(builder.clone().nonce_strategy(&random))
+        );
+
+        let path = env::temp_dir().join(format!("battleship-nonce-{}", process::id()));
+        let _ = fs::remove_file(&path);
+        let counter = CounterFileNonce::new(path.clone());
+        assert_eq!(nonce_of(builder.clone().nonce_strategy(&counter)), "1");
+        assert_eq!(nonce_of(builder.clone().nonce_strategy(&counter)), "2");
+        assert_eq!(
+            CounterFileNonce::new(path.clone()).next_nonce().unwrap(),
+            "3"
+        );
+
+        fs::write(&path, "not a number").unwrap();
+        assert!(counter.next_nonce().is_err());
+        fs::remove_file(&path).unwrap();
+    }
 }
@@ -15,8 +15,21 @@
  * ------------------------------------------------------------------------------
  */
 
+use std::iter::repeat;
+
+use crypto::digest::Digest;
+use crypto::sha2::Sha256;
+
 pub const NAMESPACE: &str = "00b10c";
 
+/// The setting which, if set, overrides the target number of blocks kept in
+/// state.
+pub const TARGET_COUNT_SETTING: &str = "sawtooth.block_info.target_count";
+
+const SETTING_NAMESPACE: &str = "000000";
+const SETTING_MAX_KEY_PARTS: usize = 4;
+const SETTING_ADDRESS_PART_SIZE: usize = 16;
+
 pub fn get_config_addr() -> String {
     format!("{}01{}", NAMESPACE, "0".repeat(62))
 }
@@ -24,3 +37,26 @@ pub fn get_config_addr() -> String {
 pub fn create_block_address(block_num: u64) -> String {
     format!("{}00{:062x}", NAMESPACE, block_num)
 }
+
+/// Computes the address of a setting, from the short hashes of the first
+/// four dot-separated parts of its key.
+pub fn setting_key_to_address(key: &str) -> String {
+    let mut address = String::new();
+    address.push_str(SETTING_NAMESPACE);
+    address.push_str(
+        &key.splitn(SETTING_MAX_KEY_PARTS, '.')
+            .chain(repeat(""))
+            .map(short_hash)
+            .take(SETTING_MAX_KEY_PARTS)
+            .collect::<Vec<_>>()
+            .join(""),
+    );
+
+    address
+}
+
+fn short_hash(s: &str) -> String {
+    let mut sha = Sha256::new();
+    sha.input(s.as_bytes());
+    sha.result_str()[..SETTING_ADDRESS_PART_SIZE].to_string()
+}
@@ -0,0 +1,110 @@
+/*
+ * Copyright 2018 Bitwise IO, Inc.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+//! Reading block info records from state.
+//!
+//! Clients reading state directly, for example through the REST API's
+//! `/state/{address}` endpoint, can use these to locate and decode the
+//! BlockInfoConfig record and the BlockInfo entries it describes:
+//!
+//! ```ignore
+//! let config = parse_config(&fetch(&config_address()))?;
+//! for block_num in config.oldest_block..=config.latest_block {
+//!     let block = parse_block_info(&fetch(&block_address(block_num)))?;
+//! }
+//! ```
+
+use protobuf::{Message, ProtobufError};
+
+use addressing::{create_block_address, get_config_addr};
+use protos;
+use state::{BlockInfo, Config};
+
+/// The address of the BlockInfoConfig record.
+pub fn config_address() -> String {
+    get_config_addr()
+}
+
+/// The address of the BlockInfo entry for a block number.
+pub fn block_address(block_num: u64) -> String {
+    create_block_address(block_num)
+}
+
+/// Decodes the BlockInfoConfig record. Unset target counts and sync
+/// tolerances are replaced with their defaults, as the transaction processor
+/// does.
+pub fn parse_config(bytes: &[u8]) -> Result<Config, ProtobufError> {
+    let config: protos::block_info::BlockInfoConfig = Message::parse_from_bytes(bytes)?;
+    Ok(config.into())
+}
+
+/// Decodes a BlockInfo entry.
+pub fn parse_block_info(bytes: &[u8]) -> Result<BlockInfo, ProtobufError> {
+    let block_info: protos::block_info::BlockInfo = Message::parse_from_bytes(bytes)?;
+    Ok(block_info.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use state::{DEFAULT_SYNC_TOLERANCE, DEFAULT_TARGET_COUNT};
+
+    #[test]
+    fn parse_config_fills_defaults() {
+        let mut config = protos::block_info::BlockInfoConfig::new();
+        config.set_latest_block(10);
+        config.set_oldest_block(3);
+
+        let parsed = parse_config(&config.write_to_bytes().unwrap()).unwrap();
+
+        assert_eq!(
+            parsed,
+            Config {
+                latest_block: 10,
+                oldest_block: 3,
+                target_count: DEFAULT_TARGET_COUNT,
+                sync_tolerance: DEFAULT_SYNC_TOLERANCE,
+            }
+        );
+    }
+
+    #[test]
+    fn parse_block_info_round_trip() {
+        let block = BlockInfo {
+            block_num: 7,
+            previous_block_id: "a".repeat(128),
+            signer_public_key: "b".repeat(66),
+            header_signature: "c".repeat(128),
+            timestamp: 1_500_000_000,
+        };
+        let proto: protos::block_info::BlockInfo = block.clone().into();
+
+        assert_eq!(
+            parse_block_info(&proto.write_to_bytes().unwrap()).unwrap(),
+            block
+        );
+    }
+
+    #[test]
+    fn addresses_are_in_namespace() {
+        assert_eq!(config_address().len(), 70);
+        assert_eq!(block_address(1).len(), 70);
+        assert!(block_address(1).starts_with("00b10c00"));
+        assert_ne!(block_address(1), block_address(2));
+    }
+}
@@ -107,7 +107,7 @@ impl BlockInfoTransactionHandler {
                 ));
             }
 
-            state.set_config_and_block(config, payload.block)?;
+            state.set_config_and_block(config, payload.block, payload.prune_before)?;
         } else {
             let sync_tolerance = if payload.sync_tolerance != 0 {
                 payload.sync_tolerance
@@ -115,7 +115,9 @@ impl BlockInfoTransactionHandler {
                 DEFAULT_SYNC_TOLERANCE
             };
 
-            let target_count = if payload.target_count != 0 {
+            let target_count = if let Some(target_count) = state.get_target_count_setting()? {
+                target_count
+            } else if payload.target_count != 0 {
                 payload.target_count
             } else {
                 DEFAULT_TARGET_COUNT
@@ -130,20 +132,23 @@ impl BlockInfoTransactionHandler {
 
             validate_timestamp(payload.block.timestamp, config.sync_tolerance)?;
 
-            state.set_config_and_block(config, payload.block)?;
+            state.set_config_and_block(config, payload.block, payload.prune_before)?;
         }
 
         Ok(())
     }
 
     /// If the config exists in state, modify the target count, sync_tolerance if they are set,
-    /// and update the latest block.
+    /// and update the latest block. The target count setting, if set, takes precedence over the
+    /// target count of the payload.
     fn mutate_config_if_exists(
         payload: &BlockInfoPayload,
         state: &mut BlockInfoState,
     ) -> Result<Option<Config>, ApplyError> {
         if let Some(mut config) = state.get_config_from_state()? {
-            if payload.target_count != 0 {
+            if let Some(target_count) = state.get_target_count_setting()? {
+                config.target_count = target_count;
+            } else if payload.target_count != 0 {
                 config.target_count = payload.target_count;
             }
 
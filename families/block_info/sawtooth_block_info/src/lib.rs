@@ -28,13 +28,13 @@ cfg_if! {
     } else {
         #[macro_use]
         extern crate log;
-        extern crate log4rs;
         extern crate rustc_serialize;
         extern crate sawtooth_sdk;
     }
 }
 
 pub mod addressing;
+pub mod client;
 pub mod handler;
 pub mod payload;
 pub mod protos;
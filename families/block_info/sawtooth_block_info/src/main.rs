@@ -27,14 +27,11 @@ cfg_if! {
         extern crate clap;
         #[macro_use]
         extern crate log;
-        extern crate log4rs;
+        extern crate sawtooth_logging;
         extern crate sawtooth_sdk;
         extern crate block_info_tp;
         use std::process;
-        use log::LevelFilter;
-        use log4rs::append::console::ConsoleAppender;
-        use log4rs::config::{Appender, Config, Root};
-        use log4rs::encode::pattern::PatternEncoder;
+        use sawtooth_logging::{level_from_verbosity, LoggingConfig};
         use sawtooth_sdk::processor::TransactionProcessor;
         use block_info_tp::handler::BlockInfoTransactionHandler;
     }
@@ -42,44 +39,26 @@ cfg_if! {
 
 #[cfg(not(target_arch = "wasm32"))]
 fn main() {
-    let matches = clap_app!(block_info =>
+    let matches = sawtooth_logging::add_args(clap_app!(block_info =>
         (version: crate_version!())
         (about: "BlockInfo Transaction Processor")
         (@arg connect: -C --connect +takes_value
          "connection endpoint for validator")
         (@arg verbose: -v --verbose +multiple
-         "increase output verbosity"))
+         "increase output verbosity")))
     .get_matches();
 
     let endpoint = matches
         .value_of("connect")
         .unwrap_or("tcp://localhost:4004");
 
-    let console_log_level;
-    match matches.occurrences_of("verbose") {
-        0 => console_log_level = LevelFilter::Warn,
-        1 => console_log_level = LevelFilter::Info,
-        2 => console_log_level = LevelFilter::Debug,
-        3 | _ => console_log_level = LevelFilter::Trace,
-    }
-
-    let stdout = ConsoleAppender::builder()
-        .encoder(Box::new(PatternEncoder::new(
-            "{h({l:5.5})} | {({M}:{L}):20.20} | {m}{n}",
-        )))
-        .build();
+    let console_log_level = level_from_verbosity(matches.occurrences_of("verbose"));
 
-    let config = match Config::builder()
-        .appender(Appender::builder().build("stdout", Box::new(stdout)))
-        .build(Root::builder().appender("stdout").build(console_log_level))
+    if let Err(err) = LoggingConfig::from_matches(&matches, console_log_level)
+        .and_then(|config| sawtooth_logging::init(&config))
     {
-        Ok(x) => x,
-        Err(_) => process::exit(1),
-    };
-
-    match log4rs::init_config(config) {
-        Ok(_) => (),
-        Err(_) => process::exit(1),
+        eprintln!("{}", err);
+        process::exit(1);
     }
 
     let handler = BlockInfoTransactionHandler::new();
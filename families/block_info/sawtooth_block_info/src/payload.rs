@@ -36,6 +36,7 @@ pub struct BlockInfoPayload {
     pub block: BlockInfo,
     pub target_count: u64,
     pub sync_tolerance: u64,
+    pub prune_before: u64,
 }
 
 impl BlockInfoPayload {
@@ -71,6 +72,15 @@ impl BlockInfoPayload {
                 warn!("Invalid Transaction: {}", &warning_string);
                 return Err(ApplyError::InvalidTransaction(warning_string));
             }
+            if payload.get_prune_before() > next_block.get_block_num() {
+                let warning_string = format!(
+                    "Cannot prune blocks before {}, which is after the new block {}",
+                    payload.get_prune_before(),
+                    next_block.get_block_num()
+                );
+                warn!("Invalid Transaction: {}", &warning_string);
+                return Err(ApplyError::InvalidTransaction(warning_string));
+            }
         }
 
         Ok(payload.into())
@@ -91,6 +101,7 @@ impl From<BlockInfoTxn> for BlockInfoPayload {
             block: other.get_block().into(),
             target_count: other.get_target_count(),
             sync_tolerance: other.get_sync_tolerance(),
+            prune_before: other.get_prune_before(),
         }
     }
 }
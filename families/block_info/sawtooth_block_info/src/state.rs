@@ -15,7 +15,9 @@
  * ------------------------------------------------------------------------------
  */
 
-use addressing::{create_block_address, get_config_addr};
+use addressing::{
+    create_block_address, get_config_addr, setting_key_to_address, TARGET_COUNT_SETTING,
+};
 use protobuf::Message;
 use protos;
 
@@ -32,6 +34,7 @@ cfg_if! {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
 pub struct Config {
     pub latest_block: u64,
     pub oldest_block: u64,
@@ -39,6 +42,7 @@ pub struct Config {
     pub sync_tolerance: u64,
 }
 
+#[derive(Debug, Clone, PartialEq)]
 pub struct BlockInfo {
     pub block_num: u64,
     pub previous_block_id: String,
@@ -80,6 +84,46 @@ impl<'a> BlockInfoState<'a> {
         }
     }
 
+    /// Returns the target number of blocks set by the setting
+    /// sawtooth.block_info.target_count, or None if it is unset or not a
+    /// positive integer.
+    pub fn get_target_count_setting(&mut self) -> Result<Option<u64>, ApplyError> {
+        let state_data = self
+            .context
+            .get_state_entry(&setting_key_to_address(TARGET_COUNT_SETTING))
+            .map_err(|err| {
+                warn!("Error getting {} from state, {}", TARGET_COUNT_SETTING, err);
+                ApplyError::InternalError(format!(
+                    "Error getting {} from state",
+                    TARGET_COUNT_SETTING
+                ))
+            })?;
+
+        let setting: protos::setting::Setting = match state_data {
+            Some(ref d) => Message::parse_from_bytes(d)
+                .map_err(|_| ApplyError::InternalError("Failed to deserialize Setting".into()))?,
+            None => return Ok(None),
+        };
+
+        let value = setting
+            .get_entries()
+            .iter()
+            .find(|entry| entry.get_key() == TARGET_COUNT_SETTING)
+            .map(|entry| entry.get_value());
+
+        match value.map(str::parse::<u64>) {
+            Some(Ok(target_count)) if target_count > 0 => Ok(Some(target_count)),
+            Some(_) => {
+                warn!(
+                    "Ignoring {}, which is not a positive integer",
+                    TARGET_COUNT_SETTING
+                );
+                Ok(None)
+            }
+            None => Ok(None),
+        }
+    }
+
     pub fn get_block_by_num(&mut self, block_num: u64) -> Result<Option<BlockInfo>, ApplyError> {
         let state_data = self
             .context
@@ -107,15 +151,20 @@ impl<'a> BlockInfoState<'a> {
         }
     }
 
+    /// Stores the block and the config, removing the oldest blocks beyond
+    /// the target count, as well as every block before prune_before.
     pub fn set_config_and_block(
         &mut self,
         config: Config,
         block: BlockInfo,
+        prune_before: u64,
     ) -> Result<(), ApplyError> {
         let mut deletes = vec![];
         let mut possible_oldest_block = config.oldest_block;
 
-        while block.block_num - possible_oldest_block > config.target_count {
+        while block.block_num - possible_oldest_block > config.target_count
+            || possible_oldest_block < prune_before
+        {
             deletes.push(create_block_address(possible_oldest_block));
             possible_oldest_block += 1;
         }
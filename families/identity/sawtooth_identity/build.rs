@@ -35,6 +35,8 @@ fn main() {
         "../protos/identities.proto",
         "../../../protos/setting.proto",
         "../../../protos/identity.proto",
+        "../../../protos/validator.proto",
+        "../../../protos/client_identity.proto",
     ];
 
     protoc_rust::Codegen::new()
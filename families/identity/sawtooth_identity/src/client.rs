@@ -0,0 +1,311 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+//! A client for inspecting identity policies through a validator, without
+//! submitting batches.
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use protobuf;
+use protobuf::Message as M;
+use zmq;
+
+use protos::client_identity::{
+    ClientIdentityEvaluateRequest, ClientIdentityEvaluateResponse,
+    ClientIdentityEvaluateResponse_Status, ClientIdentityPolicyListRequest,
+    ClientIdentityPolicyListResponse, ClientIdentityPolicyListResponse_Status,
+};
+use protos::identity::{Policy_Entry, Policy_EntryType};
+use protos::validator::{Message, Message_MessageType};
+
+const DEFAULT_TIMEOUT_MS: i32 = 10_000;
+
+#[derive(Debug)]
+pub enum ClientError {
+    ConnectionError(String),
+    SerializationError(String),
+    /// The validator answered the request with a status other than OK
+    ValidatorError(String),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ClientError::ConnectionError(ref s) => write!(f, "ConnectionError: {}", s),
+            ClientError::SerializationError(ref s) => write!(f, "SerializationError: {}", s),
+            ClientError::ValidatorError(ref s) => write!(f, "ValidatorError: {}", s),
+        }
+    }
+}
+
+impl StdError for ClientError {
+    fn description(&self) -> &str {
+        match *self {
+            ClientError::ConnectionError(ref s) => s,
+            ClientError::SerializationError(ref s) => s,
+            ClientError::ValidatorError(ref s) => s,
+        }
+    }
+}
+
+impl From<zmq::Error> for ClientError {
+    fn from(err: zmq::Error) -> Self {
+        ClientError::ConnectionError(err.to_string())
+    }
+}
+
+impl From<protobuf::ProtobufError> for ClientError {
+    fn from(err: protobuf::ProtobufError) -> Self {
+        ClientError::SerializationError(err.to_string())
+    }
+}
+
+/// An entry of a policy, which permits or denies the keys matching its key
+/// pattern.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PolicyEntry {
+    Permit(String),
+    Deny(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Policy {
+    pub name: String,
+    pub entries: Vec<PolicyEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Role {
+    pub name: String,
+    pub policy_name: String,
+}
+
+/// The policies and roles set at a state root.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolicyListing {
+    pub state_root: String,
+    pub policies: Vec<Policy>,
+    pub roles: Vec<Role>,
+}
+
+/// The outcome of evaluating a public key against a role's policy.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Evaluation {
+    pub state_root: String,
+    pub allowed: bool,
+    /// The role whose policy was used, after falling back from unset roles;
+    /// "default" if no role along the way was set
+    pub role: String,
+    /// The evaluated policy, or None if no policy is set and every key is
+    /// permitted
+    pub policy_name: Option<String>,
+    /// The entry that matched the public key, or None if no entry matched
+    /// and the key is denied by default
+    pub matched_entry: Option<PolicyEntry>,
+}
+
+/// Sends identity requests to a validator's client endpoint.
+pub struct IdentityClient {
+    socket: zmq::Socket,
+    // The socket must not outlive its context
+    _context: zmq::Context,
+    next_id: u64,
+}
+
+impl IdentityClient {
+    /// Connects to the validator at url, e.g. "tcp://localhost:4004".
+    pub fn new(url: &str) -> Result<IdentityClient, ClientError> {
+        let context = zmq::Context::new();
+        let socket = context.socket(zmq::DEALER)?;
+        socket.set_linger(0)?;
+        socket.set_rcvtimeo(DEFAULT_TIMEOUT_MS)?;
+        socket.connect(url)?;
+
+        Ok(IdentityClient {
+            socket,
+            _context: context,
+            next_id: 0,
+        })
+    }
+
+    /// Lists the policies and roles at the given state root, or at the
+    /// current chain head if state_root is None.
+    pub fn list_policies(
+        &mut self,
+        state_root: Option<&str>,
+    ) -> Result<PolicyListing, ClientError> {
+        let mut request = ClientIdentityPolicyListRequest::new();
+        if let Some(state_root) = state_root {
+            request.set_state_root(state_root.into());
+        }
+
+        let mut response: ClientIdentityPolicyListResponse = self.send(
+            Message_MessageType::CLIENT_IDENTITY_POLICY_LIST_REQUEST,
+            Message_MessageType::CLIENT_IDENTITY_POLICY_LIST_RESPONSE,
+            &request,
+        )?;
+
+        if response.get_status() != ClientIdentityPolicyListResponse_Status::OK {
+            return Err(ClientError::ValidatorError(format!(
+                "Unable to list policies: {:?}",
+                response.get_status()
+            )));
+        }
+
+        Ok(PolicyListing {
+            state_root: response.take_state_root(),
+            policies: response
+                .take_policies()
+                .into_iter()
+                .map(|mut policy| Policy {
+                    name: policy.take_name(),
+                    entries: policy
+                        .take_entries()
+                        .into_iter()
+                        .filter_map(to_policy_entry)
+                        .collect(),
+                })
+                .collect(),
+            roles: response
+                .take_roles()
+                .into_iter()
+                .map(|mut role| Role {
+                    name: role.take_name(),
+                    policy_name: role.take_policy_name(),
+                })
+                .collect(),
+        })
+    }
+
+    /// Evaluates whether public_key would be permitted by the policy of
+    /// role, at the given state root or at the current chain head if
+    /// state_root is None.
+    pub fn evaluate(
+        &mut self,
+        role: &str,
+        public_key: &str,
+        state_root: Option<&str>,
+    ) -> Result<Evaluation, ClientError> {
+        let mut request = ClientIdentityEvaluateRequest::new();
+        request.set_role(role.into());
+        request.set_public_key(public_key.into());
+        if let Some(state_root) = state_root {
+            request.set_state_root(state_root.into());
+        }
+
+        let mut response: ClientIdentityEvaluateResponse = self.send(
+            Message_MessageType::CLIENT_IDENTITY_EVALUATE_REQUEST,
+            Message_MessageType::CLIENT_IDENTITY_EVALUATE_RESPONSE,
+            &request,
+        )?;
+
+        if response.get_status() != ClientIdentityEvaluateResponse_Status::OK {
+            return Err(ClientError::ValidatorError(format!(
+                "Unable to evaluate role {}: {:?}",
+                role,
+                response.get_status()
+            )));
+        }
+
+        let policy_name = response.take_policy_name();
+        let matched_entry = if response.has_matched_entry() {
+            to_policy_entry(response.take_matched_entry())
+        } else {
+            None
+        };
+
+        Ok(Evaluation {
+            state_root: response.take_state_root(),
+            allowed: response.get_allowed(),
+            role: response.take_role(),
+            policy_name: if policy_name.is_empty() {
+                None
+            } else {
+                Some(policy_name)
+            },
+            matched_entry,
+        })
+    }
+
+    /// Sends a request and waits for the response with the same
+    /// correlation id, answering any pings from the validator meanwhile.
+    fn send<Req: M, Resp: M>(
+        &mut self,
+        request_type: Message_MessageType,
+        response_type: Message_MessageType,
+        request: &Req,
+    ) -> Result<Resp, ClientError> {
+        let correlation_id = self.generate_correlation_id();
+
+        let mut msg = Message::new();
+        msg.set_message_type(request_type);
+        msg.set_correlation_id(correlation_id.clone());
+        msg.set_content(request.write_to_bytes()?);
+        self.socket.send(&msg.write_to_bytes()?, 0)?;
+
+        loop {
+            let bytes = self.socket.recv_bytes(0).map_err(|err| match err {
+                zmq::Error::EAGAIN => ClientError::ConnectionError(
+                    "Timed out waiting for the validator to respond".into(),
+                ),
+                err => ClientError::from(err),
+            })?;
+            let mut reply = Message::parse_from_bytes(&bytes)?;
+
+            if reply.get_message_type() == Message_MessageType::PING_REQUEST {
+                let mut pong = Message::new();
+                pong.set_message_type(Message_MessageType::PING_RESPONSE);
+                pong.set_correlation_id(reply.take_correlation_id());
+                self.socket.send(&pong.write_to_bytes()?, 0)?;
+                continue;
+            }
+
+            if reply.get_correlation_id() != correlation_id {
+                continue;
+            }
+
+            if reply.get_message_type() != response_type {
+                return Err(ClientError::ValidatorError(format!(
+                    "Expected {:?}, received {:?}",
+                    response_type,
+                    reply.get_message_type()
+                )));
+            }
+
+            return Ok(Resp::parse_from_bytes(reply.get_content())?);
+        }
+    }
+
+    fn generate_correlation_id(&mut self) -> String {
+        self.next_id += 1;
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.subsec_nanos())
+            .unwrap_or(0);
+        format!("identity-{:x}-{}", nanos, self.next_id)
+    }
+}
+
+fn to_policy_entry(mut entry: Policy_Entry) -> Option<PolicyEntry> {
+    match entry.get_field_type() {
+        Policy_EntryType::PERMIT_KEY => Some(PolicyEntry::Permit(entry.take_key())),
+        Policy_EntryType::DENY_KEY => Some(PolicyEntry::Deny(entry.take_key())),
+        Policy_EntryType::ENTRY_TYPE_UNSET => None,
+    }
+}
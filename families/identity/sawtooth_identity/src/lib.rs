@@ -27,9 +27,11 @@ cfg_if! {
     } else {
         #[macro_use]
         extern crate log;
-        extern crate log4rs;
         extern crate rustc_serialize;
         extern crate sawtooth_sdk;
+        extern crate zmq;
+
+        pub mod client;
     }
 }
 
@@ -27,13 +27,10 @@ cfg_if! {
         extern crate clap;
         #[macro_use]
         extern crate log;
-        extern crate log4rs;
+        extern crate sawtooth_logging;
         extern crate sawtooth_sdk;
         extern crate sawtooth_identity;
-        use log::LevelFilter;
-        use log4rs::append::console::ConsoleAppender;
-        use log4rs::config::{Appender, Config, Root};
-        use log4rs::encode::pattern::PatternEncoder;
+        use sawtooth_logging::{level_from_verbosity, LoggingConfig};
         use std::process;
         use sawtooth_sdk::processor::TransactionProcessor;
         use sawtooth_identity::handler::IdentityTransactionHandler;
@@ -45,43 +42,25 @@ fn main() {}
 
 #[cfg(not(target_arch = "wasm32"))]
 fn main() {
-    let matches = clap_app!(identity =>
+    let matches = sawtooth_logging::add_args(clap_app!(identity =>
         (version: crate_version!())
         (about: "Identity Transaction Processor (Rust)")
         (@arg connect: -C --connect +takes_value "connection endpoint for validator")
         (@arg verbose: -v --verbose +multiple "increase output verbosity")
-    )
+    ))
     .get_matches();
 
     let endpoint = matches
         .value_of("connect")
         .unwrap_or("tcp://localhost:4004");
 
-    let console_log_level;
-    match matches.occurrences_of("verbose") {
-        0 => console_log_level = LevelFilter::Warn,
-        1 => console_log_level = LevelFilter::Info,
-        2 => console_log_level = LevelFilter::Debug,
-        3 | _ => console_log_level = LevelFilter::Trace,
-    }
-
-    let stdout = ConsoleAppender::builder()
-        .encoder(Box::new(PatternEncoder::new(
-            "{h({l:5.5})} | {({M}:{L}):20.20} | {m}{n}",
-        )))
-        .build();
+    let console_log_level = level_from_verbosity(matches.occurrences_of("verbose"));
 
-    let config = match Config::builder()
-        .appender(Appender::builder().build("stdout", Box::new(stdout)))
-        .build(Root::builder().appender("stdout").build(console_log_level))
+    if let Err(err) = LoggingConfig::from_matches(&matches, console_log_level)
+        .and_then(|config| sawtooth_logging::init(&config))
     {
-        Ok(x) => x,
-        Err(_) => process::exit(1),
-    };
-
-    match log4rs::init_config(config) {
-        Ok(_) => (),
-        Err(_) => process::exit(1),
+        eprintln!("{}", err);
+        process::exit(1);
     }
 
     let handler = IdentityTransactionHandler::new();
@@ -39,9 +39,10 @@ use crypto::digest::Digest;
 use crypto::sha2::Sha256;
 use protobuf::{self, Message};
 use protos::settings::{
-    SettingCandidate, SettingCandidate_VoteRecord, SettingCandidates, SettingProposal, SettingVote,
-    SettingVote_Vote, SettingsPayload, SettingsPayload_Action,
+    SettingCandidate, SettingCandidate_VoteRecord, SettingCandidates, SettingMultiProposal,
+    SettingProposal, SettingVote, SettingVote_Vote, SettingsPayload, SettingsPayload_Action,
 };
+use std::collections::HashSet;
 use std::iter::repeat;
 
 #[cfg(target_arch = "wasm32")]
@@ -63,6 +64,10 @@ pub unsafe fn entrypoint(payload: WasmPtr, signer: WasmPtr, signature: WasmPtr)
 // The config namespace is special: it is not derived from a hash.
 const SETTINGS_NAMESPACE: &str = "000000";
 
+// PROPOSE_MULTIPLE payloads are only valid in transactions of this version,
+// so that validators which only run version 1.0 reject them consistently.
+const MULTI_PROPOSAL_FAMILY_VERSION: &str = "1.1";
+
 #[derive(Default)]
 pub struct SettingsTransactionHandler {
     family_name: String,
@@ -74,7 +79,7 @@ impl SettingsTransactionHandler {
     pub fn new() -> SettingsTransactionHandler {
         SettingsTransactionHandler {
             family_name: "sawtooth_settings".to_string(),
-            family_versions: vec!["1.0".to_string()],
+            family_versions: vec!["1.0".to_string(), MULTI_PROPOSAL_FAMILY_VERSION.to_string()],
             namespaces: vec![SETTINGS_NAMESPACE.to_string()],
         }
     }
@@ -122,9 +127,25 @@ impl TransactionHandler for SettingsTransactionHandler {
                 settings_payload.get_data(),
                 context,
             ),
-            SettingsPayload_Action::ACTION_UNSET => Err(ApplyError::InvalidTransaction(
-                String::from("'action' must be one of {PROPOSE, VOTE} in 'Ballot' mode"),
-            )),
+            SettingsPayload_Action::PROPOSE_MULTIPLE => {
+                if transaction.get_header().get_family_version() != MULTI_PROPOSAL_FAMILY_VERSION {
+                    return Err(ApplyError::InvalidTransaction(format!(
+                        "PROPOSE_MULTIPLE requires family version {}",
+                        MULTI_PROPOSAL_FAMILY_VERSION
+                    )));
+                }
+                apply_multi_proposal(
+                    &auth_keys,
+                    &public_key,
+                    settings_payload.get_data(),
+                    context,
+                )
+            }
+            SettingsPayload_Action::ACTION_UNSET => {
+                Err(ApplyError::InvalidTransaction(String::from(
+                    "'action' must be one of {PROPOSE, VOTE, PROPOSE_MULTIPLE} in 'Ballot' mode",
+                )))
+            }
         }
     }
 }
@@ -147,13 +168,86 @@ fn apply_proposal(
         setting_proposal.get_value(),
     )?;
 
+    let mut candidate = SettingCandidate::new();
+    candidate.set_proposal_id(proposal_id);
+    candidate.set_proposal(setting_proposal);
+
+    propose_candidate(public_key, approval_threshold, candidate, context)
+}
+
+fn apply_multi_proposal(
+    auth_keys: &[String],
+    public_key: &str,
+    multi_proposal_data: &[u8],
+    context: &mut dyn TransactionContext,
+) -> Result<(), ApplyError> {
+    let multi_proposal: SettingMultiProposal = unpack_data(multi_proposal_data)?;
+
+    let proposal_id = proposal_to_hash(multi_proposal_data);
+
+    let approval_threshold = get_approval_threshold(context)?;
+
+    if multi_proposal.get_proposals().is_empty() {
+        return Err(ApplyError::InvalidTransaction(
+            "A multiple setting proposal must propose at least one setting".into(),
+        ));
+    }
+
+    let mut proposed_settings = HashSet::new();
+    for proposal in multi_proposal.get_proposals() {
+        if !proposed_settings.insert(proposal.get_setting()) {
+            return Err(ApplyError::InvalidTransaction(format!(
+                "{:?} is proposed more than once",
+                proposal.get_setting()
+            )));
+        }
+    }
+
+    // The settings are changed together, so the others are validated against
+    // the authorized keys they would be changed with
+    let proposed_auth_keys = multi_proposal
+        .get_proposals()
+        .iter()
+        .find(|proposal| proposal.get_setting() == "sawtooth.settings.vote.authorized_keys")
+        .map(|proposal| split_ignore_empties(proposal.get_value()));
+    for proposal in multi_proposal.get_proposals() {
+        let validating_keys = match proposed_auth_keys {
+            Some(ref keys)
+                if proposal.get_setting() != "sawtooth.settings.vote.authorized_keys" =>
+            {
+                keys.as_slice()
+            }
+            _ => auth_keys,
+        };
+        validate_setting(
+            validating_keys,
+            proposal.get_setting(),
+            proposal.get_value(),
+        )?;
+    }
+
+    let mut candidate = SettingCandidate::new();
+    candidate.set_proposal_id(proposal_id);
+    candidate.set_multi_proposal(multi_proposal);
+
+    propose_candidate(public_key, approval_threshold, candidate, context)
+}
+
+/// Makes the candidate's changes if a single vote is enough to approve them,
+/// or else records it, with the proposer's vote, to be voted on.
+fn propose_candidate(
+    public_key: &str,
+    approval_threshold: i32,
+    mut candidate: SettingCandidate,
+    context: &mut dyn TransactionContext,
+) -> Result<(), ApplyError> {
     if approval_threshold > 1 {
         let mut setting_candidates = get_setting_candidates(context)?;
-        for candidate in setting_candidates.get_candidates().iter() {
-            if candidate.get_proposal_id() == proposal_id {
+        for existing in setting_candidates.get_candidates().iter() {
+            if existing.get_proposal_id() == candidate.get_proposal_id() {
                 return Err(ApplyError::InvalidTransaction(format!(
                     "Duplicate proposal for {:?}",
-                    setting_proposal.get_setting()
+                    candidate_settings_names(&candidate)
                 )));
             }
         }
@@ -162,22 +256,56 @@ fn apply_proposal(
         vote_record.set_public_key(public_key.to_string());
         vote_record.set_vote(SettingVote_Vote::ACCEPT);
 
-        let mut candidate = SettingCandidate::new();
-        candidate.set_proposal_id(proposal_id);
-        candidate.set_proposal(setting_proposal);
         candidate.set_votes(protobuf::RepeatedField::from_vec(vec![vote_record]));
         setting_candidates.mut_candidates().push(candidate);
 
         save_settings_candidates(context, &setting_candidates)
     } else {
-        set_setting_value(
-            context,
-            setting_proposal.get_setting(),
-            setting_proposal.get_value(),
-        )
+        set_candidate_settings(context, &candidate)
     }
 }
 
+/// Returns the settings a candidate changes, and the values it changes them to.
+fn candidate_settings(candidate: &SettingCandidate) -> Vec<(String, String)> {
+    if candidate.has_multi_proposal() {
+        candidate
+            .get_multi_proposal()
+            .get_proposals()
+            .iter()
+            .map(|proposal| {
+                (
+                    proposal.get_setting().to_string(),
+                    proposal.get_value().to_string(),
+                )
+            })
+            .collect()
+    } else {
+        let proposal = candidate.get_proposal();
+        vec![(
+            proposal.get_setting().to_string(),
+            proposal.get_value().to_string(),
+        )]
+    }
+}
+
+fn candidate_settings_names(candidate: &SettingCandidate) -> String {
+    candidate_settings(candidate)
+        .into_iter()
+        .map(|(setting, _)| setting)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn set_candidate_settings(
+    context: &mut dyn TransactionContext,
+    candidate: &SettingCandidate,
+) -> Result<(), ApplyError> {
+    for (setting, value) in candidate_settings(candidate) {
+        set_setting_value(context, &setting, &value)?;
+    }
+    Ok(())
+}
+
 fn apply_vote(
     auth_keys: &[String],
     public_key: &str,
@@ -191,8 +319,6 @@ fn apply_vote(
 
     let mut accepted_count = 0;
     let mut rejected_count = 0;
-    let candidate_value;
-    let candidate_setting;
     let candidate_index;
 
     {
@@ -227,22 +353,25 @@ fn apply_vote(
                 }
             }
         }
-
-        candidate_value = candidate.get_proposal().get_value().to_string();
-        candidate_setting = candidate.get_proposal().get_setting().to_string();
     }
 
     let approval_threshold = get_approval_threshold(context)?;
     if accepted_count >= approval_threshold {
-        set_setting_value(context, &candidate_setting, &candidate_value)?;
-        setting_candidates.mut_candidates().remove(candidate_index);
+        let candidate = setting_candidates.mut_candidates().remove(candidate_index);
+        set_candidate_settings(context, &candidate)?;
     } else if rejected_count >= approval_threshold
         || rejected_count + accepted_count == auth_keys.len() as i32
     {
-        debug!("Proposal for {} was rejected", &candidate_setting);
-        setting_candidates.mut_candidates().remove(candidate_index);
+        let candidate = setting_candidates.mut_candidates().remove(candidate_index);
+        debug!(
+            "Proposal for {} was rejected",
+            candidate_settings_names(&candidate)
+        );
     } else {
-        debug!("Vote recorded for {}", &candidate_setting);
+        debug!(
+            "Vote recorded for {}",
+            candidate_settings_names(&setting_candidates.get_candidates()[candidate_index])
+        );
     }
     save_settings_candidates(context, &setting_candidates)
 }
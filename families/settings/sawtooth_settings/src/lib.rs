@@ -25,7 +25,6 @@ cfg_if! {
         #[macro_use]
         extern crate sabre_sdk;
     } else {
-        extern crate log4rs;
         extern crate rustc_serialize;
         extern crate sawtooth_sdk;
         #[macro_use]
@@ -16,6 +16,7 @@
 from sawtooth_processor_test.message_factory import MessageFactory
 from sawtooth_settings_test.protobuf.settings_pb2 import SettingsPayload
 from sawtooth_settings_test.protobuf.settings_pb2 import SettingProposal
+from sawtooth_settings_test.protobuf.settings_pb2 import SettingMultiProposal
 from sawtooth_settings_test.protobuf.settings_pb2 import SettingVote
 from sawtooth_settings_test.protobuf.setting_pb2 import Setting
 
@@ -24,10 +25,10 @@ _ADDRESS_PART_SIZE = 16
 
 
 class SettingsMessageFactory:
-    def __init__(self, signer=None):
+    def __init__(self, signer=None, family_version="1.0"):
         self._factory = MessageFactory(
             family_name="sawtooth_settings",
-            family_version="1.0",
+            family_version=family_version,
             namespace="000000",
             signer=signer)
 
@@ -52,17 +53,18 @@ class SettingsMessageFactory:
         return self._factory.create_tp_response(status)
 
     def _create_tp_process_request(self, setting, payload):
+        return self._create_multi_tp_process_request([setting], payload)
+
+    def _create_multi_tp_process_request(self, settings, payload):
         inputs = [
             self._key_to_address('sawtooth.settings.vote.proposals'),
             self._key_to_address('sawtooth.settings.vote.authorized_keys'),
-            self._key_to_address('sawtooth.settings.vote.approval_threshold'),
-            self._key_to_address(setting)
-        ]
+            self._key_to_address('sawtooth.settings.vote.approval_threshold')
+        ] + [self._key_to_address(setting) for setting in settings]
 
         outputs = [
-            self._key_to_address('sawtooth.settings.vote.proposals'),
-            self._key_to_address(setting)
-        ]
+            self._key_to_address('sawtooth.settings.vote.proposals')
+        ] + [self._key_to_address(setting) for setting in settings]
 
         return self._factory.create_tp_process_request(
             payload.SerializeToString(), inputs, outputs, [])
@@ -74,6 +76,23 @@ class SettingsMessageFactory:
 
         return self._create_tp_process_request(setting, payload)
 
+    def create_multi_proposal_transaction(self, settings, nonce):
+        """Creates a proposal of several settings, given as a list of
+        (setting, value) tuples. The settings family only accepts these in
+        transactions of version 1.1.
+        """
+        proposal = SettingMultiProposal(
+            proposals=[
+                SettingProposal(setting=setting, value=value)
+                for setting, value in settings
+            ],
+            nonce=nonce)
+        payload = SettingsPayload(action=SettingsPayload.PROPOSE_MULTIPLE,
+                                  data=proposal.SerializeToString())
+
+        return self._create_multi_tp_process_request(
+            [setting for setting, _ in settings], payload)
+
     def create_vote_proposal(self, proposal_id, setting, vote):
         vote = SettingVote(proposal_id=proposal_id, vote=vote)
         payload = SettingsPayload(action=SettingsPayload.VOTE,
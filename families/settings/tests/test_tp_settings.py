@@ -16,6 +16,9 @@
 import hashlib
 import base64
 
+from sawtooth_signing import create_context
+from sawtooth_signing import CryptoFactory
+
 from sawtooth_processor_test.transaction_processor_test_case \
     import TransactionProcessorTestCase
 
@@ -23,6 +26,7 @@ from sawtooth_settings_test.protobuf.settings_pb2 import SettingCandidates
 from sawtooth_settings_test.protobuf.settings_pb2 import SettingCandidate
 from sawtooth_settings_test.protobuf.settings_pb2 import SettingVote
 from sawtooth_settings_test.protobuf.settings_pb2 import SettingProposal
+from sawtooth_settings_test.protobuf.settings_pb2 import SettingMultiProposal
 
 from sawtooth_settings_test.settings_message_factory \
     import SettingsMessageFactory
@@ -40,7 +44,12 @@ class TestSettings(TransactionProcessorTestCase):
     @classmethod
     def setUpClass(cls):
         super().setUpClass()
-        cls.factory = SettingsMessageFactory()
+        context = create_context('secp256k1')
+        signer = CryptoFactory(context).new_signer(
+            context.new_random_private_key())
+        cls.factory = SettingsMessageFactory(signer=signer)
+        cls.multi_factory = SettingsMessageFactory(
+            signer=signer, family_version='1.1')
 
     def _expect_get(self, key, value=None):
         received = self.validator.expect(
@@ -79,6 +88,11 @@ class TestSettings(TransactionProcessorTestCase):
         self.validator.send(self.factory.create_proposal_transaction(
             key, value, "somenonce"))
 
+    def _propose_multiple(self, settings, factory=None):
+        factory = factory or self.multi_factory
+        self.validator.send(factory.create_multi_proposal_transaction(
+            settings, "somenonce"))
+
     def _vote(self, proposal_id, setting, vote):
         self.validator.send(self.factory.create_vote_proposal(
             proposal_id, setting, vote))
@@ -203,6 +217,110 @@ class TestSettings(TransactionProcessorTestCase):
 
         self._expect_ok()
 
+    def test_propose_multiple(self):
+        """
+        Tests proposing several values at once, without a ballot, which sets
+        all of them.
+        """
+        self._propose_multiple([('my.config.setting', 'myvalue'),
+                                ('my.other.setting', 'othervalue')])
+
+        self._expect_get('sawtooth.settings.vote.authorized_keys',
+                         self._public_key)
+        self._expect_get('sawtooth.settings.vote.approval_threshold')
+
+        self._expect_get('my.config.setting')
+        self._expect_set('my.config.setting', 'myvalue')
+        self._expect_add_event('my.config.setting')
+
+        self._expect_get('my.other.setting')
+        self._expect_set('my.other.setting', 'othervalue')
+        self._expect_add_event('my.other.setting')
+
+        self._expect_ok()
+
+    def test_propose_multiple_requires_version_1_1(self):
+        """
+        Tests that a proposal of several values is invalid in a transaction
+        of family version 1.0.
+        """
+        self._propose_multiple([('my.config.setting', 'myvalue'),
+                                ('my.other.setting', 'othervalue')],
+                               factory=self.factory)
+
+        self._expect_get('sawtooth.settings.vote.authorized_keys',
+                         self._public_key)
+
+        self._expect_invalid_transaction()
+
+    def test_propose_multiple_duplicate_setting(self):
+        """
+        Tests that a proposal of several values may not propose the same
+        setting twice.
+        """
+        self._propose_multiple([('my.config.setting', 'myvalue'),
+                                ('my.config.setting', 'othervalue')])
+
+        self._expect_get('sawtooth.settings.vote.authorized_keys',
+                         self._public_key)
+        self._expect_get('sawtooth.settings.vote.approval_threshold')
+
+        self._expect_invalid_transaction()
+
+    def test_propose_multiple_validates_each_setting(self):
+        """
+        Tests that a proposal of several values is invalid if any of them is.
+        """
+        self._propose_multiple([('my.config.setting', 'myvalue'),
+                                ('sawtooth.settings.vote.proposals',
+                                 'othervalue')])
+
+        self._expect_get('sawtooth.settings.vote.authorized_keys',
+                         self._public_key)
+        self._expect_get('sawtooth.settings.vote.approval_threshold')
+
+        self._expect_invalid_transaction()
+
+    def test_propose_multiple_ballot(self):
+        """
+        Tests proposing several values at once in ballot mode, which records
+        them as a single candidate.
+        """
+        self._propose_multiple([('my.config.setting', 'myvalue'),
+                                ('my.other.setting', 'othervalue')])
+
+        self._expect_get('sawtooth.settings.vote.authorized_keys',
+                         self._public_key)
+        self._expect_get('sawtooth.settings.vote.approval_threshold', '2')
+        self._expect_get('sawtooth.settings.vote.proposals')
+
+        proposal = SettingMultiProposal(
+            proposals=[
+                SettingProposal(setting='my.config.setting', value='myvalue'),
+                SettingProposal(setting='my.other.setting',
+                                value='othervalue')
+            ],
+            nonce='somenonce'
+        )
+        proposal_id = _to_hash(proposal.SerializeToString())
+        record = SettingCandidate.VoteRecord(
+            public_key=self._public_key,
+            vote=SettingVote.ACCEPT)
+        candidate = SettingCandidate(
+            proposal_id=proposal_id,
+            multi_proposal=proposal,
+            votes=[record])
+
+        candidates = SettingCandidates(candidates=[candidate])
+
+        self._expect_get('sawtooth.settings.vote.proposals')
+        self._expect_set('sawtooth.settings.vote.proposals',
+                         base64.b64encode(candidates.SerializeToString()))
+
+        self._expect_add_event('sawtooth.settings.vote.proposals')
+
+        self._expect_ok()
+
     def test_vote_approved(self):
         """
         Tests voting on a given setting, where the setting is approved
@@ -0,0 +1,223 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+//! The logging setup shared by the Sawtooth Rust binaries.
+//!
+//! A binary adds the `--log-format` and `--log-file` arguments to its command
+//! line with `add_args`, then initializes logging from them, at the level set
+//! by its own verbosity flags, with `init`.
+
+extern crate clap;
+extern crate log;
+extern crate log4rs;
+
+use std::error;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use clap::{App, Arg, ArgMatches};
+use log::LevelFilter;
+use log4rs::append::console::ConsoleAppender;
+use log4rs::append::rolling_file::policy::compound::roll::fixed_window::FixedWindowRoller;
+use log4rs::append::rolling_file::policy::compound::trigger::size::SizeTrigger;
+use log4rs::append::rolling_file::policy::compound::CompoundPolicy;
+use log4rs::append::rolling_file::RollingFileAppender;
+use log4rs::config::{Appender, Config, Root};
+use log4rs::encode::json::JsonEncoder;
+use log4rs::encode::pattern::PatternEncoder;
+use log4rs::encode::Encode;
+
+const CONSOLE_PATTERN: &str = "{h({l:5.5})} | {({M}:{L}):20.20} | {m}{n}";
+/// Unlike the console, a log file is read after the fact, so each message
+/// records when it was logged.
+const FILE_PATTERN: &str = "{d(%Y-%m-%d %H:%M:%S%.3f)} {l:5.5} | {({M}:{L}):20.20} | {m}{n}";
+
+/// The size in bytes at which the log file is rotated.
+const LOG_FILE_SIZE: u64 = 10 * 1024 * 1024;
+/// The number of rotated log files kept, from <file>.0 (the newest) to
+/// <file>.4.
+const LOG_FILE_COUNT: u32 = 5;
+
+#[derive(Debug)]
+pub struct LoggingError(pub String);
+
+impl fmt::Display for LoggingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl error::Error for LoggingError {}
+
+/// How log messages are written: as the familiar human-readable lines, or
+/// as one JSON object per line for log collectors.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LogFormat {
+    Plain,
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = LoggingError;
+
+    fn from_str(s: &str) -> Result<LogFormat, LoggingError> {
+        match s {
+            "plain" => Ok(LogFormat::Plain),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(LoggingError(format!("Invalid log format: {}", s))),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoggingConfig {
+    pub level: LevelFilter,
+    pub format: LogFormat,
+    /// A file to log to as well as the console, which is rotated as it grows
+    pub file: Option<PathBuf>,
+}
+
+impl LoggingConfig {
+    /// Reads the format and file from the arguments added by `add_args`.
+    pub fn from_matches(
+        matches: &ArgMatches,
+        level: LevelFilter,
+    ) -> Result<LoggingConfig, LoggingError> {
+        Ok(LoggingConfig {
+            level,
+            format: matches
+                .value_of("log_format")
+                .map(LogFormat::from_str)
+                .unwrap_or(Ok(LogFormat::Plain))?,
+            file: matches.value_of("log_file").map(PathBuf::from),
+        })
+    }
+}
+
+/// The level for a number of verbosity flags: warnings by default, then
+/// info, debug and trace.
+pub fn level_from_verbosity(verbosity: u64) -> LevelFilter {
+    match verbosity {
+        0 => LevelFilter::Warn,
+        1 => LevelFilter::Info,
+        2 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    }
+}
+
+/// Adds the `--log-format` and `--log-file` arguments to a command line.
+pub fn add_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app.arg(
+        Arg::with_name("log_format")
+            .long("log-format")
+            .takes_value(true)
+            .possible_values(&["plain", "json"])
+            .help("the format of log messages (default: plain)"),
+    )
+    .arg(
+        Arg::with_name("log_file")
+            .long("log-file")
+            .takes_value(true)
+            .value_name("FILE")
+            .help("also log to this file, which is rotated every 10 MB"),
+    )
+}
+
+/// Initializes the global logger, which logs to the console and to the log
+/// file if there is one. Fails if the log file cannot be opened, or if a
+/// logger was already initialized.
+pub fn init(config: &LoggingConfig) -> Result<(), LoggingError> {
+    let console = ConsoleAppender::builder()
+        .encoder(encoder(config.format, CONSOLE_PATTERN))
+        .build();
+
+    let mut builder =
+        Config::builder().appender(Appender::builder().build("stdout", Box::new(console)));
+    let mut root = Root::builder().appender("stdout");
+
+    if let Some(ref path) = config.file {
+        let roller = FixedWindowRoller::builder()
+            .build(&format!("{}.{{}}", path.display()), LOG_FILE_COUNT)
+            .map_err(|err| LoggingError(format!("Invalid log file {:?}: {}", path, err)))?;
+        let policy =
+            CompoundPolicy::new(Box::new(SizeTrigger::new(LOG_FILE_SIZE)), Box::new(roller));
+        let file = RollingFileAppender::builder()
+            .encoder(encoder(config.format, FILE_PATTERN))
+            .build(path, Box::new(policy))
+            .map_err(|err| LoggingError(format!("Unable to open log file {:?}: {}", path, err)))?;
+
+        builder = builder.appender(Appender::builder().build("file", Box::new(file)));
+        root = root.appender("file");
+    }
+
+    let log_config = builder
+        .build(root.build(config.level))
+        .map_err(|err| LoggingError(format!("Invalid logging configuration: {}", err)))?;
+
+    log4rs::init_config(log_config)
+        .map(|_| ())
+        .map_err(|err| LoggingError(format!("Unable to initialize logging: {}", err)))
+}
+
+fn encoder(format: LogFormat, pattern: &str) -> Box<dyn Encode> {
+    match format {
+        LogFormat::Plain => Box::new(PatternEncoder::new(pattern)),
+        LogFormat::Json => Box::new(JsonEncoder::new()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level_from_verbosity() {
+        assert_eq!(level_from_verbosity(0), LevelFilter::Warn);
+        assert_eq!(level_from_verbosity(2), LevelFilter::Debug);
+        assert_eq!(level_from_verbosity(5), LevelFilter::Trace);
+    }
+
+    #[test]
+    fn test_config_from_matches() {
+        let matches = add_args(App::new("test")).get_matches_from(vec![
+            "test",
+            "--log-format",
+            "json",
+            "--log-file",
+            "/var/log/sawtooth/test.log",
+        ]);
+        assert_eq!(
+            LoggingConfig::from_matches(&matches, LevelFilter::Info).unwrap(),
+            LoggingConfig {
+                level: LevelFilter::Info,
+                format: LogFormat::Json,
+                file: Some(PathBuf::from("/var/log/sawtooth/test.log")),
+            }
+        );
+
+        let matches = add_args(App::new("test")).get_matches_from(vec!["test"]);
+        let config = LoggingConfig::from_matches(&matches, LevelFilter::Warn).unwrap();
+        assert_eq!(config.format, LogFormat::Plain);
+        assert_eq!(config.file, None);
+    }
+
+    #[test]
+    fn test_invalid_log_format() {
+        assert!("yaml".parse::<LogFormat>().is_err());
+    }
+}
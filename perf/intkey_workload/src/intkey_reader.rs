@@ -0,0 +1,104 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+use rand::prelude::*;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use intkey_addresser::IntKeyAddresser;
+
+/// The names most recently set by the workload, shared with the
+/// `IntKeyReadIterator` which reads them.
+#[derive(Clone)]
+pub struct SetNames {
+    names: Rc<RefCell<VecDeque<String>>>,
+    max_names: usize,
+}
+
+impl SetNames {
+    pub fn new(max_names: usize) -> SetNames {
+        SetNames {
+            names: Rc::new(RefCell::new(VecDeque::new())),
+            max_names,
+        }
+    }
+
+    /// Records a name which is set, forgetting the oldest name once more
+    /// than `max_names` are kept.
+    pub fn record(&self, name: &str) {
+        let mut names = self.names.borrow_mut();
+        names.push_back(name.to_string());
+        if names.len() > self.max_names {
+            names.pop_front();
+        }
+    }
+}
+
+/// Iterates over the state addresses of names chosen at random from the
+/// names set so far. It has no address until a name has been set.
+pub struct IntKeyReadIterator {
+    names: SetNames,
+    addresser: IntKeyAddresser,
+    rng: StdRng,
+}
+
+impl IntKeyReadIterator {
+    pub fn new(names: SetNames, seed: u64) -> IntKeyReadIterator {
+        IntKeyReadIterator {
+            names,
+            addresser: IntKeyAddresser::new(),
+            rng: SeedableRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Iterator for IntKeyReadIterator {
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let names = self.names.names.borrow();
+        if names.is_empty() {
+            return None;
+        }
+        let index = self.rng.gen_range(0, names.len());
+        Some(self.addresser.make_address(&names[index]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IntKeyReadIterator, SetNames};
+    use intkey_addresser::IntKeyAddresser;
+
+    #[test]
+    fn test_reads_set_names() {
+        let names = SetNames::new(2);
+        let mut reads = IntKeyReadIterator::new(names.clone(), 234595182222252245);
+        assert_eq!(reads.next(), None);
+
+        names.record("a");
+        names.record("b");
+        names.record("c");
+
+        let addresser = IntKeyAddresser::new();
+        let expected = vec![addresser.make_address("b"), addresser.make_address("c")];
+        for address in reads.take(20) {
+            assert!(expected.contains(&address));
+        }
+    }
+}
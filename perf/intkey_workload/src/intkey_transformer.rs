@@ -22,8 +22,8 @@ use intkey_addresser::IntKeyAddresser;
 use intkey_iterator::IntKeyPayload;
 use protobuf::{Message, RepeatedField};
 use rand::prelude::*;
+use sawtooth_perf::signer_pool::SignerPool;
 use sawtooth_sdk::messages::transaction::{Transaction, TransactionHeader};
-use sawtooth_sdk::signing;
 use std::collections::HashMap;
 use std::error::Error;
 
@@ -36,7 +36,7 @@ pub struct IntKeyTransformer<'a> {
     wildcard: f32,
     num_names: usize,
     unnecessary: f32,
-    signer: &'a signing::Signer<'a>,
+    signers: &'a SignerPool<'a>,
 
     addresser: IntKeyAddresser,
 
@@ -50,7 +50,7 @@ pub struct IntKeyTransformer<'a> {
 
 impl<'a> IntKeyTransformer<'a> {
     pub fn new(
-        signer: &'a signing::Signer<'a>,
+        signers: &'a SignerPool<'a>,
         seed: u64,
         unsatisfiable: f32,
         wildcard: f32,
@@ -62,7 +62,7 @@ impl<'a> IntKeyTransformer<'a> {
             wildcard,
             num_names,
             unnecessary,
-            signer,
+            signers,
             rng: SeedableRng::seed_from_u64(seed),
             addresser: IntKeyAddresser::new(),
             txn_id_by_name: HashMap::new(),
@@ -113,8 +113,8 @@ impl<'a> IntKeyTransformer<'a> {
 
         txn_header.set_payload_sha512(sha.result_str());
 
-        txn_header.set_signer_public_key(self.signer.get_public_key()?.as_hex());
-        txn_header.set_batcher_public_key(self.signer.get_public_key()?.as_hex());
+        txn_header.set_signer_public_key(self.signers.current().get_public_key()?.as_hex());
+        txn_header.set_batcher_public_key(self.signers.current().get_public_key()?.as_hex());
 
         let addresser = IntKeyAddresser::new();
 
@@ -143,7 +143,7 @@ impl<'a> IntKeyTransformer<'a> {
         if self.rng.gen_range(0.0, 1.0) < self.unsatisfiable {
             let random_bytes: Vec<u8> = (0..100).map(|_| self.rng.gen()).collect();
 
-            if let Ok(dep) = self.signer.sign(random_bytes.as_slice()) {
+            if let Ok(dep) = self.signers.current().sign(random_bytes.as_slice()) {
                 txn_header.dependencies.push(dep)
             }
         }
@@ -155,7 +155,7 @@ impl<'a> IntKeyTransformer<'a> {
 
         let header_bytes = txn_header.write_to_bytes()?;
 
-        let signature = self.signer.sign(&header_bytes.to_vec())?;
+        let signature = self.signers.current().sign(&header_bytes.to_vec())?;
 
         if payload.verb == "set" {
             if !self.txn_id_by_name.contains_key(&payload.name) {
@@ -188,6 +188,7 @@ mod tests {
     use super::IntKeyTransformer;
     use intkey_iterator::IntKeyIterator;
     use protobuf::Message;
+    use sawtooth_perf::signer_pool::SignerPool;
     use sawtooth_sdk::messages::transaction::TransactionHeader;
     use sawtooth_sdk::signing;
 
@@ -200,8 +201,9 @@ mod tests {
         let context = signing::create_context("secp256k1").unwrap();
         let private_key = context.new_random_private_key().unwrap();
         let signer = signing::Signer::new(context.as_ref(), private_key.as_ref());
+        let signers = SignerPool::single(signer);
 
-        let mut transformer = IntKeyTransformer::new(&signer, seed, 1.0, 0.0, num_names, 0.0);
+        let mut transformer = IntKeyTransformer::new(&signers, seed, 1.0, 0.0, num_names, 0.0);
 
         let transaction_iterator = intkey_iterator
             .map(|payload| transformer.intkey_payload_to_transaction(&payload))
@@ -242,8 +244,9 @@ mod tests {
         let context = signing::create_context("secp256k1").unwrap();
         let private_key = context.new_random_private_key().unwrap();
         let signer = signing::Signer::new(context.as_ref(), private_key.as_ref());
+        let signers = SignerPool::single(signer);
 
-        let mut transformer = IntKeyTransformer::new(&signer, seed, 0.0, 0.0, num_names, 0.0);
+        let mut transformer = IntKeyTransformer::new(&signers, seed, 0.0, 0.0, num_names, 0.0);
 
         let transaction_iterator = intkey_iterator
             .map(|payload| transformer.intkey_payload_to_transaction(&payload))
@@ -284,8 +287,9 @@ mod tests {
         let context = signing::create_context("secp256k1").unwrap();
         let private_key = context.new_random_private_key().unwrap();
         let signer = signing::Signer::new(context.as_ref(), private_key.as_ref());
+        let signers = SignerPool::single(signer);
 
-        let mut transformer = IntKeyTransformer::new(&signer, seed, 0.0, 1.0, num_names, 0.0);
+        let mut transformer = IntKeyTransformer::new(&signers, seed, 0.0, 1.0, num_names, 0.0);
 
         let transaction_iterator = intkey_iterator
             .map(|payload| transformer.intkey_payload_to_transaction(&payload))
@@ -323,8 +327,9 @@ mod tests {
         let context = signing::create_context("secp256k1").unwrap();
         let private_key = context.new_random_private_key().unwrap();
         let signer = signing::Signer::new(context.as_ref(), private_key.as_ref());
+        let signers = SignerPool::single(signer);
 
-        let mut transformer = IntKeyTransformer::new(&signer, seed, 0.0, 0.0, num_names, 0.0);
+        let mut transformer = IntKeyTransformer::new(&signers, seed, 0.0, 0.0, num_names, 0.0);
 
         let transaction_iterator = intkey_iterator
             .map(|payload| transformer.intkey_payload_to_transaction(&payload))
@@ -362,8 +367,9 @@ mod tests {
         let context = signing::create_context("secp256k1").unwrap();
         let private_key = context.new_random_private_key().unwrap();
         let signer = signing::Signer::new(context.as_ref(), private_key.as_ref());
+        let signers = SignerPool::single(signer);
 
-        let mut transformer = IntKeyTransformer::new(&signer, seed, 0.0, 0.0, num_names, 1.0);
+        let mut transformer = IntKeyTransformer::new(&signers, seed, 0.0, 0.0, num_names, 1.0);
 
         let transaction_iterator = intkey_iterator
             .map(|payload| transformer.intkey_payload_to_transaction(&payload))
@@ -400,8 +406,9 @@ mod tests {
         let context = signing::create_context("secp256k1").unwrap();
         let private_key = context.new_random_private_key().unwrap();
         let signer = signing::Signer::new(context.as_ref(), private_key.as_ref());
+        let signers = SignerPool::single(signer);
 
-        let mut transformer = IntKeyTransformer::new(&signer, seed, 0.0, 0.0, num_names, 0.0);
+        let mut transformer = IntKeyTransformer::new(&signers, seed, 0.0, 0.0, num_names, 0.0);
 
         let transaction_iterator = intkey_iterator
             .map(|payload| transformer.intkey_payload_to_transaction(&payload))
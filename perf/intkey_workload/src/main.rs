@@ -20,50 +20,112 @@ extern crate clap;
 extern crate crypto;
 extern crate protobuf;
 extern crate rand;
+extern crate sawtooth_logging;
 extern crate sawtooth_perf;
 extern crate sawtooth_sdk;
-extern crate simplelog;
 
 mod intkey_addresser;
 mod intkey_iterator;
+mod intkey_reader;
 mod intkey_transformer;
 
 use clap::{App, Arg, ArgMatches};
 use intkey_iterator::IntKeyIterator;
+use intkey_reader::{IntKeyReadIterator, SetNames};
 use intkey_transformer::IntKeyTransformer;
 use rand::prelude::*;
-use sawtooth_perf::batch_gen::SignedBatchIterator;
-use sawtooth_perf::batch_submit::{run_workload, InfiniteBatchListIterator};
+use sawtooth_logging::{level_from_verbosity, LoggingConfig};
+use sawtooth_perf::batch_gen::{DependencyGenerator, SignedBatchIterator};
+use sawtooth_perf::batch_submit::{run_workload, InfiniteBatchListIterator, ReadOptions};
+use sawtooth_perf::key_loader::{load_private_key, passphrase_from_env};
+use sawtooth_perf::latency::LatencyTracker;
+use sawtooth_perf::manifest::{key_fingerprint, manifest_path, RunManifest};
+use sawtooth_perf::rate::{RateSchedule, WorkloadLimits};
+use sawtooth_perf::report::ReportOptions;
+use sawtooth_perf::signer_pool::{read_private_keys, SignerPool, SignerSelection};
+use sawtooth_perf::target::ConnectionOptions;
+use sawtooth_perf::tls::TlsOptions;
 use sawtooth_sdk::signing;
-use sawtooth_sdk::signing::secp256k1::Secp256k1PrivateKey;
-use simplelog::{Config, LevelFilter, SimpleLogger};
 use std::convert::From;
+use std::env;
 use std::error::Error;
+use std::ffi::OsString;
 use std::fmt;
-use std::fs::File;
-use std::io::Read;
 use std::num::{ParseFloatError, ParseIntError};
+use std::path::Path;
 use std::str::Split;
+use std::time;
 
 const APP_NAME: &str = env!("CARGO_PKG_NAME");
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// The arguments which shape the workload, as their names and long names,
+/// which are recorded in a run's manifest to replay it.
+const MANIFEST_ARGS: &[(&str, &str)] = &[
+    ("key", "key-file"),
+    ("key-dir", "key-dir"),
+    ("key-weights", "key-weights"),
+    ("batch-size", "batch-size"),
+    ("names", "num-names"),
+    ("rate", "rate"),
+    ("ramp", "ramp"),
+    ("duration", "duration"),
+    ("max-batches", "max-batches"),
+    ("unnecessary", "unnecessary"),
+    ("unsatisfiable", "unsatisfiable"),
+    ("dependency-probability", "dependency-probability"),
+    ("max-dependencies", "max-dependencies"),
+    ("read-ratio", "read-ratio"),
+    ("urls", "urls"),
+    ("invalid", "invalid"),
+    ("wildcard", "wildcard"),
+    ("max-in-flight", "max-in-flight"),
+    ("keep-alive", "keep-alive"),
+    ("http2", "http2"),
+    ("commit-latency", "commit-latency"),
+];
+
 fn main() {
-    match SimpleLogger::init(LevelFilter::Warn, Config::default()) {
-        Ok(_) => (),
-        Err(err) => println!("Failed to load logger: {}", err.to_string()),
-    }
+    let arg_matches = get_arg_matches(env::args());
 
-    let arg_matches = get_arg_matches();
+    let log_level = level_from_verbosity(arg_matches.occurrences_of("verbose"));
+    if let Err(err) = LoggingConfig::from_matches(&arg_matches, log_level)
+        .and_then(|config| sawtooth_logging::init(&config))
+    {
+        println!("Failed to load logger: {}", err);
+    }
 
-    match run_load_command(&arg_matches) {
+    match apply_manifest(arg_matches)
+        .and_then(|(arg_matches, manifest)| run_load_command(&arg_matches, manifest.as_ref()))
+    {
         Ok(_) => (),
         Err(err) => println!("{}", err.to_string()),
     }
 }
 
-fn get_arg_matches<'a>() -> ArgMatches<'a> {
-    App::new(APP_NAME)
+/// Reads the run manifest given by --manifest, if any, and fills in the
+/// arguments of its run which are not given on the command line.
+fn apply_manifest<'a>(
+    arg_matches: ArgMatches<'a>,
+) -> Result<(ArgMatches<'a>, Option<RunManifest>), Box<dyn Error>> {
+    let manifest = match arg_matches.value_of("manifest") {
+        Some(path) => RunManifest::read(path)?,
+        None => return Ok((arg_matches, None)),
+    };
+    let argv = manifest.replay_args(env::args().collect(), |long| {
+        MANIFEST_ARGS
+            .iter()
+            .any(|&(name, arg_long)| arg_long == long && arg_matches.occurrences_of(name) > 0)
+    });
+    Ok((get_arg_matches(argv), Some(manifest)))
+}
+
+fn get_arg_matches<'a, I, T>(argv: I) -> ArgMatches<'a>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let app = App::new(APP_NAME)
         .version(VERSION)
         .about("Submit intkey workload at a continuous rate")
         .arg(
@@ -82,6 +144,24 @@ fn get_arg_matches<'a>() -> ArgMatches<'a> {
                 .value_name("KEY_FILE")
                 .help("File containing a private key to sign transactions and batches"),
         )
+        .arg(
+            Arg::with_name("key-dir")
+                .long("key-dir")
+                .takes_value(true)
+                .number_of_values(1)
+                .conflicts_with("key")
+                .value_name("KEY_DIR")
+                .help("Directory of .priv files containing private keys to sign batches with in turn"),
+        )
+        .arg(
+            Arg::with_name("key-weights")
+                .long("key-weights")
+                .takes_value(true)
+                .number_of_values(1)
+                .requires("key-dir")
+                .value_name("KEY_WEIGHTS")
+                .help("Comma separated weights to choose each key in --key-dir by, in file name order"),
+        )
         .arg(
             Arg::with_name("batch-size")
                 .short("n")
@@ -111,6 +191,30 @@ fn get_arg_matches<'a>() -> ArgMatches<'a> {
                 .value_name("RATE")
                 .help("Batches per second to send to a Sawtooth REST Api"),
         )
+        .arg(
+            Arg::with_name("ramp")
+                .long("ramp")
+                .takes_value(true)
+                .number_of_values(1)
+                .value_name("START,END,SECONDS")
+                .help("Ramp the batches per second from START to END over SECONDS, instead of sending at --rate"),
+        )
+        .arg(
+            Arg::with_name("duration")
+                .long("duration")
+                .takes_value(true)
+                .number_of_values(1)
+                .value_name("DURATION")
+                .help("Seconds to run the workload for"),
+        )
+        .arg(
+            Arg::with_name("max-batches")
+                .long("max-batches")
+                .takes_value(true)
+                .number_of_values(1)
+                .value_name("MAX_BATCHES")
+                .help("Most batches to send before stopping the workload"),
+        )
         .arg(
             Arg::with_name("seed")
                 .short("s")
@@ -120,6 +224,15 @@ fn get_arg_matches<'a>() -> ArgMatches<'a> {
                 .value_name("SEED")
                 .help("a u64 value to make the workload reproduceable"),
         )
+        .arg(
+            Arg::with_name("manifest")
+                .long("manifest")
+                .takes_value(true)
+                .number_of_values(1)
+                .conflicts_with("seed")
+                .value_name("MANIFEST")
+                .help("Run manifest of an earlier run to replay an identical workload from"),
+        )
         .arg(
             Arg::with_name("unnecessary")
                 .long("unnecessary")
@@ -138,6 +251,33 @@ fn get_arg_matches<'a>() -> ArgMatches<'a> {
                 .value_name("UNSATISFIABLE")
                 .help("Probability of a transaction having an unsatisfiable dependency"),
         )
+        .arg(
+            Arg::with_name("dependency-probability")
+                .long("dependency-probability")
+                .takes_value(true)
+                .number_of_values(1)
+                .default_value("0.0")
+                .value_name("DEPENDENCY_PROBABILITY")
+                .help("Probability of each of a transaction's --max-dependencies dependencies on earlier transactions being declared"),
+        )
+        .arg(
+            Arg::with_name("max-dependencies")
+                .long("max-dependencies")
+                .takes_value(true)
+                .number_of_values(1)
+                .default_value("1")
+                .value_name("MAX_DEPENDENCIES")
+                .help("Most dependencies on earlier transactions a transaction may declare"),
+        )
+        .arg(
+            Arg::with_name("read-ratio")
+                .long("read-ratio")
+                .takes_value(true)
+                .number_of_values(1)
+                .default_value("0.0")
+                .value_name("READ_RATIO")
+                .help("Proportion of requests which are reads of IntKey names from state, less than 1.0"),
+        )
         .arg(
             Arg::with_name("urls")
                 .short("u")
@@ -178,7 +318,87 @@ fn get_arg_matches<'a>() -> ArgMatches<'a> {
                 .value_name("BASIC_AUTH_PASSWORD")
                 .help("Basic auth password to authenticate with the Sawtooth REST Api"),
         )
-        .get_matches()
+        .arg(
+            Arg::with_name("max-in-flight")
+                .long("max-in-flight")
+                .takes_value(true)
+                .number_of_values(1)
+                .default_value("64")
+                .value_name("MAX_IN_FLIGHT")
+                .help("Most requests in flight to each Sawtooth REST Api at once"),
+        )
+        .arg(
+            Arg::with_name("keep-alive")
+                .long("keep-alive")
+                .takes_value(true)
+                .number_of_values(1)
+                .default_value("90")
+                .value_name("KEEP_ALIVE")
+                .help("Seconds to keep idle connections to a Sawtooth REST Api open"),
+        )
+        .arg(
+            Arg::with_name("http2")
+                .long("http2")
+                .help("Speak HTTP/2 to each Sawtooth REST Api, multiplexing requests over one connection"),
+        )
+        .arg(
+            Arg::with_name("commit-latency")
+                .long("commit-latency")
+                .help("Poll batch statuses to measure the latency and throughput of batch commits"),
+        )
+        .arg(
+            Arg::with_name("latency-csv")
+                .long("latency-csv")
+                .takes_value(true)
+                .number_of_values(1)
+                .value_name("LATENCY_CSV")
+                .help("File to write every latency sample to, as CSV"),
+        )
+        .arg(
+            Arg::with_name("report")
+                .long("report")
+                .takes_value(true)
+                .number_of_values(1)
+                .value_name("REPORT")
+                .help("File to write a JSON report of the workload to once it ends"),
+        )
+        .arg(
+            Arg::with_name("report-html")
+                .long("report-html")
+                .takes_value(true)
+                .number_of_values(1)
+                .requires("report")
+                .value_name("REPORT_HTML")
+                .help("File to also write the report to, as a self-contained HTML page"),
+        )
+        .arg(
+            Arg::with_name("tls-ca-cert")
+                .long("tls-ca-cert")
+                .takes_value(true)
+                .number_of_values(1)
+                .value_name("TLS_CA_CERT")
+                .help("CA certificate, as PEM or DER, to trust when connecting to HTTPS targets"),
+        )
+        .arg(
+            Arg::with_name("tls-client-identity")
+                .long("tls-client-identity")
+                .takes_value(true)
+                .number_of_values(1)
+                .value_name("TLS_CLIENT_IDENTITY")
+                .help(
+                    "PKCS #12 archive of a client certificate and key to present to HTTPS \
+                     targets; its password is read from SAWTOOTH_TLS_IDENTITY_PASSWORD",
+                ),
+        )
+        .arg(
+            Arg::with_name("verbose")
+                .short("v")
+                .long("verbose")
+                .multiple(true)
+                .help("Increase output verbosity"),
+        );
+
+    sawtooth_logging::add_args(app).get_matches_from(argv)
 }
 
 fn err_if_out_of_range(val: f32) -> Result<f32, IntKeyCliError> {
@@ -190,6 +410,15 @@ fn err_if_out_of_range(val: f32) -> Result<f32, IntKeyCliError> {
     Ok(val)
 }
 
+fn less_than_one(val: f32) -> Result<f32, IntKeyCliError> {
+    if val >= 1.0 {
+        return Err(IntKeyCliError {
+            msg: "Value must be less than 1.0".to_string(),
+        });
+    }
+    Ok(val)
+}
+
 fn greater_than_zero32(val: u32) -> Result<u32, IntKeyCliError> {
     if val == 0 {
         return Err(IntKeyCliError {
@@ -199,6 +428,15 @@ fn greater_than_zero32(val: u32) -> Result<u32, IntKeyCliError> {
     Ok(val)
 }
 
+fn greater_than_zero64(val: u64) -> Result<u64, IntKeyCliError> {
+    if val == 0 {
+        return Err(IntKeyCliError {
+            msg: "Value must be greater than zero".to_string(),
+        });
+    }
+    Ok(val)
+}
+
 fn greater_than_zero(val: usize) -> Result<usize, IntKeyCliError> {
     if val == 0 {
         return Err(IntKeyCliError {
@@ -208,7 +446,10 @@ fn greater_than_zero(val: usize) -> Result<usize, IntKeyCliError> {
     Ok(val)
 }
 
-fn run_load_command(args: &ArgMatches) -> Result<(), Box<dyn Error>> {
+fn run_load_command(
+    args: &ArgMatches,
+    replayed: Option<&RunManifest>,
+) -> Result<(), Box<dyn Error>> {
     let batch_size: usize = args
         .value_of("batch-size")
         .unwrap_or("1")
@@ -241,6 +482,36 @@ fn run_load_command(args: &ArgMatches) -> Result<(), Box<dyn Error>> {
         .map_err(IntKeyCliError::from)
         .and_then(greater_than_zero)?;
 
+    let rate_schedule = match args.value_of("ramp") {
+        Some(ramp) => RateSchedule::parse_ramp(ramp)?,
+        None => RateSchedule::Constant(rate as f64),
+    };
+
+    let duration: Option<u64> = match args.value_of("duration") {
+        Some(duration) => Some(
+            duration
+                .parse()
+                .map_err(IntKeyCliError::from)
+                .and_then(greater_than_zero64)?,
+        ),
+        None => None,
+    };
+
+    let max_batches: Option<u64> = match args.value_of("max-batches") {
+        Some(max_batches) => Some(
+            max_batches
+                .parse()
+                .map_err(IntKeyCliError::from)
+                .and_then(greater_than_zero64)?,
+        ),
+        None => None,
+    };
+
+    let limits = WorkloadLimits {
+        duration: duration.map(time::Duration::from_secs),
+        max_batches,
+    };
+
     let unsatisfiable: f32 = args
         .value_of("unsatisfiable")
         .unwrap_or("0.0")
@@ -269,6 +540,27 @@ fn run_load_command(args: &ArgMatches) -> Result<(), Box<dyn Error>> {
         .map_err(IntKeyCliError::from)
         .and_then(err_if_out_of_range)?;
 
+    let dependency_probability: f32 = args
+        .value_of("dependency-probability")
+        .unwrap_or("0.0")
+        .parse()
+        .map_err(IntKeyCliError::from)
+        .and_then(err_if_out_of_range)?;
+
+    let read_ratio: f32 = args
+        .value_of("read-ratio")
+        .unwrap_or("0.0")
+        .parse()
+        .map_err(IntKeyCliError::from)
+        .and_then(err_if_out_of_range)
+        .and_then(less_than_one)?;
+
+    let max_dependencies: usize = args
+        .value_of("max-dependencies")
+        .unwrap_or("1")
+        .parse()
+        .map_err(IntKeyCliError::from)?;
+
     let display: u32 = args
         .value_of("display")
         .unwrap_or("30")
@@ -276,6 +568,30 @@ fn run_load_command(args: &ArgMatches) -> Result<(), Box<dyn Error>> {
         .map_err(IntKeyCliError::from)
         .and_then(greater_than_zero32)?;
 
+    let max_in_flight: usize = args
+        .value_of("max-in-flight")
+        .unwrap_or("64")
+        .parse()
+        .map_err(IntKeyCliError::from)
+        .and_then(greater_than_zero)?;
+
+    let keep_alive: u64 = args
+        .value_of("keep-alive")
+        .unwrap_or("90")
+        .parse()
+        .map_err(IntKeyCliError::from)?;
+
+    let connection_options = ConnectionOptions {
+        max_in_flight,
+        keep_alive_timeout: Some(time::Duration::from_secs(keep_alive)),
+        http2: args.is_present("http2"),
+        tls: TlsOptions::load(
+            args.value_of("tls-ca-cert"),
+            args.value_of("tls-client-identity"),
+        )?,
+        ..ConnectionOptions::default()
+    };
+
     let username = args.value_of("username");
     let password = args.value_of("password");
 
@@ -289,6 +605,11 @@ fn run_load_command(args: &ArgMatches) -> Result<(), Box<dyn Error>> {
         }
     };
 
+    let latency = LatencyTracker::new(
+        args.is_present("commit-latency"),
+        args.value_of("latency-csv"),
+    )?;
+
     let seed: u64 = args.value_of("seed").map(str::parse).unwrap_or_else(|| {
         let mut rng = rand::thread_rng();
         Ok(rng.gen::<u64>())
@@ -299,11 +620,7 @@ fn run_load_command(args: &ArgMatches) -> Result<(), Box<dyn Error>> {
     let private_key: Result<Box<dyn signing::PrivateKey>, Box<dyn Error>> =
         match args.value_of("key") {
             Some(file) => {
-                let mut key_file = File::open(file)?;
-                let mut buf = String::new();
-                key_file.read_to_string(&mut buf)?;
-                buf.pop(); // remove the new line
-                let private_key = Secp256k1PrivateKey::from_hex(&buf)?;
+                let private_key = load_private_key(file, passphrase_from_env)?;
                 Ok(Box::new(private_key))
             }
             None => {
@@ -312,14 +629,42 @@ fn run_load_command(args: &ArgMatches) -> Result<(), Box<dyn Error>> {
             }
         };
 
-    let priv_key = private_key?;
+    let private_keys: Vec<Box<dyn signing::PrivateKey>> = match args.value_of("key-dir") {
+        Some(dir) => read_private_keys(Path::new(dir))?
+            .into_iter()
+            .map(|key| Box::new(key) as Box<dyn signing::PrivateKey>)
+            .collect(),
+        None => vec![private_key?],
+    };
+
+    let key_fingerprints = private_keys
+        .iter()
+        .map(|key| {
+            context
+                .get_public_key(key.as_ref())
+                .map(|public_key| key_fingerprint(public_key.as_ref()))
+        })
+        .collect::<Result<Vec<String>, signing::Error>>()?;
+    if let Some(manifest) = replayed {
+        manifest.check_replay("intkey", &key_fingerprints)?;
+    }
 
-    let signer = signing::Signer::new(context.as_ref(), priv_key.as_ref());
+    let selection = match args.value_of("key-weights") {
+        Some(weights) => SignerSelection::parse_weights(weights)?,
+        None => SignerSelection::RoundRobin,
+    };
 
-    let signer_ref = &signer;
+    let signers = SignerPool::new(
+        private_keys
+            .iter()
+            .map(|key| signing::Signer::new(context.as_ref(), key.as_ref()))
+            .collect(),
+        selection,
+        seed,
+    )?;
 
     let mut transformer = IntKeyTransformer::new(
-        signer_ref,
+        &signers,
         seed,
         unsatisfiable,
         wildcard,
@@ -327,32 +672,105 @@ fn run_load_command(args: &ArgMatches) -> Result<(), Box<dyn Error>> {
         unnecessary,
     );
 
+    let set_names = SetNames::new(num_names);
+    let mut read_iter = IntKeyReadIterator::new(set_names.clone(), seed);
+
     let mut transaction_iterator = IntKeyIterator::new(num_names, invalid, seed)
+        .inspect(|payload| {
+            if payload.verb == "set" {
+                set_names.record(&payload.name);
+            }
+        })
         .map(|payload| transformer.intkey_payload_to_transaction(&payload))
         .filter_map(|payload| payload.ok());
-    let mut batch_iter =
-        SignedBatchIterator::new(&mut transaction_iterator, batch_size, signer_ref);
+    let mut batch_iter = SignedBatchIterator::new(&mut transaction_iterator, batch_size, &signers);
+    if dependency_probability > 0.0 {
+        batch_iter = batch_iter.with_dependencies(DependencyGenerator::new(
+            f64::from(dependency_probability),
+            max_dependencies,
+            seed,
+        ));
+    }
     let mut batchlist_iter = InfiniteBatchListIterator::new(&mut batch_iter);
 
-    let time_to_wait: u32 = 1_000_000_000 / rate as u32;
-
-    println!("--invalid {} --batch-size {} --rate {} --wildcard {} --urls {:?} --unsatisfiable {} --seed {:?} --num-names {} --display {}",
+    println!("--invalid {} --batch-size {} --rate {:?} --wildcard {} --urls {:?} --unsatisfiable {} --seed {:?} --num-names {} --display {} --duration {:?} --max-batches {:?} --dependency-probability {} --max-dependencies {} --read-ratio {}",
         invalid,
         batch_size,
-        rate,
+        rate_schedule,
         wildcard,
         urls,
         unsatisfiable,
         seed,
         num_names,
-        display);
+        display,
+        duration,
+        max_batches,
+        dependency_probability,
+        max_dependencies,
+        read_ratio);
+
+    let report = args.value_of("report").map(|json_path| ReportOptions {
+        json_path: json_path.to_string(),
+        html_path: args.value_of("report-html").map(String::from),
+        configuration: vec![
+            ("workload".into(), "intkey".into()),
+            ("urls".into(), urls.join(",")),
+            ("rate".into(), format!("{:?}", rate_schedule)),
+            ("batch-size".into(), batch_size.to_string()),
+            ("num-names".into(), num_names.to_string()),
+            ("duration".into(), format!("{:?}", duration)),
+            ("max-batches".into(), format!("{:?}", max_batches)),
+            ("invalid".into(), invalid.to_string()),
+            ("wildcard".into(), wildcard.to_string()),
+            ("unsatisfiable".into(), unsatisfiable.to_string()),
+            ("unnecessary".into(), unnecessary.to_string()),
+            (
+                "dependency-probability".into(),
+                dependency_probability.to_string(),
+            ),
+            ("max-dependencies".into(), max_dependencies.to_string()),
+            ("read-ratio".into(), read_ratio.to_string()),
+            ("max-in-flight".into(), max_in_flight.to_string()),
+            ("seed".into(), seed.to_string()),
+        ],
+    });
+
+    if let Some(ref report) = report {
+        let manifest = RunManifest {
+            workload: "intkey".into(),
+            version: VERSION.into(),
+            seed,
+            key_fingerprints,
+            generated_key: !args.is_present("key") && !args.is_present("key-dir"),
+            arguments: MANIFEST_ARGS
+                .iter()
+                .filter(|&&(name, _)| args.is_present(name))
+                .map(|&(name, long)| (long.to_string(), args.value_of(name).map(String::from)))
+                .collect(),
+        };
+        manifest.write(&manifest_path(&report.json_path))?;
+    }
+
+    let reads = if read_ratio > 0.0 {
+        Some(ReadOptions {
+            ratio: f64::from(read_ratio),
+            addresses: &mut read_iter,
+        })
+    } else {
+        None
+    };
 
     match run_workload(
         &mut batchlist_iter,
-        time_to_wait,
+        &rate_schedule,
+        &limits,
         display,
         urls,
         &basic_auth,
+        &connection_options,
+        latency,
+        reads,
+        report.as_ref(),
     ) {
         Ok(_) => Ok(()),
         Err(err) => Err(Box::new(err)),
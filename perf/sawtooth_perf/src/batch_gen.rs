@@ -17,18 +17,22 @@
 
 //! Tools for generating signed batches from a stream of transactions
 
+use std::collections::{HashMap, VecDeque};
 use std::error;
 use std::fmt;
 use std::io::Read;
 use std::io::Write;
 
 use protobuf::{self, Message};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use sawtooth_sdk::messages::batch::Batch;
 use sawtooth_sdk::messages::batch::BatchHeader;
-use sawtooth_sdk::messages::transaction::Transaction;
+use sawtooth_sdk::messages::transaction::{Transaction, TransactionHeader};
 
 use sawtooth_sdk::signing;
 
+use signer_pool::SignerPool;
 use source::LengthDelimitedMessageSource;
 
 /// Generates signed batches from a stream of length-delimited transactions.
@@ -181,41 +185,185 @@ fn batch_transactions(txns: Vec<Transaction>, signer: &signing::Signer) -> Batch
     }
 }
 
+/// The number of most recently generated transactions which a transaction
+/// may be given a dependency on.
+const DEPENDENCY_WINDOW: usize = 1_000;
+
+/// The number of re-signed transactions whose new ids are remembered, so that
+/// dependencies declared on their original ids can be updated.
+const RESIGNED_ID_CAPACITY: usize = 100_000;
+
+/// Adds dependencies on earlier generated transactions to transactions, so
+/// that a scheduler has to resolve them rather than taking its path for
+/// independent transactions.
+///
+/// Adding a dependency changes a transaction's header, so the transaction is
+/// re-signed and given a new id. Dependencies which later transactions
+/// declare on the original id are updated to the new one.
+pub struct DependencyGenerator {
+    probability: f64,
+    max_fan_in: usize,
+    rng: StdRng,
+    // The ids of the most recently generated transactions, oldest first
+    recent_ids: VecDeque<String>,
+    // The ids of re-signed transactions, by their original ids
+    resigned_ids: HashMap<String, String>,
+    resigned_order: VecDeque<String>,
+}
+
+impl DependencyGenerator {
+    /// Creates a generator which gives each transaction up to `max_fan_in`
+    /// dependencies, each added with `probability`, on transactions chosen
+    /// at random from those recently generated. The choices are reproducible
+    /// for a given `seed`.
+    pub fn new(probability: f64, max_fan_in: usize, seed: u64) -> Self {
+        DependencyGenerator {
+            probability,
+            max_fan_in,
+            rng: StdRng::seed_from_u64(seed),
+            recent_ids: VecDeque::new(),
+            resigned_ids: HashMap::new(),
+            resigned_order: VecDeque::new(),
+        }
+    }
+
+    /// Returns the transaction with its dependencies added, re-signed by
+    /// `signer` if its header has changed. `signer` must be the transaction's
+    /// signer.
+    pub fn add_dependencies(
+        &mut self,
+        txn: Transaction,
+        signer: &signing::Signer,
+    ) -> Result<Transaction, BatchingError> {
+        let mut header: TransactionHeader = protobuf::parse_from_bytes(txn.get_header())?;
+        let mut changed = false;
+
+        for dependency in header.mut_dependencies().iter_mut() {
+            if let Some(resigned_id) = self.resigned_ids.get(dependency) {
+                *dependency = resigned_id.clone();
+                changed = true;
+            }
+        }
+
+        for _ in 0..self.max_fan_in {
+            if self.recent_ids.is_empty() || !self.rng.gen_bool(self.probability) {
+                continue;
+            }
+            let index = self.rng.gen_range(0, self.recent_ids.len());
+            let id = &self.recent_ids[index];
+            if !header.get_dependencies().contains(id) {
+                header.mut_dependencies().push(id.clone());
+                changed = true;
+            }
+        }
+
+        let txn = if changed {
+            self.resign(txn, &header, signer)?
+        } else {
+            txn
+        };
+
+        self.recent_ids
+            .push_back(txn.get_header_signature().to_string());
+        if self.recent_ids.len() > DEPENDENCY_WINDOW {
+            self.recent_ids.pop_front();
+        }
+
+        Ok(txn)
+    }
+
+    fn resign(
+        &mut self,
+        mut txn: Transaction,
+        header: &TransactionHeader,
+        signer: &signing::Signer,
+    ) -> Result<Transaction, BatchingError> {
+        let header_bytes = header.write_to_bytes()?;
+        let signature = signer.sign(&header_bytes)?;
+
+        let original_id = txn.take_header_signature();
+        self.resigned_ids
+            .insert(original_id.clone(), signature.clone());
+        self.resigned_order.push_back(original_id);
+        if self.resigned_order.len() > RESIGNED_ID_CAPACITY {
+            if let Some(oldest) = self.resigned_order.pop_front() {
+                self.resigned_ids.remove(&oldest);
+            }
+        }
+
+        txn.set_header(header_bytes);
+        txn.set_header_signature(signature);
+        Ok(txn)
+    }
+}
+
+/// Produces signed batches from an iterator of Transactions, signing each
+/// batch with the current signer of a pool and then moving to the next. The
+/// transactions must be signed with the pool's current signer as they are
+/// produced.
 pub struct SignedBatchIterator<'a> {
     transaction_iterator: &'a mut dyn Iterator<Item = Transaction>,
     max_batch_size: usize,
-    signer: &'a signing::Signer<'a>,
+    signers: &'a SignerPool<'a>,
+    dependencies: Option<DependencyGenerator>,
 }
 
 impl<'a> SignedBatchIterator<'a> {
     pub fn new(
         iterator: &'a mut dyn Iterator<Item = Transaction>,
         max_batch_size: usize,
-        signer: &'a signing::Signer,
+        signers: &'a SignerPool<'a>,
     ) -> Self {
         SignedBatchIterator {
             transaction_iterator: iterator,
             max_batch_size,
-            signer,
+            signers,
+            dependencies: None,
         }
     }
+
+    /// Adds dependencies on earlier transactions to the transactions batched,
+    /// using `dependencies`.
+    pub fn with_dependencies(mut self, dependencies: DependencyGenerator) -> Self {
+        self.dependencies = Some(dependencies);
+        self
+    }
 }
 
 impl<'a> Iterator for SignedBatchIterator<'a> {
     type Item = BatchResult;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let txns = self
+        let txns: Vec<Transaction> = self
             .transaction_iterator
             .take(self.max_batch_size)
             .collect();
 
-        Some(batch_transactions(txns, self.signer))
+        let txns = match self.dependencies {
+            Some(ref mut dependencies) => {
+                let signer = self.signers.current();
+                match txns
+                    .into_iter()
+                    .map(|txn| dependencies.add_dependencies(txn, signer))
+                    .collect::<Result<Vec<_>, _>>()
+                {
+                    Ok(txns) => txns,
+                    Err(err) => return Some(Err(err)),
+                }
+            }
+            None => txns,
+        };
+
+        let batch = batch_transactions(txns, self.signers.current());
+        self.signers.advance();
+
+        Some(batch)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::DependencyGenerator;
     use super::LengthDelimitedMessageSource;
     use super::SignedBatchProducer;
     use super::TransactionSource;
@@ -379,6 +527,59 @@ mod tests {
         assert_eq!(batch_header.transaction_ids[0], String::from("sig3"));
     }
 
+    #[test]
+    fn add_dependencies() {
+        let context = signing::create_context("secp256k1").unwrap();
+        let private_key = context.new_random_private_key().unwrap();
+        let signer = signing::Signer::new(context.as_ref(), private_key.as_ref());
+
+        let mut dependencies = DependencyGenerator::new(1.0, 1, 0);
+
+        // There is nothing earlier to depend on, so the first is unchanged
+        let first = dependencies
+            .add_dependencies(make_txn("sig1"), &signer)
+            .unwrap();
+        assert_eq!(first.get_header_signature(), "sig1");
+
+        let second = dependencies
+            .add_dependencies(make_txn("sig2"), &signer)
+            .unwrap();
+        let header: TransactionHeader = Message::parse_from_bytes(&second.header).unwrap();
+        assert_eq!(header.get_dependencies(), &["sig1".to_string()]);
+        assert_ne!(second.get_header_signature(), "sig2");
+        assert_eq!(
+            signer.sign(&second.header).unwrap(),
+            second.get_header_signature()
+        );
+
+        // A dependency on the second's original id is updated to its new id
+        let mut third = make_txn("sig3");
+        let mut header: TransactionHeader = Message::parse_from_bytes(&third.header).unwrap();
+        header.mut_dependencies().push("sig2".to_string());
+        third.set_header(header.write_to_bytes().unwrap());
+        let third = dependencies.add_dependencies(third, &signer).unwrap();
+        let header: TransactionHeader = Message::parse_from_bytes(&third.header).unwrap();
+        assert!(header
+            .get_dependencies()
+            .contains(&second.get_header_signature().to_string()));
+        assert!(!header.get_dependencies().contains(&"sig2".to_string()));
+    }
+
+    #[test]
+    fn no_dependencies_without_probability() {
+        let context = signing::create_context("secp256k1").unwrap();
+        let private_key = context.new_random_private_key().unwrap();
+        let signer = signing::Signer::new(context.as_ref(), private_key.as_ref());
+
+        let mut dependencies = DependencyGenerator::new(0.0, 3, 0);
+        for sig in &["sig1", "sig2", "sig3"] {
+            let txn = dependencies
+                .add_dependencies(make_txn(sig), &signer)
+                .unwrap();
+            assert_eq!(txn.get_header_signature(), *sig);
+        }
+    }
+
     fn make_txn(sig: &str) -> Transaction {
         let mut txn_header = TransactionHeader::new();
 
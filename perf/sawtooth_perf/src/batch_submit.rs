@@ -20,47 +20,60 @@
 use std::cell::RefCell;
 use std::error;
 use std::fmt;
-use std::io::Read;
-use std::iter::Cycle;
+use std::io::{Read, Write};
 use std::rc::Rc;
 use std::sync::mpsc;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::thread;
 use std::time;
-use std::vec::IntoIter;
 
 use futures::{Future, Stream};
-use hyper::client::{Client, HttpConnector, Request};
-use hyper::header::{ContentLength, ContentType};
-use hyper::Method;
+use hyper::client::Client;
+use hyper::header::{CONTENT_LENGTH, CONTENT_TYPE};
+use hyper::{Body, Method, Request};
 use protobuf;
 use protobuf::Message;
-use tokio_core::reactor::{Core, Interval};
-use tokio_timer;
+use tokio::runtime::current_thread::Runtime;
+use tokio::timer::Interval;
 
 use sawtooth_sdk::messages::batch::Batch;
 use sawtooth_sdk::messages::batch::BatchList;
 
 use batch_gen::{BatchResult, BatchingError};
 use batch_map::BatchMap;
+use capture::CaptureWriter;
+use latency::LatencyTracker;
+use rate::{RateSchedule, WorkloadLimits};
+use report::{self, ReportOptions};
 use source::LengthDelimitedMessageSource;
+use target::{ConnectionOptions, Target, TargetPool};
+use tls::{self, TlsOptions};
 use workload;
 
 /// Populates a channel from a stream of length-delimited batches.
 /// Starts one workload submitter of the appropriate type (http, zmq)
 /// per target. Workload submitters consume from the channel at
 /// the configured rate until the channel is exhausted.
+///
+/// If `capture` is given, each batch is written to it with the time it was
+/// submitted, so the submission can be replayed. HTTPS targets are connected
+/// to with the certificates of `tls`, and spoken to over HTTP/2 if `http2` is
+/// set.
 pub fn submit_signed_batches(
     reader: &mut dyn Read,
     target: String,
     rate: usize,
+    capture: Option<Box<dyn Write + Send>>,
+    tls: &TlsOptions,
+    http2: bool,
 ) -> Result<(), BatchReadingError> {
     let (sender, receiver) = mpsc::channel();
     let receiver = Arc::new(Mutex::new(receiver));
 
+    let tls = tls.clone();
     let submit_thread = thread::spawn(move || {
-        http_submitter(&target, rate as u64, &receiver);
+        http_submitter(&target, rate as u64, &receiver, capture, &tls, http2);
     });
 
     let mut feeder = BatchListFeeder::new(reader);
@@ -87,17 +100,17 @@ pub fn http_submitter(
     target: &str,
     rate: u64,
     receiver: &Arc<Mutex<mpsc::Receiver<Option<BatchList>>>>,
+    capture: Option<Box<dyn Write + Send>>,
+    tls: &TlsOptions,
+    http2: bool,
 ) {
-    let mut core = Core::new().unwrap();
+    let mut capture = capture.map(CaptureWriter::new);
 
-    let client = Client::configure()
-        .connector(HttpConnector::new(1, &core.handle()))
-        .keep_alive(true)
-        .build(&core.handle());
+    let mut runtime = Runtime::new().unwrap();
 
-    let timer = tokio_timer::wheel()
-        .tick_duration(time::Duration::new(0, 1_000_000))
-        .build();
+    let client = Client::builder().keep_alive(true).http2_only(http2).build(
+        tls::https_connector(1, tls, http2).expect("TLS options are checked when they are loaded"),
+    );
 
     // Define a target timeslice (how often to submit batches) based
     // on number of nanoseconds in a second divided by rate
@@ -120,104 +133,229 @@ pub fn http_submitter(
 
         let bytes = batch_list.write_to_bytes().unwrap();
 
-        let mut req = Request::new(Method::Post, uri.parse().unwrap());
-        req.headers_mut().set(ContentType::octet_stream());
-        req.headers_mut().set(ContentLength(bytes.len() as u64));
-        req.set_body(bytes);
-
-        let work = client.request(req).and_then(|_| {
-            count += 1;
-
-            if count % rate == 0 {
-                let log_duration = time::Instant::now() - last_time;
-                let log_duration_flt =
-                    log_duration.as_secs() as f64 + f64::from(log_duration.subsec_nanos()) * 1e-9;
-
-                println!(
-                    "target: {} target rate: {} count: {} effective rate: {} per sec",
-                    target,
-                    rate,
-                    count,
-                    (count - last_count) as f64 / log_duration_flt
-                );
-
-                last_count = count;
-                last_time = time::Instant::now();
+        if let Some(ref mut writer) = capture {
+            if let Err(err) = writer.write(&batch_list) {
+                warn!("Unable to capture batch, no longer capturing: {}", err);
+                capture = None;
             }
+        }
 
-            Ok(())
-        });
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri(uri.as_str())
+            .header(CONTENT_TYPE, "application/octet-stream")
+            .header(CONTENT_LENGTH, bytes.len())
+            .body(Body::from(bytes))
+            .unwrap();
+
+        let work = client
+            .request(req)
+            .and_then(workload::response_status)
+            .map(|_| {
+                count += 1;
+
+                if count % rate == 0 {
+                    let log_duration = time::Instant::now() - last_time;
+                    let log_duration_flt = log_duration.as_secs() as f64
+                        + f64::from(log_duration.subsec_nanos()) * 1e-9;
+
+                    println!(
+                        "target: {} target rate: {} count: {} effective rate: {} per sec",
+                        target,
+                        rate,
+                        count,
+                        (count - last_count) as f64 / log_duration_flt
+                    );
+
+                    last_count = count;
+                    last_time = time::Instant::now();
+                }
+            });
 
         let request_time = time::Instant::now();
-        core.run(work).unwrap();
-        let runtime = time::Instant::now() - request_time;
+        runtime.block_on(work).unwrap();
+        let request_duration = time::Instant::now() - request_time;
 
-        if let Some(sleep_duration) = timeslice.checked_sub(runtime) {
-            let sleep = timer.sleep(sleep_duration);
-            sleep.wait().unwrap();
+        if let Some(sleep_duration) = timeslice.checked_sub(request_duration) {
+            thread::sleep(sleep_duration);
         }
     }
 }
 
-/// Run a continuous load of the BatchLists that are generated by BatchListIter.
+/// How often the statuses of batches waiting to be committed are polled, when
+/// commits are being tracked.
+const STATUS_POLL_INTERVAL: time::Duration = time::Duration::from_secs(1);
+
+/// How often batches which are due to be sent are sent. Every batch due since
+/// the last tick is sent at once, so the rate is not limited by the tick.
+const PACING_TICK: time::Duration = time::Duration::from_millis(1);
+
+/// The longest to wait for requests still in flight once a workload has
+/// reached its limits.
+const DRAIN_TIMEOUT: time::Duration = time::Duration::from_secs(30);
+
+/// State reads to interleave with the batches of a workload.
+pub struct ReadOptions<'a> {
+    /// The proportion of the requests which are reads, at least 0 and less
+    /// than 1
+    pub ratio: f64,
+    /// The state addresses to read; no read is made while it has none
+    pub addresses: &'a mut dyn Iterator<Item = String>,
+}
+
+/// Run a load of the BatchLists that are generated by BatchListIter, at the
+/// rates given by `rate`, until the workload reaches its `limits`.
+///
+/// Each target has its own pool of kept-alive connections, configured by
+/// `connection_options`, and is taken out of rotation while its status checks
+/// fail; batches due while no target can take another request are counted as
+/// throttled rather than sent late. Batch latencies are recorded by
+/// `latency`, which logs them at each update. If `reads` is given, state
+/// reads are sent along with the batches, and their latency is recorded
+/// separately. A summary is logged once the workload ends, and a report is
+/// written if `report` is given.
+#[allow(clippy::too_many_arguments)]
 pub fn run_workload(
     batch_list_iter: &mut dyn Iterator<Item = BatchListResult>,
-    time_to_wait: u32,
+    rate: &RateSchedule,
+    limits: &WorkloadLimits,
     update_time: u32,
     targets: Vec<String>,
     basic_auth: &Option<String>,
+    connection_options: &ConnectionOptions,
+    mut latency: LatencyTracker,
+    mut reads: Option<ReadOptions>,
+    report: Option<&ReportOptions>,
 ) -> Result<(), workload::WorkloadError> {
-    let mut core = Core::new().unwrap();
-    let handle = core.handle();
-    let client = Rc::new(Client::configure().build(&handle));
+    let mut runtime = Runtime::new()?;
+    let targets = TargetPool::new(targets, connection_options);
     let counter = Rc::new(workload::HTTPRequestCounter::new());
+    if reads.is_some() {
+        latency.track_reads();
+    }
+    let latency = Rc::new(RefCell::new(latency));
+
+    {
+        let targets: Vec<Rc<Target>> = targets.targets().to_vec();
+        let basic_auth = basic_auth.clone();
+        let checks = Interval::new_interval(connection_options.health_check_interval)
+            .map_err(|err| info!("Target health checks stopped: {}", err))
+            .for_each(move |_| {
+                for target in &targets {
+                    if let Err(err) = workload::check_target_health(target, &basic_auth) {
+                        info!("Unable to check the health of {}: {}", target.url(), err);
+                    }
+                }
+                Ok(())
+            });
+        runtime.spawn(checks);
+    }
 
-    let mut urls: Cycle<IntoIter<String>> = targets.into_iter().cycle();
+    if latency.borrow().is_tracking_commits() {
+        let targets: Vec<Rc<Target>> = targets.targets().to_vec();
+        let basic_auth = basic_auth.clone();
+        let latency = Rc::clone(&latency);
+        let poll = Interval::new_interval(STATUS_POLL_INTERVAL)
+            .map_err(|err| info!("Batch status polling stopped: {}", err))
+            .for_each(move |_| {
+                // Any target can report a commit, so poll the first one up
+                if let Some(target) = targets.iter().find(|target| target.is_healthy()) {
+                    if let Err(err) = workload::poll_batch_statuses(target, &basic_auth, &latency) {
+                        info!("Unable to poll batch statuses: {}", err);
+                    }
+                }
+                Ok(())
+            });
+        runtime.spawn(poll);
+    }
 
     let batch_map = Rc::new(RefCell::new(BatchMap::new()));
-    let batch_map_clone = Rc::clone(&batch_map);
-
     let batches = Rc::new(RefCell::new(Vec::new()));
-    let batches_clone = Rc::clone(&batches);
 
-    let interval = Interval::new(time::Duration::new(0, time_to_wait), &handle).unwrap();
+    let start_time = time::Instant::now();
+    let mut dispatched: u64 = 0;
+    // The reads due, which are sent as they add up to whole reads
+    let mut reads_due: f64 = 0.0;
     let mut log_time = time::Instant::now();
-    let stream = interval
-        .map_err(workload::WorkloadError::from)
-        .map(|_: ()| -> Result<(), workload::WorkloadError> {
-            let counter_clone = Rc::clone(&counter);
-            workload::log(&counter_clone, &mut log_time, update_time)
-        })
-        .map(move |_| -> Result<BatchList, workload::WorkloadError> {
-            let batch_map = Rc::clone(&batch_map_clone);
-            let batches_clone = Rc::clone(&batches_clone);
-            workload::get_next_batchlist(batch_list_iter, &batch_map, &batches_clone)
-        })
-        .map(|batch_list: Result<BatchList, workload::WorkloadError>| {
-            let basic_auth_c = basic_auth.clone();
-            let urls_c = &mut urls;
-            workload::form_request_from_batchlist(urls_c, batch_list, &basic_auth_c)
-        })
+    let stream = Interval::new_interval(PACING_TICK)
         .map_err(workload::WorkloadError::from)
-        .and_then(
-            |req: Result<(Request, Option<String>), workload::WorkloadError>| {
-                let handle_clone = handle.clone();
-                let client_clone = Rc::clone(&client);
-                let counter_clone = Rc::clone(&counter);
-                let batches_clone = Rc::clone(&batches);
+        .take_while(|_| Ok(!limits.is_reached(start_time.elapsed(), counter.total_sent() as u64)))
+        .for_each(|_| -> Result<(), workload::WorkloadError> {
+            workload::log(&counter, &targets, &latency, &mut log_time, update_time)?;
+
+            let elapsed = start_time.elapsed();
+            let due = rate.batches_due(elapsed);
+
+            while dispatched < due && !limits.is_reached(elapsed, counter.total_sent() as u64) {
+                let target = match targets.next_available() {
+                    Some(target) => target,
+                    None => {
+                        counter.add_throttled((due - dispatched) as usize);
+                        dispatched = due;
+                        break;
+                    }
+                };
+                dispatched += 1;
+
+                let batch_list =
+                    workload::get_next_batchlist(batch_list_iter, &batch_map, &batches);
+                let req = workload::form_request_from_batchlist(&target, batch_list, basic_auth);
                 workload::make_request(
-                    &client_clone,
-                    &handle_clone,
-                    counter_clone,
+                    target,
+                    Rc::clone(&counter),
                     Rc::clone(&batch_map),
-                    batches_clone,
+                    Rc::clone(&batches),
+                    Rc::clone(&latency),
                     req,
-                )
-            },
-        )
-        .for_each(|_| Ok(()));
+                )?;
+
+                if let Some(ref mut reads) = reads {
+                    reads_due += reads.ratio / (1.0 - reads.ratio);
+                    while reads_due >= 1.0 {
+                        reads_due -= 1.0;
+                        let (target, address) =
+                            match (targets.next_available(), reads.addresses.next()) {
+                                (Some(target), Some(address)) => (target, address),
+                                _ => break,
+                            };
+                        workload::make_read_request(
+                            target,
+                            basic_auth,
+                            Rc::clone(&counter),
+                            Rc::clone(&latency),
+                            address,
+                        )?;
+                    }
+                }
+            }
 
-    core.run(stream)
+            Ok(())
+        });
+
+    let result = runtime.block_on(stream);
+
+    if result.is_ok() {
+        // Wait for the requests still in flight, so they are in the summary
+        let drain_start = time::Instant::now();
+        let drain = Interval::new_interval(PACING_TICK)
+            .take_while(|_| {
+                Ok(drain_start.elapsed() < DRAIN_TIMEOUT
+                    && targets
+                        .targets()
+                        .iter()
+                        .any(|target| target.in_flight() > 0))
+            })
+            .for_each(|_| Ok(()));
+        runtime.block_on(drain)?;
+    }
+
+    let elapsed = start_time.elapsed();
+    counter.log_summary(elapsed);
+    latency.borrow_mut().log_summary(elapsed);
+    let written = report.map_or(Ok(()), |options| {
+        report::write_report(options, &counter, &latency.borrow(), elapsed)
+    });
+    result.and(written.map_err(workload::WorkloadError::from))
 }
 
 type BatchSource<'a> = LengthDelimitedMessageSource<'a, Batch>;
@@ -0,0 +1,258 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+//! Tools for capturing submitted batches with the time they were submitted,
+//! and replaying them with the same timing.
+//!
+//! A capture is a stream of records, each a varint of the microseconds from
+//! the start of the capture to the batch's submission, followed by the
+//! length-delimited Batch.
+
+use std::io::{Read, Write};
+use std::rc::Rc;
+use std::time;
+
+use futures::{Future, Stream};
+use hyper::header::{CONTENT_LENGTH, CONTENT_TYPE};
+use hyper::Error as HyperError;
+use hyper::{Body, Method, StatusCode};
+use protobuf::{self, Message};
+use tokio::runtime::current_thread::Runtime;
+use tokio::timer::{Delay, Interval};
+
+use sawtooth_sdk::messages::batch::{Batch, BatchList};
+
+use batch_submit::BatchReadingError;
+use target::{ConnectionOptions, Target};
+use workload::{request_builder, response_status, HTTPRequestCounter, WorkloadError};
+
+/// The longest to wait for requests still in flight once every captured
+/// batch has been replayed.
+const DRAIN_TIMEOUT: time::Duration = time::Duration::from_secs(30);
+
+/// How often requests still in flight are checked for while draining.
+const DRAIN_TICK: time::Duration = time::Duration::from_millis(10);
+
+/// Writes batches to a capture, with the time since the capture started.
+pub struct CaptureWriter<W: Write> {
+    writer: W,
+    start: time::Instant,
+}
+
+impl<W: Write> CaptureWriter<W> {
+    pub fn new(writer: W) -> Self {
+        CaptureWriter {
+            writer,
+            start: time::Instant::now(),
+        }
+    }
+
+    /// Records that the batches in `batch_list` were submitted now.
+    pub fn write(&mut self, batch_list: &BatchList) -> Result<(), protobuf::ProtobufError> {
+        let offset = to_micros(self.start.elapsed());
+        self.write_at(offset, batch_list)
+    }
+
+    fn write_at(
+        &mut self,
+        offset: u64,
+        batch_list: &BatchList,
+    ) -> Result<(), protobuf::ProtobufError> {
+        let mut output = protobuf::CodedOutputStream::new(&mut self.writer);
+        for batch in batch_list.get_batches() {
+            output.write_raw_varint64(offset)?;
+            batch.write_length_delimited_to(&mut output)?;
+        }
+        output.flush()
+    }
+}
+
+/// Reads the batches in a capture, with the time from the start of the
+/// capture that each was submitted.
+pub struct CaptureSource<'a> {
+    source: protobuf::CodedInputStream<'a>,
+}
+
+impl<'a> CaptureSource<'a> {
+    pub fn new(source: &'a mut dyn Read) -> Self {
+        CaptureSource {
+            source: protobuf::CodedInputStream::new(source),
+        }
+    }
+
+    fn read_record(&mut self) -> Result<Option<(time::Duration, Batch)>, protobuf::ProtobufError> {
+        if self.source.eof()? {
+            return Ok(None);
+        }
+
+        let offset = self.source.read_raw_varint64()?;
+        let len = self.source.read_raw_varint32()?;
+        let buf = self.source.read_raw_bytes(len)?;
+        let batch = protobuf::parse_from_bytes(&buf)?;
+
+        Ok(Some((time::Duration::from_micros(offset), batch)))
+    }
+}
+
+impl<'a> Iterator for CaptureSource<'a> {
+    type Item = Result<(time::Duration, Batch), BatchReadingError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.read_record() {
+            Ok(Some(record)) => Some(Ok(record)),
+            Ok(None) => None,
+            Err(err) => Some(Err(BatchReadingError::MessageError(err))),
+        }
+    }
+}
+
+/// Submits the batches in a capture to the target, each at the same time
+/// from the start of the replay as it was from the start of the capture.
+pub fn replay_captured_batches(reader: &mut dyn Read, target: &str) -> Result<(), WorkloadError> {
+    let mut runtime = Runtime::new()?;
+    let target = Rc::new(Target::new(
+        target.to_string(),
+        &ConnectionOptions::default(),
+    ));
+    let batches_url = format!("{}/batches", target.url());
+    let counter = Rc::new(HTTPRequestCounter::new());
+
+    let start = time::Instant::now();
+    let mut late = 0;
+    for record in CaptureSource::new(reader) {
+        let (offset, batch) = record?;
+
+        let elapsed = start.elapsed();
+        if offset > elapsed {
+            runtime.block_on(Delay::new(time::Instant::now() + (offset - elapsed)))?;
+        } else if elapsed - offset > time::Duration::from_millis(10) {
+            late += 1;
+        }
+
+        let mut batch_list = BatchList::new();
+        batch_list.set_batches(protobuf::RepeatedField::from_vec(vec![batch]));
+        let bytes = batch_list.write_to_bytes()?;
+
+        let req = request_builder(Method::POST, &batches_url, &None)
+            .header(CONTENT_TYPE, "application/octet-stream")
+            .header(CONTENT_LENGTH, bytes.len())
+            .body(Body::from(bytes))?;
+
+        counter.increment_sent();
+        target.start_request();
+        let request_target = Rc::clone(&target);
+        let request_counter = Rc::clone(&counter);
+        let request = target.client().request(req).and_then(response_status).then(
+            move |response| -> Result<(), ()> {
+                request_target.finish_request();
+                record_response(&request_target, &request_counter, response);
+                Ok(())
+            },
+        );
+        runtime.spawn(request);
+    }
+
+    // Wait for the requests still in flight, so they are in the summary
+    let drain_start = time::Instant::now();
+    let drain = Interval::new_interval(DRAIN_TICK)
+        .take_while(|_| Ok(drain_start.elapsed() < DRAIN_TIMEOUT && target.in_flight() > 0))
+        .for_each(|_| Ok(()));
+    runtime.block_on(drain)?;
+
+    counter.log_summary(start.elapsed());
+    println!("{}", target);
+    println!("Replayed late by more than 10ms: {}", late);
+
+    Ok(())
+}
+
+fn record_response(
+    target: &Target,
+    counter: &HTTPRequestCounter,
+    response: Result<StatusCode, HyperError>,
+) {
+    match response {
+        Ok(StatusCode::ACCEPTED) => target.record_success(),
+        Ok(StatusCode::TOO_MANY_REQUESTS) => {
+            counter.increment_queue_full();
+            target.record_failure();
+        }
+        Ok(_) => target.record_failure(),
+        Err(err) => {
+            info!("{}", err);
+            target.record_failure();
+        }
+    }
+}
+
+fn to_micros(duration: time::Duration) -> u64 {
+    duration.as_secs() * 1_000_000 + u64::from(duration.subsec_micros())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CaptureSource, CaptureWriter};
+
+    use std::io::Cursor;
+    use std::time::Duration;
+
+    use protobuf::RepeatedField;
+
+    use sawtooth_sdk::messages::batch::{Batch, BatchList};
+
+    #[test]
+    fn test_capture_round_trip() {
+        let mut buf = Vec::new();
+        {
+            let mut writer = CaptureWriter::new(&mut buf);
+            writer.write_at(0, &batch_list(&["a", "b"])).unwrap();
+            writer.write_at(1_500, &batch_list(&["c"])).unwrap();
+        }
+
+        let mut reader = Cursor::new(buf);
+        let records: Vec<(Duration, String)> = CaptureSource::new(&mut reader)
+            .map(|record| {
+                let (offset, batch) = record.unwrap();
+                (offset, batch.header_signature)
+            })
+            .collect();
+
+        assert_eq!(
+            records,
+            vec![
+                (Duration::from_micros(0), "a".to_string()),
+                (Duration::from_micros(0), "b".to_string()),
+                (Duration::from_micros(1_500), "c".to_string()),
+            ]
+        );
+    }
+
+    fn batch_list(ids: &[&str]) -> BatchList {
+        let batches = ids
+            .iter()
+            .map(|id| {
+                let mut batch = Batch::new();
+                batch.set_header_signature(id.to_string());
+                batch
+            })
+            .collect();
+
+        let mut batch_list = BatchList::new();
+        batch_list.set_batches(RepeatedField::from_vec(batches));
+        batch_list
+    }
+}
@@ -0,0 +1,238 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+//! Tools for loading secp256k1 private keys from files
+
+use std::env;
+use std::error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use openssl::nid::Nid;
+use openssl::pkey::PKey;
+
+use sawtooth_sdk::signing::secp256k1::Secp256k1PrivateKey;
+
+/// The environment variable read by `passphrase_from_env`.
+pub const PASSPHRASE_ENV_VAR: &str = "SAWTOOTH_KEY_PASSPHRASE";
+
+const PRIVATE_KEY_LEN: usize = 32;
+
+/// Loads a secp256k1 private key from a file holding either the hex encoded
+/// key, or a PEM encoded key, which may be encrypted. The passphrase callback
+/// is only called for encrypted keys.
+pub fn load_private_key<P, F>(path: P, passphrase: F) -> Result<Secp256k1PrivateKey, KeyLoadError>
+where
+    P: AsRef<Path>,
+    F: FnOnce() -> Result<String, KeyLoadError>,
+{
+    let path = path.as_ref();
+    let contents = fs::read_to_string(path).map_err(|err| {
+        KeyLoadError::IoError(format!("Unable to read {}: {}", path.display(), err))
+    })?;
+
+    parse_private_key(&contents, passphrase).map_err(|err| match err {
+        KeyLoadError::InvalidKey(msg) => {
+            KeyLoadError::InvalidKey(format!("{}: {}", path.display(), msg))
+        }
+        err => err,
+    })
+}
+
+/// Parses a secp256k1 private key in any of the formats read by
+/// `load_private_key`.
+pub fn parse_private_key<F>(
+    contents: &str,
+    passphrase: F,
+) -> Result<Secp256k1PrivateKey, KeyLoadError>
+where
+    F: FnOnce() -> Result<String, KeyLoadError>,
+{
+    let contents = contents.trim();
+    if !contents.starts_with("-----BEGIN") {
+        return Secp256k1PrivateKey::from_hex(contents)
+            .map_err(|err| KeyLoadError::InvalidKey(err.to_string()));
+    }
+
+    let pkey = if contents.contains("ENCRYPTED") {
+        PKey::private_key_from_pem_passphrase(contents.as_bytes(), passphrase()?.as_bytes())
+    } else {
+        PKey::private_key_from_pem(contents.as_bytes())
+    }
+    .map_err(|err| KeyLoadError::InvalidKey(format!("Unable to decode PEM: {}", err)))?;
+
+    let ec_key = pkey
+        .ec_key()
+        .map_err(|_| KeyLoadError::InvalidKey("PEM does not hold an EC key".into()))?;
+    if ec_key.group().curve_name() != Some(Nid::SECP256K1) {
+        return Err(KeyLoadError::InvalidKey(
+            "PEM does not hold a secp256k1 key".into(),
+        ));
+    }
+
+    // The private key is a big number, which drops leading zero bytes
+    let bytes = ec_key.private_key().to_vec();
+    if bytes.len() > PRIVATE_KEY_LEN {
+        return Err(KeyLoadError::InvalidKey(
+            "PEM holds a private key that is too long".into(),
+        ));
+    }
+    let mut hex = "00".repeat(PRIVATE_KEY_LEN - bytes.len());
+    for byte in bytes {
+        hex.push_str(&format!("{:02x}", byte));
+    }
+
+    Secp256k1PrivateKey::from_hex(&hex).map_err(|err| KeyLoadError::InvalidKey(err.to_string()))
+}
+
+/// A passphrase callback that reads the passphrase from the
+/// SAWTOOTH_KEY_PASSPHRASE environment variable.
+pub fn passphrase_from_env() -> Result<String, KeyLoadError> {
+    env::var(PASSPHRASE_ENV_VAR).map_err(|_| {
+        KeyLoadError::PassphraseRequired(format!(
+            "The key is encrypted; set {} to its passphrase",
+            PASSPHRASE_ENV_VAR
+        ))
+    })
+}
+
+/// Returns the path of the user key with the given name, following the
+/// `~/.sawtooth/keys/<name>.priv` convention. If name is None, the name of
+/// the current user is used.
+pub fn resolve_user_key(name: Option<&str>) -> Result<PathBuf, KeyLoadError> {
+    let name = match name {
+        Some(name) => name.to_string(),
+        None => env::var("USER").map_err(|_| {
+            KeyLoadError::IoError("Unable to determine the user name; USER is not set".into())
+        })?,
+    };
+    let home = env::var("HOME").map_err(|_| {
+        KeyLoadError::IoError("Unable to find the home directory; HOME is not set".into())
+    })?;
+
+    Ok(Path::new(&home)
+        .join(".sawtooth")
+        .join("keys")
+        .join(name)
+        .with_extension("priv"))
+}
+
+/// Errors that may occur loading a private key.
+#[derive(Debug)]
+pub enum KeyLoadError {
+    IoError(String),
+    InvalidKey(String),
+    PassphraseRequired(String),
+}
+
+impl From<io::Error> for KeyLoadError {
+    fn from(err: io::Error) -> Self {
+        KeyLoadError::IoError(err.to_string())
+    }
+}
+
+impl fmt::Display for KeyLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            KeyLoadError::IoError(ref msg) => write!(f, "Unable to load key: {}", msg),
+            KeyLoadError::InvalidKey(ref msg) => write!(f, "Invalid private key {}", msg),
+            KeyLoadError::PassphraseRequired(ref msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl error::Error for KeyLoadError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::symm::Cipher;
+    use sawtooth_sdk::signing::PrivateKey;
+
+    fn no_passphrase() -> Result<String, KeyLoadError> {
+        panic!("The passphrase should not be requested")
+    }
+
+    fn generate_key() -> EcKey<openssl::pkey::Private> {
+        let group = EcGroup::from_curve_name(Nid::SECP256K1).unwrap();
+        EcKey::generate(&group).unwrap()
+    }
+
+    fn to_hex(ec_key: &EcKey<openssl::pkey::Private>) -> String {
+        let bytes = ec_key.private_key().to_vec();
+        let mut hex = "00".repeat(PRIVATE_KEY_LEN - bytes.len());
+        for byte in bytes {
+            hex.push_str(&format!("{:02x}", byte));
+        }
+        hex
+    }
+
+    #[test]
+    fn test_parse_hex() {
+        let hex = to_hex(&generate_key());
+        let key = parse_private_key(&format!("{}\n", hex), no_passphrase).unwrap();
+        assert_eq!(key.as_hex(), hex);
+    }
+
+    #[test]
+    fn test_parse_pem() {
+        let ec_key = generate_key();
+        let pem = String::from_utf8(ec_key.private_key_to_pem().unwrap()).unwrap();
+
+        let key = parse_private_key(&pem, no_passphrase).unwrap();
+        assert_eq!(key.as_hex(), to_hex(&ec_key));
+    }
+
+    #[test]
+    fn test_parse_encrypted_pem() {
+        let ec_key = generate_key();
+        let pem = String::from_utf8(
+            ec_key
+                .private_key_to_pem_passphrase(Cipher::aes_256_cbc(), b"secret")
+                .unwrap(),
+        )
+        .unwrap();
+
+        let key = parse_private_key(&pem, || Ok("secret".into())).unwrap();
+        assert_eq!(key.as_hex(), to_hex(&ec_key));
+
+        assert!(parse_private_key(&pem, || Ok("wrong".into())).is_err());
+        assert!(parse_private_key(&pem, || {
+            Err(KeyLoadError::PassphraseRequired("none".into()))
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_parse_other_curve() {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let ec_key = EcKey::generate(&group).unwrap();
+        let pem = String::from_utf8(ec_key.private_key_to_pem().unwrap()).unwrap();
+
+        assert!(parse_private_key(&pem, no_passphrase).is_err());
+    }
+
+    #[test]
+    fn test_resolve_user_key() {
+        let path = resolve_user_key(Some("alice")).unwrap();
+        assert!(path.ends_with(".sawtooth/keys/alice.priv"));
+    }
+}
@@ -0,0 +1,539 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+//! Tools for measuring batch submission, commit and state read latency, and
+//! commit throughput
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Write};
+use std::time;
+
+use chrono;
+
+const SUB_BUCKET_BITS: u32 = 5;
+const SUB_BUCKETS: u64 = 1 << SUB_BUCKET_BITS;
+const BUCKETS: usize = (SUB_BUCKETS * (64 - SUB_BUCKET_BITS as u64 + 1)) as usize;
+
+/// The most batches waiting to be committed whose submission times are kept;
+/// batches submitted while this many are waiting do not have their commit
+/// latency measured, nor are their commits counted.
+const MAX_PENDING_COMMITS: usize = 100_000;
+
+/// A histogram of latencies in microseconds, with fixed memory use.
+///
+/// Values are counted in buckets whose width is about 3% of the values they
+/// hold, so percentiles are accurate to within about 3%.
+pub struct Histogram {
+    counts: Vec<u64>,
+    count: u64,
+    max: u64,
+}
+
+impl Histogram {
+    pub fn new() -> Self {
+        Histogram {
+            counts: vec![0; BUCKETS],
+            count: 0,
+            max: 0,
+        }
+    }
+
+    pub fn record(&mut self, micros: u64) {
+        self.counts[bucket_index(micros)] += 1;
+        self.count += 1;
+        if micros > self.max {
+            self.max = micros;
+        }
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn max(&self) -> u64 {
+        self.max
+    }
+
+    /// Returns the value at the given percentile, from 0 to 100, or `None`
+    /// if no values have been recorded.
+    pub fn percentile(&self, percentile: f64) -> Option<u64> {
+        if self.count == 0 {
+            return None;
+        }
+
+        let rank = ((percentile / 100.0 * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (index, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return Some(bucket_upper_bound(index).min(self.max));
+            }
+        }
+
+        Some(self.max)
+    }
+
+    pub fn clear(&mut self) {
+        for count in self.counts.iter_mut() {
+            *count = 0;
+        }
+        self.count = 0;
+        self.max = 0;
+    }
+}
+
+impl fmt::Display for Histogram {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (
+            self.percentile(50.0),
+            self.percentile(95.0),
+            self.percentile(99.0),
+        ) {
+            (Some(p50), Some(p95), Some(p99)) => write!(
+                f,
+                "p50 {:.3}ms p95 {:.3}ms p99 {:.3}ms max {:.3}ms (n={})",
+                to_millis(p50),
+                to_millis(p95),
+                to_millis(p99),
+                to_millis(self.max),
+                self.count
+            ),
+            _ => write!(f, "no samples"),
+        }
+    }
+}
+
+fn bucket_index(micros: u64) -> usize {
+    if micros < SUB_BUCKETS {
+        return micros as usize;
+    }
+
+    let magnitude = 63 - micros.leading_zeros();
+    let shift = magnitude - SUB_BUCKET_BITS;
+    let sub_bucket = (micros >> shift) - SUB_BUCKETS;
+    (SUB_BUCKETS * (u64::from(shift) + 1) + sub_bucket) as usize
+}
+
+fn bucket_upper_bound(index: usize) -> u64 {
+    let index = index as u64;
+    if index < SUB_BUCKETS {
+        return index;
+    }
+
+    let shift = index / SUB_BUCKETS - 1;
+    let sub_bucket = index % SUB_BUCKETS;
+    // The upper bound of the last bucket wraps around to u64::max_value()
+    ((SUB_BUCKETS + sub_bucket + 1) << shift).wrapping_sub(1)
+}
+
+/// Counts of the batches seen to be committed or invalid, separate from the
+/// batches accepted by the REST Api.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CommitCounts {
+    pub committed: u64,
+    pub invalid: u64,
+    /// Batches submitted while too many were waiting to be committed, whose
+    /// statuses were not polled
+    pub untracked: u64,
+}
+
+impl CommitCounts {
+    /// Returns the rate of commits over `elapsed`.
+    pub fn commits_per_second(&self, elapsed: time::Duration) -> f64 {
+        let seconds = elapsed.as_secs() as f64 + f64::from(elapsed.subsec_nanos()) * 1e-9;
+        if seconds > 0.0 {
+            self.committed as f64 / seconds
+        } else {
+            0.0
+        }
+    }
+
+    fn clear(&mut self) {
+        *self = CommitCounts::default();
+    }
+}
+
+fn to_millis(micros: u64) -> f64 {
+    micros as f64 / 1000.0
+}
+
+fn to_micros(duration: time::Duration) -> u64 {
+    duration.as_secs() * 1_000_000 + u64::from(duration.subsec_micros())
+}
+
+/// Tracks the latency of batch submissions, from sending a batch to the REST
+/// Api until it is accepted, and optionally of batch commits, from sending a
+/// batch until its status is seen as committed. While tracking commits, the
+/// batches committed and invalid are also counted, to measure commit
+/// throughput apart from submit throughput. The latency of state reads is
+/// tracked once `track_reads` is called.
+pub struct LatencyTracker {
+    submit_interval: Histogram,
+    submit_total: Histogram,
+    commit_interval: Histogram,
+    commit_total: Histogram,
+    commits_interval: CommitCounts,
+    commits_total: CommitCounts,
+    // Read latencies, if reads are being tracked
+    reads: Option<(Histogram, Histogram)>,
+    // Batches waiting to be committed, by id, with the time they were sent,
+    // if commit latency is being tracked
+    pending_commits: Option<HashMap<String, time::Instant>>,
+    samples: Option<BufWriter<File>>,
+}
+
+impl LatencyTracker {
+    /// Creates a `LatencyTracker`, which tracks commit latency if
+    /// `track_commits` is set, and writes every sample to a CSV file at
+    /// `samples_path` if it is given.
+    pub fn new(track_commits: bool, samples_path: Option<&str>) -> Result<Self, io::Error> {
+        let samples = match samples_path {
+            Some(path) => {
+                let mut writer = BufWriter::new(File::create(path)?);
+                writeln!(writer, "timestamp_ms,kind,batch_id,latency_us")?;
+                Some(writer)
+            }
+            None => None,
+        };
+
+        Ok(LatencyTracker {
+            submit_interval: Histogram::new(),
+            submit_total: Histogram::new(),
+            commit_interval: Histogram::new(),
+            commit_total: Histogram::new(),
+            commits_interval: CommitCounts::default(),
+            commits_total: CommitCounts::default(),
+            reads: None,
+            pending_commits: if track_commits {
+                Some(HashMap::new())
+            } else {
+                None
+            },
+            samples,
+        })
+    }
+
+    pub fn is_tracking_commits(&self) -> bool {
+        self.pending_commits.is_some()
+    }
+
+    /// Starts tracking the latency of state reads.
+    pub fn track_reads(&mut self) {
+        if self.reads.is_none() {
+            self.reads = Some((Histogram::new(), Histogram::new()));
+        }
+    }
+
+    /// Records that a read of `address` sent at `sent` was answered, if reads
+    /// are being tracked.
+    pub fn record_read(&mut self, address: &str, sent: time::Instant) {
+        let latency = to_micros(sent.elapsed());
+        match self.reads {
+            Some((ref mut interval, ref mut total)) => {
+                interval.record(latency);
+                total.record(latency);
+            }
+            None => return,
+        }
+        self.write_sample("read", address, latency);
+    }
+
+    /// Records that a batch sent at `sent` was accepted by the REST Api.
+    pub fn record_submit(&mut self, batch_id: &str, sent: time::Instant) {
+        let latency = to_micros(sent.elapsed());
+        self.submit_interval.record(latency);
+        self.submit_total.record(latency);
+        self.write_sample("submit", batch_id, latency);
+
+        let tracked = match self.pending_commits {
+            Some(ref mut pending_commits) if pending_commits.len() < MAX_PENDING_COMMITS => {
+                pending_commits.insert(batch_id.to_string(), sent);
+                true
+            }
+            Some(_) => false,
+            None => return,
+        };
+        if !tracked {
+            self.commits_interval.untracked += 1;
+            self.commits_total.untracked += 1;
+        }
+    }
+
+    /// Records that a batch has been committed, if it is waiting to be.
+    pub fn record_commit(&mut self, batch_id: &str) {
+        let sent = match self.pending_commits {
+            Some(ref mut pending_commits) => pending_commits.remove(batch_id),
+            None => None,
+        };
+
+        if let Some(sent) = sent {
+            let latency = to_micros(sent.elapsed());
+            self.commit_interval.record(latency);
+            self.commit_total.record(latency);
+            self.commits_interval.committed += 1;
+            self.commits_total.committed += 1;
+            self.write_sample("commit", batch_id, latency);
+        }
+    }
+
+    /// Records that a batch was found invalid, if it is waiting to be
+    /// committed.
+    pub fn record_invalid(&mut self, batch_id: &str) {
+        let was_pending = match self.pending_commits {
+            Some(ref mut pending_commits) => pending_commits.remove(batch_id).is_some(),
+            None => false,
+        };
+
+        if was_pending {
+            self.commits_interval.invalid += 1;
+            self.commits_total.invalid += 1;
+        }
+    }
+
+    /// Stops waiting for a batch which will not be committed.
+    pub fn forget(&mut self, batch_id: &str) {
+        if let Some(ref mut pending_commits) = self.pending_commits {
+            pending_commits.remove(batch_id);
+        }
+    }
+
+    /// Returns the ids of up to `max` batches waiting to be committed.
+    pub fn pending_commit_ids(&self, max: usize) -> Vec<String> {
+        match self.pending_commits {
+            Some(ref pending_commits) => pending_commits.keys().take(max).cloned().collect(),
+            None => vec![],
+        }
+    }
+
+    /// Returns the number of batches waiting to be committed.
+    pub fn pending_commit_count(&self) -> usize {
+        self.pending_commits
+            .as_ref()
+            .map_or(0, |pending_commits| pending_commits.len())
+    }
+
+    /// Prints the latencies and commit counts of the last interval, which
+    /// lasted `elapsed`, and starts a new one.
+    pub fn log_interval(&mut self, elapsed: time::Duration) {
+        println!("Submit latency: {}", self.submit_interval);
+        self.submit_interval.clear();
+
+        if self.is_tracking_commits() {
+            println!("Commit latency: {}", self.commit_interval);
+            self.commit_interval.clear();
+            self.log_commits(self.commits_interval, elapsed);
+            self.commits_interval.clear();
+        }
+
+        if let Some((ref mut interval, _)) = self.reads {
+            println!("Read latency: {}", interval);
+            interval.clear();
+        }
+
+        self.flush_samples();
+    }
+
+    /// Returns the submit latencies since the tracker was created.
+    pub fn submit_summary(&self) -> &Histogram {
+        &self.submit_total
+    }
+
+    /// Returns the commit latencies since the tracker was created, if commit
+    /// latency is being tracked.
+    pub fn commit_summary(&self) -> Option<&Histogram> {
+        if self.is_tracking_commits() {
+            Some(&self.commit_total)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the read latencies since the tracker was created, if reads are
+    /// being tracked.
+    pub fn read_summary(&self) -> Option<&Histogram> {
+        self.reads.as_ref().map(|&(_, ref total)| total)
+    }
+
+    /// Returns the commit counts since the tracker was created, if commits
+    /// are being tracked.
+    pub fn commit_counts(&self) -> Option<CommitCounts> {
+        if self.is_tracking_commits() {
+            Some(self.commits_total)
+        } else {
+            None
+        }
+    }
+
+    /// Prints the latencies and commit counts since the tracker was created,
+    /// which was `elapsed` ago.
+    pub fn log_summary(&mut self, elapsed: time::Duration) {
+        println!("Summary submit latency: {}", self.submit_total);
+        if self.is_tracking_commits() {
+            println!("Summary commit latency: {}", self.commit_total);
+            print!("Summary ");
+            self.log_commits(self.commits_total, elapsed);
+        }
+        if let Some((_, ref total)) = self.reads {
+            println!("Summary read latency: {}", total);
+        }
+
+        self.flush_samples();
+    }
+
+    fn log_commits(&self, counts: CommitCounts, elapsed: time::Duration) {
+        println!(
+            "Committed: {}, Invalid {}, Pending {}, Untracked {}, Commits/s {:.3}",
+            counts.committed,
+            counts.invalid,
+            self.pending_commit_count(),
+            counts.untracked,
+            counts.commits_per_second(elapsed)
+        );
+    }
+
+    fn write_sample(&mut self, kind: &str, batch_id: &str, latency: u64) {
+        let result = match self.samples {
+            Some(ref mut writer) => writeln!(
+                writer,
+                "{},{},{},{}",
+                chrono::Utc::now().timestamp_millis(),
+                kind,
+                batch_id,
+                latency
+            ),
+            None => return,
+        };
+
+        if let Err(err) = result {
+            warn!(
+                "Unable to write latency sample, no longer writing samples: {}",
+                err
+            );
+            self.samples = None;
+        }
+    }
+
+    fn flush_samples(&mut self) {
+        let result = match self.samples {
+            Some(ref mut writer) => writer.flush(),
+            None => return,
+        };
+
+        if let Err(err) = result {
+            warn!(
+                "Unable to write latency samples, no longer writing samples: {}",
+                err
+            );
+            self.samples = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        bucket_index, bucket_upper_bound, CommitCounts, Histogram, LatencyTracker, BUCKETS,
+    };
+
+    use std::time;
+
+    #[test]
+    fn test_bucket_bounds() {
+        for value in (0..100_000).chain(vec![u64::max_value() - 1, u64::max_value()]) {
+            let index = bucket_index(value);
+            assert!(index < BUCKETS);
+            assert!(value <= bucket_upper_bound(index));
+            if index > 0 {
+                assert!(value > bucket_upper_bound(index - 1));
+            }
+        }
+    }
+
+    #[test]
+    fn test_percentiles() {
+        let mut histogram = Histogram::new();
+        assert_eq!(histogram.percentile(50.0), None);
+
+        for micros in 1..=1000 {
+            histogram.record(micros);
+        }
+
+        assert_eq!(histogram.count(), 1000);
+        assert_eq!(histogram.max(), 1000);
+
+        // Percentiles are accurate to the width of their bucket
+        for &(percentile, expected) in &[(50.0, 500.0), (95.0, 950.0), (99.0, 990.0)] {
+            let value = histogram.percentile(percentile).unwrap() as f64;
+            assert!(value >= expected && value <= expected * 1.04);
+        }
+        assert_eq!(histogram.percentile(100.0), Some(1000));
+
+        histogram.clear();
+        assert_eq!(histogram.count(), 0);
+        assert_eq!(histogram.percentile(50.0), None);
+    }
+
+    #[test]
+    fn test_commit_counts() {
+        let mut tracker = LatencyTracker::new(true, None).unwrap();
+        let sent = time::Instant::now();
+        for batch_id in &["a", "b", "c", "d"] {
+            tracker.record_submit(batch_id, sent);
+        }
+        assert_eq!(tracker.pending_commit_count(), 4);
+
+        tracker.record_commit("a");
+        tracker.record_commit("b");
+        tracker.record_invalid("c");
+        // Batches not waiting to be committed are not counted again
+        tracker.record_commit("a");
+        tracker.record_invalid("e");
+
+        let counts = tracker.commit_counts().unwrap();
+        assert_eq!(
+            counts,
+            CommitCounts {
+                committed: 2,
+                invalid: 1,
+                untracked: 0,
+            }
+        );
+        assert_eq!(tracker.pending_commit_count(), 1);
+        assert!((counts.commits_per_second(time::Duration::from_secs(2)) - 1.0).abs() < 1e-9);
+
+        assert_eq!(
+            LatencyTracker::new(false, None).unwrap().commit_counts(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_read_latency() {
+        let mut tracker = LatencyTracker::new(false, None).unwrap();
+        tracker.record_read("address", time::Instant::now());
+        assert!(tracker.read_summary().is_none());
+
+        tracker.track_reads();
+        tracker.record_read("address", time::Instant::now());
+        tracker.record_read("address", time::Instant::now());
+        assert_eq!(tracker.read_summary().map(Histogram::count), Some(2));
+        assert_eq!(tracker.submit_summary().count(), 0);
+    }
+}
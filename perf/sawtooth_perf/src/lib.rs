@@ -17,20 +17,36 @@
 
 extern crate sawtooth_sdk;
 
+extern crate base64;
 extern crate chrono;
 extern crate futures;
 extern crate hyper;
+extern crate hyper_tls;
+extern crate native_tls;
+extern crate openssl;
 
 #[macro_use]
 extern crate log;
 extern crate protobuf;
 extern crate rand;
+#[macro_use]
+extern crate serde_json;
 
-extern crate tokio_core;
-extern crate tokio_timer;
+extern crate tokio;
 
 pub mod batch_gen;
 mod batch_map;
 pub mod batch_submit;
+pub mod capture;
+pub mod key_loader;
+pub mod latency;
+pub mod manifest;
+pub mod rate;
+pub mod report;
+pub mod signer_pool;
 pub mod source;
+pub mod state;
+pub mod target;
+pub mod tls;
 mod workload;
+pub mod zmq_submit;
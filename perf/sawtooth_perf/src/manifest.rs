@@ -0,0 +1,308 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+//! Tools for recording what a workload run was started with, so that an
+//! identical workload can be generated again
+
+use std::error;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::io::{Read, Write};
+
+use openssl::sha::sha256;
+use sawtooth_sdk::signing;
+use serde_json;
+
+/// The seed, keys and arguments of a workload run.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RunManifest {
+    /// The name of the workload, such as intkey or smallbank
+    pub workload: String,
+    /// The version of the workload generator
+    pub version: String,
+    pub seed: u64,
+    /// The fingerprints of the keys the batches were signed with
+    pub key_fingerprints: Vec<String>,
+    /// Whether the key was generated for the run, rather than loaded
+    pub generated_key: bool,
+    /// The long names of the options the run was given, with their values;
+    /// flags have no value. They are written as a JSON object, so are read
+    /// back sorted by name.
+    pub arguments: Vec<(String, Option<String>)>,
+}
+
+impl RunManifest {
+    pub fn to_json(&self) -> serde_json::Value {
+        let arguments: serde_json::Map<String, serde_json::Value> = self
+            .arguments
+            .iter()
+            .map(|&(ref name, ref value)| match *value {
+                Some(ref value) => (name.clone(), json!(value)),
+                None => (name.clone(), json!(true)),
+            })
+            .collect();
+
+        json!({
+            "workload": self.workload,
+            "version": self.version,
+            "seed": self.seed,
+            "key_fingerprints": self.key_fingerprints,
+            "generated_key": self.generated_key,
+            "arguments": arguments,
+        })
+    }
+
+    pub fn from_json(value: &serde_json::Value) -> Result<Self, ManifestError> {
+        let string = |field: &str| {
+            value[field]
+                .as_str()
+                .map(String::from)
+                .ok_or_else(|| ManifestError::InvalidManifest(format!("{} is not a string", field)))
+        };
+
+        let seed = value["seed"]
+            .as_u64()
+            .ok_or_else(|| ManifestError::InvalidManifest("seed is not a number".into()))?;
+        let key_fingerprints = value["key_fingerprints"]
+            .as_array()
+            .ok_or_else(|| ManifestError::InvalidManifest("key_fingerprints is not a list".into()))?
+            .iter()
+            .map(|fingerprint| {
+                fingerprint.as_str().map(String::from).ok_or_else(|| {
+                    ManifestError::InvalidManifest("a key fingerprint is not a string".into())
+                })
+            })
+            .collect::<Result<Vec<String>, ManifestError>>()?;
+        let arguments = value["arguments"]
+            .as_object()
+            .ok_or_else(|| ManifestError::InvalidManifest("arguments is not a map".into()))?
+            .iter()
+            .map(|(name, value)| match *value {
+                serde_json::Value::String(ref value) => Ok((name.clone(), Some(value.clone()))),
+                serde_json::Value::Bool(true) => Ok((name.clone(), None)),
+                _ => Err(ManifestError::InvalidManifest(format!(
+                    "argument {} is neither a string nor true",
+                    name
+                ))),
+            })
+            .collect::<Result<Vec<(String, Option<String>)>, ManifestError>>()?;
+
+        Ok(RunManifest {
+            workload: string("workload")?,
+            version: string("version")?,
+            seed,
+            key_fingerprints,
+            generated_key: value["generated_key"].as_bool().unwrap_or(false),
+            arguments,
+        })
+    }
+
+    pub fn read(path: &str) -> Result<Self, ManifestError> {
+        let mut contents = String::new();
+        File::open(path)?.read_to_string(&mut contents)?;
+        let value: serde_json::Value = serde_json::from_str(&contents)
+            .map_err(|err| ManifestError::InvalidManifest(err.to_string()))?;
+        RunManifest::from_json(&value)
+    }
+
+    pub fn write(&self, path: &str) -> Result<(), ManifestError> {
+        let json = serde_json::to_string_pretty(&self.to_json())
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        File::create(path)?.write_all(json.as_bytes())?;
+        println!("Wrote run manifest to {}", path);
+        Ok(())
+    }
+
+    /// Returns the command line `argv`, without its --manifest option, with
+    /// the arguments of the manifest which `is_present` says it does not
+    /// already give, and the manifest's seed, appended to it.
+    pub fn replay_args<F>(&self, argv: Vec<String>, is_present: F) -> Vec<String>
+    where
+        F: Fn(&str) -> bool,
+    {
+        let mut skip_value = false;
+        let mut argv: Vec<String> = argv
+            .into_iter()
+            .filter(|arg| {
+                if skip_value {
+                    skip_value = false;
+                    return false;
+                }
+                skip_value = arg == "--manifest";
+                !skip_value && !arg.starts_with("--manifest=")
+            })
+            .collect();
+        for &(ref name, ref value) in &self.arguments {
+            if is_present(name) {
+                continue;
+            }
+            argv.push(format!("--{}", name));
+            if let Some(ref value) = *value {
+                argv.push(value.clone());
+            }
+        }
+        argv.push("--seed".into());
+        argv.push(self.seed.to_string());
+        argv
+    }
+
+    /// Checks that a replay of the run is the workload of the manifest and
+    /// signs with the same keys. A run whose key was generated can't be
+    /// signed identically, so any keys are accepted for it.
+    pub fn check_replay(
+        &self,
+        workload: &str,
+        key_fingerprints: &[String],
+    ) -> Result<(), ManifestError> {
+        if self.workload != workload {
+            return Err(ManifestError::ReplayMismatch(format!(
+                "the manifest is of a {} workload, not {}",
+                self.workload, workload
+            )));
+        }
+        if self.generated_key {
+            warn!("The manifest's run used a generated key, so its signatures will differ");
+        } else if self.key_fingerprints.as_slice() != key_fingerprints {
+            return Err(ManifestError::ReplayMismatch(format!(
+                "the keys {:?} are not the manifest's keys {:?}",
+                key_fingerprints, self.key_fingerprints
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Returns where to write the manifest of a run whose report is written to
+/// `report_path`: beside it, with `.manifest.json` in place of its extension.
+pub fn manifest_path(report_path: &str) -> String {
+    let stem = match report_path.rfind('.') {
+        Some(dot) if !report_path[dot..].contains('/') => &report_path[..dot],
+        _ => report_path,
+    };
+    format!("{}.manifest.json", stem)
+}
+
+/// Returns the SHA-256 digest of a public key, as hex.
+pub fn key_fingerprint(public_key: &dyn signing::PublicKey) -> String {
+    sha256(public_key.as_slice())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+#[derive(Debug)]
+pub enum ManifestError {
+    IoError(io::Error),
+    /// The manifest could not be understood
+    InvalidManifest(String),
+    /// The replayed run does not match the manifest
+    ReplayMismatch(String),
+}
+
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ManifestError::IoError(ref err) => write!(f, "An io error occurred: {}", err),
+            ManifestError::InvalidManifest(ref msg) => write!(f, "Invalid run manifest: {}", msg),
+            ManifestError::ReplayMismatch(ref msg) => {
+                write!(f, "Cannot replay the run manifest: {}", msg)
+            }
+        }
+    }
+}
+
+impl error::Error for ManifestError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            ManifestError::IoError(ref err) => Some(err),
+            ManifestError::InvalidManifest(_) => None,
+            ManifestError::ReplayMismatch(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for ManifestError {
+    fn from(err: io::Error) -> Self {
+        ManifestError::IoError(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest() -> RunManifest {
+        RunManifest {
+            workload: "intkey".into(),
+            version: "0.1.0".into(),
+            seed: 42,
+            key_fingerprints: vec!["ab".into()],
+            generated_key: false,
+            arguments: vec![
+                ("commit-latency".into(), None),
+                ("rate".into(), Some("20".into())),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_manifest_round_trips_through_json() {
+        let manifest = manifest();
+        assert_eq!(
+            manifest,
+            RunManifest::from_json(&manifest.to_json()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_replay_args() {
+        let argv = vec![
+            "intkey-workload".into(),
+            "--manifest".into(),
+            "run.manifest.json".into(),
+            "--rate".into(),
+            "5".into(),
+        ];
+        let replayed = manifest().replay_args(argv, |name| name == "rate");
+        assert_eq!(
+            replayed,
+            vec![
+                "intkey-workload",
+                "--rate",
+                "5",
+                "--commit-latency",
+                "--seed",
+                "42"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_replay() {
+        let manifest = manifest();
+        assert!(manifest.check_replay("intkey", &["ab".into()]).is_ok());
+        assert!(manifest.check_replay("smallbank", &["ab".into()]).is_err());
+        assert!(manifest.check_replay("intkey", &["cd".into()]).is_err());
+    }
+
+    #[test]
+    fn test_manifest_path() {
+        assert_eq!(manifest_path("run.json"), "run.manifest.json");
+        assert_eq!(manifest_path("out.d/run"), "out.d/run.manifest.json");
+    }
+}
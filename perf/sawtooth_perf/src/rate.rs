@@ -0,0 +1,206 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+//! Tools for controlling the rate and length of a workload
+
+use std::time;
+
+/// The rate, in batches per second, at which a workload sends batches over
+/// time.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RateSchedule {
+    /// Send batches at a fixed rate.
+    Constant(f64),
+    /// Change the rate linearly from `start` to `end` over `duration`, then
+    /// keep sending at `end`.
+    Ramp {
+        start: f64,
+        end: f64,
+        duration: time::Duration,
+    },
+}
+
+impl RateSchedule {
+    /// Parses a ramp given as `START,END,SECONDS`, where `START` and `END`
+    /// are rates in batches per second.
+    pub fn parse_ramp(value: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = value.split(',').map(str::trim).collect();
+        if parts.len() != 3 {
+            return Err(format!(
+                "A ramp is START,END,SECONDS, but {} was given",
+                value
+            ));
+        }
+
+        let start: f64 = parts[0]
+            .parse()
+            .map_err(|_| format!("Invalid ramp start rate: {}", parts[0]))?;
+        let end: f64 = parts[1]
+            .parse()
+            .map_err(|_| format!("Invalid ramp end rate: {}", parts[1]))?;
+        let seconds: u64 = parts[2]
+            .parse()
+            .map_err(|_| format!("Invalid ramp duration: {}", parts[2]))?;
+
+        if start < 0.0 || end < 0.0 || (start == 0.0 && end == 0.0) {
+            return Err("Ramp rates must not be negative, and one must be above 0".into());
+        }
+        if seconds == 0 {
+            return Err("A ramp must last at least 1 second".into());
+        }
+
+        Ok(RateSchedule::Ramp {
+            start,
+            end,
+            duration: time::Duration::from_secs(seconds),
+        })
+    }
+
+    /// Returns the rate at `elapsed` into the workload.
+    pub fn rate_at(&self, elapsed: time::Duration) -> f64 {
+        match *self {
+            RateSchedule::Constant(rate) => rate,
+            RateSchedule::Ramp {
+                start,
+                end,
+                duration,
+            } => {
+                let ramp_secs = to_secs(duration);
+                let secs = to_secs(elapsed);
+                if secs >= ramp_secs {
+                    end
+                } else {
+                    start + (end - start) * secs / ramp_secs
+                }
+            }
+        }
+    }
+
+    /// Returns the number of batches which should have been sent by
+    /// `elapsed` into the workload.
+    pub fn batches_due(&self, elapsed: time::Duration) -> u64 {
+        let secs = to_secs(elapsed);
+        let due = match *self {
+            RateSchedule::Constant(rate) => rate * secs,
+            RateSchedule::Ramp {
+                start,
+                end,
+                duration,
+            } => {
+                // The area under the rate over time
+                let ramp_secs = to_secs(duration);
+                let ramping_secs = secs.min(ramp_secs);
+                let ramped = (start + self.rate_at(elapsed)) / 2.0 * ramping_secs;
+                ramped + end * (secs - ramping_secs)
+            }
+        };
+
+        due.max(0.0) as u64
+    }
+}
+
+/// Limits on how long a workload runs; a workload without limits runs until
+/// it runs out of batches or is killed.
+#[derive(Clone, Debug, Default)]
+pub struct WorkloadLimits {
+    /// How long to send batches for.
+    pub duration: Option<time::Duration>,
+    /// The most batches to send, including resubmissions of batches which
+    /// were not accepted.
+    pub max_batches: Option<u64>,
+}
+
+impl WorkloadLimits {
+    /// Returns whether a workload which has sent `sent` batches over
+    /// `elapsed` has reached its limits.
+    pub fn is_reached(&self, elapsed: time::Duration, sent: u64) -> bool {
+        self.duration.map_or(false, |duration| elapsed >= duration)
+            || self
+                .max_batches
+                .map_or(false, |max_batches| sent >= max_batches)
+    }
+}
+
+fn to_secs(duration: time::Duration) -> f64 {
+    duration.as_secs() as f64 + f64::from(duration.subsec_nanos()) * 1e-9
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RateSchedule, WorkloadLimits};
+
+    use std::time::Duration;
+
+    #[test]
+    fn test_constant_rate() {
+        let schedule = RateSchedule::Constant(10.0);
+
+        assert_eq!(schedule.rate_at(Duration::from_secs(5)), 10.0);
+        assert_eq!(schedule.batches_due(Duration::from_millis(0)), 0);
+        assert_eq!(schedule.batches_due(Duration::from_millis(250)), 2);
+        assert_eq!(schedule.batches_due(Duration::from_secs(3)), 30);
+    }
+
+    #[test]
+    fn test_ramp_rate() {
+        let schedule = RateSchedule::Ramp {
+            start: 10.0,
+            end: 110.0,
+            duration: Duration::from_secs(10),
+        };
+
+        assert_eq!(schedule.rate_at(Duration::from_secs(0)), 10.0);
+        assert_eq!(schedule.rate_at(Duration::from_secs(5)), 60.0);
+        assert_eq!(schedule.rate_at(Duration::from_secs(20)), 110.0);
+
+        // Half way through the ramp, (10 + 60) / 2 * 5
+        assert_eq!(schedule.batches_due(Duration::from_secs(5)), 175);
+        // The whole ramp, (10 + 110) / 2 * 10, and then 2 seconds at 110
+        assert_eq!(schedule.batches_due(Duration::from_secs(10)), 600);
+        assert_eq!(schedule.batches_due(Duration::from_secs(12)), 820);
+    }
+
+    #[test]
+    fn test_parse_ramp() {
+        assert_eq!(
+            RateSchedule::parse_ramp("10, 100,60"),
+            Ok(RateSchedule::Ramp {
+                start: 10.0,
+                end: 100.0,
+                duration: Duration::from_secs(60),
+            })
+        );
+
+        assert!(RateSchedule::parse_ramp("10,100").is_err());
+        assert!(RateSchedule::parse_ramp("10,abc,60").is_err());
+        assert!(RateSchedule::parse_ramp("0,0,60").is_err());
+        assert!(RateSchedule::parse_ramp("10,100,0").is_err());
+    }
+
+    #[test]
+    fn test_limits() {
+        assert!(!WorkloadLimits::default().is_reached(Duration::from_secs(1_000), 1_000));
+
+        let limits = WorkloadLimits {
+            duration: Some(Duration::from_secs(60)),
+            max_batches: Some(100),
+        };
+        assert!(!limits.is_reached(Duration::from_secs(59), 99));
+        assert!(limits.is_reached(Duration::from_secs(60), 99));
+        assert!(limits.is_reached(Duration::from_secs(59), 100));
+    }
+}
@@ -0,0 +1,528 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+//! Tools for writing a report of a workload run, for archiving benchmark
+//! results
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fmt::Write as FmtWrite;
+use std::fs::File;
+use std::io;
+use std::io::Write;
+use std::time;
+
+use serde_json;
+
+use latency::{CommitCounts, Histogram, LatencyTracker};
+use workload::HTTPRequestCounter;
+
+/// Where to write the report of a workload once it ends.
+pub struct ReportOptions {
+    /// The file to write the report to, as JSON
+    pub json_path: String,
+    /// A file to also write the report to, as a self-contained HTML page
+    pub html_path: Option<String>,
+    /// The workload's configuration, as option names and values
+    pub configuration: Vec<(String, String)>,
+}
+
+/// The counts of one logging interval of a workload.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IntervalCounts {
+    /// When the interval ended, in RFC 3339 format
+    pub timestamp: String,
+    pub seconds: f64,
+    pub sent: usize,
+    pub queue_full: usize,
+    pub throttled: usize,
+}
+
+impl IntervalCounts {
+    pub fn batches_per_second(&self) -> f64 {
+        if self.seconds > 0.0 {
+            self.sent as f64 / self.seconds
+        } else {
+            0.0
+        }
+    }
+}
+
+/// The latency percentiles of a histogram, in milliseconds.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LatencySummary {
+    pub count: u64,
+    pub p50: Option<f64>,
+    pub p95: Option<f64>,
+    pub p99: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl LatencySummary {
+    pub fn from_histogram(histogram: &Histogram) -> Self {
+        let to_millis = |micros: u64| micros as f64 / 1000.0;
+        LatencySummary {
+            count: histogram.count(),
+            p50: histogram.percentile(50.0).map(to_millis),
+            p95: histogram.percentile(95.0).map(to_millis),
+            p99: histogram.percentile(99.0).map(to_millis),
+            max: histogram.percentile(100.0).map(to_millis),
+        }
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        json!({
+            "count": self.count,
+            "p50_ms": self.p50,
+            "p95_ms": self.p95,
+            "p99_ms": self.p99,
+            "max_ms": self.max,
+        })
+    }
+}
+
+/// The batches seen to be committed or invalid, while commits are tracked.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CommitSummary {
+    pub counts: CommitCounts,
+    /// Batches still waiting to be committed when the workload ended
+    pub pending: usize,
+    pub commits_per_second: f64,
+}
+
+impl CommitSummary {
+    fn to_json(&self) -> serde_json::Value {
+        json!({
+            "committed": self.counts.committed,
+            "invalid": self.counts.invalid,
+            "pending": self.pending,
+            "untracked": self.counts.untracked,
+            "commits_per_second": self.commits_per_second,
+        })
+    }
+}
+
+/// A report of a workload run: its configuration, its throughput over each
+/// logging interval, the requests which failed by status, its latency
+/// percentiles and, if commits are tracked, its commit throughput.
+#[derive(Debug, PartialEq)]
+pub struct WorkloadReport {
+    pub configuration: Vec<(String, String)>,
+    pub seconds: f64,
+    pub sent: usize,
+    pub queue_full: usize,
+    pub throttled: usize,
+    pub intervals: Vec<IntervalCounts>,
+    pub errors: BTreeMap<String, usize>,
+    pub submit_latency: LatencySummary,
+    pub commit_latency: Option<LatencySummary>,
+    pub read_latency: Option<LatencySummary>,
+    pub commits: Option<CommitSummary>,
+}
+
+impl WorkloadReport {
+    /// Creates the report of a workload which ran for `elapsed`.
+    pub fn new(
+        configuration: Vec<(String, String)>,
+        counter: &HTTPRequestCounter,
+        latency: &LatencyTracker,
+        elapsed: time::Duration,
+    ) -> Self {
+        WorkloadReport {
+            configuration,
+            seconds: elapsed.as_secs() as f64 + f64::from(elapsed.subsec_nanos()) * 1e-9,
+            sent: counter.total_sent(),
+            queue_full: counter.total_queue_full(),
+            throttled: counter.total_throttled(),
+            intervals: counter.intervals(),
+            errors: counter.errors(),
+            submit_latency: LatencySummary::from_histogram(latency.submit_summary()),
+            commit_latency: latency.commit_summary().map(LatencySummary::from_histogram),
+            read_latency: latency.read_summary().map(LatencySummary::from_histogram),
+            commits: latency.commit_counts().map(|counts| CommitSummary {
+                counts,
+                pending: latency.pending_commit_count(),
+                commits_per_second: counts.commits_per_second(elapsed),
+            }),
+        }
+    }
+
+    pub fn batches_per_second(&self) -> f64 {
+        if self.seconds > 0.0 {
+            self.sent as f64 / self.seconds
+        } else {
+            0.0
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let configuration: serde_json::Map<String, serde_json::Value> = self
+            .configuration
+            .iter()
+            .map(|&(ref name, ref value)| (name.clone(), json!(value)))
+            .collect();
+        let intervals: Vec<serde_json::Value> = self
+            .intervals
+            .iter()
+            .map(|interval| {
+                json!({
+                    "timestamp": interval.timestamp,
+                    "seconds": interval.seconds,
+                    "sent": interval.sent,
+                    "queue_full": interval.queue_full,
+                    "throttled": interval.throttled,
+                    "batches_per_second": interval.batches_per_second(),
+                })
+            })
+            .collect();
+
+        json!({
+            "configuration": configuration,
+            "summary": {
+                "seconds": self.seconds,
+                "sent": self.sent,
+                "queue_full": self.queue_full,
+                "throttled": self.throttled,
+                "batches_per_second": self.batches_per_second(),
+            },
+            "intervals": intervals,
+            "errors": self.errors,
+            "latency": {
+                "submit": self.submit_latency.to_json(),
+                "commit": self.commit_latency.as_ref().map(LatencySummary::to_json),
+                "read": self.read_latency.as_ref().map(LatencySummary::to_json),
+            },
+            "commits": self.commits.as_ref().map(CommitSummary::to_json),
+        })
+    }
+
+    /// Returns the report as an HTML page which needs no other files.
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        // Writing to a String cannot fail
+        let _ = self.write_html(&mut html);
+        html
+    }
+
+    fn write_html(&self, html: &mut String) -> Result<(), fmt::Error> {
+        html.push_str(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>Workload report</title>\n<style>\n\
+             body { font-family: sans-serif; margin: 2em; }\n\
+             table { border-collapse: collapse; margin-bottom: 1em; }\n\
+             th, td { border: 1px solid #ccc; padding: 0.2em 0.6em; text-align: left; }\n\
+             </style>\n</head>\n<body>\n<h1>Workload report</h1>\n",
+        );
+
+        html.push_str("<h2>Summary</h2>\n<table>\n");
+        writeln!(
+            html,
+            "<tr><th>Seconds</th><td>{:.3}</td></tr>",
+            self.seconds
+        )?;
+        writeln!(html, "<tr><th>Sent</th><td>{}</td></tr>", self.sent)?;
+        writeln!(
+            html,
+            "<tr><th>Queue full</th><td>{}</td></tr>",
+            self.queue_full
+        )?;
+        writeln!(
+            html,
+            "<tr><th>Throttled</th><td>{}</td></tr>",
+            self.throttled
+        )?;
+        writeln!(
+            html,
+            "<tr><th>Batches/s</th><td>{:.3}</td></tr>",
+            self.batches_per_second()
+        )?;
+        html.push_str("</table>\n");
+
+        if let Some(ref commits) = self.commits {
+            html.push_str("<h2>Commits</h2>\n<table>\n");
+            for &(name, value) in &[
+                ("Committed", commits.counts.committed),
+                ("Invalid", commits.counts.invalid),
+                ("Pending", commits.pending as u64),
+                ("Untracked", commits.counts.untracked),
+            ] {
+                writeln!(html, "<tr><th>{}</th><td>{}</td></tr>", name, value)?;
+            }
+            writeln!(
+                html,
+                "<tr><th>Commits/s</th><td>{:.3}</td></tr>",
+                commits.commits_per_second
+            )?;
+            html.push_str("</table>\n");
+        }
+
+        html.push_str("<h2>Configuration</h2>\n<table>\n");
+        for &(ref name, ref value) in &self.configuration {
+            writeln!(
+                html,
+                "<tr><th>{}</th><td>{}</td></tr>",
+                escape_html(name),
+                escape_html(value)
+            )?;
+        }
+        html.push_str("</table>\n");
+
+        html.push_str(
+            "<h2>Latency</h2>\n<table>\n<tr><th></th><th>Count</th><th>p50 ms</th>\
+             <th>p95 ms</th><th>p99 ms</th><th>Max ms</th></tr>\n",
+        );
+        write_latency_row(html, "Submit", &self.submit_latency)?;
+        if let Some(ref commit_latency) = self.commit_latency {
+            write_latency_row(html, "Commit", commit_latency)?;
+        }
+        if let Some(ref read_latency) = self.read_latency {
+            write_latency_row(html, "Read", read_latency)?;
+        }
+        html.push_str("</table>\n");
+
+        html.push_str("<h2>Errors</h2>\n");
+        if self.errors.is_empty() {
+            html.push_str("<p>None</p>\n");
+        } else {
+            html.push_str("<table>\n<tr><th>Status</th><th>Count</th></tr>\n");
+            for (status, count) in &self.errors {
+                writeln!(
+                    html,
+                    "<tr><td>{}</td><td>{}</td></tr>",
+                    escape_html(status),
+                    count
+                )?;
+            }
+            html.push_str("</table>\n");
+        }
+
+        html.push_str("<h2>Throughput</h2>\n");
+        html.push_str(&throughput_chart(&self.intervals));
+        html.push_str(
+            "<table>\n<tr><th>Time</th><th>Sent</th><th>Queue full</th>\
+             <th>Throttled</th><th>Batches/s</th></tr>\n",
+        );
+        for interval in &self.intervals {
+            writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{:.3}</td></tr>",
+                escape_html(&interval.timestamp),
+                interval.sent,
+                interval.queue_full,
+                interval.throttled,
+                interval.batches_per_second()
+            )?;
+        }
+        html.push_str("</table>\n</body>\n</html>\n");
+
+        Ok(())
+    }
+
+    /// Writes the report to the files given by `options`.
+    pub fn write(&self, options: &ReportOptions) -> Result<(), io::Error> {
+        let json = serde_json::to_string_pretty(&self.to_json())
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        File::create(&options.json_path)?.write_all(json.as_bytes())?;
+
+        if let Some(ref html_path) = options.html_path {
+            File::create(html_path)?.write_all(self.to_html().as_bytes())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes the report of a workload which ran for `elapsed` to the files
+/// given by `options`.
+pub fn write_report(
+    options: &ReportOptions,
+    counter: &HTTPRequestCounter,
+    latency: &LatencyTracker,
+    elapsed: time::Duration,
+) -> Result<(), io::Error> {
+    let report = WorkloadReport::new(options.configuration.clone(), counter, latency, elapsed);
+    report.write(options)?;
+    println!("Wrote report to {}", options.json_path);
+    Ok(())
+}
+
+fn write_latency_row(
+    html: &mut String,
+    name: &str,
+    latency: &LatencySummary,
+) -> Result<(), fmt::Error> {
+    let format_millis = |millis: Option<f64>| match millis {
+        Some(millis) => format!("{:.3}", millis),
+        None => "-".into(),
+    };
+    writeln!(
+        html,
+        "<tr><th>{}</th><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+        name,
+        latency.count,
+        format_millis(latency.p50),
+        format_millis(latency.p95),
+        format_millis(latency.p99),
+        format_millis(latency.max)
+    )
+}
+
+const CHART_WIDTH: f64 = 600.0;
+const CHART_HEIGHT: f64 = 150.0;
+
+/// Returns an SVG line chart of the batches per second of each interval.
+fn throughput_chart(intervals: &[IntervalCounts]) -> String {
+    if intervals.len() < 2 {
+        return String::new();
+    }
+
+    let max_rate = intervals
+        .iter()
+        .map(IntervalCounts::batches_per_second)
+        .fold(0.0, f64::max);
+    let step = CHART_WIDTH / (intervals.len() - 1) as f64;
+    let points: Vec<String> = intervals
+        .iter()
+        .enumerate()
+        .map(|(index, interval)| {
+            let y = if max_rate > 0.0 {
+                CHART_HEIGHT - interval.batches_per_second() / max_rate * CHART_HEIGHT
+            } else {
+                CHART_HEIGHT
+            };
+            format!("{:.1},{:.1}", index as f64 * step, y)
+        })
+        .collect();
+
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" \
+         viewBox=\"0 0 {w} {h}\">\n\
+         <rect width=\"{w}\" height=\"{h}\" fill=\"none\" stroke=\"#ccc\"/>\n\
+         <polyline points=\"{points}\" fill=\"none\" stroke=\"#36c\" stroke-width=\"2\"/>\n\
+         <text x=\"4\" y=\"14\" font-size=\"12\">{max:.3} batches/s</text>\n\
+         </svg>\n",
+        w = CHART_WIDTH,
+        h = CHART_HEIGHT,
+        points = points.join(" "),
+        max = max_rate
+    )
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> WorkloadReport {
+        let mut errors = BTreeMap::new();
+        errors.insert("429".to_string(), 3);
+
+        WorkloadReport {
+            configuration: vec![
+                ("rate".into(), "10".into()),
+                ("urls".into(), "<a&b>".into()),
+            ],
+            seconds: 60.0,
+            sent: 600,
+            queue_full: 3,
+            throttled: 0,
+            intervals: vec![
+                IntervalCounts {
+                    timestamp: "2018-01-01T00:00:30Z".into(),
+                    seconds: 30.0,
+                    sent: 270,
+                    queue_full: 3,
+                    throttled: 0,
+                },
+                IntervalCounts {
+                    timestamp: "2018-01-01T00:01:00Z".into(),
+                    seconds: 30.0,
+                    sent: 330,
+                    queue_full: 0,
+                    throttled: 0,
+                },
+            ],
+            errors,
+            submit_latency: LatencySummary {
+                count: 597,
+                p50: Some(1.5),
+                p95: Some(4.0),
+                p99: Some(9.0),
+                max: Some(12.0),
+            },
+            commit_latency: None,
+            read_latency: Some(LatencySummary {
+                count: 1800,
+                p50: Some(0.5),
+                p95: Some(1.0),
+                p99: Some(2.0),
+                max: Some(3.0),
+            }),
+            commits: Some(CommitSummary {
+                counts: CommitCounts {
+                    committed: 540,
+                    invalid: 6,
+                    untracked: 0,
+                },
+                pending: 51,
+                commits_per_second: 9.0,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_json_report() {
+        let json = report().to_json();
+
+        assert_eq!(json["configuration"]["rate"], "10");
+        assert_eq!(json["summary"]["sent"], 600);
+        assert_eq!(json["summary"]["batches_per_second"], 10.0);
+        assert_eq!(json["intervals"][1]["batches_per_second"], 11.0);
+        assert_eq!(json["errors"]["429"], 3);
+        assert_eq!(json["latency"]["submit"]["p99_ms"], 9.0);
+        assert!(json["latency"]["commit"].is_null());
+        assert_eq!(json["latency"]["read"]["count"], 1800);
+        assert_eq!(json["commits"]["committed"], 540);
+        assert_eq!(json["commits"]["invalid"], 6);
+        assert_eq!(json["commits"]["pending"], 51);
+    }
+
+    #[test]
+    fn test_html_report() {
+        let html = report().to_html();
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<polyline"));
+        assert!(html.contains("<tr><th>Invalid</th><td>6</td></tr>"));
+        assert!(html.contains("<td>&lt;a&amp;b&gt;</td>"));
+        assert!(!html.contains("<a&b>"));
+    }
+}
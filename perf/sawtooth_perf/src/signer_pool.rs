@@ -0,0 +1,265 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+//! Tools for signing batches with a pool of keys
+
+use std::cell::{Cell, RefCell};
+use std::error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use sawtooth_sdk::signing;
+use sawtooth_sdk::signing::secp256k1::Secp256k1PrivateKey;
+
+use key_loader::{load_private_key, passphrase_from_env, KeyLoadError};
+
+/// How the signer of each batch is chosen from a pool.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SignerSelection {
+    /// Each signer in turn.
+    RoundRobin,
+    /// A random signer, chosen in proportion to its weight. There is a weight
+    /// for each signer, in the same order.
+    Weighted(Vec<u32>),
+}
+
+impl SignerSelection {
+    /// Parses a comma separated list of weights, one for each signer, as
+    /// weighted selection.
+    pub fn parse_weights(value: &str) -> Result<Self, SignerPoolError> {
+        value
+            .split(',')
+            .map(|weight| {
+                weight.trim().parse().map_err(|_| {
+                    SignerPoolError::InvalidWeights(format!("{} is not a weight", weight))
+                })
+            })
+            .collect::<Result<Vec<u32>, _>>()
+            .map(SignerSelection::Weighted)
+    }
+}
+
+/// A pool of signers, one of which is current at a time.
+///
+/// A batch and the transactions in it must be signed by the current signer,
+/// so that the batch's signer matches the transactions' batcher; the batch
+/// producer moves to the next signer after each batch.
+pub struct SignerPool<'a> {
+    signers: Vec<signing::Signer<'a>>,
+    current: Cell<usize>,
+    // Chooses the next signer, if signers are weighted
+    weights: Option<RefCell<(WeightedIndex<u32>, StdRng)>>,
+}
+
+impl<'a> SignerPool<'a> {
+    /// Creates a pool of `signers`, chosen by `selection`. Weighted selection
+    /// is reproducible for a given `seed`.
+    pub fn new(
+        signers: Vec<signing::Signer<'a>>,
+        selection: SignerSelection,
+        seed: u64,
+    ) -> Result<Self, SignerPoolError> {
+        if signers.is_empty() {
+            return Err(SignerPoolError::NoSigners);
+        }
+
+        let weights = match selection {
+            SignerSelection::RoundRobin => None,
+            SignerSelection::Weighted(weights) => {
+                if weights.len() != signers.len() {
+                    return Err(SignerPoolError::InvalidWeights(format!(
+                        "{} weights were given for {} signers",
+                        weights.len(),
+                        signers.len()
+                    )));
+                }
+                let index = WeightedIndex::new(&weights)
+                    .map_err(|err| SignerPoolError::InvalidWeights(format!("{:?}", err)))?;
+                Some(RefCell::new((index, StdRng::seed_from_u64(seed))))
+            }
+        };
+
+        Ok(SignerPool {
+            signers,
+            current: Cell::new(0),
+            weights,
+        })
+    }
+
+    /// Creates a pool of a single signer.
+    pub fn single(signer: signing::Signer<'a>) -> Self {
+        SignerPool {
+            signers: vec![signer],
+            current: Cell::new(0),
+            weights: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.signers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.signers.is_empty()
+    }
+
+    /// Returns the current signer.
+    pub fn current(&self) -> &signing::Signer<'a> {
+        &self.signers[self.current.get()]
+    }
+
+    /// Makes the next signer, as chosen by the pool's selection, current.
+    pub fn advance(&self) {
+        let next = match self.weights {
+            Some(ref weights) => {
+                let mut weights = weights.borrow_mut();
+                let (ref index, ref mut rng) = *weights;
+                index.sample(rng)
+            }
+            None => (self.current.get() + 1) % self.signers.len(),
+        };
+        self.current.set(next);
+    }
+}
+
+/// Reads every secp256k1 private key in a `.priv` file in `dir`, in the order
+/// of their file names. Encrypted keys are decrypted with the passphrase in
+/// SAWTOOTH_KEY_PASSPHRASE.
+pub fn read_private_keys(dir: &Path) -> Result<Vec<Secp256k1PrivateKey>, SignerPoolError> {
+    let mut paths = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, io::Error>>()?;
+    paths.retain(|path| path.extension().map_or(false, |ext| ext == "priv"));
+    paths.sort();
+
+    paths
+        .iter()
+        .map(|path| load_private_key(path, passphrase_from_env).map_err(SignerPoolError::from))
+        .collect()
+}
+
+/// Errors that may occur creating a pool of signers.
+#[derive(Debug)]
+pub enum SignerPoolError {
+    IoError(io::Error),
+    KeyError(KeyLoadError),
+    InvalidWeights(String),
+    NoSigners,
+}
+
+impl From<io::Error> for SignerPoolError {
+    fn from(err: io::Error) -> Self {
+        SignerPoolError::IoError(err)
+    }
+}
+
+impl From<KeyLoadError> for SignerPoolError {
+    fn from(err: KeyLoadError) -> Self {
+        SignerPoolError::KeyError(err)
+    }
+}
+
+impl fmt::Display for SignerPoolError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SignerPoolError::IoError(ref err) => write!(f, "Unable to read keys: {}", err),
+            SignerPoolError::KeyError(ref err) => write!(f, "{}", err),
+            SignerPoolError::InvalidWeights(ref msg) => {
+                write!(f, "Invalid signer weights: {}", msg)
+            }
+            SignerPoolError::NoSigners => write!(f, "A signer pool needs at least one signer"),
+        }
+    }
+}
+
+impl error::Error for SignerPoolError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            SignerPoolError::IoError(ref err) => Some(err),
+            SignerPoolError::KeyError(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SignerPool, SignerSelection};
+
+    use sawtooth_sdk::signing;
+
+    #[test]
+    fn test_round_robin() {
+        let context = signing::create_context("secp256k1").unwrap();
+        let keys: Vec<_> = (0..3)
+            .map(|_| context.new_random_private_key().unwrap())
+            .collect();
+        let public_keys: Vec<String> = keys
+            .iter()
+            .map(|key| context.get_public_key(key.as_ref()).unwrap().as_hex())
+            .collect();
+        let signers = keys
+            .iter()
+            .map(|key| signing::Signer::new(context.as_ref(), key.as_ref()))
+            .collect();
+
+        let pool = SignerPool::new(signers, SignerSelection::RoundRobin, 0).unwrap();
+        for expected in public_keys.iter().cycle().take(7) {
+            assert_eq!(&pool.current().get_public_key().unwrap().as_hex(), expected);
+            pool.advance();
+        }
+    }
+
+    #[test]
+    fn test_parse_weights() {
+        assert_eq!(
+            SignerSelection::parse_weights("1, 2,3").unwrap(),
+            SignerSelection::Weighted(vec![1, 2, 3])
+        );
+        assert!(SignerSelection::parse_weights("1,x").is_err());
+    }
+
+    #[test]
+    fn test_weighted() {
+        let context = signing::create_context("secp256k1").unwrap();
+        let keys: Vec<_> = (0..2)
+            .map(|_| context.new_random_private_key().unwrap())
+            .collect();
+        let heavy_key = context.get_public_key(keys[1].as_ref()).unwrap().as_hex();
+        let signers = || {
+            keys.iter()
+                .map(|key| signing::Signer::new(context.as_ref(), key.as_ref()))
+                .collect::<Vec<_>>()
+        };
+
+        assert!(SignerPool::new(signers(), SignerSelection::Weighted(vec![1]), 0).is_err());
+        assert!(SignerPool::new(signers(), SignerSelection::Weighted(vec![0, 0]), 0).is_err());
+
+        // A signer with no weight is never chosen
+        let pool = SignerPool::new(signers(), SignerSelection::Weighted(vec![0, 1]), 7).unwrap();
+        for _ in 0..20 {
+            pool.advance();
+            assert_eq!(pool.current().get_public_key().unwrap().as_hex(), heavy_key);
+        }
+    }
+}
@@ -0,0 +1,168 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+//! Tools for reading state from the Sawtooth REST Api, e.g. to check the
+//! results of a workload
+
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::io;
+
+use base64;
+use futures::{Future, Stream};
+use hyper::client::Client;
+use hyper::http;
+use hyper::Error as HyperError;
+use hyper::{Body, Method, StatusCode};
+use serde_json;
+use tokio::runtime::current_thread::Runtime;
+
+use tls::{https_connector, TlsError, TlsOptions};
+use workload::request_builder;
+
+#[derive(Debug)]
+pub enum StateReadError {
+    HttpError(HyperError),
+    RequestError(http::Error),
+    IoError(io::Error),
+    TlsError(TlsError),
+    /// The REST Api answered with an unexpected status
+    UnexpectedStatus(String, StatusCode),
+    /// The REST Api's answer could not be understood
+    InvalidResponse(String),
+}
+
+impl fmt::Display for StateReadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            StateReadError::HttpError(ref err) => write!(f, "An http error occurred: {}", err),
+            StateReadError::RequestError(ref err) => {
+                write!(f, "Unable to form a request: {}", err)
+            }
+            StateReadError::IoError(ref err) => write!(f, "An io error occurred: {}", err),
+            StateReadError::TlsError(ref err) => write!(f, "A tls error occurred: {}", err),
+            StateReadError::UnexpectedStatus(ref address, status) => {
+                write!(f, "Reading {} returned {}", address, status)
+            }
+            StateReadError::InvalidResponse(ref msg) => {
+                write!(f, "An invalid response was received: {}", msg)
+            }
+        }
+    }
+}
+
+impl error::Error for StateReadError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            StateReadError::HttpError(ref err) => Some(err),
+            StateReadError::RequestError(ref err) => Some(err),
+            StateReadError::IoError(ref err) => Some(err),
+            StateReadError::TlsError(ref err) => Some(err),
+            StateReadError::UnexpectedStatus(_, _) => None,
+            StateReadError::InvalidResponse(_) => None,
+        }
+    }
+}
+
+impl From<HyperError> for StateReadError {
+    fn from(err: HyperError) -> Self {
+        StateReadError::HttpError(err)
+    }
+}
+
+impl From<http::Error> for StateReadError {
+    fn from(err: http::Error) -> Self {
+        StateReadError::RequestError(err)
+    }
+}
+
+impl From<TlsError> for StateReadError {
+    fn from(err: TlsError) -> Self {
+        StateReadError::TlsError(err)
+    }
+}
+
+impl From<io::Error> for StateReadError {
+    fn from(err: io::Error) -> Self {
+        StateReadError::IoError(err)
+    }
+}
+
+/// GETs the state entries at `addresses` from the REST Api at `target`, one
+/// at a time, connecting over HTTPS with the certificates of `tls`. Addresses
+/// which have no entry are left out of the result.
+pub fn read_state_entries(
+    target: &str,
+    addresses: &[String],
+    basic_auth: &Option<String>,
+    tls: &TlsOptions,
+) -> Result<HashMap<String, Vec<u8>>, StateReadError> {
+    let mut runtime = Runtime::new()?;
+    let client = Client::builder().build(https_connector(1, tls, false)?);
+
+    let mut entries = HashMap::new();
+    for address in addresses {
+        let state_url = format!("{}/state/{}", target, address);
+        debug!("State GET: {}", state_url);
+
+        let req = request_builder(Method::GET, &state_url, basic_auth).body(Body::empty())?;
+
+        let (status, body) = runtime.block_on(client.request(req).and_then(|response| {
+            let status = response.status();
+            response
+                .into_body()
+                .concat2()
+                .map(move |body| (status, body))
+        }))?;
+
+        match status {
+            StatusCode::OK => {
+                entries.insert(address.clone(), parse_state_entry(&body)?);
+            }
+            StatusCode::NOT_FOUND => (),
+            status => return Err(StateReadError::UnexpectedStatus(address.clone(), status)),
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Returns the data of a state entry response body.
+fn parse_state_entry(body: &[u8]) -> Result<Vec<u8>, StateReadError> {
+    let response: serde_json::Value = serde_json::from_slice(body)
+        .map_err(|err| StateReadError::InvalidResponse(err.to_string()))?;
+    let data = response["data"]
+        .as_str()
+        .ok_or_else(|| StateReadError::InvalidResponse("state entry has no data".into()))?;
+    base64::decode(data).map_err(|err| StateReadError::InvalidResponse(err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_state_entry;
+
+    #[test]
+    fn test_parse_state_entry() {
+        assert_eq!(
+            parse_state_entry(br#"{"data": "aGVsbG8=", "head": "abc"}"#).unwrap(),
+            b"hello".to_vec()
+        );
+        assert!(parse_state_entry(br#"{"error": {}}"#).is_err());
+        assert!(parse_state_entry(br#"{"data": "not base64!"}"#).is_err());
+    }
+}
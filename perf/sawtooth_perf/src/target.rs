@@ -0,0 +1,270 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+//! Tools for spreading requests over the Sawtooth REST Apis being targeted
+
+use std::cell::Cell;
+use std::fmt;
+use std::rc::Rc;
+use std::time;
+
+use hyper::client::{Client, HttpConnector};
+use hyper_tls::HttpsConnector;
+
+use tls::{https_connector, TlsOptions};
+
+/// Threads each target's connector uses to resolve its host name.
+const DNS_THREADS: usize = 1;
+
+/// Options for the connections made to each target.
+#[derive(Clone, Debug)]
+pub struct ConnectionOptions {
+    /// The most requests in flight to a target at once. As each request in
+    /// flight holds a connection, this also bounds the connections pooled
+    /// for a target.
+    pub max_in_flight: usize,
+    /// How long an idle connection is kept alive in the pool, or `None` to
+    /// keep idle connections until the target closes them.
+    pub keep_alive_timeout: Option<time::Duration>,
+    /// How often each target's status is checked, to take targets which are
+    /// down out of rotation and return them once they recover.
+    pub health_check_interval: time::Duration,
+    /// Whether to speak HTTP/2 to targets, multiplexing the requests to each
+    /// over a single connection. HTTPS targets must offer HTTP/2 by ALPN, and
+    /// plain HTTP targets must accept it without an upgrade.
+    pub http2: bool,
+    /// The certificates used to connect to targets over HTTPS.
+    pub tls: TlsOptions,
+}
+
+impl Default for ConnectionOptions {
+    fn default() -> Self {
+        ConnectionOptions {
+            max_in_flight: 64,
+            keep_alive_timeout: Some(time::Duration::from_secs(90)),
+            health_check_interval: time::Duration::from_secs(5),
+            http2: false,
+            tls: TlsOptions::default(),
+        }
+    }
+}
+
+/// A Sawtooth REST Api, with its own pool of kept-alive connections.
+pub struct Target {
+    url: String,
+    client: Client<HttpsConnector<HttpConnector>>,
+    in_flight: Cell<usize>,
+    max_in_flight: usize,
+    healthy: Cell<bool>,
+    // Requests completed since the counts were last reset
+    succeeded: Cell<usize>,
+    failed: Cell<usize>,
+}
+
+impl Target {
+    pub fn new(url: String, options: &ConnectionOptions) -> Self {
+        let client = Client::builder()
+            .keep_alive(true)
+            .keep_alive_timeout(options.keep_alive_timeout)
+            .max_idle_per_host(options.max_in_flight)
+            .http2_only(options.http2)
+            .build(
+                https_connector(DNS_THREADS, &options.tls, options.http2)
+                    .expect("TLS options are checked when they are loaded"),
+            );
+
+        Target {
+            url,
+            client,
+            in_flight: Cell::new(0),
+            max_in_flight: options.max_in_flight,
+            healthy: Cell::new(true),
+            succeeded: Cell::new(0),
+            failed: Cell::new(0),
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn client(&self) -> &Client<HttpsConnector<HttpConnector>> {
+        &self.client
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.get()
+    }
+
+    pub fn has_capacity(&self) -> bool {
+        self.in_flight.get() < self.max_in_flight
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.healthy.get()
+    }
+
+    /// Returns the target to rotation, logging if it had been taken out.
+    pub fn mark_healthy(&self) {
+        if !self.healthy.replace(true) {
+            info!(
+                "Target {} has recovered, returning it to rotation",
+                self.url
+            );
+        }
+    }
+
+    /// Takes the target out of rotation, logging if it had been in it.
+    pub fn mark_unhealthy(&self, reason: &str) {
+        if self.healthy.replace(false) {
+            warn!(
+                "Target {} is unhealthy, taking it out of rotation: {}",
+                self.url, reason
+            );
+        }
+    }
+
+    /// Marks that a request to this target has been sent.
+    pub fn start_request(&self) {
+        self.in_flight.set(self.in_flight.get() + 1);
+    }
+
+    /// Marks that a request to this target has completed, successfully or
+    /// otherwise.
+    pub fn finish_request(&self) {
+        self.in_flight.set(self.in_flight.get().saturating_sub(1));
+    }
+
+    pub fn record_success(&self) {
+        self.succeeded.set(self.succeeded.get() + 1);
+    }
+
+    pub fn record_failure(&self) {
+        self.failed.set(self.failed.get() + 1);
+    }
+
+    /// Resets the counts of succeeded and failed requests.
+    pub fn reset_counts(&self) {
+        self.succeeded.set(0);
+        self.failed.set(0);
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Target {0} ({1}), Succeeded: {2}, Failed: {3}, In Flight: {4}",
+            self.url,
+            if self.is_healthy() {
+                "healthy"
+            } else {
+                "unhealthy"
+            },
+            self.succeeded.get(),
+            self.failed.get(),
+            self.in_flight.get()
+        )
+    }
+}
+
+/// The targets of a workload, which are handed out in turn.
+pub struct TargetPool {
+    targets: Vec<Rc<Target>>,
+    next: Cell<usize>,
+}
+
+impl TargetPool {
+    pub fn new(urls: Vec<String>, options: &ConnectionOptions) -> Self {
+        TargetPool {
+            targets: urls
+                .into_iter()
+                .map(|url| Rc::new(Target::new(url, options)))
+                .collect(),
+            next: Cell::new(0),
+        }
+    }
+
+    pub fn targets(&self) -> &[Rc<Target>] {
+        &self.targets
+    }
+
+    /// Returns the next healthy target, in turn, with capacity for another
+    /// request, or `None` if every healthy target has its most requests in
+    /// flight or no target is healthy.
+    pub fn next_available(&self) -> Option<Rc<Target>> {
+        let len = self.targets.len();
+        for offset in 0..len {
+            let index = (self.next.get() + offset) % len;
+            let target = &self.targets[index];
+            if target.is_healthy() && target.has_capacity() {
+                self.next.set((index + 1) % len);
+                return Some(Rc::clone(target));
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConnectionOptions, TargetPool};
+
+    #[test]
+    fn test_next_available_skips_full_targets() {
+        let options = ConnectionOptions {
+            max_in_flight: 1,
+            ..ConnectionOptions::default()
+        };
+        let pool = TargetPool::new(
+            vec!["http://a:8008".into(), "http://b:8008".into()],
+            &options,
+        );
+
+        let first = pool.next_available().unwrap();
+        assert_eq!(first.url(), "http://a:8008");
+        first.start_request();
+
+        let second = pool.next_available().unwrap();
+        assert_eq!(second.url(), "http://b:8008");
+        second.start_request();
+
+        assert!(pool.next_available().is_none());
+
+        first.finish_request();
+        assert_eq!(pool.next_available().unwrap().url(), "http://a:8008");
+    }
+
+    #[test]
+    fn test_next_available_skips_unhealthy_targets() {
+        let pool = TargetPool::new(
+            vec!["http://a:8008".into(), "http://b:8008".into()],
+            &ConnectionOptions::default(),
+        );
+
+        pool.targets()[0].mark_unhealthy("down");
+        assert_eq!(pool.next_available().unwrap().url(), "http://b:8008");
+        assert_eq!(pool.next_available().unwrap().url(), "http://b:8008");
+
+        pool.targets()[1].mark_unhealthy("down");
+        assert!(pool.next_available().is_none());
+
+        pool.targets()[0].mark_healthy();
+        assert_eq!(pool.next_available().unwrap().url(), "http://a:8008");
+    }
+}
@@ -0,0 +1,211 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+//! Tools for connecting to Sawtooth REST Apis over HTTPS, e.g. behind a
+//! TLS-terminating load balancer
+
+use std::env;
+use std::error;
+use std::fmt;
+use std::fs;
+
+use hyper::client::HttpConnector;
+use hyper_tls::HttpsConnector;
+use native_tls::{Certificate, Identity, TlsConnector};
+
+/// The environment variable read for the password of a client identity.
+pub const IDENTITY_PASSWORD_ENV_VAR: &str = "SAWTOOTH_TLS_IDENTITY_PASSWORD";
+
+/// The ALPN protocol id of HTTP/2.
+const H2_ALPN_PROTOCOL: &str = "h2";
+
+/// The certificates trusted and presented when connecting to a target over
+/// HTTPS. By default only the system's CA certificates are trusted, and no
+/// client certificate is presented.
+#[derive(Clone, Default)]
+pub struct TlsOptions {
+    // A CA certificate to trust besides the system's
+    ca_certificate: Option<Certificate>,
+    // A PKCS #12 archive of the client certificate and key, with its password
+    client_identity: Option<(Vec<u8>, String)>,
+}
+
+impl TlsOptions {
+    /// Loads the CA certificate at `ca_certificate_path`, as PEM or DER, and
+    /// the PKCS #12 client identity at `client_identity_path`, whose password
+    /// is read from SAWTOOTH_TLS_IDENTITY_PASSWORD. Either may be left out.
+    pub fn load(
+        ca_certificate_path: Option<&str>,
+        client_identity_path: Option<&str>,
+    ) -> Result<Self, TlsError> {
+        let ca_certificate = match ca_certificate_path {
+            Some(path) => Some(parse_certificate(&read_file(path)?).map_err(|_| {
+                TlsError::InvalidCertificate(format!("{}: not a PEM or DER certificate", path))
+            })?),
+            None => None,
+        };
+        let client_identity = match client_identity_path {
+            Some(path) => Some((
+                read_file(path)?,
+                env::var(IDENTITY_PASSWORD_ENV_VAR).unwrap_or_default(),
+            )),
+            None => None,
+        };
+
+        let options = TlsOptions {
+            ca_certificate,
+            client_identity,
+        };
+        // Check the certificates can be used before any connection is made
+        options.connector(false)?;
+        Ok(options)
+    }
+
+    /// Returns a connector trusting and presenting the certificates, which
+    /// offers only HTTP/2 to servers if `http2` is set.
+    pub fn connector(&self, http2: bool) -> Result<TlsConnector, TlsError> {
+        let mut builder = TlsConnector::builder();
+        if let Some(ref certificate) = self.ca_certificate {
+            builder.add_root_certificate(certificate.clone());
+        }
+        if let Some((ref der, ref password)) = self.client_identity {
+            builder.identity(Identity::from_pkcs12(der, password)?);
+        }
+        if http2 {
+            builder.request_alpns(&[H2_ALPN_PROTOCOL]);
+        }
+        Ok(builder.build()?)
+    }
+}
+
+impl fmt::Debug for TlsOptions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // The identity's password is left out
+        f.debug_struct("TlsOptions")
+            .field("ca_certificate", &self.ca_certificate.is_some())
+            .field("client_identity", &self.client_identity.is_some())
+            .finish()
+    }
+}
+
+/// Returns a connector for both HTTP and HTTPS targets, which resolves host
+/// names on `dns_threads` threads. If `http2` is set, HTTPS targets are asked
+/// to speak HTTP/2.
+pub fn https_connector(
+    dns_threads: usize,
+    options: &TlsOptions,
+    http2: bool,
+) -> Result<HttpsConnector<HttpConnector>, TlsError> {
+    let mut http = HttpConnector::new(dns_threads);
+    http.enforce_http(false);
+    Ok(HttpsConnector::from((http, options.connector(http2)?)))
+}
+
+/// Parses a certificate as PEM or DER.
+fn parse_certificate(contents: &[u8]) -> Result<Certificate, native_tls::Error> {
+    Certificate::from_pem(contents).or_else(|_| Certificate::from_der(contents))
+}
+
+fn read_file(path: &str) -> Result<Vec<u8>, TlsError> {
+    fs::read(path).map_err(|err| TlsError::IoError(format!("Unable to read {}: {}", path, err)))
+}
+
+#[derive(Debug)]
+pub enum TlsError {
+    IoError(String),
+    InvalidCertificate(String),
+    TlsError(String),
+}
+
+impl From<native_tls::Error> for TlsError {
+    fn from(err: native_tls::Error) -> Self {
+        TlsError::TlsError(err.to_string())
+    }
+}
+
+impl fmt::Display for TlsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TlsError::IoError(ref msg) => write!(f, "Unable to load certificate: {}", msg),
+            TlsError::InvalidCertificate(ref msg) => write!(f, "Invalid certificate {}", msg),
+            TlsError::TlsError(ref msg) => write!(f, "Unable to configure TLS: {}", msg),
+        }
+    }
+}
+
+impl error::Error for TlsError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use openssl::asn1::Asn1Time;
+    use openssl::hash::MessageDigest;
+    use openssl::pkey::PKey;
+    use openssl::rsa::Rsa;
+    use openssl::x509::{X509Builder, X509NameBuilder, X509};
+
+    fn self_signed_certificate() -> X509 {
+        let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", "sawtooth-test-ca").unwrap();
+        let name = name.build();
+
+        let mut builder = X509Builder::new().unwrap();
+        builder.set_version(2).unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_issuer_name(&name).unwrap();
+        builder.set_pubkey(&key).unwrap();
+        builder
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&Asn1Time::days_from_now(1).unwrap())
+            .unwrap();
+        builder.sign(&key, MessageDigest::sha256()).unwrap();
+        builder.build()
+    }
+
+    #[test]
+    fn test_parse_certificate() {
+        let certificate = self_signed_certificate();
+        let der = certificate.to_der().unwrap();
+
+        assert_eq!(
+            parse_certificate(&certificate.to_pem().unwrap())
+                .unwrap()
+                .to_der()
+                .unwrap(),
+            der
+        );
+        assert_eq!(parse_certificate(&der).unwrap().to_der().unwrap(), der);
+        assert!(parse_certificate(b"not a certificate").is_err());
+    }
+
+    #[test]
+    fn test_connector_trusts_ca_certificate() {
+        let options = TlsOptions {
+            ca_certificate: Some(
+                parse_certificate(&self_signed_certificate().to_der().unwrap()).unwrap(),
+            ),
+            client_identity: None,
+        };
+        assert!(options.connector(false).is_ok());
+        assert!(options.connector(true).is_ok());
+        assert!(TlsOptions::default().connector(false).is_ok());
+    }
+}
@@ -17,28 +17,27 @@
 
 /// Tools for interacting with the Sawtooth Rest API
 use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::error;
 use std::fmt;
 use std::io;
-use std::iter::Cycle;
 use std::rc::Rc;
-use std::str::FromStr;
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
 use std::time;
-use std::vec::IntoIter;
 
+use base64;
 use chrono;
-use futures::Future;
-use hyper::client::{Client, HttpConnector, Request, Response};
-use hyper::error::UriError;
-use hyper::header::{Authorization, Basic, ContentLength, ContentType};
+use futures::{Future, Stream};
+use hyper::header::{AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE};
+use hyper::http;
 use hyper::Error as HyperError;
-use hyper::Method;
-use hyper::StatusCode;
-use hyper::Uri;
+use hyper::{Body, Method, Request, Response, StatusCode};
 use protobuf;
 use protobuf::Message;
-use tokio_core::reactor::Handle;
+use serde_json;
+use tokio::runtime::current_thread;
+use tokio::timer;
 
 use sawtooth_sdk::messages::batch::BatchList;
 
@@ -46,14 +45,26 @@ use batch_submit::BatchListResult;
 use batch_submit::BatchReadingError;
 
 use batch_map::BatchMap;
+use latency::LatencyTracker;
+use report::IntervalCounts;
+use target::{Target, TargetPool};
+
+/// The most batch ids to request the statuses of at once.
+const MAX_STATUS_IDS: usize = 50;
+
+/// The most batch statuses requests to make at each poll, so that commits
+/// are counted at up to `MAX_STATUS_IDS * MAX_STATUS_REQUESTS` per poll.
+const MAX_STATUS_REQUESTS: usize = 20;
 
 #[derive(Debug)]
 pub enum WorkloadError {
     HttpError(HyperError),
-    UriError(UriError),
+    RequestError(http::Error),
+    TimerError(timer::Error),
     ProtobufError(protobuf::ProtobufError),
     BatchReadingError(BatchReadingError),
     IoError(io::Error),
+    ZmqError(String),
     NoBatchError,
     UnknownRestApiError,
 }
@@ -62,8 +73,12 @@ impl fmt::Display for WorkloadError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             WorkloadError::HttpError(ref err) => write!(f, "An http error occurred: {}", err),
-            WorkloadError::UriError(ref err) => write!(f, "A uri error occurred: {}", err),
+            WorkloadError::RequestError(ref err) => {
+                write!(f, "Unable to form a request: {}", err)
+            }
+            WorkloadError::TimerError(ref err) => write!(f, "A timer error occurred: {}", err),
             WorkloadError::IoError(ref err) => write!(f, "An io error occurred: {}", err),
+            WorkloadError::ZmqError(ref err) => write!(f, "A zmq error occurred: {}", err),
             WorkloadError::ProtobufError(ref err) => {
                 write!(f, "A protobuf error occurred: {}", err)
             }
@@ -86,8 +101,10 @@ impl error::Error for WorkloadError {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match *self {
             WorkloadError::HttpError(ref err) => Some(err),
-            WorkloadError::UriError(ref err) => Some(err),
+            WorkloadError::RequestError(ref err) => Some(err),
+            WorkloadError::TimerError(ref err) => Some(err),
             WorkloadError::IoError(ref err) => Some(err),
+            WorkloadError::ZmqError(_) => None,
             WorkloadError::ProtobufError(ref err) => Some(err),
             WorkloadError::BatchReadingError(ref err) => Some(err),
             WorkloadError::NoBatchError => Some(&WorkloadError::NoBatchError),
@@ -120,9 +137,15 @@ impl From<HyperError> for WorkloadError {
     }
 }
 
-impl From<UriError> for WorkloadError {
-    fn from(err: UriError) -> Self {
-        WorkloadError::UriError(err)
+impl From<http::Error> for WorkloadError {
+    fn from(err: http::Error) -> Self {
+        WorkloadError::RequestError(err)
+    }
+}
+
+impl From<timer::Error> for WorkloadError {
+    fn from(err: timer::Error) -> Self {
+        WorkloadError::TimerError(err)
     }
 }
 
@@ -131,6 +154,15 @@ impl From<UriError> for WorkloadError {
 pub struct HTTPRequestCounter {
     sent_count: AtomicUsize,
     queue_full_count: AtomicUsize,
+    throttled_count: AtomicUsize,
+    // Counts since the workload started, which are not reset at each log
+    total_sent_count: AtomicUsize,
+    total_queue_full_count: AtomicUsize,
+    total_throttled_count: AtomicUsize,
+    // The counts of each logged interval, and the requests which failed by
+    // status, for the workload's report
+    intervals: Mutex<Vec<IntervalCounts>>,
+    errors: Mutex<BTreeMap<String, usize>>,
 }
 
 impl HTTPRequestCounter {
@@ -138,15 +170,70 @@ impl HTTPRequestCounter {
         HTTPRequestCounter {
             sent_count: AtomicUsize::new(0),
             queue_full_count: AtomicUsize::new(0),
+            throttled_count: AtomicUsize::new(0),
+            total_sent_count: AtomicUsize::new(0),
+            total_queue_full_count: AtomicUsize::new(0),
+            total_throttled_count: AtomicUsize::new(0),
+            intervals: Mutex::new(Vec::new()),
+            errors: Mutex::new(BTreeMap::new()),
         }
     }
 
     pub fn increment_sent(&self) {
         self.sent_count.fetch_add(1, Ordering::Relaxed);
+        self.total_sent_count.fetch_add(1, Ordering::Relaxed);
     }
 
     pub fn increment_queue_full(&self) {
         self.queue_full_count.fetch_add(1, Ordering::Relaxed);
+        self.total_queue_full_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn total_sent(&self) -> usize {
+        self.total_sent_count.load(Ordering::Relaxed)
+    }
+
+    pub fn total_queue_full(&self) -> usize {
+        self.total_queue_full_count.load(Ordering::Relaxed)
+    }
+
+    pub fn total_throttled(&self) -> usize {
+        self.total_throttled_count.load(Ordering::Relaxed)
+    }
+
+    /// Counts a request which failed with `status`, e.g. the HTTP status
+    /// code of the response.
+    pub fn record_error(&self, status: &str) {
+        *self
+            .errors
+            .lock()
+            .expect("Error counts lock poisoned")
+            .entry(status.to_string())
+            .or_insert(0) += 1;
+    }
+
+    /// Returns the counts of each interval logged so far.
+    pub fn intervals(&self) -> Vec<IntervalCounts> {
+        self.intervals
+            .lock()
+            .expect("Interval counts lock poisoned")
+            .clone()
+    }
+
+    /// Returns the number of failed requests by status.
+    pub fn errors(&self) -> BTreeMap<String, usize> {
+        self.errors
+            .lock()
+            .expect("Error counts lock poisoned")
+            .clone()
+    }
+
+    /// Counts batches which were not sent because every target had its most
+    /// requests in flight.
+    pub fn add_throttled(&self, count: usize) {
+        self.throttled_count.fetch_add(count, Ordering::Relaxed);
+        self.total_throttled_count
+            .fetch_add(count, Ordering::Relaxed);
     }
 
     pub fn log(&self, seconds: u64, nanoseconds: u32) {
@@ -157,8 +244,34 @@ impl HTTPRequestCounter {
             self.sent_count.load(Ordering::Relaxed) as f64 / update
         );
 
+        self.intervals
+            .lock()
+            .expect("Interval counts lock poisoned")
+            .push(IntervalCounts {
+                timestamp: chrono::Utc::now().to_rfc3339(),
+                seconds: update,
+                sent: self.sent_count.load(Ordering::Relaxed),
+                queue_full: self.queue_full_count.load(Ordering::Relaxed),
+                throttled: self.throttled_count.load(Ordering::Relaxed),
+            });
+
         self.sent_count.store(0, Ordering::Relaxed);
         self.queue_full_count.store(0, Ordering::Relaxed);
+        self.throttled_count.store(0, Ordering::Relaxed);
+    }
+
+    /// Prints the counts since the workload started, which ran for `elapsed`.
+    pub fn log_summary(&self, elapsed: time::Duration) {
+        let seconds = elapsed.as_secs() as f64 + f64::from(elapsed.subsec_nanos()) * 1e-9;
+        let total_sent = self.total_sent_count.load(Ordering::Relaxed);
+        println!(
+            "Summary ran {:.3}s, Sent: {}, Queue Full {}, Throttled {}, Batches/s {:.3}",
+            seconds,
+            total_sent,
+            self.total_queue_full_count.load(Ordering::Relaxed),
+            self.total_throttled_count.load(Ordering::Relaxed),
+            total_sent as f64 / seconds
+        );
     }
 }
 
@@ -167,10 +280,11 @@ impl fmt::Display for HTTPRequestCounter {
         let time = chrono::Utc::now();
         write!(
             f,
-            "{0}, Sent: {1}, Queue Full {2}",
+            "{0}, Sent: {1}, Queue Full {2}, Throttled {3}",
             time.format("%h-%d-%Y %H:%M:%S%.3f").to_string(),
             self.sent_count.load(Ordering::Relaxed),
-            self.queue_full_count.load(Ordering::Relaxed)
+            self.queue_full_count.load(Ordering::Relaxed),
+            self.throttled_count.load(Ordering::Relaxed)
         )
     }
 }
@@ -178,12 +292,19 @@ impl fmt::Display for HTTPRequestCounter {
 /// Log if time since last log is greater than update time.
 pub fn log(
     counter: &Rc<HTTPRequestCounter>,
+    targets: &TargetPool,
+    latency: &Rc<RefCell<LatencyTracker>>,
     last_log_time: &mut time::Instant,
     update_time: u32,
 ) -> Result<(), WorkloadError> {
     let log_time = time::Instant::now() - *last_log_time;
     if log_time.as_secs() as u32 >= update_time {
         counter.log(log_time.as_secs(), log_time.subsec_nanos());
+        for target in targets.targets() {
+            println!("{}", target);
+            target.reset_counts();
+        }
+        latency.borrow_mut().log_interval(log_time);
         *last_log_time = time::Instant::now();
     }
     Ok(())
@@ -208,14 +329,40 @@ pub fn get_next_batchlist(
     }
 }
 
-/// Create the request from the next Target Url and the batchlist.
+/// Starts a request to `url`, authenticated with `basic_auth`, a username and
+/// password joined by a colon, if it is given.
+pub fn request_builder(
+    method: Method,
+    url: &str,
+    basic_auth: &Option<String>,
+) -> http::request::Builder {
+    let mut builder = Request::builder();
+    builder.method(method).uri(url);
+    if let Some(ref basic_auth) = *basic_auth {
+        builder.header(
+            AUTHORIZATION,
+            format!("Basic {}", base64::encode(basic_auth)),
+        );
+    }
+    builder
+}
+
+/// Reads the body of a response, returning its status. A connection is only
+/// returned to its target's pool once the body of its response has been read.
+pub fn response_status(
+    response: Response<Body>,
+) -> impl Future<Item = StatusCode, Error = HyperError> {
+    let status = response.status();
+    response.into_body().concat2().map(move |_| status)
+}
+
+/// Create the request to the target from the batchlist.
 pub fn form_request_from_batchlist(
-    targets: &mut Cycle<IntoIter<String>>,
+    target: &Target,
     batch_list: Result<BatchList, WorkloadError>,
     basic_auth: &Option<String>,
-) -> Result<(Request, Option<String>), WorkloadError> {
-    let mut batch_url = targets.next().unwrap();
-    batch_url.push_str("/batches");
+) -> Result<(Request<Body>, Option<String>), WorkloadError> {
+    let batch_url = format!("{}/batches", target.url());
     debug!("Batches POST: {}", batch_url);
 
     let batchlist_unwrapped = batch_list?;
@@ -225,35 +372,38 @@ pub fn form_request_from_batchlist(
         None => None,
     };
     let bytes = batchlist_unwrapped.write_to_bytes()?;
-    let mut req = Request::new(Method::Post, Uri::from_str(&batch_url)?);
-    let content_len = bytes.len() as u64;
-    req.set_body(bytes);
-    req.headers_mut().set(ContentType::octet_stream());
-    req.headers_mut().set(ContentLength(content_len));
-
-    if let Some(ref basic_auth) = *basic_auth {
-        req.headers_mut()
-            .set(Authorization(Basic::from_str(&basic_auth)?));
-    }
+    let req = request_builder(Method::POST, &batch_url, basic_auth)
+        .header(CONTENT_TYPE, "application/octet-stream")
+        .header(CONTENT_LENGTH, bytes.len())
+        .body(Body::from(bytes))?;
 
     Ok((req, batch_id))
 }
 
 /// Log if there is a HTTP Error.
 fn handle_http_error(
-    response: Result<Response, HyperError>,
+    response: Result<StatusCode, HyperError>,
     batch_id: Option<String>,
+    sent: time::Instant,
     batches: &Rc<RefCell<Vec<BatchList>>>,
     batch_map: &Rc<RefCell<BatchMap>>,
     counter: &Rc<HTTPRequestCounter>,
+    latency: &Rc<RefCell<LatencyTracker>>,
 ) -> Result<(), HyperError> {
     if let Some(batch_id) = batch_id {
         match response {
-            Ok(response) => match response.status() {
-                StatusCode::Accepted => batch_map.borrow_mut().mark_submit_success(&batch_id),
-                StatusCode::TooManyRequests => counter.increment_queue_full(),
+            Ok(status) => match status {
+                StatusCode::ACCEPTED => {
+                    batch_map.borrow_mut().mark_submit_success(&batch_id);
+                    latency.borrow_mut().record_submit(&batch_id, sent);
+                }
+                StatusCode::TOO_MANY_REQUESTS => {
+                    counter.increment_queue_full();
+                    counter.record_error(&status.as_u16().to_string());
+                }
 
-                _ => {
+                status => {
+                    counter.record_error(&status.as_u16().to_string());
                     if let Some(batchlist) =
                         batch_map.borrow_mut().get_batchlist_to_submit(&batch_id)
                     {
@@ -262,6 +412,7 @@ fn handle_http_error(
                 }
             },
             Err(err) => {
+                counter.record_error("connection");
                 if let Some(batchlist) = batch_map.borrow_mut().get_batchlist_to_submit(&batch_id) {
                     batches.borrow_mut().push(batchlist)
                 }
@@ -272,28 +423,48 @@ fn handle_http_error(
     Ok(())
 }
 
-/// POST the batchlist to the rest api.
+/// Count the outcome of a request against its target, and take the target out
+/// of rotation if it could not be reached.
+fn record_target_result(target: &Target, response: &Result<StatusCode, HyperError>) {
+    match *response {
+        Ok(StatusCode::ACCEPTED) => target.record_success(),
+        Ok(_) => target.record_failure(),
+        Err(ref err) => {
+            target.record_failure();
+            target.mark_unhealthy(&err.to_string());
+        }
+    }
+}
+
+/// POST the batchlist to the target.
 pub fn make_request(
-    client: &Rc<Client<HttpConnector>>,
-    handle: &Handle,
+    target: Rc<Target>,
     counter: Rc<HTTPRequestCounter>,
     batch_map: Rc<RefCell<BatchMap>>,
     batches: Rc<RefCell<Vec<BatchList>>>,
-    req: Result<(Request, Option<String>), WorkloadError>,
+    latency: Rc<RefCell<LatencyTracker>>,
+    req: Result<(Request<Body>, Option<String>), WorkloadError>,
 ) -> Result<(), WorkloadError> {
-    let handle_clone = handle.clone();
     match req {
         Ok((req, batch_id)) => {
             counter.increment_sent();
-            let response_future = client
+            target.start_request();
+            let sent = time::Instant::now();
+            let response_future = target
+                .client()
                 .request(req)
-                .then(move |response: Result<Response, HyperError>| {
-                    handle_http_error(response, batch_id, &batches, &batch_map, &counter)
+                .and_then(response_status)
+                .then(move |response: Result<StatusCode, HyperError>| {
+                    target.finish_request();
+                    record_target_result(&target, &response);
+                    handle_http_error(
+                        response, batch_id, sent, &batches, &batch_map, &counter, &latency,
+                    )
                 })
                 .map(|_| ())
                 .map_err(|_| ());
 
-            handle_clone.spawn(response_future);
+            current_thread::spawn(response_future);
 
             Ok(())
         }
@@ -301,3 +472,151 @@ pub fn make_request(
         Err(err) => Err(err),
     }
 }
+
+/// GET `address` from the state of the target, to measure the latency of
+/// state reads. Every answered read is measured, and reads which are not
+/// answered with success are counted as errors.
+pub fn make_read_request(
+    target: Rc<Target>,
+    basic_auth: &Option<String>,
+    counter: Rc<HTTPRequestCounter>,
+    latency: Rc<RefCell<LatencyTracker>>,
+    address: String,
+) -> Result<(), WorkloadError> {
+    let state_url = format!("{}/state/{}", target.url(), address);
+    debug!("State GET: {}", state_url);
+
+    let req = request_builder(Method::GET, &state_url, basic_auth).body(Body::empty())?;
+
+    target.start_request();
+    let sent = time::Instant::now();
+    let read_future = target.client().request(req).and_then(response_status).then(
+        move |status| -> Result<(), ()> {
+            target.finish_request();
+            match status {
+                Ok(status) => {
+                    if status.is_server_error() {
+                        target.record_failure();
+                    } else {
+                        target.record_success();
+                    }
+                    if !status.is_success() {
+                        counter.record_error(&format!("read {}", status.as_u16()));
+                    }
+                    latency.borrow_mut().record_read(&address, sent);
+                }
+                Err(err) => {
+                    target.record_failure();
+                    target.mark_unhealthy(&err.to_string());
+                    counter.record_error("read connection");
+                }
+            }
+            Ok(())
+        },
+    );
+
+    current_thread::spawn(read_future);
+
+    Ok(())
+}
+
+/// GET the statuses of batches waiting to be committed from the target, in
+/// requests of up to `MAX_STATUS_IDS` batches each, to measure their commit
+/// latency and throughput.
+pub fn poll_batch_statuses(
+    target: &Target,
+    basic_auth: &Option<String>,
+    latency: &Rc<RefCell<LatencyTracker>>,
+) -> Result<(), WorkloadError> {
+    let batch_ids = latency
+        .borrow()
+        .pending_commit_ids(MAX_STATUS_IDS * MAX_STATUS_REQUESTS);
+    for batch_ids in batch_ids.chunks(MAX_STATUS_IDS) {
+        request_batch_statuses(batch_ids, target, basic_auth, latency)?;
+    }
+
+    Ok(())
+}
+
+fn request_batch_statuses(
+    batch_ids: &[String],
+    target: &Target,
+    basic_auth: &Option<String>,
+    latency: &Rc<RefCell<LatencyTracker>>,
+) -> Result<(), WorkloadError> {
+    let status_url = format!("{}/batch_statuses?id={}", target.url(), batch_ids.join(","));
+    debug!("Batch statuses GET: {}", status_url);
+
+    let req = request_builder(Method::GET, &status_url, basic_auth).body(Body::empty())?;
+
+    let latency = Rc::clone(latency);
+    let status_future = target
+        .client()
+        .request(req)
+        .and_then(|response| response.into_body().concat2())
+        .map(move |body| record_batch_statuses(&body, &latency))
+        .map_err(|err| info!("Unable to get batch statuses: {}", err));
+
+    current_thread::spawn(status_future);
+
+    Ok(())
+}
+
+/// Record the commits and invalid batches in a batch statuses response body.
+fn record_batch_statuses(body: &[u8], latency: &Rc<RefCell<LatencyTracker>>) {
+    let response: serde_json::Value = match serde_json::from_slice(body) {
+        Ok(response) => response,
+        Err(err) => {
+            info!("Unable to parse batch statuses: {}", err);
+            return;
+        }
+    };
+
+    let statuses = match response["data"].as_array() {
+        Some(statuses) => statuses,
+        None => return,
+    };
+
+    let mut latency = latency.borrow_mut();
+    for status in statuses {
+        let batch_id = match status["id"].as_str() {
+            Some(batch_id) => batch_id,
+            None => continue,
+        };
+
+        match status["status"].as_str() {
+            Some("COMMITTED") => latency.record_commit(batch_id),
+            Some("INVALID") => latency.record_invalid(batch_id),
+            Some("UNKNOWN") => latency.forget(batch_id),
+            _ => (),
+        }
+    }
+}
+
+/// GET the status of the target, to take it out of rotation if it is down
+/// and return it once it has recovered.
+pub fn check_target_health(
+    target: &Rc<Target>,
+    basic_auth: &Option<String>,
+) -> Result<(), WorkloadError> {
+    let status_url = format!("{}/status", target.url());
+    debug!("Status GET: {}", status_url);
+
+    let req = request_builder(Method::GET, &status_url, basic_auth).body(Body::empty())?;
+
+    let target = Rc::clone(target);
+    let health_future = target.client().request(req).and_then(response_status).then(
+        move |status| -> Result<(), ()> {
+            match status {
+                Ok(status) if status.is_success() => target.mark_healthy(),
+                Ok(status) => target.mark_unhealthy(&format!("status check returned {}", status)),
+                Err(err) => target.mark_unhealthy(&err.to_string()),
+            }
+            Ok(())
+        },
+    );
+
+    current_thread::spawn(health_future);
+
+    Ok(())
+}
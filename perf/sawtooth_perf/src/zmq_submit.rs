@@ -0,0 +1,286 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+//! Tools for submitting batches directly to a validator's client interface,
+//! bypassing the REST Api
+
+use std::thread;
+use std::time;
+
+use protobuf;
+use protobuf::Message;
+
+use sawtooth_sdk::messages::batch::BatchList;
+use sawtooth_sdk::messages::client_batch_submit::{
+    ClientBatchSubmitRequest, ClientBatchSubmitResponse, ClientBatchSubmitResponse_Status,
+};
+use sawtooth_sdk::messages::validator::Message_MessageType;
+use sawtooth_sdk::messaging::stream::{
+    MessageConnection, MessageFuture, MessageSender, ReceiveError,
+};
+use sawtooth_sdk::messaging::zmq_stream::{ZmqMessageConnection, ZmqMessageSender};
+
+use batch_map::BatchMap;
+use batch_submit::BatchListResult;
+use latency::LatencyTracker;
+use rate::{RateSchedule, WorkloadLimits};
+use report::{self, ReportOptions};
+use workload::{HTTPRequestCounter, WorkloadError};
+
+/// How often batches which are due to be sent are sent, and responses are
+/// checked for.
+const PACING_TICK: time::Duration = time::Duration::from_millis(1);
+
+/// The longest to wait for responses to requests still in flight once a
+/// workload has reached its limits.
+const DRAIN_TIMEOUT: time::Duration = time::Duration::from_secs(30);
+
+/// A ClientBatchSubmitRequest waiting for its response.
+struct InFlight {
+    batch_id: String,
+    sent: time::Instant,
+    future: MessageFuture,
+}
+
+/// Run a load of the BatchLists that are generated by BatchListIter, sending
+/// each as a ClientBatchSubmitRequest to the validator at `connect`, at the
+/// rates given by `rate`, until the workload reaches its `limits`.
+///
+/// At most `max_in_flight` requests wait for a response at once; batches due
+/// while that many are waiting are counted as throttled rather than sent
+/// late. Batch submission latencies are recorded by `latency`, and a report
+/// is written once the workload ends if `report` is given.
+#[allow(clippy::too_many_arguments)]
+pub fn run_zmq_workload(
+    batch_list_iter: &mut dyn Iterator<Item = BatchListResult>,
+    rate: &RateSchedule,
+    limits: &WorkloadLimits,
+    update_time: u32,
+    connect: &str,
+    max_in_flight: usize,
+    mut latency: LatencyTracker,
+    report: Option<&ReportOptions>,
+) -> Result<(), WorkloadError> {
+    let connection = ZmqMessageConnection::new(connect);
+    let (mut sender, _receiver) = connection.create();
+
+    let counter = HTTPRequestCounter::new();
+    let mut batch_map = BatchMap::new();
+    let mut resubmits: Vec<BatchList> = Vec::new();
+    let mut in_flight: Vec<InFlight> = Vec::new();
+    let mut correlation_id: u64 = 0;
+
+    let start_time = time::Instant::now();
+    let mut dispatched: u64 = 0;
+    let mut log_time = time::Instant::now();
+
+    let result = loop {
+        let elapsed = start_time.elapsed();
+        if limits.is_reached(elapsed, counter.total_sent() as u64) {
+            break Ok(());
+        }
+
+        let log_elapsed = log_time.elapsed();
+        if log_elapsed.as_secs() as u32 >= update_time {
+            counter.log(log_elapsed.as_secs(), log_elapsed.subsec_nanos());
+            latency.log_interval(log_elapsed);
+            log_time = time::Instant::now();
+        }
+
+        let due = rate.batches_due(elapsed);
+        let mut error = None;
+        while dispatched < due && !limits.is_reached(elapsed, counter.total_sent() as u64) {
+            if in_flight.len() >= max_in_flight {
+                counter.add_throttled((due - dispatched) as usize);
+                dispatched = due;
+                break;
+            }
+            dispatched += 1;
+
+            let batch_list = match next_batch_list(batch_list_iter, &mut batch_map, &mut resubmits)
+            {
+                Ok(batch_list) => batch_list,
+                Err(err) => {
+                    error = Some(err);
+                    break;
+                }
+            };
+
+            correlation_id += 1;
+            match send_batch_list(&sender, batch_list, &correlation_id.to_string()) {
+                Ok(request) => {
+                    counter.increment_sent();
+                    in_flight.push(request);
+                }
+                Err(err) => {
+                    error = Some(err);
+                    break;
+                }
+            }
+        }
+
+        if let Some(err) = error {
+            break Err(err);
+        }
+
+        receive_responses(
+            &mut in_flight,
+            time::Duration::from_millis(0),
+            &counter,
+            &mut batch_map,
+            &mut resubmits,
+            &mut latency,
+        );
+
+        thread::sleep(PACING_TICK);
+    };
+
+    if result.is_ok() {
+        // Wait for the responses still outstanding, so they are in the summary
+        let drain_start = time::Instant::now();
+        while !in_flight.is_empty() && drain_start.elapsed() < DRAIN_TIMEOUT {
+            receive_responses(
+                &mut in_flight,
+                PACING_TICK,
+                &counter,
+                &mut batch_map,
+                &mut resubmits,
+                &mut latency,
+            );
+        }
+    }
+
+    sender.close();
+
+    let elapsed = start_time.elapsed();
+    counter.log_summary(elapsed);
+    latency.log_summary(elapsed);
+    let written = report.map_or(Ok(()), |options| {
+        report::write_report(options, &counter, &latency, elapsed)
+    });
+    result.and(written.map_err(WorkloadError::from))
+}
+
+/// Returns a batch list to resubmit, if there is one, or else the next batch
+/// list from the iterator.
+fn next_batch_list(
+    batch_list_iter: &mut dyn Iterator<Item = BatchListResult>,
+    batch_map: &mut BatchMap,
+    resubmits: &mut Vec<BatchList>,
+) -> Result<BatchList, WorkloadError> {
+    match resubmits.pop() {
+        Some(batch_list) => Ok(batch_list),
+        None => match batch_list_iter.next() {
+            Some(Ok(batch_list)) => {
+                batch_map.add(batch_list.clone());
+                Ok(batch_list)
+            }
+            Some(Err(err)) => Err(WorkloadError::from(err)),
+            None => Err(WorkloadError::NoBatchError),
+        },
+    }
+}
+
+/// Sends the batches of a batch list as a ClientBatchSubmitRequest.
+fn send_batch_list(
+    sender: &ZmqMessageSender,
+    batch_list: BatchList,
+    correlation_id: &str,
+) -> Result<InFlight, WorkloadError> {
+    let batch_id = batch_list
+        .batches
+        .last()
+        .map(|batch| batch.header_signature.clone())
+        .ok_or(WorkloadError::NoBatchError)?;
+
+    let mut request = ClientBatchSubmitRequest::new();
+    request.set_batches(batch_list.batches);
+    let bytes = request.write_to_bytes()?;
+
+    let future = sender
+        .send(
+            Message_MessageType::CLIENT_BATCH_SUBMIT_REQUEST,
+            correlation_id,
+            &bytes,
+        )
+        .map_err(|err| WorkloadError::ZmqError(format!("{:?}", err)))?;
+
+    Ok(InFlight {
+        batch_id,
+        sent: time::Instant::now(),
+        future,
+    })
+}
+
+/// Handles the responses which arrive within `timeout` to the requests in
+/// flight, resubmitting batches which were not accepted.
+fn receive_responses(
+    in_flight: &mut Vec<InFlight>,
+    timeout: time::Duration,
+    counter: &HTTPRequestCounter,
+    batch_map: &mut BatchMap,
+    resubmits: &mut Vec<BatchList>,
+    latency: &mut LatencyTracker,
+) {
+    let mut index = 0;
+    while index < in_flight.len() {
+        let response = match in_flight[index].future.get_timeout(timeout) {
+            Err(ReceiveError::TimeoutError) => {
+                index += 1;
+                continue;
+            }
+            response => response,
+        };
+
+        let request = in_flight.swap_remove(index);
+        let status = response
+            .map_err(|err| format!("{:?}", err))
+            .and_then(|message| {
+                protobuf::parse_from_bytes::<ClientBatchSubmitResponse>(message.get_content())
+                    .map_err(|err| err.to_string())
+            })
+            .map(|response| response.get_status());
+
+        match status {
+            Ok(ClientBatchSubmitResponse_Status::OK) => {
+                batch_map.mark_submit_success(&request.batch_id);
+                latency.record_submit(&request.batch_id, request.sent);
+            }
+            Ok(ClientBatchSubmitResponse_Status::INVALID_BATCH) => {
+                // Resubmitting an invalid batch will not make it valid
+                batch_map.mark_submit_success(&request.batch_id);
+                info!("Batch {} was invalid", request.batch_id);
+            }
+            Ok(status) => {
+                if status == ClientBatchSubmitResponse_Status::QUEUE_FULL {
+                    counter.increment_queue_full();
+                }
+                counter.record_error(&format!("{:?}", status));
+                if let Some(batch_list) = batch_map.get_batchlist_to_submit(&request.batch_id) {
+                    resubmits.push(batch_list);
+                }
+            }
+            Err(err) => {
+                counter.record_error("connection");
+                if let Some(batch_list) = batch_map.get_batchlist_to_submit(&request.batch_id) {
+                    resubmits.push(batch_list);
+                }
+                info!("{}", err);
+            }
+        }
+    }
+}
@@ -21,7 +21,6 @@ extern crate sawtooth_sdk;
 
 use std::error::Error;
 use std::fs::File;
-use std::io::Read;
 
 use batch_gen::generate_signed_batches;
 use batch_submit::submit_signed_batches;
@@ -29,9 +28,10 @@ use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
 
 use sawtooth_perf::batch_gen;
 use sawtooth_perf::batch_submit;
+use sawtooth_perf::key_loader::{load_private_key, passphrase_from_env};
+use sawtooth_perf::tls::TlsOptions;
 
 use sawtooth_sdk::signing;
-use sawtooth_sdk::signing::secp256k1::Secp256k1PrivateKey;
 
 const APP_NAME: &str = env!("CARGO_PKG_NAME");
 const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -121,13 +121,7 @@ fn run_batch_command(args: &ArgMatches) -> Result<(), Box<dyn Error>> {
     let mut in_file = File::open(args.value_of("input").unwrap())?;
     let mut out_file = File::create(args.value_of("output").unwrap())?;
 
-    let mut key_file = File::open(args.value_of("key").unwrap())?;
-
-    let mut buf = String::new();
-    key_file.read_to_string(&mut buf)?;
-    buf.pop(); // remove the new line
-
-    let private_key = Secp256k1PrivateKey::from_hex(&buf)?;
+    let private_key = load_private_key(args.value_of("key").unwrap(), passphrase_from_env)?;
     let context = signing::create_context("secp256k1")?;
 
     if let Err(err) = generate_signed_batches(
@@ -172,6 +166,26 @@ fn create_submit_subcommand_args<'a, 'b>() -> App<'a, 'b> {
                 .value_name("RATE")
                 .help("The number of batches per second to submit to the target"),
         )
+        .arg(
+            Arg::with_name("tls-ca-cert")
+                .long("tls-ca-cert")
+                .value_name("FILE")
+                .help("A CA certificate, as PEM or DER, to trust when connecting to HTTPS targets"),
+        )
+        .arg(
+            Arg::with_name("tls-client-identity")
+                .long("tls-client-identity")
+                .value_name("FILE")
+                .help(
+                    "A PKCS #12 archive of a client certificate and key to present to HTTPS \
+                     targets; its password is read from SAWTOOTH_TLS_IDENTITY_PASSWORD",
+                ),
+        )
+        .arg(
+            Arg::with_name("http2")
+                .long("http2")
+                .help("Speak HTTP/2 to the target"),
+        )
 }
 
 fn run_submit_command(args: &ArgMatches) -> Result<(), Box<dyn Error>> {
@@ -208,9 +222,21 @@ fn run_submit_command(args: &ArgMatches) -> Result<(), Box<dyn Error>> {
 
     let mut in_file = File::open(args.value_of("input").unwrap())?;
 
+    let tls = TlsOptions::load(
+        args.value_of("tls-ca-cert"),
+        args.value_of("tls-client-identity"),
+    )?;
+
     println!("Input: {} Target: {} Rate: {}", input, target, rate);
 
-    if let Err(err) = submit_signed_batches(&mut in_file, target, rate) {
+    if let Err(err) = submit_signed_batches(
+        &mut in_file,
+        target,
+        rate,
+        None,
+        &tls,
+        args.is_present("http2"),
+    ) {
         return Err(Box::new(err));
     }
 
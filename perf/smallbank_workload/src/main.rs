@@ -16,9 +16,9 @@
  */
 extern crate clap;
 extern crate crypto;
-extern crate env_logger;
 extern crate protobuf;
 extern crate rand;
+extern crate sawtooth_logging;
 extern crate sawtooth_perf;
 extern crate sawtooth_sdk;
 
@@ -26,11 +26,14 @@ mod playlist;
 mod protos;
 mod smallbank_transformer;
 
+use std::env;
 use std::error::Error;
+use std::ffi::OsString;
 use std::fs::File;
-use std::io::Read;
 use std::io::Write;
+use std::path::Path;
 use std::str::{FromStr, Split};
+use std::time;
 
 use batch_gen::generate_signed_batches;
 use batch_gen::SignedBatchIterator;
@@ -40,13 +43,24 @@ use batch_submit::InfiniteBatchListIterator;
 use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
 use playlist::generate_smallbank_playlist;
 use playlist::process_smallbank_playlist;
+use playlist::verify_smallbank_playlist;
 use rand::Rng;
 
+use sawtooth_logging::{level_from_verbosity, LoggingConfig};
 use sawtooth_perf::batch_gen;
 use sawtooth_perf::batch_submit;
+use sawtooth_perf::capture::replay_captured_batches;
+use sawtooth_perf::key_loader::{load_private_key, passphrase_from_env};
+use sawtooth_perf::latency::LatencyTracker;
+use sawtooth_perf::manifest::{key_fingerprint, manifest_path, RunManifest};
+use sawtooth_perf::rate::{RateSchedule, WorkloadLimits};
+use sawtooth_perf::report::ReportOptions;
+use sawtooth_perf::signer_pool::{read_private_keys, SignerPool, SignerSelection};
+use sawtooth_perf::target::ConnectionOptions;
+use sawtooth_perf::tls::{TlsError, TlsOptions};
+use sawtooth_perf::zmq_submit::run_zmq_workload;
 
 use sawtooth_sdk::signing;
-use sawtooth_sdk::signing::secp256k1::Secp256k1PrivateKey;
 
 use playlist::SmallbankGeneratingIter;
 use smallbank_transformer::SBPayloadTransformer;
@@ -54,23 +68,52 @@ use smallbank_transformer::SBPayloadTransformer;
 const APP_NAME: &str = env!("CARGO_PKG_NAME");
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// The arguments of the load subcommand which shape the workload, as their
+/// names and long names, which are recorded in a run's manifest to replay it.
+const MANIFEST_ARGS: &[(&str, &str)] = &[
+    ("key", "key"),
+    ("key-dir", "key-dir"),
+    ("key-weights", "key-weights"),
+    ("max-batch-size", "max-batch-size"),
+    ("num-accounts", "accounts"),
+    ("rate", "rate"),
+    ("ramp", "ramp"),
+    ("duration", "duration"),
+    ("max-batches", "max-batches"),
+    ("target", "target"),
+    ("connect", "connect"),
+    ("max-in-flight", "max-in-flight"),
+    ("keep-alive", "keep-alive"),
+    ("http2", "http2"),
+    ("commit-latency", "commit-latency"),
+];
+
 fn main() {
-    env_logger::init();
+    let arg_matches = get_arg_matches(env::args());
 
-    let arg_matches = App::new(APP_NAME)
-        .version(VERSION)
-        .setting(AppSettings::SubcommandRequiredElseHelp)
-        .subcommand(create_batch_subcommand_args())
-        .subcommand(create_submit_subcommand_args())
-        .subcommand(create_playlist_subcommand_args())
-        .subcommand(create_load_subcommand_args())
-        .get_matches();
+    let log_level = level_from_verbosity(arg_matches.occurrences_of("verbose"));
+    if let Err(err) = LoggingConfig::from_matches(&arg_matches, log_level)
+        .and_then(|config| sawtooth_logging::init(&config))
+    {
+        eprintln!("Error: {}", err);
+        std::process::exit(1);
+    }
+
+    let (arg_matches, manifest) = match apply_manifest(arg_matches) {
+        Ok(applied) => applied,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            std::process::exit(1);
+        }
+    };
 
     let result = match arg_matches.subcommand() {
         ("batch", Some(args)) => run_batch_command(args),
         ("submit", Some(args)) => run_submit_command(args),
         ("playlist", Some(args)) => run_playlist_command(args),
-        ("load", Some(args)) => run_load_command(args),
+        ("load", Some(args)) => run_load_command(args, manifest.as_ref()),
+        ("replay", Some(args)) => run_replay_command(args),
+        ("verify", Some(args)) => run_verify_command(args),
         _ => panic!("Should have processed a subcommand or exited before here"),
     };
 
@@ -83,22 +126,84 @@ fn main() {
     });
 }
 
+fn get_arg_matches<'a, I, T>(argv: I) -> ArgMatches<'a>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let app = App::new(APP_NAME)
+        .version(VERSION)
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg(
+            Arg::with_name("verbose")
+                .short("v")
+                .long("verbose")
+                .multiple(true)
+                .help("Increase output verbosity"),
+        )
+        .subcommand(create_batch_subcommand_args())
+        .subcommand(create_submit_subcommand_args())
+        .subcommand(create_playlist_subcommand_args())
+        .subcommand(create_load_subcommand_args())
+        .subcommand(create_replay_subcommand_args())
+        .subcommand(create_verify_subcommand_args());
+    sawtooth_logging::add_args(app).get_matches_from(argv)
+}
+
+/// Reads the run manifest given to the load subcommand by --manifest, if
+/// any, and fills in the arguments of its run which are not given on the
+/// command line.
+fn apply_manifest<'a>(
+    arg_matches: ArgMatches<'a>,
+) -> Result<(ArgMatches<'a>, Option<RunManifest>), Box<dyn Error>> {
+    let manifest = match arg_matches
+        .subcommand_matches("load")
+        .and_then(|args| args.value_of("manifest"))
+    {
+        Some(path) => RunManifest::read(path)?,
+        None => return Ok((arg_matches, None)),
+    };
+    let argv = {
+        let args = arg_matches.subcommand_matches("load").unwrap();
+        manifest.replay_args(env::args().collect(), |long| {
+            MANIFEST_ARGS
+                .iter()
+                .any(|&(name, arg_long)| arg_long == long && args.occurrences_of(name) > 0)
+        })
+    };
+    Ok((get_arg_matches(argv), Some(manifest)))
+}
+
 #[inline]
 fn arg_error(msg: &str) -> Result<(), Box<dyn Error>> {
     Err(Box::new(CliError::ArgumentError(String::from(msg))))
 }
 
 fn create_load_subcommand_args<'a, 'b>() -> App<'a, 'b> {
-    SubCommand::with_name("load")
+    let app = SubCommand::with_name("load")
         .about("Submit smallbank workload at a continuous rate")
         .arg(
             Arg::with_name("key")
                 .short("k")
                 .long("key")
                 .value_name("KEY_FILE")
-                .required(true)
+                .required_unless("key-dir")
                 .help("The signing key for both batches and transactions."),
         )
+        .arg(
+            Arg::with_name("key-dir")
+                .long("key-dir")
+                .value_name("KEY_DIR")
+                .conflicts_with("key")
+                .help("A directory of .priv files with signing keys to use in turn, instead of one key."),
+        )
+        .arg(
+            Arg::with_name("key-weights")
+                .long("key-weights")
+                .value_name("KEY_WEIGHTS")
+                .requires("key-dir")
+                .help("Comma separated weights to choose each key in the key directory by, in file name order."),
+        )
         .arg(
             Arg::with_name("max-batch-size")
                 .short("n")
@@ -120,6 +225,24 @@ fn create_load_subcommand_args<'a, 'b>() -> App<'a, 'b> {
                 .value_name("RATE")
                 .help("The number of batches per second. Defaults to 2."),
         )
+        .arg(
+            Arg::with_name("ramp")
+                .long("ramp")
+                .value_name("START,END,SECONDS")
+                .help("Ramp the batches per second from START to END over SECONDS, instead of using the rate."),
+        )
+        .arg(
+            Arg::with_name("duration")
+                .long("duration")
+                .value_name("DURATION")
+                .help("The time in seconds to run the workload for."),
+        )
+        .arg(
+            Arg::with_name("max-batches")
+                .long("max-batches")
+                .value_name("MAX_BATCHES")
+                .help("The most batches to send before stopping the workload."),
+        )
         .arg(
             Arg::with_name("target")
                 .short("t")
@@ -127,6 +250,13 @@ fn create_load_subcommand_args<'a, 'b>() -> App<'a, 'b> {
                 .value_name("TARGET")
                 .help("A comma separated list of Sawtooth REST Api endpoints."),
         )
+        .arg(
+            Arg::with_name("connect")
+                .long("connect")
+                .value_name("CONNECT")
+                .conflicts_with("target")
+                .help("A validator's client endpoint, such as tcp://localhost:4004, to submit batches to directly instead of through the REST Api."),
+        )
         .arg(
             Arg::with_name("seed")
                 .short("s")
@@ -134,6 +264,13 @@ fn create_load_subcommand_args<'a, 'b>() -> App<'a, 'b> {
                 .value_name("SEED")
                 .help("An integer to use as a seed to make the workload reproduceable."),
         )
+        .arg(
+            Arg::with_name("manifest")
+                .long("manifest")
+                .value_name("MANIFEST")
+                .conflicts_with("seed")
+                .help("The run manifest of an earlier run to replay an identical workload from."),
+        )
         .arg(
             Arg::with_name("update")
                 .short("u")
@@ -153,9 +290,81 @@ fn create_load_subcommand_args<'a, 'b>() -> App<'a, 'b> {
                 .value_name("AUTH_PASSWORD")
                 .help("The basic auth password to authenticate with the Sawtooth REST Api."),
         )
+        .arg(
+            Arg::with_name("max-in-flight")
+                .long("max-in-flight")
+                .value_name("MAX_IN_FLIGHT")
+                .help("The most requests in flight to each target, or to the validator, at once. Defaults to 64."),
+        )
+        .arg(
+            Arg::with_name("keep-alive")
+                .long("keep-alive")
+                .value_name("KEEP_ALIVE")
+                .help("The time in seconds to keep idle connections open. Defaults to 90."),
+        )
+        .arg(http2_arg())
+        .arg(
+            Arg::with_name("commit-latency")
+                .long("commit-latency")
+                .help("Poll batch statuses to measure the latency and throughput of batch commits."),
+        )
+        .arg(
+            Arg::with_name("latency-csv")
+                .long("latency-csv")
+                .value_name("LATENCY_CSV")
+                .help("A file to write every latency sample to, as CSV."),
+        )
+        .arg(
+            Arg::with_name("report")
+                .long("report")
+                .value_name("REPORT")
+                .help("A file to write a JSON report of the workload to once it ends."),
+        )
+        .arg(
+            Arg::with_name("report-html")
+                .long("report-html")
+                .value_name("REPORT_HTML")
+                .requires("report")
+                .help("A file to also write the report to, as a self-contained HTML page."),
+        );
+    add_tls_args(app)
 }
 
-fn run_load_command(args: &ArgMatches) -> Result<(), Box<dyn Error>> {
+fn add_tls_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app.arg(
+        Arg::with_name("tls-ca-cert")
+            .long("tls-ca-cert")
+            .value_name("FILE")
+            .help("A CA certificate, as PEM or DER, to trust when connecting to HTTPS targets."),
+    )
+    .arg(
+        Arg::with_name("tls-client-identity")
+            .long("tls-client-identity")
+            .value_name("FILE")
+            .help(
+                "A PKCS #12 archive of a client certificate and key to present to HTTPS \
+                 targets. Its password is read from SAWTOOTH_TLS_IDENTITY_PASSWORD.",
+            ),
+    )
+}
+
+fn http2_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("http2")
+        .long("http2")
+        .help("Speak HTTP/2 to targets, multiplexing requests over one connection to each.")
+}
+
+fn load_tls_options(args: &ArgMatches) -> Result<TlsOptions, TlsError> {
+    TlsOptions::load(
+        args.value_of("tls-ca-cert"),
+        args.value_of("tls-client-identity"),
+    )
+}
+
+fn run_load_command(
+    args: &ArgMatches,
+    replayed: Option<&RunManifest>,
+) -> Result<(), Box<dyn Error>> {
     let max_txns: usize = match args.value_of("max-batch-size").unwrap_or("1").parse() {
         Ok(n) => n,
         Err(_) => 0,
@@ -196,6 +405,45 @@ fn run_load_command(args: &ArgMatches) -> Result<(), Box<dyn Error>> {
         Ok(r) => r,
         Err(_) => return arg_error("The rate is the number of batches per second."),
     };
+    let rate_schedule = match args.value_of("ramp") {
+        Some(ramp) => match RateSchedule::parse_ramp(ramp) {
+            Ok(rate_schedule) => rate_schedule,
+            Err(err) => return arg_error(&err),
+        },
+        None => RateSchedule::Constant(rate as f64),
+    };
+    let duration: Option<u64> = match args.value_of("duration").map(str::parse) {
+        Some(Ok(duration)) if duration > 0 => Some(duration),
+        Some(_) => return arg_error("duration must be a number of seconds greater than 0"),
+        None => None,
+    };
+    let max_batches: Option<u64> = match args.value_of("max-batches").map(str::parse) {
+        Some(Ok(max_batches)) if max_batches > 0 => Some(max_batches),
+        Some(_) => return arg_error("max-batches must be a number greater than 0"),
+        None => None,
+    };
+    let limits = WorkloadLimits {
+        duration: duration.map(time::Duration::from_secs),
+        max_batches,
+    };
+    let max_in_flight: usize = match args.value_of("max-in-flight").unwrap_or("64").parse() {
+        Ok(n) => n,
+        Err(_) => 0,
+    };
+    if max_in_flight == 0 {
+        return arg_error("max-in-flight must be a number greater than 0");
+    }
+    let keep_alive: u64 = match args.value_of("keep-alive").unwrap_or("90").parse() {
+        Ok(n) => n,
+        Err(_) => return arg_error("keep-alive must be a number of seconds."),
+    };
+    let connection_options = ConnectionOptions {
+        max_in_flight,
+        keep_alive_timeout: Some(time::Duration::from_secs(keep_alive)),
+        http2: args.is_present("http2"),
+        tls: load_tls_options(args)?,
+        ..ConnectionOptions::default()
+    };
     let username = args.value_of("username");
     let password = args.value_of("password");
 
@@ -215,34 +463,118 @@ fn run_load_command(args: &ArgMatches) -> Result<(), Box<dyn Error>> {
         Ok(seed) => seed,
         Err(_) => return arg_error("The seed is a number to seed the random number generator."),
     };
-    let mut key_file = File::open(args.value_of("key").unwrap())?;
-
-    let mut buf = String::new();
-    key_file.read_to_string(&mut buf)?;
-    buf.pop(); // remove the new line
+    let connect = args.value_of("connect");
+    if connect.is_some() && args.is_present("commit-latency") {
+        return arg_error("commit-latency is only measured when submitting through the REST Api");
+    }
+    let latency = LatencyTracker::new(
+        args.is_present("commit-latency"),
+        args.value_of("latency-csv"),
+    )?;
+    let private_keys = match args.value_of("key-dir") {
+        Some(dir) => read_private_keys(Path::new(dir))?,
+        None => vec![load_private_key(
+            args.value_of("key").unwrap(),
+            passphrase_from_env,
+        )?],
+    };
+    let selection = match args.value_of("key-weights") {
+        Some(weights) => SignerSelection::parse_weights(weights)?,
+        None => SignerSelection::RoundRobin,
+    };
 
-    let private_key = Secp256k1PrivateKey::from_hex(&buf)?;
     let context = signing::create_context("secp256k1")?;
-    let signer = signing::Signer::new(context.as_ref(), &private_key);
+    let key_fingerprints = private_keys
+        .iter()
+        .map(|key| {
+            context
+                .get_public_key(key)
+                .map(|public_key| key_fingerprint(public_key.as_ref()))
+        })
+        .collect::<Result<Vec<String>, signing::Error>>()?;
+    if let Some(manifest) = replayed {
+        manifest.check_replay("smallbank", &key_fingerprints)?;
+    }
+
+    let signers = SignerPool::new(
+        private_keys
+            .iter()
+            .map(|key| signing::Signer::new(context.as_ref(), key))
+            .collect(),
+        selection,
+        seed,
+    )?;
 
-    let mut transformer = SBPayloadTransformer::new(&signer);
+    let mut transformer = SBPayloadTransformer::new(&signers);
 
     let mut transaction_iterator = SmallbankGeneratingIter::new(accounts, seed)
         .map(|payload| transformer.payload_to_transaction(&payload))
         .map(|item| item.unwrap());
 
-    let mut batch_iter = SignedBatchIterator::new(&mut transaction_iterator, max_txns, &signer);
+    let mut batch_iter = SignedBatchIterator::new(&mut transaction_iterator, max_txns, &signers);
     let mut batchlist_iter = InfiniteBatchListIterator::new(&mut batch_iter);
 
-    let time_to_wait: u32 = 1_000_000_000 / rate as u32;
+    let report = args.value_of("report").map(|json_path| ReportOptions {
+        json_path: json_path.to_string(),
+        html_path: args.value_of("report-html").map(String::from),
+        configuration: vec![
+            ("workload".into(), "smallbank".into()),
+            (
+                "target".into(),
+                connect.map_or_else(|| target.join(","), String::from),
+            ),
+            ("rate".into(), format!("{:?}", rate_schedule)),
+            ("max-batch-size".into(), max_txns.to_string()),
+            ("num-accounts".into(), accounts.to_string()),
+            ("duration".into(), format!("{:?}", duration)),
+            ("max-batches".into(), format!("{:?}", max_batches)),
+            ("max-in-flight".into(), max_in_flight.to_string()),
+            ("seed".into(), seed.to_string()),
+        ],
+    });
 
-    match run_workload(
-        &mut batchlist_iter,
-        time_to_wait,
-        update,
-        target,
-        &basic_auth,
-    ) {
+    if let Some(ref report) = report {
+        let manifest = RunManifest {
+            workload: "smallbank".into(),
+            version: VERSION.into(),
+            seed,
+            key_fingerprints,
+            generated_key: false,
+            arguments: MANIFEST_ARGS
+                .iter()
+                .filter(|&&(name, _)| args.is_present(name))
+                .map(|&(name, long)| (long.to_string(), args.value_of(name).map(String::from)))
+                .collect(),
+        };
+        manifest.write(&manifest_path(&report.json_path))?;
+    }
+
+    let result = match connect {
+        Some(connect) => run_zmq_workload(
+            &mut batchlist_iter,
+            &rate_schedule,
+            &limits,
+            update,
+            connect,
+            connection_options.max_in_flight,
+            latency,
+            report.as_ref(),
+        ),
+        None => run_workload(
+            &mut batchlist_iter,
+            &rate_schedule,
+            &limits,
+            update,
+            target,
+            &basic_auth,
+            &connection_options,
+            latency,
+            None,
+            report.as_ref(),
+        ),
+    };
+
+    match result {
         Ok(_) => Ok(()),
         Err(err) => {
             println!("{}", err);
@@ -308,13 +640,7 @@ fn run_batch_command(args: &ArgMatches) -> Result<(), Box<dyn Error>> {
     let mut in_file = File::open(args.value_of("input").unwrap())?;
     let mut out_file = File::create(args.value_of("output").unwrap())?;
 
-    let mut key_file = File::open(args.value_of("key").unwrap())?;
-
-    let mut buf = String::new();
-    key_file.read_to_string(&mut buf)?;
-    buf.pop(); // remove the new line
-
-    let private_key = Secp256k1PrivateKey::from_hex(&buf)?;
+    let private_key = load_private_key(args.value_of("key").unwrap(), passphrase_from_env)?;
     let context = signing::create_context("secp256k1")?;
 
     if let Err(err) = generate_signed_batches(
@@ -331,7 +657,7 @@ fn run_batch_command(args: &ArgMatches) -> Result<(), Box<dyn Error>> {
 }
 
 fn create_submit_subcommand_args<'a, 'b>() -> App<'a, 'b> {
-    SubCommand::with_name("submit")
+    let app = SubCommand::with_name("submit")
         .about(
             "Submits signed batches to one or more targets from batch input.\n \
              The batch input is expected to be length-delimited protobuf \
@@ -359,6 +685,14 @@ fn create_submit_subcommand_args<'a, 'b>() -> App<'a, 'b> {
                 .value_name("RATE")
                 .help("The number of batches per second to submit to the target"),
         )
+        .arg(
+            Arg::with_name("capture")
+                .long("capture")
+                .value_name("FILE")
+                .help("A file to capture the submitted batches to, for replay"),
+        )
+        .arg(http2_arg());
+    add_tls_args(app)
 }
 
 fn run_submit_command(args: &ArgMatches) -> Result<(), Box<dyn Error>> {
@@ -395,15 +729,135 @@ fn run_submit_command(args: &ArgMatches) -> Result<(), Box<dyn Error>> {
 
     let mut in_file = File::open(args.value_of("input").unwrap())?;
 
+    let capture: Option<Box<dyn Write + Send>> = match args.value_of("capture") {
+        Some(capture) => Some(Box::new(File::create(capture)?)),
+        None => None,
+    };
+
+    let tls = load_tls_options(args)?;
+
     println!("Input: {} Target: {} Rate: {}", input, target, rate);
 
-    if let Err(err) = submit_signed_batches(&mut in_file, target, rate) {
+    if let Err(err) = submit_signed_batches(
+        &mut in_file,
+        target,
+        rate,
+        capture,
+        &tls,
+        args.is_present("http2"),
+    ) {
+        return Err(Box::new(err));
+    }
+
+    Ok(())
+}
+
+fn create_replay_subcommand_args<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("replay")
+        .about(
+            "Replays batches captured by submit --capture to a target.\n \
+             Each batch is resubmitted at the same time from the start of \
+             the replay as it was from the start of the capture.",
+        )
+        .arg(
+            Arg::with_name("input")
+                .short("i")
+                .long("input")
+                .value_name("FILE")
+                .required(true)
+                .help("The captured batches"),
+        )
+        .arg(
+            Arg::with_name("target")
+                .short("t")
+                .long("target")
+                .value_name("TARGET")
+                .help("A Sawtooth REST API endpoint"),
+        )
+}
+
+fn run_replay_command(args: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let target = args.value_of("target").unwrap_or("http://localhost:8008");
+    let input = args.value_of("input").unwrap();
+
+    let mut in_file = File::open(input)?;
+
+    println!("Input: {} Target: {}", input, target);
+
+    if let Err(err) = replay_captured_batches(&mut in_file, target) {
         return Err(Box::new(err));
     }
 
     Ok(())
 }
 
+fn create_verify_subcommand_args<'a, 'b>() -> App<'a, 'b> {
+    let app = SubCommand::with_name("verify")
+        .about(
+            "Verifies the account balances expected by a playlist.\n \
+             Once all of the transactions of a version 2 playlist have been \
+             submitted and committed, the accounts are read from state and \
+             their balances checked against the last balances the playlist \
+             expects of them.",
+        )
+        .arg(
+            Arg::with_name("input")
+                .short("i")
+                .long("input")
+                .value_name("FILE")
+                .required(true)
+                .help("The playlist with the expected balances"),
+        )
+        .arg(
+            Arg::with_name("target")
+                .short("t")
+                .long("target")
+                .value_name("TARGET")
+                .help("A Sawtooth REST API endpoint"),
+        )
+        .arg(
+            Arg::with_name("username")
+                .long("--auth-username")
+                .value_name("AUTH_USERNAME")
+                .help("The basic auth username to authenticate with the Sawtooth REST Api."),
+        )
+        .arg(
+            Arg::with_name("password")
+                .long("--auth-password")
+                .value_name("AUTH_PASSWORD")
+                .help("The basic auth password to authenticate with the Sawtooth REST Api."),
+        );
+    add_tls_args(app)
+}
+
+fn run_verify_command(args: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let target = args.value_of("target").unwrap_or("http://localhost:8008");
+    let mut in_file = File::open(args.value_of("input").unwrap())?;
+
+    let basic_auth = match (args.value_of("username"), args.value_of("password")) {
+        (Some(username), None) => Some(String::from(username)),
+        (Some(username), Some(password)) => Some([username, password].join(":")),
+        (None, _) => None,
+    };
+
+    let tls = load_tls_options(args)?;
+
+    let mismatches = verify_smallbank_playlist(&mut in_file, target, &basic_auth, &tls)?;
+    for mismatch in &mismatches {
+        println!("{}", mismatch);
+    }
+
+    if mismatches.is_empty() {
+        println!("All expected balances were found");
+        Ok(())
+    } else {
+        Err(Box::new(CliError::VerificationError(format!(
+            "{} balances were not as expected",
+            mismatches.len()
+        ))))
+    }
+}
+
 fn create_playlist_subcommand_args<'a, 'b>() -> App<'a, 'b> {
     SubCommand::with_name("playlist")
         .subcommand(create_playlist_create_subcommand_args())
@@ -451,6 +905,15 @@ fn create_playlist_create_subcommand_args<'a, 'b>() -> App<'a, 'b> {
                      addition to the created accounts",
                 ),
         )
+        .arg(
+            Arg::with_name("expectations")
+                .short("e")
+                .long("expectations")
+                .help(
+                    "Write a version 2 playlist, with the account balances \
+                     expected after each transaction, for verify",
+                ),
+        )
 }
 
 fn create_playlist_process_subcommand_args<'a, 'b>() -> App<'a, 'b> {
@@ -531,6 +994,7 @@ fn run_playlist_create_command(args: &ArgMatches) -> Result<(), Box<dyn Error>>
         num_accounts,
         num_transactions,
         random_seed,
+        args.is_present("expectations"),
     )?;
 
     Ok(())
@@ -544,14 +1008,8 @@ fn run_playlist_process_command(args: &ArgMatches) -> Result<(), Box<dyn Error>>
         None => Box::new(std::io::stdout()),
     };
 
-    let mut key_file = File::open(args.value_of("key").unwrap())?;
-
-    let mut buf = String::new();
-    key_file.read_to_string(&mut buf)?;
-    buf.pop(); // remove the new line
-
     let context = signing::create_context("secp256k1")?;
-    let private_key = Secp256k1PrivateKey::from_hex(&buf)?;
+    let private_key = load_private_key(args.value_of("key").unwrap(), passphrase_from_env)?;
 
     process_smallbank_playlist(
         &mut output_writer,
@@ -566,12 +1024,14 @@ fn run_playlist_process_command(args: &ArgMatches) -> Result<(), Box<dyn Error>>
 #[derive(Debug)]
 enum CliError {
     ArgumentError(String),
+    VerificationError(String),
 }
 
 impl std::fmt::Display for CliError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match *self {
             CliError::ArgumentError(ref msg) => write!(f, "ArgumentError: {}", msg),
+            CliError::VerificationError(ref msg) => write!(f, "VerificationError: {}", msg),
         }
     }
 }
@@ -580,6 +1040,7 @@ impl std::error::Error for CliError {
     fn cause(&self) -> Option<&dyn Error> {
         match *self {
             CliError::ArgumentError(_) => None,
+            CliError::VerificationError(_) => None,
         }
     }
 }
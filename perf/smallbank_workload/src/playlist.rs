@@ -16,12 +16,29 @@
  */
 
 //! Tools for generating YAML playlists of transactions.
+//!
+//! A playlist is either a list of transactions (version 1), or a map with
+//! `version: 2` whose `transactions` may each list under `expect` the
+//! balances accounts are expected to have once the transaction is applied,
+//! which `verify_smallbank_playlist` checks against state:
+//!
+//! ```yaml
+//! version: 2
+//! transactions:
+//!   - transaction_type: deposit_checking
+//!     customer_id: 1
+//!     amount: 100
+//!     expect:
+//!       - customer_id: 1
+//!         checking_balance: 1000100
+//! ```
 
 extern crate crypto;
 extern crate rand;
 extern crate yaml_rust;
 
 use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
 use std::error;
 use std::fmt;
 use std::io::Error as StdIoError;
@@ -37,6 +54,7 @@ use self::yaml_rust::YamlLoader;
 use rand::prelude::*;
 
 use protos::smallbank;
+use protos::smallbank::Account;
 use protos::smallbank::SmallbankTransactionPayload;
 use protos::smallbank::SmallbankTransactionPayload_PayloadType as SBPayloadType;
 
@@ -47,6 +65,9 @@ use sawtooth_sdk::messages::transaction::Transaction;
 use sawtooth_sdk::messages::transaction::TransactionHeader;
 use sawtooth_sdk::signing;
 
+use sawtooth_perf::state::{read_state_entries, StateReadError};
+use sawtooth_perf::tls::TlsOptions;
+
 use self::crypto::digest::Digest;
 use self::crypto::sha2::Sha512;
 
@@ -67,21 +88,39 @@ macro_rules! yaml_map(
 /// `num_accounts` CREATE_ACCOUNT transactions, followed by `num_transactions`
 /// additional transactions (deposits, transfers, etc).
 ///
-/// A random seed may be provided to create repeatable, random output.
+/// A random seed may be provided to create repeatable, random output. If
+/// `with_expectations` is set, a version 2 playlist is written, with the
+/// balances expected after each transaction.
 pub fn generate_smallbank_playlist(
     output: &mut dyn Write,
     num_accounts: usize,
     num_transactions: usize,
     seed: Option<i32>,
+    with_expectations: bool,
 ) -> Result<(), PlaylistError> {
     let mut fmt_writer = FmtWriter::new(output);
     let mut emitter = YamlEmitter::new(&mut fmt_writer);
 
-    let txn_array: Vec<Yaml> = create_smallbank_playlist(num_accounts, num_transactions, seed)
-        .map(Yaml::from)
-        .collect();
+    let payloads = create_smallbank_playlist(num_accounts, num_transactions, seed);
+    let final_yaml = if with_expectations {
+        let mut balances = BalanceModel::default();
+        let txn_array: Vec<Yaml> = payloads
+            .map(|payload| {
+                let expectations = balances.apply(&payload);
+                Yaml::from(PlaylistEntry {
+                    payload,
+                    expectations,
+                })
+            })
+            .collect();
+        yaml_map! {
+            "version" => Yaml::Integer(2),
+            "transactions" => Yaml::Array(txn_array)
+        }
+    } else {
+        Yaml::Array(payloads.map(Yaml::from).collect())
+    };
 
-    let final_yaml = Yaml::Array(txn_array);
     emitter
         .dump(&final_yaml)
         .map_err(PlaylistError::YamlOutputError)?;
@@ -92,8 +131,9 @@ pub fn generate_smallbank_playlist(
 /// Created signed Smallbank transactions from a given playlist.
 ///
 /// The playlist input is expected to be the same Yaml format as generated by
-/// the `generate_smallbank_playlist` function.  All transactions will be
-/// signed with the given `PrivateKey` instance.
+/// the `generate_smallbank_playlist` function, of either version; the
+/// expected balances of a version 2 playlist are not needed to process it.
+/// All transactions will be signed with the given `PrivateKey` instance.
 pub fn process_smallbank_playlist(
     output: &mut dyn Write,
     playlist_input: &mut dyn Read,
@@ -216,14 +256,307 @@ pub fn create_smallbank_playlist(
 pub fn read_smallbank_playlist(
     input: &mut dyn Read,
 ) -> Result<Vec<SmallbankTransactionPayload>, PlaylistError> {
-    let mut results = Vec::new();
+    Ok(read_smallbank_playlist_entries(input)?
+        .into_iter()
+        .map(|entry| entry.payload)
+        .collect())
+}
+
+/// Reads the transactions of a playlist with their expected balances, which
+/// are empty for a version 1 playlist.
+pub fn read_smallbank_playlist_entries(
+    input: &mut dyn Read,
+) -> Result<Vec<PlaylistEntry>, PlaylistError> {
     let buf = read_yaml(input)?;
     let yaml_array = load_yaml_array(&buf)?;
-    for yaml in yaml_array.iter() {
-        results.push(SmallbankTransactionPayload::from(yaml));
+    yaml_array
+        .iter()
+        .map(|yaml| {
+            Ok(PlaylistEntry {
+                payload: SmallbankTransactionPayload::from(yaml),
+                expectations: read_expectations(yaml)?,
+            })
+        })
+        .collect()
+}
+
+/// Checks the balances a playlist expects against the accounts in state at
+/// `target`, once all of its transactions have been committed. As the
+/// transactions may have been committed in any order, only the last balances
+/// expected of each account are checked.
+///
+/// Returns a description of each balance which is not as expected.
+pub fn verify_smallbank_playlist(
+    playlist_input: &mut dyn Read,
+    target: &str,
+    basic_auth: &Option<String>,
+    tls: &TlsOptions,
+) -> Result<Vec<String>, PlaylistError> {
+    let expectations = final_expectations(&read_smallbank_playlist_entries(playlist_input)?);
+    let addresses: Vec<String> = expectations
+        .iter()
+        .map(|expectation| customer_id_address(expectation.customer_id))
+        .collect();
+    let entries = read_state_entries(target, &addresses, basic_auth, tls)
+        .map_err(PlaylistError::StateReadError)?;
+
+    let mut mismatches = Vec::new();
+    for (expectation, address) in expectations.iter().zip(addresses.iter()) {
+        let account: Account = match entries.get(address) {
+            Some(data) => protobuf::parse_from_bytes(data).map_err(PlaylistError::MessageError)?,
+            None => {
+                mismatches.push(format!("Account {} was not found", expectation.customer_id));
+                continue;
+            }
+        };
+        mismatches.extend(expectation.check(&account));
+    }
+
+    Ok(mismatches)
+}
+
+/// Returns the last balances expected of each account, by customer id.
+fn final_expectations(entries: &[PlaylistEntry]) -> Vec<AccountExpectation> {
+    let mut expected: BTreeMap<u32, AccountExpectation> = BTreeMap::new();
+    for expectation in entries.iter().flat_map(|entry| entry.expectations.iter()) {
+        let last = expected
+            .entry(expectation.customer_id)
+            .or_insert_with(|| AccountExpectation {
+                customer_id: expectation.customer_id,
+                checking_balance: None,
+                savings_balance: None,
+            });
+        if expectation.checking_balance.is_some() {
+            last.checking_balance = expectation.checking_balance;
+        }
+        if expectation.savings_balance.is_some() {
+            last.savings_balance = expectation.savings_balance;
+        }
+    }
+    expected
+        .into_iter()
+        .map(|(_, expectation)| expectation)
+        .collect()
+}
+
+/// A transaction of a playlist, with the balances expected once it is
+/// applied.
+#[derive(Debug, PartialEq)]
+pub struct PlaylistEntry {
+    pub payload: SmallbankTransactionPayload,
+    pub expectations: Vec<AccountExpectation>,
+}
+
+/// The balances an account is expected to have. A balance which is not given
+/// is not checked.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AccountExpectation {
+    pub customer_id: u32,
+    pub checking_balance: Option<u32>,
+    pub savings_balance: Option<u32>,
+}
+
+impl AccountExpectation {
+    /// Returns a description of each balance of `account` which is not as
+    /// expected.
+    fn check(&self, account: &Account) -> Vec<String> {
+        let mut mismatches = Vec::new();
+        let balances = [
+            (
+                "checking",
+                self.checking_balance,
+                account.get_checking_balance(),
+            ),
+            (
+                "savings",
+                self.savings_balance,
+                account.get_savings_balance(),
+            ),
+        ];
+        for &(name, expected, actual) in &balances {
+            match expected {
+                Some(expected) if expected != actual => mismatches.push(format!(
+                    "Account {} has a {} balance of {}, but {} was expected",
+                    self.customer_id, name, actual, expected
+                )),
+                _ => (),
+            }
+        }
+        mismatches
+    }
+}
+
+impl From<PlaylistEntry> for Yaml {
+    fn from(entry: PlaylistEntry) -> Self {
+        let mut yaml = Yaml::from(entry.payload);
+        if let Yaml::Hash(ref mut txn_hash) = yaml {
+            if !entry.expectations.is_empty() {
+                let expectations = entry
+                    .expectations
+                    .iter()
+                    .map(|expectation| {
+                        let mut m = Hash::new();
+                        m.insert(
+                            Yaml::from_str("customer_id"),
+                            Yaml::Integer(i64::from(expectation.customer_id)),
+                        );
+                        if let Some(balance) = expectation.checking_balance {
+                            m.insert(
+                                Yaml::from_str("checking_balance"),
+                                Yaml::Integer(i64::from(balance)),
+                            );
+                        }
+                        if let Some(balance) = expectation.savings_balance {
+                            m.insert(
+                                Yaml::from_str("savings_balance"),
+                                Yaml::Integer(i64::from(balance)),
+                            );
+                        }
+                        Yaml::Hash(m)
+                    })
+                    .collect();
+                txn_hash.insert(Yaml::from_str("expect"), Yaml::Array(expectations));
+            }
+        }
+        yaml
     }
+}
+
+/// Reads the balances expected of a playlist transaction, if any.
+fn read_expectations(yaml: &Yaml) -> Result<Vec<AccountExpectation>, PlaylistError> {
+    let expectations = match yaml["expect"] {
+        Yaml::BadValue => return Ok(vec![]),
+        Yaml::Array(ref expectations) => expectations,
+        _ => {
+            return Err(PlaylistError::InvalidPlaylist(
+                "expect must be a list".into(),
+            ))
+        }
+    };
+
+    let read_balance = |yaml: &Yaml, name: &str| match yaml[name] {
+        Yaml::BadValue => Ok(None),
+        Yaml::Integer(balance) if balance >= 0 && balance <= i64::from(u32::max_value()) => {
+            Ok(Some(balance as u32))
+        }
+        _ => Err(PlaylistError::InvalidPlaylist(format!(
+            "{} must be a balance",
+            name
+        ))),
+    };
+
+    expectations
+        .iter()
+        .map(|expectation| {
+            let customer_id = match expectation["customer_id"].as_i64() {
+                Some(customer_id) if customer_id >= 0 => customer_id as u32,
+                _ => {
+                    return Err(PlaylistError::InvalidPlaylist(
+                        "An expectation must have a customer_id".into(),
+                    ))
+                }
+            };
+            Ok(AccountExpectation {
+                customer_id,
+                checking_balance: read_balance(expectation, "checking_balance")?,
+                savings_balance: read_balance(expectation, "savings_balance")?,
+            })
+        })
+        .collect()
+}
+
+/// The balances of the accounts of a playlist, as the Smallbank transaction
+/// processor applies its transactions.
+#[derive(Default)]
+struct BalanceModel {
+    // The checking and savings balances of each account, by customer id
+    accounts: HashMap<u32, (u32, u32)>,
+}
 
-    Ok(results)
+impl BalanceModel {
+    /// Applies a transaction, unless the transaction processor would find it
+    /// invalid, and returns the balances of the accounts it uses.
+    fn apply(&mut self, payload: &SmallbankTransactionPayload) -> Vec<AccountExpectation> {
+        let customer_ids = match payload.get_payload_type() {
+            SBPayloadType::CREATE_ACCOUNT => {
+                let data = payload.get_create_account();
+                self.accounts.entry(data.get_customer_id()).or_insert((
+                    data.get_initial_checking_balance(),
+                    data.get_initial_savings_balance(),
+                ));
+                vec![data.get_customer_id()]
+            }
+            SBPayloadType::DEPOSIT_CHECKING => {
+                let data = payload.get_deposit_checking();
+                if let Some(account) = self.accounts.get_mut(&data.get_customer_id()) {
+                    account.0 = account.0.wrapping_add(data.get_amount());
+                }
+                vec![data.get_customer_id()]
+            }
+            SBPayloadType::WRITE_CHECK => {
+                let data = payload.get_write_check();
+                if let Some(account) = self.accounts.get_mut(&data.get_customer_id()) {
+                    account.0 = account.0.wrapping_sub(data.get_amount());
+                }
+                vec![data.get_customer_id()]
+            }
+            SBPayloadType::TRANSACT_SAVINGS => {
+                let data = payload.get_transact_savings();
+                if let Some(account) = self.accounts.get_mut(&data.get_customer_id()) {
+                    let amount = data.get_amount();
+                    if amount >= 0 {
+                        account.1 = account.1.wrapping_add(amount as u32);
+                    } else if amount.wrapping_neg() as u32 <= account.1 {
+                        account.1 -= amount.wrapping_neg() as u32;
+                    }
+                }
+                vec![data.get_customer_id()]
+            }
+            SBPayloadType::SEND_PAYMENT => {
+                let data = payload.get_send_payment();
+                let (source_id, dest_id) =
+                    (data.get_source_customer_id(), data.get_dest_customer_id());
+                if let (Some(&source), Some(&dest)) =
+                    (self.accounts.get(&source_id), self.accounts.get(&dest_id))
+                {
+                    if source.0 >= data.get_amount() {
+                        self.accounts
+                            .insert(source_id, (source.0 - data.get_amount(), source.1));
+                        self.accounts
+                            .insert(dest_id, (dest.0.wrapping_add(data.get_amount()), dest.1));
+                    }
+                }
+                vec![source_id, dest_id]
+            }
+            SBPayloadType::AMALGAMATE => {
+                let data = payload.get_amalgamate();
+                let (source_id, dest_id) =
+                    (data.get_source_customer_id(), data.get_dest_customer_id());
+                if let (Some(&source), Some(&dest)) =
+                    (self.accounts.get(&source_id), self.accounts.get(&dest_id))
+                {
+                    self.accounts.insert(source_id, (source.0, 0));
+                    self.accounts
+                        .insert(dest_id, (dest.0.wrapping_add(source.1), dest.1));
+                }
+                vec![source_id, dest_id]
+            }
+            SBPayloadType::PAYLOAD_TYPE_UNSET => vec![],
+        };
+
+        customer_ids
+            .into_iter()
+            .filter_map(|customer_id| {
+                self.accounts
+                    .get(&customer_id)
+                    .map(|&(checking, savings)| AccountExpectation {
+                        customer_id,
+                        checking_balance: Some(checking),
+                        savings_balance: Some(savings),
+                    })
+            })
+            .collect()
+    }
 }
 
 fn read_yaml(input: &mut dyn Read) -> Result<Cow<str>, PlaylistError> {
@@ -234,10 +567,28 @@ fn read_yaml(input: &mut dyn Read) -> Result<Cow<str>, PlaylistError> {
     Ok(buf.into())
 }
 
+/// Returns the transactions of a playlist of either version.
 fn load_yaml_array(yaml_str: &str) -> Result<Cow<Vec<Yaml>>, PlaylistError> {
     let mut yaml = YamlLoader::load_from_str(yaml_str).map_err(PlaylistError::YamlInputError)?;
     let element = yaml.remove(0);
-    let yaml_array = element.as_vec().cloned().unwrap().clone();
+    let yaml_array = match element {
+        Yaml::Array(ref yaml_array) => yaml_array.clone(),
+        Yaml::Hash(_) => match element["version"].as_i64() {
+            Some(2) => element["transactions"].as_vec().cloned().ok_or_else(|| {
+                PlaylistError::InvalidPlaylist("transactions must be a list".into())
+            })?,
+            _ => {
+                return Err(PlaylistError::InvalidPlaylist(
+                    "Only versions 1 and 2 are supported".into(),
+                ))
+            }
+        },
+        _ => {
+            return Err(PlaylistError::InvalidPlaylist(
+                "A playlist must be a list or a map".into(),
+            ))
+        }
+    };
 
     Ok(Cow::Owned(yaml_array))
 }
@@ -568,6 +919,8 @@ pub enum PlaylistError {
     YamlInputError(yaml_rust::ScanError),
     MessageError(protobuf::ProtobufError),
     SigningError(signing::Error),
+    StateReadError(StateReadError),
+    InvalidPlaylist(String),
 }
 
 impl fmt::Display for PlaylistError {
@@ -584,6 +937,10 @@ impl fmt::Display for PlaylistError {
             PlaylistError::SigningError(ref err) => {
                 write!(f, "Error occurred signing transactions: {}", err)
             }
+            PlaylistError::StateReadError(ref err) => {
+                write!(f, "Error occurred reading accounts: {}", err)
+            }
+            PlaylistError::InvalidPlaylist(ref msg) => write!(f, "Invalid playlist: {}", msg),
         }
     }
 }
@@ -596,6 +953,8 @@ impl error::Error for PlaylistError {
             PlaylistError::YamlInputError(_) => None,
             PlaylistError::MessageError(ref err) => Some(err),
             PlaylistError::SigningError(ref err) => Some(err),
+            PlaylistError::StateReadError(ref err) => Some(err),
+            PlaylistError::InvalidPlaylist(_) => None,
         }
     }
 }
@@ -625,3 +984,125 @@ pub fn bytes_to_hex_str(b: &[u8]) -> String {
         .collect::<Vec<_>>()
         .join("")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAYLIST_V2: &str = "
+version: 2
+transactions:
+  - transaction_type: create_account
+    customer_id: 0
+    customer_name: alice
+    initial_savings_balance: 100
+    initial_checking_balance: 200
+  - transaction_type: deposit_checking
+    customer_id: 0
+    amount: 50
+    expect:
+      - customer_id: 0
+        checking_balance: 250
+  - transaction_type: transact_savings
+    customer_id: 0
+    amount: -10
+    expect:
+      - customer_id: 0
+        savings_balance: 90
+";
+
+    #[test]
+    fn test_read_v2_playlist() {
+        let entries = read_smallbank_playlist_entries(&mut PLAYLIST_V2.as_bytes()).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert!(entries[0].expectations.is_empty());
+        assert_eq!(
+            entries[1].expectations,
+            vec![AccountExpectation {
+                customer_id: 0,
+                checking_balance: Some(250),
+                savings_balance: None,
+            }]
+        );
+
+        assert_eq!(
+            final_expectations(&entries),
+            vec![AccountExpectation {
+                customer_id: 0,
+                checking_balance: Some(250),
+                savings_balance: Some(90),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_generated_expectations() {
+        let mut playlist = Vec::new();
+        generate_smallbank_playlist(&mut playlist, 3, 20, Some(7), true).unwrap();
+
+        let entries = read_smallbank_playlist_entries(&mut playlist.as_slice()).unwrap();
+        assert_eq!(entries.len(), 20);
+
+        // The expectations written are those of the balances as modelled
+        let mut balances = BalanceModel::default();
+        for entry in &entries {
+            assert_eq!(entry.expectations, balances.apply(&entry.payload));
+            assert!(!entry.expectations.is_empty());
+        }
+
+        // A version 1 playlist is still read, without expectations
+        let mut playlist = Vec::new();
+        generate_smallbank_playlist(&mut playlist, 3, 20, Some(7), false).unwrap();
+        let v1_entries = read_smallbank_playlist_entries(&mut playlist.as_slice()).unwrap();
+        assert_eq!(
+            v1_entries
+                .iter()
+                .map(|entry| &entry.payload)
+                .collect::<Vec<_>>(),
+            entries
+                .iter()
+                .map(|entry| &entry.payload)
+                .collect::<Vec<_>>()
+        );
+        assert!(v1_entries.iter().all(|entry| entry.expectations.is_empty()));
+    }
+
+    #[test]
+    fn test_model_rejects_insufficient_funds() {
+        let entries = read_smallbank_playlist_entries(&mut PLAYLIST_V2.as_bytes()).unwrap();
+        let mut balances = BalanceModel::default();
+        balances.apply(&entries[0].payload);
+
+        let mut payload = SmallbankTransactionPayload::new();
+        payload.set_payload_type(SBPayloadType::TRANSACT_SAVINGS);
+        let mut data = smallbank::SmallbankTransactionPayload_TransactSavingsTransactionData::new();
+        data.set_customer_id(0);
+        data.set_amount(-101);
+        payload.set_transact_savings(data);
+
+        assert_eq!(
+            balances.apply(&payload),
+            vec![AccountExpectation {
+                customer_id: 0,
+                checking_balance: Some(200),
+                savings_balance: Some(100),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_check_account() {
+        let expectation = AccountExpectation {
+            customer_id: 1,
+            checking_balance: Some(10),
+            savings_balance: None,
+        };
+        let mut account = Account::new();
+        account.set_checking_balance(10);
+        account.set_savings_balance(5);
+        assert!(expectation.check(&account).is_empty());
+
+        account.set_checking_balance(11);
+        assert_eq!(expectation.check(&account).len(), 1);
+    }
+}
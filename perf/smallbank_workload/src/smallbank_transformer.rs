@@ -26,8 +26,8 @@ use crypto::sha2::Sha512;
 use protobuf;
 use protobuf::Message;
 
+use sawtooth_perf::signer_pool::SignerPool;
 use sawtooth_sdk::messages::transaction::{Transaction, TransactionHeader};
-use sawtooth_sdk::signing;
 
 use playlist::bytes_to_hex_str;
 use playlist::make_addresses;
@@ -35,14 +35,14 @@ use protos::smallbank::{SmallbankTransactionPayload, SmallbankTransactionPayload
 
 /// Transforms SmallbankTransactionPayloads into Sawtooth Transactions.
 pub struct SBPayloadTransformer<'a> {
-    signer: &'a signing::Signer<'a>,
+    signers: &'a SignerPool<'a>,
     dependencies: SignatureTracker<u32>,
 }
 
 impl<'a> SBPayloadTransformer<'a> {
-    pub fn new(signer: &'a signing::Signer) -> Self {
+    pub fn new(signers: &'a SignerPool<'a>) -> Self {
         SBPayloadTransformer {
-            signer,
+            signers,
             dependencies: SignatureTracker::new(),
         }
     }
@@ -121,12 +121,12 @@ impl<'a> SBPayloadTransformer<'a> {
         sha.result(hash);
 
         txn_header.set_payload_sha512(bytes_to_hex_str(hash));
-        txn_header.set_signer_public_key(self.signer.get_public_key()?.as_hex());
-        txn_header.set_batcher_public_key(self.signer.get_public_key()?.as_hex());
+        txn_header.set_signer_public_key(self.signers.current().get_public_key()?.as_hex());
+        txn_header.set_batcher_public_key(self.signers.current().get_public_key()?.as_hex());
 
         let header_bytes = txn_header.write_to_bytes()?;
 
-        let signature = self.signer.sign(&header_bytes.to_vec())?;
+        let signature = self.signers.current().sign(&header_bytes.to_vec())?;
         self.add_signature_if_create_account(&payload, signature.clone());
 
         txn.set_header(header_bytes);
@@ -168,6 +168,7 @@ mod tests {
     use super::SBPayloadTransformer;
 
     use protobuf::Message;
+    use sawtooth_perf::signer_pool::SignerPool;
     use sawtooth_sdk::messages::transaction::TransactionHeader;
     use sawtooth_sdk::signing;
 
@@ -185,8 +186,9 @@ mod tests {
         let context = signing::create_context("secp256k1").unwrap();
         let private_key = context.new_random_private_key().unwrap();
         let signer = signing::Signer::new(context.as_ref(), private_key.as_ref());
+        let signers = SignerPool::single(signer);
 
-        let mut transformer = SBPayloadTransformer::new(&signer);
+        let mut transformer = SBPayloadTransformer::new(&signers);
 
         let mut transaction_iterator = payload_iterator
             .map(|payload| transformer.payload_to_transaction(&payload))
@@ -0,0 +1,278 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+//! The REST API configuration, read from the same `rest_api.toml` file as
+//! the Python REST API, with additional CORS, TLS and basic auth settings.
+
+use std::env;
+use std::error;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const VALID_KEYS: &[&str] = &[
+    "bind",
+    "connect",
+    "timeout",
+    "client_max_size",
+    "cors_allowed_origins",
+    "tls_cert",
+    "tls_key",
+    "basic_auth",
+    // Metrics are not reported by this REST API, but the keys are accepted
+    // so that configuration files can be shared with the Python REST API
+    "opentsdb_url",
+    "opentsdb_db",
+    "opentsdb_username",
+    "opentsdb_password",
+];
+
+#[derive(Debug)]
+pub struct RestApiConfigError(pub String);
+
+impl fmt::Display for RestApiConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl error::Error for RestApiConfigError {}
+
+/// A set of configuration values, any of which may be unset. Configurations
+/// from several sources are combined with `merge`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RestApiConfig {
+    pub bind: Option<Vec<String>>,
+    pub connect: Option<String>,
+    /// Seconds to wait for a validator response
+    pub timeout: Option<u64>,
+    /// The largest request body accepted, in bytes
+    pub client_max_size: Option<usize>,
+    /// The origins allowed to make cross-origin requests; "*" allows any
+    pub cors_allowed_origins: Option<Vec<String>>,
+    /// The PEM encoded certificate to serve HTTPS with, if tls_key is set
+    pub tls_cert: Option<String>,
+    /// The PEM encoded private key of tls_cert
+    pub tls_key: Option<String>,
+    /// The "username:password" clients must authenticate with
+    pub basic_auth: Option<String>,
+}
+
+impl RestApiConfig {
+    pub fn default_config() -> RestApiConfig {
+        RestApiConfig {
+            bind: Some(vec!["127.0.0.1:8008".into()]),
+            connect: Some("tcp://localhost:4004".into()),
+            timeout: Some(300),
+            client_max_size: Some(10_485_760),
+            cors_allowed_origins: Some(vec![]),
+            tls_cert: None,
+            tls_key: None,
+            basic_auth: None,
+        }
+    }
+
+    /// Merges the configs into a single config, giving priority in the order
+    /// of the configs (first has highest priority).
+    pub fn merge(configs: &[RestApiConfig]) -> RestApiConfig {
+        let mut merged = RestApiConfig::default();
+
+        for config in configs.iter().rev() {
+            macro_rules! merge_field {
+                ($field:ident) => {
+                    if config.$field.is_some() {
+                        merged.$field = config.$field.clone();
+                    }
+                };
+            }
+
+            merge_field!(bind);
+            merge_field!(connect);
+            merge_field!(timeout);
+            merge_field!(client_max_size);
+            merge_field!(cors_allowed_origins);
+            merge_field!(tls_cert);
+            merge_field!(tls_key);
+            merge_field!(basic_auth);
+        }
+
+        merged
+    }
+
+    /// Loads a config from a TOML file. A file which does not exist gives an
+    /// empty config.
+    pub fn from_toml_file(path: &Path) -> Result<RestApiConfig, RestApiConfigError> {
+        if !path.exists() {
+            info!(
+                "Skipping rest api loading from non-existent config file: {}",
+                path.display()
+            );
+            return Ok(RestApiConfig::default());
+        }
+
+        info!(
+            "Loading rest api information from config: {}",
+            path.display()
+        );
+
+        let raw_config = fs::read_to_string(path).map_err(|err| {
+            RestApiConfigError(format!(
+                "Unable to load rest api configuration file: {}",
+                err
+            ))
+        })?;
+
+        RestApiConfig::from_toml(&raw_config)
+    }
+
+    pub fn from_toml(raw_config: &str) -> Result<RestApiConfig, RestApiConfigError> {
+        let toml_config: toml::value::Table = toml::from_str(raw_config).map_err(|err| {
+            RestApiConfigError(format!(
+                "Unable to parse rest api configuration file: {}",
+                err
+            ))
+        })?;
+
+        let mut invalid_keys: Vec<&str> = toml_config
+            .keys()
+            .map(String::as_str)
+            .filter(|key| !VALID_KEYS.contains(key))
+            .collect();
+        if !invalid_keys.is_empty() {
+            invalid_keys.sort();
+            return Err(RestApiConfigError(format!(
+                "Invalid keys in rest api config: {}",
+                invalid_keys.join(", ")
+            )));
+        }
+
+        Ok(RestApiConfig {
+            bind: get_string_list(&toml_config, "bind")?,
+            connect: get_string(&toml_config, "connect")?,
+            timeout: get_integer(&toml_config, "timeout")?.map(|timeout| timeout as u64),
+            client_max_size: get_integer(&toml_config, "client_max_size")?
+                .map(|size| size as usize),
+            cors_allowed_origins: get_string_list(&toml_config, "cors_allowed_origins")?,
+            tls_cert: get_string(&toml_config, "tls_cert")?,
+            tls_key: get_string(&toml_config, "tls_key")?,
+            basic_auth: get_string(&toml_config, "basic_auth")?,
+        })
+    }
+}
+
+/// Returns the directory the configuration files are read from:
+/// $SAWTOOTH_HOME/etc if SAWTOOTH_HOME is set, or /etc/sawtooth otherwise.
+pub fn get_config_dir() -> PathBuf {
+    match env::var("SAWTOOTH_HOME") {
+        Ok(home) => Path::new(&home).join("etc"),
+        Err(_) => PathBuf::from("/etc/sawtooth"),
+    }
+}
+
+fn get_string(table: &toml::value::Table, key: &str) -> Result<Option<String>, RestApiConfigError> {
+    match table.get(key) {
+        None => Ok(None),
+        Some(toml::Value::String(value)) => Ok(Some(value.clone())),
+        Some(_) => Err(RestApiConfigError(format!("{} must be a string", key))),
+    }
+}
+
+fn get_integer(table: &toml::value::Table, key: &str) -> Result<Option<i64>, RestApiConfigError> {
+    match table.get(key) {
+        None => Ok(None),
+        Some(toml::Value::Integer(value)) if *value >= 0 => Ok(Some(*value)),
+        Some(_) => Err(RestApiConfigError(format!(
+            "{} must be a non-negative integer",
+            key
+        ))),
+    }
+}
+
+fn get_string_list(
+    table: &toml::value::Table,
+    key: &str,
+) -> Result<Option<Vec<String>>, RestApiConfigError> {
+    let invalid = || RestApiConfigError(format!("{} must be a list of strings", key));
+
+    match table.get(key) {
+        None => Ok(None),
+        Some(toml::Value::Array(values)) => values
+            .iter()
+            .map(|value| value.as_str().map(String::from).ok_or_else(invalid))
+            .collect::<Result<Vec<String>, RestApiConfigError>>()
+            .map(Some),
+        Some(_) => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_toml() {
+        let config = RestApiConfig::from_toml(
+            r#"
+            bind = ["0.0.0.0:8008"]
+            connect = "tcp://validator:4004"
+            timeout = 30
+            cors_allowed_origins = ["https://example.com"]
+            basic_auth = "sawtooth:secret"
+            opentsdb_db = "metrics"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.bind, Some(vec!["0.0.0.0:8008".to_string()]));
+        assert_eq!(config.connect, Some("tcp://validator:4004".to_string()));
+        assert_eq!(config.timeout, Some(30));
+        assert_eq!(config.client_max_size, None);
+        assert_eq!(
+            config.cors_allowed_origins,
+            Some(vec!["https://example.com".to_string()])
+        );
+        assert_eq!(config.basic_auth, Some("sawtooth:secret".to_string()));
+    }
+
+    #[test]
+    fn test_load_toml_invalid_keys() {
+        let err = RestApiConfig::from_toml("bind = []\nport = 8008\nhost = \"x\"").unwrap_err();
+        assert_eq!(err.0, "Invalid keys in rest api config: host, port");
+
+        assert!(RestApiConfig::from_toml("timeout = \"30\"").is_err());
+    }
+
+    #[test]
+    fn test_merge() {
+        let cli = RestApiConfig {
+            timeout: Some(10),
+            ..RestApiConfig::default()
+        };
+        let toml = RestApiConfig {
+            timeout: Some(20),
+            connect: Some("tcp://validator:4004".into()),
+            ..RestApiConfig::default()
+        };
+
+        let merged = RestApiConfig::merge(&[cli, toml, RestApiConfig::default_config()]);
+
+        assert_eq!(merged.timeout, Some(10));
+        assert_eq!(merged.connect, Some("tcp://validator:4004".to_string()));
+        assert_eq!(merged.bind, Some(vec!["127.0.0.1:8008".to_string()]));
+        assert_eq!(merged.tls_cert, None);
+    }
+}
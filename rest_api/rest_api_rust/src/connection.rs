@@ -0,0 +1,189 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+//! The connection to the validator's client endpoint.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use protobuf::Message as M;
+use sawtooth_sdk::messages::network::PingResponse;
+use sawtooth_sdk::messages::validator::{Message, Message_MessageType};
+use sawtooth_sdk::messaging::stream::{MessageConnection, MessageSender, ReceiveError, SendError};
+use sawtooth_sdk::messaging::zmq_stream::{ZmqMessageConnection, ZmqMessageSender};
+
+use crate::error::ApiError;
+
+/// Sends requests to a validator, returning the content of its responses.
+pub trait ValidatorConnection: Send + Sync {
+    fn send(
+        &self,
+        message_type: Message_MessageType,
+        content: &[u8],
+        timeout: Duration,
+    ) -> Result<Vec<u8>, ApiError>;
+}
+
+/// A connection to a validator over ZMQ, which may be shared by the threads
+/// serving requests.
+pub struct ZmqValidatorConnection {
+    sender: Mutex<ZmqMessageSender>,
+    next_id: AtomicUsize,
+}
+
+impl ZmqValidatorConnection {
+    /// Connects to the validator at url, e.g. "tcp://localhost:4004".
+    /// Unsolicited messages from the validator are handled by a background
+    /// thread, which answers its pings.
+    pub fn new(url: &str) -> ZmqValidatorConnection {
+        let connection = ZmqMessageConnection::new(url);
+        let (sender, receiver) = connection.create();
+
+        let ping_sender = sender.clone();
+        thread::spawn(move || {
+            for message in receiver {
+                let message = match message {
+                    Ok(message) => message,
+                    Err(ReceiveError::DisconnectedError) => {
+                        warn!("Validator disconnected");
+                        continue;
+                    }
+                    Err(err) => {
+                        debug!("Unable to receive message from validator: {:?}", err);
+                        continue;
+                    }
+                };
+
+                if message.get_message_type() == Message_MessageType::PING_REQUEST {
+                    answer_ping(&ping_sender, &message);
+                } else {
+                    debug!(
+                        "Ignoring unsolicited {:?} message from validator",
+                        message.get_message_type()
+                    );
+                }
+            }
+        });
+
+        ZmqValidatorConnection {
+            sender: Mutex::new(sender),
+            next_id: AtomicUsize::new(0),
+        }
+    }
+
+    fn generate_correlation_id(&self) -> String {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.subsec_nanos())
+            .unwrap_or(0);
+        format!(
+            "rest-api-{:x}-{}",
+            nanos,
+            self.next_id.fetch_add(1, Ordering::SeqCst)
+        )
+    }
+}
+
+impl ValidatorConnection for ZmqValidatorConnection {
+    fn send(
+        &self,
+        message_type: Message_MessageType,
+        content: &[u8],
+        timeout: Duration,
+    ) -> Result<Vec<u8>, ApiError> {
+        let sender = self
+            .sender
+            .lock()
+            .map_err(|_| {
+                error!("The validator sender lock was poisoned");
+                ApiError::UnknownValidatorError
+            })?
+            .clone();
+
+        send_request(
+            &sender,
+            &self.generate_correlation_id(),
+            message_type,
+            content,
+            timeout,
+        )
+    }
+}
+
+/// Sends a request over a ZMQ connection to a validator, returning the
+/// content of its response.
+pub(crate) fn send_request(
+    sender: &ZmqMessageSender,
+    correlation_id: &str,
+    message_type: Message_MessageType,
+    content: &[u8],
+    timeout: Duration,
+) -> Result<Vec<u8>, ApiError> {
+    debug!("Sending {:?} request to validator", message_type);
+
+    let mut future =
+        sender
+            .send(message_type, correlation_id, content)
+            .map_err(|err| match err {
+                SendError::DisconnectedError => {
+                    warn!("Validator disconnected while sending request");
+                    ApiError::ValidatorDisconnected
+                }
+                SendError::TimeoutError => {
+                    warn!("Failed sending message - timed out");
+                    ApiError::SendBackoffTimeout
+                }
+                SendError::UnknownError => ApiError::UnknownValidatorError,
+            })?;
+
+    let mut response = future.get_timeout(timeout).map_err(|err| match err {
+        ReceiveError::TimeoutError => {
+            warn!("Timed out while waiting for validator response");
+            ApiError::ValidatorTimedOut
+        }
+        err => {
+            warn!(
+                "Validator disconnected while waiting for response: {:?}",
+                err
+            );
+            ApiError::ValidatorDisconnected
+        }
+    })?;
+
+    debug!(
+        "Received {:?} response from validator",
+        response.get_message_type()
+    );
+
+    Ok(response.take_content())
+}
+
+/// Answers a ping sent by the validator to check the connection is alive.
+pub(crate) fn answer_ping(sender: &ZmqMessageSender, ping: &Message) {
+    let pong = PingResponse::new()
+        .write_to_bytes()
+        .expect("Unable to serialize ping response");
+    if let Err(err) = sender.reply(
+        Message_MessageType::PING_RESPONSE,
+        ping.get_correlation_id(),
+        &pong,
+    ) {
+        warn!("Unable to answer validator ping: {:?}", err);
+    }
+}
@@ -0,0 +1,279 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+//! The errors sent back to clients, with the same codes, statuses and
+//! messages as the Python REST API.
+
+use std::error;
+use std::fmt;
+
+use serde_json::Value;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ApiError {
+    UnknownValidatorError,
+    ValidatorNotReady,
+    ValidatorTimedOut,
+    ValidatorDisconnected,
+    SendBackoffTimeout,
+    ValidatorResponseInvalid,
+    ResourceHeaderInvalid,
+    StatusResponseMissing,
+    SubmittedBatchesInvalid,
+    BatchQueueFull,
+    NoBatchesSubmitted,
+    BadProtobufSubmitted,
+    SubmissionWrongContentType,
+    StatusWrongContentType,
+    StatusBodyInvalid,
+    HeadNotFound,
+    CountInvalid,
+    PagingInvalid,
+    SortInvalid,
+    /// The invalid id is appended to the message
+    InvalidResourceId(String),
+    InvalidStateAddress,
+    StatusIdQueryInvalid,
+    BlockNotFound,
+    BatchNotFound,
+    TransactionNotFound,
+    StateNotFound,
+}
+
+impl ApiError {
+    /// The fixed code included in the JSON error response.
+    pub fn api_code(&self) -> u32 {
+        match *self {
+            ApiError::UnknownValidatorError => 10,
+            ApiError::ValidatorNotReady => 15,
+            ApiError::ValidatorTimedOut => 17,
+            ApiError::ValidatorDisconnected => 18,
+            ApiError::SendBackoffTimeout => 19,
+            ApiError::ValidatorResponseInvalid => 20,
+            ApiError::ResourceHeaderInvalid => 21,
+            ApiError::StatusResponseMissing => 27,
+            ApiError::SubmittedBatchesInvalid => 30,
+            ApiError::BatchQueueFull => 31,
+            ApiError::NoBatchesSubmitted => 34,
+            ApiError::BadProtobufSubmitted => 35,
+            ApiError::SubmissionWrongContentType => 42,
+            ApiError::StatusWrongContentType => 43,
+            ApiError::StatusBodyInvalid => 46,
+            ApiError::HeadNotFound => 50,
+            ApiError::CountInvalid => 53,
+            ApiError::PagingInvalid => 54,
+            ApiError::SortInvalid => 57,
+            ApiError::InvalidResourceId(_) => 60,
+            ApiError::InvalidStateAddress => 62,
+            ApiError::StatusIdQueryInvalid => 66,
+            ApiError::BlockNotFound => 70,
+            ApiError::BatchNotFound => 71,
+            ApiError::TransactionNotFound => 72,
+            ApiError::StateNotFound => 75,
+        }
+    }
+
+    /// The HTTP status of the response.
+    pub fn status_code(&self) -> u16 {
+        match *self {
+            ApiError::UnknownValidatorError
+            | ApiError::ValidatorResponseInvalid
+            | ApiError::ResourceHeaderInvalid
+            | ApiError::StatusResponseMissing => 500,
+            ApiError::ValidatorNotReady
+            | ApiError::ValidatorTimedOut
+            | ApiError::ValidatorDisconnected => 503,
+            ApiError::SendBackoffTimeout => 408,
+            ApiError::BatchQueueFull => 429,
+            ApiError::HeadNotFound
+            | ApiError::BlockNotFound
+            | ApiError::BatchNotFound
+            | ApiError::TransactionNotFound
+            | ApiError::StateNotFound => 404,
+            _ => 400,
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match *self {
+            ApiError::UnknownValidatorError => "Unknown Validator Error",
+            ApiError::ValidatorNotReady => "Validator Not Ready",
+            ApiError::ValidatorTimedOut => "Validator Timed Out",
+            ApiError::ValidatorDisconnected => "Validator Disconnected",
+            ApiError::SendBackoffTimeout => "Send timed out",
+            ApiError::ValidatorResponseInvalid => "Invalid Validator Response",
+            ApiError::ResourceHeaderInvalid => "Invalid Resource Header",
+            ApiError::StatusResponseMissing => "Unable to Fetch Statuses",
+            ApiError::SubmittedBatchesInvalid => "Submitted Batches Invalid",
+            ApiError::BatchQueueFull => "Unable to Accept Batches",
+            ApiError::NoBatchesSubmitted => "No Batches Submitted",
+            ApiError::BadProtobufSubmitted => "Protobuf Not Decodable",
+            ApiError::SubmissionWrongContentType | ApiError::StatusWrongContentType => {
+                "Wrong Content Type"
+            }
+            ApiError::StatusBodyInvalid => "Bad Status Request",
+            ApiError::HeadNotFound => "Head Not Found",
+            ApiError::CountInvalid => "Invalid Count Query",
+            ApiError::PagingInvalid => "Invalid Paging Query",
+            ApiError::SortInvalid => "Invalid Sort Query",
+            ApiError::InvalidResourceId(_) => "Invalid Resource Id",
+            ApiError::InvalidStateAddress => "Invalid State Address",
+            ApiError::StatusIdQueryInvalid => "Id Query Invalid or Missing",
+            ApiError::BlockNotFound => "Block Not Found",
+            ApiError::BatchNotFound => "Batch Not Found",
+            ApiError::TransactionNotFound => "Transaction Not Found",
+            ApiError::StateNotFound => "State Not Found",
+        }
+    }
+
+    /// The human-readable description of the error.
+    pub fn message(&self) -> String {
+        let message = match *self {
+            ApiError::UnknownValidatorError => {
+                "An unknown error occurred with the validator while processing your request."
+            }
+            ApiError::ValidatorNotReady => {
+                "The validator has no genesis block, and is not yet ready to be queried. Try your \
+                 request again later."
+            }
+            ApiError::ValidatorTimedOut => {
+                "The request timed out while waiting for a response from the validator. Your \
+                 request may or may not have been processed."
+            }
+            ApiError::ValidatorDisconnected => {
+                "The validator disconnected before sending a response. Try your request again \
+                 later."
+            }
+            ApiError::SendBackoffTimeout => {
+                "Sending message to validator timed out. Retry limit reached. Try your request \
+                 again later."
+            }
+            ApiError::ValidatorResponseInvalid => {
+                "The response from the validator could not be decoded. It may have been corrupted \
+                 or compromised."
+            }
+            ApiError::ResourceHeaderInvalid => {
+                "The resource fetched from the validator had an invalid header, and may be \
+                 corrupted."
+            }
+            ApiError::StatusResponseMissing => {
+                "An unknown error occurred while attempting to fetch batch statuses, and nothing \
+                 was returned."
+            }
+            ApiError::SubmittedBatchesInvalid => {
+                "The submitted BatchList was rejected by the validator. It was poorly formed, or \
+                 has an invalid signature."
+            }
+            ApiError::BatchQueueFull => {
+                "The validator cannot currently accept more batches, due to a full queue.  Please \
+                 submit your request again."
+            }
+            ApiError::NoBatchesSubmitted => {
+                "The protobuf BatchList you submitted was empty and contained no Batches. You \
+                 must submit at least one Batch."
+            }
+            ApiError::BadProtobufSubmitted => {
+                "The protobuf BatchList you submitted was malformed and could not be read."
+            }
+            ApiError::SubmissionWrongContentType => {
+                "Batches must be submitted in a BatchList protobuf binary, with a 'Content-Type' \
+                 header of 'application/octet-stream'."
+            }
+            ApiError::StatusWrongContentType => {
+                "Requests for batch statuses sent as a POST must have a 'Content-Type' header of \
+                 'application/json'."
+            }
+            ApiError::StatusBodyInvalid => {
+                "Requests for batch statuses sent as a POST must have a JSON formatted body with \
+                 an array of at least one id string."
+            }
+            ApiError::HeadNotFound => {
+                "There is no block with the id specified in the 'head' query parameter."
+            }
+            ApiError::CountInvalid => {
+                "The 'count' query parameter must be a positive, non-zero integer."
+            }
+            ApiError::PagingInvalid => {
+                "Paging request failed as written. One or more of the 'min', 'max', or 'count' \
+                 query parameters were invalid or out of range."
+            }
+            ApiError::SortInvalid => {
+                "The sort request failed as written. Some of the keys specified were not valid."
+            }
+            ApiError::InvalidResourceId(_) => {
+                "Blockchain items are identified by 128 character hex-strings. A submitted block, \
+                 batch, or transaction id was invalid: "
+            }
+            ApiError::InvalidStateAddress => {
+                "The state address submitted was invalid. To fetch specific state data, you must \
+                 submit the full 70-character address."
+            }
+            ApiError::StatusIdQueryInvalid => {
+                "Requests for batch statuses sent as a GET request must have an 'id' query \
+                 parameter with a comma-separated list of at least one batch id."
+            }
+            ApiError::BlockNotFound => "There is no block with the id specified in the blockchain.",
+            ApiError::BatchNotFound => "There is no batch with the id specified in the blockchain.",
+            ApiError::TransactionNotFound => {
+                "There is no transaction with the id specified in the blockchain."
+            }
+            ApiError::StateNotFound => "There is no state data at the address specified.",
+        };
+
+        match *self {
+            ApiError::InvalidResourceId(ref id) => format!("{}{}", message, id),
+            _ => message.into(),
+        }
+    }
+
+    /// The JSON body of the error response.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "error": {
+                "code": self.api_code(),
+                "title": self.title(),
+                "message": self.message(),
+            }
+        })
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.title(), self.message())
+    }
+}
+
+impl error::Error for ApiError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_body() {
+        let body = ApiError::InvalidResourceId("abc".into()).to_json();
+
+        assert_eq!(body["error"]["code"], 60);
+        assert_eq!(body["error"]["title"], "Invalid Resource Id");
+        assert!(body["error"]["message"]
+            .as_str()
+            .unwrap()
+            .ends_with("id was invalid: abc"));
+        assert_eq!(ApiError::BatchQueueFull.status_code(), 429);
+    }
+}
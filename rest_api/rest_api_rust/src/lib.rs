@@ -0,0 +1,35 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+//! A Rust implementation of the Sawtooth REST API, which serves the same
+//! endpoints as the Python REST API by speaking the client protocol to a
+//! validator.
+
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate serde_json;
+
+pub mod config;
+pub mod connection;
+pub mod error;
+pub mod middleware;
+pub mod request;
+pub mod resources;
+pub mod routes;
+pub mod server;
+pub mod subscriptions;
@@ -0,0 +1,154 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+#[macro_use]
+extern crate clap;
+#[macro_use]
+extern crate log;
+
+use std::fs;
+use std::process;
+use std::sync::Arc;
+use std::time::Duration;
+
+use sawtooth_logging::{level_from_verbosity, LoggingConfig};
+use sawtooth_rest_api::config::{get_config_dir, RestApiConfig};
+use sawtooth_rest_api::connection::ZmqValidatorConnection;
+use sawtooth_rest_api::middleware::{BasicAuth, Cors};
+use sawtooth_rest_api::routes::RouteHandler;
+use sawtooth_rest_api::server::{RestApiServer, TlsConfig};
+use sawtooth_rest_api::subscriptions::SubscriptionHandler;
+
+fn main() {
+    let matches = sawtooth_logging::add_args(clap_app!(rest_api =>
+        (version: crate_version!())
+        (about: "Starts the REST API application and connects to a specified validator.")
+        (@arg bind: -B --bind +takes_value +multiple number_of_values(1)
+            "identify host and port for API to run on default: http://localhost:8008)")
+        (@arg connect: -C --connect +takes_value "specify URL to connect to a running validator")
+        (@arg timeout: -t --timeout +takes_value
+            "set time (in seconds) to wait for validator response")
+        (@arg client_max_size: --("client-max-size") +takes_value
+            "the max size (in bytes) of a request body")
+        (@arg cors_allowed_origin: --("cors-allowed-origin") +takes_value +multiple
+            number_of_values(1) "allow cross-origin requests from the origin, or any origin if *")
+        (@arg tls_cert: --("tls-cert") +takes_value "serve HTTPS with this PEM certificate")
+        (@arg tls_key: --("tls-key") +takes_value "the PEM private key of the TLS certificate")
+        (@arg basic_auth: --("basic-auth") +takes_value
+            "require clients to authenticate as username:password")
+        (@arg verbose: -v --verbose +multiple "enable more verbose output to stderr")
+    ))
+    .get_matches();
+
+    let console_log_level = level_from_verbosity(matches.occurrences_of("verbose"));
+
+    if let Err(err) = LoggingConfig::from_matches(&matches, console_log_level)
+        .and_then(|config| sawtooth_logging::init(&config))
+    {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+
+    let opts_config = RestApiConfig {
+        bind: matches
+            .values_of("bind")
+            .map(|values| values.map(String::from).collect()),
+        connect: matches.value_of("connect").map(String::from),
+        timeout: parse_arg(&matches, "timeout"),
+        client_max_size: parse_arg(&matches, "client_max_size"),
+        cors_allowed_origins: matches
+            .values_of("cors_allowed_origin")
+            .map(|values| values.map(String::from).collect()),
+        tls_cert: matches.value_of("tls_cert").map(String::from),
+        tls_key: matches.value_of("tls_key").map(String::from),
+        basic_auth: matches.value_of("basic_auth").map(String::from),
+    };
+
+    let toml_config = match RestApiConfig::from_toml_file(&get_config_dir().join("rest_api.toml")) {
+        Ok(config) => config,
+        Err(err) => exit_with_error(&err.to_string()),
+    };
+
+    let config = RestApiConfig::merge(&[opts_config, toml_config, RestApiConfig::default_config()]);
+
+    let connect = config.connect.unwrap_or_default();
+    let url = if connect.contains("tcp://") {
+        connect
+    } else {
+        format!("tcp://{}", connect)
+    };
+
+    let tls = match (config.tls_cert, config.tls_key) {
+        (Some(cert), Some(key)) => Some(TlsConfig {
+            certificate: read_file(&cert),
+            private_key: read_file(&key),
+        }),
+        (None, None) => None,
+        _ => exit_with_error("tls_cert and tls_key must be set together"),
+    };
+
+    let auth = config.basic_auth.map(|credentials| {
+        BasicAuth::from_credentials(&credentials).unwrap_or_else(|| {
+            exit_with_error("basic_auth must be in the format username:password")
+        })
+    });
+
+    info!("Creating handlers for validator at {}", url);
+
+    let timeout = Duration::from_secs(config.timeout.unwrap_or_default());
+    let handler = RouteHandler::new(Box::new(ZmqValidatorConnection::new(&url)), timeout);
+    let server = Arc::new(RestApiServer::new(
+        handler,
+        SubscriptionHandler::new(&url, timeout),
+        Cors::new(config.cors_allowed_origins.unwrap_or_default()),
+        auth,
+        config.client_max_size.unwrap_or_default(),
+    ));
+
+    let mut threads = vec![];
+    for bind in config.bind.unwrap_or_default() {
+        match RestApiServer::serve(&server, &bind, tls.clone()) {
+            Ok(bind_threads) => threads.extend(bind_threads),
+            Err(err) => exit_with_error(&err.to_string()),
+        }
+    }
+
+    for thread in threads {
+        if thread.join().is_err() {
+            error!("A REST API thread panicked");
+        }
+    }
+}
+
+fn parse_arg<T: std::str::FromStr>(matches: &clap::ArgMatches, name: &str) -> Option<T> {
+    matches.value_of(name).map(|value| {
+        value
+            .parse()
+            .unwrap_or_else(|_| exit_with_error(&format!("Invalid {}: {}", name, value)))
+    })
+}
+
+fn read_file(path: &str) -> Vec<u8> {
+    fs::read(path)
+        .unwrap_or_else(|err| exit_with_error(&format!("Unable to read {}: {}", path, err)))
+}
+
+fn exit_with_error(msg: &str) -> ! {
+    error!("{}", msg);
+    eprintln!("{}", msg);
+    process::exit(1);
+}
@@ -0,0 +1,213 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+//! The CORS and basic auth checks applied to every request before it is
+//! routed.
+
+use crate::request::{Request, Response};
+
+const ALLOWED_METHODS: &str = "GET, POST, OPTIONS";
+const ALLOWED_HEADERS: &str = "Authorization, Content-Type";
+
+/// Cross-origin resource sharing for a set of allowed origins.
+#[derive(Clone, Debug, Default)]
+pub struct Cors {
+    allowed_origins: Vec<String>,
+}
+
+impl Cors {
+    /// Allows requests from the given origins. An origin of "*" allows
+    /// requests from any origin; no origins disables CORS.
+    pub fn new(allowed_origins: Vec<String>) -> Cors {
+        Cors { allowed_origins }
+    }
+
+    /// Returns the request's origin if it is allowed.
+    fn allowed_origin<'a>(&self, request: &'a Request) -> Option<&'a str> {
+        let origin = request.header("Origin")?;
+        if self
+            .allowed_origins
+            .iter()
+            .any(|allowed| allowed == "*" || allowed == origin)
+        {
+            Some(origin)
+        } else {
+            None
+        }
+    }
+
+    /// Answers a preflight request, or returns None if the request is not
+    /// one.
+    pub fn preflight(&self, request: &Request) -> Option<Response> {
+        if request.method != "OPTIONS" || request.header("Access-Control-Request-Method").is_none()
+        {
+            return None;
+        }
+
+        let response = Response::text(200, "");
+        Some(match self.allowed_origin(request) {
+            Some(origin) => response
+                .with_header("Access-Control-Allow-Origin", origin)
+                .with_header("Access-Control-Allow-Methods", ALLOWED_METHODS)
+                .with_header("Access-Control-Allow-Headers", ALLOWED_HEADERS)
+                .with_header("Access-Control-Allow-Credentials", "true")
+                .with_header("Vary", "Origin"),
+            None => response,
+        })
+    }
+
+    /// Adds the CORS headers to the response to an allowed origin.
+    pub fn apply(&self, request: &Request, response: Response) -> Response {
+        match self.allowed_origin(request) {
+            Some(origin) => response
+                .with_header("Access-Control-Allow-Origin", origin)
+                .with_header("Access-Control-Allow-Credentials", "true")
+                .with_header("Vary", "Origin"),
+            None => response,
+        }
+    }
+}
+
+/// HTTP basic authentication with a single set of credentials.
+#[derive(Clone, Debug)]
+pub struct BasicAuth {
+    username: String,
+    password: String,
+}
+
+impl BasicAuth {
+    pub fn new(username: &str, password: &str) -> BasicAuth {
+        BasicAuth {
+            username: username.into(),
+            password: password.into(),
+        }
+    }
+
+    /// Parses credentials in the "username:password" form used by the
+    /// configuration.
+    pub fn from_credentials(credentials: &str) -> Option<BasicAuth> {
+        let index = credentials.find(':')?;
+        let (username, password) = (&credentials[..index], &credentials[index + 1..]);
+        if username.is_empty() {
+            return None;
+        }
+
+        Some(BasicAuth::new(username, password))
+    }
+
+    /// Checks the request's credentials, returning the response to send
+    /// instead if they are missing or wrong.
+    pub fn authorize(&self, request: &Request) -> Result<(), Response> {
+        let authorized = request
+            .header("Authorization")
+            .and_then(|value| {
+                let mut parts = value.trim().splitn(2, ' ');
+                match (parts.next(), parts.next()) {
+                    (Some(scheme), Some(encoded)) if scheme.eq_ignore_ascii_case("Basic") => {
+                        base64::decode(encoded.trim()).ok()
+                    }
+                    _ => None,
+                }
+            })
+            .map(|decoded| {
+                let expected = format!("{}:{}", self.username, self.password);
+                constant_time_eq(&decoded, expected.as_bytes())
+            })
+            .unwrap_or(false);
+
+        if authorized {
+            Ok(())
+        } else {
+            Err(Response::text(401, "401: Unauthorized")
+                .with_header("WWW-Authenticate", "Basic realm=\"Sawtooth REST API\""))
+        }
+    }
+}
+
+/// Compares the bytes in time independent of where they differ, so that the
+/// credentials cannot be guessed by timing responses.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cors() {
+        let cors = Cors::new(vec!["https://example.com".into()]);
+
+        let request = Request::new("GET", "/blocks").with_header("Origin", "https://example.com");
+        let response = cors.apply(&request, Response::text(200, ""));
+        assert_eq!(
+            response.header("Access-Control-Allow-Origin"),
+            Some("https://example.com")
+        );
+
+        let request = Request::new("GET", "/blocks").with_header("Origin", "https://other.com");
+        let response = cors.apply(&request, Response::text(200, ""));
+        assert_eq!(response.header("Access-Control-Allow-Origin"), None);
+
+        let request = Request::new("OPTIONS", "/batches")
+            .with_header("Origin", "https://example.com")
+            .with_header("Access-Control-Request-Method", "POST");
+        let response = cors.preflight(&request).unwrap();
+        assert_eq!(
+            response.header("Access-Control-Allow-Methods"),
+            Some(ALLOWED_METHODS)
+        );
+
+        assert!(cors.preflight(&Request::new("GET", "/batches")).is_none());
+    }
+
+    #[test]
+    fn test_cors_any_origin() {
+        let cors = Cors::new(vec!["*".into()]);
+        let request = Request::new("GET", "/status").with_header("Origin", "http://localhost:3000");
+
+        let response = cors.apply(&request, Response::text(200, ""));
+        assert_eq!(
+            response.header("Access-Control-Allow-Origin"),
+            Some("http://localhost:3000")
+        );
+    }
+
+    #[test]
+    fn test_basic_auth() {
+        let auth = BasicAuth::from_credentials("sawtooth:pass:word").unwrap();
+
+        let request = Request::new("GET", "/status").with_header(
+            "Authorization",
+            &format!("Basic {}", base64::encode("sawtooth:pass:word")),
+        );
+        assert!(auth.authorize(&request).is_ok());
+
+        let request = Request::new("GET", "/status").with_header(
+            "Authorization",
+            &format!("Basic {}", base64::encode("sawtooth:wrong")),
+        );
+        assert_eq!(auth.authorize(&request).unwrap_err().status, 401);
+
+        assert!(auth.authorize(&Request::new("GET", "/status")).is_err());
+        assert!(BasicAuth::from_credentials("sawtooth").is_none());
+    }
+}
@@ -0,0 +1,307 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+//! The HTTP requests and responses handled by the routes, independent of the
+//! server that receives them.
+
+use std::collections::HashMap;
+
+use serde_json::{self, Value};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Request {
+    pub method: String,
+    /// "http" or "https", depending on how the request was received
+    pub scheme: String,
+    pub path: String,
+    /// The decoded query parameters, in the order they were sent
+    pub query: Vec<(String, String)>,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// Creates a request for a url made of a path and an optional query
+    /// string, e.g. "/blocks?limit=5".
+    pub fn new(method: &str, url: &str) -> Request {
+        let (path, query) = match url.find('?') {
+            Some(index) => (&url[..index], parse_query(&url[index + 1..])),
+            None => (url, vec![]),
+        };
+
+        Request {
+            method: method.to_uppercase(),
+            scheme: "http".into(),
+            path: path.into(),
+            query,
+            headers: vec![],
+            body: vec![],
+        }
+    }
+
+    pub fn with_scheme(mut self, scheme: &str) -> Request {
+        self.scheme = scheme.into();
+        self
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Request {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn with_body(mut self, body: Vec<u8>) -> Request {
+        self.body = body;
+        self
+    }
+
+    /// Returns the value of the first header with the given name, which is
+    /// matched case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the value of the first query parameter with the given name.
+    pub fn query(&self, name: &str) -> Option<&str> {
+        self.query
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The media type of the request body, without any parameters.
+    pub fn content_type(&self) -> Option<&str> {
+        self.header("Content-Type")
+            .map(|value| value.split(';').next().unwrap_or("").trim())
+    }
+
+    /// Builds a url to this server, with the query of this request
+    /// overridden by the given changes. Changes set to None are not used.
+    /// The head, start and limit queries come first, followed by any others
+    /// in alphabetical order.
+    pub fn build_url(&self, path: Option<&str>, changes: &[(&str, Option<String>)]) -> String {
+        let mut queries: HashMap<String, String> = HashMap::new();
+        for (key, value) in &self.query {
+            queries.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+        for (key, value) in changes {
+            if let Some(value) = value {
+                queries.insert((*key).to_string(), value.clone());
+            }
+        }
+
+        let mut query_strings = vec![];
+        let mut add_query = |key: &str, queries: &mut HashMap<String, String>| {
+            if let Some(value) = queries.remove(key) {
+                if value.is_empty() {
+                    query_strings.push(key.to_string());
+                } else {
+                    query_strings.push(format!("{}={}", key, value));
+                }
+            }
+        };
+
+        add_query("head", &mut queries);
+        add_query("start", &mut queries);
+        add_query("limit", &mut queries);
+
+        let mut keys: Vec<String> = queries.keys().cloned().collect();
+        keys.sort();
+        for key in keys {
+            add_query(&key, &mut queries);
+        }
+
+        let scheme = self
+            .forwarded("proto")
+            .unwrap_or_else(|| self.scheme.clone());
+        let host = self
+            .forwarded("host")
+            .or_else(|| self.header("Host").map(String::from))
+            .unwrap_or_default();
+        let forwarded_path = self.forwarded("path").unwrap_or_default();
+        let path = path.unwrap_or(&self.path);
+        let query = if query_strings.is_empty() {
+            String::new()
+        } else {
+            format!("?{}", query_strings.join("&"))
+        };
+
+        format!("{}://{}{}{}{}", scheme, host, forwarded_path, path, query)
+    }
+
+    /// Gets a value from the `Forwarded` header if present, or from the
+    /// equivalent `X-Forwarded-` header if not.
+    fn forwarded(&self, key: &str) -> Option<String> {
+        let forwarded = self.header("Forwarded").unwrap_or("");
+        let lowercase = forwarded.to_ascii_lowercase();
+        let pattern = format!("{}=", key);
+
+        if let Some(index) = lowercase.find(&pattern) {
+            let value: String = forwarded[index + pattern.len()..]
+                .chars()
+                .take_while(|c| !c.is_whitespace() && *c != ',' && *c != ';')
+                .collect();
+
+            if !value.is_empty() {
+                if value.len() > 1 && value.starts_with('"') && value.ends_with('"') {
+                    return Some(value[1..value.len() - 1].to_string());
+                }
+                return Some(value);
+            }
+        }
+
+        let mut title = key.to_string();
+        if let Some(first) = title.get_mut(0..1) {
+            first.make_ascii_uppercase();
+        }
+        self.header(&format!("X-Forwarded-{}", title))
+            .filter(|value| !value.is_empty())
+            .map(String::from)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    /// Creates a response with a JSON body, formatted like the Python REST
+    /// API's responses.
+    pub fn json(status: u16, body: &Value) -> Response {
+        let body = serde_json::to_string_pretty(body).unwrap_or_else(|_| "{}".into());
+
+        Response {
+            status,
+            headers: vec![(
+                "Content-Type".into(),
+                "application/json; charset=utf-8".into(),
+            )],
+            body: body.into_bytes(),
+        }
+    }
+
+    pub fn text(status: u16, body: &str) -> Response {
+        Response {
+            status,
+            headers: vec![("Content-Type".into(), "text/plain; charset=utf-8".into())],
+            body: body.as_bytes().to_vec(),
+        }
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Response {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+fn parse_query(query: &str) -> Vec<(String, String)> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.find('=') {
+            Some(index) => (decode(&pair[..index]), decode(&pair[index + 1..])),
+            None => (decode(pair), String::new()),
+        })
+        .collect()
+}
+
+/// Decodes a percent-encoded query component, in which '+' is a space.
+fn decode(component: &str) -> String {
+    let bytes = component.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                match (hex_value(bytes[i + 1]), hex_value(bytes[i + 2])) {
+                    (Some(high), Some(low)) => {
+                        decoded.push(high << 4 | low);
+                        i += 2;
+                    }
+                    _ => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn hex_value(digit: u8) -> Option<u8> {
+    (digit as char).to_digit(16).map(|value| value as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_query() {
+        let request = Request::new("get", "/state?address=1cf1&reverse&id=a%2Cb+c&id=d");
+
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.path, "/state");
+        assert_eq!(request.query("address"), Some("1cf1"));
+        assert_eq!(request.query("reverse"), Some(""));
+        assert_eq!(request.query("id"), Some("a,b c"));
+        assert_eq!(request.query("limit"), None);
+    }
+
+    #[test]
+    fn test_build_url_orders_queries() {
+        let request = Request::new("GET", "/blocks?reverse&limit=5&id=abc&head=123")
+            .with_header("Host", "localhost:8008");
+
+        assert_eq!(
+            request.build_url(None, &[("start", Some("0x02".into())), ("head", None)]),
+            "http://localhost:8008/blocks?head=123&start=0x02&limit=5&id=abc&reverse"
+        );
+        assert_eq!(
+            request.build_url(Some("/batch_statuses"), &[("id", Some("def".into()))]),
+            "http://localhost:8008/batch_statuses?head=123&limit=5&id=def&reverse"
+        );
+    }
+
+    #[test]
+    fn test_build_url_forwarded() {
+        let request = Request::new("GET", "/status")
+            .with_header("Host", "localhost:8008")
+            .with_header("Forwarded", "for=10.0.0.1;proto=https;host=\"example.com\"")
+            .with_header("X-Forwarded-Path", "/sawtooth");
+
+        assert_eq!(
+            request.build_url(None, &[]),
+            "https://example.com/sawtooth/status"
+        );
+    }
+}
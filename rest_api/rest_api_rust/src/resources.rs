@@ -0,0 +1,248 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+//! Converts the resources sent by the validator to JSON, in the same form as
+//! the Python REST API: headers are deserialized, bytes are base64 encoded,
+//! 64 bit integers are strings and enums are their names.
+
+use protobuf::Message as M;
+use serde_json::{Map, Value};
+
+use sawtooth_sdk::messages::batch::{Batch, BatchHeader};
+use sawtooth_sdk::messages::block::{Block, BlockHeader};
+use sawtooth_sdk::messages::client_batch_submit::ClientBatchStatus;
+use sawtooth_sdk::messages::client_state::ClientStateListResponse_Entry;
+use sawtooth_sdk::messages::transaction::{Transaction, TransactionHeader};
+
+use crate::error::ApiError;
+
+/// Deserializes a block's header, and the headers of its batches.
+pub fn expand_block(block: &Block) -> Result<Value, ApiError> {
+    let header: BlockHeader = parse_header(block.get_header())?;
+
+    Ok(json!({
+        "header": {
+            "block_num": header.get_block_num().to_string(),
+            "previous_block_id": header.get_previous_block_id(),
+            "signer_public_key": header.get_signer_public_key(),
+            "batch_ids": header.get_batch_ids(),
+            "consensus": base64::encode(header.get_consensus()),
+            "state_root_hash": header.get_state_root_hash(),
+        },
+        "header_signature": block.get_header_signature(),
+        "batches": block
+            .get_batches()
+            .iter()
+            .map(expand_batch)
+            .collect::<Result<Vec<_>, _>>()?,
+    }))
+}
+
+/// Deserializes a batch's header, and the headers of its transactions.
+pub fn expand_batch(batch: &Batch) -> Result<Value, ApiError> {
+    let header: BatchHeader = parse_header(batch.get_header())?;
+
+    Ok(json!({
+        "header": {
+            "signer_public_key": header.get_signer_public_key(),
+            "transaction_ids": header.get_transaction_ids(),
+        },
+        "header_signature": batch.get_header_signature(),
+        "transactions": batch
+            .get_transactions()
+            .iter()
+            .map(expand_transaction)
+            .collect::<Result<Vec<_>, _>>()?,
+        "trace": batch.get_trace(),
+    }))
+}
+
+/// Deserializes a transaction's header.
+pub fn expand_transaction(transaction: &Transaction) -> Result<Value, ApiError> {
+    let header: TransactionHeader = parse_header(transaction.get_header())?;
+
+    Ok(json!({
+        "header": {
+            "batcher_public_key": header.get_batcher_public_key(),
+            "dependencies": header.get_dependencies(),
+            "family_name": header.get_family_name(),
+            "family_version": header.get_family_version(),
+            "inputs": header.get_inputs(),
+            "nonce": header.get_nonce(),
+            "outputs": header.get_outputs(),
+            "payload_sha512": header.get_payload_sha512(),
+            "signer_public_key": header.get_signer_public_key(),
+        },
+        "header_signature": transaction.get_header_signature(),
+        "payload": base64::encode(transaction.get_payload()),
+    }))
+}
+
+pub fn state_entry(entry: &ClientStateListResponse_Entry) -> Value {
+    json!({
+        "address": entry.get_address(),
+        "data": base64::encode(entry.get_data()),
+    })
+}
+
+/// Converts a batch status, dropping its empty properties and renaming its
+/// ids to "id".
+pub fn batch_status(status: &ClientBatchStatus) -> Value {
+    let status = json!({
+        "batch_id": status.get_batch_id(),
+        "status": format!("{:?}", status.get_status()),
+        "invalid_transactions": status
+            .get_invalid_transactions()
+            .iter()
+            .map(|invalid| json!({
+                "transaction_id": invalid.get_transaction_id(),
+                "message": invalid.get_message(),
+                "extended_data": base64::encode(invalid.get_extended_data()),
+            }))
+            .collect::<Vec<_>>(),
+    });
+
+    drop_id_prefixes(drop_empty_props(status))
+}
+
+fn parse_header<H: M>(header: &[u8]) -> Result<H, ApiError> {
+    H::parse_from_bytes(header).map_err(|err| {
+        error!(
+            "The validator sent a resource with an invalid header: {}",
+            err
+        );
+        ApiError::ResourceHeaderInvalid
+    })
+}
+
+/// Removes the properties with empty strings from nested objects.
+fn drop_empty_props(item: Value) -> Value {
+    match item {
+        Value::Array(items) => Value::Array(items.into_iter().map(drop_empty_props).collect()),
+        Value::Object(props) => Value::Object(
+            props
+                .into_iter()
+                .filter(|(_, value)| value != "")
+                .map(|(key, value)| (key, drop_empty_props(value)))
+                .collect::<Map<String, Value>>(),
+        ),
+        item => item,
+    }
+}
+
+/// Renames the keys ending in "id" to just "id" in nested objects.
+fn drop_id_prefixes(item: Value) -> Value {
+    match item {
+        Value::Array(items) => Value::Array(items.into_iter().map(drop_id_prefixes).collect()),
+        Value::Object(props) => Value::Object(
+            props
+                .into_iter()
+                .map(|(key, value)| {
+                    let key = if key.ends_with("id") {
+                        "id".into()
+                    } else {
+                        key
+                    };
+                    (key, drop_id_prefixes(value))
+                })
+                .collect::<Map<String, Value>>(),
+        ),
+        item => item,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use protobuf::RepeatedField;
+    use sawtooth_sdk::messages::client_batch_submit::{
+        ClientBatchStatus_InvalidTransaction, ClientBatchStatus_Status,
+    };
+
+    #[test]
+    fn test_expand_block() {
+        let mut txn_header = TransactionHeader::new();
+        txn_header.set_family_name("intkey".into());
+        txn_header.set_inputs(RepeatedField::from_vec(vec!["1cf126".into()]));
+        let mut txn = Transaction::new();
+        txn.set_header(txn_header.write_to_bytes().unwrap());
+        txn.set_header_signature("c".into());
+        txn.set_payload(b"payload".to_vec());
+
+        let mut batch_header = BatchHeader::new();
+        batch_header.set_transaction_ids(RepeatedField::from_vec(vec!["c".into()]));
+        let mut batch = Batch::new();
+        batch.set_header(batch_header.write_to_bytes().unwrap());
+        batch.set_header_signature("b".into());
+        batch.set_transactions(RepeatedField::from_vec(vec![txn]));
+
+        let mut block_header = BlockHeader::new();
+        block_header.set_block_num(18_446_744_073_709_551_615);
+        block_header.set_consensus(b"Devmode".to_vec());
+        let mut block = Block::new();
+        block.set_header(block_header.write_to_bytes().unwrap());
+        block.set_header_signature("a".into());
+        block.set_batches(RepeatedField::from_vec(vec![batch]));
+
+        let json = expand_block(&block).unwrap();
+
+        assert_eq!(json["header"]["block_num"], "18446744073709551615");
+        assert_eq!(json["header"]["consensus"], "RGV2bW9kZQ==");
+        assert_eq!(json["header"]["previous_block_id"], "");
+        assert_eq!(
+            json["batches"][0]["header"]["transaction_ids"],
+            json!(["c"])
+        );
+        assert_eq!(json["batches"][0]["trace"], false);
+
+        let txn = &json["batches"][0]["transactions"][0];
+        assert_eq!(txn["header"]["family_name"], "intkey");
+        assert_eq!(txn["header"]["inputs"], json!(["1cf126"]));
+        assert_eq!(txn["header"]["dependencies"], json!([]));
+        assert_eq!(txn["payload"], "cGF5bG9hZA==");
+    }
+
+    #[test]
+    fn test_invalid_header() {
+        let mut batch = Batch::new();
+        batch.set_header(vec![0xff, 0xff]);
+
+        assert_eq!(expand_batch(&batch), Err(ApiError::ResourceHeaderInvalid));
+    }
+
+    #[test]
+    fn test_batch_status() {
+        let mut invalid = ClientBatchStatus_InvalidTransaction::new();
+        invalid.set_transaction_id("c".into());
+        invalid.set_message("Invalid nonce".into());
+
+        let mut status = ClientBatchStatus::new();
+        status.set_batch_id("b".into());
+        status.set_status(ClientBatchStatus_Status::INVALID);
+        status.set_invalid_transactions(RepeatedField::from_vec(vec![invalid]));
+
+        assert_eq!(
+            batch_status(&status),
+            json!({
+                "id": "b",
+                "status": "INVALID",
+                "invalid_transactions": [{"id": "c", "message": "Invalid nonce"}],
+            })
+        );
+    }
+}
@@ -0,0 +1,1023 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+//! The handlers of the REST API's endpoints.
+//!
+//! Each handler uses the data in a request to send a protobuf message to the
+//! validator, and answers with the validator's response formatted as JSON,
+//! exactly as the Python REST API does.
+
+use std::time::Duration;
+
+use protobuf::{Message as M, RepeatedField};
+use serde_json::{self, Map, Value};
+
+use sawtooth_sdk::messages::batch::BatchList;
+use sawtooth_sdk::messages::block::BlockHeader;
+use sawtooth_sdk::messages::client_batch::{
+    ClientBatchGetRequest, ClientBatchGetResponse, ClientBatchGetResponse_Status,
+    ClientBatchListRequest, ClientBatchListResponse, ClientBatchListResponse_Status,
+};
+use sawtooth_sdk::messages::client_batch_submit::{
+    ClientBatchStatusRequest, ClientBatchStatusResponse, ClientBatchStatusResponse_Status,
+    ClientBatchSubmitRequest, ClientBatchSubmitResponse, ClientBatchSubmitResponse_Status,
+};
+use sawtooth_sdk::messages::client_block::{
+    ClientBlockGetByIdRequest, ClientBlockGetResponse, ClientBlockGetResponse_Status,
+    ClientBlockListRequest, ClientBlockListResponse, ClientBlockListResponse_Status,
+};
+use sawtooth_sdk::messages::client_list_control::{
+    ClientPagingControls, ClientPagingResponse, ClientSortControls,
+};
+use sawtooth_sdk::messages::client_peers::{ClientPeersGetRequest, ClientPeersGetResponse};
+use sawtooth_sdk::messages::client_state::{
+    ClientStateGetRequest, ClientStateGetResponse, ClientStateGetResponse_Status,
+    ClientStateListRequest, ClientStateListResponse, ClientStateListResponse_Status,
+};
+use sawtooth_sdk::messages::client_status::{ClientStatusGetRequest, ClientStatusGetResponse};
+use sawtooth_sdk::messages::client_transaction::{
+    ClientTransactionGetRequest, ClientTransactionGetResponse, ClientTransactionGetResponse_Status,
+    ClientTransactionListRequest, ClientTransactionListResponse,
+    ClientTransactionListResponse_Status,
+};
+use sawtooth_sdk::messages::validator::Message_MessageType;
+
+use crate::connection::ValidatorConnection;
+use crate::error::ApiError;
+use crate::request::{Request, Response};
+use crate::resources;
+
+/// Seconds to wait for a validator response, unless configured otherwise.
+pub const DEFAULT_TIMEOUT: u64 = 300;
+
+/// Returns the error for a response status, if the status is one of the
+/// listed variants.
+macro_rules! check_status {
+    ($response:expr, $status:ident, { $($variant:ident => $error:expr,)* }) => {
+        match $response.get_status() {
+            $($status::$variant => return Err($error),)*
+            _ => (),
+        }
+    };
+}
+
+/// Returns the error for the statuses common to the listing responses.
+macro_rules! check_list_status {
+    ($response:expr, $status:ident) => {
+        check_status!($response, $status, {
+            INTERNAL_ERROR => ApiError::UnknownValidatorError,
+            NOT_READY => ApiError::ValidatorNotReady,
+            NO_ROOT => ApiError::HeadNotFound,
+            INVALID_PAGING => ApiError::PagingInvalid,
+            INVALID_SORT => ApiError::SortInvalid,
+        })
+    };
+}
+
+pub struct RouteHandler {
+    connection: Box<dyn ValidatorConnection>,
+    timeout: Duration,
+}
+
+impl RouteHandler {
+    pub fn new(connection: Box<dyn ValidatorConnection>, timeout: Duration) -> RouteHandler {
+        RouteHandler {
+            connection,
+            timeout,
+        }
+    }
+
+    /// Routes a request to its handler, answering with the JSON error of
+    /// any failure.
+    pub fn handle(&self, request: &Request) -> Response {
+        let segments: Vec<&str> = request.path.trim_start_matches('/').split('/').collect();
+
+        let result = match (request.method.as_str(), segments.as_slice()) {
+            ("POST", ["batches"]) => self.submit_batches(request),
+            ("GET", ["batch_statuses"]) | ("POST", ["batch_statuses"]) => {
+                self.list_statuses(request)
+            }
+            ("GET", ["state"]) => self.list_state(request),
+            ("GET", ["state", address]) if !address.is_empty() => {
+                self.fetch_state(request, address)
+            }
+            ("GET", ["blocks"]) => self.list_blocks(request),
+            ("GET", ["blocks", block_id]) if !block_id.is_empty() => {
+                self.fetch_block(request, block_id)
+            }
+            ("GET", ["batches"]) => self.list_batches(request),
+            ("GET", ["batches", batch_id]) if !batch_id.is_empty() => {
+                self.fetch_batch(request, batch_id)
+            }
+            ("GET", ["transactions"]) => self.list_transactions(request),
+            ("GET", ["transactions", transaction_id]) if !transaction_id.is_empty() => {
+                self.fetch_transaction(request, transaction_id)
+            }
+            ("GET", ["peers"]) => self.fetch_peers(request),
+            ("GET", ["status"]) => self.fetch_status(request),
+            (_, segments) if is_route(segments) => {
+                return Response::text(405, "405: Method Not Allowed");
+            }
+            _ => return Response::text(404, "404: Not Found"),
+        };
+
+        result.unwrap_or_else(|err| {
+            debug!(
+                "Responding to {} {} with {:?}",
+                request.method, request.path, err
+            );
+            Response::json(err.status_code(), &err.to_json())
+        })
+    }
+
+    /// Accepts a binary encoded BatchList and submits it to the validator,
+    /// answering with a link to the status of the submitted batches.
+    fn submit_batches(&self, request: &Request) -> Result<Response, ApiError> {
+        if request.content_type() != Some("application/octet-stream") {
+            debug!(
+                "Submission headers had wrong Content-Type: {:?}",
+                request.content_type()
+            );
+            return Err(ApiError::SubmissionWrongContentType);
+        }
+
+        if request.body.is_empty() {
+            debug!("Submission contained an empty body");
+            return Err(ApiError::NoBatchesSubmitted);
+        }
+
+        let mut batch_list = BatchList::parse_from_bytes(&request.body).map_err(|err| {
+            debug!("Submission body could not be decoded: {}", err);
+            ApiError::BadProtobufSubmitted
+        })?;
+
+        let ids = batch_list
+            .get_batches()
+            .iter()
+            .map(|batch| batch.get_header_signature())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let mut validator_query = ClientBatchSubmitRequest::new();
+        validator_query.set_batches(batch_list.take_batches());
+
+        let response: ClientBatchSubmitResponse = self.query_validator(
+            Message_MessageType::CLIENT_BATCH_SUBMIT_REQUEST,
+            &validator_query,
+        )?;
+        check_status!(response, ClientBatchSubmitResponse_Status, {
+            INTERNAL_ERROR => ApiError::UnknownValidatorError,
+            INVALID_BATCH => ApiError::SubmittedBatchesInvalid,
+            QUEUE_FULL => ApiError::BatchQueueFull,
+        });
+
+        let mut metadata = Map::new();
+        metadata.insert(
+            "link".into(),
+            request
+                .build_url(Some("/batch_statuses"), &[("id", Some(ids))])
+                .into(),
+        );
+
+        Ok(wrap_response(None, metadata, 202))
+    }
+
+    /// Fetches the committed status of batches, whose ids are either a JSON
+    /// array in the body of a POST, or the id query of a GET.
+    fn list_statuses(&self, request: &Request) -> Result<Response, ApiError> {
+        let ids = if request.method == "POST" {
+            if request.content_type() != Some("application/json") {
+                debug!(
+                    "Request headers had wrong Content-Type: {:?}",
+                    request.content_type()
+                );
+                return Err(ApiError::StatusWrongContentType);
+            }
+
+            let ids = parse_id_list(&request.body).ok_or_else(|| {
+                debug!("Request body was invalid");
+                ApiError::StatusBodyInvalid
+            })?;
+            for id in &ids {
+                validate_id(id)?;
+            }
+            ids
+        } else {
+            match get_filter_ids(request)? {
+                Some(ids) => ids,
+                None => {
+                    debug!("Request for statuses missing id query");
+                    return Err(ApiError::StatusIdQueryInvalid);
+                }
+            }
+        };
+
+        let mut validator_query = ClientBatchStatusRequest::new();
+        validator_query.set_batch_ids(RepeatedField::from_vec(ids));
+        if let Some(wait) = request.query("wait") {
+            if wait.to_lowercase() != "false" {
+                validator_query.set_wait(true);
+                // By default, waits for 95% of the configured timeout
+                validator_query.set_timeout(
+                    wait.parse()
+                        .unwrap_or((self.timeout.as_secs() * 95 / 100) as u32),
+                );
+            }
+        }
+
+        let response: ClientBatchStatusResponse = self.query_validator(
+            Message_MessageType::CLIENT_BATCH_STATUS_REQUEST,
+            &validator_query,
+        )?;
+        check_status!(response, ClientBatchStatusResponse_Status, {
+            INTERNAL_ERROR => ApiError::UnknownValidatorError,
+            NO_RESOURCE => ApiError::StatusResponseMissing,
+        });
+
+        let metadata = if request.method == "POST" {
+            Map::new()
+        } else {
+            get_metadata(request, None)
+        };
+        let data = response
+            .get_batch_statuses()
+            .iter()
+            .map(resources::batch_status)
+            .collect();
+
+        Ok(wrap_response(Some(Value::Array(data)), metadata, 200))
+    }
+
+    /// Fetches a page of state entries, optionally filtered by an address
+    /// prefix.
+    fn list_state(&self, request: &Request) -> Result<Response, ApiError> {
+        let paging_controls = get_paging_controls(request)?;
+        let (head, root) = self.head_to_root(request.query("head"))?;
+
+        let mut validator_query = ClientStateListRequest::new();
+        validator_query.set_state_root(root);
+        if let Some(address) = request.query("address") {
+            validator_query.set_address(address.into());
+        }
+        validator_query.set_sorting(get_sorting_message(request, "default"));
+        validator_query.set_paging(paging_controls.to_message());
+
+        let response: ClientStateListResponse = self.query_validator(
+            Message_MessageType::CLIENT_STATE_LIST_REQUEST,
+            &validator_query,
+        )?;
+        check_list_status!(response, ClientStateListResponse_Status);
+
+        let data = response
+            .get_entries()
+            .iter()
+            .map(resources::state_entry)
+            .collect();
+
+        Ok(wrap_paginated_response(
+            request,
+            response.get_paging(),
+            &paging_controls,
+            data,
+            &head,
+        ))
+    }
+
+    /// Fetches the data at an address, base64 encoded.
+    fn fetch_state(&self, request: &Request, address: &str) -> Result<Response, ApiError> {
+        let (head, root) = self.head_to_root(request.query("head"))?;
+
+        let mut validator_query = ClientStateGetRequest::new();
+        validator_query.set_state_root(root);
+        validator_query.set_address(address.into());
+
+        let response: ClientStateGetResponse = self.query_validator(
+            Message_MessageType::CLIENT_STATE_GET_REQUEST,
+            &validator_query,
+        )?;
+        check_status!(response, ClientStateGetResponse_Status, {
+            INTERNAL_ERROR => ApiError::UnknownValidatorError,
+            NOT_READY => ApiError::ValidatorNotReady,
+            NO_ROOT => ApiError::HeadNotFound,
+            INVALID_ADDRESS => ApiError::InvalidStateAddress,
+            NO_RESOURCE => ApiError::StateNotFound,
+        });
+
+        Ok(wrap_response(
+            Some(base64::encode(response.get_value()).into()),
+            get_metadata(request, Some(&head)),
+            200,
+        ))
+    }
+
+    /// Fetches a page of blocks, optionally filtered by id.
+    fn list_blocks(&self, request: &Request) -> Result<Response, ApiError> {
+        let paging_controls = get_paging_controls(request)?;
+
+        let mut validator_query = ClientBlockListRequest::new();
+        if let Some(head_id) = get_head_id(request)? {
+            validator_query.set_head_id(head_id);
+        }
+        if let Some(ids) = get_filter_ids(request)? {
+            validator_query.set_block_ids(RepeatedField::from_vec(ids));
+        }
+        validator_query.set_sorting(get_sorting_message(request, "block_num"));
+        validator_query.set_paging(paging_controls.to_message());
+
+        let response: ClientBlockListResponse = self.query_validator(
+            Message_MessageType::CLIENT_BLOCK_LIST_REQUEST,
+            &validator_query,
+        )?;
+        check_list_status!(response, ClientBlockListResponse_Status);
+
+        let data = response
+            .get_blocks()
+            .iter()
+            .map(resources::expand_block)
+            .collect::<Result<_, _>>()?;
+
+        Ok(wrap_paginated_response(
+            request,
+            response.get_paging(),
+            &paging_controls,
+            data,
+            response.get_head_id(),
+        ))
+    }
+
+    fn fetch_block(&self, request: &Request, block_id: &str) -> Result<Response, ApiError> {
+        validate_id(block_id)?;
+
+        let mut validator_query = ClientBlockGetByIdRequest::new();
+        validator_query.set_block_id(block_id.into());
+
+        let response: ClientBlockGetResponse = self.query_validator(
+            Message_MessageType::CLIENT_BLOCK_GET_BY_ID_REQUEST,
+            &validator_query,
+        )?;
+        check_status!(response, ClientBlockGetResponse_Status, {
+            INTERNAL_ERROR => ApiError::UnknownValidatorError,
+            NO_RESOURCE => ApiError::BlockNotFound,
+        });
+
+        Ok(wrap_response(
+            Some(resources::expand_block(response.get_block())?),
+            get_metadata(request, None),
+            200,
+        ))
+    }
+
+    /// Fetches a page of batches, optionally filtered by id.
+    fn list_batches(&self, request: &Request) -> Result<Response, ApiError> {
+        let paging_controls = get_paging_controls(request)?;
+
+        let mut validator_query = ClientBatchListRequest::new();
+        if let Some(head_id) = get_head_id(request)? {
+            validator_query.set_head_id(head_id);
+        }
+        if let Some(ids) = get_filter_ids(request)? {
+            validator_query.set_batch_ids(RepeatedField::from_vec(ids));
+        }
+        validator_query.set_sorting(get_sorting_message(request, "default"));
+        validator_query.set_paging(paging_controls.to_message());
+
+        let response: ClientBatchListResponse = self.query_validator(
+            Message_MessageType::CLIENT_BATCH_LIST_REQUEST,
+            &validator_query,
+        )?;
+        check_list_status!(response, ClientBatchListResponse_Status);
+
+        let data = response
+            .get_batches()
+            .iter()
+            .map(resources::expand_batch)
+            .collect::<Result<_, _>>()?;
+
+        Ok(wrap_paginated_response(
+            request,
+            response.get_paging(),
+            &paging_controls,
+            data,
+            response.get_head_id(),
+        ))
+    }
+
+    fn fetch_batch(&self, request: &Request, batch_id: &str) -> Result<Response, ApiError> {
+        validate_id(batch_id)?;
+
+        let mut validator_query = ClientBatchGetRequest::new();
+        validator_query.set_batch_id(batch_id.into());
+
+        let response: ClientBatchGetResponse = self.query_validator(
+            Message_MessageType::CLIENT_BATCH_GET_REQUEST,
+            &validator_query,
+        )?;
+        check_status!(response, ClientBatchGetResponse_Status, {
+            INTERNAL_ERROR => ApiError::UnknownValidatorError,
+            NO_RESOURCE => ApiError::BatchNotFound,
+        });
+
+        Ok(wrap_response(
+            Some(resources::expand_batch(response.get_batch())?),
+            get_metadata(request, None),
+            200,
+        ))
+    }
+
+    /// Fetches a page of transactions, optionally filtered by id.
+    fn list_transactions(&self, request: &Request) -> Result<Response, ApiError> {
+        let paging_controls = get_paging_controls(request)?;
+
+        let mut validator_query = ClientTransactionListRequest::new();
+        if let Some(head_id) = get_head_id(request)? {
+            validator_query.set_head_id(head_id);
+        }
+        if let Some(ids) = get_filter_ids(request)? {
+            validator_query.set_transaction_ids(RepeatedField::from_vec(ids));
+        }
+        validator_query.set_sorting(get_sorting_message(request, "default"));
+        validator_query.set_paging(paging_controls.to_message());
+
+        let response: ClientTransactionListResponse = self.query_validator(
+            Message_MessageType::CLIENT_TRANSACTION_LIST_REQUEST,
+            &validator_query,
+        )?;
+        check_list_status!(response, ClientTransactionListResponse_Status);
+
+        let data = response
+            .get_transactions()
+            .iter()
+            .map(resources::expand_transaction)
+            .collect::<Result<_, _>>()?;
+
+        Ok(wrap_paginated_response(
+            request,
+            response.get_paging(),
+            &paging_controls,
+            data,
+            response.get_head_id(),
+        ))
+    }
+
+    fn fetch_transaction(
+        &self,
+        request: &Request,
+        transaction_id: &str,
+    ) -> Result<Response, ApiError> {
+        validate_id(transaction_id)?;
+
+        let mut validator_query = ClientTransactionGetRequest::new();
+        validator_query.set_transaction_id(transaction_id.into());
+
+        let response: ClientTransactionGetResponse = self.query_validator(
+            Message_MessageType::CLIENT_TRANSACTION_GET_REQUEST,
+            &validator_query,
+        )?;
+        check_status!(response, ClientTransactionGetResponse_Status, {
+            INTERNAL_ERROR => ApiError::UnknownValidatorError,
+            NO_RESOURCE => ApiError::TransactionNotFound,
+        });
+
+        Ok(wrap_response(
+            Some(resources::expand_transaction(response.get_transaction())?),
+            get_metadata(request, None),
+            200,
+        ))
+    }
+
+    /// Fetches the endpoints of the validator's peers.
+    fn fetch_peers(&self, request: &Request) -> Result<Response, ApiError> {
+        let response: ClientPeersGetResponse = self.query_validator(
+            Message_MessageType::CLIENT_PEERS_GET_REQUEST,
+            &ClientPeersGetRequest::new(),
+        )?;
+
+        Ok(wrap_response(
+            Some(json!(response.get_peers())),
+            get_metadata(request, None),
+            200,
+        ))
+    }
+
+    /// Fetches the validator's endpoint and the endpoints of its peers.
+    fn fetch_status(&self, request: &Request) -> Result<Response, ApiError> {
+        let response: ClientStatusGetResponse = self.query_validator(
+            Message_MessageType::CLIENT_STATUS_GET_REQUEST,
+            &ClientStatusGetRequest::new(),
+        )?;
+
+        let peers = response
+            .get_peers()
+            .iter()
+            .map(|peer| json!({ "endpoint": peer.get_endpoint() }))
+            .collect::<Vec<_>>();
+
+        Ok(wrap_response(
+            Some(json!({
+                "peers": peers,
+                "endpoint": response.get_endpoint(),
+            })),
+            get_metadata(request, None),
+            200,
+        ))
+    }
+
+    /// Returns the id and state root hash of the block with the given id,
+    /// or of the chain head if block_id is None.
+    fn head_to_root(&self, block_id: Option<&str>) -> Result<(String, String), ApiError> {
+        let block = match block_id {
+            Some(block_id) => {
+                let mut validator_query = ClientBlockGetByIdRequest::new();
+                validator_query.set_block_id(block_id.into());
+
+                let mut response: ClientBlockGetResponse = self.query_validator(
+                    Message_MessageType::CLIENT_BLOCK_GET_BY_ID_REQUEST,
+                    &validator_query,
+                )?;
+                check_status!(response, ClientBlockGetResponse_Status, {
+                    INTERNAL_ERROR => ApiError::UnknownValidatorError,
+                    NO_RESOURCE => ApiError::BlockNotFound,
+                });
+                response.take_block()
+            }
+            None => {
+                let mut paging = ClientPagingControls::new();
+                paging.set_limit(1);
+                let mut validator_query = ClientBlockListRequest::new();
+                validator_query.set_paging(paging);
+
+                let mut response: ClientBlockListResponse = self.query_validator(
+                    Message_MessageType::CLIENT_BLOCK_LIST_REQUEST,
+                    &validator_query,
+                )?;
+                check_list_status!(response, ClientBlockListResponse_Status);
+                check_status!(response, ClientBlockListResponse_Status, {
+                    NO_RESOURCE => ApiError::BlockNotFound,
+                });
+                if response.get_blocks().is_empty() {
+                    return Err(ApiError::ValidatorNotReady);
+                }
+                response.take_blocks().remove(0)
+            }
+        };
+
+        let header = BlockHeader::parse_from_bytes(block.get_header()).map_err(|err| {
+            error!("The validator sent a block with an invalid header: {}", err);
+            ApiError::ResourceHeaderInvalid
+        })?;
+
+        Ok((
+            block.get_header_signature().to_string(),
+            header.get_state_root_hash().to_string(),
+        ))
+    }
+
+    /// Sends a request to the validator and parses the response.
+    fn query_validator<Req: M, Resp: M>(
+        &self,
+        message_type: Message_MessageType,
+        request: &Req,
+    ) -> Result<Resp, ApiError> {
+        let content = request.write_to_bytes().map_err(|err| {
+            error!("Unable to serialize {:?} request: {}", message_type, err);
+            ApiError::UnknownValidatorError
+        })?;
+
+        let response = self.connection.send(message_type, &content, self.timeout)?;
+
+        Resp::parse_from_bytes(&response).map_err(|err| {
+            error!("Validator response was not parsable: {}", err);
+            ApiError::ValidatorResponseInvalid
+        })
+    }
+}
+
+/// Whether the path is served by the REST API, for any method.
+fn is_route(segments: &[&str]) -> bool {
+    match segments {
+        ["batches"]
+        | ["batch_statuses"]
+        | ["state"]
+        | ["blocks"]
+        | ["transactions"]
+        | ["peers"]
+        | ["status"] => true,
+        ["state", id] | ["blocks", id] | ["batches", id] | ["transactions", id] => !id.is_empty(),
+        _ => false,
+    }
+}
+
+/// The start and limit queries of a listing.
+struct PagingControls {
+    start: Option<String>,
+    limit: Option<i32>,
+}
+
+impl PagingControls {
+    fn to_message(&self) -> ClientPagingControls {
+        let mut paging = ClientPagingControls::new();
+        if let Some(ref start) = self.start {
+            paging.set_start(start.clone());
+        }
+        if let Some(limit) = self.limit {
+            paging.set_limit(limit);
+        }
+        paging
+    }
+}
+
+fn get_paging_controls(request: &Request) -> Result<PagingControls, ApiError> {
+    let limit = match request.query("limit") {
+        Some(limit) => match limit.trim().parse::<i32>() {
+            Ok(limit) if limit > 0 => Some(limit),
+            _ => {
+                debug!("Request query had an invalid limit: {}", limit);
+                return Err(ApiError::CountInvalid);
+            }
+        },
+        None => None,
+    };
+
+    Ok(PagingControls {
+        start: request.query("start").map(String::from),
+        limit,
+    })
+}
+
+/// Parses the reverse query into sort controls. A bare reverse query
+/// reverses the listing by the given default key.
+fn get_sorting_message(request: &Request, key: &str) -> RepeatedField<ClientSortControls> {
+    let keys = match request.query("reverse") {
+        Some("") => Some(key),
+        Some(reverse) if reverse.to_lowercase() != "false" => Some(reverse),
+        _ => None,
+    };
+
+    let mut controls = RepeatedField::new();
+    if let Some(keys) = keys {
+        let mut control = ClientSortControls::new();
+        control.set_reverse(true);
+        control.set_keys(keys.split(',').map(String::from).collect());
+        controls.push(control);
+    }
+    controls
+}
+
+/// Fetches the request's head query, and validates it if present.
+fn get_head_id(request: &Request) -> Result<Option<String>, ApiError> {
+    match request.query("head") {
+        Some(head_id) => {
+            validate_id(head_id)?;
+            Ok(Some(head_id.into()))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Parses the comma separated id query, validating each id.
+fn get_filter_ids(request: &Request) -> Result<Option<Vec<String>>, ApiError> {
+    match request.query("id") {
+        Some(id_query) => {
+            let ids: Vec<String> = id_query.split(',').map(String::from).collect();
+            for id in &ids {
+                validate_id(id)?;
+            }
+            Ok(Some(ids))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Parses a JSON array of at least one id string.
+fn parse_id_list(body: &[u8]) -> Option<Vec<String>> {
+    match serde_json::from_slice(body) {
+        Ok(Value::Array(ref ids)) if !ids.is_empty() => {
+            ids.iter().map(|id| id.as_str().map(String::from)).collect()
+        }
+        _ => None,
+    }
+}
+
+/// Confirms a header signature is 128 lowercase hex characters.
+pub(crate) fn validate_id(resource_id: &str) -> Result<(), ApiError> {
+    if resource_id.len() == 128
+        && resource_id
+            .chars()
+            .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))
+    {
+        Ok(())
+    } else {
+        Err(ApiError::InvalidResourceId(resource_id.into()))
+    }
+}
+
+/// The link to the request, and the head it was answered at if any.
+fn get_metadata(request: &Request, head: Option<&str>) -> Map<String, Value> {
+    let mut metadata = Map::new();
+    metadata.insert(
+        "link".into(),
+        request
+            .build_url(None, &[("head", head.map(String::from))])
+            .into(),
+    );
+    if let Some(head) = head {
+        metadata.insert("head".into(), head.into());
+    }
+    metadata
+}
+
+fn wrap_response(data: Option<Value>, mut metadata: Map<String, Value>, status: u16) -> Response {
+    if let Some(data) = data {
+        metadata.insert("data".into(), data);
+    }
+    Response::json(status, &Value::Object(metadata))
+}
+
+/// Wraps a page of a listing with its head, link and paging metadata,
+/// including a link to the next page if there is one.
+fn wrap_paginated_response(
+    request: &Request,
+    paging_response: &ClientPagingResponse,
+    controls: &PagingControls,
+    data: Vec<Value>,
+    head: &str,
+) -> Response {
+    let link = request.build_url(
+        None,
+        &[
+            ("head", Some(head.into())),
+            ("start", Some(paging_response.get_start().into())),
+            ("limit", Some(paging_response.get_limit().to_string())),
+        ],
+    );
+
+    let mut paging = Map::new();
+    paging.insert("limit".into(), json!(controls.limit));
+    paging.insert("start".into(), json!(controls.start));
+
+    // If there are no more resources, there is nothing else in paging
+    let next = paging_response.get_next();
+    if !next.is_empty() {
+        paging.insert("next_position".into(), next.into());
+        paging.insert(
+            "next".into(),
+            request
+                .build_url(
+                    None,
+                    &[
+                        ("head", Some(head.into())),
+                        ("limit", controls.limit.map(|limit| limit.to_string())),
+                        ("start", Some(next.into())),
+                    ],
+                )
+                .into(),
+        );
+    }
+
+    let mut metadata = Map::new();
+    metadata.insert("head".into(), head.into());
+    metadata.insert("link".into(), link.into());
+    metadata.insert("paging".into(), Value::Object(paging));
+
+    wrap_response(Some(Value::Array(data)), metadata, 200)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    use sawtooth_sdk::messages::batch::Batch;
+    use sawtooth_sdk::messages::block::Block;
+
+    const HEAD: &str = "\
+        aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa\
+        aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+    const BATCH_ID: &str = "\
+        bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb\
+        bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb";
+
+    /// Answers each type of request with a fixed response, recording the
+    /// requests it was sent.
+    struct MockConnection {
+        responses: HashMap<Message_MessageType, Vec<u8>>,
+        sent: Mutex<Vec<(Message_MessageType, Vec<u8>)>>,
+    }
+
+    impl MockConnection {
+        fn new() -> MockConnection {
+            MockConnection {
+                responses: HashMap::new(),
+                sent: Mutex::new(vec![]),
+            }
+        }
+
+        fn respond<Resp: M>(mut self, message_type: Message_MessageType, response: Resp) -> Self {
+            self.responses
+                .insert(message_type, response.write_to_bytes().unwrap());
+            self
+        }
+    }
+
+    impl ValidatorConnection for MockConnection {
+        fn send(
+            &self,
+            message_type: Message_MessageType,
+            content: &[u8],
+            _timeout: Duration,
+        ) -> Result<Vec<u8>, ApiError> {
+            self.sent
+                .lock()
+                .unwrap()
+                .push((message_type, content.to_vec()));
+            self.responses
+                .get(&message_type)
+                .cloned()
+                .ok_or(ApiError::ValidatorTimedOut)
+        }
+    }
+
+    fn handler(connection: MockConnection) -> RouteHandler {
+        RouteHandler::new(Box::new(connection), Duration::from_secs(DEFAULT_TIMEOUT))
+    }
+
+    fn body(response: &Response) -> Value {
+        serde_json::from_slice(&response.body).unwrap()
+    }
+
+    #[test]
+    fn test_submit_batches() {
+        let mut response = ClientBatchSubmitResponse::new();
+        response.set_status(ClientBatchSubmitResponse_Status::OK);
+        let handler = handler(
+            MockConnection::new()
+                .respond(Message_MessageType::CLIENT_BATCH_SUBMIT_REQUEST, response),
+        );
+
+        let mut batch = Batch::new();
+        batch.set_header_signature(BATCH_ID.into());
+        let mut batch_list = BatchList::new();
+        batch_list.set_batches(RepeatedField::from_vec(vec![batch]));
+
+        let request = Request::new("POST", "/batches")
+            .with_header("Host", "localhost:8008")
+            .with_header("Content-Type", "application/octet-stream")
+            .with_body(batch_list.write_to_bytes().unwrap());
+        let response = handler.handle(&request);
+
+        assert_eq!(response.status, 202);
+        assert_eq!(
+            body(&response),
+            json!({
+                "link": format!("http://localhost:8008/batch_statuses?id={}", BATCH_ID)
+            })
+        );
+
+        let request = Request::new("POST", "/batches").with_header("Content-Type", "text/plain");
+        let response = handler.handle(&request);
+        assert_eq!(response.status, 400);
+        assert_eq!(body(&response)["error"]["code"], 42);
+    }
+
+    #[test]
+    fn test_submit_batches_queue_full() {
+        let mut response = ClientBatchSubmitResponse::new();
+        response.set_status(ClientBatchSubmitResponse_Status::QUEUE_FULL);
+        let handler = handler(
+            MockConnection::new()
+                .respond(Message_MessageType::CLIENT_BATCH_SUBMIT_REQUEST, response),
+        );
+
+        let request = Request::new("POST", "/batches")
+            .with_header("Content-Type", "application/octet-stream")
+            .with_body(BatchList::new().write_to_bytes().unwrap());
+        assert_eq!(handler.handle(&request).status, 400);
+
+        let mut batch_list = BatchList::new();
+        batch_list.set_batches(RepeatedField::from_vec(vec![Batch::new()]));
+        let request = Request::new("POST", "/batches")
+            .with_header("Content-Type", "application/octet-stream")
+            .with_body(batch_list.write_to_bytes().unwrap());
+        let response = handler.handle(&request);
+
+        assert_eq!(response.status, 429);
+        assert_eq!(body(&response)["error"]["code"], 31);
+    }
+
+    #[test]
+    fn test_list_blocks_paging() {
+        let mut header = BlockHeader::new();
+        header.set_block_num(3);
+        let mut block = Block::new();
+        block.set_header(header.write_to_bytes().unwrap());
+        block.set_header_signature(HEAD.into());
+
+        let mut paging = ClientPagingResponse::new();
+        paging.set_start("0x0003".into());
+        paging.set_limit(1);
+        paging.set_next("0x0002".into());
+
+        let mut response = ClientBlockListResponse::new();
+        response.set_status(ClientBlockListResponse_Status::OK);
+        response.set_blocks(RepeatedField::from_vec(vec![block]));
+        response.set_head_id(HEAD.into());
+        response.set_paging(paging);
+
+        let connection =
+            MockConnection::new().respond(Message_MessageType::CLIENT_BLOCK_LIST_REQUEST, response);
+        let handler = handler(connection);
+
+        let request = Request::new("GET", "/blocks?limit=1&reverse").with_header("Host", "api");
+        let response = handler.handle(&request);
+        assert_eq!(response.status, 200);
+
+        let body = body(&response);
+        assert_eq!(body["head"], HEAD);
+        assert_eq!(body["data"][0]["header"]["block_num"], "3");
+        assert_eq!(
+            body["link"],
+            format!(
+                "http://api/blocks?head={}&start=0x0003&limit=1&reverse",
+                HEAD
+            )
+        );
+        assert_eq!(body["paging"]["limit"], 1);
+        assert_eq!(body["paging"]["start"], Value::Null);
+        assert_eq!(body["paging"]["next_position"], "0x0002");
+        assert_eq!(
+            body["paging"]["next"],
+            format!(
+                "http://api/blocks?head={}&start=0x0002&limit=1&reverse",
+                HEAD
+            )
+        );
+    }
+
+    #[test]
+    fn test_invalid_queries() {
+        let handler = handler(MockConnection::new());
+
+        let response = handler.handle(&Request::new("GET", "/blocks?limit=0"));
+        assert_eq!(body(&response)["error"]["code"], 53);
+
+        let response = handler.handle(&Request::new("GET", "/batches?id=abc"));
+        assert_eq!(body(&response)["error"]["code"], 60);
+
+        let response = handler.handle(&Request::new("GET", "/batch_statuses"));
+        assert_eq!(body(&response)["error"]["code"], 66);
+
+        let request = Request::new("POST", "/batch_statuses")
+            .with_header("Content-Type", "application/json")
+            .with_body(b"[]".to_vec());
+        assert_eq!(body(&handler.handle(&request))["error"]["code"], 46);
+    }
+
+    #[test]
+    fn test_routing() {
+        let handler = handler(MockConnection::new());
+
+        assert_eq!(handler.handle(&Request::new("GET", "/receipt")).status, 404);
+        assert_eq!(handler.handle(&Request::new("GET", "/blocks/")).status, 404);
+        assert_eq!(
+            handler.handle(&Request::new("DELETE", "/blocks")).status,
+            405
+        );
+
+        // The mock validator never answers the status request
+        assert_eq!(handler.handle(&Request::new("GET", "/status")).status, 503);
+    }
+
+    #[test]
+    fn test_sorting() {
+        let request = Request::new("GET", "/blocks?reverse");
+        let sorting = get_sorting_message(&request, "block_num");
+        assert_eq!(sorting.len(), 1);
+        assert_eq!(sorting[0].get_keys(), &["block_num".to_string()]);
+
+        let request = Request::new("GET", "/state?reverse=address,value");
+        let sorting = get_sorting_message(&request, "default");
+        assert_eq!(
+            sorting[0].get_keys(),
+            &["address".to_string(), "value".to_string()]
+        );
+
+        let request = Request::new("GET", "/state?reverse=False");
+        assert!(get_sorting_message(&request, "default").is_empty());
+    }
+}
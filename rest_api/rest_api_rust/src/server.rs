@@ -0,0 +1,342 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+//! Serves the routes over HTTP or HTTPS.
+
+use std::error;
+use std::fmt;
+use std::io::Read;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+
+use crate::middleware::{BasicAuth, Cors};
+use crate::request::{Request, Response};
+use crate::routes::RouteHandler;
+use crate::subscriptions::{Subscription, SubscriptionHandler};
+
+/// The number of threads serving the requests to each bound address. Each
+/// thread serves one request at a time, for as long as the validator takes
+/// to respond.
+const WORKER_THREADS: usize = 16;
+
+#[derive(Debug)]
+pub struct ServerError(pub String);
+
+impl fmt::Display for ServerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl error::Error for ServerError {}
+
+/// The PEM encoded certificate and private key to serve HTTPS with.
+#[derive(Clone)]
+pub struct TlsConfig {
+    pub certificate: Vec<u8>,
+    pub private_key: Vec<u8>,
+}
+
+pub struct RestApiServer {
+    handler: RouteHandler,
+    subscriptions: SubscriptionHandler,
+    cors: Cors,
+    auth: Option<BasicAuth>,
+    client_max_size: usize,
+}
+
+impl RestApiServer {
+    pub fn new(
+        handler: RouteHandler,
+        subscriptions: SubscriptionHandler,
+        cors: Cors,
+        auth: Option<BasicAuth>,
+        client_max_size: usize,
+    ) -> RestApiServer {
+        RestApiServer {
+            handler,
+            subscriptions,
+            cors,
+            auth,
+            client_max_size,
+        }
+    }
+
+    /// Answers a request: CORS preflight requests are answered directly, and
+    /// any other request is routed if its credentials are accepted.
+    pub fn respond(&self, request: &Request) -> Response {
+        if let Some(response) = self.cors.preflight(request) {
+            return response;
+        }
+
+        let response = match self.authorize(request) {
+            Err(response) => response,
+            Ok(()) => self.handler.handle(request),
+        };
+
+        self.cors.apply(request, response)
+    }
+
+    /// Subscribes an authorized GET request to `/subscriptions` to the
+    /// validator's events, returning the response that starts its event
+    /// stream. Any other request, or a failed subscription, is answered with
+    /// an error response instead.
+    pub fn subscribe(&self, request: &Request) -> Result<(Subscription, Response), Response> {
+        if let Some(response) = self.cors.preflight(request) {
+            return Err(response);
+        }
+
+        let result = self.authorize(request).and_then(|()| {
+            if request.method != "GET" {
+                return Err(Response::text(405, "405: Method Not Allowed"));
+            }
+            self.subscriptions.subscribe(request).map_err(|err| {
+                debug!("Unable to subscribe to events: {:?}", err);
+                Response::json(err.status_code(), &err.to_json())
+            })
+        });
+
+        match result {
+            Ok(subscription) => Ok((
+                subscription,
+                self.cors.apply(request, Subscription::response()),
+            )),
+            Err(response) => Err(self.cors.apply(request, response)),
+        }
+    }
+
+    fn authorize(&self, request: &Request) -> Result<(), Response> {
+        match self.auth {
+            Some(ref auth) => auth.authorize(request),
+            None => Ok(()),
+        }
+    }
+
+    /// Starts serving requests to the address, over HTTPS if tls is set,
+    /// returning the serving threads.
+    pub fn serve(
+        server: &Arc<RestApiServer>,
+        bind: &str,
+        tls: Option<TlsConfig>,
+    ) -> Result<Vec<JoinHandle<()>>, ServerError> {
+        let (http_server, scheme) = match tls {
+            Some(tls) => (
+                tiny_http::Server::https(
+                    bind,
+                    tiny_http::SslConfig {
+                        certificate: tls.certificate,
+                        private_key: tls.private_key,
+                    },
+                ),
+                "https",
+            ),
+            None => (tiny_http::Server::http(bind), "http"),
+        };
+        let http_server = Arc::new(
+            http_server
+                .map_err(|err| ServerError(format!("Unable to bind to {}: {}", bind, err)))?,
+        );
+
+        info!("Starting REST API on {}://{}", scheme, bind);
+
+        Ok((0..WORKER_THREADS)
+            .map(|_| {
+                let http_server = http_server.clone();
+                let server = server.clone();
+                thread::spawn(move || loop {
+                    match http_server.recv() {
+                        Ok(request) => server.serve_request(request, scheme),
+                        Err(err) => {
+                            error!("Unable to receive request: {}", err);
+                            break;
+                        }
+                    }
+                })
+            })
+            .collect())
+    }
+
+    fn serve_request(&self, mut http_request: tiny_http::Request, scheme: &str) {
+        let start = Instant::now();
+
+        let response = match self.read_request(&mut http_request, scheme) {
+            Ok(ref request) if request.path == "/subscriptions" => {
+                match self.subscribe(request) {
+                    Ok((subscription, response)) => {
+                        info!(
+                            "{} {}: streaming events",
+                            request.method,
+                            http_request.url()
+                        );
+                        // The stream lasts as long as the client is connected,
+                        // so it is given a thread of its own.
+                        thread::spawn(move || {
+                            let mut writer = http_request.into_writer();
+                            subscription.stream(&mut writer, &response);
+                        });
+                        return;
+                    }
+                    Err(response) => response,
+                }
+            }
+            Ok(request) => self.respond(&request),
+            Err(response) => response,
+        };
+
+        let elapsed = start.elapsed();
+        info!(
+            "{} {}: {} status, {} size, in {}.{:06} s",
+            http_request.method(),
+            http_request.url(),
+            response.status,
+            response.body.len(),
+            elapsed.as_secs(),
+            elapsed.subsec_micros()
+        );
+
+        let mut http_response =
+            tiny_http::Response::from_data(response.body).with_status_code(response.status);
+        for (name, value) in &response.headers {
+            match tiny_http::Header::from_bytes(name.as_bytes(), value.as_bytes()) {
+                Ok(header) => http_response.add_header(header),
+                Err(_) => warn!("Dropping invalid response header {}", name),
+            }
+        }
+
+        if let Err(err) = http_request.respond(http_response) {
+            debug!("Unable to send response: {}", err);
+        }
+    }
+
+    /// Reads a request, answering with an error if its body is too large or
+    /// cannot be read.
+    fn read_request(
+        &self,
+        http_request: &mut tiny_http::Request,
+        scheme: &str,
+    ) -> Result<Request, Response> {
+        let too_large = || Response::text(413, "413: Request Entity Too Large");
+
+        if http_request.body_length().unwrap_or(0) > self.client_max_size {
+            return Err(too_large());
+        }
+
+        let mut body = vec![];
+        http_request
+            .as_reader()
+            .take(self.client_max_size as u64 + 1)
+            .read_to_end(&mut body)
+            .map_err(|err| {
+                debug!("Unable to read request body: {}", err);
+                Response::text(400, "400: Bad Request")
+            })?;
+        if body.len() > self.client_max_size {
+            return Err(too_large());
+        }
+
+        let mut request = Request::new(http_request.method().as_str(), http_request.url())
+            .with_scheme(scheme)
+            .with_body(body);
+        for header in http_request.headers() {
+            request = request.with_header(header.field.as_str().as_str(), header.value.as_str());
+        }
+
+        Ok(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    use sawtooth_sdk::messages::validator::Message_MessageType;
+
+    use crate::connection::ValidatorConnection;
+    use crate::error::ApiError;
+
+    struct UnavailableConnection;
+
+    impl ValidatorConnection for UnavailableConnection {
+        fn send(
+            &self,
+            _message_type: Message_MessageType,
+            _content: &[u8],
+            _timeout: Duration,
+        ) -> Result<Vec<u8>, ApiError> {
+            Err(ApiError::ValidatorDisconnected)
+        }
+    }
+
+    fn server(auth: Option<BasicAuth>) -> RestApiServer {
+        RestApiServer::new(
+            RouteHandler::new(Box::new(UnavailableConnection), Duration::from_secs(1)),
+            SubscriptionHandler::new("tcp://localhost:4004", Duration::from_secs(1)),
+            Cors::new(vec!["*".into()]),
+            auth,
+            1024,
+        )
+    }
+
+    #[test]
+    fn test_auth_before_routing() {
+        let server = server(Some(BasicAuth::new("sawtooth", "secret")));
+
+        let request = Request::new("GET", "/status").with_header("Origin", "http://example.com");
+        let response = server.respond(&request);
+        assert_eq!(response.status, 401);
+        assert_eq!(
+            response.header("Access-Control-Allow-Origin"),
+            Some("http://example.com")
+        );
+
+        let request = Request::new("GET", "/status").with_header(
+            "Authorization",
+            &format!("Basic {}", base64::encode("sawtooth:secret")),
+        );
+        assert_eq!(server.respond(&request).status, 503);
+    }
+
+    #[test]
+    fn test_subscribe_errors() {
+        let server = server(Some(BasicAuth::new("sawtooth", "secret")));
+        let authorization = format!("Basic {}", base64::encode("sawtooth:secret"));
+
+        let request = Request::new("GET", "/subscriptions");
+        assert_eq!(server.subscribe(&request).err().unwrap().status, 401);
+
+        let request =
+            Request::new("POST", "/subscriptions").with_header("Authorization", &authorization);
+        assert_eq!(server.subscribe(&request).err().unwrap().status, 405);
+
+        let request = Request::new("GET", "/subscriptions?last_known_block_id=abc")
+            .with_header("Authorization", &authorization);
+        assert_eq!(server.subscribe(&request).err().unwrap().status, 400);
+    }
+
+    #[test]
+    fn test_preflight_without_auth() {
+        let server = server(Some(BasicAuth::new("sawtooth", "secret")));
+
+        let request = Request::new("OPTIONS", "/batches")
+            .with_header("Origin", "http://example.com")
+            .with_header("Access-Control-Request-Method", "POST");
+        assert_eq!(server.respond(&request).status, 200);
+    }
+}
@@ -0,0 +1,489 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+//! The `/subscriptions` endpoint, which streams the state changes of each
+//! committed block to a client as server-sent events.
+//!
+//! Each event is sent with the id of its block, so a client that reconnects
+//! with a `Last-Event-ID` header (or a `last_known_block_id` query) is caught
+//! up on the blocks committed since that block.
+
+use std::io::{self, Write};
+use std::sync::mpsc::RecvTimeoutError;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use protobuf::{Message as M, RepeatedField};
+use serde_json::Value;
+
+use sawtooth_sdk::messages::client_event::{
+    ClientEventsSubscribeRequest, ClientEventsSubscribeResponse,
+    ClientEventsSubscribeResponse_Status, ClientEventsUnsubscribeRequest,
+};
+use sawtooth_sdk::messages::events::{Event, EventList, EventSubscription};
+use sawtooth_sdk::messages::transaction_receipt::{StateChange, StateChangeList, StateChange_Type};
+use sawtooth_sdk::messages::validator::Message_MessageType;
+use sawtooth_sdk::messaging::stream::{
+    MessageConnection, MessageReceiver, MessageSender, ReceiveError,
+};
+use sawtooth_sdk::messaging::zmq_stream::{ZmqMessageConnection, ZmqMessageSender};
+
+use crate::connection::{answer_ping, send_request};
+use crate::error::ApiError;
+use crate::request::{Request, Response};
+use crate::routes::validate_id;
+
+const BLOCK_COMMIT_EVENT: &str = "sawtooth/block-commit";
+const STATE_DELTA_EVENT: &str = "sawtooth/state-delta";
+
+/// How long a stream may be idle before a comment is sent, so that clients
+/// which have gone away are noticed.
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Subscribes clients to the validator's block-commit and state-delta events.
+pub struct SubscriptionHandler {
+    url: String,
+    timeout: Duration,
+}
+
+impl SubscriptionHandler {
+    /// Creates a handler for the validator at url, e.g.
+    /// "tcp://localhost:4004".
+    pub fn new(url: &str, timeout: Duration) -> SubscriptionHandler {
+        SubscriptionHandler {
+            url: url.into(),
+            timeout,
+        }
+    }
+
+    /// Subscribes to the validator's events for a request, over a
+    /// connection of its own. The addresses streamed are limited by the
+    /// comma-separated `address_prefix` query, if any.
+    pub fn subscribe(&self, request: &Request) -> Result<Subscription, ApiError> {
+        let address_prefixes = request
+            .query("address_prefix")
+            .map(|prefixes| {
+                prefixes
+                    .split(',')
+                    .filter(|prefix| !prefix.is_empty())
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let last_known_block_id = request
+            .query("last_known_block_id")
+            .or_else(|| request.header("Last-Event-ID"))
+            .filter(|block_id| !block_id.is_empty());
+        if let Some(block_id) = last_known_block_id {
+            validate_id(block_id)?;
+        }
+
+        let (mut sender, receiver) = ZmqMessageConnection::new(&self.url).create();
+
+        let mut message = ClientEventsSubscribeRequest::new();
+        message.set_subscriptions(event_subscriptions());
+        message.set_last_known_block_ids(RepeatedField::from_vec(
+            last_known_block_id.into_iter().map(String::from).collect(),
+        ));
+        let content = message
+            .write_to_bytes()
+            .map_err(|_| ApiError::UnknownValidatorError)?;
+
+        let response = send_request(
+            &sender,
+            &correlation_id("subscribe"),
+            Message_MessageType::CLIENT_EVENTS_SUBSCRIBE_REQUEST,
+            &content,
+            self.timeout,
+        )
+        .and_then(|content| {
+            ClientEventsSubscribeResponse::parse_from_bytes(&content)
+                .map_err(|_| ApiError::UnknownValidatorError)
+        });
+        let response = match response {
+            Ok(response) => response,
+            Err(err) => {
+                sender.close();
+                return Err(err);
+            }
+        };
+
+        match response.get_status() {
+            ClientEventsSubscribeResponse_Status::OK => Ok(Subscription {
+                sender,
+                receiver,
+                address_prefixes,
+                last_block_num: None,
+            }),
+            status => {
+                sender.close();
+                match status {
+                    ClientEventsSubscribeResponse_Status::UNKNOWN_BLOCK => {
+                        Err(ApiError::BlockNotFound)
+                    }
+                    _ => Err(ApiError::UnknownValidatorError),
+                }
+            }
+        }
+    }
+}
+
+/// A client's subscription to the validator's events.
+pub struct Subscription {
+    sender: ZmqMessageSender,
+    receiver: MessageReceiver,
+    address_prefixes: Vec<String>,
+    last_block_num: Option<u64>,
+}
+
+impl Subscription {
+    /// The response that starts the event stream, to which any further
+    /// headers may be added before it is written.
+    pub fn response() -> Response {
+        Response {
+            status: 200,
+            headers: vec![
+                ("Content-Type".into(), "text/event-stream".into()),
+                ("Cache-Control".into(), "no-cache".into()),
+                ("Connection".into(), "close".into()),
+            ],
+            body: vec![],
+        }
+    }
+
+    /// Writes the response head to a client's connection, then streams
+    /// events to it until either the client or the validator disconnects.
+    pub fn stream<W: Write>(mut self, writer: &mut W, response: &Response) {
+        if let Err(err) = write_head(writer, response) {
+            debug!("Unable to start event stream: {}", err);
+            self.unsubscribe();
+            return;
+        }
+
+        loop {
+            let result = match self.receiver.recv_timeout(KEEPALIVE_INTERVAL) {
+                Ok(Ok(message)) => match message.get_message_type() {
+                    Message_MessageType::CLIENT_EVENTS => {
+                        match EventList::parse_from_bytes(message.get_content()) {
+                            Ok(events) => self.forward(writer, events.get_events()),
+                            Err(err) => {
+                                warn!("Unable to parse events from validator: {}", err);
+                                Ok(())
+                            }
+                        }
+                    }
+                    Message_MessageType::PING_REQUEST => {
+                        answer_ping(&self.sender, &message);
+                        Ok(())
+                    }
+                    message_type => {
+                        debug!("Ignoring {:?} message from validator", message_type);
+                        Ok(())
+                    }
+                },
+                Ok(Err(ReceiveError::DisconnectedError)) | Err(RecvTimeoutError::Disconnected) => {
+                    warn!("Validator disconnected, ending event stream");
+                    let warning = json!({"warning": "Validator unavailable"});
+                    let _ = write_event(writer, None, Some("warning"), &warning);
+                    break;
+                }
+                Ok(Err(err)) => {
+                    debug!("Unable to receive message from validator: {:?}", err);
+                    Ok(())
+                }
+                Err(RecvTimeoutError::Timeout) => write_keepalive(writer),
+            };
+
+            if let Err(err) = result {
+                debug!("Client disconnected from event stream: {}", err);
+                break;
+            }
+        }
+
+        self.unsubscribe();
+    }
+
+    /// Sends the client the state changes of a committed block, marking any
+    /// block that is not past the last block sent as a fork.
+    fn forward<W: Write>(&mut self, writer: &mut W, events: &[Event]) -> io::Result<()> {
+        let delta = match StateDeltaEvent::from_events(events) {
+            Some(delta) => delta,
+            None => {
+                debug!("Ignoring events without a block commit");
+                return Ok(());
+            }
+        };
+
+        let fork_detected = self
+            .last_block_num
+            .map_or(false, |last_block_num| delta.block_num <= last_block_num);
+        self.last_block_num = Some(delta.block_num);
+
+        write_event(
+            writer,
+            Some(&delta.block_id),
+            None,
+            &delta.to_json(&self.address_prefixes, fork_detected),
+        )
+    }
+
+    fn unsubscribe(mut self) {
+        let content = ClientEventsUnsubscribeRequest::new()
+            .write_to_bytes()
+            .expect("Unable to serialize unsubscribe request");
+        if let Err(err) = send_request(
+            &self.sender,
+            &correlation_id("unsubscribe"),
+            Message_MessageType::CLIENT_EVENTS_UNSUBSCRIBE_REQUEST,
+            &content,
+            KEEPALIVE_INTERVAL,
+        ) {
+            debug!("Unable to unsubscribe from validator events: {:?}", err);
+        }
+        self.sender.close();
+    }
+}
+
+/// The state changes of a committed block, built from the block-commit and
+/// state-delta events the validator sends for it.
+#[derive(Debug, PartialEq)]
+pub struct StateDeltaEvent {
+    pub block_id: String,
+    pub block_num: u64,
+    pub previous_block_id: String,
+    pub state_changes: Vec<StateChange>,
+}
+
+impl StateDeltaEvent {
+    /// Returns None if the events have no valid block-commit event.
+    pub fn from_events(events: &[Event]) -> Option<StateDeltaEvent> {
+        let block_commit = events
+            .iter()
+            .find(|event| event.get_event_type() == BLOCK_COMMIT_EVENT)?;
+        let attribute = |key: &str| {
+            block_commit
+                .get_attributes()
+                .iter()
+                .find(|attribute| attribute.get_key() == key)
+                .map(|attribute| attribute.get_value().to_string())
+        };
+
+        let state_changes = events
+            .iter()
+            .filter(|event| event.get_event_type() == STATE_DELTA_EVENT)
+            .filter_map(|event| StateChangeList::parse_from_bytes(event.get_data()).ok())
+            .flat_map(|mut changes| changes.take_state_changes().into_vec())
+            .collect();
+
+        Some(StateDeltaEvent {
+            block_id: attribute("block_id")?,
+            block_num: attribute("block_num")?.parse().ok()?,
+            previous_block_id: attribute("previous_block_id")?,
+            state_changes,
+        })
+    }
+
+    /// Formats the event like the Python REST API's websocket messages, with
+    /// only the state changes to addresses matching one of the prefixes, or
+    /// to any address if there are none.
+    pub fn to_json(&self, address_prefixes: &[String], fork_detected: bool) -> Value {
+        let state_changes: Vec<Value> = self
+            .state_changes
+            .iter()
+            .filter(|change| {
+                address_prefixes.is_empty()
+                    || address_prefixes
+                        .iter()
+                        .any(|prefix| change.get_address().starts_with(prefix.as_str()))
+            })
+            .map(|change| {
+                json!({
+                    "address": change.get_address(),
+                    "value": base64::encode(change.get_value()),
+                    "type": match change.get_field_type() {
+                        StateChange_Type::SET => "SET",
+                        StateChange_Type::DELETE => "DELETE",
+                        StateChange_Type::TYPE_UNSET => "TYPE_UNSET",
+                    },
+                })
+            })
+            .collect();
+
+        let mut event = json!({
+            "block_id": self.block_id,
+            "block_num": self.block_num.to_string(),
+            "previous_block_id": self.previous_block_id,
+            "state_changes": state_changes,
+        });
+        if fork_detected {
+            event["fork_detected"] = Value::Bool(true);
+        }
+
+        event
+    }
+}
+
+fn event_subscriptions() -> RepeatedField<EventSubscription> {
+    [BLOCK_COMMIT_EVENT, STATE_DELTA_EVENT]
+        .iter()
+        .map(|event_type| {
+            let mut subscription = EventSubscription::new();
+            subscription.set_event_type((*event_type).into());
+            subscription
+        })
+        .collect()
+}
+
+fn correlation_id(action: &str) -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+    format!("rest-api-{}-{:x}", action, nanos)
+}
+
+fn write_head<W: Write>(writer: &mut W, response: &Response) -> io::Result<()> {
+    write!(writer, "HTTP/1.1 {} OK\r\n", response.status)?;
+    for (name, value) in &response.headers {
+        write!(writer, "{}: {}\r\n", name, value)?;
+    }
+    write!(writer, "\r\n")?;
+    writer.flush()
+}
+
+/// Writes an event in the text/event-stream format, flushing it so the
+/// client receives it immediately.
+fn write_event<W: Write>(
+    writer: &mut W,
+    id: Option<&str>,
+    event_type: Option<&str>,
+    data: &Value,
+) -> io::Result<()> {
+    if let Some(id) = id {
+        writeln!(writer, "id: {}", id)?;
+    }
+    if let Some(event_type) = event_type {
+        writeln!(writer, "event: {}", event_type)?;
+    }
+    writeln!(writer, "data: {}\n", data)?;
+    writer.flush()
+}
+
+fn write_keepalive<W: Write>(writer: &mut W) -> io::Result<()> {
+    writeln!(writer, ": keepalive\n")?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use sawtooth_sdk::messages::events::Event_Attribute;
+
+    fn block_commit(block_id: &str, block_num: &str) -> Event {
+        let mut event = Event::new();
+        event.set_event_type(BLOCK_COMMIT_EVENT.into());
+        let attributes = [
+            ("block_id", block_id),
+            ("block_num", block_num),
+            ("previous_block_id", "0000"),
+        ];
+        event.set_attributes(
+            attributes
+                .iter()
+                .map(|(key, value)| {
+                    let mut attribute = Event_Attribute::new();
+                    attribute.set_key((*key).into());
+                    attribute.set_value((*value).into());
+                    attribute
+                })
+                .collect(),
+        );
+        event
+    }
+
+    fn state_delta(changes: &[(&str, &str, StateChange_Type)]) -> Event {
+        let mut list = StateChangeList::new();
+        list.set_state_changes(
+            changes
+                .iter()
+                .map(|(address, value, change_type)| {
+                    let mut change = StateChange::new();
+                    change.set_address((*address).into());
+                    change.set_value(value.as_bytes().to_vec());
+                    change.set_field_type(*change_type);
+                    change
+                })
+                .collect(),
+        );
+
+        let mut event = Event::new();
+        event.set_event_type(STATE_DELTA_EVENT.into());
+        event.set_data(list.write_to_bytes().unwrap());
+        event
+    }
+
+    #[test]
+    fn test_state_delta_event_filters_prefixes() {
+        let events = [
+            block_commit("b1", "3"),
+            state_delta(&[
+                ("1cf126aa", "abc", StateChange_Type::SET),
+                ("000000bb", "", StateChange_Type::DELETE),
+            ]),
+        ];
+
+        let delta = StateDeltaEvent::from_events(&events).unwrap();
+        assert_eq!(delta.block_num, 3);
+
+        assert_eq!(
+            delta.to_json(&["1cf126".into()], false),
+            json!({
+                "block_id": "b1",
+                "block_num": "3",
+                "previous_block_id": "0000",
+                "state_changes": [
+                    {"address": "1cf126aa", "value": "YWJj", "type": "SET"},
+                ],
+            })
+        );
+        assert_eq!(
+            delta.to_json(&[], true)["state_changes"][1],
+            json!({"address": "000000bb", "value": "", "type": "DELETE"})
+        );
+        assert_eq!(delta.to_json(&[], true)["fork_detected"], json!(true));
+    }
+
+    #[test]
+    fn test_state_delta_event_requires_block_commit() {
+        let events = [state_delta(&[("1cf126aa", "abc", StateChange_Type::SET)])];
+        assert_eq!(StateDeltaEvent::from_events(&events), None);
+
+        let events = [block_commit("b1", "not a number")];
+        assert_eq!(StateDeltaEvent::from_events(&events), None);
+    }
+
+    #[test]
+    fn test_write_event() {
+        let mut buffer = vec![];
+        write_event(&mut buffer, Some("b1"), Some("warning"), &json!({"a": 1})).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "id: b1\nevent: warning\ndata: {\"a\":1}\n\n"
+        );
+    }
+}
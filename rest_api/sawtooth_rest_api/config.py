@@ -54,9 +54,12 @@ def load_toml_rest_api_config(filename):
 
     toml_config = toml.loads(raw_config)
 
+    # The cors, tls and basic auth keys are only read by the Rust REST API,
+    # but are accepted so that both can share a configuration file
     invalid_keys = set(toml_config.keys()).difference(
         ['bind', 'connect', 'timeout', 'opentsdb_db', 'opentsdb_url',
-         'opentsdb_username', 'opentsdb_password', 'client_max_size'])
+         'opentsdb_username', 'opentsdb_password', 'client_max_size',
+         'cors_allowed_origins', 'tls_cert', 'tls_key', 'basic_auth'])
     if invalid_keys:
         raise RestApiConfigurationError(
             "Invalid keys in rest api config: {}".format(
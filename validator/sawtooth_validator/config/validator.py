@@ -19,7 +19,6 @@ import os
 
 import toml
 
-from sawtooth_validator.exceptions import LocalConfigurationError
 from sawtooth_validator.config.path import load_path_config
 from sawtooth_validator.protobuf.identity_pb2 import Policy
 
@@ -27,222 +26,6 @@ from sawtooth_validator.protobuf.identity_pb2 import Policy
 LOGGER = logging.getLogger(__name__)
 
 
-def load_default_validator_config():
-    return ValidatorConfig(
-        bind_network='tcp://127.0.0.1:8800',
-        bind_component='tcp://127.0.0.1:4004',
-        bind_consensus='tcp://127.0.0.1:5050',
-        endpoint=None,
-        peering='static',
-        scheduler='parallel',
-        minimum_peer_connectivity=3,
-        maximum_peer_connectivity=10,
-        state_pruning_block_depth=100,
-        fork_cache_keep_time=300,
-        component_thread_pool_workers=10,
-        network_thread_pool_workers=10,
-        signature_thread_pool_workers=3
-    )
-
-
-def load_toml_validator_config(filename):
-    """Returns a ValidatorConfig created by loading a TOML file from the
-    filesystem.
-    """
-    if not os.path.exists(filename):
-        LOGGER.info(
-            "Skipping validator config loading from non-existent config file:"
-            " %s", filename)
-        return ValidatorConfig()
-
-    LOGGER.info("Loading validator information from config: %s", filename)
-
-    try:
-        with open(filename) as fd:
-            raw_config = fd.read()
-    except IOError as e:
-        raise LocalConfigurationError(
-            "Unable to load validator configuration file: {}".format(
-                str(e))) from e
-
-    toml_config = toml.loads(raw_config)
-    invalid_keys = set(toml_config.keys()).difference(
-        ['bind', 'endpoint', 'peering', 'seeds', 'peers', 'network_public_key',
-         'network_private_key', 'scheduler', 'permissions', 'roles',
-         'opentsdb_url', 'opentsdb_db', 'opentsdb_username',
-         'opentsdb_password', 'minimum_peer_connectivity',
-         'maximum_peer_connectivity', 'state_pruning_block_depth',
-         'fork_cache_keep_time',
-         'component_thread_pool_workers', 'network_thread_pool_workers',
-         'signature_thread_pool_workers'])
-    if invalid_keys:
-        raise LocalConfigurationError(
-            "Invalid keys in validator config: "
-            "{}".format(", ".join(sorted(list(invalid_keys)))))
-    bind_network = None
-    bind_component = None
-    bind_consensus = None
-    for bind in toml_config.get("bind", []):
-        if "network" in bind:
-            bind_network = bind[bind.find(":") + 1:]
-        if "component" in bind:
-            bind_component = bind[bind.find(":") + 1:]
-        if "consensus" in bind:
-            bind_consensus = bind[bind.find(":") + 1:]
-
-    network_public_key = None
-    network_private_key = None
-
-    if toml_config.get("network_public_key") is not None:
-        network_public_key = toml_config.get("network_public_key").encode()
-
-    if toml_config.get("network_private_key") is not None:
-        network_private_key = toml_config.get("network_private_key").encode()
-
-    config = ValidatorConfig(
-        bind_network=bind_network,
-        bind_component=bind_component,
-        bind_consensus=bind_consensus,
-        endpoint=toml_config.get("endpoint", None),
-        peering=toml_config.get("peering", None),
-        seeds=toml_config.get("seeds", None),
-        peers=toml_config.get("peers", None),
-        network_public_key=network_public_key,
-        network_private_key=network_private_key,
-        scheduler=toml_config.get("scheduler", None),
-        permissions=parse_permissions(toml_config.get("permissions", None)),
-        roles=toml_config.get("roles", None),
-        opentsdb_url=toml_config.get("opentsdb_url", None),
-        opentsdb_db=toml_config.get("opentsdb_db", None),
-        opentsdb_username=toml_config.get("opentsdb_username", None),
-        opentsdb_password=toml_config.get("opentsdb_password", None),
-        minimum_peer_connectivity=toml_config.get(
-            "minimum_peer_connectivity", None),
-        maximum_peer_connectivity=toml_config.get(
-            "maximum_peer_connectivity", None),
-        state_pruning_block_depth=toml_config.get(
-            "state_pruning_block_depth", None),
-        fork_cache_keep_time=toml_config.get(
-            "fork_cache_keep_time", None),
-        component_thread_pool_workers=toml_config.get(
-            "component_thread_pool_workers", None),
-        network_thread_pool_workers=toml_config.get(
-            "network_thread_pool_workers", None),
-        signature_thread_pool_workers=toml_config.get(
-            "signature_thread_pool_workers", None)
-    )
-
-    return config
-
-
-def merge_validator_config(configs):
-    """
-    Given a list of ValidatorConfig objects, merges them into a single
-    ValidatorConfig, giving priority in the order of the configs
-    (first has highest priority).
-    """
-    bind_network = None
-    bind_component = None
-    bind_consensus = None
-    endpoint = None
-    peering = None
-    seeds = None
-    peers = None
-    network_public_key = None
-    network_private_key = None
-    scheduler = None
-    permissions = None
-    roles = None
-    opentsdb_url = None
-    opentsdb_db = None
-    opentsdb_username = None
-    opentsdb_password = None
-    minimum_peer_connectivity = None
-    maximum_peer_connectivity = None
-    state_pruning_block_depth = None
-    fork_cache_keep_time = None
-    component_thread_pool_workers = None
-    network_thread_pool_workers = None
-    signature_thread_pool_workers = None
-
-    for config in reversed(configs):
-        if config.bind_network is not None:
-            bind_network = config.bind_network
-        if config.bind_component is not None:
-            bind_component = config.bind_component
-        if config.bind_consensus is not None:
-            bind_consensus = config.bind_consensus
-        if config.endpoint is not None:
-            endpoint = config.endpoint
-        if config.peering is not None:
-            peering = config.peering
-        if config.seeds is not None:
-            seeds = config.seeds
-        if config.peers is not None:
-            peers = config.peers
-        if config.network_public_key is not None:
-            network_public_key = config.network_public_key
-        if config.network_private_key is not None:
-            network_private_key = config.network_private_key
-        if config.scheduler is not None:
-            scheduler = config.scheduler
-        if config.permissions is not None or config.permissions == {}:
-            permissions = config.permissions
-        if config.roles is not None:
-            roles = config.roles
-        if config.opentsdb_url is not None:
-            opentsdb_url = config.opentsdb_url
-        if config.opentsdb_db is not None:
-            opentsdb_db = config.opentsdb_db
-        if config.opentsdb_username is not None:
-            opentsdb_username = config.opentsdb_username
-        if config.opentsdb_password is not None:
-            opentsdb_password = config.opentsdb_password
-        if config.minimum_peer_connectivity is not None:
-            minimum_peer_connectivity = config.minimum_peer_connectivity
-        if config.maximum_peer_connectivity is not None:
-            maximum_peer_connectivity = config.maximum_peer_connectivity
-        if config.state_pruning_block_depth is not None:
-            state_pruning_block_depth = config.state_pruning_block_depth
-        if config.fork_cache_keep_time is not None:
-            fork_cache_keep_time = config.fork_cache_keep_time
-        if config.component_thread_pool_workers is not None:
-            component_thread_pool_workers = \
-                config.component_thread_pool_workers
-        if config.network_thread_pool_workers is not None:
-            network_thread_pool_workers = \
-                config.network_thread_pool_workers
-        if config.signature_thread_pool_workers is not None:
-            signature_thread_pool_workers = \
-                config.signature_thread_pool_workers
-
-    return ValidatorConfig(
-        bind_network=bind_network,
-        bind_component=bind_component,
-        bind_consensus=bind_consensus,
-        endpoint=endpoint,
-        peering=peering,
-        seeds=seeds,
-        peers=peers,
-        network_public_key=network_public_key,
-        network_private_key=network_private_key,
-        scheduler=scheduler,
-        permissions=permissions,
-        roles=roles,
-        opentsdb_url=opentsdb_url,
-        opentsdb_db=opentsdb_db,
-        opentsdb_username=opentsdb_username,
-        opentsdb_password=opentsdb_password,
-        minimum_peer_connectivity=minimum_peer_connectivity,
-        maximum_peer_connectivity=maximum_peer_connectivity,
-        state_pruning_block_depth=state_pruning_block_depth,
-        fork_cache_keep_time=fork_cache_keep_time,
-        component_thread_pool_workers=component_thread_pool_workers,
-        network_thread_pool_workers=network_thread_pool_workers,
-        signature_thread_pool_workers=signature_thread_pool_workers
-    )
-
-
 def parse_permissions(permissions):
     roles = {}
     path_config = load_path_config()
@@ -294,7 +77,15 @@ class ValidatorConfig:
                  fork_cache_keep_time=None,
                  component_thread_pool_workers=None,
                  network_thread_pool_workers=None,
-                 signature_thread_pool_workers=None):
+                 signature_thread_pool_workers=None,
+                 merkle_node_cache_size=None,
+                 batch_rate_limit=None,
+                 batch_rate_burst=None,
+                 receipt_retention_blocks=None,
+                 receipt_retention_count=None,
+                 receipt_sync_mode=None,
+                 consensus_max_missed_pings=None,
+                 accept_any_chain_id=None):
 
         self._bind_network = bind_network
         self._bind_component = bind_component
@@ -319,6 +110,14 @@ class ValidatorConfig:
         self._component_thread_pool_workers = component_thread_pool_workers
         self._network_thread_pool_workers = network_thread_pool_workers
         self._signature_thread_pool_workers = signature_thread_pool_workers
+        self._merkle_node_cache_size = merkle_node_cache_size
+        self._batch_rate_limit = batch_rate_limit
+        self._batch_rate_burst = batch_rate_burst
+        self._receipt_retention_blocks = receipt_retention_blocks
+        self._receipt_retention_count = receipt_retention_count
+        self._receipt_sync_mode = receipt_sync_mode
+        self._consensus_max_missed_pings = consensus_max_missed_pings
+        self._accept_any_chain_id = accept_any_chain_id
 
     @property
     def bind_network(self):
@@ -412,6 +211,38 @@ class ValidatorConfig:
     def signature_thread_pool_workers(self):
         return self._signature_thread_pool_workers
 
+    @property
+    def merkle_node_cache_size(self):
+        return self._merkle_node_cache_size
+
+    @property
+    def batch_rate_limit(self):
+        return self._batch_rate_limit
+
+    @property
+    def batch_rate_burst(self):
+        return self._batch_rate_burst
+
+    @property
+    def receipt_retention_blocks(self):
+        return self._receipt_retention_blocks
+
+    @property
+    def receipt_retention_count(self):
+        return self._receipt_retention_count
+
+    @property
+    def receipt_sync_mode(self):
+        return self._receipt_sync_mode
+
+    @property
+    def consensus_max_missed_pings(self):
+        return self._consensus_max_missed_pings
+
+    @property
+    def accept_any_chain_id(self):
+        return self._accept_any_chain_id
+
     def __repr__(self):
         # not including  password for opentsdb
         return (
@@ -425,7 +256,11 @@ class ValidatorConfig:
             "fork_cache_keep_time={})"
             "component_thread_pool_workers={}, "
             "network_thread_pool_workers={}, "
-            "signature_thread_pool_workers={})"
+            "signature_thread_pool_workers={}, "
+            "merkle_node_cache_size={}, batch_rate_limit={}, "
+            "batch_rate_burst={}, receipt_retention_blocks={}, "
+            "receipt_retention_count={}, receipt_sync_mode={}, "
+            "consensus_max_missed_pings={}, accept_any_chain_id={})"
         ).format(
             self.__class__.__name__,
             repr(self._bind_network),
@@ -449,7 +284,15 @@ class ValidatorConfig:
             repr(self._fork_cache_keep_time),
             repr(self._component_thread_pool_workers),
             repr(self._network_thread_pool_workers),
-            repr(self._signature_thread_pool_workers)
+            repr(self._signature_thread_pool_workers),
+            repr(self._merkle_node_cache_size),
+            repr(self._batch_rate_limit),
+            repr(self._batch_rate_burst),
+            repr(self._receipt_retention_blocks),
+            repr(self._receipt_retention_count),
+            repr(self._receipt_sync_mode),
+            repr(self._consensus_max_missed_pings),
+            repr(self._accept_any_chain_id)
         )
 
     def to_dict(self):
@@ -478,7 +321,15 @@ class ValidatorConfig:
                 self._component_thread_pool_workers),
             ('network_thread_pool_workers', self._network_thread_pool_workers),
             ('network_thread_pool_workers',
-                self._signature_thread_pool_workers)
+                self._signature_thread_pool_workers),
+            ('merkle_node_cache_size', self._merkle_node_cache_size),
+            ('batch_rate_limit', self._batch_rate_limit),
+            ('batch_rate_burst', self._batch_rate_burst),
+            ('receipt_retention_blocks', self._receipt_retention_blocks),
+            ('receipt_retention_count', self._receipt_retention_count),
+            ('receipt_sync_mode', self._receipt_sync_mode),
+            ('consensus_max_missed_pings', self._consensus_max_missed_pings),
+            ('network', {'accept_any_chain_id': self._accept_any_chain_id})
         ])
 
     def to_toml_string(self):
@@ -30,6 +30,7 @@ from sawtooth_validator.networking.dispatch import HandlerStatus
 from sawtooth_validator.journal.block_wrapper import BlockStatus
 from sawtooth_validator.journal.journal import BlockEmpty
 from sawtooth_validator.journal.journal import BlockInProgress
+from sawtooth_validator.journal.journal import PublishingPaused
 from sawtooth_validator.journal.journal import BlockNotInitialized
 from sawtooth_validator.journal.journal import MissingPredecessor
 
@@ -233,6 +234,9 @@ class ConsensusInitializeBlockHandler(ConsensusServiceHandler):
         except BlockInProgress:
             response.status =\
                 consensus_pb2.ConsensusInitializeBlockResponse.INVALID_STATE
+        except PublishingPaused:
+            response.status =\
+                consensus_pb2.ConsensusInitializeBlockResponse.NOT_READY
         except Exception:  # pylint: disable=broad-except
             LOGGER.exception("ConsensusInitializeBlock")
             response.status =\
@@ -288,6 +292,9 @@ class ConsensusFinalizeBlockHandler(ConsensusServiceHandler):
         except BlockEmpty:
             response.status =\
                 consensus_pb2.ConsensusFinalizeBlockResponse.BLOCK_NOT_READY
+        except PublishingPaused:
+            response.status =\
+                consensus_pb2.ConsensusFinalizeBlockResponse.NOT_READY
         except Exception:  # pylint: disable=broad-except
             LOGGER.exception("ConsensusFinalizeBlock")
             response.status =\
@@ -371,6 +378,7 @@ class ConsensusCheckBlocksNotifier(Handler):
             if block_status == BlockStatus.Valid:
                 self._consensus_notifier.notify_block_valid(block_id)
             elif block_status == BlockStatus.Invalid:
+                self._proxy.report_invalid_block(block_id)
                 self._consensus_notifier.notify_block_invalid(block_id)
             elif block_status == BlockStatus.Unknown:
                 # No need to worry about unknown block, this is checked in the
@@ -529,6 +537,34 @@ class ConsensusChainHeadGetHandler(ConsensusServiceHandler):
         return HandlerStatus.RETURN
 
 
+class ConsensusReplayCommitsHandler(ConsensusServiceHandler):
+    def __init__(self, proxy):
+        super().__init__(
+            consensus_pb2.ConsensusReplayCommitsRequest,
+            validator_pb2.Message.CONSENSUS_REPLAY_COMMITS_REQUEST,
+            consensus_pb2.ConsensusReplayCommitsResponse,
+            validator_pb2.Message.CONSENSUS_REPLAY_COMMITS_RESPONSE)
+
+        self._proxy = proxy
+
+    def handle_request(self, request, response, connection_id):
+        try:
+            replayed = self._proxy.replay_commits(
+                request.block_id, request.max_count)
+            response.replayed = len(replayed)
+            if replayed:
+                response.last_block_id = bytes.fromhex(replayed[-1])
+        except UnknownBlock:
+            response.status =\
+                consensus_pb2.ConsensusReplayCommitsResponse.UNKNOWN_BLOCK
+        except Exception:  # pylint: disable=broad-except
+            LOGGER.exception("ConsensusReplayCommits")
+            response.status =\
+                consensus_pb2.ConsensusReplayCommitsResponse.SERVICE_ERROR
+
+        return HandlerStatus.RETURN
+
+
 class ConsensusSettingsGetHandler(ConsensusServiceHandler):
     def __init__(self, proxy):
         super().__init__(
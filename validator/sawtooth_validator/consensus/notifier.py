@@ -166,6 +166,12 @@ class ConsensusNotifier(ffi.OwnedPointer):
             "consensus_notifier_notify_block_invalid",
             ctypes.c_char_p(block_id.encode()))
 
+    def notify_block_commit(self, block_id):
+        """This block has been committed"""
+        self._notify(
+            "consensus_notifier_notify_block_commit",
+            ctypes.c_char_p(block_id.encode()))
+
     def notify_engine_activated(self, chain_head):
         """The consensus engine has been activated."""
         chain_head_bytes = chain_head.SerializeToString()
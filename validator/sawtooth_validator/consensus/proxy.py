@@ -14,6 +14,7 @@
 # ------------------------------------------------------------------------------
 
 import hashlib
+import itertools
 import logging
 
 from sawtooth_validator.consensus.registry import EngineAlreadyActive
@@ -30,6 +31,10 @@ from sawtooth_validator.protobuf.consensus_pb2 import \
 
 LOGGER = logging.getLogger(__name__)
 
+# The most block commit notifications replayed for a single request, which is
+# handled on the dispatcher thread
+MAX_REPLAY_COMMITS = 100
+
 
 class UnknownBlock(Exception):
     """The given block could not be found."""
@@ -103,10 +108,11 @@ class ConsensusProxy:
     """Receives requests from the consensus engine handlers and delegates them
     to the appropriate components."""
 
-    def __init__(self, block_manager, journal, gossip, identity_signer,
-                 settings_view_factory, state_view_factory,
+    def __init__(self, block_manager, block_store, journal, gossip,
+                 identity_signer, settings_view_factory, state_view_factory,
                  consensus_registry, consensus_notifier):
         self._block_manager = block_manager
+        self._block_store = block_store
         self._journal = journal
         self._gossip = gossip
         self._identity_signer = identity_signer
@@ -217,6 +223,10 @@ class ConsensusProxy:
         except KeyError as key_error:
             raise UnknownBlock(key_error.args[0]) from KeyError
 
+    def report_invalid_block(self, block_id):
+        """Penalize the peer that signed and sent an invalid block."""
+        self._gossip.peer_scores.record_invalid_block(block_id)
+
     def validate_block(self, block_id):
         """Instruct the chain controller to validate the given block."""
         try:
@@ -261,6 +271,33 @@ class ConsensusProxy:
 
         return chain_head
 
+    def replay_commits(self, block_id, max_count=0):
+        '''Sends a block commit notification for each block committed after
+        the given block, oldest first, stopping after max_count blocks or
+        MAX_REPLAY_COMMITS, whichever is fewer. Returns the ids of the blocks
+        notified.'''
+        try:
+            start_block = self._block_store[block_id.hex()]
+        except KeyError:
+            raise UnknownBlock(block_id.hex()) from KeyError
+
+        if max_count <= 0 or max_count > MAX_REPLAY_COMMITS:
+            max_count = MAX_REPLAY_COMMITS
+
+        blocks = (
+            block for block in self._block_store.get_block_iter(
+                start_block=start_block, reverse=False)
+            if block.header_signature != start_block.header_signature
+        )
+
+        replayed = []
+        for block in itertools.islice(blocks, max_count):
+            self._consensus_notifier.notify_block_commit(
+                block.header_signature)
+            replayed.append(block.header_signature)
+
+        return replayed
+
     def settings_get(self, block_id, settings):
         '''Returns a list of key/value pairs (str, str).'''
 
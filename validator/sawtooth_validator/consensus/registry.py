@@ -14,7 +14,25 @@
 # ------------------------------------------------------------------------------
 
 from collections import namedtuple
-from threading import RLock
+import ctypes
+from enum import IntEnum
+import logging
+
+from sawtooth_validator import ffi
+from sawtooth_validator.ffi import PY_LIBRARY
+from sawtooth_validator.ffi import LIBRARY
+from sawtooth_validator.ffi import CommonErrorCode
+from sawtooth_validator.protobuf.admin_pb2 import \
+    AdminConsensusEnginesGetResponse
+from sawtooth_validator.protobuf.consensus_pb2 import \
+    ConsensusRegisterRequest
+from sawtooth_validator.protobuf.network_pb2 import PingRequest
+from sawtooth_validator.protobuf.validator_pb2 import Message
+
+LOGGER = logging.getLogger(__name__)
+
+# Seconds between the pings sent to each registered engine
+PING_INTERVAL = 10
 
 
 class EngineNotRegistered(Exception):
@@ -29,88 +47,178 @@ EngineInfo = namedtuple(
     'EngineInfo', ['connection_id', 'name', 'version', 'additional_protocols'])
 
 
-class ConsensusRegistry:
+class ErrorCode(IntEnum):
+    Success = CommonErrorCode.Success
+    NullPointerProvided = CommonErrorCode.NullPointerProvided
+    InvalidArgument = 0x02
+    EngineNotRegistered = 0x03
+    EngineAlreadyActive = 0x04
+    Error = 0x05
+
+
+def _check_error(return_code):
+    if return_code == ErrorCode.Success:
+        return
+    if return_code == ErrorCode.NullPointerProvided:
+        raise TypeError("Provided null pointer(s)")
+    if return_code == ErrorCode.InvalidArgument:
+        raise ValueError("Input was not valid")
+    if return_code == ErrorCode.EngineNotRegistered:
+        raise EngineNotRegistered()
+    if return_code == ErrorCode.EngineAlreadyActive:
+        raise EngineAlreadyActive()
+    raise RuntimeError(
+        "An unknown error occurred in the consensus registry: {}".format(
+            return_code))
+
+
+class ConsensusRegistry(ffi.OwnedPointer):
     """A thread-safe construct that stores the connection_id, name, version,
     and any additional supported protocols of all registered consensus engines,
-    and tracks which engine is currently active."""
+    tracks which engine is currently active, and whether each engine still
+    answers pings.
 
-    def __init__(self):
-        self._registry = []
-        self._active = None
-        self._lock = RLock()
+    An engine that has not answered max_missed_pings pings in a row is
+    reported as unresponsive.
+    """
+
+    def __init__(self, max_missed_pings=3):
+        super().__init__('consensus_registry_drop')
+
+        _check_error(LIBRARY.call(
+            'consensus_registry_new',
+            ctypes.c_uint32(max_missed_pings),
+            ctypes.byref(self.pointer)))
 
     def __bool__(self):
         return self.has_active_engine()
 
     def has_active_engine(self):
-        with self._lock:
-            return self._active is not None
+        has_active = ctypes.c_bool(False)
+        _check_error(LIBRARY.call(
+            'consensus_registry_has_active_engine',
+            self.pointer,
+            ctypes.byref(has_active)))
+        return has_active.value
 
     def register_engine(self, connection_id, name, version,
                         additional_protocols):
-        with self._lock:
-            engine_info = EngineInfo(
-                connection_id, name, version, additional_protocols)
-
-            # If this engine is already registered or if there is an existing
-            # engine that this one replaces (supports all the same protocols),
-            # remove the old connection.
-            def is_same_engine(eng):
-                return eng.name == name and eng.version == version
-
-            def is_replaced(eng):
-                return (
-                    engine_handles_protocol(eng.name, eng.version, engine_info)
-                    and all([True for (n, v) in eng.additional_protocols
-                             if engine_handles_protocol(n, v, engine_info)]))
-
-            self._registry = list(filter(
-                lambda e: not is_same_engine(e) and not is_replaced(e),
-                self._registry))
-
-            # If this engine displaced the active engine, reset active engine
-            if self._active not in self._registry:
-                self._active = None
-
-            self._registry.append(engine_info)
+        """Registers an engine. An earlier registration of the same engine,
+        or of an engine that this one supports all the protocols of, is
+        replaced, deactivating it if it was active.
+        """
+        request = ConsensusRegisterRequest(
+            name=name,
+            version=version,
+            additional_protocols=[
+                ConsensusRegisterRequest.Protocol(name=n, version=v)
+                for (n, v) in additional_protocols
+            ]).SerializeToString()
+
+        _check_error(LIBRARY.call(
+            'consensus_registry_register_engine',
+            self.pointer,
+            ctypes.c_char_p(connection_id.encode()),
+            request,
+            len(request)))
 
     def activate_engine(self, name, version):
-        with self._lock:
-            if self._active is not None:
-                if engine_handles_protocol(name, version, self._active):
-                    raise EngineAlreadyActive()
-                else:
-                    # A different engine is active; remove it
-                    self._active = None
-            try:
-                self._active = next(
-                    e for e in self._registry
-                    if engine_handles_protocol(name, version, e))
-            except StopIteration:
-                raise EngineNotRegistered() from StopIteration
+        _check_error(LIBRARY.call(
+            'consensus_registry_activate_engine',
+            self.pointer,
+            ctypes.c_char_p(name.encode()),
+            ctypes.c_char_p(version.encode())))
 
     def deactivate_current_engine(self):
-        with self._lock:
-            self._active = None
+        _check_error(LIBRARY.call(
+            'consensus_registry_deactivate_current_engine',
+            self.pointer))
 
     def get_active_engine_info(self):
-        with self._lock:
-            return self._active
+        for engine in self.get_engines():
+            if engine.active:
+                return EngineInfo(
+                    engine.connection_id,
+                    engine.name,
+                    engine.version,
+                    [(p.name, p.version)
+                     for p in engine.additional_protocols])
+        return None
+
+    def get_engines(self):
+        """Returns the registered engines, as
+        AdminConsensusEnginesGetResponse.Engine messages.
+        """
+        (vec_ptr, vec_len, vec_cap) = ffi.prepare_vec_result()
+        _check_error(LIBRARY.call(
+            'consensus_registry_get_engines',
+            self.pointer,
+            ctypes.byref(vec_ptr),
+            ctypes.byref(vec_len),
+            ctypes.byref(vec_cap)))
+
+        response = AdminConsensusEnginesGetResponse()
+        response.ParseFromString(ffi.from_rust_vec(vec_ptr, vec_len, vec_cap))
+        return list(response.engines)
 
     def is_active_engine_id(self, connection_id):
-        with self._lock:
-            return (
-                self._active is not None
-                and connection_id == self._active.connection_id)
+        is_active = ctypes.c_bool(False)
+        _check_error(LIBRARY.call(
+            'consensus_registry_is_active_engine_id',
+            self.pointer,
+            ctypes.c_char_p(connection_id.encode()),
+            ctypes.byref(is_active)))
+        return is_active.value
 
     def is_active_engine_name_version(self, name, version):
-        with self._lock:
-            return (
-                self._active is not None
-                and name == self._active.name
-                and version == self._active.version)
-
-
-def engine_handles_protocol(name, version, engine):
-    return ((name, version) == (engine.name, engine.version)
-            or (name, version) in engine.additional_protocols)
+        is_active = ctypes.c_bool(False)
+        _check_error(LIBRARY.call(
+            'consensus_registry_is_active_engine_name_version',
+            self.pointer,
+            ctypes.c_char_p(name.encode()),
+            ctypes.c_char_p(version.encode()),
+            ctypes.byref(is_active)))
+        return is_active.value
+
+    def ping_answered(self, connection_id):
+        _check_error(LIBRARY.call(
+            'consensus_registry_ping_answered',
+            self.pointer,
+            ctypes.c_char_p(connection_id.encode())))
+
+
+class _PingService:
+    def __init__(self, consensus_service, consensus_registry):
+        self._service = consensus_service
+        self._consensus_registry = consensus_registry
+
+    def ping(self, connection_id):
+        def on_response(_request, result):
+            if result.message_type == Message.PING_RESPONSE:
+                self._consensus_registry.ping_answered(connection_id)
+
+        self._service.send(
+            Message.PING_REQUEST,
+            PingRequest().SerializeToString(),
+            connection_id,
+            callback=on_response)
+
+
+class ConsensusLivenessMonitor(ffi.OwnedPointer):
+    """Pings every engine in the registry each ping_interval seconds, over
+    the consensus_service, until dropped.
+    """
+
+    def __init__(self, consensus_service, consensus_registry,
+                 ping_interval=PING_INTERVAL):
+        super().__init__('consensus_liveness_monitor_drop')
+
+        self._ping_service = _PingService(
+            consensus_service, consensus_registry)
+
+        _check_error(PY_LIBRARY.call(
+            'consensus_liveness_monitor_new',
+            consensus_registry.pointer,
+            ctypes.py_object(self._ping_service),
+            ctypes.c_uint64(ping_interval),
+            ctypes.byref(self.pointer)))
@@ -13,6 +13,8 @@
 # limitations under the License.
 # ------------------------------------------------------------------------------
 
+from bisect import bisect_left
+
 from sawtooth_validator.database import database
 
 
@@ -109,6 +111,9 @@ class DictDatabase(database.Database):
     def sync(self):
         pass
 
+    def flush(self):
+        pass
+
     def __contains__(self, item):
         return item in self._data
 
@@ -124,16 +129,16 @@ class DictDatabase(database.Database):
 
 class DictCursor(database.Cursor):
     def __init__(self, data):
-        self._data = list(data.items())
+        self._data = sorted(data.items(), key=lambda item: item[0])
         self._position = -1
 
     def iter(self):
-        if self._position not in self._data:
+        if not (self._position >= 0 and self._position <= len(self._data)):
             self.first()
         return DictCursor._wrap_iter(self._data, self._position)
 
     def iter_rev(self):
-        if self._position not in self._data:
+        if not (self._position >= 0 and self._position < len(self._data)):
             self.last()
         return DictCursor._wrap_iter(self._data, self._position, reverse=True)
 
@@ -144,13 +149,12 @@ class DictCursor(database.Cursor):
         self._position = len(self._data) - 1
 
     def seek(self, key):
-        # this cursor actually assumes that the key is header_signature
-        for i, (stored_key, _) in enumerate(self._data):
-            if stored_key == key:
-                self._position = i
-                return
-
-        raise ValueError("Unknown key: {}".format(key))
+        """Sets the position to the given key, or the first key after it if
+        it is not in the database.
+        """
+        self._position = bisect_left([item[0] for item in self._data], key)
+        return self._position < len(self._data) \
+            and self._data[self._position][0] == key
 
     def key(self):
         if self._position >= 0 and self._position < len(self._data):
@@ -174,7 +178,7 @@ class DictCursor(database.Cursor):
                 return self
 
             def __next__(self):
-                if self._pos >= 0 and self._pos < len(data):
+                if not (self._pos >= 0 and self._pos < len(data)):
                     raise StopIteration()
 
                 val = data[self._pos][1]
@@ -19,6 +19,14 @@ import cbor
 
 from sawtooth_validator.database import database
 
+# How committed writes are flushed to disk:
+#   'sync': every commit waits for the data to be written to disk.
+#   'mapasync': every commit starts writing the data to disk, but does not
+#       wait; a system crash may lose the most recent commits.
+#   'nosync': commits leave writing the data to the operating system until
+#       `flush` is called; a system crash may lose any unflushed commits.
+SYNC_MODES = ('sync', 'mapasync', 'nosync')
+
 
 class LMDBNoLockDatabase(database.Database):
     """LMDBNoLockDatabase is an implementation of the
@@ -29,16 +37,23 @@ class LMDBNoLockDatabase(database.Database):
        _lmdb (lmdb.Environment): The underlying lmdb database.
     """
 
-    def __init__(self, filename, flag):
+    def __init__(self, filename, flag, sync_mode='mapasync'):
         """Constructor for the LMDBNoLockDatabase class.
 
         Args:
             filename (str): The filename of the database file.
             flag (str): a flag indicating the mode for opening the database.
                 Refer to the documentation for anydbm.open().
+            sync_mode (str): how committed writes are flushed to disk; one
+                of SYNC_MODES.
         """
         super().__init__()
 
+        if sync_mode not in SYNC_MODES:
+            raise ValueError(
+                'Invalid sync mode {}; must be one of {}'.format(
+                    sync_mode, ', '.join(SYNC_MODES)))
+
         create = bool(flag == 'c')
 
         if flag == 'n':
@@ -49,7 +64,8 @@ class LMDBNoLockDatabase(database.Database):
         self._lmdb = lmdb.Environment(
             path=filename,
             map_size=1024**4,
-            map_async=True,
+            sync=sync_mode != 'nosync',
+            map_async=sync_mode == 'mapasync',
             writemap=True,
             readahead=False,
             subdir=False,
@@ -84,10 +100,13 @@ class LMDBNoLockDatabase(database.Database):
         return result
 
     def cursor(self, index=None):
+        """Creates a cursor on the database, which traverses the keys in
+        their natural order. Indexes are not supported.
         """
-        This currently is just to satisfy the interface.
-        """
-        raise NotImplementedError()
+        if index is not None:
+            raise ValueError('Index {} does not exist'.format(index))
+
+        return LMDBNoLockCursor(self._lmdb)
 
     def count(self, index=None):
         """
@@ -114,10 +133,16 @@ class LMDBNoLockDatabase(database.Database):
             txn.delete(key.encode())
 
     def sync(self):
-        """Ensures that pending writes are flushed to disk
+        """Flushes pending writes to disk, as the sync mode allows
         """
         self._lmdb.sync()
 
+    def flush(self):
+        """Flushes pending writes to disk and waits for them to be written,
+        whatever the sync mode
+        """
+        self._lmdb.sync(True)
+
     def close(self):
         """Closes the connection to the database
         """
@@ -128,3 +153,69 @@ class LMDBNoLockDatabase(database.Database):
         """
         with self._lmdb.begin() as txn:
             return [key.decode() for key, _ in txn.cursor()]
+
+
+class LMDBNoLockCursor(database.Cursor):
+    """A cursor over the keys of an LMDBNoLockDatabase, which must be opened
+    before use, and sees a consistent view of the database until it is
+    closed.
+    """
+
+    def __init__(self, lmdb_env):
+        self._lmdb = lmdb_env
+        self._txn = None
+        self._cursor = None
+        self._positioned = False
+        self._exhausted = False
+
+    def open(self):
+        self._txn = self._lmdb.begin()
+        self._cursor = self._txn.cursor()
+
+    def close(self):
+        self._cursor.close()
+        self._txn.abort()
+
+    def iter(self):
+        if not self._positioned:
+            self.first()
+        if self._exhausted:
+            return iter(())
+        return (cbor.loads(value) for value in self._cursor.iternext(
+            keys=False, values=True))
+
+    def iter_rev(self):
+        if not self._positioned or self._exhausted:
+            self.last()
+        return (cbor.loads(value) for value in self._cursor.iterprev(
+            keys=False, values=True))
+
+    def first(self):
+        self._positioned = True
+        self._exhausted = not self._cursor.first()
+
+    def last(self):
+        self._positioned = True
+        self._exhausted = not self._cursor.last()
+
+    def seek(self, key):
+        """Sets the position to the given key, or the first key after it if
+        it is not in the database.
+        """
+        self._positioned = True
+        # An unpositioned LMDB cursor iterates from the first key, so
+        # seeking past the last key is tracked separately
+        self._exhausted = not self._cursor.set_range(key.encode())
+        return self.key() == key
+
+    def key(self):
+        if self._exhausted:
+            return None
+        key = self._cursor.key()
+        return key.decode() if key else None
+
+    def value(self):
+        if self._exhausted:
+            return None
+        value = self._cursor.value()
+        return cbor.loads(value) if value else None
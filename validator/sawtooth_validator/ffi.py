@@ -16,6 +16,7 @@
 from abc import ABCMeta
 import ctypes
 from enum import IntEnum
+import functools
 import logging
 import os
 import sys
@@ -64,9 +65,65 @@ class Library:
         return getattr(self._cdll, name)(*args)
 
 
+def _emitted_level(logger):
+    """Returns the lowest level of the records that a logger passes to any
+    handler, either its own or one of its ancestors'.
+    """
+    handler_levels = []
+    current = logger
+    while current is not None:
+        handler_levels.extend(handler.level for handler in current.handlers)
+        if not current.propagate:
+            break
+        current = current.parent
+
+    if not handler_levels:
+        # Records with no handler go to logging.lastResort
+        handler_levels.append(logging.WARNING)
+
+    return max(logger.getEffectiveLevel(), min(handler_levels))
+
+
+def sync_rust_log_level():
+    """Sets the lowest level of the records logged by Rust that are passed to
+    Python logging, to the lowest level that the root logger, or any logger
+    with a level of its own, would emit.
+    """
+    loggers = [logging.getLogger()]
+    loggers.extend(
+        logger for logger in list(logging.Logger.manager.loggerDict.values())
+        if isinstance(logger, logging.Logger)
+        and logger.level != logging.NOTSET)
+
+    LIBRARY.call(
+        "pylogger_set_level",
+        min(_emitted_level(logger) for logger in loggers))
+
+
+def _sync_rust_log_level_after(method):
+    @functools.wraps(method)
+    def wrapper(*args, **kwargs):
+        result = method(*args, **kwargs)
+        sync_rust_log_level()
+        return result
+
+    return wrapper
+
+
 LIBRARY = Library(ctypes.CDLL)
 LIBRARY.call("pylogger_init", LOGGER.getEffectiveLevel())
-LIBRARY.call("pymetrics_init")
+
+# Logging configuration changes the levels of loggers and handlers through
+# these methods, so the level of Rust logging follows it, including at runtime
+logging.Logger.setLevel = _sync_rust_log_level_after(logging.Logger.setLevel)
+logging.Logger.addHandler = _sync_rust_log_level_after(
+    logging.Logger.addHandler)
+logging.Logger.removeHandler = _sync_rust_log_level_after(
+    logging.Logger.removeHandler)
+logging.Handler.setLevel = _sync_rust_log_level_after(
+    logging.Handler.setLevel)
+sync_rust_log_level()
+
 PY_LIBRARY = Library(ctypes.PyDLL)
 
 
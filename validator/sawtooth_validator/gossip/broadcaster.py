@@ -0,0 +1,182 @@
+# Copyright 2018 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#     http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+# ------------------------------------------------------------------------------
+
+import ctypes
+from enum import IntEnum
+
+from sawtooth_validator.exceptions import PeeringException
+from sawtooth_validator.ffi import PY_LIBRARY
+from sawtooth_validator.ffi import LIBRARY
+from sawtooth_validator.ffi import OwnedPointer
+
+
+class ErrorCode(IntEnum):
+    Success = 0
+    NullPointerProvided = 0x01
+    InvalidInputString = 0x02
+    InvalidArgument = 0x03
+    MaximumPeersReached = 0x04
+    Error = 0x05
+
+
+class _GossipService:
+    """Sends gossip over the network and looks up requested blocks and
+    batches on behalf of the Rust gossip implementation.
+    """
+
+    def __init__(self, network, completer):
+        self._network = network
+        self._completer = completer
+
+    def send(self, message_type, payload, connection_id):
+        # Raises ValueError if the connection has closed
+        if self._network.is_connection_handshake_complete(connection_id):
+            self._network.send(
+                message_type, payload, connection_id, one_way=True)
+
+    def get_block(self, block_id):
+        block = self._completer.get_block(block_id)
+        return block.SerializeToString() if block is not None else None
+
+    def get_batch(self, batch_id):
+        batch = self._completer.get_batch(batch_id)
+        return batch.SerializeToString() if batch is not None else None
+
+
+class GossipBroadcaster(OwnedPointer):
+    """Registers peers, broadcasts blocks and batches to them, and answers
+    block and batch requests, using the Rust gossip implementation.
+    """
+
+    def __init__(self, network, completer, maximum_peer_connectivity,
+                 fanout=None):
+        """
+        Args:
+            network (:obj:`Interconnect`): The network to send gossip over.
+            completer (:obj:`Completer`): Where requested blocks and batches
+                are looked up.
+            maximum_peer_connectivity (int): The maximum number of peers.
+            fanout (int): The maximum number of peers each broadcast is sent
+                to; by default, broadcasts are sent to every peer.
+        """
+        super().__init__('gossip_drop')
+
+        self._gossip_service = _GossipService(network, completer)
+        self._maximum_peer_connectivity = maximum_peer_connectivity
+
+        PY_LIBRARY.call(
+            'gossip_new',
+            ctypes.py_object(self._gossip_service),
+            ctypes.c_size_t(maximum_peer_connectivity),
+            ctypes.c_size_t(fanout or 0),
+            ctypes.byref(self.pointer))
+
+    def register_peer(self, connection_id, endpoint):
+        """Registers a connected connection_id.
+
+        Raises:
+            PeeringException: if the maximum number of peers are registered.
+        """
+        self._call(
+            'gossip_register_peer',
+            ctypes.c_char_p(connection_id.encode()),
+            ctypes.c_char_p(endpoint.encode()))
+
+    def unregister_peer(self, connection_id):
+        """Removes a connection_id from the registry, returning whether it
+        was registered.
+        """
+        removed = ctypes.c_bool(False)
+        self._call(
+            'gossip_unregister_peer',
+            ctypes.c_char_p(connection_id.encode()),
+            ctypes.byref(removed))
+        return removed.value
+
+    def broadcast_block(self, block, time_to_live, exclude=None):
+        payload = block.SerializeToString()
+        self._call(
+            'gossip_broadcast_block',
+            payload,
+            len(payload),
+            _optional_str(exclude),
+            ctypes.c_uint32(time_to_live))
+
+    def broadcast_batch(self, batch, time_to_live, exclude=None):
+        payload = batch.SerializeToString()
+        self._call(
+            'gossip_broadcast_batch',
+            payload,
+            len(payload),
+            _optional_str(exclude),
+            ctypes.c_uint32(time_to_live))
+
+    def broadcast_block_request(self, block_id, time_to_live):
+        self._call(
+            'gossip_broadcast_block_request',
+            ctypes.c_char_p(block_id.encode()),
+            ctypes.c_uint32(time_to_live))
+
+    def broadcast_batch_by_batch_id_request(self, batch_id, time_to_live):
+        self._call(
+            'gossip_broadcast_batch_request',
+            ctypes.c_char_p(batch_id.encode()),
+            ctypes.c_uint32(time_to_live))
+
+    def handle_block_request(self, connection_id, request):
+        """Answers a GossipBlockRequest if the block is known, otherwise
+        forwards it to the other peers.
+        """
+        payload = request.SerializeToString()
+        self._call(
+            'gossip_handle_block_request',
+            ctypes.c_char_p(connection_id.encode()),
+            payload,
+            len(payload))
+
+    def handle_batch_request(self, connection_id, request):
+        """Answers a GossipBatchByBatchIdRequest if the batch is known,
+        otherwise forwards it to the other peers.
+        """
+        payload = request.SerializeToString()
+        self._call(
+            'gossip_handle_batch_request',
+            ctypes.c_char_p(connection_id.encode()),
+            payload,
+            len(payload))
+
+    def _call(self, fn_name, *args):
+        return_code = LIBRARY.call(fn_name, self.pointer, *args)
+
+        if return_code == ErrorCode.Success:
+            return
+        if return_code == ErrorCode.NullPointerProvided:
+            raise TypeError("Provided null pointer(s)")
+        if return_code == ErrorCode.InvalidInputString:
+            raise ValueError("Input was not valid UTF-8")
+        if return_code == ErrorCode.InvalidArgument:
+            raise ValueError("Input was not valid")
+        if return_code == ErrorCode.MaximumPeersReached:
+            raise PeeringException(
+                "At maximum configured number of peers: {}".format(
+                    self._maximum_peer_connectivity))
+
+        raise RuntimeError(
+            "An unknown error occurred in {}: {}".format(
+                fn_name, return_code))
+
+
+def _optional_str(value):
+    return ctypes.c_char_p(value.encode()) if value is not None else None
@@ -37,6 +37,7 @@ from sawtooth_validator.protobuf.network_pb2 import GetPeersRequest
 from sawtooth_validator.protobuf.network_pb2 import GetPeersResponse
 from sawtooth_validator.protobuf.network_pb2 import NetworkAcknowledgement
 from sawtooth_validator.exceptions import PeeringException
+from sawtooth_validator.gossip.peer_scores import PeerScoreboard
 
 LOGGER = logging.getLogger(__name__)
 
@@ -150,6 +151,10 @@ class Gossip:
         self._topology = None
         self._peers = {}
 
+        self.peer_scores = PeerScoreboard(
+            network.connection_id_to_public_key,
+            on_ban=self._ban_peer)
+
     def send_peers(self, connection_id):
         """Sends a message containing our peers to the
         connection identified by connection_id.
@@ -227,6 +232,11 @@ class Gossip:
             endpoint (str): The publically reachable endpoint of the new
                 peer
         """
+        if self.peer_scores.is_banned(connection_id):
+            raise PeeringException(
+                "Rejecting peering request from banned peer {}.".format(
+                    endpoint))
+
         with self._lock:
             if len(self._peers) < self._maximum_peer_connectivity:
                 self._peers[connection_id] = endpoint
@@ -377,6 +387,7 @@ class Gossip:
                          connection_id)
             if connection_id in self._peers:
                 del self._peers[connection_id]
+                self.peer_scores.record_unresponsive(connection_id)
 
     def broadcast(self, gossip_message, message_type, exclude=None):
         """Broadcast gossip messages.
@@ -394,7 +405,8 @@ class Gossip:
             if exclude is None:
                 exclude = []
             message_as_string = gossip_message.SerializeToString()
-            for connection_id in self._peers.copy():
+            for connection_id in self.peer_scores.prioritize(
+                    self._peers.copy()):
                 if connection_id not in exclude and \
                         self._network.is_connection_handshake_complete(
                             connection_id):
@@ -404,6 +416,12 @@ class Gossip:
                         connection_id,
                         one_way=True)
 
+    def _ban_peer(self, connection_id):
+        LOGGER.debug("Disconnecting banned peer %s", connection_id)
+        if connection_id in self._peers:
+            del self._peers[connection_id]
+        self._network.remove_connection(connection_id)
+
     def connect_success(self, connection_id):
         """
         Notify topology that a connection has been properly authorized
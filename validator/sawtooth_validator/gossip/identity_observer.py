@@ -23,6 +23,8 @@ from sawtooth_validator.server.events.subscription import EventSubscription
 
 LOGGER = logging.getLogger(__name__)
 
+IDENTITY_NAMESPACE = '00001d'
+
 
 class IdentityObserver(ChainObserver):
     """
@@ -43,7 +45,8 @@ class IdentityObserver(ChainObserver):
         Handles both "sawtooth/block-commit" Events and "identity/update"
         Events. For "sawtooth/block-commit", the last_block_num is updated or a
         fork is detected. For "identity/update", the corresponding cache entry
-        will be updated.
+        will be updated. Writes to the identity namespace without an
+        "identity/update" event invalidate the entire cache.
         """
 
         block_events = BlockEventExtractor(block).extract([
@@ -56,10 +59,30 @@ class IdentityObserver(ChainObserver):
             if forked:
                 return
 
+        if any(self._has_unnamed_identity_write(receipt)
+               for receipt in receipts):
+            # The updated roles and policies are not known, so none of the
+            # cached values can be trusted
+            self.forked()
+            return
+
         for event in receipt_events:
             if event.event_type == "identity/update":
                 self._handle_txn_commit(event)
 
+    @staticmethod
+    def _has_unnamed_identity_write(receipt):
+        """Returns whether the receipt's transaction wrote to the identity
+        namespace without an "identity/update" event naming what changed.
+        """
+        wrote_identity = any(
+            change.address.startswith(IDENTITY_NAMESPACE)
+            for change in receipt.state_changes)
+        named = any(
+            event.event_type == "identity/update"
+            for event in receipt.events)
+        return wrote_identity and not named
+
     def _handle_txn_commit(self, event):
         updated = event.attributes[0].value
         self.to_update(updated)
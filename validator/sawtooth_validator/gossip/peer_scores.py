@@ -0,0 +1,230 @@
+# Copyright 2018 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#     http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+# ------------------------------------------------------------------------------
+
+import logging
+import math
+from threading import Lock
+import time
+
+from sawtooth_validator import metrics
+from sawtooth_validator.journal.timed_cache import TimedCache
+
+LOGGER = logging.getLogger(__name__)
+COLLECTOR = metrics.get_collector(__name__)
+
+MAXIMUM_SCORE = 100.0
+
+INVALID_BLOCK_PENALTY = 40.0
+MALFORMED_MESSAGE_PENALTY = 10.0
+UNRESPONSIVE_PENALTY = 5.0
+
+
+class PeerScoreboard:
+    """Scores peers by their behavior.
+
+    Every peer starts at MAXIMUM_SCORE, and loses points for publishing
+    invalid blocks, sending malformed messages, and dropping their
+    connection. Blocks are gossiped before they are validated, so a peer
+    that relays an invalid block someone else signed is not penalized. Lost
+    points recover over time, halving the gap to MAXIMUM_SCORE every
+    `half_life` seconds.
+
+    Peers scoring below `deprioritize_threshold` are sent gossip after every
+    other peer. Peers scoring at or below `ban_threshold` are banned for
+    `ban_duration` seconds, during which they may not peer.
+
+    Peers are scored by public key, so that reconnecting does not reset their
+    score.
+    """
+
+    def __init__(self, to_peer_id, on_ban=None, half_life=600,
+                 deprioritize_threshold=50.0, ban_threshold=0.0,
+                 ban_duration=600, block_source_keep_time=300,
+                 clock=time.monotonic):
+        """
+        Args:
+            to_peer_id (fn(connection_id) -> str): Returns the public key of
+                the peer on a connection, or None if it is not known.
+            on_ban (fn(connection_id)): Called when the peer on a connection
+                is banned.
+            half_life (float): Seconds for half of a peer's lost points to
+                recover.
+            deprioritize_threshold (float): Score below which a peer is
+                deprioritized.
+            ban_threshold (float): Score at or below which a peer is banned.
+            ban_duration (float): Seconds a ban lasts.
+            block_source_keep_time (float): Seconds to remember which peer
+                signed and sent a block, for penalizing the peer if the block
+                turns out to be invalid.
+        """
+        self._to_peer_id = to_peer_id
+        self._on_ban = on_ban
+        self._decay_rate = math.log(2) / half_life
+        self._deprioritize_threshold = deprioritize_threshold
+        self._ban_threshold = ban_threshold
+        self._ban_duration = ban_duration
+        self._clock = clock
+
+        self._lock = Lock()
+        # peer id -> (score, time last updated)
+        self._scores = {}
+        # peer id -> time ban ends
+        self._bans = {}
+        self._block_sources = TimedCache(keep_time=block_source_keep_time)
+
+        self._penalties_count = COLLECTOR.counter(
+            'peer_penalties_count', instance=self)
+        self._bans_count = COLLECTOR.counter(
+            'peer_bans_count', instance=self)
+        self._banned_gauge = COLLECTOR.gauge(
+            'banned_peers', instance=self)
+        self._banned_gauge.set_value(0)
+
+    def record_block_source(self, block_id, signer_public_key,
+                            connection_id):
+        """Remembers the connection a block was received on, if the peer on
+        it signed the block. Blocks relayed for another signer are not
+        remembered.
+        """
+        if self._to_peer_id(connection_id) == signer_public_key:
+            self._block_sources[block_id] = connection_id
+
+    def record_invalid_block(self, block_id):
+        """Penalizes the peer that signed and sent a now invalid block, if
+        it is known.
+        """
+        try:
+            connection_id = self._block_sources.pop(block_id)
+        except KeyError:
+            return
+
+        self.penalize(connection_id, INVALID_BLOCK_PENALTY, 'invalid block')
+
+    def record_malformed_message(self, connection_id):
+        self.penalize(
+            connection_id, MALFORMED_MESSAGE_PENALTY, 'malformed message')
+
+    def record_unresponsive(self, connection_id):
+        self.penalize(connection_id, UNRESPONSIVE_PENALTY, 'unresponsive')
+
+    def penalize(self, connection_id, penalty, reason):
+        peer_id = self._resolve(connection_id)
+
+        with self._lock:
+            now = self._clock()
+            score = self._current_score(peer_id, now) - penalty
+            self._scores[peer_id] = (score, now)
+            self._penalties_count.inc()
+
+            LOGGER.debug(
+                'Penalized peer %s for %s, score is now %.1f',
+                peer_id[:8], reason, score)
+
+            banned = score <= self._ban_threshold and \
+                not self._is_banned(peer_id, now)
+            if banned:
+                LOGGER.warning(
+                    'Banning peer %s for %s seconds', peer_id[:8],
+                    self._ban_duration)
+                self._bans[peer_id] = now + self._ban_duration
+                # Give the peer a fresh start when the ban ends
+                self._scores[peer_id] = (
+                    self._deprioritize_threshold, now + self._ban_duration)
+                self._bans_count.inc()
+                self._update_banned_gauge(now)
+
+        if banned and self._on_ban is not None:
+            self._on_ban(connection_id)
+
+    def score(self, connection_id):
+        peer_id = self._resolve(connection_id)
+        with self._lock:
+            return self._current_score(peer_id, self._clock())
+
+    def is_banned(self, connection_id):
+        peer_id = self._resolve(connection_id)
+        with self._lock:
+            return self._is_banned(peer_id, self._clock())
+
+    def is_deprioritized(self, connection_id):
+        return self.score(connection_id) < self._deprioritize_threshold
+
+    def prioritize(self, connection_ids):
+        """Returns the connection ids with deprioritized peers last, leaving
+        out banned peers.
+        """
+        preferred = []
+        deprioritized = []
+        for connection_id in connection_ids:
+            if self.is_banned(connection_id):
+                continue
+            if self.is_deprioritized(connection_id):
+                deprioritized.append(connection_id)
+            else:
+                preferred.append(connection_id)
+
+        return preferred + deprioritized
+
+    def scores(self):
+        """Returns a list of (peer id, score, seconds left banned) for every
+        peer that has been penalized and has not fully recovered.
+        """
+        with self._lock:
+            now = self._clock()
+            self._update_banned_gauge(now)
+
+            result = []
+            for peer_id in list(self._scores):
+                score = self._current_score(peer_id, now)
+                ban_remaining = max(0.0, self._bans.get(peer_id, now) - now)
+                if score >= MAXIMUM_SCORE - 0.5 and not ban_remaining:
+                    # Close enough to a full recovery to forget the peer
+                    del self._scores[peer_id]
+                    continue
+
+                result.append((peer_id, score, ban_remaining))
+
+            return sorted(result, key=lambda entry: entry[1])
+
+    def _resolve(self, connection_id):
+        peer_id = self._to_peer_id(connection_id)
+        return peer_id if peer_id is not None else connection_id
+
+    def _current_score(self, peer_id, now):
+        try:
+            score, last_update = self._scores[peer_id]
+        except KeyError:
+            return MAXIMUM_SCORE
+
+        elapsed = max(0.0, now - last_update)
+        deficit = (MAXIMUM_SCORE - score) * math.exp(
+            -self._decay_rate * elapsed)
+        return MAXIMUM_SCORE - deficit
+
+    def _is_banned(self, peer_id, now):
+        ban_end = self._bans.get(peer_id)
+        if ban_end is None:
+            return False
+        if ban_end <= now:
+            self._update_banned_gauge(now)
+            return False
+        return True
+
+    def _update_banned_gauge(self, now):
+        self._bans = {
+            peer_id: ban_end for peer_id, ban_end in self._bans.items()
+            if ban_end > now
+        }
+        self._banned_gauge.set_value(len(self._bans))
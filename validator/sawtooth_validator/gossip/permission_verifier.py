@@ -12,8 +12,10 @@
 # See the License for the specific language governing permissions and
 # limitations under the License.
 # ------------------------------------------------------------------------------
+from collections import OrderedDict
 import logging
 
+from sawtooth_validator import metrics
 from sawtooth_validator.protobuf import client_batch_submit_pb2
 from sawtooth_validator.protobuf.batch_pb2 import BatchHeader
 from sawtooth_validator.protobuf.transaction_pb2 import TransactionHeader
@@ -32,6 +34,7 @@ from sawtooth_validator.state.merkle import INIT_ROOT_KEY
 
 
 LOGGER = logging.getLogger(__name__)
+COLLECTOR = metrics.get_collector(__name__)
 
 
 class _LogGuard:
@@ -348,18 +351,23 @@ class PermissionVerifier:
         return True
 
     def _allowed(self, public_key, policy):
-        for entry in policy.entries:
-            if entry.type == Policy.PERMIT_KEY:
-                if public_key == entry.key or entry.key == "*":
-                    return True
+        entry = evaluate_policy(public_key, policy)
+        # Default last entry is always DENY all
+        return entry is not None and entry.type == Policy.PERMIT_KEY
 
-            elif entry.type == Policy.DENY_KEY:
 
-                if public_key == entry.key or entry.key == "*":
-                    return False
+def evaluate_policy(public_key, policy):
+    """Returns the first entry of the policy whose key matches the public
+    key, which decides whether the key is permitted, or None if no entry
+    matches and the key is denied by default.
+    """
+    for entry in policy.entries:
+        if entry.type not in (Policy.PERMIT_KEY, Policy.DENY_KEY):
+            continue
+        if entry.key in (public_key, "*"):
+            return entry
 
-        # Default last entry is always DENY all
-        return False
+    return None
 
 
 class BatchListPermissionVerifier(Handler):
@@ -467,10 +475,28 @@ class NetworkConsensusPermissionHandler(Handler):
 
 
 class IdentityCache():
-    def __init__(self, identity_view_factory):
+    """Caches identity roles and policies.
+
+    Values read at the current chain head are cached by name, and are
+    invalidated by the IdentityObserver as identity transactions are
+    committed. Values read directly from state, at a state root which may
+    not be the chain head's, are cached by state root; since the state at a
+    root never changes, these are only evicted to bound the cache to the
+    `state_root_cache_size` most recently used roots.
+    """
+
+    def __init__(self, identity_view_factory, state_root_cache_size=16):
         self._identity_view_factory = identity_view_factory
         self._identity_view = None
         self._cache = {}
+        self._state_root_cache_size = state_root_cache_size
+        # state root -> {(kind, name) -> value}
+        self._state_root_caches = OrderedDict()
+
+        self._hit_count = COLLECTOR.counter(
+            'identity_cache_hit_count', instance=self)
+        self._miss_count = COLLECTOR.counter(
+            'identity_cache_miss_count', instance=self)
 
     def __len__(self):
         return len(self._cache)
@@ -491,23 +517,24 @@ class IdentityCache():
             item (string): the name of the role to be fetched
             state_root_func(fn -> string): The state root of the previous block
             from_state (bool): Whether the identity value should be read
-                directly from state, instead of using the cached values.
-                This should be used when the state_root passed is not from
-                the current chain head.
+                at the given state root, instead of using the values cached
+                for the chain head. This should be used when the state_root
+                passed is not from the current chain head.
         """
         if from_state:
-            # if from state use identity_view and do not add to cache
-            if self._identity_view is None:
-                self.update_view(state_root_func())
-            value = self._identity_view.get_role(item)
-            return value
+            return self._get_at_state_root(
+                'role', item, state_root_func(),
+                lambda view: view.get_role(item))
 
         value = self._cache.get(item)
         if value is None:
+            self._miss_count.inc()
             if self._identity_view is None:
                 self.update_view(state_root_func())
             value = self._identity_view.get_role(item)
             self._cache[item] = value
+        else:
+            self._hit_count.inc()
         return value
 
     def get_policy(self, item, state_root_func, from_state=False):
@@ -517,28 +544,50 @@ class IdentityCache():
             item (string): the name of the policy to be fetched
             state_root_func(fn -> string): The state root of the previous block
             from_state (bool): Whether the identity value should be read
-                directly from state, instead of using the cached values.
-                This should be used when the state_root passed is not from
-                the current chain head.
+                at the given state root, instead of using the values cached
+                for the chain head. This should be used when the state_root
+                passed is not from the current chain head.
         """
         if from_state:
-            # if from state use identity_view and do not add to cache
-            if self._identity_view is None:
-                self.update_view(state_root_func())
-            value = self._identity_view.get_policy(item)
-            return value
+            return self._get_at_state_root(
+                'policy', item, state_root_func(),
+                lambda view: view.get_policy(item))
 
         if item in self._cache:
+            self._hit_count.inc()
             value = self._cache.get(item)
         else:
+            self._miss_count.inc()
             if self._identity_view is None:
                 self.update_view(state_root_func())
             value = self._identity_view.get_policy(item)
             self._cache[item] = value
         return value
 
+    def _get_at_state_root(self, kind, item, state_root, read):
+        try:
+            values = self._state_root_caches[state_root]
+            self._state_root_caches.move_to_end(state_root)
+        except KeyError:
+            values = {}
+            self._state_root_caches[state_root] = values
+            while len(self._state_root_caches) > self._state_root_cache_size:
+                self._state_root_caches.popitem(last=False)
+
+        key = (kind, item)
+        if key in values:
+            self._hit_count.inc()
+            return values[key]
+
+        self._miss_count.inc()
+        value = read(
+            self._identity_view_factory.create_identity_view(state_root))
+        values[key] = value
+        return value
+
     def forked(self):
         self._cache = {}
+        self._state_root_caches = OrderedDict()
 
     def invalidate(self, item):
         if item in self._cache:
@@ -139,8 +139,22 @@ def is_valid_consensus_message(message):
     return True
 
 
+def _record_malformed(peer_scores, connection_id):
+    if peer_scores is not None:
+        peer_scores.record_malformed_message(connection_id)
+
+
+def _record_block_source(peer_scores, block, connection_id):
+    if peer_scores is not None:
+        header = BlockHeader()
+        header.ParseFromString(block.header)
+        peer_scores.record_block_source(
+            block.header_signature, header.signer_public_key, connection_id)
+
+
 class GossipMessageSignatureVerifier(Handler):
-    def __init__(self):
+    def __init__(self, peer_scores=None):
+        self._peer_scores = peer_scores
         self._seen_cache = TimedCache()
         self._batch_dropped_count = COLLECTOR.counter(
             'already_validated_batch_dropped_count', instance=self)
@@ -158,9 +172,11 @@ class GossipMessageSignatureVerifier(Handler):
             if not is_valid_block(obj):
                 LOGGER.debug("block signature is invalid: %s",
                              obj.header_signature)
+                _record_malformed(self._peer_scores, connection_id)
                 return HandlerResult(status=HandlerStatus.DROP)
 
             self._seen_cache[obj.header_signature] = None
+            _record_block_source(self._peer_scores, obj, connection_id)
             return HandlerResult(status=HandlerStatus.PASS)
 
         if tag == GossipMessage.BATCH:
@@ -171,6 +187,7 @@ class GossipMessageSignatureVerifier(Handler):
             if not is_valid_batch(obj):
                 LOGGER.debug("batch signature is invalid: %s",
                              obj.header_signature)
+                _record_malformed(self._peer_scores, connection_id)
                 return HandlerResult(status=HandlerStatus.DROP)
 
             self._seen_cache[obj.header_signature] = None
@@ -178,6 +195,7 @@ class GossipMessageSignatureVerifier(Handler):
 
         if tag == GossipMessage.CONSENSUS:
             if not is_valid_consensus_message(obj):
+                _record_malformed(self._peer_scores, connection_id)
                 return HandlerResult(status=HandlerStatus.DROP)
 
             return HandlerResult(status=HandlerStatus.PASS)
@@ -187,7 +205,8 @@ class GossipMessageSignatureVerifier(Handler):
 
 
 class GossipBlockResponseSignatureVerifier(Handler):
-    def __init__(self):
+    def __init__(self, peer_scores=None):
+        self._peer_scores = peer_scores
         self._seen_cache = TimedCache()
         self._block_dropped_count = COLLECTOR.counter(
             'already_validated_block_dropped_count', instance=self)
@@ -202,14 +221,17 @@ class GossipBlockResponseSignatureVerifier(Handler):
         if not is_valid_block(block):
             LOGGER.debug("requested block's signature is invalid: %s",
                          block.header_signature)
+            _record_malformed(self._peer_scores, connection_id)
             return HandlerResult(status=HandlerStatus.DROP)
 
         self._seen_cache[block.header_signature] = None
+        _record_block_source(self._peer_scores, block, connection_id)
         return HandlerResult(status=HandlerStatus.PASS)
 
 
 class GossipBatchResponseSignatureVerifier(Handler):
-    def __init__(self):
+    def __init__(self, peer_scores=None):
+        self._peer_scores = peer_scores
         self._seen_cache = TimedCache()
         self._batch_dropped_count = COLLECTOR.counter(
             'already_validated_batch_dropped_count', instance=self)
@@ -224,6 +246,7 @@ class GossipBatchResponseSignatureVerifier(Handler):
         if not is_valid_batch(batch):
             LOGGER.debug("requested batch's signature is invalid: %s",
                          batch.header_signature)
+            _record_malformed(self._peer_scores, connection_id)
             return HandlerResult(status=HandlerStatus.DROP)
 
         self._seen_cache[batch.header_signature] = None
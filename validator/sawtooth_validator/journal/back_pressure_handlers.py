@@ -13,7 +13,10 @@
 # limitations under the License.
 # ------------------------------------------------------------------------------
 
+import collections
 import logging
+import threading
+import time
 
 from sawtooth_validator.protobuf.client_batch_submit_pb2 \
     import ClientBatchSubmitResponse
@@ -80,3 +83,127 @@ class ClientBatchSubmitBackpressureHandler(Handler):
             LOGGER.info('Ending back pressure on client submitted batches')
 
         return HandlerResult(status=HandlerStatus.PASS)
+
+
+class ClientBatchSubmitDuplicateHandler(Handler):
+    """This handler receives a batch list, and returns an INVALID_BATCH
+    response if any of its batches were committed in a recent block.
+    """
+
+    def __init__(self, recent_batch_filter):
+        self._recent_batch_filter = recent_batch_filter
+
+        self._batches_rejected_count = COLLECTOR.counter(
+            'duplicate_batches_rejected_count', instance=self)
+
+    def handle(self, connection_id, message_content):
+        for batch in message_content.batches:
+            if self._recent_batch_filter.is_recently_committed(
+                    batch.header_signature):
+                LOGGER.debug(
+                    'Rejecting already committed batch %s',
+                    batch.header_signature[:8])
+                self._batches_rejected_count.inc()
+
+                response = ClientBatchSubmitResponse(
+                    status=ClientBatchSubmitResponse.INVALID_BATCH)
+                return HandlerResult(
+                    status=HandlerStatus.RETURN,
+                    message_out=response,
+                    message_type=Message.CLIENT_BATCH_SUBMIT_RESPONSE
+                )
+
+        return HandlerResult(status=HandlerStatus.PASS)
+
+
+class ClientBatchSubmitRateLimitHandler(Handler):
+    """This handler receives a batch list, and accepts it if none of its
+    signers have exceeded their submission rate.  Otherwise it returns a
+    QUEUE_FULL response.
+
+    Each signer public key has a bucket of `burst` batches, refilled at `rate`
+    batches per second. A batch list is only charged against the buckets if
+    it is accepted.
+
+    A batch list with more than `burst` batches from one signer could never
+    fit in its bucket, so it is accepted whenever the bucket is full. The
+    bucket is left owing the difference, and the signer must wait for it to
+    refill past zero before submitting again.
+    """
+
+    # Once this many signers are tracked, signers whose buckets have refilled
+    # are forgotten.
+    _MAX_TRACKED_SIGNERS = 10000
+
+    def __init__(self, allowlist_public_key, rate, burst,
+                 clock=time.monotonic):
+        self._allowlist_public_key = allowlist_public_key
+        self._rate = rate
+        self._burst = burst
+        self._clock = clock
+
+        self._lock = threading.Lock()
+        # signer public key -> (available batches, time last updated)
+        self._buckets = {}
+
+        self._batches_rejected_count = COLLECTOR.counter(
+            'rate_limited_batches_rejected_count', instance=self)
+
+    def handle(self, connection_id, message_content):
+        batch_counts = collections.Counter()
+        batch_header = BatchHeader()
+        for batch in message_content.batches:
+            batch_header.ParseFromString(batch.header)
+            if batch_header.signer_public_key == self._allowlist_public_key:
+                # There is a allow-listed batch, so allow it to continue
+                return HandlerResult(status=HandlerStatus.PASS)
+
+            batch_counts[batch_header.signer_public_key] += 1
+            batch_header.Clear()
+
+        with self._lock:
+            now = self._clock()
+            available = {
+                signer: self._available(signer, now)
+                for signer in batch_counts
+            }
+
+            limited = [
+                signer for signer, count in batch_counts.items()
+                if available[signer] < min(count, self._burst)
+            ]
+            if limited:
+                LOGGER.debug(
+                    'Rate limiting batches from signers %s',
+                    ', '.join(signer[:8] for signer in limited))
+                self._batches_rejected_count.inc()
+
+                response = ClientBatchSubmitResponse(
+                    status=ClientBatchSubmitResponse.QUEUE_FULL)
+                return HandlerResult(
+                    status=HandlerStatus.RETURN,
+                    message_out=response,
+                    message_type=Message.CLIENT_BATCH_SUBMIT_RESPONSE
+                )
+
+            if len(self._buckets) >= self._MAX_TRACKED_SIGNERS:
+                self._forget_idle_signers(now)
+
+            for signer, count in batch_counts.items():
+                self._buckets[signer] = (available[signer] - count, now)
+
+        return HandlerResult(status=HandlerStatus.PASS)
+
+    def _available(self, signer, now):
+        try:
+            tokens, last_update = self._buckets[signer]
+        except KeyError:
+            return self._burst
+
+        return min(self._burst, tokens + (now - last_update) * self._rate)
+
+    def _forget_idle_signers(self, now):
+        self._buckets = {
+            signer: bucket for signer, bucket in self._buckets.items()
+            if self._available(signer, now) < self._burst
+        }
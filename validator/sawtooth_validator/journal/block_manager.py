@@ -45,7 +45,9 @@ class ErrorCode(IntEnum):
     UnknownBlock = 0x05
     InvalidInputString = 0x06
     Error = 0x07
-    InvalidPythonObject = 0x0F
+    InvalidBlockBytes = 0x08
+    Panic = 0x09
+    InvalidPythonObject = 0x10
     StopIteration = 0x11
 
 
@@ -136,26 +138,48 @@ def _exec(library, name, *args):
     _check_error(library.call(name, *args))
 
 
+def _last_error():
+    """Returns the message describing the last error returned by the block
+    manager on this thread.
+    """
+    (vec_ptr, vec_len, vec_cap) = ffi.prepare_vec_result()
+    res = ffi.LIBRARY.call(
+        "block_manager_last_error",
+        ctypes.byref(vec_ptr),
+        ctypes.byref(vec_len),
+        ctypes.byref(vec_cap))
+    if res != ErrorCode.Success:
+        return ''
+
+    return ffi.from_rust_vec(vec_ptr, vec_len, vec_cap).decode()
+
+
 def _check_error(res):
     if res == ErrorCode.Success:
         return
 
-    if res == ErrorCode.NullPointerProvided:
-        raise TypeError("Provided null pointer(s)")
-    elif res == ErrorCode.StopIteration:
+    if res == ErrorCode.StopIteration:
         raise StopIteration()
+
+    message = _last_error()
+    if res == ErrorCode.NullPointerProvided:
+        raise TypeError(message or "Provided null pointer(s)")
     elif res == ErrorCode.MissingPredecessor:
-        raise MissingPredecessor("Missing predecessor")
+        raise MissingPredecessor(message or "Missing predecessor")
     elif res == ErrorCode.MissingPredecessorInBranch:
-        raise MissingPredecessorInBranch("Missing predecessor")
+        raise MissingPredecessorInBranch(message or "Missing predecessor")
     elif res == ErrorCode.MissingInput:
-        raise MissingInput("Missing input to put method")
+        raise MissingInput(message or "Missing input to put method")
     elif res == ErrorCode.UnknownBlock:
-        raise UnknownBlock("Block was unknown")
-    elif res == ErrorCode.InvalidInputString:
-        raise TypeError("Invalid block store name provided")
+        raise UnknownBlock(message or "Block was unknown")
+    elif res in (ErrorCode.InvalidInputString,
+                 ErrorCode.InvalidPythonObject):
+        raise TypeError(message or "Invalid string provided")
+    elif res == ErrorCode.InvalidBlockBytes:
+        raise ValueError(message or "Invalid block provided")
     else:
-        raise Exception("There was an unknown error: {}".format(res))
+        raise Exception("There was an unknown error: {}: {}".format(
+            res, message))
 
 
 class _GetBlockIterator(ffi.BlockIterator):
@@ -206,3 +230,4 @@ class _BranchIterator(ffi.BlockIterator):
                  block_manager_ptr,
                  c_tip,
                  ctypes.byref(self.pointer))
+
@@ -0,0 +1,46 @@
+# Copyright 2018 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#     http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+# ------------------------------------------------------------------------------
+
+import os
+
+from sawtooth_validator.exceptions import LocalConfigurationError
+
+
+class ChainIdManager:
+    """
+    Provides the block-chain-id, the id of the genesis block, which the
+    journal stores in the data_dir once the genesis block is committed.
+    """
+
+    def __init__(self, data_dir):
+        self._data_dir = data_dir
+
+    def get_block_chain_id(self):
+        """Returns the block-chain-id, or None if no genesis block has been
+        committed yet.
+        """
+        block_chain_id_file = os.path.join(self._data_dir, 'block-chain-id')
+        if not os.path.exists(block_chain_id_file):
+            return None
+
+        try:
+            with open(block_chain_id_file, 'r') as f:
+                block_chain_id = f.read().strip()
+        except IOError as e:
+            raise LocalConfigurationError(
+                'The block-chain-id file exists, but is unreadable: '
+                '{}'.format(e)) from e
+
+        return block_chain_id if block_chain_id else None
@@ -13,25 +13,34 @@
 # limitations under the License.
 # ------------------------------------------------------------------------------
 
+import ctypes
+from enum import IntEnum
 import logging
-from threading import RLock
-from collections import deque
 
+from sawtooth_validator import ffi
+from sawtooth_validator.ffi import OwnedPointer
 from sawtooth_validator.journal.block_manager import MissingPredecessor
 from sawtooth_validator.journal.block_wrapper import BlockWrapper
-from sawtooth_validator.journal.timed_cache import TimedCache
-from sawtooth_validator.protobuf.transaction_pb2 import TransactionHeader
+from sawtooth_validator.protobuf.batch_pb2 import Batch
+from sawtooth_validator.protobuf.block_pb2 import Block
 from sawtooth_validator.protobuf import network_pb2
 from sawtooth_validator.networking.dispatch import Handler
 from sawtooth_validator.networking.dispatch import HandlerResult
 from sawtooth_validator.networking.dispatch import HandlerStatus
-from sawtooth_validator import metrics
 
 LOGGER = logging.getLogger(__name__)
-COLLECTOR = metrics.get_collector(__name__)
 
 
-class Completer:
+class ErrorCode(IntEnum):
+    Success = ffi.CommonErrorCode.Success
+    NullPointerProvided = ffi.CommonErrorCode.NullPointerProvided
+    InvalidInputString = 0x02
+    InvalidArgument = 0x03
+    NotFound = 0x04
+    Error = 0x05
+
+
+class Completer(OwnedPointer):
     """
     The Completer is responsible for making sure blocks are formally
     complete before they are delivered to the chain controller. A formally
@@ -40,7 +49,11 @@ class Completer:
     block header. If the predecessor or a batch is missing, a request message
     is sent sent out over the gossip network. It also checks that all batches
     have their dependencies satisifed, otherwise it will request the batch that
-    has the missing transaction.
+    has the missing transaction. Requests that time out while what they asked
+    for is still missing are sent again.
+
+    The tracking of incomplete blocks and batches is done by the validator's
+    Rust library.
     """
 
     def __init__(self,
@@ -68,291 +81,56 @@ class Completer:
             TimedCaches.
         :param cache_purge_frequency (float) Time between purging the
             TimedCaches.
-        :param requested_keep_time (float) Time in seconds to keep the ids
-            of requested objects. WARNING this time should always be less than
-            cache_keep_time or the validator can get into a state where it
-            fails to make progress because it thinks it has already requested
-            something that it is missing.
+        :param requested_keep_time (float) Time in seconds after which a
+            request is sent again if what it asked for is still missing.
+            WARNING this time should always be less than cache_keep_time, or
+            the blocks and batches waiting on a request are dropped before it
+            is sent again.
         """
-        self._gossip = gossip
-        self._batch_cache = TimedCache(cache_keep_time, cache_purge_frequency)
-        self._block_manager = block_manager
+        super().__init__('completer_drop')
 
-        self._transaction_committed = transaction_committed
+        self._block_manager = block_manager
         self._get_committed_batch_by_id = get_committed_batch_by_id
         self._get_committed_batch_by_txn_id = get_committed_batch_by_txn_id
-
-        self._seen_txns = TimedCache(cache_keep_time, cache_purge_frequency)
-        self._incomplete_batches = TimedCache(cache_keep_time,
-                                              cache_purge_frequency)
-        self._incomplete_blocks = TimedCache(cache_keep_time,
-                                             cache_purge_frequency)
-        self._requested = TimedCache(requested_keep_time,
-                                     cache_purge_frequency)
         self._get_chain_head = None
-        self._on_block_received = None
-        self._on_batch_received = None
-        self.lock = RLock()
-
-        # Tracks how many times an unsatisfied dependency is found
-        self._unsatisfied_dependency_count = COLLECTOR.counter(
-            'unsatisfied_dependency_count', instance=self)
-        # Tracks the length of the completer's _seen_txns
-        self._seen_txns_length = COLLECTOR.gauge(
-            'seen_txns_length', instance=self)
-        self._seen_txns_length.set_value(0)
-        # Tracks the length of the completer's _incomplete_blocks
-        self._incomplete_blocks_length = COLLECTOR.gauge(
-            'incomplete_blocks_length', instance=self)
-        self._incomplete_blocks_length.set_value(0)
-        # Tracks the length of the completer's _incomplete_batches
-        self._incomplete_batches_length = COLLECTOR.gauge(
-            'incomplete_batches_length', instance=self)
-        self._incomplete_batches_length.set_value(0)
-
-    def _put_or_request_if_missing_predecessor(self, blkw):
-        try:
-            # Create Ref-B
-            self._block_manager.put([blkw.block])
-            return blkw
-        except MissingPredecessor:
-            # The predecessor dropped out of the block manager between when we
-            # checked if it was there and when the block was determined to be
-            # complete.
-            return self._request_previous_if_not_already_requested(blkw)
-
-    def _request_previous_if_not_already_requested(self, blkw):
-        if blkw.previous_block_id not in self._incomplete_blocks:
-            self._incomplete_blocks[blkw.previous_block_id] = [blkw]
-        elif blkw not in \
-                self._incomplete_blocks[blkw.previous_block_id]:
-            self._incomplete_blocks[blkw.previous_block_id] += [blkw]
-
-        # We have already requested the block, do not do so again
-        if blkw.previous_block_id in self._requested:
-            return None
-
-        LOGGER.debug(
-            "Request missing predecessor: %s",
-            blkw.previous_block_id)
-        self._requested[blkw.previous_block_id] = None
-        self._gossip.broadcast_block_request(blkw.previous_block_id)
-        return None
-
-    def _complete_block(self, block):
-        """ Check the block to see if it is complete and if it can be passed to
-            the journal. If the block's predecessor is not in the block_manager
-            the predecessor is requested and the current block is added to the
-            the incomplete_block cache. If the block.batches and
-            block.header.batch_ids are not the same length, the batch_id list
-            is checked against the batch_cache to see if the batch_list can be
-            built. If any batches are missing from the block and we do not have
-            the batches in the batch_cache, they are requested. The block is
-            then added to the incomplete_block cache. If we can complete the
-            block, a new batch list is created in the correct order and added
-            to the block. The block is now considered complete and is returned.
-            If block.batches and block.header.batch_ids are the same length,
-            the block's batch list needs to be in the same order as the
-            block.header.batch_ids list. If the block has all of its expected
-            batches but are not in the correct order, the batch list is rebuilt
-            and added to the block. Once a block has the correct batch list it
-            is added to the block_manager and is returned.
-
-        """
-
-        if block.header_signature in self._block_manager:
-            LOGGER.debug("Drop duplicate block: %s", block)
-            return None
 
-        # NOTE: We cannot assume that if the previous block _is_ in the block
-        # manager, that it will still be in there when this block is complete.
-        if block.previous_block_id not in self._block_manager:
-            return self._request_previous_if_not_already_requested(block)
+        # Holds the callbacks, and is kept alive for as long as the Rust
+        # completer calls it
+        self._service = _CompleterService(
+            block_manager, transaction_committed, gossip)
 
-        # Check for same number of batch_ids and batches
-        # If different starting building batch list, Otherwise there is a batch
-        # that does not belong, block should be dropped.
-        if len(block.batches) > len(block.header.batch_ids):
-            LOGGER.debug("Block has extra batches. Dropping %s", block)
-            return None
-
-        # used to supplement batch_cache, contains batches already in block
-        temp_batches = {}
-        for batch in block.batches:
-            temp_batches[batch.header_signature] = batch
-
-        # The block is missing batches. Check to see if we can complete it.
-        if len(block.batches) != len(block.header.batch_ids):
-            building = True
-            for batch_id in block.header.batch_ids:
-                if batch_id not in self._batch_cache and \
-                        batch_id not in temp_batches:
-                    # Request all missing batches
-                    if batch_id not in self._incomplete_blocks:
-                        self._incomplete_blocks[batch_id] = [block]
-                    elif block not in self._incomplete_blocks[batch_id]:
-                        self._incomplete_blocks[batch_id] += [block]
-
-                    # We have already requested the batch, do not do so again
-                    if batch_id in self._requested:
-                        return None
-                    self._requested[batch_id] = None
-                    self._gossip.broadcast_batch_by_batch_id_request(batch_id)
-                    building = False
-
-            # The block cannot be completed.
-            if not building:
-                return None
-
-            batches = self._finalize_batch_list(block, temp_batches)
-            del block.batches[:]
-            # reset batches with full list batches
-            block.batches.extend(batches)
-            if block.header_signature in self._requested:
-                del self._requested[block.header_signature]
-
-            return self._put_or_request_if_missing_predecessor(block)
-
-        batch_id_list = [x.header_signature for x in block.batches]
-        # Check to see if batchs are in the correct order.
-        if batch_id_list == list(block.header.batch_ids):
-            if block.header_signature in self._requested:
-                del self._requested[block.header_signature]
-
-            return self._put_or_request_if_missing_predecessor(block)
-
-        # Check to see if the block has all batch_ids and they can be put
-        # in the correct order
-        if sorted(batch_id_list) == sorted(list(block.header.batch_ids)):
-            batches = self._finalize_batch_list(block, temp_batches)
-            # Clear batches from block
-            del block.batches[:]
-            # reset batches with full list batches
-            if batches is not None:
-                block.batches.extend(batches)
-            else:
-                return None
-
-            if block.header_signature in self._requested:
-                del self._requested[block.header_signature]
-
-            return self._put_or_request_if_missing_predecessor(block)
-
-        LOGGER.debug("Block.header.batch_ids does not match set of "
-                     "batches in block.batches Dropping %s", block)
-        return None
-
-    def _finalize_batch_list(self, block, temp_batches):
-        batches = []
-        for batch_id in block.header.batch_ids:
-            if batch_id in self._batch_cache:
-                batches.append(self._batch_cache[batch_id])
-            elif batch_id in temp_batches:
-                batches.append(temp_batches[batch_id])
-            else:
-                return None
-
-        return batches
-
-    def _complete_batch(self, batch):
-        valid = True
-        dependencies = []
-        for txn in batch.transactions:
-            txn_header = TransactionHeader()
-            txn_header.ParseFromString(txn.header)
-            for dependency in txn_header.dependencies:
-                # Check to see if the dependency has been seen or is committed
-                if dependency not in self._seen_txns and not \
-                        self._transaction_committed(dependency):
-                    self._unsatisfied_dependency_count.inc()
-
-                    # Check to see if the dependency has already been requested
-                    if dependency not in self._requested:
-                        dependencies.append(dependency)
-                        self._requested[dependency] = None
-                    if dependency not in self._incomplete_batches:
-                        self._incomplete_batches[dependency] = [batch]
-                    elif batch not in self._incomplete_batches[dependency]:
-                        self._incomplete_batches[dependency] += [batch]
-                    valid = False
-        if not valid:
-            self._gossip.broadcast_batch_by_transaction_id_request(
-                dependencies)
-
-        return valid
-
-    def _add_seen_txns(self, batch):
-        for txn in batch.transactions:
-            self._seen_txns[txn.header_signature] = batch.header_signature
-            self._seen_txns_length.set_value(
-                len(self._seen_txns))
-
-    def _process_incomplete_batches(self, key):
-        # Keys are transaction_id
-        if key in self._incomplete_batches:
-            batches = self._incomplete_batches[key]
-            for batch in batches:
-                self.add_batch(batch)
-            del self._incomplete_batches[key]
-
-    def _process_incomplete_blocks(self, key):
-        # Keys are either a block_id or batch_id
-        if key in self._incomplete_blocks:
-            to_complete = deque()
-            to_complete.append(key)
-
-            while to_complete:
-                my_key = to_complete.popleft()
-                if my_key in self._incomplete_blocks:
-                    inc_blocks = self._incomplete_blocks[my_key]
-                    for inc_block in inc_blocks:
-                        if self._complete_block(inc_block):
-                            self._send_block(inc_block.block)
-                            to_complete.append(inc_block.header_signature)
-                    del self._incomplete_blocks[my_key]
-
-    def _send_block(self, block):
-        self._on_block_received(block.header_signature)
+        _libexec(
+            'completer_new',
+            ctypes.py_object(self._service),
+            ctypes.c_uint64(int(cache_keep_time)),
+            ctypes.c_uint64(int(cache_purge_frequency)),
+            ctypes.c_uint64(int(requested_keep_time)),
+            ctypes.byref(self.pointer))
 
     def set_get_chain_head(self, get_chain_head):
         self._get_chain_head = get_chain_head
 
     def set_on_block_received(self, on_block_received_func):
-        self._on_block_received = on_block_received_func
+        self._service.on_block_received = on_block_received_func
 
     def set_on_batch_received(self, on_batch_received_func):
-        self._on_batch_received = on_batch_received_func
+        self._service.on_batch_received = on_batch_received_func
 
     def add_block(self, block):
-        with self.lock:
-            blkw = BlockWrapper(block)
-            block = self._complete_block(blkw)
-            if block is not None:
-                self._send_block(block.block)
-                self._process_incomplete_blocks(block.header_signature)
-            self._incomplete_blocks_length.set_value(
-                len(self._incomplete_blocks))
+        block_bytes = BlockWrapper.wrap(block).block.SerializeToString()
+        _libexec(
+            'completer_add_block',
+            self.pointer,
+            block_bytes,
+            len(block_bytes))
 
     def add_batch(self, batch):
-        with self.lock:
-            if batch.header_signature in self._batch_cache:
-                return
-            if self._complete_batch(batch):
-                self._batch_cache[batch.header_signature] = batch
-                self._add_seen_txns(batch)
-                self._on_batch_received(batch)
-                self._process_incomplete_blocks(batch.header_signature)
-                if batch.header_signature in self._requested:
-                    del self._requested[batch.header_signature]
-                # If there was a batch waiting on this transaction, process
-                # that batch
-                for txn in batch.transactions:
-                    if txn.header_signature in self._incomplete_batches:
-                        if txn.header_signature in self._requested:
-                            del self._requested[txn.header_signature]
-                        self._process_incomplete_batches(txn.header_signature)
-            self._incomplete_batches_length.set_value(
-                len(self._incomplete_batches))
+        batch_bytes = batch.SerializeToString()
+        _libexec(
+            'completer_add_batch',
+            self.pointer,
+            batch_bytes,
+            len(batch_bytes))
 
     def get_chain_head(self):
         """Returns the block which is the current head of the chain.
@@ -360,37 +138,117 @@ class Completer:
         Returns:
             BlockWrapper: The head of the chain.
         """
-        with self.lock:
-            return self._get_chain_head()
+        return self._get_chain_head()
 
     def get_block(self, block_id):
-        with self.lock:
-            try:
-                return next(self._block_manager.get([block_id]))
-            except StopIteration:
-                return None
+        try:
+            return next(self._block_manager.get([block_id]))
+        except StopIteration:
+            return None
 
     def get_batch(self, batch_id):
-        with self.lock:
-            if batch_id in self._batch_cache:
-                return self._batch_cache[batch_id]
+        batch = self._get_received_batch('completer_get_batch', batch_id)
+        if batch is not None:
+            return batch
 
-            try:
-                return self._get_committed_batch_by_id(batch_id)
-            except ValueError:
-                return None
+        try:
+            return self._get_committed_batch_by_id(batch_id)
+        except ValueError:
+            return None
 
     def get_batch_by_transaction(self, transaction_id):
-        with self.lock:
-            if transaction_id in self._seen_txns:
-                batch_id = self._seen_txns[transaction_id]
-                return self.get_batch(batch_id)
-
-            try:
-                return self._get_committed_batch_by_txn_id(
-                    transaction_id)
-            except ValueError:
-                return None
+        batch = self._get_received_batch(
+            'completer_get_batch_by_transaction', transaction_id)
+        if batch is not None:
+            return batch
+
+        try:
+            return self._get_committed_batch_by_txn_id(
+                transaction_id)
+        except ValueError:
+            return None
+
+    def _get_received_batch(self, name, item_id):
+        (vec_ptr, vec_len, vec_cap) = ffi.prepare_vec_result()
+        res = ffi.LIBRARY.call(
+            name,
+            self.pointer,
+            ctypes.c_char_p(item_id.encode()),
+            ctypes.byref(vec_ptr),
+            ctypes.byref(vec_len),
+            ctypes.byref(vec_cap))
+
+        if res == ErrorCode.NotFound:
+            return None
+        _check_error(res)
+
+        return Batch.FromString(ffi.from_rust_vec(vec_ptr, vec_len, vec_cap))
+
+
+class _CompleterService:
+    """The block storage, gossip, and delivery operations called by the Rust
+    completer.
+    """
+
+    def __init__(self, block_manager, transaction_committed, gossip):
+        self._block_manager = block_manager
+        self._transaction_committed = transaction_committed
+        self._gossip = gossip
+        self.on_block_received = None
+        self.on_batch_received = None
+
+    def has_block(self, block_id):
+        return block_id in self._block_manager
+
+    def put_block(self, block_bytes):
+        try:
+            # Create Ref-B
+            self._block_manager.put([Block.FromString(block_bytes)])
+            return True
+        except MissingPredecessor:
+            return False
+
+    def transaction_committed(self, transaction_id):
+        return bool(self._transaction_committed(transaction_id))
+
+    def request_block(self, block_id):
+        self._gossip.broadcast_block_request(block_id)
+
+    def request_batch(self, batch_id):
+        self._gossip.broadcast_batch_by_batch_id_request(batch_id)
+
+    def request_batches_by_transaction_ids(self, transaction_ids):
+        self._gossip.broadcast_batch_by_transaction_id_request(
+            transaction_ids)
+
+    def block_completed(self, block_id):
+        self.on_block_received(block_id)
+
+    def batch_completed(self, batch_bytes):
+        self.on_batch_received(Batch.FromString(batch_bytes))
+
+
+def _libexec(name, *args):
+    _check_error(ffi.LIBRARY.call(name, *args))
+
+
+def _check_error(res):
+    if res == ErrorCode.Success:
+        return
+
+    if res == ErrorCode.NullPointerProvided:
+        raise TypeError("Provided null pointer(s)")
+
+    if res == ErrorCode.InvalidInputString:
+        raise ValueError("Provided id was not a valid string")
+
+    if res == ErrorCode.InvalidArgument:
+        raise ValueError("Unable to parse the block or batch")
+
+    if res == ErrorCode.Error:
+        raise Exception("The completer failed; see the validator log")
+
+    raise Exception("Unknown error code {}".format(res))
 
 
 class CompleterBatchListBroadcastHandler(Handler):
@@ -85,14 +85,13 @@ class BlockPublisher(BlockPublisherInterface):
                 self._state_view_factory)
 
         settings_view = SettingsView(state_view)
-        self._min_wait_time = settings_view.get_setting(
-            "sawtooth.consensus.min_wait_time", self._min_wait_time, int)
-        self._max_wait_time = settings_view.get_setting(
-            "sawtooth.consensus.max_wait_time", self._max_wait_time, int)
-        self._valid_block_publishers = settings_view.get_setting(
+        self._min_wait_time = settings_view.get_setting_u64(
+            "sawtooth.consensus.min_wait_time", self._min_wait_time)
+        self._max_wait_time = settings_view.get_setting_u64(
+            "sawtooth.consensus.max_wait_time", self._max_wait_time)
+        self._valid_block_publishers = settings_view.get_setting_list(
             "sawtooth.consensus.valid_block_publishers",
-            self._valid_block_publishers,
-            list)
+            self._valid_block_publishers)
 
         block_header.consensus = b"Devmode"
         self._start_time = time.time()
@@ -0,0 +1,98 @@
+# Copyright 2018 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#     http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+# ------------------------------------------------------------------------------
+
+from collections import namedtuple
+import threading
+
+from sawtooth_validator.journal.block_manager import UnknownBlock
+from sawtooth_validator.protobuf.block_pb2 import BlockHeader
+
+
+# A chain of blocks branching off the current chain. fork_point_id is the
+# block on the current chain it branches from, and length the number of blocks
+# on the fork.
+Fork = namedtuple(
+    'Fork', ['head_id', 'head_block_num', 'fork_point_id', 'length'])
+
+
+class ForkTracker:
+    """Remembers the received blocks that no other received block builds on,
+    so that the forks held by the block manager can be listed.
+    """
+
+    # Once this many tips are tracked, tips the block manager no longer holds
+    # are forgotten.
+    _MAX_TRACKED_TIPS = 1000
+
+    def __init__(self, block_manager):
+        self._block_manager = block_manager
+        self._lock = threading.Lock()
+        self._tips = set()
+
+    def block_received(self, block_id):
+        """Records a block that has been completed and is about to be
+        queued for validation.
+        """
+        try:
+            block = next(self._block_manager.get([block_id]))
+        except StopIteration:
+            return
+
+        header = BlockHeader()
+        header.ParseFromString(block.header)
+
+        with self._lock:
+            self._tips.discard(header.previous_block_id)
+            self._tips.add(block_id)
+
+            if len(self._tips) > self._MAX_TRACKED_TIPS:
+                self._tips = {
+                    tip for tip in self._tips if tip in self._block_manager
+                }
+
+    def forks(self, chain_head_id):
+        """Returns a Fork for each tracked tip that is not on the chain ending
+        at chain_head_id, longest first.
+        """
+        with self._lock:
+            tips = list(self._tips)
+
+        forks = []
+        for tip in tips:
+            try:
+                blocks = list(
+                    self._block_manager.branch_diff(tip, chain_head_id))
+            except UnknownBlock:
+                # The block manager has dropped the tip
+                with self._lock:
+                    self._tips.discard(tip)
+                continue
+
+            if not blocks:
+                # The tip is on the current chain. It is kept, since the
+                # chain may yet move to another fork.
+                continue
+
+            head = BlockHeader()
+            head.ParseFromString(blocks[0].header)
+            first = BlockHeader()
+            first.ParseFromString(blocks[-1].header)
+            forks.append(Fork(
+                head_id=tip,
+                head_block_num=head.block_num,
+                fork_point_id=first.previous_block_id,
+                length=len(blocks)))
+
+        return sorted(forks, key=lambda fork: (-fork.length, fork.head_id))
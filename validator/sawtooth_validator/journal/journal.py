@@ -23,6 +23,9 @@ from sawtooth_validator.ffi import OwnedPointer
 from sawtooth_validator.protobuf.block_pb2 import Block
 from sawtooth_validator.journal.block_wrapper import BlockStatus
 
+# The longest timeout, in seconds, that publishing may be paused with
+MAX_PAUSE_TIMEOUT = 7 * 24 * 60 * 60
+
 
 class Journal(OwnedPointer):
     def __init__(
@@ -36,6 +39,7 @@ class Journal(OwnedPointer):
         consensus_registry,
         state_pruning_block_depth=1000,
         fork_cache_keep_time=300,  # seconds
+        merkle_node_cache_size=10000,
         data_dir=None,
         batch_observers=None,
         invalid_transaction_observers=None,
@@ -70,6 +74,7 @@ class Journal(OwnedPointer):
             ctypes.py_object(observers),
             ctypes.c_long(state_pruning_block_depth),
             ctypes.c_long(fork_cache_keep_time),
+            ctypes.c_size_t(merkle_node_cache_size),
             ctypes.c_char_p(data_dir.encode()),
             ctypes.c_char_p(key_dir.encode()),
             ctypes.py_object(genesis_observers),
@@ -142,6 +147,32 @@ class Journal(OwnedPointer):
     def cancel_block(self):
         _libexec("block_publisher_cancel_block", self.pointer)
 
+    def pause_publishing(self, timeout=0):
+        """Stops blocks from being initialized or finalized until
+        resume_publishing is called, or for at most timeout seconds. A
+        timeout of 0 uses the default of one hour.
+
+        Raises:
+            ValueError: if the timeout is longer than MAX_PAUSE_TIMEOUT.
+        """
+        _libexec(
+            'block_publisher_pause_publishing',
+            self.pointer,
+            ctypes.c_uint64(timeout))
+
+    def resume_publishing(self):
+        _libexec('block_publisher_resume_publishing', self.pointer)
+
+    def is_publishing_paused(self):
+        paused = ctypes.c_bool(False)
+
+        _libexec(
+            'block_publisher_is_publishing_paused',
+            self.pointer,
+            ctypes.byref(paused))
+
+        return paused.value
+
     def validate_block(self, block):
         self._journal_serialize_ffi_fn(
             'chain_controller_validate_block',
@@ -248,6 +279,10 @@ def _exec(library, name, *args):
         raise BlockEmpty()
     if res == ErrorCode.VerifyStateError:
         raise VerifyStateError()
+    if res == ErrorCode.PublishingPaused:
+        raise PublishingPaused()
+    if res == ErrorCode.InvalidPauseTimeout:
+        raise ValueError("Pause timeout is out of range")
 
     raise TypeError("Unknown error occurred")
 
@@ -269,6 +304,8 @@ class ErrorCode(IntEnum):
     BlockNotInitialized = 0x13
     BlockEmpty = 0x14
     VerifyStateError = 0x15
+    PublishingPaused = 0x16
+    InvalidPauseTimeout = 0x17
 
 
 class GenesisError(Exception):
@@ -312,3 +349,7 @@ class BlockEmpty(Exception):
 
 class VerifyStateError(Exception):
     """Unable to verify state during journal creation"""
+
+
+class PublishingPaused(Exception):
+    """Block publishing is paused."""
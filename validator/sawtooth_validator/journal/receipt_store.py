@@ -12,6 +12,10 @@
 # See the License for the specific language governing permissions and
 # limitations under the License.
 # ------------------------------------------------------------------------------
+from collections import OrderedDict
+import logging
+from threading import Lock
+
 from sawtooth_validator.protobuf.transaction_receipt_pb2 import \
     TransactionReceipt
 from sawtooth_validator.protobuf.client_receipt_pb2 import \
@@ -25,22 +29,66 @@ from sawtooth_validator.networking.dispatch import HandlerStatus
 from sawtooth_validator.protobuf import validator_pb2
 
 from sawtooth_validator.journal.chain import ChainObserver
+from sawtooth_validator.concurrent.thread import InstrumentedThread
+
+LOGGER = logging.getLogger(__name__)
+
+
+# Index entries are stored alongside receipts, under keys which sort after
+# every transaction id
+_BLOCK_INDEX_PREFIX = '~block/'
+_ADDRESS_INDEX_PREFIX = '~address/'
+_HEIGHT_INDEX_PREFIX = '~height/'
+
+DEFAULT_PAGE_SIZE = 100
+
+# The number of blocks committed between automatic prunes
+PRUNE_FREQUENCY = 100
 
 
 class TransactionReceiptStore(ChainObserver):
     """A TransactionReceiptStore persists TransactionReceipt records to a
     provided database implementation.
+
+    Receipts of committed blocks are also indexed by block id and by the
+    addresses of their state changes, for `list_by_block` and
+    `list_by_address_prefix`, and by block height, for pruning.
+
+    When a committed block does not build on the previously committed block,
+    the chain has switched forks, and the receipts of any other blocks at or
+    above its height are deleted, as those blocks belong to the abandoned
+    fork.
     """
 
-    def __init__(self, receipt_db):
+    def __init__(self, receipt_db, retention_blocks=0, retention_count=0):
         """Constructs a TransactionReceiptStore, backed by a given database
         implementation.
 
+        Receipts are pruned a block at a time. A block's receipts are kept
+        if the block is one of the `retention_blocks` most recent by height,
+        or if fewer than `retention_count` receipts of more recent blocks are
+        kept. Once retention is configured, receipts are pruned in the
+        background every PRUNE_FREQUENCY blocks.
+
         Args:
             receipt_db (:obj:sawtooth_validator.database.database.Database): A
                 database implementation that backs this store.
+            retention_blocks (int): The number of most recent blocks whose
+                receipts are kept, or 0 to not keep receipts by height.
+            retention_count (int): The number of most recent receipts that
+                are kept, or 0 to not keep receipts by count. If both this
+                and `retention_blocks` are 0, receipts are never pruned.
         """
         self._receipt_db = receipt_db
+        self._retention_blocks = retention_blocks
+        self._retention_count = retention_count
+
+        self._prune_lock = Lock()
+        self._blocks_since_prune = 0
+        self._prune_thread = None
+
+        # The last block committed, unknown until the first chain update
+        self._chain_head_id = None
 
     def put(self, txn_id, txn_receipt):
         """Add the given transaction receipt to the store. Does not guarantee
@@ -73,9 +121,212 @@ class TransactionReceiptStore(ChainObserver):
         txn_receipt.ParseFromString(txn_receipt_bytes)
         return txn_receipt
 
+    def list_by_block(self, block_id, start=None, limit=DEFAULT_PAGE_SIZE):
+        """Returns a page of the receipts of a committed block, in the order
+        of the block's transactions.
+
+        Args:
+            block_id (str): the id of the block.
+            start (str): the paging token returned with the previous page,
+                or None for the first page.
+            limit (int): the maximum number of receipts to return.
+
+        Returns:
+            (list of TransactionReceipt, str): The receipts, and the paging
+                token for the next page, or None if this is the last page.
+        """
+        return self._list_index(
+            _BLOCK_INDEX_PREFIX + block_id + '/', start, limit)
+
+    def list_by_address_prefix(self, address_prefix, start=None,
+                               limit=DEFAULT_PAGE_SIZE):
+        """Returns a page of the receipts of committed transactions with a
+        state change at an address starting with the given prefix, ordered by
+        address.
+
+        A receipt with state changes at several matching addresses is listed
+        once for each address, though at most once per page.
+
+        Args:
+            address_prefix (str): the address prefix.
+            start (str): the paging token returned with the previous page,
+                or None for the first page.
+            limit (int): the maximum number of receipts to return.
+
+        Returns:
+            (list of TransactionReceipt, str): The receipts, and the paging
+                token for the next page, or None if this is the last page.
+        """
+        return self._list_index(
+            _ADDRESS_INDEX_PREFIX + address_prefix, start, limit)
+
     def chain_update(self, block, receipts):
-        for receipt in receipts:
-            self.put(receipt.transaction_id, receipt)
+        if block.previous_block_id != self._chain_head_id:
+            self._prune_abandoned_blocks(
+                block.block_num, block.header_signature)
+
+        puts = []
+        for position, receipt in enumerate(receipts):
+            txn_id = receipt.transaction_id
+            puts.append((txn_id, receipt.SerializeToString()))
+
+            index_keys = ['{}{}/{:08}'.format(
+                _BLOCK_INDEX_PREFIX, block.header_signature, position)]
+            index_keys.extend(
+                '{}{}/{}'.format(_ADDRESS_INDEX_PREFIX, address, txn_id)
+                for address in {change.address
+                                for change in receipt.state_changes})
+            # Index entries hold their own key, so a page can be read with
+            # a cursor's value iterator alone
+            puts.extend(
+                (index_key, [index_key, txn_id]) for index_key in index_keys)
+
+        height_key = '{}{:020}/{}'.format(
+            _HEIGHT_INDEX_PREFIX, block.block_num, block.header_signature)
+        puts.append((
+            height_key,
+            [height_key, block.header_signature, block.block_num,
+             len(receipts)]))
+
+        self._receipt_db.put_multi(puts)
+        self._chain_head_id = block.header_signature
+
+        if self._retention_blocks or self._retention_count:
+            self._blocks_since_prune += 1
+            if self._blocks_since_prune >= PRUNE_FREQUENCY:
+                self._start_prune()
+
+    def flush(self):
+        """Waits for every receipt put in the store to be written to the
+        backing store.
+        """
+        self._receipt_db.flush()
+
+    def prune(self):
+        """Deletes the receipts, and their index entries, of the blocks
+        outside of the retention policy.
+
+        Returns:
+            int: The number of receipts deleted.
+        """
+        if not (self._retention_blocks or self._retention_count):
+            return 0
+
+        with self._prune_lock:
+            with self._receipt_db.cursor() as cursor:
+                cursor.seek(_HEIGHT_INDEX_PREFIX)
+                heights = []
+                for entry in cursor.iter():
+                    if not entry[0].startswith(_HEIGHT_INDEX_PREFIX):
+                        break
+                    heights.append(entry)
+
+            if not heights:
+                return 0
+
+            newest_block_num = heights[-1][2]
+            kept_count = 0
+            pruned_count = 0
+            for height_key, block_id, block_num, receipt_count in \
+                    reversed(heights):
+                keep = (
+                    self._retention_blocks and
+                    block_num > newest_block_num - self._retention_blocks
+                ) or (
+                    self._retention_count and
+                    kept_count < self._retention_count
+                )
+
+                if keep:
+                    kept_count += receipt_count
+                else:
+                    self._prune_block(height_key, block_id)
+                    pruned_count += receipt_count
+
+        if pruned_count:
+            LOGGER.debug('Pruned %s transaction receipts', pruned_count)
+
+        return pruned_count
+
+    def _start_prune(self):
+        if self._prune_thread is not None and self._prune_thread.is_alive():
+            return
+
+        self._blocks_since_prune = 0
+        self._prune_thread = InstrumentedThread(
+            target=self.prune, name='ReceiptPruneThread', daemon=True)
+        self._prune_thread.start()
+
+    def _prune_abandoned_blocks(self, block_num, block_id):
+        height_prefix = '{}{:020}'.format(_HEIGHT_INDEX_PREFIX, block_num)
+
+        abandoned = []
+        with self._receipt_db.cursor() as cursor:
+            cursor.seek(height_prefix)
+            for height_key, abandoned_id, *_ in cursor.iter():
+                if not height_key.startswith(_HEIGHT_INDEX_PREFIX):
+                    break
+                if abandoned_id != block_id:
+                    abandoned.append((height_key, abandoned_id))
+
+        if not abandoned:
+            return
+
+        with self._prune_lock:
+            for height_key, abandoned_id in abandoned:
+                self._prune_block(height_key, abandoned_id)
+
+        LOGGER.debug(
+            'Deleted the receipts of %s blocks abandoned for block %s',
+            len(abandoned), block_id)
+
+    def _prune_block(self, height_key, block_id):
+        block_prefix = _BLOCK_INDEX_PREFIX + block_id + '/'
+
+        deletes = [height_key]
+        txn_ids = []
+        with self._receipt_db.cursor() as cursor:
+            cursor.seek(block_prefix)
+            for index_key, txn_id, *_ in cursor.iter():
+                if not index_key.startswith(block_prefix):
+                    break
+                deletes.append(index_key)
+                txn_ids.append(txn_id)
+
+        for txn_id in txn_ids:
+            try:
+                receipt = self.get(txn_id)
+            except KeyError:
+                continue
+            deletes.append(txn_id)
+            deletes.extend(
+                '{}{}/{}'.format(_ADDRESS_INDEX_PREFIX, address, txn_id)
+                for address in {change.address
+                                for change in receipt.state_changes})
+
+        self._receipt_db.update([], deletes)
+
+    def _list_index(self, key_prefix, start, limit):
+        if start is not None and not start.startswith(key_prefix):
+            raise ValueError('Invalid paging token {}'.format(start))
+
+        txn_ids = []
+        next_start = None
+        with self._receipt_db.cursor() as cursor:
+            cursor.seek(start or key_prefix)
+            for index_key, txn_id, *_ in cursor.iter():
+                if not index_key.startswith(key_prefix):
+                    break
+                if len(txn_ids) >= limit:
+                    next_start = index_key
+                    break
+                txn_ids.append(txn_id)
+
+        receipts = []
+        for txn_id in OrderedDict.fromkeys(txn_ids):
+            receipts.append(self.get(txn_id))
+
+        return receipts, next_start
 
 
 class ClientReceiptGetRequestHandler(Handler):
@@ -0,0 +1,96 @@
+# Copyright 2018 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#     http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+# ------------------------------------------------------------------------------
+
+import hashlib
+import threading
+
+from sawtooth_validator.journal.chain import ChainObserver
+
+
+class BloomFilter:
+    """A fixed size set of strings that may report false positives, but
+    never false negatives.
+    """
+
+    def __init__(self, size_bits, hash_count):
+        self._size_bits = size_bits
+        self._hash_count = hash_count
+        self._bits = bytearray((size_bits + 7) // 8)
+
+    def add(self, item):
+        for index in self._indexes(item):
+            self._bits[index // 8] |= 1 << (index % 8)
+
+    def __contains__(self, item):
+        return all(
+            self._bits[index // 8] & (1 << (index % 8))
+            for index in self._indexes(item))
+
+    def _indexes(self, item):
+        digest = hashlib.sha512(item.encode()).digest()
+        for i in range(self._hash_count):
+            chunk = digest[i * 4:(i + 1) * 4]
+            yield int.from_bytes(chunk, 'big') % self._size_bits
+
+
+class RecentBatchFilter(ChainObserver):
+    """Tracks the ids of batches committed in recent blocks.
+
+    Batch ids are added to a bloom filter that is replaced after every
+    `block_window` blocks; the previous filter is kept, so a batch is
+    remembered for at least `block_window` blocks. Since a bloom filter may
+    report a batch that was never committed, a match is confirmed with
+    `has_batch` before the batch is treated as committed.
+    """
+
+    def __init__(self, has_batch, block_window=100, size_bits=2 ** 20,
+                 hash_count=7):
+        """
+        Args:
+            has_batch (fn(batch_id) -> bool): Returns whether the batch is
+                committed.
+            block_window (int): The number of blocks covered by each filter.
+            size_bits (int): The size of each filter in bits.
+            hash_count (int): The number of bits set for each batch id; at
+                most 16.
+        """
+        self._has_batch = has_batch
+        self._block_window = block_window
+        self._size_bits = size_bits
+        self._hash_count = hash_count
+
+        self._lock = threading.Lock()
+        self._current = BloomFilter(size_bits, hash_count)
+        self._previous = BloomFilter(size_bits, hash_count)
+        self._blocks_in_current = 0
+
+    def chain_update(self, block, receipts):
+        with self._lock:
+            if self._blocks_in_current >= self._block_window:
+                self._previous = self._current
+                self._current = BloomFilter(
+                    self._size_bits, self._hash_count)
+                self._blocks_in_current = 0
+
+            for batch in block.batches:
+                self._current.add(batch.header_signature)
+            self._blocks_in_current += 1
+
+    def is_recently_committed(self, batch_id):
+        with self._lock:
+            maybe_committed = \
+                batch_id in self._current or batch_id in self._previous
+
+        return maybe_committed and self._has_batch(batch_id)
@@ -15,6 +15,7 @@
 
 from sawtooth_validator.metrics.metrics import init_metrics
 from sawtooth_validator.metrics.metrics import get_collector
+from sawtooth_validator.metrics.metrics import snapshot
 
 from sawtooth_validator.metrics.metrics import DEBUG
 from sawtooth_validator.metrics.metrics import INFO
@@ -98,6 +98,14 @@ def get_collector(module_name=None):
     return MetricsCollectorHandle(module_name)
 
 
+def snapshot():
+    """
+    Get the current values of all reported metrics, as a dict of metric names
+    to dicts of field values, or None if metrics reporting is not enabled.
+    """
+    return MetricsCollector.get_instance().snapshot()
+
+
 class MetricsCollector:
     __instance = None
 
@@ -144,6 +152,12 @@ class MetricsCollector:
         return self._registry.timer(
             self._join(identifier, instance, tags))
 
+    def snapshot(self):
+        if self._registry is None:
+            return None
+
+        return self._registry.dump_metrics()
+
     # Private methods
     def _disabled(self, identifier, level):
         """Check if the metric is enabled based on the level."""
@@ -116,6 +116,23 @@ class ConnectHandler(Handler):
                 message_type=validator_pb2.Message.
                 AUTHORIZATION_CONNECTION_RESPONSE)
 
+        if not self._network.is_chain_id_accepted(message.chain_id):
+            LOGGER.warning("Connecting peer %s has chain id %s, which differs "
+                           "from this validator's chain id %s; Ignoring "
+                           "connection request. Set "
+                           "network.accept_any_chain_id to allow it.",
+                           message.endpoint,
+                           message.chain_id,
+                           self._network.chain_id)
+            connection_response = ConnectionResponse(
+                status=ConnectionResponse.CHAIN_ID_MISMATCH,
+                chain_id=self._network.chain_id)
+            return HandlerResult(
+                HandlerStatus.RETURN_AND_CLOSE,
+                message_out=connection_response,
+                message_type=validator_pb2.Message.
+                AUTHORIZATION_CONNECTION_RESPONSE)
+
         LOGGER.debug("Endpoint of connecting node is %s", message.endpoint)
         self._network.update_connection_endpoint(connection_id,
                                                  message.endpoint)
@@ -144,6 +161,8 @@ class ConnectHandler(Handler):
                 message_type=validator_pb2.Message.
                 AUTHORIZATION_CONNECTION_RESPONSE)
 
+        connection_response.chain_id = self._network.chain_id
+
         try:
             is_outbound_connection = self._network.is_outbound_connection(
                 connection_id)
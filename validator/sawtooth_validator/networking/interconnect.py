@@ -731,7 +731,9 @@ class Interconnect:
                  max_future_callback_workers=10,
                  roles=None,
                  authorize=False,
-                 signer=None):
+                 signer=None,
+                 chain_id_manager=None,
+                 accept_any_chain_id=True):
         """
         Constructor for Interconnect.
 
@@ -749,6 +751,10 @@ class Interconnect:
             max_future_callback_workers (int): max number of workers for future
                 callbacks, defaults to 10
             signer (:obj:`Signer`): cryptographic signer for the validator
+            chain_id_manager (:obj:`ChainIdManager`): provides the chain id
+                exchanged with peers when connecting
+            accept_any_chain_id (bool): whether to connect to peers whose
+                chain id differs from this validator's
         """
         self._endpoint = endpoint
         self._public_endpoint = public_endpoint
@@ -782,6 +788,8 @@ class Interconnect:
 
         self._authorize = authorize
         self._signer = signer
+        self._chain_id_manager = chain_id_manager
+        self._accept_any_chain_id = accept_any_chain_id
 
         self._send_receive_thread = _SendReceive(
             "ServerThread",
@@ -808,6 +816,25 @@ class Interconnect:
     def endpoint(self):
         return self._endpoint
 
+    @property
+    def chain_id(self):
+        """The chain id of this validator, or the empty string if it is not
+        known yet.
+        """
+        if self._chain_id_manager is None:
+            return ''
+        return self._chain_id_manager.get_block_chain_id() or ''
+
+    def is_chain_id_accepted(self, chain_id):
+        """Returns whether a peer with the given chain id may be connected
+        to. Peers are only refused when both chain ids are known and differ,
+        so that a validator without a genesis block can still join.
+        """
+        if self._accept_any_chain_id:
+            return True
+        own_chain_id = self.chain_id
+        return not chain_id or not own_chain_id or chain_id == own_chain_id
+
     def _get_send_response_timer(self, tag):
         if tag not in self._send_response_timers:
             self._send_response_timers[tag] = COLLECTOR.timer(
@@ -913,7 +940,8 @@ class Interconnect:
 
         self._add_connection(conn, uri)
 
-        connect_message = ConnectionRequest(endpoint=self._public_endpoint)
+        connect_message = ConnectionRequest(
+            endpoint=self._public_endpoint, chain_id=self.chain_id)
         conn.send(
             validator_pb2.Message.NETWORK_CONNECT,
             connect_message.SerializeToString(),
@@ -929,7 +957,8 @@ class Interconnect:
         Send ConnectionRequest to an inbound connection. This allows
         the validator to be authorized by the incoming connection.
         """
-        connect_message = ConnectionRequest(endpoint=self._public_endpoint)
+        connect_message = ConnectionRequest(
+            endpoint=self._public_endpoint, chain_id=self.chain_id)
         self._safe_send(
             validator_pb2.Message.NETWORK_CONNECT,
             connect_message.SerializeToString(),
@@ -942,7 +971,10 @@ class Interconnect:
         connection_response = ConnectionResponse()
         connection_response.ParseFromString(result.content)
 
-        if connection_response.status == connection_response.ERROR:
+        if not self._check_peer_chain_id(connection_response,
+                                         connection.connection_id):
+            self.remove_connection(connection.connection_id)
+        elif connection_response.status == connection_response.ERROR:
             LOGGER.debug("Received an error response to the NETWORK_CONNECT "
                          "we sent. Removing connection: %s",
                          connection.connection_id)
@@ -985,6 +1017,10 @@ class Interconnect:
                                              connection_id=None):
         connection_response = ConnectionResponse()
         connection_response.ParseFromString(result.content)
+        if not self._check_peer_chain_id(connection_response, connection_id):
+            self.remove_connection(connection_id)
+            return
+
         if connection_response.status == connection_response.ERROR:
             LOGGER.debug("Received an error response to the NETWORK_CONNECT "
                          "we sent. Removing connection: %s",
@@ -1022,6 +1058,32 @@ class Interconnect:
                     connection_id=connection_id)
             )
 
+    def _check_peer_chain_id(self, connection_response, connection_id):
+        """Returns whether the peer that sent connection_response is on the
+        same chain as this validator, logging why it is not otherwise.
+        """
+        if connection_response.status == \
+                connection_response.CHAIN_ID_MISMATCH:
+            LOGGER.warning(
+                "Peer %s refused the connection: its chain id %s differs "
+                "from this validator's chain id %s",
+                connection_id,
+                connection_response.chain_id or '(unknown)',
+                self.chain_id or '(unknown)')
+            return False
+
+        if not self.is_chain_id_accepted(connection_response.chain_id):
+            LOGGER.warning(
+                "Refusing to connect to peer %s: its chain id %s differs "
+                "from this validator's chain id %s; set "
+                "network.accept_any_chain_id to connect anyway",
+                connection_id,
+                connection_response.chain_id,
+                self.chain_id)
+            return False
+
+        return True
+
     def _challenge_authorization_callback(self, request, result,
                                           connection=None,
                                           ):
@@ -13,6 +13,7 @@
 # limitations under the License.
 # ------------------------------------------------------------------------------
 
+import ctypes
 import logging
 import sys
 import os
@@ -24,9 +25,7 @@ from pyformance.reporters import InfluxReporter
 import netifaces
 
 from sawtooth_validator.config.path import load_path_config
-from sawtooth_validator.config.validator import load_default_validator_config
-from sawtooth_validator.config.validator import load_toml_validator_config
-from sawtooth_validator.config.validator import merge_validator_config
+from sawtooth_validator.config.validator import parse_permissions
 from sawtooth_validator.config.validator import ValidatorConfig
 from sawtooth_validator.config.logs import get_log_config
 from sawtooth_validator.server.core import Validator
@@ -35,6 +34,7 @@ from sawtooth_validator.server.log import init_console_logging
 from sawtooth_validator.server.log import log_configuration
 from sawtooth_validator.exceptions import LocalConfigurationError
 from sawtooth_validator.journal.journal import GenesisError
+from sawtooth_validator import ffi
 from sawtooth_validator import metrics
 
 
@@ -42,6 +42,27 @@ LOGGER = logging.getLogger(__name__)
 DISTRIBUTION_NAME = 'sawtooth-validator'
 
 
+def _c_string(value):
+    return ctypes.c_char_p(value.encode()) if value is not None else None
+
+
+def init_rust_metrics(validator_config):
+    """Reports the metrics recorded in Rust straight to the InfluxDB or
+    Telegraf at opentsdb_url, rather than through the Python registry.
+    """
+    result = ffi.LIBRARY.call(
+        "influx_metrics_init",
+        _c_string(validator_config.opentsdb_url),
+        _c_string(validator_config.opentsdb_db or ""),
+        _c_string(validator_config.opentsdb_username),
+        _c_string(validator_config.opentsdb_password))
+    if result != 0:
+        LOGGER.error("Unable to report Rust metrics to %s; reporting them "
+                     "with the Python metrics instead",
+                     validator_config.opentsdb_url)
+        ffi.LIBRARY.call("pymetrics_init")
+
+
 def check_directory(path, human_readable_name):
     """Verify that the directory exists and is readable and writable.
 
@@ -79,14 +100,17 @@ def check_directory(path, human_readable_name):
     return errors
 
 
-def load_validator_config(first_config, config_dir):
-    default_validator_config = load_default_validator_config()
-    conf_file = os.path.join(config_dir, 'validator.toml')
-
-    toml_config = load_toml_validator_config(conf_file)
+def resolved_validator_config(config):
+    """Returns a ValidatorConfig from the keyword arguments resolved by the
+    native CLI, reading the policy files named in its permissions.
+    """
+    config = dict(config)
+    config['permissions'] = parse_permissions(config['permissions'])
+    for key in ('network_public_key', 'network_private_key'):
+        if config[key] is not None:
+            config[key] = config[key].encode()
 
-    return merge_validator_config(
-        configs=[first_config, toml_config, default_validator_config])
+    return ValidatorConfig(**config)
 
 
 def main(args):
@@ -96,27 +120,11 @@ def main(args):
         LOGGER.error(str(local_config_err))
         sys.exit(1)
 
+    # The validator configuration has been loaded from validator.toml,
+    # merged with the command line arguments and the defaults, and
+    # validated by the native CLI.
     try:
-        opts_config = ValidatorConfig(
-            bind_component=args['bind_component'],
-            bind_network=args['bind_network'],
-            bind_consensus=args['bind_consensus'],
-            endpoint=args['endpoint'],
-            maximum_peer_connectivity=args['maximum_peer_connectivity'],
-            minimum_peer_connectivity=args['minimum_peer_connectivity'],
-            roles=args['roles'],
-            opentsdb_db=args['opentsdb_db'],
-            opentsdb_url=args['opentsdb_url'],
-            peering=args['peering'],
-            peers=args['peers'],
-            scheduler=args['scheduler'],
-            seeds=args['seeds'],
-            state_pruning_block_depth=args['state_pruning_block_depth'],
-            fork_cache_keep_time=args['fork_cache_keep_time'],
-        )
-
-        validator_config = \
-            load_validator_config(opts_config, path_config.config_dir)
+        validator_config = resolved_validator_config(args['validator_config'])
     except LocalConfigurationError as local_config_err:
         LOGGER.error(str(local_config_err))
         sys.exit(1)
@@ -219,20 +227,28 @@ def main(args):
         url = urlparse(validator_config.opentsdb_url)
         proto, db_server, db_port, = url.scheme, url.hostname, url.port
 
-        registry = MetricsRegistry()
-        metrics.init_metrics(registry=registry)
-
-        metrics_reporter = InfluxReporter(
-            registry=registry,
-            reporting_interval=10,
-            database=validator_config.opentsdb_db,
-            prefix="sawtooth_validator",
-            port=db_port,
-            protocol=proto,
-            server=db_server,
-            username=validator_config.opentsdb_username,
-            password=validator_config.opentsdb_password)
-        metrics_reporter.start()
+        if proto in ('http', 'https'):
+            registry = MetricsRegistry()
+            metrics.init_metrics(registry=registry)
+
+            metrics_reporter = InfluxReporter(
+                registry=registry,
+                reporting_interval=10,
+                database=validator_config.opentsdb_db,
+                prefix="sawtooth_validator",
+                port=db_port,
+                protocol=proto,
+                server=db_server,
+                username=validator_config.opentsdb_username,
+                password=validator_config.opentsdb_password)
+            metrics_reporter.start()
+        else:
+            LOGGER.warning(
+                "Only metrics recorded in Rust are reported over %s; the "
+                "rest are reported over http or https", proto)
+            metrics.init_metrics()
+
+        init_rust_metrics(validator_config)
     else:
         metrics.init_metrics()
 
@@ -243,6 +259,12 @@ def main(args):
     component_workers = validator_config.component_thread_pool_workers
     network_workers = validator_config.network_thread_pool_workers
     sig_workers = validator_config.signature_thread_pool_workers
+    merkle_node_cache_size = validator_config.merkle_node_cache_size
+    batch_rate_limit = validator_config.batch_rate_limit
+    batch_rate_burst = validator_config.batch_rate_burst
+    receipt_retention_blocks = validator_config.receipt_retention_blocks
+    receipt_retention_count = validator_config.receipt_retention_count
+    receipt_sync_mode = validator_config.receipt_sync_mode
     validator = Validator(
         bind_network,
         bind_component,
@@ -266,7 +288,15 @@ def main(args):
         roles=validator_config.roles,
         component_thread_pool_workers=component_workers,
         network_thread_pool_workers=network_workers,
-        signature_thread_pool_workers=sig_workers)
+        signature_thread_pool_workers=sig_workers,
+        merkle_node_cache_size=merkle_node_cache_size,
+        batch_rate_limit=batch_rate_limit,
+        batch_rate_burst=batch_rate_burst,
+        receipt_retention_blocks=receipt_retention_blocks,
+        receipt_retention_count=receipt_retention_count,
+        receipt_sync_mode=receipt_sync_mode,
+        consensus_max_missed_pings=validator_config.consensus_max_missed_pings,
+        accept_any_chain_id=validator_config.accept_any_chain_id)
 
     # pylint: disable=broad-except
     try:
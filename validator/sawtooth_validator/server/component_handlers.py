@@ -20,6 +20,10 @@ from sawtooth_validator.journal.completer import \
     CompleterBatchListBroadcastHandler
 from sawtooth_validator.journal.back_pressure_handlers import \
     ClientBatchSubmitBackpressureHandler
+from sawtooth_validator.journal.back_pressure_handlers import \
+    ClientBatchSubmitDuplicateHandler
+from sawtooth_validator.journal.back_pressure_handlers import \
+    ClientBatchSubmitRateLimitHandler
 
 from sawtooth_validator.gossip import structure_verifier
 
@@ -63,6 +67,11 @@ def add(
         sig_pool,
         journal,
         public_key,
+        recent_batch_filter,
+        consensus_registry,
+        fork_tracker,
+        batch_rate_limit=0,
+        batch_rate_burst=50,
 ):
 
     # -- Client -- #
@@ -86,11 +95,27 @@ def add(
             journal.is_batch_pool_full),
         client_thread_pool)
 
+    dispatcher.add_handler(
+        validator_pb2.Message.CLIENT_BATCH_SUBMIT_REQUEST,
+        ClientBatchSubmitDuplicateHandler(recent_batch_filter),
+        client_thread_pool)
+
     dispatcher.add_handler(
         validator_pb2.Message.CLIENT_BATCH_SUBMIT_REQUEST,
         signature_verifier.BatchListSignatureVerifier(),
         sig_pool)
 
+    # Signers are only rate limited once their signatures are verified, so a
+    # client cannot use up another signer's allowance.
+    if batch_rate_limit > 0:
+        dispatcher.add_handler(
+            validator_pb2.Message.CLIENT_BATCH_SUBMIT_REQUEST,
+            ClientBatchSubmitRateLimitHandler(
+                public_key,
+                batch_rate_limit,
+                batch_rate_burst),
+            client_thread_pool)
+
     dispatcher.add_handler(
         validator_pb2.Message.CLIENT_BATCH_SUBMIT_REQUEST,
         structure_verifier.BatchListStructureVerifier(),
@@ -127,6 +152,21 @@ def add(
             block_store),
         client_thread_pool)
 
+    # Identity
+    dispatcher.add_handler(
+        validator_pb2.Message.CLIENT_IDENTITY_POLICY_LIST_REQUEST,
+        client_handlers.IdentityPolicyListRequest(
+            merkle_db,
+            block_store),
+        client_thread_pool)
+
+    dispatcher.add_handler(
+        validator_pb2.Message.CLIENT_IDENTITY_EVALUATE_REQUEST,
+        client_handlers.IdentityEvaluateRequest(
+            merkle_db,
+            block_store),
+        client_thread_pool)
+
     # Blocks
     dispatcher.add_handler(
         validator_pb2.Message.CLIENT_BLOCK_LIST_REQUEST,
@@ -211,6 +251,11 @@ def add(
         client_handlers.PeersGetRequest(gossip),
         thread_pool)
 
+    dispatcher.add_handler(
+        validator_pb2.Message.CLIENT_PEER_SCORES_GET_REQUEST,
+        client_handlers.PeerScoresGetRequest(gossip),
+        thread_pool)
+
     # Status
 
     dispatcher.add_handler(
@@ -218,6 +263,41 @@ def add(
         client_handlers.StatusGetRequest(gossip),
         thread_pool)
 
+    # Admin
+    #
+    # Any client of the component endpoint may send these, as the endpoint
+    # does not authenticate its clients; see protos/admin.proto.
+
+    dispatcher.add_handler(
+        validator_pb2.Message.ADMIN_LOG_LEVEL_SET_REQUEST,
+        client_handlers.AdminLogLevelSetRequest(),
+        thread_pool)
+
+    dispatcher.add_handler(
+        validator_pb2.Message.ADMIN_METRICS_GET_REQUEST,
+        client_handlers.AdminMetricsGetRequest(),
+        thread_pool)
+
+    dispatcher.add_handler(
+        validator_pb2.Message.ADMIN_PUBLISHING_PAUSE_REQUEST,
+        client_handlers.AdminPublishingPauseRequest(journal),
+        thread_pool)
+
+    dispatcher.add_handler(
+        validator_pb2.Message.ADMIN_PUBLISHING_RESUME_REQUEST,
+        client_handlers.AdminPublishingResumeRequest(journal),
+        thread_pool)
+
+    dispatcher.add_handler(
+        validator_pb2.Message.ADMIN_CONSENSUS_ENGINES_GET_REQUEST,
+        client_handlers.AdminConsensusEnginesGetRequest(consensus_registry),
+        thread_pool)
+
+    dispatcher.add_handler(
+        validator_pb2.Message.ADMIN_FORKS_GET_REQUEST,
+        client_handlers.AdminForksGetRequest(fork_tracker, journal),
+        thread_pool)
+
     # Ping
 
     dispatcher.add_handler(
@@ -69,6 +69,9 @@ def add(
     handler = handlers.ConsensusChainHeadGetHandler(consensus_proxy)
     dispatcher.add_handler(handler.request_type, handler, thread_pool)
 
+    handler = handlers.ConsensusReplayCommitsHandler(consensus_proxy)
+    dispatcher.add_handler(handler.request_type, handler, thread_pool)
+
     handler = handlers.ConsensusSettingsGetHandler(consensus_proxy)
     dispatcher.add_handler(handler.request_type, handler, thread_pool)
 
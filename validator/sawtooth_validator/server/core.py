@@ -25,6 +25,7 @@ from sawtooth_validator.concurrent.threadpool import \
 from sawtooth_validator.consensus.notifier import ConsensusNotifier
 from sawtooth_validator.consensus.proxy import ConsensusProxy
 from sawtooth_validator.consensus.proxy import ConsensusActivationObserver
+from sawtooth_validator.consensus.registry import ConsensusLivenessMonitor
 from sawtooth_validator.consensus.registry import ConsensusRegistry
 from sawtooth_validator.database.lmdb_nolock_database import LMDBNoLockDatabase
 from sawtooth_validator.database.native_lmdb import NativeLmdbDatabase
@@ -34,8 +35,11 @@ from sawtooth_validator.journal.block_sender import BroadcastBlockSender
 from sawtooth_validator.journal.block_store import BlockStore
 from sawtooth_validator.journal.block_manager import BlockManager
 from sawtooth_validator.journal.completer import Completer
+from sawtooth_validator.journal.chain_id_manager import ChainIdManager
+from sawtooth_validator.journal.fork_tracker import ForkTracker
 from sawtooth_validator.journal.responder import Responder
 from sawtooth_validator.journal.journal import Journal
+from sawtooth_validator.journal.recent_batches import RecentBatchFilter
 from sawtooth_validator.networking.dispatch import Dispatcher
 from sawtooth_validator.state.batch_tracker import BatchTracker
 from sawtooth_validator.state.merkle import MerkleDatabase
@@ -85,7 +89,15 @@ class Validator:
                  roles=None,
                  component_thread_pool_workers=10,
                  network_thread_pool_workers=10,
-                 signature_thread_pool_workers=3):
+                 signature_thread_pool_workers=3,
+                 merkle_node_cache_size=10000,
+                 batch_rate_limit=0,
+                 batch_rate_burst=50,
+                 receipt_retention_blocks=0,
+                 receipt_retention_count=0,
+                 receipt_sync_mode='mapasync',
+                 consensus_max_missed_pings=3,
+                 accept_any_chain_id=False):
         """Constructs a validator instance.
 
         Args:
@@ -117,6 +129,29 @@ class Validator:
                 thread pool; defaults to 10.
             signature_thread_pool_workers (int): number of workers in the
                 signature thread pool; defaults to 3.
+            merkle_node_cache_size (int): number of merkle trie nodes kept in
+                memory by the journal; defaults to 10000. Zero disables the
+                cache.
+            batch_rate_limit (float): number of batches per second each
+                signer may submit through the client API; defaults to 0,
+                which disables rate limiting.
+            batch_rate_burst (int): number of batches each signer may submit
+                at once before batch_rate_limit applies; defaults to 50.
+            receipt_retention_blocks (int): number of most recent blocks
+                whose transaction receipts are kept; defaults to 0, which
+                does not prune receipts by block.
+            receipt_retention_count (int): number of most recent transaction
+                receipts kept; defaults to 0, which does not prune receipts
+                by count.
+            receipt_sync_mode (str): how receipt writes are flushed to disk;
+                one of "sync", "mapasync", or "nosync". Defaults to
+                "mapasync".
+            consensus_max_missed_pings (int): number of pings in a row a
+                consensus engine may leave unanswered before it is reported
+                as unresponsive; defaults to 3.
+            accept_any_chain_id (bool): whether to peer with validators
+                whose chain id differs from this validator's; defaults to
+                False.
         """
         # -- Setup Global State Database and Factory -- #
         global_state_db_filename = os.path.join(
@@ -132,8 +167,12 @@ class Validator:
         receipt_db_filename = os.path.join(
             data_dir, 'txn_receipts-{}.lmdb'.format(bind_network[-2:]))
         LOGGER.debug('txn receipt store file is %s', receipt_db_filename)
-        receipt_db = LMDBNoLockDatabase(receipt_db_filename, 'c')
-        receipt_store = TransactionReceiptStore(receipt_db)
+        receipt_db = LMDBNoLockDatabase(
+            receipt_db_filename, 'c', sync_mode=receipt_sync_mode)
+        receipt_store = TransactionReceiptStore(
+            receipt_db,
+            retention_blocks=receipt_retention_blocks,
+            retention_count=receipt_retention_count)
 
         # -- Setup Block Store -- #
         block_db_filename = os.path.join(
@@ -201,10 +240,13 @@ class Validator:
             max_future_callback_workers=10,
             authorize=True,
             signer=identity_signer,
-            roles=roles)
+            roles=roles,
+            chain_id_manager=ChainIdManager(data_dir),
+            accept_any_chain_id=accept_any_chain_id)
 
         # -- Setup Transaction Execution Platform -- #
         batch_tracker = BatchTracker(block_store.has_batch)
+        recent_batch_filter = RecentBatchFilter(block_store.has_batch)
 
         settings_cache = SettingsCache(
             SettingsViewFactory(state_view_factory),
@@ -226,7 +268,8 @@ class Validator:
             max_incoming_connections=20,
             max_future_callback_workers=10)
 
-        consensus_registry = ConsensusRegistry()
+        consensus_registry = ConsensusRegistry(
+            max_missed_pings=consensus_max_missed_pings)
 
         consensus_notifier = ConsensusNotifier(
             consensus_service,
@@ -301,6 +344,7 @@ class Validator:
             consensus_registry=consensus_registry,
             state_pruning_block_depth=state_pruning_block_depth,
             fork_cache_keep_time=fork_cache_keep_time,
+            merkle_node_cache_size=merkle_node_cache_size,
             data_dir=data_dir,
             batch_observers=[batch_tracker],
             invalid_transaction_observers=[batch_tracker],
@@ -308,6 +352,7 @@ class Validator:
                 event_broadcaster,
                 receipt_store,
                 batch_tracker,
+                recent_batch_filter,
                 identity_observer,
                 settings_observer,
                 consensus_activation_observer
@@ -320,7 +365,13 @@ class Validator:
 
         responder = Responder(completer)
 
-        completer.set_on_block_received(journal.queue_block)
+        fork_tracker = ForkTracker(block_manager)
+
+        def on_block_received(block_id):
+            fork_tracker.block_received(block_id)
+            journal.queue_block(block_id)
+
+        completer.set_on_block_received(on_block_received)
 
         # -- Register Message Handler -- #
         network_handlers.add(
@@ -335,7 +386,9 @@ class Validator:
             global_state_db, self.get_chain_head_state_root_hash,
             receipt_store, event_broadcaster, permission_verifier,
             component_thread_pool, client_thread_pool,
-            sig_pool, journal, identity_signer.get_public_key().as_hex())
+            sig_pool, journal, identity_signer.get_public_key().as_hex(),
+            recent_batch_filter, consensus_registry, fork_tracker,
+            batch_rate_limit, batch_rate_burst)
 
         # -- Store Object References -- #
         self._component_dispatcher = component_dispatcher
@@ -348,6 +401,7 @@ class Validator:
 
         consensus_proxy = ConsensusProxy(
             block_manager=block_manager,
+            block_store=block_store,
             journal=journal,
             gossip=gossip,
             identity_signer=identity_signer,
@@ -369,6 +423,7 @@ class Validator:
         self._consensus_service = consensus_service
         self._consensus_thread_pool = consensus_thread_pool
         self._consensus_registry = consensus_registry
+        self._consensus_liveness_monitor = None
 
         self._client_thread_pool = client_thread_pool
         self._sig_pool = sig_pool
@@ -376,6 +431,7 @@ class Validator:
         self._gossip = gossip
 
         self._journal = journal
+        self._receipt_store = receipt_store
 
     def start(self):
         self._component_dispatcher.start()
@@ -385,6 +441,8 @@ class Validator:
 
         self._consensus_dispatcher.start()
         self._consensus_service.start()
+        self._consensus_liveness_monitor = ConsensusLivenessMonitor(
+            self._consensus_service, self._consensus_registry)
         self._network_dispatcher.start()
         self._network_service.start()
 
@@ -408,6 +466,8 @@ class Validator:
 
         self._component_service.stop()
 
+        if self._consensus_liveness_monitor is not None:
+            self._consensus_liveness_monitor.drop()
         self._consensus_service.stop()
         self._consensus_dispatcher.stop()
 
@@ -417,6 +477,7 @@ class Validator:
         self._sig_pool.shutdown(wait=True)
 
         self._journal.stop()
+        self._receipt_store.flush()
 
         threads = threading.enumerate()
 
@@ -13,6 +13,7 @@
 # limitations under the License.
 # ------------------------------------------------------------------------------
 
+from functools import partial
 import logging
 from threading import Condition
 
@@ -20,14 +21,23 @@ from sawtooth_validator.protobuf.events_pb2 import EventList
 from sawtooth_validator.protobuf import validator_pb2
 
 from sawtooth_validator.journal.chain import ChainObserver
-from sawtooth_validator.journal.event_extractors \
-    import BlockEventExtractor
-from sawtooth_validator.journal.event_extractors \
-    import ReceiptEventExtractor
 from sawtooth_validator.journal.block_wrapper import NULL_BLOCK_IDENTIFIER
+from sawtooth_validator.server.events.flow_control import FlowControl
+from sawtooth_validator.server.events.flow_control \
+    import UnknownSubscriberError
+from sawtooth_validator.server.events.native_extractor import extract_events
 
 LOGGER = logging.getLogger(__name__)
 
+# The most event messages sent to a subscriber that acknowledges events that
+# it has not yet acknowledged
+MAX_OUTSTANDING_EVENTS = 10
+# The most blocks whose events are sent in one catch-up message
+CATCHUP_CHUNK_SIZE = 10
+# The seconds after which a subscriber that has not acknowledged an event
+# message is disconnected
+EVENTS_ACK_TIMEOUT = 30
+
 
 class NoKnownBlockError(Exception):
     pass
@@ -40,10 +50,14 @@ class EventBroadcaster(ChainObserver):
         self._service = service
         self._block_store = block_store
         self._receipt_store = receipt_store
+        self._flow_control = FlowControl(
+            MAX_OUTSTANDING_EVENTS, CATCHUP_CHUNK_SIZE, EVENTS_ACK_TIMEOUT)
 
     def add_subscriber(self, connection_id, subscriptions,
-                       last_known_block_id):
-        """Register the subscriber for the given event subscriptions.
+                       last_known_block_id, acknowledged=False):
+        """Register the subscriber for the given event subscriptions. If the
+        subscriber acknowledges events, the messages sent to it are flow
+        controlled.
 
         Raises:
             InvalidFilterError
@@ -52,7 +66,10 @@ class EventBroadcaster(ChainObserver):
         with self._subscribers_cv:
             self._subscribers[connection_id] = \
                 EventSubscriber(
-                    connection_id, subscriptions, last_known_block_id)
+                    connection_id, subscriptions, last_known_block_id,
+                    acknowledged=acknowledged)
+            if acknowledged:
+                self._flow_control.add_subscriber(connection_id)
 
         LOGGER.debug(
             'Added Subscriber %s for %s', connection_id, subscriptions)
@@ -75,7 +92,24 @@ class EventBroadcaster(ChainObserver):
             last_known_block_id = subscriber.get_last_known_block_id()
             subscriptions = subscriber.subscriptions
 
-        if last_known_block_id is not None:
+        if last_known_block_id is not None and subscriber.acknowledged:
+            chain_head = self._block_store.chain_head
+            if chain_head is None:
+                return
+            if last_known_block_id == NULL_BLOCK_IDENTIFIER:
+                first = 0
+            else:
+                first = self._block_store[last_known_block_id].block_num + 1
+
+            LOGGER.debug(
+                'Catching up Subscriber %s from block %s',
+                connection_id, first)
+
+            # Blocks are sent in chunks as the subscriber acknowledges them
+            self._flow_control.push_blocks(
+                connection_id, first, chain_head.block_num)
+            self._send_queued(connection_id)
+        elif last_known_block_id is not None:
             LOGGER.debug(
                 'Catching up Subscriber %s from %s',
                 connection_id, last_known_block_id)
@@ -105,6 +139,7 @@ class EventBroadcaster(ChainObserver):
         with self._subscribers_cv:
             if connection_id in self._subscribers:
                 del self._subscribers[connection_id]
+            self._flow_control.remove_subscriber(connection_id)
 
     def get_catchup_block_ids(self, last_known_block_id):
         '''
@@ -214,37 +249,22 @@ class EventBroadcaster(ChainObserver):
                         txn.header_signature[:10],
                         blkw.identifier[:10])
 
-        block_event_extractor = BlockEventExtractor(blkw)
-        receipt_event_extractor = ReceiptEventExtractor(receipts=receipts)
-
-        events = []
-        events.extend(block_event_extractor.extract(subscriptions))
-        events.extend(receipt_event_extractor.extract(subscriptions))
-
-        return events
+        return extract_events(blkw, receipts, subscriptions)
 
     def chain_update(self, block, receipts):
-        extractors = [
-            BlockEventExtractor(block),
-            ReceiptEventExtractor(receipts),
-        ]
-
         subscriptions = []
         for subscriber in self._subscribers.values():
             for subscription in subscriber.subscriptions:
                 if subscription not in subscriptions:
                     subscriptions.append(subscription)
 
-        events = []
-        for extractor in extractors:
-            extracted_events = extractor.extract(subscriptions)
-            if extracted_events:
-                events.extend(extracted_events)
-
+        events = extract_events(block, receipts, subscriptions)
         if events:
-            self.broadcast_events(events)
+            self.broadcast_events(events, block.block_num)
+
+        self._drop_expired_subscribers()
 
-    def broadcast_events(self, events):
+    def broadcast_events(self, events, block_num):
         LOGGER.debug("Broadcasting events: %s", events)
         with self._subscribers_cv:
             # Copy the subscribers
@@ -261,7 +281,78 @@ class EventBroadcaster(ChainObserver):
                         if subscriber.is_subscribed(event)
                     ]
                     event_list = EventList(events=subscriber_events)
-                    self._send(connection_id, event_list.SerializeToString())
+                    if not subscriber.acknowledged:
+                        self._send(
+                            connection_id, event_list.SerializeToString())
+                        continue
+
+                    try:
+                        if self._flow_control.try_send(connection_id):
+                            self._send_acknowledged(
+                                connection_id, event_list.SerializeToString())
+                        else:
+                            self._flow_control.push_blocks(
+                                connection_id, block_num, block_num)
+                    except UnknownSubscriberError:
+                        # The subscriber was removed since it was copied
+                        pass
+
+    def _send_queued(self, connection_id):
+        """Sends the events of the blocks queued for the subscriber, one chunk
+        of blocks per message, while its window has room.
+        """
+        with self._subscribers_cv:
+            subscriber = self._subscribers.get(connection_id)
+        if subscriber is None:
+            return
+
+        while True:
+            try:
+                chunk = self._flow_control.next_chunk(connection_id)
+            except UnknownSubscriberError:
+                return
+            if chunk is None:
+                return
+
+            (first, last) = chunk
+            blocks = []
+            for block_num in range(first, last + 1):
+                try:
+                    blocks.append(
+                        self._block_store.get_block_by_number(block_num))
+                except KeyError:
+                    # The chain switched to a shorter fork
+                    break
+
+            events = self.get_events_for_blocks(
+                blocks, subscriber.subscriptions)
+            event_list = EventList(events=events)
+            self._send_acknowledged(
+                connection_id, event_list.SerializeToString())
+
+    def _on_ack(self, connection_id, _request, result):
+        if result.message_type != validator_pb2.Message.CLIENT_EVENTS_ACK:
+            LOGGER.warning(
+                'Subscriber %s replied to events with %s',
+                connection_id, result.message_type)
+            return
+
+        try:
+            self._flow_control.ack(connection_id)
+        except UnknownSubscriberError:
+            return
+
+        self._send_queued(connection_id)
+        self._drop_expired_subscribers()
+
+    def _drop_expired_subscribers(self):
+        for connection_id in self._flow_control.expired():
+            LOGGER.warning(
+                'Disconnecting subscriber %s, which stopped acknowledging '
+                'events', connection_id)
+            with self._subscribers_cv:
+                self._subscribers.pop(connection_id, None)
+            self._service.remove_connection(connection_id)
 
     def _send(self, connection_id, message_bytes):
         self._service.send(
@@ -270,14 +361,22 @@ class EventBroadcaster(ChainObserver):
             connection_id=connection_id,
             one_way=True)
 
+    def _send_acknowledged(self, connection_id, message_bytes):
+        self._service.send(
+            validator_pb2.Message.CLIENT_EVENTS,
+            message_bytes,
+            connection_id=connection_id,
+            callback=partial(self._on_ack, connection_id))
+
 
 class EventSubscriber:
     def __init__(self, connection_id, subscriptions, last_known_block,
-                 listening=False):
+                 listening=False, acknowledged=False):
         self._connection_id = connection_id
         self._subscriptions = subscriptions
         self._listening = listening
         self._last_known_block = last_known_block
+        self.acknowledged = acknowledged
 
     def start_listening(self):
         self._listening = True
@@ -310,4 +409,5 @@ class EventSubscriber:
             self._connection_id,
             self._subscriptions,
             self._last_known_block,
-            self._listening)
+            self._listening,
+            self.acknowledged)
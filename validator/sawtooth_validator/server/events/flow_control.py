@@ -0,0 +1,132 @@
+# Copyright 2018 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#     http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+# ------------------------------------------------------------------------------
+
+import ctypes
+
+from sawtooth_validator import ffi
+from sawtooth_validator.ffi import OwnedPointer
+from sawtooth_validator.server.events.native_extractor import ErrorCode
+
+
+class UnknownSubscriberError(Exception):
+    pass
+
+
+class FlowControl(OwnedPointer):
+    """Limits the event messages awaiting acknowledgement by each subscriber
+    that acknowledges events, queues the numbers of the blocks whose events
+    wait to be sent, and finds the subscribers that stopped acknowledging.
+    """
+
+    def __init__(self, max_outstanding, chunk_size, ack_timeout):
+        """
+        Args:
+            max_outstanding (int): The most messages sent to a subscriber
+                that it has not yet acknowledged.
+            chunk_size (int): The most blocks whose events are sent in one
+                message.
+            ack_timeout (int): The seconds after which a subscriber that has
+                not acknowledged a message is dropped.
+        """
+        super().__init__('events_flow_control_drop')
+        _check_error(ffi.LIBRARY.call(
+            'events_flow_control_new',
+            ctypes.c_size_t(max_outstanding),
+            ctypes.c_uint64(chunk_size),
+            ctypes.c_uint64(ack_timeout),
+            ctypes.byref(self.pointer)))
+
+    def add_subscriber(self, connection_id):
+        self._call('add_subscriber', connection_id)
+
+    def remove_subscriber(self, connection_id):
+        self._call('remove_subscriber', connection_id)
+
+    def try_send(self, connection_id):
+        """Returns whether a message may be sent to the subscriber now,
+        reserving room for it if so. If not, its blocks must be queued with
+        push_blocks.
+        """
+        return self._call('try_send', connection_id) == ErrorCode.Success
+
+    def push_blocks(self, connection_id, first, last):
+        """Queues the blocks numbered first to last, inclusive."""
+        self._call(
+            'push_blocks', connection_id,
+            ctypes.c_uint64(first), ctypes.c_uint64(last))
+
+    def next_chunk(self, connection_id):
+        """Returns the first and last numbers of the next queued blocks to
+        send, reserving room for the message, or None if the subscriber's
+        window is full or no blocks are queued.
+        """
+        first = ctypes.c_uint64(0)
+        last = ctypes.c_uint64(0)
+        res = self._call(
+            'next_chunk', connection_id,
+            ctypes.byref(first), ctypes.byref(last))
+        if res == ErrorCode.NotFound:
+            return None
+
+        return (first.value, last.value)
+
+    def ack(self, connection_id):
+        self._call('ack', connection_id)
+
+    def expired(self):
+        """Removes the subscribers that stopped acknowledging events and
+        returns their connection ids.
+        """
+        connection_ids = []
+        while True:
+            (vec_ptr, vec_len, vec_cap) = ffi.prepare_vec_result()
+            res = ffi.LIBRARY.call(
+                'events_flow_control_next_expired',
+                self.pointer,
+                ctypes.byref(vec_ptr),
+                ctypes.byref(vec_len),
+                ctypes.byref(vec_cap))
+            if res == ErrorCode.NotFound:
+                return connection_ids
+
+            _check_error(res)
+            connection_ids.append(
+                ffi.from_rust_vec(vec_ptr, vec_len, vec_cap).decode())
+
+    def _call(self, name, connection_id, *args):
+        res = ffi.LIBRARY.call(
+            'events_flow_control_' + name,
+            self.pointer,
+            ctypes.c_char_p(connection_id.encode()),
+            *args)
+        if res != ErrorCode.NotFound:
+            _check_error(res)
+        return res
+
+
+def _check_error(res):
+    if res == ErrorCode.Success:
+        return
+
+    if res == ErrorCode.NullPointerProvided:
+        raise TypeError("Provided null pointer(s)")
+
+    if res == ErrorCode.UnknownSubscriber:
+        raise UnknownSubscriberError()
+
+    if res == ErrorCode.InvalidArgument:
+        raise ValueError("Invalid connection id")
+
+    raise Exception("Unknown error code {}".format(res))
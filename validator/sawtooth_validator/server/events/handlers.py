@@ -39,6 +39,8 @@ from sawtooth_validator.server.events.subscription import EventSubscription
 from sawtooth_validator.server.events.subscription import EventFilterFactory
 from sawtooth_validator.server.events.subscription import InvalidFilterError
 from sawtooth_validator.server.events.broadcaster import NoKnownBlockError
+from sawtooth_validator.server.events.native_extractor \
+    import validate_subscriptions
 
 LOGGER = logging.getLogger(__name__)
 
@@ -61,6 +63,7 @@ class ClientEventsSubscribeValidationHandler(Handler):
 
         ack = ClientEventsSubscribeResponse()
         try:
+            validate_subscriptions(request.subscriptions)
             subscriptions = [
                 EventSubscription(
                     event_type=sub.event_type,
@@ -98,7 +101,8 @@ class ClientEventsSubscribeValidationHandler(Handler):
                     message_type=self._msg_type)
 
         self._event_broadcaster.add_subscriber(
-            connection_id, subscriptions, last_known_block_id)
+            connection_id, subscriptions, last_known_block_id,
+            acknowledged=request.acknowledge_events)
 
         ack.status = ack.OK
         return HandlerResult(
@@ -161,6 +165,7 @@ class ClientEventsGetRequestHandler(Handler):
 
         resp = ClientEventsGetResponse()
         try:
+            validate_subscriptions(request.subscriptions)
             subscriptions = [
                 EventSubscription(
                     event_type=sub.event_type,
@@ -0,0 +1,119 @@
+# Copyright 2018 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#     http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+# ------------------------------------------------------------------------------
+
+import ctypes
+from enum import IntEnum
+
+from sawtooth_validator import ffi
+from sawtooth_validator.journal.block_wrapper import BlockWrapper
+from sawtooth_validator.protobuf.client_event_pb2 \
+    import ClientEventsSubscribeRequest
+from sawtooth_validator.protobuf.events_pb2 import EventList
+from sawtooth_validator.protobuf.transaction_receipt_pb2 \
+    import TransactionReceiptList
+from sawtooth_validator.server.events.subscription import InvalidFilterError
+
+
+class ErrorCode(IntEnum):
+    Success = 0
+    NullPointerProvided = 0x01
+    InvalidArgument = 0x02
+    InvalidFilter = 0x03
+    UnknownSubscriber = 0x04
+    NotFound = 0x05
+
+
+def extract_events(block, receipts, subscriptions):
+    """Returns the events of a committed block that are in any of the
+    subscriptions, extracted by the validator's Rust library.
+
+    Args:
+        block (:obj:`BlockWrapper` or :obj:`Block`): The committed block.
+        receipts (list of :obj:`TransactionReceipt`): The receipts of the
+            block's transactions.
+        subscriptions (list of :obj:`EventSubscription`): The subscriptions
+            to return events for.
+
+    Returns:
+        A list of protobuf Events.
+    """
+    if not subscriptions:
+        return []
+
+    block_bytes = BlockWrapper.wrap(block).block.SerializeToString()
+    receipts_bytes = TransactionReceiptList(
+        receipts=receipts).SerializeToString()
+    subscriptions_bytes = _subscriptions_to_bytes(
+        sub.to_proto() for sub in subscriptions)
+
+    (vec_ptr, vec_len, vec_cap) = ffi.prepare_vec_result()
+    _check_error(ffi.LIBRARY.call(
+        "events_extract",
+        block_bytes, len(block_bytes),
+        receipts_bytes, len(receipts_bytes),
+        subscriptions_bytes, len(subscriptions_bytes),
+        ctypes.byref(vec_ptr),
+        ctypes.byref(vec_len),
+        ctypes.byref(vec_cap)))
+
+    event_list = EventList()
+    event_list.ParseFromString(ffi.from_rust_vec(vec_ptr, vec_len, vec_cap))
+    return list(event_list.events)
+
+
+def validate_subscriptions(subscriptions):
+    """Checks that the filters of the protobuf EventSubscriptions can be
+    applied by the Rust event extractor.
+
+    Raises:
+        InvalidFilterError
+            One of the filters in the subscriptions is invalid.
+    """
+    subscriptions_bytes = _subscriptions_to_bytes(subscriptions)
+
+    (vec_ptr, vec_len, vec_cap) = ffi.prepare_vec_result()
+    res = ffi.LIBRARY.call(
+        "events_validate_subscriptions",
+        subscriptions_bytes, len(subscriptions_bytes),
+        ctypes.byref(vec_ptr),
+        ctypes.byref(vec_len),
+        ctypes.byref(vec_cap))
+
+    if res == ErrorCode.InvalidFilter:
+        raise InvalidFilterError(
+            ffi.from_rust_vec(vec_ptr, vec_len, vec_cap).decode())
+
+    _check_error(res)
+
+
+def _subscriptions_to_bytes(subscriptions):
+    return ClientEventsSubscribeRequest(
+        subscriptions=subscriptions).SerializeToString()
+
+
+def _check_error(res):
+    if res == ErrorCode.Success:
+        return
+
+    if res == ErrorCode.NullPointerProvided:
+        raise TypeError("Provided null pointer(s)")
+
+    if res == ErrorCode.InvalidFilter:
+        raise InvalidFilterError("Invalid event filter")
+
+    if res == ErrorCode.InvalidArgument:
+        raise ValueError("Unable to extract events from the block")
+
+    raise Exception("Unknown error code {}".format(res))
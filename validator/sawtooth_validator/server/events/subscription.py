@@ -53,6 +53,17 @@ class EventSubscription:
 
         return False
 
+    def to_proto(self):
+        return events_pb2.EventSubscription(
+            event_type=self.event_type,
+            filters=[
+                events_pb2.EventFilter(
+                    key=sub_filter.key,
+                    match_string=sub_filter.match_string,
+                    filter_type=sub_filter.filter_type)
+                for sub_filter in self.filters
+            ])
+
 
 class InvalidFilterError(Exception):
     pass
@@ -99,6 +110,8 @@ class EventFilter(metaclass=ABCMeta):
 
 
 class SimpleAnyFilter(EventFilter):
+    filter_type = events_pb2.EventFilter.SIMPLE_ANY
+
     def matches(self, event):
         for attribute in event.attributes:
             if self.key == attribute.key:
@@ -108,6 +121,8 @@ class SimpleAnyFilter(EventFilter):
 
 
 class SimpleAllFilter(EventFilter):
+    filter_type = events_pb2.EventFilter.SIMPLE_ALL
+
     def matches(self, event):
         for attribute in event.attributes:
             if self.key == attribute.key:
@@ -136,6 +151,8 @@ class RegexAnyFilter(EventFilter):
     Because it matches one of the two attributes with the key "address".
     """
 
+    filter_type = events_pb2.EventFilter.REGEX_ANY
+
     def __init__(self, key, match_string):
         super().__init__(key, match_string)
         try:
@@ -173,6 +190,8 @@ class RegexAllFilter(EventFilter):
     Because it does not match all attributes with the key "address".
     """
 
+    filter_type = events_pb2.EventFilter.REGEX_ALL
+
     def __init__(self, key, match_string):
         super().__init__(key, match_string)
         try:
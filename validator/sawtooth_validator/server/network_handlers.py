@@ -228,7 +228,8 @@ def add(
     # GOSSIP_MESSAGE ) Verifies signature
     dispatcher.add_handler(
         validator_pb2.Message.GOSSIP_MESSAGE,
-        signature_verifier.GossipMessageSignatureVerifier(),
+        signature_verifier.GossipMessageSignatureVerifier(
+            peer_scores=gossip.peer_scores),
         sig_pool)
 
     # GOSSIP_MESSAGE ) Verifies batch structure
@@ -311,7 +312,8 @@ def add(
     # GOSSIP_BLOCK_RESPONSE 3) Verifies signature
     dispatcher.add_handler(
         validator_pb2.Message.GOSSIP_BLOCK_RESPONSE,
-        signature_verifier.GossipBlockResponseSignatureVerifier(),
+        signature_verifier.GossipBlockResponseSignatureVerifier(
+            peer_scores=gossip.peer_scores),
         sig_pool)
 
     # GOSSIP_BLOCK_RESPONSE 4) Check batch structure
@@ -383,7 +385,8 @@ def add(
     # GOSSIP_BATCH_RESPONSE 2) Verifies signature
     dispatcher.add_handler(
         validator_pb2.Message.GOSSIP_BATCH_RESPONSE,
-        signature_verifier.GossipBatchResponseSignatureVerifier(),
+        signature_verifier.GossipBatchResponseSignatureVerifier(
+            peer_scores=gossip.peer_scores),
         sig_pool)
 
     # GOSSIP_BATCH_RESPONSE 3) Check batch structure
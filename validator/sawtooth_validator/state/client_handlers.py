@@ -17,6 +17,8 @@
 # Until this module can be sensibly broken up
 
 import abc
+import ctypes
+from enum import IntEnum
 import logging
 from time import time
 import itertools
@@ -27,22 +29,31 @@ from threading import Condition
 # needed for google.protobuf import
 from google.protobuf.message import DecodeError
 
+from sawtooth_validator import ffi
+from sawtooth_validator import metrics
 from sawtooth_validator.state.merkle import MerkleDatabase
+from sawtooth_validator.state.identity_view import IdentityView
+from sawtooth_validator.state.state_view import StateView
+from sawtooth_validator.gossip.permission_verifier import evaluate_policy
+from sawtooth_validator.journal.journal import MAX_PAUSE_TIMEOUT
 from sawtooth_validator.state.batch_tracker import BatchFinishObserver
 from sawtooth_validator.networking.dispatch import Handler
 from sawtooth_validator.networking.dispatch import HandlerResult
 from sawtooth_validator.networking.dispatch import HandlerStatus
 from sawtooth_validator.networking.dispatch import PreprocessorResult
 
+from sawtooth_validator.protobuf import admin_pb2
 from sawtooth_validator.protobuf import client_batch_pb2
 from sawtooth_validator.protobuf import client_block_pb2
 from sawtooth_validator.protobuf import client_state_pb2
 from sawtooth_validator.protobuf import client_transaction_pb2
 from sawtooth_validator.protobuf import client_batch_submit_pb2
+from sawtooth_validator.protobuf import client_identity_pb2
 from sawtooth_validator.protobuf import client_list_control_pb2
 from sawtooth_validator.protobuf import client_peers_pb2
 from sawtooth_validator.protobuf import client_status_pb2
 from sawtooth_validator.protobuf.block_pb2 import BlockHeader
+from sawtooth_validator.protobuf.identity_pb2 import Policy
 from sawtooth_validator.protobuf import validator_pb2
 from sawtooth_validator.protobuf.client_batch_submit_pb2 \
     import ClientBatchSubmitResponse
@@ -50,8 +61,6 @@ from sawtooth_validator.protobuf.client_batch_submit_pb2 \
 
 LOGGER = logging.getLogger(__name__)
 DEFAULT_TIMEOUT = 300
-MAX_PAGE_SIZE = 1000
-DEFAULT_PAGE_SIZE = 100
 
 
 class _ResponseFailed(BaseException):
@@ -337,21 +346,44 @@ class _ClientRequestHandler(Handler, metaclass=abc.ABCMeta):
             raise _ResponseFailed(self._status.INVALID_ADDRESS)
 
 
+class _PagingErrorCode(IntEnum):
+    Success = ffi.CommonErrorCode.Success
+    NullPointerProvided = ffi.CommonErrorCode.NullPointerProvided
+    InvalidId = 0x02
+    StartNotFound = 0x03
+
+
 class _Pager:
     """A static class containing methods to paginate lists of resources.
 
-    Contains a paginate method, as well as two helpers to fetch index of a
-    resource by its id (either address or header_signature), or conversely
-    to fetch the id by an index.
+    The paging itself is done natively, so that every listing handler pages
+    the same way: a page starts at the resource whose id (either address or
+    header_signature) is the start of the paging controls, and the next page
+    starts at the resource after its last.
     """
 
+    @staticmethod
+    def limit(paging):
+        """Returns the limit to read a page with, given ClientPagingControls
+        """
+        limit = ctypes.c_size_t(0)
+        _pager_libexec(
+            'client_paging_limit',
+            ctypes.c_size_t(paging.limit),
+            ctypes.byref(limit))
+        return limit.value
+
     @classmethod
-    def paginate_resources(cls, request, resources, on_fail_status):
+    def paginate_resources(cls, request, resources, on_fail_status,
+                           reverse=False):
         """Truncates a list of resources based on ClientPagingControls
 
         Args:
             request (object): The parsed protobuf request object
             resources (list of objects): The resources to be paginated
+            on_fail_status (int, enum): The status to raise if the start of
+                the paging controls is not found
+            reverse (bool): Whether to page through the resources in reverse
 
         Returns:
             list: The paginated list of resources
@@ -361,52 +393,37 @@ class _Pager:
             return (resources, client_list_control_pb2.ClientPagingResponse())
 
         paging = request.paging
-        limit = min(paging.limit, MAX_PAGE_SIZE) or DEFAULT_PAGE_SIZE
-        # Find the start index from the location marker sent
+        ids = [cls.id_by_index(i, resources) for i in range(len(resources))]
+        c_ids = (ctypes.c_char_p * len(ids))(*[i.encode() for i in ids])
+        c_start = ctypes.c_char_p(paging.start.encode()) \
+            if paging.start else None
+
+        start = ctypes.c_size_t(0)
+        end = ctypes.c_size_t(0)
+        limit = ctypes.c_size_t(0)
         try:
-            if paging.start:
-                start_index = cls.index_by_id(paging.start, resources)
-            else:
-                start_index = 0
+            _pager_libexec(
+                'client_paging_paginate',
+                c_ids,
+                ctypes.c_size_t(len(ids)),
+                c_start,
+                ctypes.c_size_t(paging.limit),
+                ctypes.c_bool(reverse),
+                ctypes.byref(start),
+                ctypes.byref(end),
+                ctypes.byref(limit))
+        except KeyError:
+            raise _ResponseFailed(on_fail_status) from KeyError
 
-            if start_index < 0 or start_index >= len(resources):
-                raise AssertionError
-        except AssertionError:
-            raise _ResponseFailed(on_fail_status) from AssertionError
-
-        paged_resources = resources[start_index: start_index + limit]
-        if start_index + limit < len(resources):
-            paging_response = client_list_control_pb2.ClientPagingResponse(
-                next=cls.id_by_index(start_index + limit, resources),
-                start=cls.id_by_index(start_index, resources),
-                limit=limit)
-        else:
-            paging_response = client_list_control_pb2.ClientPagingResponse(
-                start=cls.id_by_index(start_index, resources),
-                limit=limit)
+        ordered = resources[::-1] if reverse else resources
+        paged_resources = ordered[start.value:end.value]
+        paging_response = client_list_control_pb2.ClientPagingResponse(
+            start=cls.id_by_index(start.value, ordered),
+            next=cls.id_by_index(end.value, ordered),
+            limit=limit.value)
 
         return paged_resources, paging_response
 
-    @classmethod
-    def index_by_id(cls, target_id, resources):
-        """Helper method to fetch the index of a resource by its id or address
-
-        Args:
-            resources (list of objects): The resources to be paginated
-            target_id (string): The address or header_signature of the resource
-
-        Returns:
-            integer: The index of the target resource
-
-        Raises:
-            AssertionError: Raised if the target is not found
-        """
-        for index in range(len(resources)):
-            if cls.id_by_index(index, resources) == target_id:
-                return index
-
-        raise AssertionError
-
     @staticmethod
     def id_by_index(index, resources):
         """Helper method to fetch the id or address of a resource by its index
@@ -428,6 +445,20 @@ class _Pager:
             return resources[index].address
 
 
+def _pager_libexec(name, *args):
+    res = ffi.LIBRARY.call(name, *args)
+    if res == _PagingErrorCode.Success:
+        return
+    if res == _PagingErrorCode.StartNotFound:
+        raise KeyError("Paging start not found")
+    if res == _PagingErrorCode.NullPointerProvided:
+        raise TypeError("Provided null pointer(s)")
+    if res == _PagingErrorCode.InvalidId:
+        raise ValueError("Resource ids must be valid strings")
+
+    raise TypeError("Unknown error occurred: {}".format(res))
+
+
 class _Sorter:
     """A static class containing a method to sort lists of resources based on
     ClientSortControls sent with the request.
@@ -704,6 +735,10 @@ class StateListRequest(_ClientRequestHandler):
 
         # Fetch entries and encode as protobuf
         self._validate_namespace(request.address)
+
+        if not self.is_reverse(request.sorting, self._status.INVALID_SORT):
+            return self._respond_paged(request, state_root)
+
         entries = [
             client_state_pb2.ClientStateListResponse.Entry(address=a, data=v)
             for a, v in self._tree.leaves(request.address or '')]
@@ -711,13 +746,12 @@ class StateListRequest(_ClientRequestHandler):
         # Order entries, remove if tree.entries refactored to be ordered
         entries.sort(key=lambda l: l.address)
 
-        if self.is_reverse(request.sorting, self._status.INVALID_SORT):
-            entries.reverse()
-
+        reverse = self.is_reverse(request.sorting, self._status.INVALID_SORT)
         entries, paging = _Pager.paginate_resources(
             request,
             entries,
-            self._status.INVALID_PAGING)
+            self._status.INVALID_PAGING,
+            reverse=reverse)
 
         if not entries:
             return self._wrap_response(
@@ -730,6 +764,46 @@ class StateListRequest(_ClientRequestHandler):
             paging=paging,
             entries=entries)
 
+    def _respond_paged(self, request, state_root):
+        """Reads only the requested page of entries from the tree, rather
+        than every leaf under the address.
+        """
+        paging = request.paging
+        limit = _Pager.limit(paging)
+
+        try:
+            leaves, next_address = self._tree.leaves_page(
+                request.address or '',
+                start_address=paging.start or None,
+                limit=limit)
+        except KeyError as e:
+            raise _ResponseFailed(self._status.INVALID_PAGING) from e
+
+        if not leaves:
+            if paging.start:
+                raise _ResponseFailed(self._status.INVALID_PAGING)
+            return self._wrap_response(
+                self._status.NO_RESOURCE,
+                state_root=state_root,
+                paging=client_list_control_pb2.ClientPagingResponse())
+
+        if paging.start and leaves[0][0] != paging.start:
+            raise _ResponseFailed(self._status.INVALID_PAGING)
+
+        entries = [
+            client_state_pb2.ClientStateListResponse.Entry(address=a, data=v)
+            for a, v in leaves]
+
+        paging_response = client_list_control_pb2.ClientPagingResponse(
+            next=next_address or '',
+            start=entries[0].address,
+            limit=limit)
+
+        return self._wrap_response(
+            state_root=state_root,
+            paging=paging_response,
+            entries=entries)
+
     @staticmethod
     def is_reverse(sorting, fail_status):
         if not sorting:
@@ -769,6 +843,89 @@ class StateGetRequest(_ClientRequestHandler):
         return self._wrap_response(state_root=state_root, value=value)
 
 
+class IdentityPolicyListRequest(_ClientRequestHandler):
+    def __init__(self, database, block_store):
+        super().__init__(
+            client_identity_pb2.ClientIdentityPolicyListRequest,
+            client_identity_pb2.ClientIdentityPolicyListResponse,
+            validator_pb2.Message.CLIENT_IDENTITY_POLICY_LIST_RESPONSE,
+            tree=MerkleDatabase(database),
+            block_store=block_store)
+
+    def _respond(self, request):
+        if request.state_root != '':
+            self._validate_state_root(request.state_root)
+        state_root = self._set_root(request)
+
+        identity_view = IdentityView(StateView(self._tree))
+
+        return self._wrap_response(
+            state_root=state_root,
+            policies=identity_view.get_policies(),
+            roles=identity_view.get_roles())
+
+
+class IdentityEvaluateRequest(_ClientRequestHandler):
+    """Evaluates whether a public key would be permitted by the policy of a
+    role, resolving the role the way the permission verifier does: if the
+    role is not set, its last dot-separated component is dropped and the
+    shorter role is tried, down to the "default" policy. If no policy is
+    found, every key is permitted.
+    """
+
+    def __init__(self, database, block_store):
+        super().__init__(
+            client_identity_pb2.ClientIdentityEvaluateRequest,
+            client_identity_pb2.ClientIdentityEvaluateResponse,
+            validator_pb2.Message.CLIENT_IDENTITY_EVALUATE_RESPONSE,
+            tree=MerkleDatabase(database),
+            block_store=block_store)
+
+    def _respond(self, request):
+        if not request.role or not request.public_key:
+            LOGGER.debug('Identity evaluation requires a role and public key')
+            return self._status.INVALID_REQUEST
+
+        if request.state_root != '':
+            self._validate_state_root(request.state_root)
+        state_root = self._set_root(request)
+
+        identity_view = IdentityView(StateView(self._tree))
+
+        role_name, policy_name = self._resolve_role(
+            identity_view, request.role)
+        policy = identity_view.get_policy(policy_name)
+        if policy is None:
+            return self._wrap_response(
+                state_root=state_root,
+                allowed=True,
+                role=role_name)
+
+        entry = evaluate_policy(request.public_key, policy)
+        allowed = entry is not None and entry.type == Policy.PERMIT_KEY
+
+        return self._wrap_response(
+            state_root=state_root,
+            allowed=allowed,
+            role=role_name,
+            policy_name=policy_name,
+            matched_entry=entry)
+
+    @staticmethod
+    def _resolve_role(identity_view, name):
+        """Returns the name of the first role set in state, starting with the
+        requested role, and the name of its policy.
+        """
+        parts = name.split('.')
+        while parts:
+            role = identity_view.get_role('.'.join(parts))
+            if role is not None:
+                return role.name, role.policy_name
+            parts.pop()
+
+        return 'default', 'default'
+
+
 class BlockListRequest(_ClientRequestHandler):
     def __init__(self, block_store):
         super().__init__(
@@ -797,7 +954,7 @@ class BlockListRequest(_ClientRequestHandler):
             paging = request.paging
             sort_reverse = BlockListRequest.is_reverse(
                 request.sorting, self._status.INVALID_SORT)
-            limit = min(paging.limit, MAX_PAGE_SIZE) or DEFAULT_PAGE_SIZE
+            limit = _Pager.limit(paging)
             iterargs = {
                 'reverse': not sort_reverse
             }
@@ -972,13 +1129,12 @@ class BatchListRequest(_ClientRequestHandler):
             self._block_store.get_batch,
             lambda block: list(block.batches))
 
-        if self.is_reverse(request.sorting, self._status.INVALID_SORT):
-            batches.reverse()
-
+        reverse = self.is_reverse(request.sorting, self._status.INVALID_SORT)
         batches, paging = _Pager.paginate_resources(
             request,
             batches,
-            self._status.INVALID_PAGING)
+            self._status.INVALID_PAGING,
+            reverse=reverse)
 
         if not batches:
             return self._wrap_response(
@@ -1041,13 +1197,12 @@ class TransactionListRequest(_ClientRequestHandler):
             self._block_store.get_transaction,
             lambda block: [t for a in block.batches for t in a.transactions])
 
-        if self.is_reverse(request.sorting, self._status.INVALID_SORT):
-            transactions.reverse()
-
+        reverse = self.is_reverse(request.sorting, self._status.INVALID_SORT)
         transactions, paging = _Pager.paginate_resources(
             request,
             transactions,
-            self._status.INVALID_PAGING)
+            self._status.INVALID_PAGING,
+            reverse=reverse)
 
         if not transactions:
             return self._wrap_response(
@@ -1106,6 +1261,27 @@ class PeersGetRequest(_ClientRequestHandler):
         return self._wrap_response(peers=endpoints)
 
 
+class PeerScoresGetRequest(_ClientRequestHandler):
+    def __init__(self, gossip):
+        super().__init__(
+            client_peers_pb2.ClientPeerScoresGetRequest,
+            client_peers_pb2.ClientPeerScoresGetResponse,
+            validator_pb2.Message.CLIENT_PEER_SCORES_GET_RESPONSE
+        )
+        self._gossip = gossip
+
+    def _respond(self, request):
+        scores = [
+            client_peers_pb2.ClientPeerScoresGetResponse.PeerScore(
+                peer_id=peer_id,
+                score=score,
+                ban_remaining=ban_remaining)
+            for peer_id, score, ban_remaining
+            in self._gossip.peer_scores.scores()
+        ]
+        return self._wrap_response(scores=scores)
+
+
 class StatusGetRequest(_ClientRequestHandler):
     def __init__(self, gossip):
         super().__init__(
@@ -1142,3 +1318,146 @@ def client_batch_submit_request_preprocessor(message_content_bytes):
             message_type=validator_pb2.Message.CLIENT_BATCH_SUBMIT_RESPONSE)
 
     return PreprocessorResult(content=request)
+
+
+class AdminLogLevelSetRequest(_ClientRequestHandler):
+    """Changes the level of one of the validator's loggers, or of the root
+    logger if none is named. Rust log levels follow through ffi.
+    """
+
+    _LEVELS = {
+        'TRACE': 5,
+        'DEBUG': logging.DEBUG,
+        'INFO': logging.INFO,
+        'WARNING': logging.WARNING,
+        'ERROR': logging.ERROR,
+        'CRITICAL': logging.CRITICAL,
+    }
+
+    def __init__(self):
+        super().__init__(
+            admin_pb2.AdminLogLevelSetRequest,
+            admin_pb2.AdminLogLevelSetResponse,
+            validator_pb2.Message.ADMIN_LOG_LEVEL_SET_RESPONSE)
+
+    def _respond(self, request):
+        level = self._LEVELS.get(request.level.upper())
+        if level is None:
+            LOGGER.debug('Invalid log level requested: %s', request.level)
+            return self._status.INVALID_LEVEL
+
+        LOGGER.info(
+            'Setting log level of %s to %s',
+            request.logger or 'root logger', request.level.upper())
+        logging.getLogger(request.logger or None).setLevel(level)
+        return self._wrap_response()
+
+
+class AdminMetricsGetRequest(_ClientRequestHandler):
+    def __init__(self):
+        super().__init__(
+            admin_pb2.AdminMetricsGetRequest,
+            admin_pb2.AdminMetricsGetResponse,
+            validator_pb2.Message.ADMIN_METRICS_GET_RESPONSE)
+
+    def _respond(self, request):
+        snapshot = metrics.snapshot()
+        if snapshot is None:
+            return self._status.NOT_ENABLED
+
+        return self._wrap_response(metrics=[
+            admin_pb2.AdminMetricsGetResponse.Metric(
+                name=name,
+                fields={
+                    field: float(value)
+                    for field, value in fields.items()
+                    if isinstance(value, (int, float))
+                })
+            for name, fields in sorted(snapshot.items())
+        ])
+
+
+class AdminPublishingPauseRequest(_ClientRequestHandler):
+    """Pauses block publishing, until resumed or for at most the requested
+    timeout. Timeouts longer than MAX_PAUSE_TIMEOUT are refused.
+    """
+
+    def __init__(self, journal):
+        super().__init__(
+            admin_pb2.AdminPublishingPauseRequest,
+            admin_pb2.AdminPublishingPauseResponse,
+            validator_pb2.Message.ADMIN_PUBLISHING_PAUSE_RESPONSE)
+        self._journal = journal
+
+    def _respond(self, request):
+        if request.timeout > MAX_PAUSE_TIMEOUT:
+            LOGGER.debug(
+                'Refusing to pause publishing for %s seconds',
+                request.timeout)
+            return self._status.INVALID_TIMEOUT
+
+        self._journal.pause_publishing(request.timeout)
+        return self._wrap_response()
+
+
+class AdminPublishingResumeRequest(_ClientRequestHandler):
+    def __init__(self, journal):
+        super().__init__(
+            admin_pb2.AdminPublishingResumeRequest,
+            admin_pb2.AdminPublishingResumeResponse,
+            validator_pb2.Message.ADMIN_PUBLISHING_RESUME_RESPONSE)
+        self._journal = journal
+
+    def _respond(self, request):
+        self._journal.resume_publishing()
+        return self._wrap_response()
+
+
+class AdminConsensusEnginesGetRequest(_ClientRequestHandler):
+    """Lists the registered consensus engines, which of them is active, and
+    whether each still answers the validator's pings.
+    """
+
+    def __init__(self, consensus_registry):
+        super().__init__(
+            admin_pb2.AdminConsensusEnginesGetRequest,
+            admin_pb2.AdminConsensusEnginesGetResponse,
+            validator_pb2.Message.ADMIN_CONSENSUS_ENGINES_GET_RESPONSE)
+        self._consensus_registry = consensus_registry
+
+    def _respond(self, request):
+        return self._wrap_response(
+            engines=self._consensus_registry.get_engines())
+
+
+class AdminForksGetRequest(_ClientRequestHandler):
+    """Lists the forks off the current chain that the fork tracker knows of,
+    with the validation status of each fork's head.
+    """
+
+    def __init__(self, fork_tracker, journal):
+        super().__init__(
+            admin_pb2.AdminForksGetRequest,
+            admin_pb2.AdminForksGetResponse,
+            validator_pb2.Message.ADMIN_FORKS_GET_RESPONSE)
+        self._fork_tracker = fork_tracker
+        self._journal = journal
+
+    def _respond(self, request):
+        chain_head = self._journal.chain_head
+        if chain_head is None:
+            return self._status.NOT_READY
+
+        chain_head_id = chain_head.header_signature
+        return self._wrap_response(
+            chain_head_id=chain_head_id,
+            forks=[
+                admin_pb2.AdminForksGetResponse.Fork(
+                    head_id=fork.head_id,
+                    head_block_num=fork.head_block_num,
+                    fork_point_id=fork.fork_point_id,
+                    length=fork.length,
+                    head_status=self._journal.block_validation_result(
+                        fork.head_id).name)
+                for fork in self._fork_tracker.forks(chain_head_id)
+            ])
@@ -19,6 +19,8 @@ from enum import IntEnum
 import cbor
 
 from sawtooth_validator import ffi
+from sawtooth_validator.protobuf.merkle_pb2 import MerkleLeavesPage
+from sawtooth_validator.protobuf.merkle_pb2 import MerkleStateDiff
 
 
 # This is included for legacy reasons.
@@ -37,6 +39,7 @@ class MerkleDatabase(ffi.OwnedPointer):
 
     def __init__(self, database, merkle_root=None):
         super().__init__('merkle_db_drop')
+        self._database = database
 
         if merkle_root:
             init_root = ctypes.c_char_p(merkle_root.encode())
@@ -113,6 +116,66 @@ class MerkleDatabase(ffi.OwnedPointer):
         return _decode(ffi.from_rust_vec(
             vec_ptr, vec_len, vec_cap))
 
+    def get_with_proof(self, address):
+        """Returns the value at an address, along with a proof that the value
+        is included under the current merkle root.
+
+        Args:
+            address (str): An address.
+
+        Returns:
+            (tuple): The decoded value and the serialized proof (bytes).
+        """
+        c_root = ctypes.c_char_p(self.get_merkle_root().encode())
+        c_address = ctypes.c_char_p(address.encode())
+        (vec_ptr, vec_len, vec_cap) = ffi.prepare_vec_result()
+        (proof_ptr, proof_len, proof_cap) = ffi.prepare_vec_result()
+
+        _libexec(
+            'merkle_db_get_with_proof',
+            self._database.pointer,
+            c_root,
+            c_address,
+            ctypes.byref(vec_ptr),
+            ctypes.byref(vec_len),
+            ctypes.byref(vec_cap),
+            ctypes.byref(proof_ptr),
+            ctypes.byref(proof_len),
+            ctypes.byref(proof_cap))
+
+        value = _decode(ffi.from_rust_vec(vec_ptr, vec_len, vec_cap))
+        proof = ffi.from_rust_vec(proof_ptr, proof_len, proof_cap)
+
+        return (value, proof)
+
+    @staticmethod
+    def verify_proof(merkle_root, address, value, proof):
+        """Checks that a proof shows the value stored at the address under
+        the given merkle root.
+
+        Args:
+            merkle_root (str): The state root the proof was built against.
+            address (str): An address.
+            value: The decoded value expected at the address.
+            proof (bytes): A proof returned by get_with_proof.
+
+        Returns:
+            (bool): True if the proof is valid, False otherwise.
+        """
+        data = _encode(value)
+        c_result = ctypes.c_bool()
+        _libexec(
+            'merkle_db_verify_proof',
+            ctypes.c_char_p(merkle_root.encode()),
+            ctypes.c_char_p(address.encode()),
+            data,
+            len(data),
+            proof,
+            len(proof),
+            ctypes.byref(c_result))
+
+        return c_result.value
+
     def __setitem__(self, address, value):
         return self.set(address, value)
 
@@ -205,6 +268,81 @@ class MerkleDatabase(ffi.OwnedPointer):
             # The prefix doesn't exist
             return iter([])
 
+    def leaves_page(self, prefix=None, start_address=None, limit=100):
+        """Returns a bounded, address-ordered page of leaves.
+
+        Args:
+            prefix (str): The address prefix to read under.
+            start_address (str): The first address to include, typically the
+                continuation address of a previous page.
+            limit (int): The maximum number of leaves to return.
+
+        Returns:
+            (tuple): A list of (address, data) tuples, and the address at
+                which the next page starts, or None if this is the last page.
+        """
+        if prefix is None:
+            prefix = ''
+
+        c_start = None
+        if start_address is not None:
+            c_start = ctypes.c_char_p(start_address.encode())
+
+        (vec_ptr, vec_len, vec_cap) = ffi.prepare_vec_result()
+
+        _libexec(
+            'merkle_db_leaves_page',
+            self._database.pointer,
+            ctypes.c_char_p(self.get_merkle_root().encode()),
+            ctypes.c_char_p(prefix.encode()),
+            c_start,
+            ctypes.c_size_t(limit),
+            ctypes.byref(vec_ptr),
+            ctypes.byref(vec_len),
+            ctypes.byref(vec_cap))
+
+        page = MerkleLeavesPage()
+        page.ParseFromString(ffi.from_rust_vec(vec_ptr, vec_len, vec_cap))
+
+        leaves = [(leaf.address, _decode(leaf.data)) for leaf in page.leaves]
+        return (leaves, page.next or None)
+
+    @staticmethod
+    def diff(database, from_root, to_root):
+        """Returns the addresses whose values differ between two state roots,
+        in address order. Only the subtrees that differ are read.
+
+        Args:
+            database (NativeLmdbDatabase): The state database.
+            from_root (str): The earlier state root.
+            to_root (str): The later state root.
+
+        Returns:
+            (list): A list of (address, old_value, new_value) tuples, where a
+                value is None if the address is not set under that root.
+        """
+        (vec_ptr, vec_len, vec_cap) = ffi.prepare_vec_result()
+
+        _libexec(
+            'merkle_db_diff',
+            database.pointer,
+            ctypes.c_char_p(from_root.encode()),
+            ctypes.c_char_p(to_root.encode()),
+            ctypes.byref(vec_ptr),
+            ctypes.byref(vec_len),
+            ctypes.byref(vec_cap))
+
+        state_diff = MerkleStateDiff()
+        state_diff.ParseFromString(
+            ffi.from_rust_vec(vec_ptr, vec_len, vec_cap))
+
+        return [
+            (change.address,
+             _decode(change.old_value) if change.old_value else None,
+             _decode(change.new_value) if change.new_value else None)
+            for change in state_diff.changes
+        ]
+
     def close(self):
         pass
 
@@ -225,6 +363,8 @@ def _libexec(name, *args):
     if res == ErrorCode.InvalidAddress:
         raise KeyError(
             "Address was not valid ")
+    if res == ErrorCode.InvalidProof:
+        raise ValueError("The merkle proof could not be read")
     if res == ErrorCode.InvalidChangeLogIndex:
         raise ValueError("The Change Log index is in an invalid state")
     if res == ErrorCode.StopIteration:
@@ -299,6 +439,7 @@ class ErrorCode(IntEnum):
     NullPointerProvided = ffi.CommonErrorCode.NullPointerProvided
     InvalidHashString = 2
     InvalidAddress = 3
+    InvalidProof = 4
 
     # output errors
     DatabaseError = 0x11
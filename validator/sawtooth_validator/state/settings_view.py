@@ -14,6 +14,7 @@
 # ------------------------------------------------------------------------------
 
 import hashlib
+import logging
 import weakref
 
 from functools import lru_cache, wraps
@@ -21,9 +22,16 @@ from functools import lru_cache, wraps
 from sawtooth_validator.protobuf.setting_pb2 import Setting
 
 
+LOGGER = logging.getLogger(__name__)
+
+
 CONFIG_STATE_NAMESPACE = '000000'
 _MAX_KEY_PARTS = 4
 _ADDRESS_PART_SIZE = 16
+_MAX_U64 = 2 ** 64 - 1
+
+_TRUE_VALUES = ('true', 'yes', 'on', '1')
+_FALSE_VALUES = ('false', 'no', 'off', '0')
 
 
 def _short_hash(byte_str):
@@ -129,6 +137,112 @@ class SettingsView:
 
         return setting_list
 
+    def get_setting_u64(self, key, default_value=None):
+        """Get the setting stored at the given key as an unsigned 64-bit
+        integer.
+
+        Args:
+            key (str): the setting key
+            default_value (int, optional): The default value, if none is
+                found or the value is not an unsigned 64-bit integer.
+                Defaults to None.
+
+        Returns:
+            int: The value of the setting if found and valid, default_value
+            otherwise.
+        """
+        value = self.get_setting(key)
+        if value is None:
+            return default_value
+
+        try:
+            int_value = int(value.strip())
+        except ValueError:
+            int_value = None
+
+        if int_value is None or not 0 <= int_value <= _MAX_U64:
+            LOGGER.warning(
+                'Setting %s is not an unsigned 64-bit integer: %r; using %r',
+                key, value, default_value)
+            return default_value
+
+        return int_value
+
+    def get_setting_bool(self, key, default_value=None):
+        """Get the setting stored at the given key as a boolean.
+
+        The values true, yes, on and 1 are True, and false, no, off and 0 are
+        False, ignoring case.
+
+        Args:
+            key (str): the setting key
+            default_value (bool, optional): The default value, if none is
+                found or the value is not a boolean. Defaults to None.
+
+        Returns:
+            bool: The value of the setting if found and valid, default_value
+            otherwise.
+        """
+        value = self.get_setting(key)
+        if value is None:
+            return default_value
+
+        normalized = value.strip().lower()
+        if normalized in _TRUE_VALUES:
+            return True
+        if normalized in _FALSE_VALUES:
+            return False
+
+        LOGGER.warning(
+            'Setting %s is not a boolean: %r; using %r',
+            key, value, default_value)
+        return default_value
+
+    def get_settings(self, prefix='sawtooth.'):
+        """Get every setting whose key starts with the given prefix, reading
+        the settings under the prefix's address in a single pass over state.
+
+        Args:
+            prefix (str, optional): the key prefix. Defaults to 'sawtooth.'.
+
+        Returns:
+            dict of str,str: The values of the settings found, by key.
+        """
+        try:
+            leaves = self._state_view.leaves(
+                SettingsView.setting_address_prefix(prefix))
+        except KeyError:
+            return {}
+
+        settings = {}
+        for state_entry in leaves.values():
+            setting = Setting()
+            setting.ParseFromString(state_entry)
+            for setting_entry in setting.entries:
+                if setting_entry.key.startswith(prefix):
+                    settings[setting_entry.key] = setting_entry.value
+
+        return settings
+
+    @staticmethod
+    def setting_address_prefix(prefix):
+        """Computes the radix address prefix under which every setting whose
+        key starts with the given prefix is stored.
+
+        Only the parts of the prefix ending in a dot are complete, so only
+        those contribute to the address prefix; for example, the settings
+        starting with `a.b.c` are under the address prefix computed from `a`
+        and `b`.
+
+        Args:
+            prefix (str): the key prefix
+        Returns:
+            str: the computed address prefix
+        """
+        complete_parts = prefix.split('.')[:-1][:_MAX_KEY_PARTS - 1]
+        return CONFIG_STATE_NAMESPACE + ''.join(
+            _short_hash(x.encode()) for x in complete_parts)
+
     @staticmethod
     @lru_cache(maxsize=128)
     def setting_address(key):
@@ -15,49 +15,158 @@
  * ------------------------------------------------------------------------------
  */
 
+use std::ops::Range;
+use std::sync::Arc;
+
 use cpython::{self, ObjectProtocol, Python, PythonObject, ToPyObject};
+use protobuf::Message;
 use sawtooth::protos::{FromBytes, IntoBytes};
 use transact::protocol::batch::Batch;
 
+use crate::proto::batch::BatchHeader;
+use crate::proto::transaction::Transaction;
+use crate::proto_view::{read_str, scan_fields, ProtoViewError};
 use crate::py_object_wrapper::PyObjectWrapper;
 
+/// A serialized batch that shares the bytes it was read from, such as those
+/// of its block, and decodes its fields only when they are requested.
+#[derive(Clone, Debug)]
+pub struct BatchView {
+    bytes: Arc<Vec<u8>>,
+    range: Range<usize>,
+    header: Range<usize>,
+    header_signature: Range<usize>,
+    transactions: Vec<Range<usize>>,
+}
+
+#[allow(dead_code)]
+impl BatchView {
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, ProtoViewError> {
+        let len = bytes.len();
+        BatchView::from_shared(Arc::new(bytes), 0..len)
+    }
+
+    /// Reads the batch serialized in `bytes[range]`.
+    pub fn from_shared(bytes: Arc<Vec<u8>>, range: Range<usize>) -> Result<Self, ProtoViewError> {
+        let mut header = 0..0;
+        let mut header_signature = 0..0;
+        let mut transactions = vec![];
+        for field in scan_fields(&bytes, range.clone())? {
+            match field.number {
+                1 => header = field.value,
+                2 => header_signature = field.value,
+                3 => transactions.push(field.value),
+                _ => (),
+            }
+        }
+
+        Ok(BatchView {
+            bytes,
+            range,
+            header,
+            header_signature,
+            transactions,
+        })
+    }
+
+    /// The serialized batch
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes[self.range.clone()]
+    }
+
+    pub fn header_bytes(&self) -> &[u8] {
+        &self.bytes[self.header.clone()]
+    }
+
+    pub fn header_signature(&self) -> Result<&str, ProtoViewError> {
+        read_str(&self.bytes, self.header_signature.clone())
+    }
+
+    pub fn header(&self) -> Result<BatchHeader, ProtoViewError> {
+        Ok(Message::parse_from_bytes(self.header_bytes())?)
+    }
+
+    pub fn transaction_count(&self) -> usize {
+        self.transactions.len()
+    }
+
+    pub fn transaction(&self, index: usize) -> Option<Result<Transaction, ProtoViewError>> {
+        self.transactions.get(index).map(|range| {
+            Message::parse_from_bytes(&self.bytes[range.clone()]).map_err(ProtoViewError::from)
+        })
+    }
+
+    /// Decodes the whole batch.
+    pub fn to_batch(&self) -> Result<Batch, ProtoViewError> {
+        Batch::from_bytes(self.bytes())
+            .map_err(|err| ProtoViewError::ConversionError(err.to_string()))
+    }
+}
+
+impl From<BatchView> for PyObjectWrapper {
+    fn from(batch_view: BatchView) -> Self {
+        PyObjectWrapper::new(parse_py_message(
+            "sawtooth_validator.protobuf.batch_pb2",
+            "Batch",
+            batch_view.bytes(),
+        ))
+    }
+}
+
+impl From<PyObjectWrapper> for BatchView {
+    fn from(py_object_wrapper: PyObjectWrapper) -> Self {
+        BatchView::from_bytes(serialize_py_message(&py_object_wrapper))
+            .expect("Unable to read batch from bytes")
+    }
+}
+
+/// Creates a Python protobuf message of the given class from its bytes.
+pub(crate) fn parse_py_message(module: &str, class: &str, bytes: &[u8]) -> cpython::PyObject {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let message = py
+        .import(module)
+        .unwrap_or_else(|_| panic!("Unable to import {}", module))
+        .call(py, class, cpython::NoArgs, None)
+        .unwrap_or_else(|_| panic!("No {} in {}", class, module));
+    message
+        .call_method(
+            py,
+            "ParseFromString",
+            (cpython::PyBytes::new(py, bytes).into_object(),),
+            None,
+        )
+        .expect("Unable to ParseFromString");
+    message
+}
+
+pub(crate) fn serialize_py_message(py_object_wrapper: &PyObjectWrapper) -> Vec<u8> {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    py_object_wrapper
+        .to_py_object(py)
+        .call_method(py, "SerializeToString", cpython::NoArgs, None)
+        .expect("Unable to serialize PyObject")
+        .extract(py)
+        .expect("Unable to extract bytes from PyObject")
+}
+
 impl From<Batch> for PyObjectWrapper {
     fn from(native_batch: Batch) -> Self {
-        let gil = Python::acquire_gil();
-        let py = gil.python();
-
-        let batch_pb2 = py
-            .import("sawtooth_validator.protobuf.batch_pb2")
-            .expect("Unable to import batch_pb2");
-        let batch = batch_pb2
-            .call(py, "Batch", cpython::NoArgs, None)
-            .expect("No Batch in batch_pb2");
-
-        batch
-            .call_method(
-                py,
-                "ParseFromString",
-                (cpython::PyBytes::new(py, &native_batch.into_bytes().unwrap()).into_object(),),
-                None,
-            )
-            .expect("Unable to ParseFromString");
-        PyObjectWrapper::new(batch)
+        PyObjectWrapper::new(parse_py_message(
+            "sawtooth_validator.protobuf.batch_pb2",
+            "Batch",
+            &native_batch.into_bytes().unwrap(),
+        ))
     }
 }
 
 impl From<PyObjectWrapper> for Batch {
     fn from(py_object_wrapper: PyObjectWrapper) -> Self {
-        let gil = Python::acquire_gil();
-        let py = gil.python();
-        let py_obj = py_object_wrapper.to_py_object(py);
-
-        let bytes: Vec<u8> = py_obj
-            .call_method(py, "SerializeToString", cpython::NoArgs, None)
-            .expect("Unable to serialize PyObject")
-            .extract(py)
-            .expect("Unable to extract bytes from PyObject");
-
-        Batch::from_bytes(&bytes).expect("Unable to parse batch from bytes")
+        Batch::from_bytes(&serialize_py_message(&py_object_wrapper))
+            .expect("Unable to parse batch from bytes")
     }
 }
 
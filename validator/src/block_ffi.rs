@@ -14,51 +14,194 @@
  * limitations under the License.
  * ------------------------------------------------------------------------------
  */
-use crate::py_object_wrapper::PyObjectWrapper;
-use cpython;
-use cpython::{ObjectProtocol, Python, PythonObject, ToPyObject};
+use std::ops::Range;
+use std::sync::Arc;
 
+use protobuf::Message;
 use sawtooth::{
     protocol::block::BlockPair,
     protos::{FromBytes, IntoBytes},
 };
 
-impl From<PyObjectWrapper> for BlockPair {
-    fn from(py_object_wrapper: PyObjectWrapper) -> Self {
-        let gil = Python::acquire_gil();
-        let py = gil.python();
-        let py_obj = py_object_wrapper.to_py_object(py);
+use crate::batch_ffi::{parse_py_message, serialize_py_message, BatchView};
+use crate::proto::block::BlockHeader;
+use crate::proto_view::{read_str, scan_fields, ProtoViewError};
+use crate::py_object_wrapper::PyObjectWrapper;
+
+/// A serialized block, whose bytes are shared by its clones and its batch
+/// views, and whose fields are decoded only when they are requested.
+#[derive(Clone, Debug)]
+pub struct BlockView {
+    bytes: Arc<Vec<u8>>,
+    header: Range<usize>,
+    header_signature: Range<usize>,
+    batches: Vec<Range<usize>>,
+}
+
+#[allow(dead_code)]
+impl BlockView {
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, ProtoViewError> {
+        let mut header = 0..0;
+        let mut header_signature = 0..0;
+        let mut batches = vec![];
+        for field in scan_fields(&bytes, 0..bytes.len())? {
+            match field.number {
+                1 => header = field.value,
+                2 => header_signature = field.value,
+                3 => batches.push(field.value),
+                _ => (),
+            }
+        }
+
+        Ok(BlockView {
+            bytes: Arc::new(bytes),
+            header,
+            header_signature,
+            batches,
+        })
+    }
+
+    pub fn from_block_pair(block: &BlockPair) -> Result<Self, ProtoViewError> {
+        let bytes = block
+            .clone()
+            .into_bytes()
+            .map_err(|err| ProtoViewError::ConversionError(err.to_string()))?;
+        BlockView::from_bytes(bytes)
+    }
 
-        let bytes: Vec<u8> = py_obj
-            .call_method(py, "SerializeToString", cpython::NoArgs, None)
-            .expect("Unable to serialize PyObject")
-            .extract(py)
-            .expect("Unable to extract bytes from PyObject");
+    /// The serialized block
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn header_bytes(&self) -> &[u8] {
+        &self.bytes[self.header.clone()]
+    }
+
+    pub fn header_signature(&self) -> Result<&str, ProtoViewError> {
+        read_str(&self.bytes, self.header_signature.clone())
+    }
+
+    pub fn header(&self) -> Result<BlockHeader, ProtoViewError> {
+        Ok(Message::parse_from_bytes(self.header_bytes())?)
+    }
+
+    pub fn batch_count(&self) -> usize {
+        self.batches.len()
+    }
+
+    /// Views of the block's batches, which share the block's bytes.
+    pub fn batches(&self) -> Result<Vec<BatchView>, ProtoViewError> {
+        self.batches
+            .iter()
+            .map(|range| BatchView::from_shared(self.bytes.clone(), range.clone()))
+            .collect()
+    }
 
-        BlockPair::from_bytes(&bytes).expect("Unable to parse block from bytes")
+    /// Decodes the whole block.
+    pub fn to_block_pair(&self) -> Result<BlockPair, ProtoViewError> {
+        BlockPair::from_bytes(&self.bytes)
+            .map_err(|err| ProtoViewError::ConversionError(err.to_string()))
+    }
+}
+
+impl From<BlockView> for PyObjectWrapper {
+    fn from(block_view: BlockView) -> Self {
+        PyObjectWrapper::new(parse_py_message(
+            "sawtooth_validator.protobuf.block_pb2",
+            "Block",
+            block_view.bytes(),
+        ))
+    }
+}
+
+impl From<PyObjectWrapper> for BlockView {
+    fn from(py_object_wrapper: PyObjectWrapper) -> Self {
+        BlockView::from_bytes(serialize_py_message(&py_object_wrapper))
+            .expect("Unable to read block from bytes")
+    }
+}
+
+impl From<PyObjectWrapper> for BlockPair {
+    fn from(py_object_wrapper: PyObjectWrapper) -> Self {
+        BlockPair::from_bytes(&serialize_py_message(&py_object_wrapper))
+            .expect("Unable to parse block from bytes")
     }
 }
 
 impl From<BlockPair> for PyObjectWrapper {
     fn from(native_block: BlockPair) -> Self {
-        let gil = Python::acquire_gil();
-        let py = gil.python();
-
-        let block_pb2 = py
-            .import("sawtooth_validator.protobuf.block_pb2")
-            .expect("Unable to import block_pb2");
-        let block = block_pb2
-            .call(py, "Block", cpython::NoArgs, None)
-            .expect("Unable to get Block");
-
-        block
-            .call_method(
-                py,
-                "ParseFromString",
-                (cpython::PyBytes::new(py, &native_block.into_bytes().unwrap()).into_object(),),
-                None,
-            )
-            .expect("Unable to ParseFromString");
-        PyObjectWrapper::new(block)
+        PyObjectWrapper::new(parse_py_message(
+            "sawtooth_validator.protobuf.block_pb2",
+            "Block",
+            &native_block.into_bytes().unwrap(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cylinder::{secp256k1::Secp256k1Context, Context, Signer};
+    use sawtooth::journal::NULL_BLOCK_IDENTIFIER;
+    use sawtooth::protocol::block::BlockBuilder;
+    use transact::protocol::{
+        batch::BatchBuilder,
+        transaction::{HashMethod, TransactionBuilder},
+    };
+
+    #[test]
+    fn block_view_decodes_requested_fields() {
+        let context = Secp256k1Context::new();
+        let signer: Box<dyn Signer> = context.new_signer(context.new_random_private_key());
+        let batches = (0..3)
+            .map(|i| {
+                let txn = TransactionBuilder::new()
+                    .with_family_name("test".into())
+                    .with_family_version("1.0".into())
+                    .with_inputs(vec![])
+                    .with_outputs(vec![])
+                    .with_payload_hash_method(HashMethod::SHA512)
+                    .with_payload(vec![i; 1024])
+                    .build(&*signer)
+                    .expect("Failed to build transaction");
+                BatchBuilder::new()
+                    .with_transactions(vec![txn])
+                    .build(&*signer)
+                    .expect("Failed to build batch")
+            })
+            .collect::<Vec<_>>();
+        let block = BlockBuilder::new()
+            .with_block_num(7)
+            .with_previous_block_id(NULL_BLOCK_IDENTIFIER.into())
+            .with_state_root_hash(vec![])
+            .with_batches(batches.clone())
+            .build_pair(&*signer)
+            .expect("Failed to build block pair");
+
+        let view = BlockView::from_block_pair(&block).expect("Unable to read block");
+
+        assert_eq!(
+            block.block().header_signature(),
+            view.header_signature().unwrap()
+        );
+        assert_eq!(7, view.header().unwrap().get_block_num());
+
+        let batch_views = view.batches().unwrap();
+        assert_eq!(batches.len(), batch_views.len());
+        for (batch, batch_view) in batches.iter().zip(&batch_views) {
+            assert_eq!(
+                batch.header_signature(),
+                batch_view.header_signature().unwrap()
+            );
+            assert_eq!(1, batch_view.transaction_count());
+            assert_eq!(batch, &batch_view.to_batch().unwrap());
+        }
+
+        assert_eq!(
+            block.block().header_signature(),
+            view.to_block_pair().unwrap().block().header_signature()
+        );
     }
 }
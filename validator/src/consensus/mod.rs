@@ -16,3 +16,5 @@
  */
 
 pub mod notifier_ffi;
+pub mod registry;
+pub mod registry_ffi;
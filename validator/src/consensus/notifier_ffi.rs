@@ -315,6 +315,22 @@ pub unsafe extern "C" fn consensus_notifier_notify_block_invalid(
     }
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn consensus_notifier_notify_block_commit(
+    notifier: *mut c_void,
+    block_id: *const c_char,
+) -> ErrorCode {
+    check_null!(notifier, block_id);
+
+    match deref_cstr(block_id) {
+        Ok(block_id) => {
+            (*(notifier as *mut BackgroundConsensusNotifier)).notify_block_commit(block_id);
+            ErrorCode::Success
+        }
+        Err(err) => err,
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn consensus_notifier_notify_engine_activated(
     notifier: *mut c_void,
@@ -0,0 +1,425 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+//! The consensus engines registered with the validator, which of them is
+//! active, and whether each still answers the validator's pings.
+//!
+//! The `LivenessMonitor` pings every registered engine periodically. An
+//! engine that misses `max_missed_pings` pings in a row is marked
+//! unresponsive, so that an engine that has died is noticed before the chain
+//! stalls.
+
+use std::error::Error;
+use std::fmt;
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+#[derive(Debug, PartialEq)]
+pub enum RegistryError {
+    EngineNotRegistered,
+    EngineAlreadyActive,
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RegistryError::EngineNotRegistered => write!(f, "Engine not registered"),
+            RegistryError::EngineAlreadyActive => write!(f, "Engine already active"),
+        }
+    }
+}
+
+impl Error for RegistryError {}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct EngineInfo {
+    pub connection_id: String,
+    pub name: String,
+    pub version: String,
+    pub additional_protocols: Vec<(String, String)>,
+}
+
+impl EngineInfo {
+    pub fn handles_protocol(&self, name: &str, version: &str) -> bool {
+        (self.name == name && self.version == version)
+            || self
+                .additional_protocols
+                .iter()
+                .any(|&(ref n, ref v)| n == name && v == version)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct EngineStatus {
+    pub info: EngineInfo,
+    pub active: bool,
+    pub missed_pings: u32,
+    pub responsive: bool,
+}
+
+struct Engine {
+    info: EngineInfo,
+    awaiting_ping: bool,
+    missed_pings: u32,
+}
+
+pub struct ConsensusRegistry {
+    engines: Vec<Engine>,
+    active: Option<EngineInfo>,
+    max_missed_pings: u32,
+}
+
+impl ConsensusRegistry {
+    pub fn new(max_missed_pings: u32) -> Self {
+        gauge!("consensus_unresponsive_engines", 0);
+        ConsensusRegistry {
+            engines: vec![],
+            active: None,
+            max_missed_pings,
+        }
+    }
+
+    pub fn has_active_engine(&self) -> bool {
+        self.active.is_some()
+    }
+
+    /// Registers an engine. An earlier registration of the same engine, or
+    /// of an engine that this one supports all the protocols of, is replaced,
+    /// deactivating it if it was active.
+    pub fn register_engine(&mut self, info: EngineInfo) {
+        self.engines.retain(|engine| {
+            let same = engine.info.name == info.name && engine.info.version == info.version;
+            let replaced = info.handles_protocol(&engine.info.name, &engine.info.version)
+                && engine
+                    .info
+                    .additional_protocols
+                    .iter()
+                    .all(|&(ref n, ref v)| info.handles_protocol(n, v));
+            !same && !replaced
+        });
+
+        let engines = &self.engines;
+        if self
+            .active
+            .as_ref()
+            .map(|active| !engines.iter().any(|engine| engine.info == *active))
+            .unwrap_or(false)
+        {
+            self.active = None;
+        }
+
+        self.engines.push(Engine {
+            info,
+            awaiting_ping: false,
+            missed_pings: 0,
+        });
+        self.update_unresponsive_gauge();
+    }
+
+    /// Activates the first registered engine that supports the protocol,
+    /// deactivating any other active engine.
+    pub fn activate_engine(&mut self, name: &str, version: &str) -> Result<(), RegistryError> {
+        if self
+            .active
+            .as_ref()
+            .map(|active| active.handles_protocol(name, version))
+            .unwrap_or(false)
+        {
+            return Err(RegistryError::EngineAlreadyActive);
+        }
+
+        self.active = self
+            .engines
+            .iter()
+            .find(|engine| engine.info.handles_protocol(name, version))
+            .map(|engine| engine.info.clone());
+
+        if self.active.is_some() {
+            Ok(())
+        } else {
+            Err(RegistryError::EngineNotRegistered)
+        }
+    }
+
+    pub fn deactivate_current_engine(&mut self) {
+        self.active = None;
+    }
+
+    pub fn is_active_engine_id(&self, connection_id: &str) -> bool {
+        self.active
+            .as_ref()
+            .map(|active| active.connection_id == connection_id)
+            .unwrap_or(false)
+    }
+
+    pub fn is_active_engine_name_version(&self, name: &str, version: &str) -> bool {
+        self.active
+            .as_ref()
+            .map(|active| active.name == name && active.version == version)
+            .unwrap_or(false)
+    }
+
+    /// Records a ping sent to every registered engine, returning the ids of
+    /// the connections to send them on. A ping still unanswered from the
+    /// previous call counts as missed.
+    pub fn ping_engines(&mut self) -> Vec<String> {
+        let max_missed_pings = self.max_missed_pings;
+        let connection_ids = self
+            .engines
+            .iter_mut()
+            .map(|engine| {
+                if engine.awaiting_ping {
+                    engine.missed_pings = engine.missed_pings.saturating_add(1);
+                    if engine.missed_pings == max_missed_pings {
+                        warn!(
+                            "Consensus engine {} {} has missed {} pings in a row and is \
+                             unresponsive",
+                            engine.info.name, engine.info.version, engine.missed_pings
+                        );
+                    }
+                }
+                engine.awaiting_ping = true;
+                engine.info.connection_id.clone()
+            })
+            .collect();
+
+        self.update_unresponsive_gauge();
+        connection_ids
+    }
+
+    pub fn ping_answered(&mut self, connection_id: &str) {
+        let max_missed_pings = self.max_missed_pings;
+        if let Some(engine) = self
+            .engines
+            .iter_mut()
+            .find(|engine| engine.info.connection_id == connection_id)
+        {
+            if engine.missed_pings >= max_missed_pings {
+                info!(
+                    "Consensus engine {} {} is responsive again",
+                    engine.info.name, engine.info.version
+                );
+            }
+            engine.awaiting_ping = false;
+            engine.missed_pings = 0;
+        }
+
+        self.update_unresponsive_gauge();
+    }
+
+    pub fn engine_statuses(&self) -> Vec<EngineStatus> {
+        self.engines
+            .iter()
+            .map(|engine| EngineStatus {
+                info: engine.info.clone(),
+                active: self.active.as_ref() == Some(&engine.info),
+                missed_pings: engine.missed_pings,
+                responsive: engine.missed_pings < self.max_missed_pings,
+            })
+            .collect()
+    }
+
+    fn update_unresponsive_gauge(&self) {
+        let unresponsive = self
+            .engines
+            .iter()
+            .filter(|engine| engine.missed_pings >= self.max_missed_pings)
+            .count();
+        gauge!("consensus_unresponsive_engines", unresponsive as i64);
+    }
+}
+
+#[derive(Debug)]
+pub struct PingError(pub String);
+
+pub trait PingService: Send {
+    /// Sends a ping to the engine on the connection. The answer is recorded
+    /// with `ConsensusRegistry::ping_answered`.
+    fn ping(&self, connection_id: &str) -> Result<(), PingError>;
+}
+
+/// Pings the registered engines every `interval` until stopped or dropped.
+pub struct LivenessMonitor {
+    exit: Option<Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl LivenessMonitor {
+    pub fn start<S: PingService + 'static>(
+        registry: Arc<Mutex<ConsensusRegistry>>,
+        service: S,
+        interval: Duration,
+    ) -> Self {
+        let (exit, exit_receiver) = channel();
+
+        let handle = thread::Builder::new()
+            .name("ConsensusLivenessMonitor".into())
+            .spawn(move || {
+                while let Err(RecvTimeoutError::Timeout) = exit_receiver.recv_timeout(interval) {
+                    let connection_ids = registry
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .ping_engines();
+
+                    for connection_id in connection_ids {
+                        if let Err(PingError(err)) = service.ping(&connection_id) {
+                            warn!("Unable to ping consensus engine: {}", err);
+                        }
+                    }
+                }
+            })
+            .expect("Unable to start consensus liveness monitor thread");
+
+        LivenessMonitor {
+            exit: Some(exit),
+            handle: Some(handle),
+        }
+    }
+
+    pub fn stop(&mut self) {
+        // Dropping the sender wakes the thread to exit
+        self.exit.take();
+        if let Some(handle) = self.handle.take() {
+            if handle.join().is_err() {
+                error!("Consensus liveness monitor thread panicked");
+            }
+        }
+    }
+}
+
+impl Drop for LivenessMonitor {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engine(connection_id: &str, name: &str, protocols: &[(&str, &str)]) -> EngineInfo {
+        EngineInfo {
+            connection_id: connection_id.into(),
+            name: name.into(),
+            version: "1.0".into(),
+            additional_protocols: protocols
+                .iter()
+                .map(|&(n, v)| (n.to_string(), v.to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn engines_are_registered_and_activated() {
+        let mut registry = ConsensusRegistry::new(3);
+        assert!(!registry.has_active_engine());
+        assert_eq!(
+            Err(RegistryError::EngineNotRegistered),
+            registry.activate_engine("pbft", "1.0")
+        );
+
+        registry.register_engine(engine("id0", "pbft", &[("alt", "1.0")]));
+        assert_eq!(Ok(()), registry.activate_engine("alt", "1.0"));
+        assert!(registry.is_active_engine_id("id0"));
+        assert!(registry.is_active_engine_name_version("pbft", "1.0"));
+        assert_eq!(
+            Err(RegistryError::EngineAlreadyActive),
+            registry.activate_engine("pbft", "1.0")
+        );
+
+        // Registering the same engine again replaces and deactivates it
+        registry.register_engine(engine("id1", "pbft", &[("alt", "1.0")]));
+        assert!(!registry.has_active_engine());
+        assert_eq!(Ok(()), registry.activate_engine("pbft", "1.0"));
+        assert!(registry.is_active_engine_id("id1"));
+
+        // An engine supporting all of pbft's protocols replaces it
+        registry.register_engine(engine("id2", "pbft2", &[("pbft", "1.0"), ("alt", "1.0")]));
+        assert!(!registry.has_active_engine());
+        assert_eq!(Ok(()), registry.activate_engine("pbft", "1.0"));
+        assert!(registry.is_active_engine_name_version("pbft2", "1.0"));
+
+        // An engine supporting only some of them does not
+        registry.register_engine(engine("id3", "other", &[("alt", "1.0")]));
+        assert!(registry.is_active_engine_id("id2"));
+        assert_eq!(2, registry.engine_statuses().len());
+
+        registry.deactivate_current_engine();
+        assert!(!registry.has_active_engine());
+    }
+
+    #[test]
+    fn engines_missing_pings_are_unresponsive() {
+        let mut registry = ConsensusRegistry::new(2);
+        registry.register_engine(engine("id0", "pbft", &[]));
+        registry.register_engine(engine("id1", "poet", &[]));
+        registry.activate_engine("pbft", "1.0").unwrap();
+
+        assert_eq!(vec!["id0", "id1"], registry.ping_engines());
+        registry.ping_answered("id1");
+        registry.ping_engines();
+        registry.ping_answered("id1");
+        registry.ping_engines();
+
+        let statuses = registry.engine_statuses();
+        assert!(statuses[0].active);
+        assert_eq!(2, statuses[0].missed_pings);
+        assert!(!statuses[0].responsive);
+        assert!(!statuses[1].active);
+        assert_eq!(0, statuses[1].missed_pings);
+        assert!(statuses[1].responsive);
+
+        // An answer makes the engine responsive again
+        registry.ping_answered("id0");
+        let statuses = registry.engine_statuses();
+        assert_eq!(0, statuses[0].missed_pings);
+        assert!(statuses[0].responsive);
+    }
+
+    struct MockPingService {
+        pinged: Sender<String>,
+    }
+
+    impl PingService for MockPingService {
+        fn ping(&self, connection_id: &str) -> Result<(), PingError> {
+            self.pinged
+                .send(connection_id.into())
+                .map_err(|err| PingError(err.to_string()))
+        }
+    }
+
+    #[test]
+    fn monitor_pings_engines() {
+        let registry = Arc::new(Mutex::new(ConsensusRegistry::new(3)));
+        registry
+            .lock()
+            .unwrap()
+            .register_engine(engine("id0", "pbft", &[]));
+
+        let (pinged, pings) = channel();
+        let mut monitor = LivenessMonitor::start(
+            registry.clone(),
+            MockPingService { pinged },
+            Duration::from_millis(1),
+        );
+
+        assert_eq!("id0", pings.recv_timeout(Duration::from_secs(5)).unwrap());
+        monitor.stop();
+    }
+}
@@ -0,0 +1,333 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+use std::ffi::CStr;
+use std::mem;
+use std::os::raw::{c_char, c_void};
+use std::slice;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+use cpython::{ObjectProtocol, PyObject, Python};
+use protobuf::{Message, RepeatedField};
+use py_ffi;
+
+use consensus::registry::{
+    ConsensusRegistry, EngineInfo, LivenessMonitor, PingError, PingService, RegistryError,
+};
+use proto::admin::{
+    AdminConsensusEnginesGetResponse, AdminConsensusEnginesGetResponse_Engine,
+    AdminConsensusEnginesGetResponse_Engine_Protocol,
+};
+use proto::consensus::ConsensusRegisterRequest;
+use pylogger;
+
+type SharedRegistry = Arc<Mutex<ConsensusRegistry>>;
+
+/// A `PingService` backed by a Python object providing `ping`.
+pub struct PyPingService {
+    py_ping_service: PyObject,
+}
+
+impl PingService for PyPingService {
+    fn ping(&self, connection_id: &str) -> Result<(), PingError> {
+        let gil_guard = Python::acquire_gil();
+        let py = gil_guard.python();
+
+        self.py_ping_service
+            .call_method(py, "ping", (connection_id,), None)
+            .map(|_| ())
+            .map_err(|py_err| {
+                pylogger::exception(py, "Unable to ping consensus engine", py_err);
+                PingError("FFI error pinging consensus engine".into())
+            })
+    }
+}
+
+#[repr(u32)]
+#[derive(Debug)]
+pub enum ErrorCode {
+    Success = 0,
+
+    // Input errors
+    NullPointerProvided = 0x01,
+    InvalidArgument = 0x02,
+
+    EngineNotRegistered = 0x03,
+    EngineAlreadyActive = 0x04,
+    Error = 0x05,
+}
+
+macro_rules! check_null {
+    ($($arg:expr) , *) => {
+        $(if $arg.is_null() { return ErrorCode::NullPointerProvided; })*
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn consensus_registry_new(
+    max_missed_pings: u32,
+    registry_ptr: *mut *const c_void,
+) -> ErrorCode {
+    check_null!(registry_ptr);
+
+    let registry: SharedRegistry = Arc::new(Mutex::new(ConsensusRegistry::new(max_missed_pings)));
+    *registry_ptr = Box::into_raw(Box::new(registry)) as *const c_void;
+
+    ErrorCode::Success
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn consensus_registry_drop(registry: *mut c_void) -> ErrorCode {
+    check_null!(registry);
+    Box::from_raw(registry as *mut SharedRegistry);
+    ErrorCode::Success
+}
+
+/// Registers the engine of a ConsensusRegisterRequest received on the
+/// connection.
+#[no_mangle]
+pub unsafe extern "C" fn consensus_registry_register_engine(
+    registry: *mut c_void,
+    connection_id: *const c_char,
+    request_bytes: *const u8,
+    request_bytes_len: usize,
+) -> ErrorCode {
+    check_null!(registry, connection_id, request_bytes);
+
+    let connection_id = match deref_cstr(connection_id) {
+        Ok(s) => s,
+        Err(err) => return err,
+    };
+    let mut request: ConsensusRegisterRequest =
+        match Message::parse_from_bytes(slice::from_raw_parts(request_bytes, request_bytes_len)) {
+            Ok(request) => request,
+            Err(err) => {
+                error!("Failed to parse ConsensusRegisterRequest: {:?}", err);
+                return ErrorCode::InvalidArgument;
+            }
+        };
+
+    lock(registry).register_engine(EngineInfo {
+        connection_id: connection_id.into(),
+        name: request.take_name(),
+        version: request.take_version(),
+        additional_protocols: request
+            .take_additional_protocols()
+            .into_iter()
+            .map(|mut protocol| (protocol.take_name(), protocol.take_version()))
+            .collect(),
+    });
+
+    ErrorCode::Success
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn consensus_registry_activate_engine(
+    registry: *mut c_void,
+    name: *const c_char,
+    version: *const c_char,
+) -> ErrorCode {
+    check_null!(registry, name, version);
+
+    let (name, version) = match (deref_cstr(name), deref_cstr(version)) {
+        (Ok(name), Ok(version)) => (name, version),
+        (Err(err), _) | (_, Err(err)) => return err,
+    };
+
+    match lock(registry).activate_engine(name, version) {
+        Ok(()) => ErrorCode::Success,
+        Err(RegistryError::EngineNotRegistered) => ErrorCode::EngineNotRegistered,
+        Err(RegistryError::EngineAlreadyActive) => ErrorCode::EngineAlreadyActive,
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn consensus_registry_deactivate_current_engine(
+    registry: *mut c_void,
+) -> ErrorCode {
+    check_null!(registry);
+    lock(registry).deactivate_current_engine();
+    ErrorCode::Success
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn consensus_registry_has_active_engine(
+    registry: *mut c_void,
+    has_active_engine: *mut bool,
+) -> ErrorCode {
+    check_null!(registry, has_active_engine);
+    *has_active_engine = lock(registry).has_active_engine();
+    ErrorCode::Success
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn consensus_registry_is_active_engine_id(
+    registry: *mut c_void,
+    connection_id: *const c_char,
+    is_active: *mut bool,
+) -> ErrorCode {
+    check_null!(registry, connection_id, is_active);
+
+    let connection_id = match deref_cstr(connection_id) {
+        Ok(s) => s,
+        Err(err) => return err,
+    };
+    *is_active = lock(registry).is_active_engine_id(connection_id);
+
+    ErrorCode::Success
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn consensus_registry_is_active_engine_name_version(
+    registry: *mut c_void,
+    name: *const c_char,
+    version: *const c_char,
+    is_active: *mut bool,
+) -> ErrorCode {
+    check_null!(registry, name, version, is_active);
+
+    let (name, version) = match (deref_cstr(name), deref_cstr(version)) {
+        (Ok(name), Ok(version)) => (name, version),
+        (Err(err), _) | (_, Err(err)) => return err,
+    };
+    *is_active = lock(registry).is_active_engine_name_version(name, version);
+
+    ErrorCode::Success
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn consensus_registry_ping_answered(
+    registry: *mut c_void,
+    connection_id: *const c_char,
+) -> ErrorCode {
+    check_null!(registry, connection_id);
+
+    let connection_id = match deref_cstr(connection_id) {
+        Ok(s) => s,
+        Err(err) => return err,
+    };
+    lock(registry).ping_answered(connection_id);
+
+    ErrorCode::Success
+}
+
+/// Returns the registered engines as an AdminConsensusEnginesGetResponse.
+#[no_mangle]
+pub unsafe extern "C" fn consensus_registry_get_engines(
+    registry: *mut c_void,
+    response_ptr: *mut *const u8,
+    response_len: *mut usize,
+    response_cap: *mut usize,
+) -> ErrorCode {
+    check_null!(registry);
+
+    let engines = lock(registry)
+        .engine_statuses()
+        .into_iter()
+        .map(|status| {
+            let mut engine = AdminConsensusEnginesGetResponse_Engine::new();
+            engine.set_connection_id(status.info.connection_id);
+            engine.set_name(status.info.name);
+            engine.set_version(status.info.version);
+            engine.set_additional_protocols(RepeatedField::from_vec(
+                status
+                    .info
+                    .additional_protocols
+                    .into_iter()
+                    .map(|(name, version)| {
+                        let mut protocol = AdminConsensusEnginesGetResponse_Engine_Protocol::new();
+                        protocol.set_name(name);
+                        protocol.set_version(version);
+                        protocol
+                    })
+                    .collect(),
+            ));
+            engine.set_active(status.active);
+            engine.set_missed_pings(status.missed_pings);
+            engine.set_responsive(status.responsive);
+            engine
+        })
+        .collect();
+
+    let mut response = AdminConsensusEnginesGetResponse::new();
+    response.set_engines(RepeatedField::from_vec(engines));
+
+    let payload = match response.write_to_bytes() {
+        Ok(payload) => payload,
+        Err(err) => {
+            error!(
+                "Failed to serialize AdminConsensusEnginesGetResponse: {:?}",
+                err
+            );
+            return ErrorCode::Error;
+        }
+    };
+
+    *response_cap = payload.capacity();
+    *response_len = payload.len();
+    *response_ptr = payload.as_slice().as_ptr();
+
+    mem::forget(payload);
+
+    ErrorCode::Success
+}
+
+/// Starts pinging the registered engines every `interval_secs` through the
+/// Python object's `ping`, until the monitor is dropped.
+#[no_mangle]
+pub unsafe extern "C" fn consensus_liveness_monitor_new(
+    registry: *mut c_void,
+    py_ping_service_ptr: *mut py_ffi::PyObject,
+    interval_secs: u64,
+    monitor_ptr: *mut *const c_void,
+) -> ErrorCode {
+    check_null!(registry, py_ping_service_ptr, monitor_ptr);
+
+    let py = Python::assume_gil_acquired();
+    let py_ping_service = PyObject::from_borrowed_ptr(py, py_ping_service_ptr);
+
+    let monitor = LivenessMonitor::start(
+        (*(registry as *mut SharedRegistry)).clone(),
+        PyPingService { py_ping_service },
+        Duration::from_secs(interval_secs),
+    );
+    *monitor_ptr = Box::into_raw(Box::new(monitor)) as *const c_void;
+
+    ErrorCode::Success
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn consensus_liveness_monitor_drop(monitor: *mut c_void) -> ErrorCode {
+    check_null!(monitor);
+    Box::from_raw(monitor as *mut LivenessMonitor);
+    ErrorCode::Success
+}
+
+/// Locks the registry. It holds no invariant that a panic while locked could
+/// break, so a poisoned lock is still used.
+unsafe fn lock<'a>(registry: *mut c_void) -> MutexGuard<'a, ConsensusRegistry> {
+    (*(registry as *mut SharedRegistry))
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+}
+
+unsafe fn deref_cstr<'a>(cstr: *const c_char) -> Result<&'a str, ErrorCode> {
+    CStr::from_ptr(cstr)
+        .to_str()
+        .map_err(|_| ErrorCode::InvalidArgument)
+}
@@ -0,0 +1,340 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+use std::ffi::CStr;
+use std::mem;
+use std::os::raw::{c_char, c_void};
+use std::slice;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use protobuf::{Message, RepeatedField};
+
+use events::extractor::extract_events;
+use events::flow_control::{FlowControl, FlowControlError};
+use events::subscription::{EventError, EventSubscription};
+use proto::block::Block;
+use proto::client_event::ClientEventsSubscribeRequest;
+use proto::events::EventList;
+use proto::transaction_receipt::TransactionReceiptList;
+
+#[repr(u32)]
+#[derive(Debug)]
+pub enum ErrorCode {
+    Success = 0,
+    NullPointerProvided = 0x01,
+    InvalidArgument = 0x02,
+    InvalidFilter = 0x03,
+    UnknownSubscriber = 0x04,
+    NotFound = 0x05,
+}
+
+macro_rules! check_null {
+    ($($arg:expr) , *) => {
+        $(if $arg.is_null() { return ErrorCode::NullPointerProvided; })*
+    }
+}
+
+/// Extracts the events of a committed block that match the subscriptions of
+/// a ClientEventsSubscribeRequest, returned as a serialized EventList.
+#[no_mangle]
+pub unsafe extern "C" fn events_extract(
+    block_bytes: *const u8,
+    block_bytes_len: usize,
+    receipts_bytes: *const u8,
+    receipts_bytes_len: usize,
+    subscriptions_bytes: *const u8,
+    subscriptions_bytes_len: usize,
+    events_ptr: *mut *const u8,
+    events_len: *mut usize,
+    events_cap: *mut usize,
+) -> ErrorCode {
+    check_null!(block_bytes, receipts_bytes, subscriptions_bytes);
+
+    let block: Block =
+        match Message::parse_from_bytes(slice::from_raw_parts(block_bytes, block_bytes_len)) {
+            Ok(block) => block,
+            Err(err) => {
+                error!("Failed to parse Block: {:?}", err);
+                return ErrorCode::InvalidArgument;
+            }
+        };
+    let receipts: TransactionReceiptList = match Message::parse_from_bytes(slice::from_raw_parts(
+        receipts_bytes,
+        receipts_bytes_len,
+    )) {
+        Ok(receipts) => receipts,
+        Err(err) => {
+            error!("Failed to parse TransactionReceiptList: {:?}", err);
+            return ErrorCode::InvalidArgument;
+        }
+    };
+    let subscriptions = match parse_subscriptions(subscriptions_bytes, subscriptions_bytes_len) {
+        Ok(subscriptions) => subscriptions,
+        Err(err) => return err,
+    };
+
+    let events = match extract_events(&block, receipts.get_receipts(), &subscriptions) {
+        Ok(events) => events,
+        Err(err) => {
+            error!("Failed to extract events: {}", err);
+            return ErrorCode::InvalidArgument;
+        }
+    };
+
+    let mut event_list = EventList::new();
+    event_list.set_events(RepeatedField::from_vec(events));
+    match event_list.write_to_bytes() {
+        Ok(payload) => return_vec(payload, events_ptr, events_len, events_cap),
+        Err(err) => {
+            error!("Failed to serialize EventList: {:?}", err);
+            ErrorCode::InvalidArgument
+        }
+    }
+}
+
+/// Checks the filters of the subscriptions of a ClientEventsSubscribeRequest.
+/// If one is invalid, returns InvalidFilter and a message describing it.
+#[no_mangle]
+pub unsafe extern "C" fn events_validate_subscriptions(
+    subscriptions_bytes: *const u8,
+    subscriptions_bytes_len: usize,
+    msg_ptr: *mut *const u8,
+    msg_len: *mut usize,
+    msg_cap: *mut usize,
+) -> ErrorCode {
+    check_null!(subscriptions_bytes);
+
+    let request: ClientEventsSubscribeRequest = match Message::parse_from_bytes(
+        slice::from_raw_parts(subscriptions_bytes, subscriptions_bytes_len),
+    ) {
+        Ok(request) => request,
+        Err(err) => {
+            error!("Failed to parse ClientEventsSubscribeRequest: {:?}", err);
+            return ErrorCode::InvalidArgument;
+        }
+    };
+
+    for subscription in request.get_subscriptions() {
+        if let Err(err) = EventSubscription::from_proto(subscription) {
+            return_vec(err.to_string().into_bytes(), msg_ptr, msg_len, msg_cap);
+            return ErrorCode::InvalidFilter;
+        }
+    }
+
+    ErrorCode::Success
+}
+
+type SharedFlowControl = Mutex<FlowControl>;
+
+#[no_mangle]
+pub unsafe extern "C" fn events_flow_control_new(
+    max_outstanding: usize,
+    chunk_size: u64,
+    ack_timeout_secs: u64,
+    flow_control_ptr: *mut *const c_void,
+) -> ErrorCode {
+    check_null!(flow_control_ptr);
+
+    let flow_control: SharedFlowControl = Mutex::new(FlowControl::new(
+        max_outstanding,
+        chunk_size,
+        Duration::from_secs(ack_timeout_secs),
+    ));
+
+    *flow_control_ptr = Box::into_raw(Box::new(flow_control)) as *const c_void;
+
+    ErrorCode::Success
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn events_flow_control_drop(flow_control: *mut c_void) -> ErrorCode {
+    check_null!(flow_control);
+    Box::from_raw(flow_control as *mut SharedFlowControl);
+    ErrorCode::Success
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn events_flow_control_add_subscriber(
+    flow_control: *mut c_void,
+    subscriber_id: *const c_char,
+) -> ErrorCode {
+    with_subscriber(flow_control, subscriber_id, |flow_control, id| {
+        flow_control.add_subscriber(id);
+        Ok(ErrorCode::Success)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn events_flow_control_remove_subscriber(
+    flow_control: *mut c_void,
+    subscriber_id: *const c_char,
+) -> ErrorCode {
+    with_subscriber(flow_control, subscriber_id, |flow_control, id| {
+        flow_control.remove_subscriber(id);
+        Ok(ErrorCode::Success)
+    })
+}
+
+/// Returns Success if a message may be sent to the subscriber now, and
+/// NotFound if the blocks must be queued with push_blocks instead.
+#[no_mangle]
+pub unsafe extern "C" fn events_flow_control_try_send(
+    flow_control: *mut c_void,
+    subscriber_id: *const c_char,
+) -> ErrorCode {
+    with_subscriber(flow_control, subscriber_id, |flow_control, id| {
+        Ok(if flow_control.try_send(id, Instant::now())? {
+            ErrorCode::Success
+        } else {
+            ErrorCode::NotFound
+        })
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn events_flow_control_push_blocks(
+    flow_control: *mut c_void,
+    subscriber_id: *const c_char,
+    first: u64,
+    last: u64,
+) -> ErrorCode {
+    with_subscriber(flow_control, subscriber_id, |flow_control, id| {
+        flow_control.push_blocks(id, first, last)?;
+        Ok(ErrorCode::Success)
+    })
+}
+
+/// Returns the block numbers of the next chunk to send, or NotFound if there
+/// is none or the subscriber's window is full.
+#[no_mangle]
+pub unsafe extern "C" fn events_flow_control_next_chunk(
+    flow_control: *mut c_void,
+    subscriber_id: *const c_char,
+    first: *mut u64,
+    last: *mut u64,
+) -> ErrorCode {
+    check_null!(first, last);
+
+    with_subscriber(
+        flow_control,
+        subscriber_id,
+        |flow_control, id| match flow_control.next_chunk(id, Instant::now())? {
+            Some((chunk_first, chunk_last)) => {
+                *first = chunk_first;
+                *last = chunk_last;
+                Ok(ErrorCode::Success)
+            }
+            None => Ok(ErrorCode::NotFound),
+        },
+    )
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn events_flow_control_ack(
+    flow_control: *mut c_void,
+    subscriber_id: *const c_char,
+) -> ErrorCode {
+    with_subscriber(flow_control, subscriber_id, |flow_control, id| {
+        flow_control.ack(id)?;
+        Ok(ErrorCode::Success)
+    })
+}
+
+/// Removes and returns a subscriber that stopped acknowledging events, or
+/// returns NotFound.
+#[no_mangle]
+pub unsafe extern "C" fn events_flow_control_next_expired(
+    flow_control: *mut c_void,
+    id_ptr: *mut *const u8,
+    id_len: *mut usize,
+    id_cap: *mut usize,
+) -> ErrorCode {
+    check_null!(flow_control);
+
+    let expired = (*(flow_control as *mut SharedFlowControl))
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .next_expired(Instant::now());
+    match expired {
+        Some(id) => return_vec(id.into_bytes(), id_ptr, id_len, id_cap),
+        None => ErrorCode::NotFound,
+    }
+}
+
+unsafe fn with_subscriber<F>(
+    flow_control: *mut c_void,
+    subscriber_id: *const c_char,
+    f: F,
+) -> ErrorCode
+where
+    F: FnOnce(&mut FlowControl, &str) -> Result<ErrorCode, FlowControlError>,
+{
+    check_null!(flow_control, subscriber_id);
+
+    let id = match CStr::from_ptr(subscriber_id).to_str() {
+        Ok(id) => id,
+        Err(_) => return ErrorCode::InvalidArgument,
+    };
+
+    let mut flow_control = (*(flow_control as *mut SharedFlowControl))
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    match f(&mut flow_control, id) {
+        Ok(code) => code,
+        Err(FlowControlError::UnknownSubscriber(_)) => ErrorCode::UnknownSubscriber,
+    }
+}
+
+unsafe fn parse_subscriptions(
+    subscriptions_bytes: *const u8,
+    subscriptions_bytes_len: usize,
+) -> Result<Vec<EventSubscription>, ErrorCode> {
+    let request: ClientEventsSubscribeRequest = Message::parse_from_bytes(slice::from_raw_parts(
+        subscriptions_bytes,
+        subscriptions_bytes_len,
+    ))
+    .map_err(|err| {
+        error!("Failed to parse ClientEventsSubscribeRequest: {:?}", err);
+        ErrorCode::InvalidArgument
+    })?;
+
+    request
+        .get_subscriptions()
+        .iter()
+        .map(EventSubscription::from_proto)
+        .collect::<Result<_, _>>()
+        .map_err(|err| match err {
+            EventError::InvalidFilter(_) => ErrorCode::InvalidFilter,
+            _ => ErrorCode::InvalidArgument,
+        })
+}
+
+unsafe fn return_vec(
+    payload: Vec<u8>,
+    vec_ptr: *mut *const u8,
+    vec_len: *mut usize,
+    vec_cap: *mut usize,
+) -> ErrorCode {
+    *vec_cap = payload.capacity();
+    *vec_len = payload.len();
+    *vec_ptr = payload.as_slice().as_ptr();
+
+    mem::forget(payload);
+
+    ErrorCode::Success
+}
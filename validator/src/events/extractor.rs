@@ -0,0 +1,277 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+use std::collections::HashSet;
+
+use protobuf::{Message, RepeatedField};
+
+use events::subscription::{EventError, EventSubscription};
+use proto::block::{Block, BlockHeader};
+use proto::events::{Event, Event_Attribute};
+use proto::transaction_receipt::{StateChangeList, TransactionReceipt};
+
+pub const BLOCK_COMMIT: &str = "sawtooth/block-commit";
+pub const STATE_DELTA: &str = "sawtooth/state-delta";
+
+/// Returns the events of a committed block that match any of the
+/// subscriptions: its block commit event, the events fired by its
+/// transactions, and a state delta event with the last change made at each
+/// address.
+///
+/// `receipts` are the receipts of the block's transactions, in the order of
+/// the block's transactions.
+pub fn extract_events(
+    block: &Block,
+    receipts: &[TransactionReceipt],
+    subscriptions: &[EventSubscription],
+) -> Result<Vec<Event>, EventError> {
+    let mut events = vec![];
+    if subscriptions.is_empty() {
+        return Ok(events);
+    }
+
+    let subscribed = |event_type: &str| {
+        subscriptions
+            .iter()
+            .any(|subscription| subscription.event_type == event_type)
+    };
+    let matches = |event: &Event| {
+        subscriptions
+            .iter()
+            .any(|subscription| subscription.matches(event))
+    };
+
+    if subscribed(BLOCK_COMMIT) {
+        let block_commit = block_commit_event(block)?;
+        if matches(&block_commit) {
+            events.push(block_commit);
+        }
+    }
+
+    events.extend(
+        receipts
+            .iter()
+            .flat_map(|receipt| receipt.get_events().iter())
+            .filter(|event| matches(event))
+            .cloned(),
+    );
+
+    if subscribed(STATE_DELTA) {
+        let state_delta = state_delta_event(receipts)?;
+        if matches(&state_delta) {
+            events.push(state_delta);
+        }
+    }
+
+    Ok(events)
+}
+
+fn block_commit_event(block: &Block) -> Result<Event, EventError> {
+    let header: BlockHeader = Message::parse_from_bytes(block.get_header())?;
+
+    let mut event = Event::new();
+    event.set_event_type(BLOCK_COMMIT.into());
+    event.set_attributes(RepeatedField::from_vec(vec![
+        attribute("block_id", block.get_header_signature()),
+        attribute("block_num", &header.get_block_num().to_string()),
+        attribute("state_root_hash", header.get_state_root_hash()),
+        attribute("previous_block_id", header.get_previous_block_id()),
+    ]));
+    Ok(event)
+}
+
+fn state_delta_event(receipts: &[TransactionReceipt]) -> Result<Event, EventError> {
+    let mut addresses = HashSet::new();
+    let mut attributes = vec![];
+    let mut changes = vec![];
+    for change in receipts
+        .iter()
+        .rev()
+        .flat_map(|receipt| receipt.get_state_changes().iter().rev())
+    {
+        if addresses.insert(change.get_address()) {
+            attributes.push(attribute("address", change.get_address()));
+            changes.push(change.clone());
+        }
+    }
+
+    let mut change_list = StateChangeList::new();
+    change_list.set_state_changes(RepeatedField::from_vec(changes));
+
+    let mut event = Event::new();
+    event.set_event_type(STATE_DELTA.into());
+    event.set_attributes(RepeatedField::from_vec(attributes));
+    event.set_data(change_list.write_to_bytes()?);
+    Ok(event)
+}
+
+fn attribute(key: &str, value: &str) -> Event_Attribute {
+    let mut attribute = Event_Attribute::new();
+    attribute.set_key(key.into());
+    attribute.set_value(value.into());
+    attribute
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use proto::events::{EventFilter as EventFilterProto, EventFilter_FilterType};
+    use proto::transaction_receipt::{StateChange, StateChange_Type};
+
+    use events::subscription::EventFilter;
+
+    #[test]
+    fn block_commit_event_is_extracted() {
+        let block = make_block();
+
+        assert!(extract_events(&block, &[], &[])
+            .expect("Unable to extract events")
+            .is_empty());
+
+        let events = extract_events(&block, &[], &[subscription(BLOCK_COMMIT, None)])
+            .expect("Unable to extract events");
+        assert_eq!(1, events.len());
+        assert_eq!(BLOCK_COMMIT, events[0].get_event_type());
+        assert_eq!(
+            vec![
+                ("block_id", "abcdef1234567890"),
+                ("block_num", "85"),
+                ("state_root_hash", "0987654321fedcba"),
+                ("previous_block_id", "0000000000000000"),
+            ],
+            events[0]
+                .get_attributes()
+                .iter()
+                .map(|attribute| (attribute.get_key(), attribute.get_value()))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn receipt_events_are_filtered() {
+        let receipts = vec![
+            make_receipt(&[("address", "a1")], &[]),
+            make_receipt(&[("address", "b1")], &[]),
+        ];
+
+        let events = extract_events(
+            &make_block(),
+            &receipts,
+            &[
+                subscription("test", Some("^a")),
+                // Matches the same event, which is only extracted once
+                subscription("test", Some("1$")),
+            ],
+        )
+        .expect("Unable to extract events");
+
+        assert_eq!(2, events.len());
+        assert_eq!(receipts[0].get_events()[0], events[0]);
+        assert_eq!(receipts[1].get_events()[0], events[1]);
+    }
+
+    #[test]
+    fn state_delta_keeps_last_change_per_address() {
+        let receipts = vec![
+            make_receipt(&[], &[("a1", b"first"), ("b1", b"only")]),
+            make_receipt(&[], &[("a1", b"second")]),
+        ];
+
+        let events = extract_events(
+            &make_block(),
+            &receipts,
+            &[subscription(STATE_DELTA, Some("^a"))],
+        )
+        .expect("Unable to extract events");
+        assert_eq!(1, events.len());
+
+        let changes: StateChangeList = Message::parse_from_bytes(events[0].get_data()).unwrap();
+        assert_eq!(
+            vec![("a1", &b"second"[..]), ("b1", &b"only"[..])],
+            changes
+                .get_state_changes()
+                .iter()
+                .map(|change| (change.get_address(), change.get_value()))
+                .collect::<Vec<_>>()
+        );
+
+        assert!(extract_events(
+            &make_block(),
+            &receipts,
+            &[subscription(STATE_DELTA, Some("^c"))]
+        )
+        .expect("Unable to extract events")
+        .is_empty());
+    }
+
+    fn subscription(event_type: &str, address_regex: Option<&str>) -> EventSubscription {
+        EventSubscription {
+            event_type: event_type.into(),
+            filters: address_regex
+                .map(|regex| {
+                    let mut filter = EventFilterProto::new();
+                    filter.set_key("address".into());
+                    filter.set_match_string(regex.into());
+                    filter.set_filter_type(EventFilter_FilterType::REGEX_ANY);
+                    vec![EventFilter::from_proto(&filter).unwrap()]
+                })
+                .unwrap_or_default(),
+        }
+    }
+
+    fn make_block() -> Block {
+        let mut header = BlockHeader::new();
+        header.set_block_num(85);
+        header.set_state_root_hash("0987654321fedcba".into());
+        header.set_previous_block_id("0000000000000000".into());
+
+        let mut block = Block::new();
+        block.set_header_signature("abcdef1234567890".into());
+        block.set_header(header.write_to_bytes().unwrap());
+        block
+    }
+
+    fn make_receipt(attributes: &[(&str, &str)], changes: &[(&str, &[u8])]) -> TransactionReceipt {
+        let mut event = Event::new();
+        event.set_event_type("test".into());
+        event.set_attributes(RepeatedField::from_vec(
+            attributes
+                .iter()
+                .map(|(key, value)| attribute(key, value))
+                .collect(),
+        ));
+
+        let mut receipt = TransactionReceipt::new();
+        if !attributes.is_empty() {
+            receipt.set_events(RepeatedField::from_vec(vec![event]));
+        }
+        receipt.set_state_changes(RepeatedField::from_vec(
+            changes
+                .iter()
+                .map(|(address, value)| {
+                    let mut change = StateChange::new();
+                    change.set_address(address.to_string());
+                    change.set_value(value.to_vec());
+                    change.set_field_type(StateChange_Type::SET);
+                    change
+                })
+                .collect(),
+        ));
+        receipt
+    }
+}
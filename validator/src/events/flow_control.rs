@@ -0,0 +1,239 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+//! Flow control of the events sent to subscribers that acknowledge them.
+//!
+//! Each subscriber has a window of at most `max_outstanding` messages sent
+//! but not yet acknowledged. Blocks whose events cannot be sent while the
+//! window is full, including those of a catch-up, are queued as a range of
+//! block numbers, and are sent in chunks of at most `chunk_size` blocks as
+//! the subscriber acknowledges earlier messages. A subscriber that leaves a
+//! message unacknowledged for longer than `ack_timeout` is dropped.
+
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, PartialEq)]
+pub enum FlowControlError {
+    UnknownSubscriber(String),
+}
+
+impl fmt::Display for FlowControlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FlowControlError::UnknownSubscriber(ref id) => write!(f, "Unknown subscriber: {}", id),
+        }
+    }
+}
+
+impl Error for FlowControlError {}
+
+#[derive(Default)]
+struct Window {
+    /// The send times of the unacknowledged messages, oldest first
+    outstanding: VecDeque<Instant>,
+    /// The first and last numbers of the blocks waiting to be sent
+    pending: Option<(u64, u64)>,
+}
+
+pub struct FlowControl {
+    max_outstanding: usize,
+    chunk_size: u64,
+    ack_timeout: Duration,
+    subscribers: HashMap<String, Window>,
+}
+
+impl FlowControl {
+    pub fn new(max_outstanding: usize, chunk_size: u64, ack_timeout: Duration) -> Self {
+        FlowControl {
+            max_outstanding: max_outstanding.max(1),
+            chunk_size: chunk_size.max(1),
+            ack_timeout,
+            subscribers: HashMap::new(),
+        }
+    }
+
+    pub fn add_subscriber(&mut self, id: &str) {
+        self.subscribers.insert(id.into(), Window::default());
+    }
+
+    pub fn remove_subscriber(&mut self, id: &str) {
+        self.subscribers.remove(id);
+    }
+
+    /// Reserves room in the window for a message sent now, unless the
+    /// window is full or blocks are waiting to be sent, which must go first.
+    pub fn try_send(&mut self, id: &str, now: Instant) -> Result<bool, FlowControlError> {
+        let max_outstanding = self.max_outstanding;
+        let window = self.window(id)?;
+        if window.pending.is_some() || window.outstanding.len() >= max_outstanding {
+            return Ok(false);
+        }
+        window.outstanding.push_back(now);
+        Ok(true)
+    }
+
+    /// Queues the blocks numbered `first` to `last` to be sent as room
+    /// becomes available in the window.
+    pub fn push_blocks(&mut self, id: &str, first: u64, last: u64) -> Result<(), FlowControlError> {
+        let window = self.window(id)?;
+        if first > last {
+            return Ok(());
+        }
+        window.pending = Some(match window.pending {
+            Some((pending_first, pending_last)) => {
+                (pending_first.min(first), pending_last.max(last))
+            }
+            None => (first, last),
+        });
+        Ok(())
+    }
+
+    /// Takes the next chunk of waiting blocks, as their first and last block
+    /// numbers, and reserves room in the window for the message sending them.
+    pub fn next_chunk(
+        &mut self,
+        id: &str,
+        now: Instant,
+    ) -> Result<Option<(u64, u64)>, FlowControlError> {
+        let max_outstanding = self.max_outstanding;
+        let chunk_size = self.chunk_size;
+        let window = self.window(id)?;
+        if window.outstanding.len() >= max_outstanding {
+            return Ok(None);
+        }
+        let (first, last) = match window.pending {
+            Some(pending) => pending,
+            None => return Ok(None),
+        };
+
+        let chunk_last = last.min(first.saturating_add(chunk_size - 1));
+        window.pending = if chunk_last < last {
+            Some((chunk_last + 1, last))
+        } else {
+            None
+        };
+        window.outstanding.push_back(now);
+        Ok(Some((first, chunk_last)))
+    }
+
+    /// Records the acknowledgement of the subscriber's oldest unacknowledged
+    /// message.
+    pub fn ack(&mut self, id: &str) -> Result<(), FlowControlError> {
+        self.window(id)?.outstanding.pop_front();
+        Ok(())
+    }
+
+    /// Removes a subscriber whose oldest unacknowledged message was sent more
+    /// than `ack_timeout` before `now`, and returns its id.
+    pub fn next_expired(&mut self, now: Instant) -> Option<String> {
+        let ack_timeout = self.ack_timeout;
+        let id = self
+            .subscribers
+            .iter()
+            .find(|(_, window)| match window.outstanding.front() {
+                Some(sent) => now.duration_since(*sent) > ack_timeout,
+                None => false,
+            })
+            .map(|(id, _)| id.clone())?;
+
+        self.subscribers.remove(&id);
+        counter!("events_dropped_subscriber_count", 1);
+        Some(id)
+    }
+
+    fn window(&mut self, id: &str) -> Result<&mut Window, FlowControlError> {
+        self.subscribers
+            .get_mut(id)
+            .ok_or_else(|| FlowControlError::UnknownSubscriber(id.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_limits_outstanding_messages() {
+        let now = Instant::now();
+        let mut flow_control = FlowControl::new(2, 10, Duration::from_secs(30));
+        flow_control.add_subscriber("sub");
+
+        assert_eq!(Ok(true), flow_control.try_send("sub", now));
+        assert_eq!(Ok(true), flow_control.try_send("sub", now));
+        assert_eq!(Ok(false), flow_control.try_send("sub", now));
+
+        flow_control.ack("sub").unwrap();
+        assert_eq!(Ok(true), flow_control.try_send("sub", now));
+
+        assert_eq!(
+            Err(FlowControlError::UnknownSubscriber("other".into())),
+            flow_control.try_send("other", now)
+        );
+    }
+
+    #[test]
+    fn pending_blocks_are_sent_in_chunks() {
+        let now = Instant::now();
+        let mut flow_control = FlowControl::new(2, 10, Duration::from_secs(30));
+        flow_control.add_subscriber("sub");
+
+        flow_control.push_blocks("sub", 5, 29).unwrap();
+        // Queued blocks are sent before new ones
+        assert_eq!(Ok(false), flow_control.try_send("sub", now));
+
+        assert_eq!(Ok(Some((5, 14))), flow_control.next_chunk("sub", now));
+        assert_eq!(Ok(Some((15, 24))), flow_control.next_chunk("sub", now));
+        assert_eq!(Ok(None), flow_control.next_chunk("sub", now));
+
+        flow_control.ack("sub").unwrap();
+        flow_control.push_blocks("sub", 30, 30).unwrap();
+        assert_eq!(Ok(Some((25, 30))), flow_control.next_chunk("sub", now));
+
+        flow_control.ack("sub").unwrap();
+        assert_eq!(Ok(None), flow_control.next_chunk("sub", now));
+        assert_eq!(Ok(true), flow_control.try_send("sub", now));
+    }
+
+    #[test]
+    fn unacknowledging_subscribers_expire() {
+        let start = Instant::now();
+        let mut flow_control = FlowControl::new(2, 10, Duration::from_secs(30));
+        flow_control.add_subscriber("slow");
+        flow_control.add_subscriber("fast");
+
+        flow_control.try_send("slow", start).unwrap();
+        flow_control.try_send("fast", start).unwrap();
+        flow_control.ack("fast").unwrap();
+
+        assert_eq!(
+            None,
+            flow_control.next_expired(start + Duration::from_secs(30))
+        );
+        assert_eq!(
+            Some("slow".to_string()),
+            flow_control.next_expired(start + Duration::from_secs(31))
+        );
+        assert_eq!(
+            None,
+            flow_control.next_expired(start + Duration::from_secs(31))
+        );
+        assert!(flow_control.try_send("slow", start).is_err());
+    }
+}
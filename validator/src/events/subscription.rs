@@ -0,0 +1,242 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+use std::error::Error;
+use std::fmt;
+
+use protobuf::ProtobufError;
+use regex::Regex;
+
+use proto::events::{
+    Event, EventFilter as EventFilterProto, EventFilter_FilterType,
+    EventSubscription as EventSubscriptionProto,
+};
+
+#[derive(Debug)]
+pub enum EventError {
+    InvalidFilter(String),
+    SerializationError(ProtobufError),
+}
+
+impl fmt::Display for EventError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EventError::InvalidFilter(ref msg) => write!(f, "Invalid filter: {}", msg),
+            EventError::SerializationError(ref err) => {
+                write!(f, "Unable to (de)serialize events: {}", err)
+            }
+        }
+    }
+}
+
+impl Error for EventError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            EventError::SerializationError(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<ProtobufError> for EventError {
+    fn from(err: ProtobufError) -> Self {
+        EventError::SerializationError(err)
+    }
+}
+
+/// Selects events by the values of their attributes with a given key.
+///
+/// An event may have several attributes with the same key. An "any" filter
+/// passes an event if one of them matches, and an "all" filter if every one
+/// of them does, which includes an event without any.
+#[derive(Clone, Debug)]
+pub enum EventFilter {
+    SimpleAny { key: String, value: String },
+    SimpleAll { key: String, value: String },
+    RegexAny { key: String, regex: Regex },
+    RegexAll { key: String, regex: Regex },
+}
+
+impl EventFilter {
+    pub fn from_proto(filter: &EventFilterProto) -> Result<Self, EventError> {
+        let key = filter.get_key().to_string();
+        let match_string = filter.get_match_string();
+
+        let regex = || {
+            Regex::new(match_string).map_err(|err| {
+                EventError::InvalidFilter(format!(
+                    "Invalid regular expression: {}: {}",
+                    match_string, err
+                ))
+            })
+        };
+
+        Ok(match filter.get_filter_type() {
+            EventFilter_FilterType::SIMPLE_ANY => EventFilter::SimpleAny {
+                key,
+                value: match_string.into(),
+            },
+            EventFilter_FilterType::SIMPLE_ALL => EventFilter::SimpleAll {
+                key,
+                value: match_string.into(),
+            },
+            EventFilter_FilterType::REGEX_ANY => EventFilter::RegexAny {
+                key,
+                regex: regex()?,
+            },
+            EventFilter_FilterType::REGEX_ALL => EventFilter::RegexAll {
+                key,
+                regex: regex()?,
+            },
+            EventFilter_FilterType::FILTER_TYPE_UNSET => {
+                return Err(EventError::InvalidFilter(
+                    "Unknown filter type: FILTER_TYPE_UNSET".into(),
+                ));
+            }
+        })
+    }
+
+    pub fn matches(&self, event: &Event) -> bool {
+        let values = |key: &str| {
+            event
+                .get_attributes()
+                .iter()
+                .filter(move |attribute| attribute.get_key() == key)
+                .map(|attribute| attribute.get_value())
+        };
+
+        match *self {
+            EventFilter::SimpleAny { ref key, ref value } => values(key).any(|v| v == value),
+            EventFilter::SimpleAll { ref key, ref value } => values(key).all(|v| v == value),
+            EventFilter::RegexAny { ref key, ref regex } => values(key).any(|v| regex.is_match(v)),
+            EventFilter::RegexAll { ref key, ref regex } => values(key).all(|v| regex.is_match(v)),
+        }
+    }
+}
+
+/// Selects the events of one type that pass all of the subscription's
+/// filters.
+#[derive(Clone, Debug)]
+pub struct EventSubscription {
+    pub event_type: String,
+    pub filters: Vec<EventFilter>,
+}
+
+impl EventSubscription {
+    pub fn from_proto(subscription: &EventSubscriptionProto) -> Result<Self, EventError> {
+        Ok(EventSubscription {
+            event_type: subscription.get_event_type().into(),
+            filters: subscription
+                .get_filters()
+                .iter()
+                .map(EventFilter::from_proto)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    pub fn matches(&self, event: &Event) -> bool {
+        event.get_event_type() == self.event_type
+            && self.filters.iter().all(|filter| filter.matches(event))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use protobuf::RepeatedField;
+
+    use proto::events::Event_Attribute;
+
+    #[test]
+    fn filters_match_attributes() {
+        let event = make_event("test", &[("address", "abc"), ("address", "def")]);
+
+        assert!(make_filter(EventFilter_FilterType::SIMPLE_ANY, "address", "abc").matches(&event));
+        assert!(!make_filter(EventFilter_FilterType::SIMPLE_ALL, "address", "abc").matches(&event));
+        assert!(make_filter(EventFilter_FilterType::REGEX_ANY, "address", "^d").matches(&event));
+        assert!(!make_filter(EventFilter_FilterType::REGEX_ALL, "address", "^d").matches(&event));
+        assert!(make_filter(EventFilter_FilterType::REGEX_ALL, "address", "[a-f]").matches(&event));
+
+        // An all filter passes an event without the filter's key
+        assert!(make_filter(EventFilter_FilterType::SIMPLE_ALL, "other", "abc").matches(&event));
+        assert!(!make_filter(EventFilter_FilterType::SIMPLE_ANY, "other", "abc").matches(&event));
+    }
+
+    #[test]
+    fn invalid_filters_are_rejected() {
+        let mut filter = EventFilterProto::new();
+        filter.set_key("address".into());
+        filter.set_match_string("(".into());
+        filter.set_filter_type(EventFilter_FilterType::REGEX_ANY);
+        match EventFilter::from_proto(&filter) {
+            Err(EventError::InvalidFilter(_)) => (),
+            res => panic!("Expected InvalidFilter, got {:?}", res),
+        }
+
+        filter.set_filter_type(EventFilter_FilterType::FILTER_TYPE_UNSET);
+        match EventFilter::from_proto(&filter) {
+            Err(EventError::InvalidFilter(_)) => (),
+            res => panic!("Expected InvalidFilter, got {:?}", res),
+        }
+    }
+
+    #[test]
+    fn subscriptions_match_event_type_and_filters() {
+        let subscription = EventSubscription {
+            event_type: "test".into(),
+            filters: vec![make_filter(
+                EventFilter_FilterType::SIMPLE_ANY,
+                "address",
+                "abc",
+            )],
+        };
+
+        assert!(subscription.matches(&make_event("test", &[("address", "abc")])));
+        assert!(!subscription.matches(&make_event("test", &[("address", "def")])));
+        assert!(!subscription.matches(&make_event("other", &[("address", "abc")])));
+    }
+
+    fn make_filter(
+        filter_type: EventFilter_FilterType,
+        key: &str,
+        match_string: &str,
+    ) -> EventFilter {
+        let mut filter = EventFilterProto::new();
+        filter.set_key(key.into());
+        filter.set_match_string(match_string.into());
+        filter.set_filter_type(filter_type);
+        EventFilter::from_proto(&filter).expect("Unable to create filter")
+    }
+
+    fn make_event(event_type: &str, attributes: &[(&str, &str)]) -> Event {
+        let mut event = Event::new();
+        event.set_event_type(event_type.into());
+        event.set_attributes(RepeatedField::from_vec(
+            attributes
+                .iter()
+                .map(|(key, value)| {
+                    let mut attribute = Event_Attribute::new();
+                    attribute.set_key(key.to_string());
+                    attribute.set_value(value.to_string());
+                    attribute
+                })
+                .collect(),
+        ));
+        event
+    }
+}
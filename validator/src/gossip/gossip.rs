@@ -0,0 +1,575 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+//! Block and batch gossip between peered validators.
+//!
+//! `Gossip` keeps the registry of peers, broadcasts blocks and batches to
+//! them, and answers block and batch requests, forwarding requests it cannot
+//! answer until their time to live runs out. Sending messages and looking up
+//! blocks and batches is left to a `GossipService`.
+
+use std::collections::{HashSet, VecDeque};
+use std::error::Error;
+use std::fmt;
+
+use hex;
+use openssl::rand::rand_bytes;
+use protobuf::{Message, ProtobufError};
+
+use proto::network::{
+    GossipBatchByBatchIdRequest, GossipBatchResponse, GossipBlockRequest, GossipBlockResponse,
+    GossipMessage, GossipMessage_ContentType,
+};
+use proto::validator::Message_MessageType;
+
+/// The number of request nonces remembered to drop requests that loop back.
+const MAX_SEEN_REQUESTS: usize = 10000;
+
+#[derive(Debug)]
+pub enum GossipError {
+    /// The peer could not be registered, as the maximum number of peers are
+    /// already registered.
+    MaximumPeersReached(usize),
+    /// The service failed to send a message or look up a block or batch.
+    ServiceError(String),
+    EncodingError(ProtobufError),
+}
+
+impl Error for GossipError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            GossipError::EncodingError(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for GossipError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            GossipError::MaximumPeersReached(max) => {
+                write!(f, "At maximum configured number of peers: {}", max)
+            }
+            GossipError::ServiceError(ref msg) => write!(f, "ServiceError: {}", msg),
+            GossipError::EncodingError(ref err) => write!(f, "EncodingError: {}", err),
+        }
+    }
+}
+
+/// The network and storage operations gossip relies on.
+pub trait GossipService: Send {
+    /// Sends a one way message to the given connection.
+    fn send(
+        &self,
+        message_type: Message_MessageType,
+        payload: Vec<u8>,
+        connection_id: &str,
+    ) -> Result<(), GossipError>;
+
+    /// Returns the serialized block, if it is known.
+    fn get_block(&self, block_id: &str) -> Result<Option<Vec<u8>>, GossipError>;
+
+    /// Returns the serialized batch, if it is known.
+    fn get_batch(&self, batch_id: &str) -> Result<Option<Vec<u8>>, GossipError>;
+}
+
+/// What was done with a block or batch request.
+#[derive(Debug, PartialEq)]
+pub enum RequestOutcome {
+    /// The requested item was sent back to the requester.
+    Responded,
+    /// The request was forwarded to the given number of peers.
+    Forwarded(usize),
+    /// The request has already been seen, or its time to live ran out.
+    Dropped,
+}
+
+pub struct Gossip<S: GossipService> {
+    service: S,
+    maximum_peers: usize,
+    fanout: Option<usize>,
+    /// Peer connection ids and endpoints, in the order they registered.
+    peers: Vec<(String, String)>,
+    /// Where the next limited broadcast starts in `peers`.
+    next_peer: usize,
+    seen_requests: HashSet<String>,
+    seen_requests_order: VecDeque<String>,
+}
+
+impl<S: GossipService> Gossip<S> {
+    /// Creates a new `Gossip`. If `fanout` is given, each broadcast is sent
+    /// to at most that many peers, rotating through the registered peers so
+    /// that successive broadcasts reach all of them.
+    pub fn new(service: S, maximum_peers: usize, fanout: Option<usize>) -> Self {
+        Gossip {
+            service,
+            maximum_peers,
+            fanout,
+            peers: vec![],
+            next_peer: 0,
+            seen_requests: HashSet::new(),
+            seen_requests_order: VecDeque::new(),
+        }
+    }
+
+    pub fn register_peer(
+        &mut self,
+        connection_id: &str,
+        endpoint: &str,
+    ) -> Result<(), GossipError> {
+        if let Some(peer) = self.peers.iter_mut().find(|(id, _)| id == connection_id) {
+            peer.1 = endpoint.into();
+            return Ok(());
+        }
+
+        if self.peers.len() >= self.maximum_peers {
+            return Err(GossipError::MaximumPeersReached(self.maximum_peers));
+        }
+
+        debug!(
+            "Added connection_id {} with endpoint {}",
+            connection_id, endpoint
+        );
+        self.peers.push((connection_id.into(), endpoint.into()));
+        Ok(())
+    }
+
+    /// Removes a peer, returning whether it was registered.
+    pub fn unregister_peer(&mut self, connection_id: &str) -> bool {
+        let count = self.peers.len();
+        self.peers.retain(|(id, _)| id != connection_id);
+        count != self.peers.len()
+    }
+
+    pub fn peers(&self) -> &[(String, String)] {
+        &self.peers
+    }
+
+    /// Broadcasts a block to peers other than `exclude`, returning the
+    /// number of peers it was sent to.
+    pub fn broadcast_block(
+        &mut self,
+        block: Vec<u8>,
+        exclude: Option<&str>,
+        time_to_live: u32,
+    ) -> Result<usize, GossipError> {
+        self.broadcast_content(
+            GossipMessage_ContentType::BLOCK,
+            block,
+            exclude,
+            time_to_live,
+        )
+    }
+
+    /// Broadcasts a batch to peers other than `exclude`, returning the
+    /// number of peers it was sent to.
+    pub fn broadcast_batch(
+        &mut self,
+        batch: Vec<u8>,
+        exclude: Option<&str>,
+        time_to_live: u32,
+    ) -> Result<usize, GossipError> {
+        self.broadcast_content(
+            GossipMessage_ContentType::BATCH,
+            batch,
+            exclude,
+            time_to_live,
+        )
+    }
+
+    /// Asks peers for a block.
+    pub fn broadcast_block_request(
+        &mut self,
+        block_id: &str,
+        time_to_live: u32,
+    ) -> Result<usize, GossipError> {
+        let mut request = GossipBlockRequest::new();
+        request.set_block_id(block_id.into());
+        request.set_nonce(new_nonce()?);
+        request.set_time_to_live(time_to_live);
+        self.remember_request(request.get_nonce());
+
+        self.broadcast(Message_MessageType::GOSSIP_BLOCK_REQUEST, &request, None)
+    }
+
+    /// Asks peers for a batch.
+    pub fn broadcast_batch_request(
+        &mut self,
+        batch_id: &str,
+        time_to_live: u32,
+    ) -> Result<usize, GossipError> {
+        let mut request = GossipBatchByBatchIdRequest::new();
+        request.set_id(batch_id.into());
+        request.set_nonce(new_nonce()?);
+        request.set_time_to_live(time_to_live);
+        self.remember_request(request.get_nonce());
+
+        self.broadcast(
+            Message_MessageType::GOSSIP_BATCH_BY_BATCH_ID_REQUEST,
+            &request,
+            None,
+        )
+    }
+
+    /// Answers a block request from `connection_id` if the block is known,
+    /// and otherwise forwards it to the other peers.
+    pub fn handle_block_request(
+        &mut self,
+        connection_id: &str,
+        mut request: GossipBlockRequest,
+    ) -> Result<RequestOutcome, GossipError> {
+        if !self.remember_request(request.get_nonce()) {
+            return Ok(RequestOutcome::Dropped);
+        }
+
+        if let Some(block) = self.service.get_block(request.get_block_id())? {
+            let mut response = GossipBlockResponse::new();
+            response.set_content(block);
+            self.send(
+                Message_MessageType::GOSSIP_BLOCK_RESPONSE,
+                &response,
+                connection_id,
+            )?;
+            return Ok(RequestOutcome::Responded);
+        }
+
+        if request.get_time_to_live() <= 1 {
+            return Ok(RequestOutcome::Dropped);
+        }
+        let time_to_live = request.get_time_to_live() - 1;
+        request.set_time_to_live(time_to_live);
+
+        self.broadcast(
+            Message_MessageType::GOSSIP_BLOCK_REQUEST,
+            &request,
+            Some(connection_id),
+        )
+        .map(RequestOutcome::Forwarded)
+    }
+
+    /// Answers a batch request from `connection_id` if the batch is known,
+    /// and otherwise forwards it to the other peers.
+    pub fn handle_batch_request(
+        &mut self,
+        connection_id: &str,
+        mut request: GossipBatchByBatchIdRequest,
+    ) -> Result<RequestOutcome, GossipError> {
+        if !self.remember_request(request.get_nonce()) {
+            return Ok(RequestOutcome::Dropped);
+        }
+
+        if let Some(batch) = self.service.get_batch(request.get_id())? {
+            let mut response = GossipBatchResponse::new();
+            response.set_content(batch);
+            self.send(
+                Message_MessageType::GOSSIP_BATCH_RESPONSE,
+                &response,
+                connection_id,
+            )?;
+            return Ok(RequestOutcome::Responded);
+        }
+
+        if request.get_time_to_live() <= 1 {
+            return Ok(RequestOutcome::Dropped);
+        }
+        let time_to_live = request.get_time_to_live() - 1;
+        request.set_time_to_live(time_to_live);
+
+        self.broadcast(
+            Message_MessageType::GOSSIP_BATCH_BY_BATCH_ID_REQUEST,
+            &request,
+            Some(connection_id),
+        )
+        .map(RequestOutcome::Forwarded)
+    }
+
+    fn broadcast_content(
+        &mut self,
+        content_type: GossipMessage_ContentType,
+        content: Vec<u8>,
+        exclude: Option<&str>,
+        time_to_live: u32,
+    ) -> Result<usize, GossipError> {
+        let mut gossip_message = GossipMessage::new();
+        gossip_message.set_content_type(content_type);
+        gossip_message.set_content(content);
+        gossip_message.set_time_to_live(time_to_live);
+
+        self.broadcast(
+            Message_MessageType::GOSSIP_MESSAGE,
+            &gossip_message,
+            exclude,
+        )
+    }
+
+    fn broadcast<M: Message>(
+        &mut self,
+        message_type: Message_MessageType,
+        message: &M,
+        exclude: Option<&str>,
+    ) -> Result<usize, GossipError> {
+        let payload = message
+            .write_to_bytes()
+            .map_err(GossipError::EncodingError)?;
+
+        let mut sent = 0;
+        for connection_id in self.broadcast_targets(exclude) {
+            match self
+                .service
+                .send(message_type, payload.clone(), &connection_id)
+            {
+                Ok(()) => sent += 1,
+                Err(err) => {
+                    debug!(
+                        "Connection {} is no longer valid, removing from peers: {}",
+                        connection_id, err
+                    );
+                    self.unregister_peer(&connection_id);
+                }
+            }
+        }
+
+        Ok(sent)
+    }
+
+    /// Returns the peers the next broadcast is sent to.
+    fn broadcast_targets(&mut self, exclude: Option<&str>) -> Vec<String> {
+        let count = self.peers.len();
+        if count == 0 {
+            return vec![];
+        }
+
+        let limit = self.fanout.unwrap_or(count);
+        let start = self.next_peer % count;
+
+        let targets: Vec<String> = self.peers[start..]
+            .iter()
+            .chain(self.peers[..start].iter())
+            .map(|(id, _)| id)
+            .filter(|id| Some(id.as_str()) != exclude)
+            .take(limit)
+            .cloned()
+            .collect();
+
+        self.next_peer = (start + targets.len()) % count;
+        targets
+    }
+
+    fn send<M: Message>(
+        &self,
+        message_type: Message_MessageType,
+        message: &M,
+        connection_id: &str,
+    ) -> Result<(), GossipError> {
+        let payload = message
+            .write_to_bytes()
+            .map_err(GossipError::EncodingError)?;
+        self.service.send(message_type, payload, connection_id)
+    }
+
+    /// Records a request nonce, returning false if it was already seen.
+    fn remember_request(&mut self, nonce: &str) -> bool {
+        if !self.seen_requests.insert(nonce.into()) {
+            return false;
+        }
+
+        self.seen_requests_order.push_back(nonce.into());
+        if self.seen_requests_order.len() > MAX_SEEN_REQUESTS {
+            if let Some(oldest) = self.seen_requests_order.pop_front() {
+                self.seen_requests.remove(&oldest);
+            }
+        }
+        true
+    }
+}
+
+fn new_nonce() -> Result<String, GossipError> {
+    let mut bytes = [0; 16];
+    rand_bytes(&mut bytes).map_err(|err| GossipError::ServiceError(err.to_string()))?;
+    Ok(hex::encode(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct MockService {
+        sent: Arc<Mutex<Vec<(Message_MessageType, Vec<u8>, String)>>>,
+        blocks: HashMap<String, Vec<u8>>,
+        closed: Vec<String>,
+    }
+
+    impl MockService {
+        fn take_sent(&self) -> Vec<(Message_MessageType, Vec<u8>, String)> {
+            self.sent.lock().unwrap().drain(..).collect()
+        }
+    }
+
+    impl GossipService for MockService {
+        fn send(
+            &self,
+            message_type: Message_MessageType,
+            payload: Vec<u8>,
+            connection_id: &str,
+        ) -> Result<(), GossipError> {
+            if self.closed.iter().any(|id| id == connection_id) {
+                return Err(GossipError::ServiceError("closed".into()));
+            }
+            self.sent
+                .lock()
+                .unwrap()
+                .push((message_type, payload, connection_id.into()));
+            Ok(())
+        }
+
+        fn get_block(&self, block_id: &str) -> Result<Option<Vec<u8>>, GossipError> {
+            Ok(self.blocks.get(block_id).cloned())
+        }
+
+        fn get_batch(&self, _batch_id: &str) -> Result<Option<Vec<u8>>, GossipError> {
+            Ok(None)
+        }
+    }
+
+    fn new_gossip(
+        service: MockService,
+        peers: &[&str],
+        fanout: Option<usize>,
+    ) -> Gossip<MockService> {
+        let mut gossip = Gossip::new(service, 10, fanout);
+        for peer in peers {
+            gossip
+                .register_peer(peer, &format!("tcp://{}:8800", peer))
+                .unwrap();
+        }
+        gossip
+    }
+
+    /// Registering past the maximum number of peers fails, and a broadcast
+    /// reaches every peer except the excluded one.
+    #[test]
+    fn test_register_and_broadcast() {
+        let service = MockService::default();
+        let mut gossip = Gossip::new(service.clone(), 2, None);
+
+        gossip.register_peer("a", "tcp://a:8800").unwrap();
+        gossip.register_peer("b", "tcp://b:8800").unwrap();
+        assert!(gossip.register_peer("c", "tcp://c:8800").is_err());
+
+        assert_eq!(
+            1,
+            gossip
+                .broadcast_block(b"block".to_vec(), Some("a"), 3)
+                .unwrap()
+        );
+
+        let sent = service.take_sent();
+        assert_eq!(1, sent.len());
+        assert_eq!(Message_MessageType::GOSSIP_MESSAGE, sent[0].0);
+        assert_eq!("b", sent[0].2);
+
+        let message: GossipMessage = Message::parse_from_bytes(&sent[0].1).unwrap();
+        assert_eq!(GossipMessage_ContentType::BLOCK, message.get_content_type());
+        assert_eq!(b"block".to_vec(), message.get_content());
+        assert_eq!(3, message.get_time_to_live());
+
+        assert!(gossip.unregister_peer("a"));
+        assert!(!gossip.unregister_peer("a"));
+        gossip.register_peer("c", "tcp://c:8800").unwrap();
+    }
+
+    /// With a fan-out limit, successive broadcasts rotate through the peers.
+    #[test]
+    fn test_broadcast_fanout() {
+        let service = MockService::default();
+        let mut gossip = new_gossip(service.clone(), &["a", "b", "c"], Some(2));
+
+        assert_eq!(2, gossip.broadcast_batch(b"1".to_vec(), None, 3).unwrap());
+        assert_eq!(2, gossip.broadcast_batch(b"2".to_vec(), None, 3).unwrap());
+
+        let targets: Vec<String> = service
+            .take_sent()
+            .into_iter()
+            .map(|(_, _, id)| id)
+            .collect();
+        assert_eq!(vec!["a", "b", "c", "a"], targets);
+    }
+
+    /// A peer that can no longer be sent to is removed.
+    #[test]
+    fn test_broadcast_removes_closed_peer() {
+        let mut service = MockService::default();
+        service.closed.push("b".into());
+        let mut gossip = new_gossip(service, &["a", "b"], None);
+
+        assert_eq!(
+            1,
+            gossip.broadcast_batch(b"batch".to_vec(), None, 3).unwrap()
+        );
+        assert_eq!(1, gossip.peers().len());
+    }
+
+    /// A known block is returned to the requester, an unknown block is
+    /// requested from the other peers, and a repeated request is dropped.
+    #[test]
+    fn test_handle_block_request() {
+        let mut service = MockService::default();
+        service.blocks.insert("known".into(), b"block".to_vec());
+        let mut gossip = new_gossip(service.clone(), &["a", "b", "c"], None);
+
+        let mut request = GossipBlockRequest::new();
+        request.set_block_id("known".into());
+        request.set_nonce("1".into());
+        request.set_time_to_live(3);
+        assert_eq!(
+            RequestOutcome::Responded,
+            gossip.handle_block_request("a", request.clone()).unwrap()
+        );
+        let sent = service.take_sent();
+        assert_eq!(Message_MessageType::GOSSIP_BLOCK_RESPONSE, sent[0].0);
+        assert_eq!("a", sent[0].2);
+
+        assert_eq!(
+            RequestOutcome::Dropped,
+            gossip.handle_block_request("b", request.clone()).unwrap()
+        );
+
+        request.set_block_id("unknown".into());
+        request.set_nonce("2".into());
+        assert_eq!(
+            RequestOutcome::Forwarded(2),
+            gossip.handle_block_request("a", request.clone()).unwrap()
+        );
+        for (message_type, payload, connection_id) in service.take_sent() {
+            assert_eq!(Message_MessageType::GOSSIP_BLOCK_REQUEST, message_type);
+            assert_ne!("a", connection_id);
+            let forwarded: GossipBlockRequest = Message::parse_from_bytes(&payload).unwrap();
+            assert_eq!(2, forwarded.get_time_to_live());
+        }
+
+        request.set_nonce("3".into());
+        request.set_time_to_live(1);
+        assert_eq!(
+            RequestOutcome::Dropped,
+            gossip.handle_block_request("a", request).unwrap()
+        );
+    }
+}
@@ -0,0 +1,378 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_void};
+use std::slice;
+use std::sync::Mutex;
+
+use cpython::{ObjectProtocol, PyBytes, PyObject, Python};
+use protobuf::{Message, ProtobufEnum};
+use py_ffi;
+
+use gossip::gossip::{Gossip, GossipError, GossipService};
+use proto::network::{GossipBatchByBatchIdRequest, GossipBlockRequest};
+use proto::validator::Message_MessageType;
+use pylogger;
+
+type PyGossip = Mutex<Gossip<PyGossipService>>;
+
+/// A `GossipService` backed by a Python object providing `send`,
+/// `get_block`, and `get_batch`.
+pub struct PyGossipService {
+    py_gossip_service: PyObject,
+}
+
+impl PyGossipService {
+    pub fn new(py_gossip_service: PyObject) -> Self {
+        PyGossipService { py_gossip_service }
+    }
+
+    fn get(&self, method: &str, id: &str) -> Result<Option<Vec<u8>>, GossipError> {
+        let gil_guard = Python::acquire_gil();
+        let py = gil_guard.python();
+
+        let res = self
+            .py_gossip_service
+            .call_method(py, method, (id,), None)
+            .map_err(|py_err| {
+                pylogger::exception(py, "Unable to call py_gossip_service", py_err);
+                GossipError::ServiceError(format!("FFI error calling {}", method))
+            })?;
+
+        if res == py.None() {
+            return Ok(None);
+        }
+
+        res.extract::<PyBytes>(py)
+            .map(|bytes| Some(bytes.data(py).to_vec()))
+            .map_err(|py_err| {
+                pylogger::exception(py, "py_gossip_service did not return bytes", py_err);
+                GossipError::ServiceError(format!("{} did not return bytes", method))
+            })
+    }
+}
+
+impl GossipService for PyGossipService {
+    fn send(
+        &self,
+        message_type: Message_MessageType,
+        payload: Vec<u8>,
+        connection_id: &str,
+    ) -> Result<(), GossipError> {
+        let gil_guard = Python::acquire_gil();
+        let py = gil_guard.python();
+
+        let payload = PyBytes::new(py, &payload);
+        self.py_gossip_service
+            .call_method(
+                py,
+                "send",
+                (message_type.value(), payload, connection_id),
+                None,
+            )
+            .map(|_| ())
+            // The network raises when the connection has closed, which is
+            // handled by removing the peer
+            .map_err(|_| GossipError::ServiceError(format!("Unable to send to {}", connection_id)))
+    }
+
+    fn get_block(&self, block_id: &str) -> Result<Option<Vec<u8>>, GossipError> {
+        self.get("get_block", block_id)
+    }
+
+    fn get_batch(&self, batch_id: &str) -> Result<Option<Vec<u8>>, GossipError> {
+        self.get("get_batch", batch_id)
+    }
+}
+
+#[repr(u32)]
+#[derive(Debug)]
+pub enum ErrorCode {
+    Success = 0,
+    NullPointerProvided = 0x01,
+    InvalidInputString = 0x02,
+    InvalidArgument = 0x03,
+    MaximumPeersReached = 0x04,
+    Error = 0x05,
+}
+
+macro_rules! check_null {
+    ($($arg:expr) , *) => {
+        $(if $arg.is_null() { return ErrorCode::NullPointerProvided; })*
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn gossip_new(
+    py_gossip_service_ptr: *mut py_ffi::PyObject,
+    maximum_peers: usize,
+    fanout: usize,
+    gossip_ptr: *mut *const c_void,
+) -> ErrorCode {
+    check_null!(py_gossip_service_ptr);
+
+    let py = Python::assume_gil_acquired();
+    let py_gossip_service = PyObject::from_borrowed_ptr(py, py_gossip_service_ptr);
+
+    let fanout = if fanout == 0 { None } else { Some(fanout) };
+    let gossip: PyGossip = Mutex::new(Gossip::new(
+        PyGossipService::new(py_gossip_service),
+        maximum_peers,
+        fanout,
+    ));
+
+    *gossip_ptr = Box::into_raw(Box::new(gossip)) as *const c_void;
+
+    ErrorCode::Success
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn gossip_drop(gossip: *mut c_void) -> ErrorCode {
+    check_null!(gossip);
+    Box::from_raw(gossip as *mut PyGossip);
+    ErrorCode::Success
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn gossip_register_peer(
+    gossip: *mut c_void,
+    connection_id: *const c_char,
+    endpoint: *const c_char,
+) -> ErrorCode {
+    check_null!(gossip, connection_id, endpoint);
+
+    let connection_id = match CStr::from_ptr(connection_id).to_str() {
+        Ok(s) => s,
+        Err(_) => return ErrorCode::InvalidInputString,
+    };
+    let endpoint = match CStr::from_ptr(endpoint).to_str() {
+        Ok(s) => s,
+        Err(_) => return ErrorCode::InvalidInputString,
+    };
+
+    let mut gossip = match lock(gossip) {
+        Ok(gossip) => gossip,
+        Err(code) => return code,
+    };
+    match gossip.register_peer(connection_id, endpoint) {
+        Ok(()) => ErrorCode::Success,
+        Err(GossipError::MaximumPeersReached(_)) => ErrorCode::MaximumPeersReached,
+        Err(err) => {
+            error!("Unable to register peer: {}", err);
+            ErrorCode::Error
+        }
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn gossip_unregister_peer(
+    gossip: *mut c_void,
+    connection_id: *const c_char,
+    result: *mut bool,
+) -> ErrorCode {
+    check_null!(gossip, connection_id, result);
+
+    let connection_id = match CStr::from_ptr(connection_id).to_str() {
+        Ok(s) => s,
+        Err(_) => return ErrorCode::InvalidInputString,
+    };
+
+    let mut gossip = match lock(gossip) {
+        Ok(gossip) => gossip,
+        Err(code) => return code,
+    };
+    *result = gossip.unregister_peer(connection_id);
+
+    ErrorCode::Success
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn gossip_broadcast_block(
+    gossip: *mut c_void,
+    block_bytes: *const u8,
+    block_bytes_len: usize,
+    exclude: *const c_char,
+    time_to_live: u32,
+) -> ErrorCode {
+    check_null!(gossip, block_bytes);
+
+    let block = slice::from_raw_parts(block_bytes, block_bytes_len).to_vec();
+    let exclude = match optional_str(exclude) {
+        Ok(exclude) => exclude,
+        Err(err) => return err,
+    };
+
+    let mut gossip = match lock(gossip) {
+        Ok(gossip) => gossip,
+        Err(code) => return code,
+    };
+    to_error_code(gossip.broadcast_block(block, exclude, time_to_live))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn gossip_broadcast_batch(
+    gossip: *mut c_void,
+    batch_bytes: *const u8,
+    batch_bytes_len: usize,
+    exclude: *const c_char,
+    time_to_live: u32,
+) -> ErrorCode {
+    check_null!(gossip, batch_bytes);
+
+    let batch = slice::from_raw_parts(batch_bytes, batch_bytes_len).to_vec();
+    let exclude = match optional_str(exclude) {
+        Ok(exclude) => exclude,
+        Err(err) => return err,
+    };
+
+    let mut gossip = match lock(gossip) {
+        Ok(gossip) => gossip,
+        Err(code) => return code,
+    };
+    to_error_code(gossip.broadcast_batch(batch, exclude, time_to_live))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn gossip_broadcast_block_request(
+    gossip: *mut c_void,
+    block_id: *const c_char,
+    time_to_live: u32,
+) -> ErrorCode {
+    check_null!(gossip, block_id);
+
+    let block_id = match CStr::from_ptr(block_id).to_str() {
+        Ok(s) => s,
+        Err(_) => return ErrorCode::InvalidInputString,
+    };
+
+    let mut gossip = match lock(gossip) {
+        Ok(gossip) => gossip,
+        Err(code) => return code,
+    };
+    to_error_code(gossip.broadcast_block_request(block_id, time_to_live))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn gossip_broadcast_batch_request(
+    gossip: *mut c_void,
+    batch_id: *const c_char,
+    time_to_live: u32,
+) -> ErrorCode {
+    check_null!(gossip, batch_id);
+
+    let batch_id = match CStr::from_ptr(batch_id).to_str() {
+        Ok(s) => s,
+        Err(_) => return ErrorCode::InvalidInputString,
+    };
+
+    let mut gossip = match lock(gossip) {
+        Ok(gossip) => gossip,
+        Err(code) => return code,
+    };
+    to_error_code(gossip.broadcast_batch_request(batch_id, time_to_live))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn gossip_handle_block_request(
+    gossip: *mut c_void,
+    connection_id: *const c_char,
+    request_bytes: *const u8,
+    request_bytes_len: usize,
+) -> ErrorCode {
+    check_null!(gossip, connection_id, request_bytes);
+
+    let connection_id = match CStr::from_ptr(connection_id).to_str() {
+        Ok(s) => s,
+        Err(_) => return ErrorCode::InvalidInputString,
+    };
+    let request: GossipBlockRequest =
+        match Message::parse_from_bytes(slice::from_raw_parts(request_bytes, request_bytes_len)) {
+            Ok(request) => request,
+            Err(err) => {
+                error!("Failed to parse GossipBlockRequest: {:?}", err);
+                return ErrorCode::InvalidArgument;
+            }
+        };
+
+    let mut gossip = match lock(gossip) {
+        Ok(gossip) => gossip,
+        Err(code) => return code,
+    };
+    to_error_code(gossip.handle_block_request(connection_id, request))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn gossip_handle_batch_request(
+    gossip: *mut c_void,
+    connection_id: *const c_char,
+    request_bytes: *const u8,
+    request_bytes_len: usize,
+) -> ErrorCode {
+    check_null!(gossip, connection_id, request_bytes);
+
+    let connection_id = match CStr::from_ptr(connection_id).to_str() {
+        Ok(s) => s,
+        Err(_) => return ErrorCode::InvalidInputString,
+    };
+    let request: GossipBatchByBatchIdRequest =
+        match Message::parse_from_bytes(slice::from_raw_parts(request_bytes, request_bytes_len)) {
+            Ok(request) => request,
+            Err(err) => {
+                error!("Failed to parse GossipBatchByBatchIdRequest: {:?}", err);
+                return ErrorCode::InvalidArgument;
+            }
+        };
+
+    let mut gossip = match lock(gossip) {
+        Ok(gossip) => gossip,
+        Err(code) => return code,
+    };
+    to_error_code(gossip.handle_batch_request(connection_id, request))
+}
+
+/// Locks gossip, or returns `ErrorCode::Error` if a thread panicked while
+/// holding the lock, rather than panicking across the FFI boundary.
+unsafe fn lock<'a>(
+    gossip: *mut c_void,
+) -> Result<::std::sync::MutexGuard<'a, Gossip<PyGossipService>>, ErrorCode> {
+    (*(gossip as *mut PyGossip)).lock().map_err(|_| {
+        error!("Gossip lock poisoned");
+        ErrorCode::Error
+    })
+}
+
+unsafe fn optional_str<'a>(s: *const c_char) -> Result<Option<&'a str>, ErrorCode> {
+    if s.is_null() {
+        return Ok(None);
+    }
+
+    CStr::from_ptr(s)
+        .to_str()
+        .map(Some)
+        .map_err(|_| ErrorCode::InvalidInputString)
+}
+
+fn to_error_code<T>(result: Result<T, GossipError>) -> ErrorCode {
+    match result {
+        Ok(_) => ErrorCode::Success,
+        Err(err) => {
+            error!("Gossip error: {}", err);
+            ErrorCode::Error
+        }
+    }
+}
@@ -0,0 +1,520 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+//! Reports the metrics recorded in Rust directly to InfluxDB or Telegraf, in
+//! the line protocol, rather than through the Python metrics registry.
+//!
+//! Metrics are reported the way the Python validator's reporter does: every
+//! ten seconds, prefixed with "sawtooth_validator" and tagged with the host.
+//! Counters report their total as `count` and gauges their latest value as
+//! `value`.
+
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::fmt;
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs, UdpSocket};
+use std::os::raw::c_char;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use libc;
+use metrics::{self, Key, Recorder};
+use openssl::ssl::{SslConnector, SslMethod};
+
+const PREFIX: &str = "sawtooth_validator";
+const REPORTING_INTERVAL: Duration = Duration::from_secs(10);
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(5);
+
+const DEFAULT_HTTP_PORT: u16 = 8086;
+const DEFAULT_UDP_PORT: u16 = 8089;
+
+/// Lines are sent over UDP in datagrams of at most this size, which fit in
+/// an Ethernet frame, so that a lost fragment does not drop a whole report.
+const MAX_DATAGRAM_SIZE: usize = 1400;
+
+#[repr(u32)]
+#[derive(Debug)]
+pub enum ErrorCode {
+    Success = 0,
+    NullPointerProvided = 0x01,
+    InvalidUrl = 0x02,
+    AlreadyInitialized = 0x03,
+}
+
+/// Starts reporting the metrics recorded in Rust to the InfluxDB or Telegraf
+/// at url. The database, username and password are only used over HTTP(S),
+/// and the username and password may be null.
+#[no_mangle]
+pub unsafe extern "C" fn influx_metrics_init(
+    url: *const c_char,
+    database: *const c_char,
+    username: *const c_char,
+    password: *const c_char,
+) -> ErrorCode {
+    if url.is_null() || database.is_null() {
+        return ErrorCode::NullPointerProvided;
+    }
+
+    let to_string = |ptr: *const c_char| {
+        if ptr.is_null() {
+            Ok(None)
+        } else {
+            CStr::from_ptr(ptr)
+                .to_str()
+                .map(|s| Some(s.to_string()))
+                .map_err(|_| InfluxError(format!("invalid string: {:?}", CStr::from_ptr(ptr))))
+        }
+    };
+
+    let config = to_string(url).and_then(|url| {
+        InfluxConfig::new(
+            &url.unwrap_or_default(),
+            to_string(database)?.unwrap_or_default(),
+            to_string(username)?,
+            to_string(password)?,
+        )
+    });
+
+    let config = match config {
+        Ok(config) => config,
+        Err(err) => {
+            error!("Unable to report metrics to InfluxDB: {}", err);
+            return ErrorCode::InvalidUrl;
+        }
+    };
+
+    if InfluxRecorder::init(config).is_err() {
+        warn!("Attempted to initialize metrics recorder twice; ignoring");
+        return ErrorCode::AlreadyInitialized;
+    }
+
+    ErrorCode::Success
+}
+
+#[derive(Debug, PartialEq)]
+pub struct InfluxError(String);
+
+impl fmt::Display for InfluxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Transport {
+    Udp,
+    Http,
+    Https,
+}
+
+/// Where and how metrics are written, parsed from the validator's
+/// `opentsdb_*` settings.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InfluxConfig {
+    transport: Transport,
+    host: String,
+    port: u16,
+    database: String,
+    username: Option<String>,
+    password: Option<String>,
+}
+
+impl InfluxConfig {
+    /// Parses a url of the form `<scheme>://<host>[:<port>]`, where the
+    /// scheme is one of udp, http or https.
+    pub fn new(
+        url: &str,
+        database: String,
+        username: Option<String>,
+        password: Option<String>,
+    ) -> Result<InfluxConfig, InfluxError> {
+        let invalid = || InfluxError(format!("invalid url: {:?}", url));
+
+        let mut parts = url.splitn(2, "://");
+        let scheme = parts.next().ok_or_else(invalid)?;
+        let address = parts
+            .next()
+            .ok_or_else(invalid)?
+            .trim_end_matches('/')
+            .to_string();
+
+        let (transport, default_port) = match scheme {
+            "udp" => (Transport::Udp, DEFAULT_UDP_PORT),
+            "http" => (Transport::Http, DEFAULT_HTTP_PORT),
+            "https" => (Transport::Https, DEFAULT_HTTP_PORT),
+            _ => {
+                return Err(InfluxError(format!(
+                    "unsupported scheme {:?}; expected udp, http or https",
+                    scheme
+                )))
+            }
+        };
+
+        let (host, port) = match address.rfind(':') {
+            Some(i) => (
+                address[..i].to_string(),
+                address[i + 1..].parse().map_err(|_| invalid())?,
+            ),
+            None => (address, default_port),
+        };
+        if host.is_empty() || host.contains('/') {
+            return Err(invalid());
+        }
+
+        if transport != Transport::Udp && database.is_empty() {
+            return Err(InfluxError(
+                "a database is required to report metrics over HTTP".into(),
+            ));
+        }
+
+        Ok(InfluxConfig {
+            transport,
+            host,
+            port,
+            database,
+            username,
+            password,
+        })
+    }
+}
+
+#[derive(Default)]
+struct Measurements {
+    counters: HashMap<String, u64>,
+    gauges: HashMap<String, i64>,
+}
+
+/// Locks the measurements, carrying on with them if a thread panicked while
+/// holding the lock; a counter or gauge left half updated is not worth
+/// panicking over.
+fn lock(measurements: &Mutex<Measurements>) -> MutexGuard<Measurements> {
+    measurements.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// A metrics recorder that batches counters and gauges in memory, and
+/// reports them from a background thread.
+pub struct InfluxRecorder {
+    measurements: Arc<Mutex<Measurements>>,
+}
+
+impl InfluxRecorder {
+    pub fn init(config: InfluxConfig) -> Result<(), metrics::SetRecorderError> {
+        let measurements = Arc::new(Mutex::new(Measurements::default()));
+        metrics::set_boxed_recorder(Box::new(InfluxRecorder {
+            measurements: measurements.clone(),
+        }))?;
+
+        info!(
+            "Reporting Rust metrics to {}:{} over {:?}",
+            config.host, config.port, config.transport
+        );
+
+        let host = hostname();
+        thread::Builder::new()
+            .name("InfluxMetricsReporter".into())
+            .spawn(move || loop {
+                thread::sleep(REPORTING_INTERVAL);
+
+                let lines = to_lines(&lock(&measurements), &host, timestamp());
+                if lines.is_empty() {
+                    continue;
+                }
+
+                if let Err(err) = report(&config, &lines) {
+                    warn!("Unable to report metrics to InfluxDB: {}", err);
+                }
+            })
+            .expect("Unable to start the metrics reporter thread");
+
+        Ok(())
+    }
+}
+
+impl Recorder for InfluxRecorder {
+    fn increment_counter(&self, key: Key, value: u64) {
+        *lock(&self.measurements)
+            .counters
+            .entry(key.name().to_string())
+            .or_insert(0) += value;
+    }
+
+    fn update_gauge(&self, key: Key, value: i64) {
+        lock(&self.measurements)
+            .gauges
+            .insert(key.name().to_string(), value);
+    }
+
+    fn record_histogram(&self, _key: Key, _value: u64) {}
+}
+
+/// Formats the measurements as lines of the line protocol, sorted so that
+/// each report lists its series in the same order.
+fn to_lines(measurements: &Measurements, host: &str, timestamp: u128) -> Vec<String> {
+    let line = |name: &str, field: String| {
+        format!(
+            "{}.{},host={} {} {}",
+            PREFIX,
+            escape(name),
+            escape(host),
+            field,
+            timestamp
+        )
+    };
+
+    let mut lines: Vec<String> = measurements
+        .counters
+        .iter()
+        .map(|(name, count)| line(name, format!("count={}", count)))
+        .chain(
+            measurements
+                .gauges
+                .iter()
+                .map(|(name, value)| line(name, format!("value={}", value))),
+        )
+        .collect();
+    lines.sort();
+    lines
+}
+
+/// Escapes the characters that separate the parts of a line.
+fn escape(s: &str) -> String {
+    s.replace(',', "\\,")
+        .replace(' ', "\\ ")
+        .replace('=', "\\=")
+}
+
+fn report(config: &InfluxConfig, lines: &[String]) -> Result<(), InfluxError> {
+    match config.transport {
+        Transport::Udp => send_udp(config, lines),
+        Transport::Http | Transport::Https => send_http(config, &lines.join("\n")),
+    }
+    .map_err(|err| InfluxError(format!("{}:{}: {}", config.host, config.port, err)))
+}
+
+fn send_udp(config: &InfluxConfig, lines: &[String]) -> Result<(), String> {
+    let socket = UdpSocket::bind("0.0.0.0:0").map_err(|err| err.to_string())?;
+    socket
+        .connect((config.host.as_str(), config.port))
+        .map_err(|err| err.to_string())?;
+
+    for datagram in datagrams(lines, MAX_DATAGRAM_SIZE) {
+        socket
+            .send(datagram.as_bytes())
+            .map_err(|err| err.to_string())?;
+    }
+
+    Ok(())
+}
+
+/// Packs lines into as few datagrams of at most max_size bytes as possible,
+/// without splitting a line. A longer line is sent on its own.
+fn datagrams(lines: &[String], max_size: usize) -> Vec<String> {
+    let mut datagrams: Vec<String> = vec![];
+    for line in lines {
+        match datagrams.last_mut() {
+            Some(ref mut datagram) if datagram.len() + 1 + line.len() <= max_size => {
+                datagram.push('\n');
+                datagram.push_str(line);
+            }
+            _ => datagrams.push(line.clone()),
+        }
+    }
+    datagrams
+}
+
+fn send_http(config: &InfluxConfig, body: &str) -> Result<(), String> {
+    let address = (config.host.as_str(), config.port)
+        .to_socket_addrs()
+        .map_err(|err| err.to_string())?
+        .next()
+        .ok_or("unable to resolve host")?;
+    let stream =
+        TcpStream::connect_timeout(&address, CONNECTION_TIMEOUT).map_err(|err| err.to_string())?;
+    stream
+        .set_read_timeout(Some(CONNECTION_TIMEOUT))
+        .and_then(|_| stream.set_write_timeout(Some(CONNECTION_TIMEOUT)))
+        .map_err(|err| err.to_string())?;
+
+    let request = http_request(config, body);
+    let response = if config.transport == Transport::Https {
+        let connector = SslConnector::builder(SslMethod::tls())
+            .map_err(|err| err.to_string())?
+            .build();
+        let mut stream = connector
+            .connect(&config.host, stream)
+            .map_err(|err| err.to_string())?;
+        exchange(&mut stream, &request)?
+    } else {
+        let mut stream = stream;
+        exchange(&mut stream, &request)?
+    };
+
+    // InfluxDB answers a successful write with 204 No Content
+    let status = response.lines().next().unwrap_or_default();
+    if status
+        .split(' ')
+        .nth(1)
+        .map_or(false, |code| code.starts_with('2'))
+    {
+        Ok(())
+    } else {
+        Err(format!("write failed: {}", status))
+    }
+}
+
+fn exchange<S: Read + Write>(stream: &mut S, request: &str) -> Result<String, String> {
+    stream
+        .write_all(request.as_bytes())
+        .map_err(|err| err.to_string())?;
+    let mut response = vec![];
+    stream
+        .read_to_end(&mut response)
+        .map_err(|err| err.to_string())?;
+    Ok(String::from_utf8_lossy(&response).into_owned())
+}
+
+fn http_request(config: &InfluxConfig, body: &str) -> String {
+    let mut path = format!("/write?db={}", percent_encode(&config.database));
+    if let Some(ref username) = config.username {
+        path.push_str(&format!("&u={}", percent_encode(username)));
+    }
+    if let Some(ref password) = config.password {
+        path.push_str(&format!("&p={}", percent_encode(password)));
+    }
+
+    format!(
+        "POST {} HTTP/1.1\r\n\
+         Host: {}:{}\r\n\
+         Content-Type: text/plain; charset=utf-8\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\
+         \r\n\
+         {}",
+        path,
+        config.host,
+        config.port,
+        body.len(),
+        body
+    )
+}
+
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn timestamp() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0)
+}
+
+/// The host the validator runs on, as reported by Python's platform.node()
+fn hostname() -> String {
+    let mut buf = [0u8; 256];
+    let result = unsafe { libc::gethostname(buf.as_mut_ptr() as *mut libc::c_char, buf.len()) };
+    if result != 0 {
+        return "unknown".into();
+    }
+    let len = buf
+        .iter()
+        .position(|&b| b == 0)
+        .unwrap_or_else(|| buf.len());
+    String::from_utf8_lossy(&buf[..len]).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config = InfluxConfig::new("udp://telegraf", "".into(), None, None).unwrap();
+        assert_eq!(config.transport, Transport::Udp);
+        assert_eq!(config.host, "telegraf");
+        assert_eq!(config.port, DEFAULT_UDP_PORT);
+
+        let config = InfluxConfig::new(
+            "https://influx.example.com:9086/",
+            "metrics".into(),
+            Some("sawtooth".into()),
+            None,
+        )
+        .unwrap();
+        assert_eq!(config.transport, Transport::Https);
+        assert_eq!(config.host, "influx.example.com");
+        assert_eq!(config.port, 9086);
+
+        assert!(InfluxConfig::new("http://influx:8086", "".into(), None, None).is_err());
+        assert!(InfluxConfig::new("tcp://influx:8086", "metrics".into(), None, None).is_err());
+        assert!(InfluxConfig::new("influx:8086", "metrics".into(), None, None).is_err());
+        assert!(InfluxConfig::new("http://influx:port", "metrics".into(), None, None).is_err());
+    }
+
+    #[test]
+    fn test_to_lines() {
+        let mut measurements = Measurements::default();
+        measurements.counters.insert("chain.commits".into(), 3);
+        measurements.gauges.insert("chain head, num".into(), -1);
+
+        assert_eq!(
+            to_lines(&measurements, "validator-0", 1_500_000_000),
+            vec![
+                "sawtooth_validator.chain.commits,host=validator-0 count=3 1500000000",
+                "sawtooth_validator.chain\\ head\\,\\ num,host=validator-0 value=-1 1500000000",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_datagrams() {
+        let lines: Vec<String> = vec!["aaaa".into(), "bbbb".into(), "cccc".into(), "d".into()];
+        assert_eq!(datagrams(&lines, 9), vec!["aaaa\nbbbb", "cccc\nd"]);
+        assert_eq!(datagrams(&lines, 3), vec!["aaaa", "bbbb", "cccc", "d"]);
+    }
+
+    #[test]
+    fn test_http_request() {
+        let config = InfluxConfig::new(
+            "http://influx:8086",
+            "metrics".into(),
+            Some("user".into()),
+            Some("p@ss word".into()),
+        )
+        .unwrap();
+
+        assert_eq!(
+            http_request(&config, "a count=1 1"),
+            "POST /write?db=metrics&u=user&p=p%40ss%20word HTTP/1.1\r\n\
+             Host: influx:8086\r\n\
+             Content-Type: text/plain; charset=utf-8\r\n\
+             Content-Length: 11\r\n\
+             Connection: close\r\n\
+             \r\n\
+             a count=1 1"
+        );
+    }
+}
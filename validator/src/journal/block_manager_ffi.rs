@@ -15,9 +15,11 @@
  * ------------------------------------------------------------------------------
  */
 
+use std::cell::RefCell;
 use std::ffi::CStr;
 use std::mem;
 use std::os::raw::{c_char, c_void};
+use std::panic::{self, AssertUnwindSafe};
 use std::slice;
 
 use sawtooth::{
@@ -32,7 +34,7 @@ use sawtooth::{
 };
 
 #[repr(u32)]
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum ErrorCode {
     Success = 0,
     NullPointerProvided = 0x01,
@@ -42,30 +44,137 @@ pub enum ErrorCode {
     UnknownBlock = 0x05,
     InvalidInputString = 0x06,
     Error = 0x07,
+    InvalidBlockBytes = 0x08,
+    Panic = 0x09,
     InvalidPythonObject = 0x10,
     StopIteration = 0x11,
 }
 
+thread_local! {
+    // The message describing the last error returned on this thread, if any
+    static LAST_ERROR: RefCell<Option<String>> = RefCell::new(None);
+}
+
 macro_rules! check_null {
     ($($arg:expr) , *) => {
-        $(if $arg.is_null() { return ErrorCode::NullPointerProvided; })*
+        $(if $arg.is_null() {
+            return fail(ErrorCode::NullPointerProvided, "Provided null pointer(s)");
+        })*
+    }
+}
+
+/// Records the message describing an error, to be retrieved by the caller
+/// with `block_manager_last_error`, and returns the error's code.
+fn fail<S: Into<String>>(code: ErrorCode, msg: S) -> ErrorCode {
+    let msg = msg.into();
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = Some(msg));
+    code
+}
+
+/// Runs the body of an FFI function, converting a panic into
+/// `ErrorCode::Panic` so that it does not unwind into the caller.
+fn ffi_boundary<F>(name: &str, f: F) -> ErrorCode
+where
+    F: FnOnce() -> ErrorCode,
+{
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = None);
+
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(code) => code,
+        Err(payload) => {
+            let msg = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown cause".into());
+            error!("Panic in {}: {}", name, msg);
+            fail(ErrorCode::Panic, format!("{} panicked: {}", name, msg))
+        }
     }
 }
 
+/// Hands a byte vector to the caller, which must reclaim it.
+unsafe fn return_bytes(
+    bytes: Vec<u8>,
+    bytes_ptr: *mut *const u8,
+    bytes_len: *mut usize,
+    bytes_cap: *mut usize,
+) {
+    *bytes_cap = bytes.capacity();
+    *bytes_len = bytes.len();
+    *bytes_ptr = bytes.as_slice().as_ptr();
+
+    mem::forget(bytes);
+}
+
+unsafe fn to_str<'a>(c_str: *const c_char, code: ErrorCode) -> Result<&'a str, ErrorCode> {
+    CStr::from_ptr(c_str)
+        .to_str()
+        .map_err(|err| fail(code, format!("Invalid input string: {}", err)))
+}
+
+/// Converts an error from the block manager into an error code, recording
+/// its message.
+fn block_manager_error(operation: &str, err: BlockManagerError) -> ErrorCode {
+    let code = match err {
+        BlockManagerError::MissingPredecessor(_) => ErrorCode::MissingPredecessor,
+        BlockManagerError::MissingPredecessorInBranch(_) => ErrorCode::MissingPredecessorInBranch,
+        BlockManagerError::MissingInput => ErrorCode::MissingInput,
+        BlockManagerError::UnknownBlock => ErrorCode::UnknownBlock,
+        _ => {
+            error!(
+                "Unexpected error calling BlockManager.{}: {:?}",
+                operation, err
+            );
+            ErrorCode::Error
+        }
+    };
+
+    fail(
+        code,
+        format!("BlockManager.{} failed: {:?}", operation, err),
+    )
+}
+
+/// Returns the message describing the last error returned by a
+/// block_manager function on the calling thread, or an empty message if the
+/// last call succeeded.
 #[no_mangle]
-pub unsafe extern "C" fn block_manager_new(block_manager_ptr: *mut *const c_void) -> ErrorCode {
-    let block_manager = BlockManager::new();
+pub unsafe extern "C" fn block_manager_last_error(
+    msg_bytes: *mut *const u8,
+    msg_len: *mut usize,
+    msg_cap: *mut usize,
+) -> ErrorCode {
+    if msg_bytes.is_null() || msg_len.is_null() || msg_cap.is_null() {
+        return ErrorCode::NullPointerProvided;
+    }
 
-    *block_manager_ptr = Box::into_raw(Box::new(block_manager)) as *const c_void;
+    let msg = LAST_ERROR.with(|last_error| last_error.borrow().clone().unwrap_or_default());
+    return_bytes(msg.into_bytes(), msg_bytes, msg_len, msg_cap);
 
     ErrorCode::Success
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn block_manager_new(block_manager_ptr: *mut *const c_void) -> ErrorCode {
+    ffi_boundary("block_manager_new", || {
+        check_null!(block_manager_ptr);
+
+        let block_manager = BlockManager::new();
+
+        *block_manager_ptr = Box::into_raw(Box::new(block_manager)) as *const c_void;
+
+        ErrorCode::Success
+    })
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn block_manager_drop(block_manager: *mut c_void) -> ErrorCode {
-    check_null!(block_manager);
-    Box::from_raw(block_manager as *mut BlockManager);
-    ErrorCode::Success
+    ffi_boundary("block_manager_drop", || {
+        check_null!(block_manager);
+        Box::from_raw(block_manager as *mut BlockManager);
+        ErrorCode::Success
+    })
 }
 
 #[no_mangle]
@@ -74,23 +183,22 @@ pub unsafe extern "C" fn block_manager_contains(
     block_id: *const c_char,
     result: *mut bool,
 ) -> ErrorCode {
-    check_null!(block_manager, block_id);
-
-    let block_id = match CStr::from_ptr(block_id).to_str() {
-        Ok(s) => s,
-        Err(_) => return ErrorCode::InvalidInputString,
-    };
-
-    match (*(block_manager as *mut BlockManager)).contains(block_id) {
-        Ok(contains) => {
-            *result = contains;
-            ErrorCode::Success
-        }
-        Err(err) => {
-            error!("Unexpected error calling BlockManager.contains: {:?}", err);
-            ErrorCode::Error
+    ffi_boundary("block_manager_contains", || {
+        check_null!(block_manager, block_id, result);
+
+        let block_id = match to_str(block_id, ErrorCode::InvalidInputString) {
+            Ok(s) => s,
+            Err(code) => return code,
+        };
+
+        match (*(block_manager as *mut BlockManager)).contains(block_id) {
+            Ok(contains) => {
+                *result = contains;
+                ErrorCode::Success
+            }
+            Err(err) => block_manager_error("contains", err),
         }
-    }
+    })
 }
 
 #[no_mangle]
@@ -98,18 +206,22 @@ pub unsafe extern "C" fn block_manager_add_commit_store(
     block_manager: *mut c_void,
     commit_store: *mut c_void,
 ) -> ErrorCode {
-    check_null!(block_manager, commit_store);
+    ffi_boundary("block_manager_add_commit_store", || {
+        check_null!(block_manager, commit_store);
 
-    let commit_store = Box::from_raw(commit_store as *mut CommitStore);
+        let commit_store = Box::from_raw(commit_store as *mut CommitStore);
 
-    let rc = (*(block_manager as *mut BlockManager))
-        .add_store("commit_store", commit_store.clone())
-        .map(|_| ErrorCode::Success)
-        .unwrap_or(ErrorCode::Error);
+        let rc = match (*(block_manager as *mut BlockManager))
+            .add_store("commit_store", commit_store.clone())
+        {
+            Ok(_) => ErrorCode::Success,
+            Err(err) => block_manager_error("add_store", err),
+        };
 
-    Box::into_raw(commit_store);
+        Box::into_raw(commit_store);
 
-    rc
+        rc
+    })
 }
 
 #[no_mangle]
@@ -118,25 +230,23 @@ pub unsafe extern "C" fn block_manager_persist(
     block_id: *const c_char,
     store_name: *const c_char,
 ) -> ErrorCode {
-    check_null!(block_manager, block_id, store_name);
-
-    let block_id = match CStr::from_ptr(block_id).to_str() {
-        Ok(s) => s,
-        Err(_) => return ErrorCode::InvalidInputString,
-    };
-    let name = match CStr::from_ptr(store_name).to_str() {
-        Ok(s) => s,
-        Err(_) => return ErrorCode::InvalidInputString,
-    };
-
-    (*(block_manager as *mut BlockManager))
-        .persist(block_id, name)
-        .map(|_| ErrorCode::Success)
-        .map_err(|err| {
-            error!("Unexpected error calling BlockManager.persist: {:?}", err);
-            err
-        })
-        .unwrap_or(ErrorCode::Error)
+    ffi_boundary("block_manager_persist", || {
+        check_null!(block_manager, block_id, store_name);
+
+        let block_id = match to_str(block_id, ErrorCode::InvalidInputString) {
+            Ok(s) => s,
+            Err(code) => return code,
+        };
+        let name = match to_str(store_name, ErrorCode::InvalidInputString) {
+            Ok(s) => s,
+            Err(code) => return code,
+        };
+
+        match (*(block_manager as *mut BlockManager)).persist(block_id, name) {
+            Ok(_) => ErrorCode::Success,
+            Err(err) => block_manager_error("persist", err),
+        }
+    })
 }
 
 #[repr(C)]
@@ -152,34 +262,40 @@ pub unsafe extern "C" fn block_manager_put(
     branch: *const *const c_void,
     branch_len: usize,
 ) -> ErrorCode {
-    check_null!(block_manager, branch);
-
-    let branch_result: Result<Vec<BlockPair>, ErrorCode> =
-        slice::from_raw_parts(branch, branch_len)
-            .iter()
-            .map(|ptr| {
-                let entry = *ptr as *const PutEntry;
-                let payload = slice::from_raw_parts((*entry).block_bytes, (*entry).block_bytes_len);
-                Ok(BlockPair::from_bytes(&payload).expect("Failed to parse block bytes"))
-            })
-            .collect();
-
-    match branch_result {
-        Ok(branch) => match (*(block_manager as *mut BlockManager)).put(branch) {
-            Err(BlockManagerError::MissingPredecessor(_)) => ErrorCode::MissingPredecessor,
-            Err(BlockManagerError::MissingInput) => ErrorCode::MissingInput,
-            Err(BlockManagerError::MissingPredecessorInBranch(_)) => {
-                ErrorCode::MissingPredecessorInBranch
-            }
-            Err(_) => ErrorCode::Error,
-            // Cannot pass block reference across FFI boundary
-            Ok(_) => ErrorCode::Success,
-        },
-        Err(err) => {
-            error!("Error processing branch: {:?}", err);
-            ErrorCode::Error
+    ffi_boundary("block_manager_put", || {
+        check_null!(block_manager, branch);
+
+        let branch_result: Result<Vec<BlockPair>, ErrorCode> =
+            slice::from_raw_parts(branch, branch_len)
+                .iter()
+                .map(|ptr| {
+                    let entry = *ptr as *const PutEntry;
+                    if entry.is_null() || (*entry).block_bytes.is_null() {
+                        return Err(fail(
+                            ErrorCode::NullPointerProvided,
+                            "Provided null pointer(s)",
+                        ));
+                    }
+                    let payload =
+                        slice::from_raw_parts((*entry).block_bytes, (*entry).block_bytes_len);
+                    BlockPair::from_bytes(&payload).map_err(|err| {
+                        fail(
+                            ErrorCode::InvalidBlockBytes,
+                            format!("Failed to parse block bytes: {:?}", err),
+                        )
+                    })
+                })
+                .collect();
+
+        match branch_result {
+            Ok(branch) => match (*(block_manager as *mut BlockManager)).put(branch) {
+                // Cannot pass block reference across FFI boundary
+                Ok(_) => ErrorCode::Success,
+                Err(err) => block_manager_error("put", err),
+            },
+            Err(code) => code,
         }
-    }
+    })
 }
 
 #[no_mangle]
@@ -187,22 +303,20 @@ pub unsafe extern "C" fn block_manager_ref_block(
     block_manager: *mut c_void,
     block_id: *const c_char,
 ) -> ErrorCode {
-    check_null!(block_manager, block_id);
+    ffi_boundary("block_manager_ref_block", || {
+        check_null!(block_manager, block_id);
 
-    let block_id = match CStr::from_ptr(block_id).to_str() {
-        Ok(s) => s,
-        Err(_) => return ErrorCode::InvalidInputString,
-    };
+        let block_id = match to_str(block_id, ErrorCode::InvalidInputString) {
+            Ok(s) => s,
+            Err(code) => return code,
+        };
 
-    match (*(block_manager as *mut BlockManager)).ref_block(block_id) {
-        // Cannot pass block reference across FFI boundary
-        Ok(_) => ErrorCode::Success,
-        Err(BlockManagerError::UnknownBlock) => ErrorCode::UnknownBlock,
-        Err(err) => {
-            error!("Unexpected error while ref'ing block: {:?}", err);
-            ErrorCode::Error
+        match (*(block_manager as *mut BlockManager)).ref_block(block_id) {
+            // Cannot pass block reference across FFI boundary
+            Ok(_) => ErrorCode::Success,
+            Err(err) => block_manager_error("ref_block", err),
         }
-    }
+    })
 }
 
 #[no_mangle]
@@ -210,21 +324,19 @@ pub unsafe extern "C" fn block_manager_unref_block(
     block_manager: *mut c_void,
     block_id: *const c_char,
 ) -> ErrorCode {
-    check_null!(block_manager, block_id);
+    ffi_boundary("block_manager_unref_block", || {
+        check_null!(block_manager, block_id);
 
-    let block_id = match CStr::from_ptr(block_id).to_str() {
-        Ok(s) => s,
-        Err(_) => return ErrorCode::InvalidInputString,
-    };
+        let block_id = match to_str(block_id, ErrorCode::InvalidInputString) {
+            Ok(s) => s,
+            Err(code) => return code,
+        };
 
-    match (*(block_manager as *mut BlockManager)).unref_block(block_id) {
-        Ok(_) => ErrorCode::Success,
-        Err(BlockManagerError::UnknownBlock) => ErrorCode::UnknownBlock,
-        Err(err) => {
-            error!("Unexpected error while unref'ing block: {:?}", err);
-            ErrorCode::Error
+        match (*(block_manager as *mut BlockManager)).unref_block(block_id) {
+            Ok(_) => ErrorCode::Success,
+            Err(err) => block_manager_error("unref_block", err),
         }
-    }
+    })
 }
 
 #[no_mangle]
@@ -234,29 +346,33 @@ pub unsafe extern "C" fn block_manager_get_iterator_new(
     block_ids_len: usize,
     iterator: *mut *const c_void,
 ) -> ErrorCode {
-    check_null!(block_manager, block_ids);
+    ffi_boundary("block_manager_get_iterator_new", || {
+        check_null!(block_manager, block_ids, iterator);
 
-    let block_ids = match slice::from_raw_parts(block_ids, block_ids_len)
-        .iter()
-        .map(|c_str| CStr::from_ptr(*c_str).to_str())
-        .collect::<Result<Vec<&str>, _>>()
-    {
-        Ok(ids) => ids,
-        Err(_) => return ErrorCode::InvalidPythonObject,
-    };
-
-    *iterator =
-        Box::into_raw((*(block_manager as *mut BlockManager)).get(&block_ids)) as *const c_void;
-
-    ErrorCode::Success
+        let block_ids = match slice::from_raw_parts(block_ids, block_ids_len)
+            .iter()
+            .map(|c_str| to_str(*c_str, ErrorCode::InvalidPythonObject))
+            .collect::<Result<Vec<&str>, _>>()
+        {
+            Ok(ids) => ids,
+            Err(code) => return code,
+        };
+
+        *iterator =
+            Box::into_raw((*(block_manager as *mut BlockManager)).get(&block_ids)) as *const c_void;
+
+        ErrorCode::Success
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn block_manager_get_iterator_drop(iterator: *mut c_void) -> ErrorCode {
-    check_null!(iterator);
+    ffi_boundary("block_manager_get_iterator_drop", || {
+        check_null!(iterator);
 
-    Box::from_raw(iterator as *mut GetBlockIterator);
-    ErrorCode::Success
+        Box::from_raw(iterator as *mut GetBlockIterator);
+        ErrorCode::Success
+    })
 }
 
 #[no_mangle]
@@ -266,21 +382,16 @@ pub unsafe extern "C" fn block_manager_get_iterator_next(
     block_len: *mut usize,
     block_cap: *mut usize,
 ) -> ErrorCode {
-    check_null!(iterator);
-
-    if let Some(Some(block_pair)) = (*(iterator as *mut GetBlockIterator)).next() {
-        let bytes = block_pair.into_bytes().expect("Failed to serialize block");
-
-        *block_cap = bytes.capacity();
-        *block_len = bytes.len();
-        *block_bytes = bytes.as_slice().as_ptr();
+    ffi_boundary("block_manager_get_iterator_next", || {
+        check_null!(iterator, block_bytes, block_len, block_cap);
 
-        mem::forget(bytes);
-
-        return ErrorCode::Success;
-    }
-
-    ErrorCode::StopIteration
+        match (*(iterator as *mut GetBlockIterator)).next() {
+            Some(Some(block_pair)) => {
+                return_block_pair(block_pair, block_bytes, block_len, block_cap)
+            }
+            _ => ErrorCode::StopIteration,
+        }
+    })
 }
 
 #[no_mangle]
@@ -289,26 +400,29 @@ pub unsafe extern "C" fn block_manager_branch_iterator_new(
     tip: *const c_char,
     iterator: *mut *const c_void,
 ) -> ErrorCode {
-    check_null!(block_manager, tip);
-
-    let tip = match CStr::from_ptr(tip).to_str() {
-        Ok(s) => s,
-        Err(_) => return ErrorCode::InvalidPythonObject,
-    };
-    match (*(block_manager as *mut BlockManager)).branch(tip) {
-        Ok(branch) => *iterator = Box::into_raw(branch) as *const c_void,
-        Err(BlockManagerError::UnknownBlock) => return ErrorCode::UnknownBlock,
-        Err(_) => return ErrorCode::Error,
-    }
+    ffi_boundary("block_manager_branch_iterator_new", || {
+        check_null!(block_manager, tip, iterator);
+
+        let tip = match to_str(tip, ErrorCode::InvalidPythonObject) {
+            Ok(s) => s,
+            Err(code) => return code,
+        };
+        match (*(block_manager as *mut BlockManager)).branch(tip) {
+            Ok(branch) => *iterator = Box::into_raw(branch) as *const c_void,
+            Err(err) => return block_manager_error("branch", err),
+        }
 
-    ErrorCode::Success
+        ErrorCode::Success
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn block_manager_branch_iterator_drop(iterator: *mut c_void) -> ErrorCode {
-    check_null!(iterator);
-    Box::from_raw(iterator as *mut BranchIterator);
-    ErrorCode::Success
+    ffi_boundary("block_manager_branch_iterator_drop", || {
+        check_null!(iterator);
+        Box::from_raw(iterator as *mut BranchIterator);
+        ErrorCode::Success
+    })
 }
 
 #[no_mangle]
@@ -318,21 +432,32 @@ pub unsafe extern "C" fn block_manager_branch_iterator_next(
     block_len: *mut usize,
     block_cap: *mut usize,
 ) -> ErrorCode {
-    check_null!(iterator);
-
-    if let Some(block_pair) = (*(iterator as *mut BranchIterator)).next() {
-        let bytes = block_pair.into_bytes().expect("Failed to serialize block");
+    ffi_boundary("block_manager_branch_iterator_next", || {
+        check_null!(iterator, block_bytes, block_len, block_cap);
 
-        *block_cap = bytes.capacity();
-        *block_len = bytes.len();
-        *block_bytes = bytes.as_slice().as_ptr();
-
-        mem::forget(bytes);
+        match (*(iterator as *mut BranchIterator)).next() {
+            Some(block_pair) => return_block_pair(block_pair, block_bytes, block_len, block_cap),
+            None => ErrorCode::StopIteration,
+        }
+    })
+}
 
-        return ErrorCode::Success;
+unsafe fn return_block_pair(
+    block_pair: BlockPair,
+    block_bytes: *mut *const u8,
+    block_len: *mut usize,
+    block_cap: *mut usize,
+) -> ErrorCode {
+    match block_pair.into_bytes() {
+        Ok(bytes) => {
+            return_bytes(bytes, block_bytes, block_len, block_cap);
+            ErrorCode::Success
+        }
+        Err(err) => fail(
+            ErrorCode::Error,
+            format!("Failed to serialize block: {:?}", err),
+        ),
     }
-
-    ErrorCode::StopIteration
 }
 
 #[no_mangle]
@@ -342,33 +467,36 @@ pub unsafe extern "C" fn block_manager_branch_diff_iterator_new(
     exclude: *const c_char,
     iterator: *mut *const c_void,
 ) -> ErrorCode {
-    check_null!(block_manager, tip, exclude);
-
-    let tip = match CStr::from_ptr(tip).to_str() {
-        Ok(s) => s,
-        Err(_) => return ErrorCode::InvalidPythonObject,
-    };
-
-    let exclude = match CStr::from_ptr(exclude).to_str() {
-        Ok(s) => s,
-        Err(_) => return ErrorCode::InvalidPythonObject,
-    };
-    match (*(block_manager as *mut BlockManager)).branch_diff(tip, exclude) {
-        Ok(branch_diff) => *iterator = Box::into_raw(branch_diff) as *const c_void,
-        Err(BlockManagerError::UnknownBlock) => return ErrorCode::UnknownBlock,
-        Err(_) => return ErrorCode::Error,
-    }
+    ffi_boundary("block_manager_branch_diff_iterator_new", || {
+        check_null!(block_manager, tip, exclude, iterator);
+
+        let tip = match to_str(tip, ErrorCode::InvalidPythonObject) {
+            Ok(s) => s,
+            Err(code) => return code,
+        };
+
+        let exclude = match to_str(exclude, ErrorCode::InvalidPythonObject) {
+            Ok(s) => s,
+            Err(code) => return code,
+        };
+        match (*(block_manager as *mut BlockManager)).branch_diff(tip, exclude) {
+            Ok(branch_diff) => *iterator = Box::into_raw(branch_diff) as *const c_void,
+            Err(err) => return block_manager_error("branch_diff", err),
+        }
 
-    ErrorCode::Success
+        ErrorCode::Success
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn block_manager_branch_diff_iterator_drop(
     iterator: *mut c_void,
 ) -> ErrorCode {
-    check_null!(iterator);
-    Box::from_raw(iterator as *mut BranchDiffIterator);
-    ErrorCode::Success
+    ffi_boundary("block_manager_branch_diff_iterator_drop", || {
+        check_null!(iterator);
+        Box::from_raw(iterator as *mut BranchDiffIterator);
+        ErrorCode::Success
+    })
 }
 
 #[no_mangle]
@@ -378,21 +506,14 @@ pub unsafe extern "C" fn block_manager_branch_diff_iterator_next(
     block_len: *mut usize,
     block_cap: *mut usize,
 ) -> ErrorCode {
-    check_null!(iterator);
-
-    if let Some(block_pair) = (*(iterator as *mut BranchDiffIterator)).next() {
-        let bytes = block_pair.into_bytes().expect("Failed to serialize block");
-
-        *block_cap = bytes.capacity();
-        *block_len = bytes.len();
-        *block_bytes = bytes.as_slice().as_ptr();
+    ffi_boundary("block_manager_branch_diff_iterator_next", || {
+        check_null!(iterator, block_bytes, block_len, block_cap);
 
-        mem::forget(bytes);
-
-        return ErrorCode::Success;
-    }
-
-    ErrorCode::StopIteration
+        match (*(iterator as *mut BranchDiffIterator)).next() {
+            Some(block_pair) => return_block_pair(block_pair, block_bytes, block_len, block_cap),
+            None => ErrorCode::StopIteration,
+        }
+    })
 }
 
 #[cfg(test)]
@@ -502,6 +623,108 @@ mod test {
         })
     }
 
+    /// Null pointers and unknown blocks are reported as error codes, with a
+    /// message retrievable through `block_manager_last_error`.
+    #[test]
+    fn ffi_reports_errors() {
+        unsafe {
+            let mut block_manager: *const c_void = std::ptr::null();
+            assert_eq!(block_manager_new(&mut block_manager), ErrorCode::Success);
+            let block_manager = block_manager as *mut c_void;
+
+            assert_eq!(
+                block_manager_ref_block(block_manager, std::ptr::null()),
+                ErrorCode::NullPointerProvided
+            );
+            assert_eq!(last_error(), "Provided null pointer(s)");
+
+            let block_id = std::ffi::CString::new("unknown").unwrap();
+            assert_eq!(
+                block_manager_ref_block(block_manager, block_id.as_ptr()),
+                ErrorCode::UnknownBlock
+            );
+            assert!(last_error().contains("UnknownBlock"));
+
+            assert_eq!(
+                block_manager_persist(block_manager, block_id.as_ptr(), block_id.as_ptr()),
+                ErrorCode::UnknownBlock
+            );
+
+            // A successful call clears the last error
+            let mut contains = true;
+            assert_eq!(
+                block_manager_contains(block_manager, block_id.as_ptr(), &mut contains),
+                ErrorCode::Success
+            );
+            assert!(!contains);
+            assert_eq!(last_error(), "");
+
+            assert_eq!(block_manager_drop(block_manager), ErrorCode::Success);
+        }
+    }
+
+    /// Blocks that cannot be parsed and blocks without their predecessors
+    /// are rejected by `block_manager_put` without panicking.
+    #[test]
+    fn ffi_put_reports_errors() {
+        unsafe {
+            let mut block_manager: *const c_void = std::ptr::null();
+            assert_eq!(block_manager_new(&mut block_manager), ErrorCode::Success);
+            let block_manager = block_manager as *mut c_void;
+
+            let mut garbage = vec![0xff_u8; 16];
+            let entry = PutEntry {
+                block_bytes: garbage.as_mut_ptr(),
+                block_bytes_len: garbage.len(),
+            };
+            let branch = [&entry as *const PutEntry as *const c_void];
+            assert_eq!(
+                block_manager_put(block_manager, branch.as_ptr(), branch.len()),
+                ErrorCode::InvalidBlockBytes
+            );
+            assert!(last_error().starts_with("Failed to parse block bytes"));
+
+            let block_a = create_block(NULL_BLOCK_IDENTIFIER, 1, None);
+            let block_b = create_block(block_a.block().header_signature(), 2, None);
+            let mut bytes = block_b.into_bytes().unwrap();
+            let entry = PutEntry {
+                block_bytes: bytes.as_mut_ptr(),
+                block_bytes_len: bytes.len(),
+            };
+            let branch = [&entry as *const PutEntry as *const c_void];
+            assert_eq!(
+                block_manager_put(block_manager, branch.as_ptr(), branch.len()),
+                ErrorCode::MissingPredecessor
+            );
+
+            assert_eq!(block_manager_drop(block_manager), ErrorCode::Success);
+        }
+    }
+
+    #[test]
+    fn ffi_boundary_catches_panics() {
+        assert_eq!(
+            ffi_boundary("test_function", || panic!("lock poisoned")),
+            ErrorCode::Panic
+        );
+        assert_eq!(
+            unsafe { last_error() },
+            "test_function panicked: lock poisoned"
+        );
+    }
+
+    unsafe fn last_error() -> String {
+        let mut msg_bytes: *const u8 = std::ptr::null();
+        let mut msg_len = 0;
+        let mut msg_cap = 0;
+        assert_eq!(
+            block_manager_last_error(&mut msg_bytes, &mut msg_len, &mut msg_cap),
+            ErrorCode::Success
+        );
+
+        String::from_utf8(Vec::from_raw_parts(msg_bytes as *mut u8, msg_len, msg_cap)).unwrap()
+    }
+
     /// `state_root_hash` should be set if two or more blocks with the same `previous_block_id` and
     /// `block_num` are created; this ensures that the resulting header signatures (IDs) of the
     /// blocks are different.
@@ -0,0 +1,743 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+//! Completion of the blocks and batches received from peers.
+//!
+//! A block is complete when its predecessor is in the block manager and it
+//! carries all the batches listed in its header, in that order. A batch is
+//! complete when the transactions it depends on have been seen or committed.
+//! The `Completer` holds incomplete blocks and batches until what they are
+//! missing arrives, requests the missing blocks and batches from peers, and
+//! requests them again if they are still missing when the request times out.
+//! Completed blocks and batches are passed on through a `CompleterService`.
+
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use protobuf::{Message, ProtobufError, RepeatedField};
+
+use proto::batch::Batch;
+use proto::block::{Block, BlockHeader};
+use proto::transaction::TransactionHeader;
+
+#[derive(Debug)]
+pub enum CompleterError {
+    /// The service failed to look up, store, request, or pass on a block or
+    /// batch.
+    ServiceError(String),
+    EncodingError(ProtobufError),
+}
+
+impl Error for CompleterError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            CompleterError::EncodingError(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for CompleterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CompleterError::ServiceError(ref msg) => write!(f, "ServiceError: {}", msg),
+            CompleterError::EncodingError(ref err) => write!(f, "EncodingError: {}", err),
+        }
+    }
+}
+
+impl From<ProtobufError> for CompleterError {
+    fn from(err: ProtobufError) -> Self {
+        CompleterError::EncodingError(err)
+    }
+}
+
+/// The block storage, network, and delivery operations the completer relies
+/// on.
+pub trait CompleterService: Send {
+    fn has_block(&self, block_id: &str) -> Result<bool, CompleterError>;
+
+    /// Stores a complete block. Returns false if its predecessor left the
+    /// block manager since it was checked.
+    fn put_block(&self, block: &Block) -> Result<bool, CompleterError>;
+
+    fn transaction_committed(&self, transaction_id: &str) -> Result<bool, CompleterError>;
+
+    fn request_block(&self, block_id: &str) -> Result<(), CompleterError>;
+
+    fn request_batch(&self, batch_id: &str) -> Result<(), CompleterError>;
+
+    fn request_batches_by_transaction_ids(
+        &self,
+        transaction_ids: &[String],
+    ) -> Result<(), CompleterError>;
+
+    /// Passes on a block once it is complete and stored.
+    fn block_completed(&self, block_id: &str) -> Result<(), CompleterError>;
+
+    /// Passes on a batch once it is complete.
+    fn batch_completed(&self, batch: &Batch) -> Result<(), CompleterError>;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Request {
+    Block,
+    Batch,
+    BatchByTransaction,
+}
+
+/// Values that are dropped once they have not been set for `keep_time`.
+struct TimedCache<V> {
+    keep_time: Duration,
+    entries: HashMap<String, (V, Instant)>,
+}
+
+impl<V> TimedCache<V> {
+    fn new(keep_time: Duration) -> Self {
+        TimedCache {
+            keep_time,
+            entries: HashMap::new(),
+        }
+    }
+
+    fn contains(&self, key: &str) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    fn get(&self, key: &str) -> Option<&V> {
+        self.entries.get(key).map(|(value, _)| value)
+    }
+
+    fn insert(&mut self, key: String, value: V, now: Instant) {
+        self.entries.insert(key, (value, now));
+    }
+
+    fn remove(&mut self, key: &str) -> Option<V> {
+        self.entries.remove(key).map(|(value, _)| value)
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Removes the values set more than `keep_time` before `now`, and
+    /// returns them.
+    fn purge(&mut self, now: Instant) -> Vec<(String, V)> {
+        let keep_time = self.keep_time;
+        let expired: Vec<String> = self
+            .entries
+            .iter()
+            .filter(|(_, (_, set))| now.duration_since(*set) > keep_time)
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        expired
+            .into_iter()
+            .filter_map(|key| {
+                let value = self.entries.remove(&key).map(|(value, _)| value)?;
+                Some((key, value))
+            })
+            .collect()
+    }
+}
+
+impl<V> TimedCache<Vec<V>> {
+    /// Adds `value` to the list at `key`, unless the list already holds a
+    /// value with the same id.
+    fn push<F>(&mut self, key: &str, value: V, now: Instant, id: F)
+    where
+        F: Fn(&V) -> &str,
+    {
+        let entry = self
+            .entries
+            .entry(key.into())
+            .or_insert_with(|| (vec![], now));
+        entry.1 = now;
+        if !entry.0.iter().any(|other| id(other) == id(&value)) {
+            entry.0.push(value);
+        }
+    }
+}
+
+pub struct Completer<S: CompleterService> {
+    service: S,
+    purge_frequency: Duration,
+    last_purge: Instant,
+    batch_cache: TimedCache<Batch>,
+    /// The ids of the batches of the transactions seen
+    seen_txns: TimedCache<String>,
+    /// Incomplete batches, by the id of a transaction they depend on
+    incomplete_batches: TimedCache<Vec<Batch>>,
+    /// Incomplete blocks, by the id of their predecessor or of a batch they
+    /// are missing
+    incomplete_blocks: TimedCache<Vec<Block>>,
+    requested: TimedCache<Request>,
+}
+
+impl<S: CompleterService> Completer<S> {
+    /// `requested_keep_time` should be less than `cache_keep_time`, so that a
+    /// request times out while the blocks and batches waiting on it are
+    /// still held.
+    pub fn new(
+        service: S,
+        cache_keep_time: Duration,
+        cache_purge_frequency: Duration,
+        requested_keep_time: Duration,
+    ) -> Self {
+        gauge!("seen_txns_length", 0);
+        gauge!("incomplete_blocks_length", 0);
+        gauge!("incomplete_batches_length", 0);
+
+        Completer {
+            service,
+            purge_frequency: cache_purge_frequency,
+            last_purge: Instant::now(),
+            batch_cache: TimedCache::new(cache_keep_time),
+            seen_txns: TimedCache::new(cache_keep_time),
+            incomplete_batches: TimedCache::new(cache_keep_time),
+            incomplete_blocks: TimedCache::new(cache_keep_time),
+            requested: TimedCache::new(requested_keep_time),
+        }
+    }
+
+    pub fn add_block(&mut self, block: Block, now: Instant) -> Result<(), CompleterError> {
+        self.purge(now)?;
+
+        if let Some(block) = self.complete_block(block, now)? {
+            let block_id = block.get_header_signature().to_string();
+            self.service.block_completed(&block_id)?;
+            self.process_incomplete_blocks(&block_id, now)?;
+        }
+
+        gauge!(
+            "incomplete_blocks_length",
+            self.incomplete_blocks.len() as i64
+        );
+        Ok(())
+    }
+
+    pub fn add_batch(&mut self, batch: Batch, now: Instant) -> Result<(), CompleterError> {
+        self.purge(now)?;
+
+        let batch_id = batch.get_header_signature().to_string();
+        if self.batch_cache.contains(&batch_id) {
+            return Ok(());
+        }
+
+        if self.complete_batch(&batch, now)? {
+            let txn_ids: Vec<String> = batch
+                .get_transactions()
+                .iter()
+                .map(|txn| txn.get_header_signature().to_string())
+                .collect();
+            for txn_id in &txn_ids {
+                self.seen_txns.insert(txn_id.clone(), batch_id.clone(), now);
+            }
+            gauge!("seen_txns_length", self.seen_txns.len() as i64);
+
+            self.service.batch_completed(&batch)?;
+            self.batch_cache.insert(batch_id.clone(), batch, now);
+            self.process_incomplete_blocks(&batch_id, now)?;
+            self.requested.remove(&batch_id);
+
+            // Batches waiting on the transactions of this batch may now be
+            // complete
+            for txn_id in &txn_ids {
+                if let Some(batches) = self.incomplete_batches.remove(txn_id) {
+                    self.requested.remove(txn_id);
+                    for batch in batches {
+                        self.add_batch(batch, now)?;
+                    }
+                }
+            }
+        }
+
+        gauge!(
+            "incomplete_batches_length",
+            self.incomplete_batches.len() as i64
+        );
+        Ok(())
+    }
+
+    pub fn get_batch(&self, batch_id: &str) -> Option<&Batch> {
+        self.batch_cache.get(batch_id)
+    }
+
+    pub fn get_batch_by_transaction(&self, transaction_id: &str) -> Option<&Batch> {
+        self.seen_txns
+            .get(transaction_id)
+            .and_then(|batch_id| self.batch_cache.get(batch_id))
+    }
+
+    /// Returns the block with its batches in the order of its header if it
+    /// is complete and was stored, and otherwise holds or drops it.
+    fn complete_block(
+        &mut self,
+        mut block: Block,
+        now: Instant,
+    ) -> Result<Option<Block>, CompleterError> {
+        let block_id = block.get_header_signature().to_string();
+        if self.service.has_block(&block_id)? {
+            debug!("Drop duplicate block: {}", block_id);
+            return Ok(None);
+        }
+
+        let header: BlockHeader = Message::parse_from_bytes(block.get_header())?;
+
+        // The predecessor being in the block manager now does not mean it
+        // will still be there once this block is complete, which is checked
+        // again when it is stored.
+        if !self.service.has_block(header.get_previous_block_id())? {
+            self.request_previous(block, header.get_previous_block_id(), now)?;
+            return Ok(None);
+        }
+
+        let batch_ids = header.get_batch_ids();
+        if block.get_batches().len() > batch_ids.len() {
+            debug!("Block has extra batches. Dropping {}", block_id);
+            return Ok(None);
+        }
+
+        let missing: Vec<String> = batch_ids
+            .iter()
+            .filter(|batch_id| {
+                !self.batch_cache.contains(batch_id)
+                    && !block
+                        .get_batches()
+                        .iter()
+                        .any(|batch| batch.get_header_signature() == batch_id.as_str())
+            })
+            .cloned()
+            .collect();
+
+        if !missing.is_empty() {
+            // A block carrying as many batches as its header lists, but not
+            // those batches, does not match its header
+            if block.get_batches().len() == batch_ids.len() {
+                debug!(
+                    "Block.header.batch_ids does not match set of batches in \
+                     block.batches Dropping {}",
+                    block_id
+                );
+                return Ok(None);
+            }
+
+            for batch_id in missing {
+                self.incomplete_blocks.push(
+                    &batch_id,
+                    block.clone(),
+                    now,
+                    Block::get_header_signature,
+                );
+                if !self.requested.contains(&batch_id) {
+                    self.requested.insert(batch_id.clone(), Request::Batch, now);
+                    self.service.request_batch(&batch_id)?;
+                }
+            }
+            return Ok(None);
+        }
+
+        let mut in_block: HashMap<String, Batch> = block
+            .take_batches()
+            .into_iter()
+            .map(|batch| (batch.get_header_signature().to_string(), batch))
+            .collect();
+        let batches: Option<Vec<Batch>> = batch_ids
+            .iter()
+            .map(|batch_id| {
+                in_block
+                    .remove(batch_id)
+                    .or_else(|| self.batch_cache.get(batch_id).cloned())
+            })
+            .collect();
+        match batches {
+            Some(batches) => block.set_batches(RepeatedField::from_vec(batches)),
+            None => {
+                debug!("Block lists a batch more than once. Dropping {}", block_id);
+                return Ok(None);
+            }
+        }
+
+        self.requested.remove(&block_id);
+
+        if self.service.put_block(&block)? {
+            Ok(Some(block))
+        } else {
+            // The predecessor dropped out of the block manager since it was
+            // checked
+            self.request_previous(block, header.get_previous_block_id(), now)?;
+            Ok(None)
+        }
+    }
+
+    fn request_previous(
+        &mut self,
+        block: Block,
+        previous_block_id: &str,
+        now: Instant,
+    ) -> Result<(), CompleterError> {
+        self.incomplete_blocks
+            .push(previous_block_id, block, now, Block::get_header_signature);
+
+        if self.requested.contains(previous_block_id) {
+            return Ok(());
+        }
+
+        debug!("Request missing predecessor: {}", previous_block_id);
+        self.requested
+            .insert(previous_block_id.into(), Request::Block, now);
+        self.service.request_block(previous_block_id)
+    }
+
+    /// Returns whether the batch's dependencies have all been seen or
+    /// committed, and otherwise holds it and requests the missing ones.
+    fn complete_batch(&mut self, batch: &Batch, now: Instant) -> Result<bool, CompleterError> {
+        let mut complete = true;
+        let mut to_request = vec![];
+
+        for txn in batch.get_transactions() {
+            let header: TransactionHeader = Message::parse_from_bytes(txn.get_header())?;
+            for dependency in header.get_dependencies() {
+                if self.seen_txns.contains(dependency)
+                    || self.service.transaction_committed(dependency)?
+                {
+                    continue;
+                }
+
+                counter!("unsatisfied_dependency_count", 1);
+                complete = false;
+
+                if !self.requested.contains(dependency) {
+                    self.requested
+                        .insert(dependency.clone(), Request::BatchByTransaction, now);
+                    to_request.push(dependency.clone());
+                }
+                self.incomplete_batches.push(
+                    dependency,
+                    batch.clone(),
+                    now,
+                    Batch::get_header_signature,
+                );
+            }
+        }
+
+        if !to_request.is_empty() {
+            self.service
+                .request_batches_by_transaction_ids(&to_request)?;
+        }
+
+        Ok(complete)
+    }
+
+    /// Completes the blocks waiting on `key`, a block or batch id, and then
+    /// the blocks waiting on those.
+    fn process_incomplete_blocks(&mut self, key: &str, now: Instant) -> Result<(), CompleterError> {
+        let mut to_complete = VecDeque::new();
+        to_complete.push_back(key.to_string());
+
+        while let Some(key) = to_complete.pop_front() {
+            let blocks = match self.incomplete_blocks.remove(&key) {
+                Some(blocks) => blocks,
+                None => continue,
+            };
+            for block in blocks {
+                if let Some(block) = self.complete_block(block, now)? {
+                    let block_id = block.get_header_signature().to_string();
+                    self.service.block_completed(&block_id)?;
+                    to_complete.push_back(block_id);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn purge(&mut self, now: Instant) -> Result<(), CompleterError> {
+        if now.duration_since(self.last_purge) < self.purge_frequency {
+            return Ok(());
+        }
+        self.last_purge = now;
+
+        self.batch_cache.purge(now);
+        self.seen_txns.purge(now);
+        self.incomplete_batches.purge(now);
+        self.incomplete_blocks.purge(now);
+
+        self.requeue_expired(now)
+    }
+
+    /// Sends again the requests that timed out for blocks and batches that
+    /// are still missing, and forgets the others.
+    fn requeue_expired(&mut self, now: Instant) -> Result<(), CompleterError> {
+        let mut transaction_ids = vec![];
+
+        for (id, request) in self.requested.purge(now) {
+            let still_missing = match request {
+                Request::BatchByTransaction => self.incomplete_batches.contains(&id),
+                Request::Block | Request::Batch => self.incomplete_blocks.contains(&id),
+            };
+            if !still_missing {
+                continue;
+            }
+
+            counter!("completer_requeued_request_count", 1);
+            self.requested.insert(id.clone(), request, now);
+            match request {
+                Request::Block => self.service.request_block(&id)?,
+                Request::Batch => self.service.request_batch(&id)?,
+                Request::BatchByTransaction => transaction_ids.push(id),
+            }
+        }
+
+        if !transaction_ids.is_empty() {
+            self.service
+                .request_batches_by_transaction_ids(&transaction_ids)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashSet;
+    use std::sync::{Arc, Mutex};
+
+    use proto::transaction::Transaction;
+
+    #[derive(Clone, Default)]
+    struct MockService {
+        blocks: Arc<Mutex<HashSet<String>>>,
+        requested_blocks: Arc<Mutex<Vec<String>>>,
+        requested_batches: Arc<Mutex<Vec<String>>>,
+        requested_txns: Arc<Mutex<Vec<String>>>,
+        completed_blocks: Arc<Mutex<Vec<String>>>,
+        completed_batches: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl CompleterService for MockService {
+        fn has_block(&self, block_id: &str) -> Result<bool, CompleterError> {
+            Ok(self.blocks.lock().unwrap().contains(block_id))
+        }
+
+        fn put_block(&self, block: &Block) -> Result<bool, CompleterError> {
+            self.blocks
+                .lock()
+                .unwrap()
+                .insert(block.get_header_signature().into());
+            Ok(true)
+        }
+
+        fn transaction_committed(&self, _transaction_id: &str) -> Result<bool, CompleterError> {
+            Ok(false)
+        }
+
+        fn request_block(&self, block_id: &str) -> Result<(), CompleterError> {
+            self.requested_blocks.lock().unwrap().push(block_id.into());
+            Ok(())
+        }
+
+        fn request_batch(&self, batch_id: &str) -> Result<(), CompleterError> {
+            self.requested_batches.lock().unwrap().push(batch_id.into());
+            Ok(())
+        }
+
+        fn request_batches_by_transaction_ids(
+            &self,
+            transaction_ids: &[String],
+        ) -> Result<(), CompleterError> {
+            self.requested_txns
+                .lock()
+                .unwrap()
+                .extend_from_slice(transaction_ids);
+            Ok(())
+        }
+
+        fn block_completed(&self, block_id: &str) -> Result<(), CompleterError> {
+            self.completed_blocks.lock().unwrap().push(block_id.into());
+            Ok(())
+        }
+
+        fn batch_completed(&self, batch: &Batch) -> Result<(), CompleterError> {
+            self.completed_batches
+                .lock()
+                .unwrap()
+                .push(batch.get_header_signature().into());
+            Ok(())
+        }
+    }
+
+    fn new_completer(service: &MockService) -> Completer<MockService> {
+        service.blocks.lock().unwrap().insert("genesis".into());
+        Completer::new(
+            service.clone(),
+            Duration::from_secs(1200),
+            Duration::from_secs(30),
+            Duration::from_secs(300),
+        )
+    }
+
+    fn create_batch(id: &str, dependencies: &[&str]) -> Batch {
+        let mut header = TransactionHeader::new();
+        header.set_dependencies(dependencies.iter().map(|dep| dep.to_string()).collect());
+        let mut txn = Transaction::new();
+        txn.set_header(header.write_to_bytes().unwrap());
+        txn.set_header_signature(format!("{}-txn", id));
+
+        let mut batch = Batch::new();
+        batch.set_header_signature(id.into());
+        batch.set_transactions(RepeatedField::from_vec(vec![txn]));
+        batch
+    }
+
+    fn create_block(id: &str, previous_id: &str, batches: &[Batch], carried: usize) -> Block {
+        let mut header = BlockHeader::new();
+        header.set_previous_block_id(previous_id.into());
+        header.set_batch_ids(
+            batches
+                .iter()
+                .map(|batch| batch.get_header_signature().to_string())
+                .collect(),
+        );
+
+        let mut block = Block::new();
+        block.set_header(header.write_to_bytes().unwrap());
+        block.set_header_signature(id.into());
+        block.set_batches(RepeatedField::from_vec(batches[..carried].to_vec()));
+        block
+    }
+
+    /// A block is released once its predecessor arrives, and then the
+    /// blocks waiting on it.
+    #[test]
+    fn blocks_wait_for_predecessors() {
+        let service = MockService::default();
+        let mut completer = new_completer(&service);
+        let now = Instant::now();
+
+        let batch = create_batch("batch", &[]);
+        completer
+            .add_block(create_block("b2", "b1", &[batch.clone()], 1), now)
+            .unwrap();
+        completer
+            .add_block(create_block("b3", "b2", &[batch.clone()], 1), now)
+            .unwrap();
+        assert_eq!(
+            vec!["b1".to_string(), "b2".into()],
+            *service.requested_blocks.lock().unwrap()
+        );
+        assert!(service.completed_blocks.lock().unwrap().is_empty());
+
+        completer
+            .add_block(create_block("b1", "genesis", &[batch], 1), now)
+            .unwrap();
+        assert_eq!(
+            vec!["b1".to_string(), "b2".into(), "b3".into()],
+            *service.completed_blocks.lock().unwrap()
+        );
+    }
+
+    /// A block missing batches is completed from the batches received, in
+    /// the order of its header.
+    #[test]
+    fn blocks_wait_for_batches() {
+        let service = MockService::default();
+        let mut completer = new_completer(&service);
+        let now = Instant::now();
+
+        let batches = vec![create_batch("batch1", &[]), create_batch("batch2", &[])];
+        let mut block = create_block("b1", "genesis", &batches, 0);
+        block.set_batches(RepeatedField::from_vec(vec![batches[1].clone()]));
+        completer.add_block(block, now).unwrap();
+        assert_eq!(
+            vec!["batch1".to_string()],
+            *service.requested_batches.lock().unwrap()
+        );
+
+        completer.add_batch(batches[0].clone(), now).unwrap();
+        assert_eq!(
+            vec!["b1".to_string()],
+            *service.completed_blocks.lock().unwrap()
+        );
+        assert!(completer.get_batch("batch1").is_some());
+        assert_eq!(
+            "batch1",
+            completer
+                .get_batch_by_transaction("batch1-txn")
+                .unwrap()
+                .get_header_signature()
+        );
+    }
+
+    /// A batch is held until the transactions it depends on are seen.
+    #[test]
+    fn batches_wait_for_dependencies() {
+        let service = MockService::default();
+        let mut completer = new_completer(&service);
+        let now = Instant::now();
+
+        completer
+            .add_batch(create_batch("batch2", &["batch1-txn"]), now)
+            .unwrap();
+        assert_eq!(
+            vec!["batch1-txn".to_string()],
+            *service.requested_txns.lock().unwrap()
+        );
+        assert!(service.completed_batches.lock().unwrap().is_empty());
+
+        completer
+            .add_batch(create_batch("batch1", &[]), now)
+            .unwrap();
+        assert_eq!(
+            vec!["batch1".to_string(), "batch2".into()],
+            *service.completed_batches.lock().unwrap()
+        );
+    }
+
+    /// Requests are sent again once they time out if what they asked for is
+    /// still missing.
+    #[test]
+    fn timed_out_requests_are_requeued() {
+        let service = MockService::default();
+        let mut completer = new_completer(&service);
+        let start = Instant::now();
+
+        let batch = create_batch("batch", &[]);
+        completer
+            .add_block(create_block("b2", "b1", &[batch.clone()], 1), start)
+            .unwrap();
+        completer
+            .add_block(
+                create_block("b3", "b1", &[batch], 1),
+                start + Duration::from_secs(200),
+            )
+            .unwrap();
+        assert_eq!(1, service.requested_blocks.lock().unwrap().len());
+
+        completer
+            .add_batch(create_batch("other", &[]), start + Duration::from_secs(301))
+            .unwrap();
+        assert_eq!(
+            vec!["b1".to_string(), "b1".into()],
+            *service.requested_blocks.lock().unwrap()
+        );
+    }
+}
@@ -0,0 +1,335 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+use std::ffi::CStr;
+use std::mem;
+use std::os::raw::{c_char, c_void};
+use std::slice;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use cpython::{ObjectProtocol, PyBytes, PyObject, PyTuple, Python, ToPyObject};
+use protobuf::Message;
+use py_ffi;
+
+use journal::completer::{Completer, CompleterError, CompleterService};
+use proto::batch::Batch;
+use proto::block::Block;
+use pylogger;
+
+type PyCompleter = Mutex<Completer<PyCompleterService>>;
+
+/// A `CompleterService` backed by a Python object providing `has_block`,
+/// `put_block`, `transaction_committed`, `request_block`, `request_batch`,
+/// `request_batches_by_transaction_ids`, `block_completed`, and
+/// `batch_completed`.
+pub struct PyCompleterService {
+    py_completer_service: PyObject,
+}
+
+impl PyCompleterService {
+    pub fn new(py_completer_service: PyObject) -> Self {
+        PyCompleterService {
+            py_completer_service,
+        }
+    }
+
+    fn call<A>(&self, py: Python, method: &str, args: A) -> Result<PyObject, CompleterError>
+    where
+        A: ToPyObject<ObjectType = PyTuple>,
+    {
+        self.py_completer_service
+            .call_method(py, method, args, None)
+            .map_err(|py_err| {
+                pylogger::exception(py, "Unable to call py_completer_service", py_err);
+                CompleterError::ServiceError(format!("FFI error calling {}", method))
+            })
+    }
+
+    fn call_bool<A>(&self, method: &str, args: A) -> Result<bool, CompleterError>
+    where
+        A: ToPyObject<ObjectType = PyTuple>,
+    {
+        let gil_guard = Python::acquire_gil();
+        let py = gil_guard.python();
+
+        self.call(py, method, args)?
+            .extract::<bool>(py)
+            .map_err(|py_err| {
+                pylogger::exception(py, "py_completer_service did not return a bool", py_err);
+                CompleterError::ServiceError(format!("{} did not return a bool", method))
+            })
+    }
+
+    fn call_unit<A>(&self, method: &str, args: A) -> Result<(), CompleterError>
+    where
+        A: ToPyObject<ObjectType = PyTuple>,
+    {
+        let gil_guard = Python::acquire_gil();
+        let py = gil_guard.python();
+
+        self.call(py, method, args).map(|_| ())
+    }
+}
+
+impl CompleterService for PyCompleterService {
+    fn has_block(&self, block_id: &str) -> Result<bool, CompleterError> {
+        self.call_bool("has_block", (block_id,))
+    }
+
+    fn put_block(&self, block: &Block) -> Result<bool, CompleterError> {
+        let bytes = block.write_to_bytes()?;
+        let gil_guard = Python::acquire_gil();
+        let py = gil_guard.python();
+        self.call_bool("put_block", (PyBytes::new(py, &bytes),))
+    }
+
+    fn transaction_committed(&self, transaction_id: &str) -> Result<bool, CompleterError> {
+        self.call_bool("transaction_committed", (transaction_id,))
+    }
+
+    fn request_block(&self, block_id: &str) -> Result<(), CompleterError> {
+        self.call_unit("request_block", (block_id,))
+    }
+
+    fn request_batch(&self, batch_id: &str) -> Result<(), CompleterError> {
+        self.call_unit("request_batch", (batch_id,))
+    }
+
+    fn request_batches_by_transaction_ids(
+        &self,
+        transaction_ids: &[String],
+    ) -> Result<(), CompleterError> {
+        self.call_unit(
+            "request_batches_by_transaction_ids",
+            (transaction_ids.to_vec(),),
+        )
+    }
+
+    fn block_completed(&self, block_id: &str) -> Result<(), CompleterError> {
+        self.call_unit("block_completed", (block_id,))
+    }
+
+    fn batch_completed(&self, batch: &Batch) -> Result<(), CompleterError> {
+        let bytes = batch.write_to_bytes()?;
+        let gil_guard = Python::acquire_gil();
+        let py = gil_guard.python();
+        self.call_unit("batch_completed", (PyBytes::new(py, &bytes),))
+    }
+}
+
+#[repr(u32)]
+#[derive(Debug)]
+pub enum ErrorCode {
+    Success = 0,
+    NullPointerProvided = 0x01,
+    InvalidInputString = 0x02,
+    InvalidArgument = 0x03,
+    NotFound = 0x04,
+    Error = 0x05,
+}
+
+macro_rules! check_null {
+    ($($arg:expr) , *) => {
+        $(if $arg.is_null() { return ErrorCode::NullPointerProvided; })*
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn completer_new(
+    py_completer_service_ptr: *mut py_ffi::PyObject,
+    cache_keep_time_secs: u64,
+    cache_purge_frequency_secs: u64,
+    requested_keep_time_secs: u64,
+    completer_ptr: *mut *const c_void,
+) -> ErrorCode {
+    check_null!(py_completer_service_ptr, completer_ptr);
+
+    let py_completer_service = {
+        let gil_guard = Python::acquire_gil();
+        let py = gil_guard.python();
+        PyObject::from_borrowed_ptr(py, py_completer_service_ptr)
+    };
+
+    let completer: PyCompleter = Mutex::new(Completer::new(
+        PyCompleterService::new(py_completer_service),
+        Duration::from_secs(cache_keep_time_secs),
+        Duration::from_secs(cache_purge_frequency_secs),
+        Duration::from_secs(requested_keep_time_secs),
+    ));
+
+    *completer_ptr = Box::into_raw(Box::new(completer)) as *const c_void;
+
+    ErrorCode::Success
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn completer_drop(completer: *mut c_void) -> ErrorCode {
+    check_null!(completer);
+    Box::from_raw(completer as *mut PyCompleter);
+    ErrorCode::Success
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn completer_add_block(
+    completer: *mut c_void,
+    block_bytes: *const u8,
+    block_bytes_len: usize,
+) -> ErrorCode {
+    check_null!(completer, block_bytes);
+
+    let block: Block =
+        match Message::parse_from_bytes(slice::from_raw_parts(block_bytes, block_bytes_len)) {
+            Ok(block) => block,
+            Err(err) => {
+                error!("Failed to parse Block: {:?}", err);
+                return ErrorCode::InvalidArgument;
+            }
+        };
+
+    let mut completer = match lock(completer) {
+        Ok(completer) => completer,
+        Err(code) => return code,
+    };
+    to_error_code(completer.add_block(block, Instant::now()))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn completer_add_batch(
+    completer: *mut c_void,
+    batch_bytes: *const u8,
+    batch_bytes_len: usize,
+) -> ErrorCode {
+    check_null!(completer, batch_bytes);
+
+    let batch: Batch =
+        match Message::parse_from_bytes(slice::from_raw_parts(batch_bytes, batch_bytes_len)) {
+            Ok(batch) => batch,
+            Err(err) => {
+                error!("Failed to parse Batch: {:?}", err);
+                return ErrorCode::InvalidArgument;
+            }
+        };
+
+    let mut completer = match lock(completer) {
+        Ok(completer) => completer,
+        Err(code) => return code,
+    };
+    to_error_code(completer.add_batch(batch, Instant::now()))
+}
+
+/// Returns a batch received but not yet committed, or NotFound.
+#[no_mangle]
+pub unsafe extern "C" fn completer_get_batch(
+    completer: *mut c_void,
+    batch_id: *const c_char,
+    batch_ptr: *mut *const u8,
+    batch_len: *mut usize,
+    batch_cap: *mut usize,
+) -> ErrorCode {
+    check_null!(completer, batch_id);
+
+    let batch_id = match CStr::from_ptr(batch_id).to_str() {
+        Ok(s) => s,
+        Err(_) => return ErrorCode::InvalidInputString,
+    };
+
+    let completer = match lock(completer) {
+        Ok(completer) => completer,
+        Err(code) => return code,
+    };
+    return_batch(
+        completer.get_batch(batch_id),
+        batch_ptr,
+        batch_len,
+        batch_cap,
+    )
+}
+
+/// Returns the batch of a transaction received but not yet committed, or
+/// NotFound.
+#[no_mangle]
+pub unsafe extern "C" fn completer_get_batch_by_transaction(
+    completer: *mut c_void,
+    transaction_id: *const c_char,
+    batch_ptr: *mut *const u8,
+    batch_len: *mut usize,
+    batch_cap: *mut usize,
+) -> ErrorCode {
+    check_null!(completer, transaction_id);
+
+    let transaction_id = match CStr::from_ptr(transaction_id).to_str() {
+        Ok(s) => s,
+        Err(_) => return ErrorCode::InvalidInputString,
+    };
+
+    let completer = match lock(completer) {
+        Ok(completer) => completer,
+        Err(code) => return code,
+    };
+    return_batch(
+        completer.get_batch_by_transaction(transaction_id),
+        batch_ptr,
+        batch_len,
+        batch_cap,
+    )
+}
+
+/// Locks the completer, or returns `ErrorCode::Error` if a thread panicked
+/// while holding the lock, rather than panicking across the FFI boundary.
+unsafe fn lock<'a>(
+    completer: *mut c_void,
+) -> Result<MutexGuard<'a, Completer<PyCompleterService>>, ErrorCode> {
+    (*(completer as *mut PyCompleter)).lock().map_err(|_| {
+        error!("Completer lock poisoned");
+        ErrorCode::Error
+    })
+}
+
+unsafe fn return_batch(
+    batch: Option<&Batch>,
+    batch_ptr: *mut *const u8,
+    batch_len: *mut usize,
+    batch_cap: *mut usize,
+) -> ErrorCode {
+    let payload = match batch.map(|batch| batch.write_to_bytes()) {
+        Some(Ok(payload)) => payload,
+        Some(Err(err)) => {
+            error!("Failed to serialize Batch: {:?}", err);
+            return ErrorCode::Error;
+        }
+        None => return ErrorCode::NotFound,
+    };
+
+    *batch_cap = payload.capacity();
+    *batch_len = payload.len();
+    *batch_ptr = payload.as_slice().as_ptr();
+
+    mem::forget(payload);
+
+    ErrorCode::Success
+}
+
+fn to_error_code(result: Result<(), CompleterError>) -> ErrorCode {
+    match result {
+        Ok(()) => ErrorCode::Success,
+        Err(err) => {
+            error!("Completer error: {}", err);
+            ErrorCode::Error
+        }
+    }
+}
@@ -21,14 +21,15 @@ use std::mem;
 use std::os::raw::{c_char, c_void};
 use std::ptr;
 use std::slice;
-use std::time::Duration;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
 
 use cpython::{self, ObjectProtocol, PyBytes, PyList, PyObject, Python, PythonObject, ToPyObject};
 use py_ffi;
 use pylogger;
 use transact::{
     context::manager::sync::ContextManager,
-    database::lmdb::LmdbDatabase,
+    database::{lmdb::LmdbDatabase, Database},
     execution::adapter::static_adapter::StaticExecutionAdapter,
     execution::executor::Executor,
     protocol::batch::{Batch, BatchPair},
@@ -74,7 +75,10 @@ use sawtooth_xo::handler::XoTransactionHandler;
 use proto::events::{Event, Event_Attribute};
 use proto::transaction_receipt::{StateChange, StateChange_Type, TransactionReceipt};
 
+use block_ffi::BlockView;
+use journal::publishing_pause::{PublishingPause, DEFAULT_PAUSE_TIMEOUT};
 use py_object_wrapper::PyObjectWrapper;
+use state::merkle_node_cache::CachedStateDatabase;
 
 struct Journal {
     batch_submitter: BatchSubmitter,
@@ -82,6 +86,7 @@ struct Journal {
     chain_controller: ChainController,
     executor: Option<Executor>,
     genesis_controller: GenesisController,
+    publishing_pause: PublishingPause,
 }
 
 impl Journal {
@@ -136,6 +141,8 @@ pub enum ErrorCode {
     BlockNotInitialized = 0x13,
     BlockEmpty = 0x14,
     VerifyStateError = 0x15,
+    PublishingPaused = 0x16,
+    InvalidPauseTimeout = 0x17,
 
     Unknown = 0xff,
 }
@@ -159,6 +166,7 @@ pub unsafe extern "C" fn journal_new(
     observers: *mut py_ffi::PyObject,
     state_pruning_block_depth: u32,
     fork_cache_keep_time: u32,
+    merkle_node_cache_size: usize,
     data_directory: *const c_char,
     key_directory: *const c_char,
     genesis_observers: *mut py_ffi::PyObject,
@@ -235,12 +243,15 @@ pub unsafe extern "C" fn journal_new(
             return ErrorCode::InvalidPythonObject;
         };
 
+        // The Python observers share the serialized block of each update
+        let last_block = SharedBlockView::default();
+
         let py_observers = PyObject::from_borrowed_ptr(py, observers);
         let observer_wrappers = if let Ok(py_list) = py_observers.extract::<PyList>(py) {
             let mut res: Vec<Box<dyn ChainObserver>> = Vec::with_capacity(py_list.len(py));
-            py_list
-                .iter(py)
-                .for_each(|pyobj| res.push(Box::new(PyChainObserver::new(pyobj))));
+            py_list.iter(py).for_each(|pyobj| {
+                res.push(Box::new(PyChainObserver::new(pyobj, last_block.clone())))
+            });
             res
         } else {
             return ErrorCode::InvalidPythonObject;
@@ -250,9 +261,9 @@ pub unsafe extern "C" fn journal_new(
         let genesis_observer_wrappers =
             if let Ok(py_list) = py_genesis_observers.extract::<PyList>(py) {
                 let mut res: Vec<Box<dyn ChainObserver>> = Vec::with_capacity(py_list.len(py));
-                py_list
-                    .iter(py)
-                    .for_each(|pyobj| res.push(Box::new(PyChainObserver::new(pyobj))));
+                py_list.iter(py).for_each(|pyobj| {
+                    res.push(Box::new(PyChainObserver::new(pyobj, last_block.clone())))
+                });
                 res
             } else {
                 return ErrorCode::InvalidPythonObject;
@@ -271,7 +282,15 @@ pub unsafe extern "C" fn journal_new(
     let state_pruning_manager = StatePruningManager::new(state_database.clone());
 
     let commit_store = Box::from_raw(commit_store as *mut CommitStore);
-    let merkle_state = CborMerkleState::new(MerkleState::new(Box::new(state_database.clone())));
+    let merkle_database: Box<dyn Database> = if merkle_node_cache_size > 0 {
+        Box::new(CachedStateDatabase::new(
+            state_database.clone(),
+            merkle_node_cache_size,
+        ))
+    } else {
+        Box::new(state_database.clone())
+    };
+    let merkle_state = CborMerkleState::new(MerkleState::new(merkle_database));
     let context_manager = ContextManager::new(Box::new(merkle_state.clone()));
 
     let mut executor = match get_executor(context_manager.clone()) {
@@ -420,6 +439,7 @@ pub unsafe extern "C" fn journal_new(
         chain_controller,
         genesis_controller,
         executor: Some(executor),
+        publishing_pause: PublishingPause::new(),
     };
 
     *journal_ptr = Box::into_raw(Box::new(journal)) as *const c_void;
@@ -617,6 +637,13 @@ pub unsafe extern "C" fn block_publisher_initialize_block(
 ) -> ErrorCode {
     check_null!(journal, previous_block_bytes);
 
+    if (*(journal as *mut Journal))
+        .publishing_pause
+        .is_paused(Instant::now())
+    {
+        return ErrorCode::PublishingPaused;
+    }
+
     let data = slice::from_raw_parts(previous_block_bytes, previous_block_bytes_len);
     let block = match BlockPair::from_bytes(&data) {
         Ok(block_pair) => block_pair,
@@ -690,6 +717,13 @@ pub unsafe extern "C" fn block_publisher_finalize_block(
 ) -> ErrorCode {
     check_null!(journal, consensus);
 
+    if (*(journal as *mut Journal))
+        .publishing_pause
+        .is_paused(Instant::now())
+    {
+        return ErrorCode::PublishingPaused;
+    }
+
     let consensus = slice::from_raw_parts(consensus, consensus_len).to_vec();
 
     match (*(journal as *mut Journal))
@@ -734,6 +768,57 @@ pub unsafe extern "C" fn block_publisher_cancel_block(journal: *mut c_void) -> E
     }
 }
 
+/// Pauses block publishing until resumed, or for at most `timeout_secs`
+/// seconds, or the default timeout if it is 0. While paused, blocks can be
+/// neither initialized nor finalized. A timeout longer than
+/// `MAX_PAUSE_TIMEOUT` is refused.
+#[no_mangle]
+pub unsafe extern "C" fn block_publisher_pause_publishing(
+    journal: *mut c_void,
+    timeout_secs: u64,
+) -> ErrorCode {
+    check_null!(journal);
+
+    let timeout = if timeout_secs == 0 {
+        DEFAULT_PAUSE_TIMEOUT
+    } else {
+        Duration::from_secs(timeout_secs)
+    };
+    match (*(journal as *mut Journal))
+        .publishing_pause
+        .pause(timeout, Instant::now())
+    {
+        Ok(()) => ErrorCode::Success,
+        Err(err) => {
+            warn!("Refusing to pause block publishing: {:?}", err);
+            ErrorCode::InvalidPauseTimeout
+        }
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn block_publisher_resume_publishing(journal: *mut c_void) -> ErrorCode {
+    check_null!(journal);
+
+    (*(journal as *mut Journal)).publishing_pause.resume();
+
+    ErrorCode::Success
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn block_publisher_is_publishing_paused(
+    journal: *mut c_void,
+    result: *mut bool,
+) -> ErrorCode {
+    check_null!(journal, result);
+
+    *result = (*(journal as *mut Journal))
+        .publishing_pause
+        .is_paused(Instant::now());
+
+    ErrorCode::Success
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn chain_controller_block_validation_result(
     journal: *mut c_void,
@@ -872,13 +957,44 @@ pub unsafe extern "C" fn chain_controller_chain_head(
     }
 }
 
+/// The block of the latest chain update, serialized once for all of the
+/// Python chain observers
+type SharedBlockView = Arc<Mutex<Option<BlockView>>>;
+
 struct PyChainObserver {
     py_observer: PyObject,
+    last_block: SharedBlockView,
 }
 
 impl PyChainObserver {
-    fn new(py_observer: PyObject) -> Self {
-        PyChainObserver { py_observer }
+    fn new(py_observer: PyObject, last_block: SharedBlockView) -> Self {
+        PyChainObserver {
+            py_observer,
+            last_block,
+        }
+    }
+
+    fn block_view(&self, block: &BlockPair) -> Option<BlockView> {
+        let mut last_block = self
+            .last_block
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(ref view) = *last_block {
+            if view.header_signature().ok() == Some(block.block().header_signature()) {
+                return Some(view.clone());
+            }
+        }
+
+        match BlockView::from_block_pair(block) {
+            Ok(view) => {
+                *last_block = Some(view.clone());
+                Some(view)
+            }
+            Err(err) => {
+                error!("Unable to serialize block for chain observers: {}", err);
+                None
+            }
+        }
     }
 }
 
@@ -891,7 +1007,10 @@ impl ChainObserver for PyChainObserver {
         let gil_guard = Python::acquire_gil();
         let py = gil_guard.python();
 
-        let wrapped_block = PyObjectWrapper::from(block.clone());
+        let wrapped_block = match self.block_view(block) {
+            Some(view) => PyObjectWrapper::from(view),
+            None => return,
+        };
         let local_receipts: Vec<TransactionReceipt> = receipts
             .iter()
             .map(|receipt| TransactionReceipt::from(receipt.clone()))
@@ -17,4 +17,7 @@
 
 pub mod block_manager_ffi;
 pub mod commit_store_ffi;
+pub mod completer;
+pub mod completer_ffi;
 pub mod journal_ffi;
+pub mod publishing_pause;
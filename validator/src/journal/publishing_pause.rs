@@ -0,0 +1,145 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+//! Pausing of block publishing, e.g. while state is pruned or exported, or
+//! while an operator maintains the validator.
+//!
+//! A pause always has a timeout, after which publishing resumes on its own,
+//! so that a pause that is never lifted cannot stop the validator from
+//! publishing for good.
+
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+/// The timeout of a pause requested without one.
+pub const DEFAULT_PAUSE_TIMEOUT: Duration = Duration::from_secs(3600);
+
+/// The longest timeout a pause may be requested with, a week.
+pub const MAX_PAUSE_TIMEOUT: Duration = Duration::from_secs(7 * 24 * 3600);
+
+/// A pause was requested with a timeout longer than `MAX_PAUSE_TIMEOUT`.
+#[derive(Debug, PartialEq)]
+pub struct PauseTimeoutOutOfRange(pub Duration);
+
+pub struct PublishingPause {
+    resume_at: Mutex<Option<Instant>>,
+}
+
+impl PublishingPause {
+    pub fn new() -> Self {
+        gauge!("block_publishing_paused", 0);
+        PublishingPause {
+            resume_at: Mutex::new(None),
+        }
+    }
+
+    /// Pauses publishing until `resume` is called or `timeout` has passed.
+    /// Pausing while paused restarts the timeout. A timeout longer than
+    /// `MAX_PAUSE_TIMEOUT` is refused, leaving publishing as it was.
+    pub fn pause(&self, timeout: Duration, now: Instant) -> Result<(), PauseTimeoutOutOfRange> {
+        let resume_at = if timeout <= MAX_PAUSE_TIMEOUT {
+            now.checked_add(timeout)
+        } else {
+            None
+        }
+        .ok_or(PauseTimeoutOutOfRange(timeout))?;
+
+        *self.lock() = Some(resume_at);
+        info!("Pausing block publishing for at most {:?}", timeout);
+        gauge!("block_publishing_paused", 1);
+        Ok(())
+    }
+
+    pub fn resume(&self) {
+        if self.lock().take().is_some() {
+            info!("Resuming block publishing");
+        }
+        gauge!("block_publishing_paused", 0);
+    }
+
+    pub fn is_paused(&self, now: Instant) -> bool {
+        let mut resume_at = self.lock();
+        match *resume_at {
+            Some(at) if now < at => true,
+            Some(_) => {
+                *resume_at = None;
+                warn!("Resuming block publishing, as the pause timed out");
+                gauge!("block_publishing_paused", 0);
+                false
+            }
+            None => false,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<Option<Instant>> {
+        self.resume_at
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Default for PublishingPause {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pause_until_resumed() {
+        let now = Instant::now();
+        let pause = PublishingPause::new();
+        assert!(!pause.is_paused(now));
+
+        pause.pause(Duration::from_secs(60), now).unwrap();
+        assert!(pause.is_paused(now + Duration::from_secs(30)));
+
+        pause.resume();
+        assert!(!pause.is_paused(now + Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn pause_times_out() {
+        let now = Instant::now();
+        let pause = PublishingPause::new();
+
+        pause.pause(Duration::from_secs(60), now).unwrap();
+        assert!(pause.is_paused(now + Duration::from_secs(59)));
+        assert!(!pause.is_paused(now + Duration::from_secs(60)));
+        // The pause stays lifted
+        assert!(!pause.is_paused(now + Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn pause_refuses_long_timeouts() {
+        let now = Instant::now();
+        let pause = PublishingPause::new();
+
+        assert!(pause.pause(MAX_PAUSE_TIMEOUT, now).is_ok());
+        pause.resume();
+
+        let too_long = Duration::from_secs(u64::MAX);
+        assert_eq!(
+            Err(PauseTimeoutOutOfRange(too_long)),
+            pause.pause(too_long, now)
+        );
+        assert!(!pause.is_paused(now));
+    }
+}
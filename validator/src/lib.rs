@@ -17,12 +17,16 @@
 
 extern crate battleship;
 extern crate block_info_tp;
+extern crate cbor;
 extern crate cpython;
 extern crate cylinder;
 extern crate hex;
 extern crate libc;
+extern crate lru;
+extern crate openssl;
 extern crate protobuf;
 extern crate python3_sys as py_ffi;
+extern crate regex;
 extern crate sawtooth_identity;
 extern crate sawtooth_intkey;
 extern crate sawtooth_sabre;
@@ -31,6 +35,7 @@ extern crate sawtooth_smallbank;
 extern crate sawtooth_xo;
 #[macro_use]
 extern crate log;
+#[macro_use]
 extern crate metrics;
 extern crate sawtooth;
 extern crate transact;
@@ -38,8 +43,12 @@ extern crate transact;
 // exported modules
 pub(crate) mod consensus;
 pub(crate) mod database;
+pub(crate) mod events;
+pub(crate) mod gossip;
+pub(crate) mod influx_metrics;
 pub(crate) mod journal;
 pub(crate) mod proto;
+pub(crate) mod proto_view;
 pub(crate) mod py_object_wrapper;
 pub(crate) mod pylogger;
 pub(crate) mod pymetrics;
@@ -3,13 +3,17 @@ extern crate cpython;
 #[macro_use]
 extern crate log;
 extern crate metrics;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate toml;
 
 mod pylogger;
 mod pymetrics;
 mod server;
 
 use cpython::Python;
-use server::cli;
+use server::{cli, config};
 
 use std::process;
 
@@ -25,7 +29,18 @@ fn main() {
 
     pymetrics::set_up_metrics(py);
 
-    let pydict = cli::wrap_in_pydict(py, &args)
+    let validator_config = match config::load_validator_config(
+        cli::args_to_config(&args),
+        &config::config_dir(args.value_of("config_dir")),
+    ) {
+        Ok(validator_config) => validator_config,
+        Err(err) => {
+            error!("{}", err);
+            process::exit(1);
+        }
+    };
+
+    let pydict = cli::wrap_in_pydict(py, &args, &validator_config)
         .map_err(|err| err.print(py))
         .unwrap();
 
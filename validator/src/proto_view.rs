@@ -0,0 +1,192 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+//! Scanning of serialized protobuf messages, for the views of blocks and
+//! batches that decode their fields only when requested.
+
+use std::error::Error;
+use std::fmt;
+use std::ops::Range;
+use std::str;
+
+use protobuf::ProtobufError;
+
+const WIRE_TYPE_VARINT: u64 = 0;
+const WIRE_TYPE_FIXED64: u64 = 1;
+const WIRE_TYPE_LENGTH_DELIMITED: u64 = 2;
+const WIRE_TYPE_FIXED32: u64 = 5;
+
+#[derive(Debug)]
+pub enum ProtoViewError {
+    /// The bytes end in the middle of a field
+    Truncated,
+    UnsupportedWireType(u64),
+    InvalidUtf8(str::Utf8Error),
+    DecodeError(ProtobufError),
+    /// The message could not be converted to or from its native type
+    ConversionError(String),
+}
+
+impl fmt::Display for ProtoViewError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ProtoViewError::Truncated => write!(f, "Message is truncated"),
+            ProtoViewError::UnsupportedWireType(wire_type) => {
+                write!(f, "Unsupported wire type: {}", wire_type)
+            }
+            ProtoViewError::InvalidUtf8(ref err) => write!(f, "Invalid string field: {}", err),
+            ProtoViewError::DecodeError(ref err) => write!(f, "Unable to decode field: {}", err),
+            ProtoViewError::ConversionError(ref msg) => write!(f, "Unable to convert: {}", msg),
+        }
+    }
+}
+
+impl Error for ProtoViewError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            ProtoViewError::InvalidUtf8(ref err) => Some(err),
+            ProtoViewError::DecodeError(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<ProtobufError> for ProtoViewError {
+    fn from(err: ProtobufError) -> Self {
+        ProtoViewError::DecodeError(err)
+    }
+}
+
+/// A field of a serialized message: its number, and the position of its
+/// value in the bytes. The value of a length-delimited field excludes its
+/// length.
+#[derive(Debug, PartialEq)]
+pub struct Field {
+    pub number: u64,
+    pub value: Range<usize>,
+}
+
+/// Lists the fields of the message serialized in `bytes[range]`, without
+/// decoding the messages nested in them.
+pub fn scan_fields(bytes: &[u8], range: Range<usize>) -> Result<Vec<Field>, ProtoViewError> {
+    let end = range.end;
+    if end > bytes.len() {
+        return Err(ProtoViewError::Truncated);
+    }
+
+    let mut fields = vec![];
+    let mut pos = range.start;
+    while pos < end {
+        let tag = read_varint(bytes, &mut pos, end)?;
+        let value = match tag & 0x7 {
+            WIRE_TYPE_VARINT => {
+                let start = pos;
+                read_varint(bytes, &mut pos, end)?;
+                start..pos
+            }
+            WIRE_TYPE_FIXED64 => pos..pos + 8,
+            WIRE_TYPE_FIXED32 => pos..pos + 4,
+            WIRE_TYPE_LENGTH_DELIMITED => {
+                let len = read_varint(bytes, &mut pos, end)? as usize;
+                pos..pos.checked_add(len).ok_or(ProtoViewError::Truncated)?
+            }
+            wire_type => return Err(ProtoViewError::UnsupportedWireType(wire_type)),
+        };
+        if value.end > end {
+            return Err(ProtoViewError::Truncated);
+        }
+
+        pos = value.end;
+        fields.push(Field {
+            number: tag >> 3,
+            value,
+        });
+    }
+
+    Ok(fields)
+}
+
+/// Returns the string in `bytes[range]`.
+pub fn read_str(bytes: &[u8], range: Range<usize>) -> Result<&str, ProtoViewError> {
+    str::from_utf8(&bytes[range]).map_err(ProtoViewError::InvalidUtf8)
+}
+
+/// Returns the varint starting at `bytes[*pos]`, and moves `pos` past it.
+pub fn read_varint(bytes: &[u8], pos: &mut usize, end: usize) -> Result<u64, ProtoViewError> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        if *pos >= end {
+            return Err(ProtoViewError::Truncated);
+        }
+        let byte = bytes[*pos];
+        *pos += 1;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(ProtoViewError::Truncated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use protobuf::{Message, RepeatedField};
+
+    use proto::batch::Batch;
+    use proto::transaction::Transaction;
+
+    #[test]
+    fn fields_are_scanned_without_decoding() {
+        let mut txn = Transaction::new();
+        txn.set_header_signature("txn".into());
+
+        let mut batch = Batch::new();
+        batch.set_header(vec![1; 300]);
+        batch.set_header_signature("batch".into());
+        batch.set_transactions(RepeatedField::from_vec(vec![txn.clone(), txn.clone()]));
+        batch.set_trace(true);
+        let bytes = batch.write_to_bytes().unwrap();
+
+        let fields = scan_fields(&bytes, 0..bytes.len()).unwrap();
+        assert_eq!(
+            vec![1, 2, 3, 3, 4],
+            fields.iter().map(|field| field.number).collect::<Vec<_>>()
+        );
+        assert_eq!(&vec![1; 300][..], &bytes[fields[0].value.clone()]);
+        assert_eq!("batch", read_str(&bytes, fields[1].value.clone()).unwrap());
+        let parsed_txn: Transaction =
+            Message::parse_from_bytes(&bytes[fields[2].value.clone()]).unwrap();
+        assert_eq!(txn, parsed_txn);
+
+        let mut pos = fields[4].value.start;
+        assert_eq!(1, read_varint(&bytes, &mut pos, bytes.len()).unwrap());
+    }
+
+    #[test]
+    fn truncated_messages_are_rejected() {
+        let mut batch = Batch::new();
+        batch.set_header_signature("batch".into());
+        let bytes = batch.write_to_bytes().unwrap();
+
+        match scan_fields(&bytes, 0..bytes.len() - 1) {
+            Err(ProtoViewError::Truncated) => (),
+            res => panic!("Expected Truncated, got {:?}", res),
+        }
+    }
+}
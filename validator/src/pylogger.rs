@@ -15,18 +15,18 @@
  * ------------------------------------------------------------------------------
  */
 
-use cpython::{
-    ObjectProtocol, PyDict, PyErr, PyModule, PyObject, PyResult, PyTuple, Python, PythonObject,
-    ToPyObject,
-};
+use cpython::{ObjectProtocol, PyDict, PyErr, PyModule, PyObject, PyResult, PyTuple, Python};
 use log;
-use log::{Level, Log, Metadata, Record, SetLoggerError};
+use log::{Level, LevelFilter, Log, Metadata, Record, SetLoggerError};
+
+/// The Python level given to Rust's trace records, below Python's DEBUG.
+const TRACE: usize = 5;
 
 #[allow(dead_code)]
 pub fn set_up_logger(verbosity: u64, py: Python) {
     let verbosity_level: Level = determine_log_level(verbosity);
 
-    PyLogger::init(verbosity_level, py).expect("Failed to set logger");
+    PyLogger::init(verbosity_level.to_level_filter(), py).expect("Failed to set logger");
 
     let server_log = py
         .import("sawtooth_validator.server.log")
@@ -41,16 +41,27 @@ pub fn set_up_logger(verbosity: u64, py: Python) {
     warn!("Started logger at level {}", verbosity_level);
 }
 
+/// Starts passing Rust records to Python logging, at or above a Python
+/// level.
 #[no_mangle]
 #[allow(unused)]
-pub extern "C" fn pylogger_init(verbosity: usize) {
+pub extern "C" fn pylogger_init(py_level: usize) {
     let gil = Python::acquire_gil();
     let py = gil.python();
-    if PyLogger::init(determine_log_level(verbosity as u64), py).is_err() {
+    if PyLogger::init(into_level_filter(py_level), py).is_err() {
         warn!("Attempted to initialize logger twice; ignoring");
     }
 }
 
+/// Sets the Python level of the least severe Rust records passed to Python
+/// logging. Less severe records are dropped without acquiring the GIL, so
+/// Python calls this whenever its logging levels change.
+#[no_mangle]
+#[allow(unused)]
+pub extern "C" fn pylogger_set_level(py_level: usize) {
+    log::set_max_level(into_level_filter(py_level));
+}
+
 pub fn exception(py: Python, msg: &str, err: PyErr) {
     let logger = PyLogger::new(py).expect("Failed to create new PyLogger");
     logger.exception(py, msg, err);
@@ -68,13 +79,19 @@ impl PyLogger {
         Ok(PyLogger { logger, logging })
     }
 
-    fn init(verbosity: Level, py: Python) -> Result<(), SetLoggerError> {
+    fn init(level: LevelFilter, py: Python) -> Result<(), SetLoggerError> {
         let logger =
             PyLogger::new(py).expect("Failed to instantiate Python logger; check library paths.");
 
+        logger
+            .logging
+            .call(py, "addLevelName", (TRACE, "TRACE"), None)
+            .map_err(|err| err.print(py))
+            .ok();
+
         log::set_boxed_logger(Box::new(logger))?;
 
-        log::set_max_level(verbosity.to_level_filter());
+        log::set_max_level(level);
 
         Ok(())
     }
@@ -91,6 +108,15 @@ impl PyLogger {
             .call_method(py, "error", (msg,), Some(&kwargs))
             .unwrap();
     }
+
+    /// Gets the Python logger for a record's target, so that records from
+    /// e.g. `sawtooth::journal::chain` are handled by the
+    /// `sawtooth.journal.chain` logger and its ancestors.
+    fn logger_for(&self, py: Python, target: &str) -> PyResult<(String, PyObject)> {
+        let name = target.replace("::", ".");
+        let logger = self.logging.call(py, "getLogger", (&name,), None)?;
+        Ok((name, logger))
+    }
 }
 
 fn determine_log_level(verbosity: u64) -> Level {
@@ -101,23 +127,25 @@ fn determine_log_level(verbosity: u64) -> Level {
     }
 }
 
-fn into_level_string(level: Level) -> &'static str {
+fn into_python_level(level: Level) -> usize {
     match level {
-        Level::Error => "ERROR",
-        Level::Warn => "WARN",
-        Level::Info => "INFO",
-        Level::Debug => "DEBUG",
-        Level::Trace => "DEBUG",
+        Level::Error => 40,
+        Level::Warn => 30,
+        Level::Info => 20,
+        Level::Debug => 10,
+        Level::Trace => TRACE,
     }
 }
 
-fn into_level_method(level: Level) -> &'static str {
-    match level {
-        Level::Error => "error",
-        Level::Warn => "warn",
-        Level::Info => "info",
-        Level::Debug => "debug",
-        Level::Trace => "debug",
+/// The filter that passes the Rust records at or above a Python level.
+fn into_level_filter(py_level: usize) -> LevelFilter {
+    match py_level {
+        0..=TRACE => LevelFilter::Trace,
+        6..=10 => LevelFilter::Debug,
+        11..=20 => LevelFilter::Info,
+        21..=30 => LevelFilter::Warn,
+        31..=40 => LevelFilter::Error,
+        _ => LevelFilter::Off,
     }
 }
 
@@ -126,14 +154,12 @@ impl Log for PyLogger {
         let gil = Python::acquire_gil();
         let py = gil.python();
 
-        let level = into_level_string(metadata.level());
-        let pylevel = self.logging.get(py, level).unwrap();
+        let level = into_python_level(metadata.level());
 
-        self.logger
-            .call_method(py, "isEnabledFor", PyTuple::new(py, &[pylevel]), None)
-            .unwrap()
-            .extract(py)
-            .unwrap()
+        self.logger_for(py, metadata.target())
+            .and_then(|(_, logger)| logger.call_method(py, "isEnabledFor", (level,), None))
+            .and_then(|enabled| enabled.extract(py))
+            .unwrap_or(false)
     }
 
     fn log(&self, record: &Record) {
@@ -144,22 +170,31 @@ impl Log for PyLogger {
             return;
         }
 
-        let method = into_level_method(record.level());
-        let record = format!(
-            "[{}: {}] {}",
-            record.file().unwrap_or("unknown file"),
-            record.line().unwrap_or(0),
-            record.args()
-        );
-
-        self.logger
-            .call_method(
-                py,
-                method,
-                PyTuple::new(py, &[record.to_py_object(py).into_object()]),
-                None,
-            )
-            .unwrap();
+        // The record is made with the Rust file and line, so that Python
+        // formatters show where it was logged rather than this bridge.
+        let result = self
+            .logger_for(py, record.target())
+            .and_then(|(name, logger)| {
+                let py_record = logger.call_method(
+                    py,
+                    "makeRecord",
+                    (
+                        name,
+                        into_python_level(record.level()),
+                        record.file().unwrap_or("unknown file"),
+                        record.line().unwrap_or(0),
+                        record.args().to_string(),
+                        PyTuple::new(py, &[]),
+                        py.None(),
+                    ),
+                    None,
+                )?;
+                logger.call_method(py, "handle", (py_record,), None)
+            });
+
+        if let Err(err) = result {
+            err.print(py);
+        }
     }
 
     fn flush(&self) {
@@ -170,3 +205,27 @@ impl Log for PyLogger {
             .unwrap();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level_filter_passes_python_level() {
+        for level in &[
+            Level::Error,
+            Level::Warn,
+            Level::Info,
+            Level::Debug,
+            Level::Trace,
+        ] {
+            assert_eq!(
+                into_level_filter(into_python_level(*level)),
+                level.to_level_filter()
+            );
+        }
+
+        assert_eq!(into_level_filter(0), LevelFilter::Trace);
+        assert_eq!(into_level_filter(50), LevelFilter::Off);
+    }
+}
@@ -17,60 +17,66 @@
 
 #![allow(unknown_lints)]
 
+use std::collections::BTreeMap;
+
 use clap::{App, Arg, ArgMatches};
 use cpython::{PyDict, PyResult, Python};
 
+use server::config::{PartialValidatorConfig, ValidatorConfig};
+
 const DISTRIBUTION_NAME: &str = "sawtooth-validator";
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
-pub fn wrap_in_pydict(py: Python, matches: &ArgMatches) -> PyResult<PyDict> {
-    let (bind_component, bind_network, bind_consensus) = parse_bindings(matches);
-
+pub fn wrap_in_pydict(
+    py: Python,
+    matches: &ArgMatches,
+    validator_config: &ValidatorConfig,
+) -> PyResult<PyDict> {
     let pydict = PyDict::new(py);
 
-    pydict.set_item(py, "bind_component", bind_component)?;
-    pydict.set_item(py, "bind_network", bind_network)?;
-    pydict.set_item(py, "bind_consensus", bind_consensus)?;
     pydict.set_item(py, "config_dir", matches.value_of("config_dir"))?;
-    pydict.set_item(py, "endpoint", matches.value_of("endpoint"))?;
-    pydict.set_item(
-        py,
-        "maximum_peer_connectivity",
-        matches
-            .value_of("maximum_peer_connectivity")
-            .and_then(|s| s.parse::<u32>().ok()),
-    )?;
-    pydict.set_item(
-        py,
-        "minimum_peer_connectivity",
-        matches
+    pydict.set_item(py, "verbose", matches.occurrences_of("verbose"))?;
+    pydict.set_item(py, "validator_config", validator_config.to_pydict(py)?)?;
+
+    Ok(pydict)
+}
+
+/// Collects the validator configuration given on the command line.
+pub fn args_to_config(matches: &ArgMatches) -> PartialValidatorConfig {
+    let (bind_component, bind_network, bind_consensus) = parse_bindings(matches);
+    let bind = vec![
+        bind_network.map(|endpoint| format!("network:{}", endpoint)),
+        bind_component.map(|endpoint| format!("component:{}", endpoint)),
+        bind_consensus.map(|endpoint| format!("consensus:{}", endpoint)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>();
+
+    PartialValidatorConfig {
+        bind: if bind.is_empty() { None } else { Some(bind) },
+        endpoint: matches.value_of("endpoint").map(String::from),
+        peering: matches.value_of("peering").map(String::from),
+        seeds: parse_comma_separated_args("seeds", matches),
+        peers: parse_comma_separated_args("peers", matches),
+        scheduler: matches.value_of("scheduler").map(String::from),
+        roles: parse_roles(matches),
+        opentsdb_url: matches.value_of("opentsdb_url").map(String::from),
+        opentsdb_db: matches.value_of("opentsdb_db").map(String::from),
+        minimum_peer_connectivity: matches
             .value_of("minimum_peer_connectivity")
             .and_then(|s| s.parse::<u32>().ok()),
-    )?;
-    pydict.set_item(py, "opentsdb_db", matches.value_of("opentsdb_db"))?;
-    pydict.set_item(py, "opentsdb_url", matches.value_of("opentsdb_url"))?;
-    pydict.set_item(py, "peering", matches.value_of("peering"))?;
-    pydict.set_item(py, "peers", parse_comma_separated_args("peers", matches))?;
-    pydict.set_item(py, "roles", parse_roles(matches, py))?;
-    pydict.set_item(py, "scheduler", matches.value_of("scheduler"))?;
-    pydict.set_item(py, "seeds", parse_comma_separated_args("seeds", matches))?;
-    pydict.set_item(py, "verbose", matches.occurrences_of("verbose"))?;
-    pydict.set_item(
-        py,
-        "state_pruning_block_depth",
-        matches
+        maximum_peer_connectivity: matches
+            .value_of("maximum_peer_connectivity")
+            .and_then(|s| s.parse::<u32>().ok()),
+        state_pruning_block_depth: matches
             .value_of("state_pruning_block_depth")
             .and_then(|s| s.parse::<u32>().ok()),
-    )?;
-    pydict.set_item(
-        py,
-        "fork_cache_keep_time",
-        matches
+        fork_cache_keep_time: matches
             .value_of("fork_cache_keep_time")
             .and_then(|s| s.parse::<u32>().ok()),
-    )?;
-
-    Ok(pydict)
+        ..PartialValidatorConfig::default()
+    }
 }
 
 pub fn parse_args<'a>() -> ArgMatches<'a> {
@@ -237,15 +243,12 @@ fn is_non_zero_integer(arg_value: String) -> Result<(), String> {
     }
 }
 
-fn parse_roles<'a>(matches: &'a ArgMatches, py: Python) -> Option<PyDict> {
-    match matches.value_of("network_auth") {
-        Some(network_auth) => {
-            let auth_dict = PyDict::new(py);
-            auth_dict.set_item(py, "network", network_auth).unwrap();
-            Some(auth_dict)
-        }
-        None => None,
-    }
+fn parse_roles(matches: &ArgMatches) -> Option<BTreeMap<String, String>> {
+    matches.value_of("network_auth").map(|network_auth| {
+        let mut roles = BTreeMap::new();
+        roles.insert("network".to_string(), network_auth.to_string());
+        roles
+    })
 }
 
 fn parse_bindings<'a>(
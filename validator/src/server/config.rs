@@ -0,0 +1,706 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+//! Loading of the validator configuration.
+//!
+//! The configuration is merged from the command line arguments, validator.toml
+//! and the defaults, in that order of precedence, and validated before it is
+//! passed to the Python validator.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use cpython::{PyDict, PyResult, Python};
+use toml;
+
+const DEFAULT_BIND_NETWORK: &str = "tcp://127.0.0.1:8800";
+const DEFAULT_BIND_COMPONENT: &str = "tcp://127.0.0.1:4004";
+const DEFAULT_BIND_CONSENSUS: &str = "tcp://127.0.0.1:5050";
+const DEFAULT_PEERING: &str = "static";
+const DEFAULT_SCHEDULER: &str = "parallel";
+const DEFAULT_MINIMUM_PEER_CONNECTIVITY: u32 = 3;
+const DEFAULT_MAXIMUM_PEER_CONNECTIVITY: u32 = 10;
+const DEFAULT_STATE_PRUNING_BLOCK_DEPTH: u32 = 100;
+const DEFAULT_FORK_CACHE_KEEP_TIME: u32 = 300;
+const DEFAULT_COMPONENT_THREAD_POOL_WORKERS: u32 = 10;
+const DEFAULT_NETWORK_THREAD_POOL_WORKERS: u32 = 10;
+const DEFAULT_SIGNATURE_THREAD_POOL_WORKERS: u32 = 3;
+const DEFAULT_MERKLE_NODE_CACHE_SIZE: u32 = 10_000;
+const DEFAULT_BATCH_RATE_LIMIT: f64 = 0.0;
+const DEFAULT_BATCH_RATE_BURST: u32 = 50;
+const DEFAULT_RECEIPT_RETENTION_BLOCKS: u64 = 0;
+const DEFAULT_RECEIPT_RETENTION_COUNT: u64 = 0;
+const DEFAULT_RECEIPT_SYNC_MODE: &str = "mapasync";
+const DEFAULT_CONSENSUS_MAX_MISSED_PINGS: u32 = 3;
+
+const PEERING_TYPES: &[&str] = &["static", "dynamic"];
+const SCHEDULER_TYPES: &[&str] = &["serial", "parallel"];
+const RECEIPT_SYNC_MODES: &[&str] = &["sync", "mapasync", "nosync"];
+const NETWORK_AUTH_TYPES: &[&str] = &["trust", "challenge"];
+
+#[derive(Debug)]
+pub enum ConfigError {
+    ReadError(String),
+    ParseError(String),
+    InvalidValue(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConfigError::ReadError(ref msg) => write!(f, "Unable to read config: {}", msg),
+            ConfigError::ParseError(ref msg) => write!(f, "Unable to parse config: {}", msg),
+            ConfigError::InvalidValue(ref msg) => write!(f, "Invalid config: {}", msg),
+        }
+    }
+}
+
+impl Error for ConfigError {
+    fn description(&self) -> &str {
+        match *self {
+            ConfigError::ReadError(ref msg) => msg,
+            ConfigError::ParseError(ref msg) => msg,
+            ConfigError::InvalidValue(ref msg) => msg,
+        }
+    }
+}
+
+/// A configuration source in which every value is optional; values from
+/// several sources are merged with `merge`, then resolved against the
+/// defaults.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PartialValidatorConfig {
+    pub bind: Option<Vec<String>>,
+    pub endpoint: Option<String>,
+    pub peering: Option<String>,
+    pub seeds: Option<Vec<String>>,
+    pub peers: Option<Vec<String>>,
+    pub network_public_key: Option<String>,
+    pub network_private_key: Option<String>,
+    pub scheduler: Option<String>,
+    pub permissions: Option<BTreeMap<String, String>>,
+    pub roles: Option<BTreeMap<String, String>>,
+    pub opentsdb_url: Option<String>,
+    pub opentsdb_db: Option<String>,
+    pub opentsdb_username: Option<String>,
+    pub opentsdb_password: Option<String>,
+    pub minimum_peer_connectivity: Option<u32>,
+    pub maximum_peer_connectivity: Option<u32>,
+    pub state_pruning_block_depth: Option<u32>,
+    pub fork_cache_keep_time: Option<u32>,
+    pub component_thread_pool_workers: Option<u32>,
+    pub network_thread_pool_workers: Option<u32>,
+    pub signature_thread_pool_workers: Option<u32>,
+    pub merkle_node_cache_size: Option<u32>,
+    pub batch_rate_limit: Option<f64>,
+    pub batch_rate_burst: Option<u32>,
+    pub receipt_retention_blocks: Option<u64>,
+    pub receipt_retention_count: Option<u64>,
+    pub receipt_sync_mode: Option<String>,
+    pub consensus_max_missed_pings: Option<u32>,
+    pub network: Option<PartialNetworkConfig>,
+}
+
+/// The `[network]` table of validator.toml.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PartialNetworkConfig {
+    /// Peer with validators whose chain id differs from this validator's
+    pub accept_any_chain_id: Option<bool>,
+}
+
+impl PartialValidatorConfig {
+    pub fn from_toml_str(contents: &str) -> Result<Self, ConfigError> {
+        toml::from_str(contents).map_err(|err| ConfigError::ParseError(err.to_string()))
+    }
+
+    /// Loads validator.toml from the given file; a missing file is treated as
+    /// an empty configuration.
+    pub fn from_toml_file(path: &Path) -> Result<Self, ConfigError> {
+        if !path.exists() {
+            info!(
+                "Skipping validator config loading from non-existent config file: {}",
+                path.display()
+            );
+            return Ok(PartialValidatorConfig::default());
+        }
+
+        info!(
+            "Loading validator information from config: {}",
+            path.display()
+        );
+
+        let contents = fs::read_to_string(path)
+            .map_err(|err| ConfigError::ReadError(format!("{}: {}", path.display(), err)))?;
+
+        PartialValidatorConfig::from_toml_str(&contents)
+    }
+
+    /// Fills any value unset in this configuration from other.
+    pub fn merge(self, other: PartialValidatorConfig) -> PartialValidatorConfig {
+        // Binds are merged per endpoint, so that binding only the network
+        // endpoint on the command line keeps the component binding from the
+        // config file.
+        let bind = match (self.bind, other.bind) {
+            (Some(mut first), Some(second)) => {
+                for binding in second {
+                    let name = binding.splitn(2, ':').next().unwrap_or("").to_string();
+                    if !first.iter().any(|b| b.splitn(2, ':').next() == Some(&name)) {
+                        first.push(binding);
+                    }
+                }
+                Some(first)
+            }
+            (first, second) => first.or(second),
+        };
+
+        PartialValidatorConfig {
+            bind,
+            endpoint: self.endpoint.or(other.endpoint),
+            peering: self.peering.or(other.peering),
+            seeds: self.seeds.or(other.seeds),
+            peers: self.peers.or(other.peers),
+            network_public_key: self.network_public_key.or(other.network_public_key),
+            network_private_key: self.network_private_key.or(other.network_private_key),
+            scheduler: self.scheduler.or(other.scheduler),
+            permissions: self.permissions.or(other.permissions),
+            roles: self.roles.or(other.roles),
+            opentsdb_url: self.opentsdb_url.or(other.opentsdb_url),
+            opentsdb_db: self.opentsdb_db.or(other.opentsdb_db),
+            opentsdb_username: self.opentsdb_username.or(other.opentsdb_username),
+            opentsdb_password: self.opentsdb_password.or(other.opentsdb_password),
+            minimum_peer_connectivity: self
+                .minimum_peer_connectivity
+                .or(other.minimum_peer_connectivity),
+            maximum_peer_connectivity: self
+                .maximum_peer_connectivity
+                .or(other.maximum_peer_connectivity),
+            state_pruning_block_depth: self
+                .state_pruning_block_depth
+                .or(other.state_pruning_block_depth),
+            fork_cache_keep_time: self.fork_cache_keep_time.or(other.fork_cache_keep_time),
+            component_thread_pool_workers: self
+                .component_thread_pool_workers
+                .or(other.component_thread_pool_workers),
+            network_thread_pool_workers: self
+                .network_thread_pool_workers
+                .or(other.network_thread_pool_workers),
+            signature_thread_pool_workers: self
+                .signature_thread_pool_workers
+                .or(other.signature_thread_pool_workers),
+            merkle_node_cache_size: self.merkle_node_cache_size.or(other.merkle_node_cache_size),
+            batch_rate_limit: self.batch_rate_limit.or(other.batch_rate_limit),
+            batch_rate_burst: self.batch_rate_burst.or(other.batch_rate_burst),
+            receipt_retention_blocks: self
+                .receipt_retention_blocks
+                .or(other.receipt_retention_blocks),
+            receipt_retention_count: self
+                .receipt_retention_count
+                .or(other.receipt_retention_count),
+            receipt_sync_mode: self.receipt_sync_mode.or(other.receipt_sync_mode),
+            consensus_max_missed_pings: self
+                .consensus_max_missed_pings
+                .or(other.consensus_max_missed_pings),
+            network: match (self.network, other.network) {
+                (Some(network), Some(other_network)) => Some(PartialNetworkConfig {
+                    accept_any_chain_id: network
+                        .accept_any_chain_id
+                        .or(other_network.accept_any_chain_id),
+                }),
+                (network, other_network) => network.or(other_network),
+            },
+        }
+    }
+
+    /// Applies the defaults to any unset values and validates the result.
+    pub fn resolve(self) -> Result<ValidatorConfig, ConfigError> {
+        let mut bind_network = None;
+        let mut bind_component = None;
+        let mut bind_consensus = None;
+        for binding in self.bind.unwrap_or_default() {
+            let mut parts = binding.splitn(2, ':');
+            let name = parts.next().unwrap_or("");
+            let endpoint = parts.next().map(String::from);
+            let target = match name {
+                "network" => &mut bind_network,
+                "component" => &mut bind_component,
+                "consensus" => &mut bind_consensus,
+                _ => {
+                    return Err(ConfigError::InvalidValue(format!(
+                        "bind must be network:<endpoint>, component:<endpoint> or \
+                         consensus:<endpoint>, found {}",
+                        binding
+                    )))
+                }
+            };
+            if target.is_none() {
+                *target = endpoint;
+            }
+        }
+
+        let peering = check_one_of(
+            "peering",
+            self.peering.unwrap_or_else(|| DEFAULT_PEERING.into()),
+            PEERING_TYPES,
+        )?;
+        let scheduler = check_one_of(
+            "scheduler",
+            self.scheduler.unwrap_or_else(|| DEFAULT_SCHEDULER.into()),
+            SCHEDULER_TYPES,
+        )?;
+        let receipt_sync_mode = check_one_of(
+            "receipt_sync_mode",
+            self.receipt_sync_mode
+                .unwrap_or_else(|| DEFAULT_RECEIPT_SYNC_MODE.into()),
+            RECEIPT_SYNC_MODES,
+        )?;
+
+        if let Some(network_auth) = self.roles.as_ref().and_then(|roles| roles.get("network")) {
+            check_one_of("roles.network", network_auth.clone(), NETWORK_AUTH_TYPES)?;
+        }
+
+        let minimum_peer_connectivity = self
+            .minimum_peer_connectivity
+            .unwrap_or(DEFAULT_MINIMUM_PEER_CONNECTIVITY);
+        let maximum_peer_connectivity = self
+            .maximum_peer_connectivity
+            .unwrap_or(DEFAULT_MAXIMUM_PEER_CONNECTIVITY);
+        if minimum_peer_connectivity > maximum_peer_connectivity {
+            return Err(ConfigError::InvalidValue(format!(
+                "minimum_peer_connectivity ({}) must not be greater than \
+                 maximum_peer_connectivity ({})",
+                minimum_peer_connectivity, maximum_peer_connectivity
+            )));
+        }
+
+        let batch_rate_limit = self.batch_rate_limit.unwrap_or(DEFAULT_BATCH_RATE_LIMIT);
+        if batch_rate_limit < 0.0 {
+            return Err(ConfigError::InvalidValue(
+                "batch_rate_limit must not be negative".into(),
+            ));
+        }
+
+        let consensus_max_missed_pings = self
+            .consensus_max_missed_pings
+            .unwrap_or(DEFAULT_CONSENSUS_MAX_MISSED_PINGS);
+        if consensus_max_missed_pings == 0 {
+            return Err(ConfigError::InvalidValue(
+                "consensus_max_missed_pings must be greater than 0".into(),
+            ));
+        }
+
+        Ok(ValidatorConfig {
+            bind_network: bind_network.unwrap_or_else(|| DEFAULT_BIND_NETWORK.into()),
+            bind_component: bind_component.unwrap_or_else(|| DEFAULT_BIND_COMPONENT.into()),
+            bind_consensus: bind_consensus.unwrap_or_else(|| DEFAULT_BIND_CONSENSUS.into()),
+            endpoint: self.endpoint,
+            peering,
+            seeds: self.seeds.unwrap_or_default(),
+            peers: self.peers.unwrap_or_default(),
+            network_public_key: self.network_public_key,
+            network_private_key: self.network_private_key,
+            scheduler,
+            permissions: self.permissions,
+            roles: self.roles,
+            opentsdb_url: self.opentsdb_url,
+            opentsdb_db: self.opentsdb_db,
+            opentsdb_username: self.opentsdb_username,
+            opentsdb_password: self.opentsdb_password,
+            minimum_peer_connectivity,
+            maximum_peer_connectivity,
+            state_pruning_block_depth: self
+                .state_pruning_block_depth
+                .unwrap_or(DEFAULT_STATE_PRUNING_BLOCK_DEPTH),
+            fork_cache_keep_time: self
+                .fork_cache_keep_time
+                .unwrap_or(DEFAULT_FORK_CACHE_KEEP_TIME),
+            component_thread_pool_workers: self
+                .component_thread_pool_workers
+                .unwrap_or(DEFAULT_COMPONENT_THREAD_POOL_WORKERS),
+            network_thread_pool_workers: self
+                .network_thread_pool_workers
+                .unwrap_or(DEFAULT_NETWORK_THREAD_POOL_WORKERS),
+            signature_thread_pool_workers: self
+                .signature_thread_pool_workers
+                .unwrap_or(DEFAULT_SIGNATURE_THREAD_POOL_WORKERS),
+            merkle_node_cache_size: self
+                .merkle_node_cache_size
+                .unwrap_or(DEFAULT_MERKLE_NODE_CACHE_SIZE),
+            batch_rate_limit,
+            batch_rate_burst: self.batch_rate_burst.unwrap_or(DEFAULT_BATCH_RATE_BURST),
+            receipt_retention_blocks: self
+                .receipt_retention_blocks
+                .unwrap_or(DEFAULT_RECEIPT_RETENTION_BLOCKS),
+            receipt_retention_count: self
+                .receipt_retention_count
+                .unwrap_or(DEFAULT_RECEIPT_RETENTION_COUNT),
+            receipt_sync_mode,
+            consensus_max_missed_pings,
+            accept_any_chain_id: self
+                .network
+                .and_then(|network| network.accept_any_chain_id)
+                .unwrap_or(false),
+        })
+    }
+}
+
+/// The fully resolved validator configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidatorConfig {
+    pub bind_network: String,
+    pub bind_component: String,
+    pub bind_consensus: String,
+    pub endpoint: Option<String>,
+    pub peering: String,
+    pub seeds: Vec<String>,
+    pub peers: Vec<String>,
+    pub network_public_key: Option<String>,
+    pub network_private_key: Option<String>,
+    pub scheduler: String,
+    /// Role names mapped to the names of policy files in the policy
+    /// directory; the files are read by the Python validator
+    pub permissions: Option<BTreeMap<String, String>>,
+    pub roles: Option<BTreeMap<String, String>>,
+    pub opentsdb_url: Option<String>,
+    pub opentsdb_db: Option<String>,
+    pub opentsdb_username: Option<String>,
+    pub opentsdb_password: Option<String>,
+    pub minimum_peer_connectivity: u32,
+    pub maximum_peer_connectivity: u32,
+    pub state_pruning_block_depth: u32,
+    pub fork_cache_keep_time: u32,
+    pub component_thread_pool_workers: u32,
+    pub network_thread_pool_workers: u32,
+    pub signature_thread_pool_workers: u32,
+    pub merkle_node_cache_size: u32,
+    pub batch_rate_limit: f64,
+    pub batch_rate_burst: u32,
+    pub receipt_retention_blocks: u64,
+    pub receipt_retention_count: u64,
+    pub receipt_sync_mode: String,
+    pub consensus_max_missed_pings: u32,
+    pub accept_any_chain_id: bool,
+}
+
+impl ValidatorConfig {
+    /// Converts the configuration into a dict of the keyword arguments of
+    /// the Python ValidatorConfig.
+    pub fn to_pydict(&self, py: Python) -> PyResult<PyDict> {
+        let pydict = PyDict::new(py);
+
+        pydict.set_item(py, "bind_network", &self.bind_network)?;
+        pydict.set_item(py, "bind_component", &self.bind_component)?;
+        pydict.set_item(py, "bind_consensus", &self.bind_consensus)?;
+        pydict.set_item(py, "endpoint", &self.endpoint)?;
+        pydict.set_item(py, "peering", &self.peering)?;
+        pydict.set_item(py, "seeds", &self.seeds)?;
+        pydict.set_item(py, "peers", &self.peers)?;
+        pydict.set_item(py, "network_public_key", &self.network_public_key)?;
+        pydict.set_item(py, "network_private_key", &self.network_private_key)?;
+        pydict.set_item(py, "scheduler", &self.scheduler)?;
+        pydict.set_item(py, "permissions", map_to_pydict(py, &self.permissions)?)?;
+        pydict.set_item(py, "roles", map_to_pydict(py, &self.roles)?)?;
+        pydict.set_item(py, "opentsdb_url", &self.opentsdb_url)?;
+        pydict.set_item(py, "opentsdb_db", &self.opentsdb_db)?;
+        pydict.set_item(py, "opentsdb_username", &self.opentsdb_username)?;
+        pydict.set_item(py, "opentsdb_password", &self.opentsdb_password)?;
+        pydict.set_item(
+            py,
+            "minimum_peer_connectivity",
+            self.minimum_peer_connectivity,
+        )?;
+        pydict.set_item(
+            py,
+            "maximum_peer_connectivity",
+            self.maximum_peer_connectivity,
+        )?;
+        pydict.set_item(
+            py,
+            "state_pruning_block_depth",
+            self.state_pruning_block_depth,
+        )?;
+        pydict.set_item(py, "fork_cache_keep_time", self.fork_cache_keep_time)?;
+        pydict.set_item(
+            py,
+            "component_thread_pool_workers",
+            self.component_thread_pool_workers,
+        )?;
+        pydict.set_item(
+            py,
+            "network_thread_pool_workers",
+            self.network_thread_pool_workers,
+        )?;
+        pydict.set_item(
+            py,
+            "signature_thread_pool_workers",
+            self.signature_thread_pool_workers,
+        )?;
+        pydict.set_item(py, "merkle_node_cache_size", self.merkle_node_cache_size)?;
+        pydict.set_item(py, "batch_rate_limit", self.batch_rate_limit)?;
+        pydict.set_item(py, "batch_rate_burst", self.batch_rate_burst)?;
+        pydict.set_item(
+            py,
+            "receipt_retention_blocks",
+            self.receipt_retention_blocks,
+        )?;
+        pydict.set_item(py, "receipt_retention_count", self.receipt_retention_count)?;
+        pydict.set_item(py, "receipt_sync_mode", &self.receipt_sync_mode)?;
+        pydict.set_item(
+            py,
+            "consensus_max_missed_pings",
+            self.consensus_max_missed_pings,
+        )?;
+        pydict.set_item(py, "accept_any_chain_id", self.accept_any_chain_id)?;
+
+        Ok(pydict)
+    }
+}
+
+/// Returns the directory holding validator.toml: the given directory, or
+/// $SAWTOOTH_HOME/etc if SAWTOOTH_HOME is set, or /etc/sawtooth.
+pub fn config_dir(config_dir: Option<&str>) -> PathBuf {
+    match config_dir {
+        Some(dir) => PathBuf::from(dir),
+        None => match env::var("SAWTOOTH_HOME") {
+            Ok(home) => Path::new(&home).join("etc"),
+            Err(_) => PathBuf::from("/etc/sawtooth"),
+        },
+    }
+}
+
+/// Loads the validator configuration, giving the command line arguments
+/// precedence over validator.toml in the config directory, and
+/// validator.toml precedence over the defaults.
+pub fn load_validator_config(
+    args_config: PartialValidatorConfig,
+    config_dir: &Path,
+) -> Result<ValidatorConfig, ConfigError> {
+    let toml_config = PartialValidatorConfig::from_toml_file(&config_dir.join("validator.toml"))?;
+
+    args_config.merge(toml_config).resolve()
+}
+
+fn check_one_of(name: &str, value: String, allowed: &[&str]) -> Result<String, ConfigError> {
+    if allowed.contains(&value.as_str()) {
+        Ok(value)
+    } else {
+        Err(ConfigError::InvalidValue(format!(
+            "{} must be one of {}, found {}",
+            name,
+            allowed.join(", "),
+            value
+        )))
+    }
+}
+
+fn map_to_pydict(py: Python, map: &Option<BTreeMap<String, String>>) -> PyResult<Option<PyDict>> {
+    match map {
+        Some(map) => {
+            let pydict = PyDict::new(py);
+            for (key, value) in map {
+                pydict.set_item(py, key, value)?;
+            }
+            Ok(Some(pydict))
+        }
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_defaults() {
+        let config = PartialValidatorConfig::default().resolve().unwrap();
+
+        assert_eq!(config.bind_network, DEFAULT_BIND_NETWORK);
+        assert_eq!(config.bind_component, DEFAULT_BIND_COMPONENT);
+        assert_eq!(config.bind_consensus, DEFAULT_BIND_CONSENSUS);
+        assert_eq!(config.peering, "static");
+        assert_eq!(config.scheduler, "parallel");
+        assert_eq!(config.minimum_peer_connectivity, 3);
+        assert_eq!(config.maximum_peer_connectivity, 10);
+        assert_eq!(config.receipt_sync_mode, "mapasync");
+        assert_eq!(config.consensus_max_missed_pings, 3);
+        assert!(!config.accept_any_chain_id);
+        assert!(config.seeds.is_empty());
+        assert_eq!(config.endpoint, None);
+    }
+
+    #[test]
+    fn parse_toml() {
+        let config = PartialValidatorConfig::from_toml_str(
+            r#"
+            bind = ["network:tcp://127.0.0.1:8801", "component:tcp://127.0.0.1:4005"]
+            peering = "dynamic"
+            seeds = ["tcp://seed:8800"]
+            scheduler = "serial"
+            minimum_peer_connectivity = 1
+            batch_rate_limit = 2.5
+
+            [roles]
+            network = "trust"
+
+            [permissions]
+            transactor = "policy.transactor"
+
+            [network]
+            accept_any_chain_id = true
+            "#,
+        )
+        .unwrap()
+        .resolve()
+        .unwrap();
+
+        assert_eq!(config.bind_network, "tcp://127.0.0.1:8801");
+        assert_eq!(config.bind_component, "tcp://127.0.0.1:4005");
+        assert_eq!(config.bind_consensus, DEFAULT_BIND_CONSENSUS);
+        assert_eq!(config.peering, "dynamic");
+        assert_eq!(config.seeds, vec!["tcp://seed:8800".to_string()]);
+        assert_eq!(config.scheduler, "serial");
+        assert_eq!(config.minimum_peer_connectivity, 1);
+        assert!((config.batch_rate_limit - 2.5).abs() < std::f64::EPSILON);
+        assert_eq!(
+            config.roles.unwrap().get("network").map(String::as_str),
+            Some("trust")
+        );
+        assert_eq!(
+            config
+                .permissions
+                .unwrap()
+                .get("transactor")
+                .map(String::as_str),
+            Some("policy.transactor")
+        );
+        assert!(config.accept_any_chain_id);
+    }
+
+    /// Uncomments a line of validator.toml.example which sets an option or
+    /// opens a table, leaving prose comments as they are.
+    fn uncomment_option(line: &str) -> &str {
+        let option = line.trim_start_matches("# ");
+        let is_table = option.starts_with('[') && option.ends_with(']');
+        let is_key = option.contains(" = ")
+            && option
+                .split(" = ")
+                .next()
+                .unwrap_or("")
+                .trim_matches('"')
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c == '_' || c == '.');
+        if line.starts_with("# ") && (is_table || is_key) {
+            option
+        } else {
+            line
+        }
+    }
+
+    /// The example config, and every option it documents, must be accepted.
+    #[test]
+    fn load_example_toml() {
+        let example = include_str!("../../packaging/validator.toml.example");
+        let config = PartialValidatorConfig::from_toml_str(example)
+            .unwrap()
+            .resolve()
+            .unwrap();
+        assert_eq!(config.consensus_max_missed_pings, 3);
+
+        let uncommented = example
+            .lines()
+            .map(uncomment_option)
+            .collect::<Vec<_>>()
+            .join("\n");
+        let partial = PartialValidatorConfig::from_toml_str(&uncommented).unwrap();
+        assert_eq!(partial.consensus_max_missed_pings, Some(3));
+        assert_eq!(partial.receipt_sync_mode, Some("mapasync".into()));
+        assert_eq!(
+            partial
+                .network
+                .as_ref()
+                .and_then(|network| network.accept_any_chain_id),
+            Some(false)
+        );
+        partial.resolve().unwrap();
+    }
+
+    #[test]
+    fn reject_unknown_keys() {
+        assert!(PartialValidatorConfig::from_toml_str("unknown_key = 1").is_err());
+        assert!(PartialValidatorConfig::from_toml_str("[network]\nunknown_key = 1").is_err());
+    }
+
+    #[test]
+    fn merge_prefers_first() {
+        let args = PartialValidatorConfig {
+            bind: Some(vec!["network:tcp://0.0.0.0:8800".into()]),
+            scheduler: Some("serial".into()),
+            ..PartialValidatorConfig::default()
+        };
+        let file = PartialValidatorConfig {
+            bind: Some(vec![
+                "network:tcp://127.0.0.1:8801".into(),
+                "component:tcp://127.0.0.1:4005".into(),
+            ]),
+            scheduler: Some("parallel".into()),
+            peering: Some("dynamic".into()),
+            ..PartialValidatorConfig::default()
+        };
+
+        let config = args.merge(file).resolve().unwrap();
+
+        assert_eq!(config.bind_network, "tcp://0.0.0.0:8800");
+        assert_eq!(config.bind_component, "tcp://127.0.0.1:4005");
+        assert_eq!(config.scheduler, "serial");
+        assert_eq!(config.peering, "dynamic");
+    }
+
+    #[test]
+    fn reject_invalid_values() {
+        let invalid = vec![
+            PartialValidatorConfig {
+                peering: Some("sometimes".into()),
+                ..PartialValidatorConfig::default()
+            },
+            PartialValidatorConfig {
+                bind: Some(vec!["gossip:tcp://127.0.0.1:8800".into()]),
+                ..PartialValidatorConfig::default()
+            },
+            PartialValidatorConfig {
+                minimum_peer_connectivity: Some(11),
+                ..PartialValidatorConfig::default()
+            },
+            PartialValidatorConfig {
+                receipt_sync_mode: Some("later".into()),
+                ..PartialValidatorConfig::default()
+            },
+            PartialValidatorConfig {
+                consensus_max_missed_pings: Some(0),
+                ..PartialValidatorConfig::default()
+            },
+        ];
+
+        for config in invalid {
+            assert!(config.resolve().is_err());
+        }
+    }
+}
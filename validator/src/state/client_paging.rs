@@ -0,0 +1,235 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+//! Paging of the resources listed by the client handlers.
+//!
+//! A page is identified by the id of its first resource, so the continuation
+//! token of a listing is the id of the first resource of the next page. The
+//! same resources in the same order always produce the same pages.
+
+/// The largest number of resources returned in a page.
+pub const MAX_PAGE_SIZE: usize = 1000;
+
+/// The number of resources returned in a page when no limit is requested.
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+
+/// A page of a listing, as positions in the paging order: the listing order,
+/// or its reverse if the listing is reversed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Page {
+    /// The position of the first resource of the page
+    pub start: usize,
+    /// The position after the last resource of the page, which is the
+    /// position of the first resource of the next page if there is one
+    pub end: usize,
+    /// The limit the page was read with
+    pub limit: usize,
+}
+
+impl Page {
+    /// Whether there are resources after this page.
+    pub fn has_next(&self, len: usize) -> bool {
+        self.end < len
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PagingError {
+    /// No resource in the listing has the requested start id
+    StartNotFound(String),
+}
+
+/// Returns the limit to read a page with, given the requested limit: the
+/// default if none was requested, and never more than the maximum.
+pub fn page_limit(requested: usize) -> usize {
+    if requested == 0 {
+        DEFAULT_PAGE_SIZE
+    } else {
+        requested.min(MAX_PAGE_SIZE)
+    }
+}
+
+/// Finds the page of the resources with the given ids which starts at the
+/// resource with id start, or at the first resource if start is None.
+pub fn paginate<S: AsRef<str>>(
+    ids: &[S],
+    start: Option<&str>,
+    limit: usize,
+    reverse: bool,
+) -> Result<Page, PagingError> {
+    let limit = page_limit(limit);
+
+    let start_position = match start {
+        Some(start) => position_of(ids, start, reverse)
+            .ok_or_else(|| PagingError::StartNotFound(start.to_string()))?,
+        None => 0,
+    };
+
+    Ok(Page {
+        start: start_position,
+        end: ids.len().min(start_position + limit),
+        limit,
+    })
+}
+
+/// Returns the index in the listing order of the resource at the given
+/// position in the paging order.
+pub fn index_of_position(len: usize, position: usize, reverse: bool) -> usize {
+    if reverse {
+        len - 1 - position
+    } else {
+        position
+    }
+}
+
+fn position_of<S: AsRef<str>>(ids: &[S], id: &str, reverse: bool) -> Option<usize> {
+    (0..ids.len())
+        .find(|&position| ids[index_of_position(ids.len(), position, reverse)].as_ref() == id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(len: usize) -> Vec<String> {
+        (0..len).map(|i| format!("{:04x}", i)).collect()
+    }
+
+    #[test]
+    fn limits() {
+        assert_eq!(page_limit(0), DEFAULT_PAGE_SIZE);
+        assert_eq!(page_limit(7), 7);
+        assert_eq!(page_limit(MAX_PAGE_SIZE + 1), MAX_PAGE_SIZE);
+    }
+
+    #[test]
+    fn pages_forward_and_reverse() {
+        let ids = ids(5);
+
+        let page = paginate(&ids, None, 2, false).unwrap();
+        assert_eq!(
+            page,
+            Page {
+                start: 0,
+                end: 2,
+                limit: 2
+            }
+        );
+        assert!(page.has_next(ids.len()));
+
+        let page = paginate(&ids, Some("0003"), 2, false).unwrap();
+        assert_eq!(
+            page,
+            Page {
+                start: 3,
+                end: 5,
+                limit: 2
+            }
+        );
+        assert!(!page.has_next(ids.len()));
+
+        // In reverse, "0003" is the second resource
+        let page = paginate(&ids, Some("0003"), 2, true).unwrap();
+        assert_eq!(
+            page,
+            Page {
+                start: 1,
+                end: 3,
+                limit: 2
+            }
+        );
+        assert_eq!(index_of_position(ids.len(), page.start, true), 3);
+    }
+
+    #[test]
+    fn unknown_start() {
+        assert_eq!(
+            paginate(&ids(3), Some("ffff"), 2, false),
+            Err(PagingError::StartNotFound("ffff".into()))
+        );
+        assert!(paginate(&ids(0), Some("0000"), 2, false).is_err());
+    }
+
+    /// A small xorshift generator, so that the fuzz tests are reproducible
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self, bound: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % bound as u64) as usize
+        }
+    }
+
+    /// Following the continuation tokens from the first page visits every
+    /// resource exactly once, in paging order, for any listing and limit.
+    #[test]
+    fn fuzz_pages_cover_listing() {
+        let mut rng = Rng(0x5eed_1234_abcd_ef01);
+
+        for _ in 0..500 {
+            let len = rng.next(300);
+            let limit = rng.next(MAX_PAGE_SIZE + 50);
+            let reverse = rng.next(2) == 1;
+            let ids = ids(len);
+
+            let mut visited = vec![];
+            let mut start: Option<String> = None;
+            loop {
+                let page =
+                    paginate(&ids, start.as_ref().map(String::as_str), limit, reverse).unwrap();
+                assert!(page.end - page.start <= page_limit(limit));
+                assert!(page.end > page.start || len == 0);
+
+                for position in page.start..page.end {
+                    visited.push(ids[index_of_position(len, position, reverse)].clone());
+                }
+
+                if !page.has_next(len) {
+                    break;
+                }
+                start = Some(ids[index_of_position(len, page.end, reverse)].clone());
+            }
+
+            let mut expected = ids.clone();
+            if reverse {
+                expected.reverse();
+            }
+            assert_eq!(visited, expected);
+        }
+    }
+
+    /// Any page started from a random resource begins with that resource,
+    /// and is the same page every time it is requested.
+    #[test]
+    fn fuzz_pages_are_deterministic() {
+        let mut rng = Rng(0x0dd_ba11_cafe_f00d);
+
+        for _ in 0..500 {
+            let len = rng.next(300) + 1;
+            let limit = rng.next(MAX_PAGE_SIZE + 50);
+            let reverse = rng.next(2) == 1;
+            let ids = ids(len);
+            let start = &ids[rng.next(len)];
+
+            let page = paginate(&ids, Some(start), limit, reverse).unwrap();
+            assert_eq!(&ids[index_of_position(len, page.start, reverse)], start);
+            assert_eq!(paginate(&ids, Some(start), limit, reverse).unwrap(), page);
+        }
+    }
+}
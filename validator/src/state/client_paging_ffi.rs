@@ -0,0 +1,105 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::slice;
+
+use state::client_paging::{self, PagingError};
+
+#[repr(u32)]
+#[derive(Debug)]
+pub enum ErrorCode {
+    Success = 0,
+    NullPointerProvided = 0x01,
+    InvalidId = 0x02,
+    StartNotFound = 0x03,
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn client_paging_limit(requested: usize, limit: *mut usize) -> ErrorCode {
+    if limit.is_null() {
+        return ErrorCode::NullPointerProvided;
+    }
+
+    *limit = client_paging::page_limit(requested);
+
+    ErrorCode::Success
+}
+
+/// Finds a page of the resources with the given ids. A null start reads the
+/// first page. The page is returned as positions in the paging order, which
+/// is the reverse of the ids if reverse is set.
+#[no_mangle]
+pub unsafe extern "C" fn client_paging_paginate(
+    ids: *const *const c_char,
+    ids_len: usize,
+    start: *const c_char,
+    limit: usize,
+    reverse: bool,
+    page_start: *mut usize,
+    page_end: *mut usize,
+    page_limit: *mut usize,
+) -> ErrorCode {
+    if (ids_len > 0 && ids.is_null())
+        || page_start.is_null()
+        || page_end.is_null()
+        || page_limit.is_null()
+    {
+        return ErrorCode::NullPointerProvided;
+    }
+
+    let ids: Result<Vec<&str>, ErrorCode> = if ids_len > 0 {
+        slice::from_raw_parts(ids, ids_len)
+            .iter()
+            .map(|id| {
+                if id.is_null() {
+                    return Err(ErrorCode::NullPointerProvided);
+                }
+                CStr::from_ptr(*id)
+                    .to_str()
+                    .map_err(|_| ErrorCode::InvalidId)
+            })
+            .collect()
+    } else {
+        Ok(Vec::with_capacity(0))
+    };
+    let ids = match ids {
+        Ok(ids) => ids,
+        Err(err) => return err,
+    };
+
+    let start = if start.is_null() {
+        None
+    } else {
+        match CStr::from_ptr(start).to_str() {
+            Ok(start) => Some(start),
+            Err(_) => return ErrorCode::InvalidId,
+        }
+    };
+
+    match client_paging::paginate(&ids, start, limit, reverse) {
+        Ok(page) => {
+            *page_start = page.start;
+            *page_end = page.end;
+            *page_limit = page.limit;
+
+            ErrorCode::Success
+        }
+        Err(PagingError::StartNotFound(_)) => ErrorCode::StartNotFound,
+    }
+}
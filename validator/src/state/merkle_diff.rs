@@ -0,0 +1,255 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+//! The changes between two state roots of the merkle trie.
+//!
+//! Both tries are walked together, and a subtree with the same hash under both
+//! roots is skipped, so the cost is proportional to the number of changed
+//! nodes rather than to the size of state.
+
+use std::collections::BTreeSet;
+
+use protobuf::{Message, RepeatedField};
+use transact::database::Database;
+
+use proto::merkle::{MerkleStateDiff, MerkleStateDiff_Change};
+use state::merkle_proof::{MerkleProofError, Node};
+
+/// An address whose value differs between two state roots. A value of None
+/// means the address is not set under that root.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StateDiff {
+    pub address: String,
+    pub old_value: Option<Vec<u8>>,
+    pub new_value: Option<Vec<u8>>,
+}
+
+pub fn diffs_into_bytes(diffs: Vec<StateDiff>) -> Result<Vec<u8>, MerkleProofError> {
+    let mut proto = MerkleStateDiff::new();
+    proto.set_changes(RepeatedField::from_vec(
+        diffs
+            .into_iter()
+            .map(|diff| {
+                let mut change = MerkleStateDiff_Change::new();
+                change.set_address(diff.address);
+                change.set_old_value(diff.old_value.unwrap_or_default());
+                change.set_new_value(diff.new_value.unwrap_or_default());
+                change
+            })
+            .collect(),
+    ));
+    Ok(proto.write_to_bytes()?)
+}
+
+/// Returns the addresses whose values differ between `from_root` and
+/// `to_root`, in address order.
+///
+/// Either state root not being present is an error.
+pub fn diff(
+    database: &dyn Database,
+    from_root: &str,
+    to_root: &str,
+) -> Result<Vec<StateDiff>, MerkleProofError> {
+    let reader = database.get_reader()?;
+    let read_node = |node_hash: &str| -> Result<Node, MerkleProofError> {
+        match reader.get(node_hash.as_bytes())? {
+            Some(bytes) => Node::from_bytes(&bytes),
+            None => Err(MerkleProofError::NotFound(format!("node {}", node_hash))),
+        }
+    };
+
+    // Read both roots up front, so that a missing root is an error even when
+    // the roots are equal.
+    read_node(from_root)?;
+    read_node(to_root)?;
+
+    let mut diffs = vec![];
+    let mut stack = vec![(
+        String::new(),
+        Some(from_root.to_string()),
+        Some(to_root.to_string()),
+    )];
+
+    while let Some((path, from_hash, to_hash)) = stack.pop() {
+        if from_hash == to_hash {
+            continue;
+        }
+
+        let from_node = match from_hash {
+            Some(ref hash) => Some(read_node(hash)?),
+            None => None,
+        };
+        let to_node = match to_hash {
+            Some(ref hash) => Some(read_node(hash)?),
+            None => None,
+        };
+
+        let old_value = from_node.as_ref().and_then(|node| node.value.clone());
+        let new_value = to_node.as_ref().and_then(|node| node.value.clone());
+        if old_value != new_value {
+            diffs.push(StateDiff {
+                address: path.clone(),
+                old_value,
+                new_value,
+            });
+        }
+
+        let child_hash = |node: &Option<Node>, token: &str| {
+            node.as_ref()
+                .and_then(|node| node.children.get(token).cloned())
+        };
+        let tokens = from_node
+            .iter()
+            .chain(to_node.iter())
+            .flat_map(|node| node.children.keys().cloned())
+            .collect::<BTreeSet<_>>();
+
+        // Children are pushed in reverse so that they are popped in order.
+        for token in tokens.iter().rev() {
+            stack.push((
+                format!("{}{}", path, token),
+                child_hash(&from_node, token),
+                child_hash(&to_node, token),
+            ));
+        }
+    }
+
+    Ok(diffs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+    use std::fs::remove_file;
+    use std::panic;
+    use std::path::Path;
+    use std::thread;
+
+    use transact::database::lmdb::{LmdbContext, LmdbDatabase};
+    use transact::state::merkle::{MerkleRadixTree, INDEXES};
+    use transact::state::StateChange;
+
+    const TEST_DB_SIZE: usize = 10 * 1024 * 1024;
+
+    #[test]
+    fn diff_lists_changed_addresses() {
+        run_test(|db_path| {
+            let db = make_lmdb(db_path);
+            let mut merkle_db = MerkleRadixTree::new(Box::new(db.clone()), None)
+                .expect("Could not create merkle database");
+
+            let first_root = merkle_db
+                .update(
+                    &[
+                        set_change("ab0000", b"a"),
+                        set_change("ab0001", b"b"),
+                        set_change("cd0000", b"c"),
+                    ],
+                    false,
+                )
+                .expect("Unable to update state");
+            merkle_db
+                .set_merkle_root(first_root.clone())
+                .expect("Unable to set root");
+
+            let second_root = merkle_db
+                .update(
+                    &[
+                        set_change("ab0001", b"B"),
+                        set_change("ef0000", b"d"),
+                        StateChange::Delete {
+                            key: "cd0000".into(),
+                        },
+                    ],
+                    false,
+                )
+                .expect("Unable to update state");
+
+            assert_eq!(
+                vec![
+                    StateDiff {
+                        address: "ab0001".into(),
+                        old_value: Some(b"b".to_vec()),
+                        new_value: Some(b"B".to_vec()),
+                    },
+                    StateDiff {
+                        address: "cd0000".into(),
+                        old_value: Some(b"c".to_vec()),
+                        new_value: None,
+                    },
+                    StateDiff {
+                        address: "ef0000".into(),
+                        old_value: None,
+                        new_value: Some(b"d".to_vec()),
+                    },
+                ],
+                diff(&db, &first_root, &second_root).expect("Unable to diff roots")
+            );
+
+            // The reverse diff swaps the old and new values
+            let reverse = diff(&db, &second_root, &first_root).expect("Unable to diff roots");
+            assert_eq!(3, reverse.len());
+            assert_eq!(Some(b"c".to_vec()), reverse[1].new_value);
+
+            assert!(diff(&db, &first_root, &first_root)
+                .expect("Unable to diff roots")
+                .is_empty());
+
+            match diff(&db, &first_root, &"00".repeat(32)) {
+                Err(MerkleProofError::NotFound(_)) => (),
+                res => panic!("Expected NotFound, got {:?}", res),
+            }
+        })
+    }
+
+    fn set_change(address: &str, value: &[u8]) -> StateChange {
+        StateChange::Set {
+            key: address.into(),
+            value: value.to_vec(),
+        }
+    }
+
+    fn make_lmdb(db_path: &str) -> LmdbDatabase {
+        let ctx = LmdbContext::new(Path::new(db_path), INDEXES.len(), Some(TEST_DB_SIZE))
+            .expect("Failed to create LmdbContext");
+        LmdbDatabase::new(ctx, &INDEXES).expect("Failed to create LmdbDatabase")
+    }
+
+    fn run_test<T>(test: T) -> ()
+    where
+        T: FnOnce(&str) -> () + panic::UnwindSafe,
+    {
+        let dbpath = temp_db_path();
+
+        let testpath = dbpath.clone();
+        let result = panic::catch_unwind(move || test(&testpath));
+
+        remove_file(dbpath).unwrap();
+
+        assert!(result.is_ok())
+    }
+
+    fn temp_db_path() -> String {
+        let mut temp_dir = env::temp_dir();
+
+        let thread_id = thread::current().id();
+        temp_dir.push(format!("merkle-diff-{:?}.lmdb", thread_id));
+        temp_dir.to_str().unwrap().to_string()
+    }
+}
@@ -18,6 +18,10 @@ use transact::database::lmdb::LmdbDatabase;
 use transact::state::merkle::{MerkleRadixTree, StateDatabaseError as TransactStateDatabaseError};
 use transact::state::StateChange;
 
+use state::merkle_diff;
+use state::merkle_leaves;
+use state::merkle_proof::{self, MerkleProof, MerkleProofError};
+
 /// This module contains all of the extern C functions for the Merkle trie
 use std::ffi::CStr;
 use std::mem;
@@ -32,6 +36,7 @@ pub enum ErrorCode {
     NullPointerProvided = 1,
     InvalidHashString = 2,
     InvalidAddress = 3,
+    InvalidProof = 4,
 
     // output errors
     DatabaseError = 0x11,
@@ -486,6 +491,230 @@ pub unsafe extern "C" fn merkle_db_prune(
     }
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn merkle_db_get_with_proof(
+    state_database: *const c_void,
+    root: *const c_char,
+    address: *const c_char,
+    bytes: *mut *const u8,
+    bytes_len: *mut usize,
+    bytes_cap: *mut usize,
+    proof: *mut *const u8,
+    proof_len: *mut usize,
+    proof_cap: *mut usize,
+) -> ErrorCode {
+    if state_database.is_null() || root.is_null() || address.is_null() {
+        return ErrorCode::NullPointerProvided;
+    }
+
+    let state_root = match CStr::from_ptr(root).to_str() {
+        Ok(s) => s,
+        Err(_) => return ErrorCode::InvalidHashString,
+    };
+
+    let address_str = match CStr::from_ptr(address).to_str() {
+        Ok(s) => s,
+        Err(_) => return ErrorCode::InvalidAddress,
+    };
+
+    let db_ref = (state_database as *const LmdbDatabase).as_ref().unwrap();
+
+    let (data_vec, inclusion_proof) =
+        match merkle_proof::get_with_proof(db_ref, state_root, address_str) {
+            Ok(res) => res,
+            Err(MerkleProofError::NotFound(_)) => return ErrorCode::NotFound,
+            Err(MerkleProofError::InvalidAddress(_)) => return ErrorCode::InvalidAddress,
+            Err(MerkleProofError::DatabaseError(err)) => {
+                error!("A Database Error occurred: {}", err);
+                return ErrorCode::DatabaseError;
+            }
+            Err(err) => {
+                error!("Unknown Error!: {:?}", err);
+                return ErrorCode::Unknown;
+            }
+        };
+
+    let proof_vec = match inclusion_proof.into_bytes() {
+        Ok(proof_vec) => proof_vec,
+        Err(err) => {
+            error!("Unable to serialize merkle proof: {}", err);
+            return ErrorCode::Unknown;
+        }
+    };
+
+    *bytes_cap = data_vec.capacity();
+    *bytes_len = data_vec.len();
+    *bytes = data_vec.as_slice().as_ptr();
+
+    *proof_cap = proof_vec.capacity();
+    *proof_len = proof_vec.len();
+    *proof = proof_vec.as_slice().as_ptr();
+
+    // It will be up to the callee to cleanup this memory
+    mem::forget(data_vec);
+    mem::forget(proof_vec);
+
+    ErrorCode::Success
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn merkle_db_verify_proof(
+    root: *const c_char,
+    address: *const c_char,
+    data: *const u8,
+    data_len: usize,
+    proof: *const u8,
+    proof_len: usize,
+    result: *mut bool,
+) -> ErrorCode {
+    if root.is_null() || address.is_null() || data.is_null() || proof.is_null() {
+        return ErrorCode::NullPointerProvided;
+    }
+
+    let state_root = match CStr::from_ptr(root).to_str() {
+        Ok(s) => s,
+        Err(_) => return ErrorCode::InvalidHashString,
+    };
+
+    let address_str = match CStr::from_ptr(address).to_str() {
+        Ok(s) => s,
+        Err(_) => return ErrorCode::InvalidAddress,
+    };
+
+    let merkle_proof = match MerkleProof::from_bytes(slice::from_raw_parts(proof, proof_len)) {
+        Ok(merkle_proof) => merkle_proof,
+        Err(err) => {
+            error!("Unable to read merkle proof: {}", err);
+            return ErrorCode::InvalidProof;
+        }
+    };
+
+    let data = slice::from_raw_parts(data, data_len);
+    *result = merkle_proof::verify_proof(state_root, address_str, data, &merkle_proof);
+
+    ErrorCode::Success
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn merkle_db_leaves_page(
+    state_database: *const c_void,
+    root: *const c_char,
+    prefix: *const c_char,
+    start_address: *const c_char,
+    limit: usize,
+    page: *mut *const u8,
+    page_len: *mut usize,
+    page_cap: *mut usize,
+) -> ErrorCode {
+    if state_database.is_null() || root.is_null() || prefix.is_null() {
+        return ErrorCode::NullPointerProvided;
+    }
+
+    let state_root = match CStr::from_ptr(root).to_str() {
+        Ok(s) => s,
+        Err(_) => return ErrorCode::InvalidHashString,
+    };
+
+    let prefix = match CStr::from_ptr(prefix).to_str() {
+        Ok(s) => s,
+        Err(_) => return ErrorCode::InvalidAddress,
+    };
+
+    // A null start address reads from the beginning of the prefix
+    let start_address = if start_address.is_null() {
+        None
+    } else {
+        match CStr::from_ptr(start_address).to_str() {
+            Ok(s) => Some(s),
+            Err(_) => return ErrorCode::InvalidAddress,
+        }
+    };
+
+    let db_ref = (state_database as *const LmdbDatabase).as_ref().unwrap();
+
+    let leaves_page =
+        match merkle_leaves::leaves_page(db_ref, state_root, prefix, start_address, limit) {
+            Ok(leaves_page) => leaves_page,
+            Err(MerkleProofError::NotFound(_)) => return ErrorCode::NotFound,
+            Err(MerkleProofError::InvalidAddress(_)) => return ErrorCode::InvalidAddress,
+            Err(MerkleProofError::DatabaseError(err)) => {
+                error!("A Database Error occurred: {}", err);
+                return ErrorCode::DatabaseError;
+            }
+            Err(err) => {
+                error!("Unknown Error!: {:?}", err);
+                return ErrorCode::Unknown;
+            }
+        };
+
+    let page_vec = match leaves_page.into_bytes() {
+        Ok(page_vec) => page_vec,
+        Err(err) => {
+            error!("Unable to serialize leaves page: {}", err);
+            return ErrorCode::Unknown;
+        }
+    };
+
+    *page_cap = page_vec.capacity();
+    *page_len = page_vec.len();
+    *page = page_vec.as_slice().as_ptr();
+
+    // It will be up to the callee to cleanup this memory
+    mem::forget(page_vec);
+
+    ErrorCode::Success
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn merkle_db_diff(
+    state_database: *const c_void,
+    from_root: *const c_char,
+    to_root: *const c_char,
+    diff: *mut *const u8,
+    diff_len: *mut usize,
+    diff_cap: *mut usize,
+) -> ErrorCode {
+    if state_database.is_null() || from_root.is_null() || to_root.is_null() {
+        return ErrorCode::NullPointerProvided;
+    }
+
+    let from_root = match CStr::from_ptr(from_root).to_str() {
+        Ok(s) => s,
+        Err(_) => return ErrorCode::InvalidHashString,
+    };
+
+    let to_root = match CStr::from_ptr(to_root).to_str() {
+        Ok(s) => s,
+        Err(_) => return ErrorCode::InvalidHashString,
+    };
+
+    let db_ref = (state_database as *const LmdbDatabase).as_ref().unwrap();
+
+    let diff_vec = match merkle_diff::diff(db_ref, from_root, to_root)
+        .and_then(merkle_diff::diffs_into_bytes)
+    {
+        Ok(diff_vec) => diff_vec,
+        Err(MerkleProofError::NotFound(_)) => return ErrorCode::NotFound,
+        Err(MerkleProofError::DatabaseError(err)) => {
+            error!("A Database Error occurred: {}", err);
+            return ErrorCode::DatabaseError;
+        }
+        Err(err) => {
+            error!("Unknown Error!: {:?}", err);
+            return ErrorCode::Unknown;
+        }
+    };
+
+    *diff_cap = diff_vec.capacity();
+    *diff_len = diff_vec.len();
+    *diff = diff_vec.as_slice().as_ptr();
+
+    // It will be up to the callee to cleanup this memory
+    mem::forget(diff_vec);
+
+    ErrorCode::Success
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn merkle_db_leaf_iterator_new(
     merkle_db: *mut c_void,
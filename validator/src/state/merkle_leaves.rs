@@ -0,0 +1,242 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+//! Bounded, ordered reads of the leaves under a prefix of the merkle trie.
+//!
+//! Unlike the leaf iterator, a page is read in address order and holds no
+//! reference to the database once returned, so a caller can resume from the
+//! continuation address at any later time.
+
+use protobuf::{Message, RepeatedField};
+use transact::database::Database;
+
+use proto::merkle::{MerkleLeavesPage as MerkleLeavesPageProto, MerkleLeavesPage_Leaf};
+use state::merkle_proof::{tokenize_address, MerkleProofError, Node};
+
+/// A page of `(address, value)` leaves, with the address at which the next
+/// page starts, if there is one.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LeavesPage {
+    pub leaves: Vec<(String, Vec<u8>)>,
+    pub next: Option<String>,
+}
+
+impl LeavesPage {
+    pub fn into_bytes(self) -> Result<Vec<u8>, MerkleProofError> {
+        let mut proto = MerkleLeavesPageProto::new();
+        proto.set_leaves(RepeatedField::from_vec(
+            self.leaves
+                .into_iter()
+                .map(|(address, data)| {
+                    let mut leaf = MerkleLeavesPage_Leaf::new();
+                    leaf.set_address(address);
+                    leaf.set_data(data);
+                    leaf
+                })
+                .collect(),
+        ));
+        proto.set_next(self.next.unwrap_or_default());
+        Ok(proto.write_to_bytes()?)
+    }
+}
+
+/// Reads at most `limit` leaves under `prefix`, in address order, starting at
+/// the first address greater than or equal to `start_address`.
+///
+/// A prefix that is not present in the trie yields an empty page; a state root
+/// that is not present is an error.
+pub fn leaves_page(
+    database: &dyn Database,
+    state_root: &str,
+    prefix: &str,
+    start_address: Option<&str>,
+    limit: usize,
+) -> Result<LeavesPage, MerkleProofError> {
+    let prefix_tokens = tokenize_address(prefix)?;
+    if let Some(start) = start_address {
+        tokenize_address(start)?;
+    }
+
+    let reader = database.get_reader()?;
+    let read_node = |node_hash: &str| -> Result<Option<Node>, MerkleProofError> {
+        match reader.get(node_hash.as_bytes())? {
+            Some(bytes) => Ok(Some(Node::from_bytes(&bytes)?)),
+            None => Ok(None),
+        }
+    };
+
+    let mut node = read_node(state_root)?
+        .ok_or_else(|| MerkleProofError::NotFound(format!("state root {}", state_root)))?;
+    for token in &prefix_tokens {
+        let child_hash = match node.children.get(*token) {
+            Some(child_hash) => child_hash.clone(),
+            None => return Ok(LeavesPage::default()),
+        };
+        node = read_node(&child_hash)?
+            .ok_or_else(|| MerkleProofError::NotFound(format!("node {}", child_hash)))?;
+    }
+
+    let mut leaves = Vec::with_capacity(limit);
+    let mut stack = vec![(prefix.to_string(), node)];
+
+    while let Some((path, node)) = stack.pop() {
+        if let Some(value) = node.value {
+            if start_address
+                .map(|start| path.as_str() >= start)
+                .unwrap_or(true)
+            {
+                if leaves.len() == limit {
+                    return Ok(LeavesPage {
+                        leaves,
+                        next: Some(path),
+                    });
+                }
+                leaves.push((path.clone(), value));
+            }
+        }
+
+        // Children are pushed in reverse so that they are popped in order.
+        for (token, child_hash) in node.children.iter().rev() {
+            let child_path = format!("{}{}", path, token);
+            if !subtree_reaches(&child_path, start_address) {
+                continue;
+            }
+            let child = read_node(child_hash)?
+                .ok_or_else(|| MerkleProofError::NotFound(format!("node {}", child_hash)))?;
+            stack.push((child_path, child));
+        }
+    }
+
+    Ok(LeavesPage { leaves, next: None })
+}
+
+/// Returns false if every address under `path` sorts before `start_address`.
+fn subtree_reaches(path: &str, start_address: Option<&str>) -> bool {
+    match start_address {
+        Some(start) if start.len() >= path.len() => path >= &start[..path.len()],
+        Some(start) => path >= start,
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+    use std::fs::remove_file;
+    use std::panic;
+    use std::path::Path;
+    use std::thread;
+
+    use transact::database::lmdb::{LmdbContext, LmdbDatabase};
+    use transact::state::merkle::{MerkleRadixTree, INDEXES};
+    use transact::state::StateChange;
+
+    const TEST_DB_SIZE: usize = 10 * 1024 * 1024;
+
+    #[test]
+    fn pages_are_ordered_and_resumable() {
+        run_test(|db_path| {
+            let db = make_lmdb(db_path);
+            let merkle_db = MerkleRadixTree::new(Box::new(db.clone()), None)
+                .expect("Could not create merkle database");
+
+            let state_root = merkle_db
+                .update(
+                    &[
+                        set_change("ab0003", b"d"),
+                        set_change("ab0001", b"b"),
+                        set_change("cd0000", b"e"),
+                        set_change("ab0000", b"a"),
+                        set_change("ab0002", b"c"),
+                    ],
+                    false,
+                )
+                .expect("Unable to update state");
+
+            let page = leaves_page(&db, &state_root, "ab", None, 2).expect("Unable to read page");
+            assert_eq!(
+                vec![
+                    ("ab0000".to_string(), b"a".to_vec()),
+                    ("ab0001".to_string(), b"b".to_vec()),
+                ],
+                page.leaves
+            );
+            assert_eq!(Some("ab0002".to_string()), page.next);
+
+            let page = leaves_page(&db, &state_root, "ab", page.next.as_ref().map(|s| &**s), 2)
+                .expect("Unable to read page");
+            assert_eq!(
+                vec![
+                    ("ab0002".to_string(), b"c".to_vec()),
+                    ("ab0003".to_string(), b"d".to_vec()),
+                ],
+                page.leaves
+            );
+            assert_eq!(None, page.next);
+
+            let page =
+                leaves_page(&db, &state_root, "", Some("ab0003"), 10).expect("Unable to read page");
+            assert_eq!(
+                vec!["ab0003".to_string(), "cd0000".to_string()],
+                page.leaves
+                    .into_iter()
+                    .map(|(address, _)| address)
+                    .collect::<Vec<_>>()
+            );
+
+            let page = leaves_page(&db, &state_root, "ef", None, 10).expect("Unable to read page");
+            assert_eq!(LeavesPage::default(), page);
+        })
+    }
+
+    fn set_change(address: &str, value: &[u8]) -> StateChange {
+        StateChange::Set {
+            key: address.into(),
+            value: value.to_vec(),
+        }
+    }
+
+    fn make_lmdb(db_path: &str) -> LmdbDatabase {
+        let ctx = LmdbContext::new(Path::new(db_path), INDEXES.len(), Some(TEST_DB_SIZE))
+            .expect("Failed to create LmdbContext");
+        LmdbDatabase::new(ctx, &INDEXES).expect("Failed to create LmdbDatabase")
+    }
+
+    fn run_test<T>(test: T) -> ()
+    where
+        T: FnOnce(&str) -> () + panic::UnwindSafe,
+    {
+        let dbpath = temp_db_path();
+
+        let testpath = dbpath.clone();
+        let result = panic::catch_unwind(move || test(&testpath));
+
+        remove_file(dbpath).unwrap();
+
+        assert!(result.is_ok())
+    }
+
+    fn temp_db_path() -> String {
+        let mut temp_dir = env::temp_dir();
+
+        let thread_id = thread::current().id();
+        temp_dir.push(format!("merkle-leaves-{:?}.lmdb", thread_id));
+        temp_dir.to_str().unwrap().to_string()
+    }
+}
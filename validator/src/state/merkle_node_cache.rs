@@ -0,0 +1,304 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+//! An in-memory cache of merkle trie nodes in front of the state database.
+//!
+//! Trie nodes are stored under the hash of their serialized bytes, so a cached
+//! node can never be out of date. Deletions made through the cache evict the
+//! node, but state pruning writes to the LMDB database directly, as
+//! `StatePruningManager` only accepts an `LmdbDatabase`. A pruned node may
+//! therefore stay cached, and be served, until it is evicted to make room for
+//! others.
+
+use std::sync::{Arc, Mutex};
+
+use lru::LruCache;
+use transact::database::error::DatabaseError;
+use transact::database::{Database, DatabaseCursor, DatabaseReader, DatabaseWriter};
+
+type NodeCache = Arc<Mutex<LruCache<Vec<u8>, Vec<u8>>>>;
+
+/// Wraps a state database, caching the serialized trie nodes read from it.
+///
+/// Clones share the same cache.
+#[derive(Clone)]
+pub struct CachedStateDatabase<D: Database + Clone + 'static> {
+    database: D,
+    cache: NodeCache,
+}
+
+impl<D: Database + Clone + 'static> CachedStateDatabase<D> {
+    /// Creates a new cache holding at most `capacity` nodes.
+    pub fn new(database: D, capacity: usize) -> Self {
+        CachedStateDatabase {
+            database,
+            cache: Arc::new(Mutex::new(LruCache::new(capacity))),
+        }
+    }
+
+    /// Returns the number of nodes currently cached.
+    pub fn len(&self) -> usize {
+        self.cache.lock().map(|cache| cache.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<D: Database + Clone + 'static> Database for CachedStateDatabase<D> {
+    fn get_reader<'a>(&'a self) -> Result<Box<dyn DatabaseReader + 'a>, DatabaseError> {
+        Ok(Box::new(CachedReader {
+            reader: self.database.get_reader()?,
+            cache: &self.cache,
+        }))
+    }
+
+    fn get_writer<'a>(&'a self) -> Result<Box<dyn DatabaseWriter + 'a>, DatabaseError> {
+        Ok(Box::new(CachedWriter {
+            writer: self.database.get_writer()?,
+            cache: &self.cache,
+            deletions: vec![],
+        }))
+    }
+
+    fn clone_box(&self) -> Box<dyn Database> {
+        Box::new(self.clone())
+    }
+}
+
+struct CachedReader<'a> {
+    reader: Box<dyn DatabaseReader + 'a>,
+    cache: &'a NodeCache,
+}
+
+impl<'a> DatabaseReader for CachedReader<'a> {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, DatabaseError> {
+        cached_get(self.cache, &*self.reader, key)
+    }
+
+    fn index_get(&self, index: &str, key: &[u8]) -> Result<Option<Vec<u8>>, DatabaseError> {
+        self.reader.index_get(index, key)
+    }
+
+    fn cursor(&self) -> Result<DatabaseCursor, DatabaseError> {
+        self.reader.cursor()
+    }
+
+    fn index_cursor(&self, index: &str) -> Result<DatabaseCursor, DatabaseError> {
+        self.reader.index_cursor(index)
+    }
+
+    fn count(&self) -> Result<usize, DatabaseError> {
+        self.reader.count()
+    }
+
+    fn index_count(&self, index: &str) -> Result<usize, DatabaseError> {
+        self.reader.index_count(index)
+    }
+}
+
+/// Passes writes through to the underlying database. Deleted nodes are only
+/// evicted from the cache once the deletion has been committed.
+struct CachedWriter<'a> {
+    writer: Box<dyn DatabaseWriter + 'a>,
+    cache: &'a NodeCache,
+    deletions: Vec<Vec<u8>>,
+}
+
+impl<'a> DatabaseWriter for CachedWriter<'a> {
+    fn put(&mut self, key: &[u8], value: &[u8]) -> Result<(), DatabaseError> {
+        self.writer.put(key, value)
+    }
+
+    fn overwrite(&mut self, key: &[u8], value: &[u8]) -> Result<(), DatabaseError> {
+        self.deletions.push(key.to_vec());
+        self.writer.overwrite(key, value)
+    }
+
+    fn delete(&mut self, key: &[u8]) -> Result<(), DatabaseError> {
+        self.deletions.push(key.to_vec());
+        self.writer.delete(key)
+    }
+
+    fn index_put(&mut self, index: &str, key: &[u8], value: &[u8]) -> Result<(), DatabaseError> {
+        self.writer.index_put(index, key, value)
+    }
+
+    fn index_delete(&mut self, index: &str, key: &[u8]) -> Result<(), DatabaseError> {
+        self.writer.index_delete(index, key)
+    }
+
+    fn commit(self: Box<Self>) -> Result<(), DatabaseError> {
+        let CachedWriter {
+            writer,
+            cache,
+            deletions,
+        } = *self;
+
+        writer.commit()?;
+
+        if !deletions.is_empty() {
+            let mut cache = cache.lock().map_err(|_| {
+                DatabaseError::ReaderError("Merkle node cache lock was poisoned".into())
+            })?;
+            for key in deletions {
+                cache.pop(&key);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn as_reader(&self) -> &dyn DatabaseReader {
+        self.writer.as_reader()
+    }
+}
+
+fn cached_get(
+    cache: &NodeCache,
+    reader: &dyn DatabaseReader,
+    key: &[u8],
+) -> Result<Option<Vec<u8>>, DatabaseError> {
+    if let Some(value) = cache
+        .lock()
+        .map_err(|_| DatabaseError::ReaderError("Merkle node cache lock was poisoned".into()))?
+        .get(key)
+    {
+        counter!("merkle_node_cache_hit_count", 1);
+        return Ok(Some(value.clone()));
+    }
+
+    counter!("merkle_node_cache_miss_count", 1);
+    let value = reader.get(key)?;
+    if let Some(ref value) = value {
+        cache
+            .lock()
+            .map_err(|_| DatabaseError::ReaderError("Merkle node cache lock was poisoned".into()))?
+            .put(key.to_vec(), value.clone());
+    }
+
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+    use std::fs::remove_file;
+    use std::panic;
+    use std::path::Path;
+    use std::thread;
+
+    use transact::database::lmdb::{LmdbContext, LmdbDatabase};
+    use transact::state::merkle::{MerkleRadixTree, INDEXES};
+    use transact::state::StateChange;
+
+    const TEST_DB_SIZE: usize = 10 * 1024 * 1024;
+
+    #[test]
+    fn cache_is_shared_and_consistent() {
+        run_test(|db_path| {
+            let db = CachedStateDatabase::new(make_lmdb(db_path), 100);
+            let mut merkle_db = MerkleRadixTree::new(Box::new(db.clone()), None)
+                .expect("Could not create merkle database");
+
+            let state_root = merkle_db
+                .update(
+                    &[StateChange::Set {
+                        key: "ab0000".into(),
+                        value: b"value".to_vec(),
+                    }],
+                    false,
+                )
+                .expect("Unable to update state");
+            merkle_db
+                .set_merkle_root(state_root)
+                .expect("Unable to set root");
+
+            assert_eq!(
+                Some(b"value".to_vec()),
+                merkle_db.get_value("ab0000").expect("Unable to read")
+            );
+            assert!(!db.is_empty());
+
+            // a clone reads through the same cache
+            let cached = db.len();
+            let clone = db.clone();
+            let reader = clone.get_reader().expect("Unable to get reader");
+            let root_hash = merkle_db.get_merkle_root();
+            assert!(reader.get(root_hash.as_bytes()).unwrap().is_some());
+            assert_eq!(cached, clone.len());
+        })
+    }
+
+    #[test]
+    fn committed_deletes_are_evicted() {
+        run_test(|db_path| {
+            let db = CachedStateDatabase::new(make_lmdb(db_path), 100);
+            {
+                let mut writer = db.get_writer().unwrap();
+                writer.put(b"node", b"bytes").unwrap();
+                writer.commit().unwrap();
+            }
+
+            assert_eq!(
+                Some(b"bytes".to_vec()),
+                db.get_reader().unwrap().get(b"node").unwrap()
+            );
+            assert_eq!(1, db.len());
+
+            {
+                let mut writer = db.get_writer().unwrap();
+                writer.delete(b"node").unwrap();
+                writer.commit().unwrap();
+            }
+
+            assert!(db.is_empty());
+            assert_eq!(None, db.get_reader().unwrap().get(b"node").unwrap());
+        })
+    }
+
+    fn make_lmdb(db_path: &str) -> LmdbDatabase {
+        let ctx = LmdbContext::new(Path::new(db_path), INDEXES.len(), Some(TEST_DB_SIZE))
+            .expect("Failed to create LmdbContext");
+        LmdbDatabase::new(ctx, &INDEXES).expect("Failed to create LmdbDatabase")
+    }
+
+    fn run_test<T>(test: T) -> ()
+    where
+        T: FnOnce(&str) -> () + panic::UnwindSafe,
+    {
+        let dbpath = temp_db_path();
+
+        let testpath = dbpath.clone();
+        let result = panic::catch_unwind(move || test(&testpath));
+
+        remove_file(dbpath).unwrap();
+
+        assert!(result.is_ok())
+    }
+
+    fn temp_db_path() -> String {
+        let mut temp_dir = env::temp_dir();
+
+        let thread_id = thread::current().id();
+        temp_dir.push(format!("merkle-node-cache-{:?}.lmdb", thread_id));
+        temp_dir.to_str().unwrap().to_string()
+    }
+}
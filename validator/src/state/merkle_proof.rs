@@ -0,0 +1,384 @@
+/*
+ * Copyright 2018 Intel Corporation
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ * ------------------------------------------------------------------------------
+ */
+
+//! Inclusion proofs for values stored in the merkle radix trie.
+//!
+//! A proof is the list of serialized trie nodes along the path from a state
+//! root down to the leaf for an address. Since a node's key in the database
+//! is the hash of its serialized bytes, anyone holding the state root can
+//! check a proof without access to the state database.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::io::Cursor;
+
+use cbor;
+use cbor::decoder::GenericDecoder;
+use cbor::value::{Bytes, Key, Text, Value};
+use hex;
+use openssl::sha::sha512;
+use protobuf::{Message, ProtobufError, RepeatedField};
+use transact::database::error::DatabaseError;
+use transact::database::Database;
+
+use proto::merkle::MerkleProof as MerkleProofProto;
+
+const TOKEN_SIZE: usize = 2;
+
+#[derive(Debug)]
+pub enum MerkleProofError {
+    DatabaseError(DatabaseError),
+    InvalidAddress(String),
+    InvalidNode(String),
+    NotFound(String),
+    SerializationError(ProtobufError),
+}
+
+impl fmt::Display for MerkleProofError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MerkleProofError::DatabaseError(ref err) => {
+                write!(f, "A database error occurred: {}", err)
+            }
+            MerkleProofError::InvalidAddress(ref addr) => write!(f, "Invalid address: {}", addr),
+            MerkleProofError::InvalidNode(ref msg) => write!(f, "Invalid trie node: {}", msg),
+            MerkleProofError::NotFound(ref msg) => write!(f, "Value not found: {}", msg),
+            MerkleProofError::SerializationError(ref err) => {
+                write!(f, "Unable to (de)serialize proof: {}", err)
+            }
+        }
+    }
+}
+
+impl Error for MerkleProofError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            MerkleProofError::DatabaseError(ref err) => Some(err),
+            MerkleProofError::SerializationError(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<DatabaseError> for MerkleProofError {
+    fn from(err: DatabaseError) -> Self {
+        MerkleProofError::DatabaseError(err)
+    }
+}
+
+impl From<ProtobufError> for MerkleProofError {
+    fn from(err: ProtobufError) -> Self {
+        MerkleProofError::SerializationError(err)
+    }
+}
+
+/// The serialized nodes on the path from a state root to a single leaf.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MerkleProof {
+    nodes: Vec<Vec<u8>>,
+}
+
+impl MerkleProof {
+    /// The serialized nodes, ordered from the state root to the leaf.
+    pub fn nodes(&self) -> &[Vec<u8>] {
+        &self.nodes
+    }
+
+    /// The chain of node hashes, ordered from the leaf up to the state root.
+    pub fn hashes(&self) -> Vec<String> {
+        self.nodes.iter().rev().map(|node| hash(node)).collect()
+    }
+
+    pub fn into_bytes(self) -> Result<Vec<u8>, MerkleProofError> {
+        let mut proto = MerkleProofProto::new();
+        proto.set_nodes(RepeatedField::from_vec(self.nodes));
+        Ok(proto.write_to_bytes()?)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MerkleProofError> {
+        let mut proto: MerkleProofProto = Message::parse_from_bytes(bytes)?;
+        Ok(MerkleProof {
+            nodes: proto.take_nodes().into_vec(),
+        })
+    }
+}
+
+/// Returns the value stored at `address` under `state_root`, along with a
+/// proof of its inclusion.
+pub fn get_with_proof(
+    database: &dyn Database,
+    state_root: &str,
+    address: &str,
+) -> Result<(Vec<u8>, MerkleProof), MerkleProofError> {
+    let tokens = tokenize_address(address)?;
+    let reader = database.get_reader()?;
+
+    let mut nodes = Vec::with_capacity(tokens.len() + 1);
+    let mut node_hash = state_root.to_string();
+    let mut path = tokens.iter();
+
+    loop {
+        let node_bytes = reader
+            .get(node_hash.as_bytes())?
+            .ok_or_else(|| MerkleProofError::NotFound(format!("node {}", node_hash)))?;
+        let node = Node::from_bytes(&node_bytes)?;
+        nodes.push(node_bytes);
+
+        match path.next() {
+            Some(token) => {
+                node_hash = node
+                    .children
+                    .get(*token)
+                    .cloned()
+                    .ok_or_else(|| MerkleProofError::NotFound(address.into()))?;
+            }
+            None => {
+                let value = node
+                    .value
+                    .ok_or_else(|| MerkleProofError::NotFound(address.into()))?;
+                return Ok((value, MerkleProof { nodes }));
+            }
+        }
+    }
+}
+
+/// Checks that `proof` shows `value` stored at `address` under `state_root`.
+pub fn verify_proof(state_root: &str, address: &str, value: &[u8], proof: &MerkleProof) -> bool {
+    let tokens = match tokenize_address(address) {
+        Ok(tokens) => tokens,
+        Err(_) => return false,
+    };
+
+    if proof.nodes.len() != tokens.len() + 1 {
+        return false;
+    }
+
+    let mut expected_hash = state_root.to_string();
+    for (depth, node_bytes) in proof.nodes.iter().enumerate() {
+        if hash(node_bytes) != expected_hash {
+            return false;
+        }
+
+        let node = match Node::from_bytes(node_bytes) {
+            Ok(node) => node,
+            Err(_) => return false,
+        };
+
+        match tokens.get(depth) {
+            Some(token) => match node.children.get(*token) {
+                Some(child_hash) => expected_hash = child_hash.clone(),
+                None => return false,
+            },
+            None => {
+                return node
+                    .value
+                    .as_ref()
+                    .map(|v| &v[..] == value)
+                    .unwrap_or(false)
+            }
+        }
+    }
+
+    false
+}
+
+/// The decoded form of a trie node, as written by the merkle radix tree.
+pub(crate) struct Node {
+    pub(crate) value: Option<Vec<u8>>,
+    pub(crate) children: BTreeMap<String, String>,
+}
+
+impl Node {
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Result<Node, MerkleProofError> {
+        let mut decoder = GenericDecoder::new(cbor::Config::default(), Cursor::new(bytes));
+        let mut root_map = match decoder.value() {
+            Ok(Value::Map(root_map)) => root_map,
+            Ok(_) => return Err(MerkleProofError::InvalidNode("node is not a map".into())),
+            Err(err) => return Err(MerkleProofError::InvalidNode(format!("{:?}", err))),
+        };
+
+        let value = match root_map.remove(&Key::Text(Text::Text("v".into()))) {
+            Some(Value::Bytes(Bytes::Bytes(bytes))) => Some(bytes),
+            Some(Value::Null) | None => None,
+            Some(_) => return Err(MerkleProofError::InvalidNode("invalid node value".into())),
+        };
+
+        let children = match root_map.remove(&Key::Text(Text::Text("c".into()))) {
+            Some(Value::Map(children)) => children
+                .into_iter()
+                .map(|(key, value)| match (key, value) {
+                    (Key::Text(Text::Text(token)), Value::Text(Text::Text(child_hash))) => {
+                        Ok((token, child_hash))
+                    }
+                    _ => Err(MerkleProofError::InvalidNode("invalid child entry".into())),
+                })
+                .collect::<Result<BTreeMap<_, _>, _>>()?,
+            None => BTreeMap::new(),
+            Some(_) => return Err(MerkleProofError::InvalidNode("invalid children".into())),
+        };
+
+        Ok(Node { value, children })
+    }
+}
+
+fn hash(bytes: &[u8]) -> String {
+    hex::encode(&sha512(bytes)[..32])
+}
+
+pub(crate) fn tokenize_address(address: &str) -> Result<Vec<&str>, MerkleProofError> {
+    if address.len() % TOKEN_SIZE != 0 || !address.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(MerkleProofError::InvalidAddress(address.into()));
+    }
+
+    Ok((0..address.len())
+        .step_by(TOKEN_SIZE)
+        .map(|i| &address[i..i + TOKEN_SIZE])
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+    use std::fs::remove_file;
+    use std::panic;
+    use std::path::Path;
+    use std::thread;
+
+    use transact::database::lmdb::{LmdbContext, LmdbDatabase};
+    use transact::state::merkle::{MerkleRadixTree, INDEXES};
+    use transact::state::StateChange;
+
+    const TEST_DB_SIZE: usize = 10 * 1024 * 1024;
+
+    #[test]
+    fn proof_round_trip() {
+        run_test(|db_path| {
+            let db = make_lmdb(db_path);
+            let mut merkle_db = MerkleRadixTree::new(Box::new(db.clone()), None)
+                .expect("Could not create merkle database");
+
+            let state_root = merkle_db
+                .update(
+                    &[
+                        set_change("ab0000", b"first"),
+                        set_change("ab0001", b"second"),
+                        set_change("cd0000", b"third"),
+                    ],
+                    false,
+                )
+                .expect("Unable to update state");
+            merkle_db
+                .set_merkle_root(state_root.clone())
+                .expect("Unable to set root");
+
+            let (value, proof) =
+                get_with_proof(&db, &state_root, "ab0001").expect("Unable to build proof");
+            assert_eq!(b"second".to_vec(), value);
+            assert_eq!(4, proof.nodes().len());
+            assert_eq!(Some(&state_root), proof.hashes().last());
+            assert!(verify_proof(&state_root, "ab0001", b"second", &proof));
+
+            let proof = MerkleProof::from_bytes(&proof.into_bytes().unwrap()).unwrap();
+            assert!(verify_proof(&state_root, "ab0001", b"second", &proof));
+        })
+    }
+
+    #[test]
+    fn proof_rejects_tampering() {
+        run_test(|db_path| {
+            let db = make_lmdb(db_path);
+            let merkle_db = MerkleRadixTree::new(Box::new(db.clone()), None)
+                .expect("Could not create merkle database");
+
+            let state_root = merkle_db
+                .update(
+                    &[
+                        set_change("ab0000", b"first"),
+                        set_change("ab0001", b"second"),
+                    ],
+                    false,
+                )
+                .expect("Unable to update state");
+
+            let (_, proof) =
+                get_with_proof(&db, &state_root, "ab0000").expect("Unable to build proof");
+
+            // wrong value, wrong address and wrong root must all fail
+            assert!(!verify_proof(&state_root, "ab0000", b"second", &proof));
+            assert!(!verify_proof(&state_root, "ab0001", b"first", &proof));
+            assert!(!verify_proof(
+                &merkle_db.get_merkle_root(),
+                "ab0000",
+                b"first",
+                &proof
+            ));
+
+            // a truncated proof must fail
+            let mut nodes = proof.nodes().to_vec();
+            nodes.pop();
+            assert!(!verify_proof(
+                &state_root,
+                "ab0000",
+                b"first",
+                &MerkleProof { nodes }
+            ));
+
+            match get_with_proof(&db, &state_root, "ab0002") {
+                Err(MerkleProofError::NotFound(_)) => (),
+                res => panic!("Expected NotFound, got {:?}", res),
+            }
+        })
+    }
+
+    fn set_change(address: &str, value: &[u8]) -> StateChange {
+        StateChange::Set {
+            key: address.into(),
+            value: value.to_vec(),
+        }
+    }
+
+    fn make_lmdb(db_path: &str) -> LmdbDatabase {
+        let ctx = LmdbContext::new(Path::new(db_path), INDEXES.len(), Some(TEST_DB_SIZE))
+            .expect("Failed to create LmdbContext");
+        LmdbDatabase::new(ctx, &INDEXES).expect("Failed to create LmdbDatabase")
+    }
+
+    fn run_test<T>(test: T) -> ()
+    where
+        T: FnOnce(&str) -> () + panic::UnwindSafe,
+    {
+        let dbpath = temp_db_path();
+
+        let testpath = dbpath.clone();
+        let result = panic::catch_unwind(move || test(&testpath));
+
+        remove_file(dbpath).unwrap();
+
+        assert!(result.is_ok())
+    }
+
+    fn temp_db_path() -> String {
+        let mut temp_dir = env::temp_dir();
+
+        let thread_id = thread::current().id();
+        temp_dir.push(format!("merkle-proof-{:?}.lmdb", thread_id));
+        temp_dir.to_str().unwrap().to_string()
+    }
+}
@@ -15,5 +15,11 @@
  * ------------------------------------------------------------------------------
  */
 
+pub mod client_paging;
+pub mod client_paging_ffi;
+pub mod merkle_diff;
 pub mod merkle_ffi;
+pub mod merkle_leaves;
+pub mod merkle_node_cache;
+pub mod merkle_proof;
 pub mod state_view_ffi;
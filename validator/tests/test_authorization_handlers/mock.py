@@ -16,10 +16,13 @@
 
 class MockNetwork():
     def __init__(self, roles, allow_inbound=True, is_outbound=False,
-                 connection_status=None):
+                 connection_status=None, chain_id='',
+                 accept_any_chain_id=False):
         self.roles = roles
         self.allow_inbound = allow_inbound
         self.is_outbound = is_outbound
+        self.chain_id = chain_id
+        self.accept_any_chain_id = accept_any_chain_id
         if connection_status is None:
             self._connection_status = {}
         else:
@@ -28,6 +31,10 @@ class MockNetwork():
     def update_connection_endpoint(self, connection_id, endpoint):
         pass
 
+    def is_chain_id_accepted(self, chain_id):
+        return self.accept_any_chain_id or not chain_id or \
+            not self.chain_id or chain_id == self.chain_id
+
     def is_outbound_connection(self, connection_id):
         return self.is_outbound
 
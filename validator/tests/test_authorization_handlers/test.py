@@ -36,6 +36,7 @@ from sawtooth_validator.networking.handlers import \
     PingRequestHandler
 
 from sawtooth_validator.protobuf.authorization_pb2 import ConnectionRequest
+from sawtooth_validator.protobuf.authorization_pb2 import ConnectionResponse
 from sawtooth_validator.protobuf.authorization_pb2 import RoleType
 from sawtooth_validator.protobuf.authorization_pb2 import \
     AuthorizationTrustRequest
@@ -141,6 +142,48 @@ class TestAuthorizationHandlers(unittest.TestCase):
             handler_status.message_type,
             validator_pb2.Message.AUTHORIZATION_CONNECTION_RESPONSE)
 
+    def test_connect_chain_id_mismatch(self):
+        """
+        Test the ConnectHandler closes a connection if the requester's chain
+        id differs from this validator's, and reports its own chain id.
+        """
+        connect_message = ConnectionRequest(endpoint="tcp://host:80",
+                                            chain_id="other_chain")
+        roles = {"network": AuthorizationType.TRUST}
+        network = MockNetwork(roles, chain_id="our_chain")
+        handler = ConnectHandler(network)
+        handler_status = handler.handle("connection_id",
+                                        connect_message.SerializeToString())
+        self.assertEqual(handler_status.status, HandlerStatus.RETURN_AND_CLOSE)
+        self.assertEqual(
+            handler_status.message_out.status,
+            ConnectionResponse.CHAIN_ID_MISMATCH)
+        self.assertEqual(handler_status.message_out.chain_id, "our_chain")
+
+    def test_connect_chain_id_accepted(self):
+        """
+        Test the ConnectHandler accepts a requester on the same chain, one
+        without a chain id yet, or any requester if accept_any_chain_id is
+        set.
+        """
+        roles = {"network": AuthorizationType.TRUST}
+        networks_and_chain_ids = [
+            (MockNetwork(roles, chain_id="our_chain"), "our_chain"),
+            (MockNetwork(roles, chain_id="our_chain"), ""),
+            (MockNetwork(roles, chain_id=""), "other_chain"),
+            (MockNetwork(roles, chain_id="our_chain",
+                         accept_any_chain_id=True), "other_chain"),
+        ]
+        for network, chain_id in networks_and_chain_ids:
+            connect_message = ConnectionRequest(endpoint="tcp://host:80",
+                                                chain_id=chain_id)
+            handler = ConnectHandler(network)
+            handler_status = handler.handle(
+                "connection_id", connect_message.SerializeToString())
+            self.assertEqual(handler_status.status, HandlerStatus.RETURN)
+            self.assertEqual(handler_status.message_out.status,
+                             ConnectionResponse.OK)
+
     def test_ping_handler(self):
         """
         Test the PingRequestHandler returns a NetworkAck if the connection has
@@ -0,0 +1,177 @@
+# Copyright 2018 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#     http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+# ------------------------------------------------------------------------------
+import unittest
+
+from sawtooth_validator.protobuf.batch_pb2 import Batch
+from sawtooth_validator.protobuf.batch_pb2 import BatchHeader
+from sawtooth_validator.protobuf.batch_pb2 import BatchList
+from sawtooth_validator.protobuf.block_pb2 import Block
+from sawtooth_validator.protobuf.client_batch_submit_pb2 \
+    import ClientBatchSubmitResponse
+from sawtooth_validator.journal.back_pressure_handlers import \
+    ClientBatchSubmitDuplicateHandler
+from sawtooth_validator.journal.back_pressure_handlers import \
+    ClientBatchSubmitRateLimitHandler
+from sawtooth_validator.journal.block_wrapper import BlockWrapper
+from sawtooth_validator.journal.recent_batches import RecentBatchFilter
+from sawtooth_validator.networking.dispatch import HandlerStatus
+
+
+class TestDuplicateHandler(unittest.TestCase):
+    def setUp(self):
+        self.committed = set()
+        self.recent_batch_filter = RecentBatchFilter(
+            self.committed.__contains__, block_window=1)
+        self.handler = ClientBatchSubmitDuplicateHandler(
+            self.recent_batch_filter)
+
+    def commit_block(self, *batch_ids):
+        self.committed.update(batch_ids)
+        block = Block(batches=[
+            Batch(header_signature=batch_id) for batch_id in batch_ids
+        ])
+        self.recent_batch_filter.chain_update(BlockWrapper(block), [])
+
+    def test_new_batch(self):
+        """
+        Test that a batch that has not been committed is passed.
+        """
+        self.commit_block('batch_a')
+
+        result = self.handler.handle(
+            'connection_id', batch_list(('batch_b', 'signer')))
+        self.assertEqual(result.status, HandlerStatus.PASS)
+
+    def test_recently_committed_batch(self):
+        """
+        Test that a batch committed in a recent block is rejected as an
+        invalid batch.
+        """
+        self.commit_block('batch_a')
+        self.commit_block('batch_b')
+
+        result = self.handler.handle(
+            'connection_id',
+            batch_list(('batch_c', 'signer'), ('batch_a', 'signer')))
+        self.assertEqual(result.status, HandlerStatus.RETURN)
+        self.assertEqual(
+            result.message_out.status,
+            ClientBatchSubmitResponse.INVALID_BATCH)
+
+    def test_forked_out_batch(self):
+        """
+        Test that a batch seen in a block that is no longer committed is
+        passed.
+        """
+        self.commit_block('batch_a')
+        self.committed.remove('batch_a')
+
+        result = self.handler.handle(
+            'connection_id', batch_list(('batch_a', 'signer')))
+        self.assertEqual(result.status, HandlerStatus.PASS)
+
+    def test_batch_outside_window(self):
+        """
+        Test that batches are forgotten once they fall outside of the block
+        window.
+        """
+        self.commit_block('batch_a')
+        self.commit_block('batch_b')
+        self.commit_block('batch_c')
+
+        result = self.handler.handle(
+            'connection_id', batch_list(('batch_a', 'signer')))
+        self.assertEqual(result.status, HandlerStatus.PASS)
+
+
+class TestRateLimitHandler(unittest.TestCase):
+    def setUp(self):
+        self.now = 0.0
+        self.handler = ClientBatchSubmitRateLimitHandler(
+            'validator', rate=1, burst=2, clock=lambda: self.now)
+
+    def submit(self, *signers):
+        result = self.handler.handle(
+            'connection_id',
+            batch_list(*[
+                ('batch_{}'.format(i), signer)
+                for i, signer in enumerate(signers)
+            ]))
+        return result.status
+
+    def test_burst(self):
+        """
+        Test that a signer can submit up to the burst size at once, and is
+        then rejected with QUEUE_FULL until the bucket refills.
+        """
+        self.assertEqual(self.submit('alice', 'alice'), HandlerStatus.PASS)
+
+        result = self.handler.handle(
+            'connection_id', batch_list(('batch', 'alice')))
+        self.assertEqual(result.status, HandlerStatus.RETURN)
+        self.assertEqual(
+            result.message_out.status, ClientBatchSubmitResponse.QUEUE_FULL)
+
+        self.now = 1.0
+        self.assertEqual(self.submit('alice'), HandlerStatus.PASS)
+        self.assertEqual(self.submit('alice'), HandlerStatus.RETURN)
+
+    def test_list_larger_than_burst(self):
+        """
+        Test that a batch list with more batches from a signer than the burst
+        size is accepted once the signer's bucket is full, and that the
+        signer is then limited until the bucket refills past the excess.
+        """
+        self.assertEqual(self.submit('alice'), HandlerStatus.PASS)
+        self.assertEqual(
+            self.submit('alice', 'alice', 'alice'), HandlerStatus.RETURN)
+
+        self.now = 1.0
+        self.assertEqual(
+            self.submit('alice', 'alice', 'alice'), HandlerStatus.PASS)
+
+        self.now = 2.0
+        self.assertEqual(self.submit('alice'), HandlerStatus.RETURN)
+
+        self.now = 3.0
+        self.assertEqual(self.submit('alice'), HandlerStatus.PASS)
+
+    def test_signers_limited_separately(self):
+        """
+        Test that one signer reaching their limit does not affect another
+        signer, and that a rejected batch list is not charged to the signers
+        within it.
+        """
+        self.assertEqual(self.submit('alice', 'alice'), HandlerStatus.PASS)
+        self.assertEqual(self.submit('alice', 'bob'), HandlerStatus.RETURN)
+        self.assertEqual(self.submit('bob', 'bob'), HandlerStatus.PASS)
+
+    def test_allowlisted_signer(self):
+        """
+        Test that batches signed by the validator are never limited.
+        """
+        for _ in range(5):
+            self.assertEqual(self.submit('validator'), HandlerStatus.PASS)
+
+
+def batch_list(*batches):
+    """Creates a BatchList from (batch id, signer public key) pairs."""
+    return BatchList(batches=[
+        Batch(
+            header_signature=batch_id,
+            header=BatchHeader(
+                signer_public_key=signer).SerializeToString())
+        for batch_id, signer in batches
+    ])
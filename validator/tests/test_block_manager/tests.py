@@ -20,6 +20,7 @@ from sawtooth_validator.journal.block_manager import BlockManager
 from sawtooth_validator.journal.block_manager import MissingPredecessor
 from sawtooth_validator.journal.block_manager import \
     MissingPredecessorInBranch
+from sawtooth_validator.journal.block_manager import UnknownBlock
 from sawtooth_validator.journal.block_wrapper import NULL_BLOCK_IDENTIFIER
 from sawtooth_validator.protobuf import block_pb2
 
@@ -85,3 +86,22 @@ class TestBlockManager(unittest.TestCase):
 
         for block in self.block_manager.get("C"):
             self.assertEqual(block.header_signature, "C")
+
+    def test_errors_carry_messages(self):
+        """Tests that failures in the block manager are raised as exceptions
+        carrying the message reported across the FFI boundary, rather than
+        aborting the process.
+        """
+        with self.assertRaises(UnknownBlock) as context:
+            self.block_manager.ref_block("Z")
+        self.assertIn("ref_block", str(context.exception))
+
+        with self.assertRaises(UnknownBlock):
+            self.block_manager.persist("Z", "commit_store")
+
+        block_a = _build_block(1, "A", NULL_BLOCK_IDENTIFIER)
+        block_a.header = b'\xff' * 16
+
+        with self.assertRaises(ValueError) as context:
+            self.block_manager.put([block_a])
+        self.assertIn("Failed to parse block bytes", str(context.exception))
@@ -0,0 +1,247 @@
+# Copyright 2018 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#     http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+# ------------------------------------------------------------------------------
+
+import logging
+
+import sawtooth_validator.state.client_handlers as handlers
+from sawtooth_validator import metrics
+from sawtooth_validator.journal.block_wrapper import BlockStatus
+from sawtooth_validator.journal.fork_tracker import Fork
+from sawtooth_validator.protobuf import admin_pb2
+from sawtooth_validator.protobuf.block_pb2 import Block
+from test_client_request_handlers.base_case import ClientHandlerTestCase
+
+
+class TestAdminLogLevelSetRequests(ClientHandlerTestCase):
+    def setUp(self):
+        self.initialize(
+            handlers.AdminLogLevelSetRequest(),
+            admin_pb2.AdminLogLevelSetRequest,
+            admin_pb2.AdminLogLevelSetResponse,
+        )
+        self._logger = logging.getLogger('test_admin_handlers')
+        self._original_level = self._logger.level
+
+    def tearDown(self):
+        self._logger.setLevel(self._original_level)
+
+    def test_log_level_set(self):
+        """Verifies a named logger's level is changed, case insensitively.
+        """
+        response = self.make_request(
+            logger='test_admin_handlers', level='debug')
+
+        self.assertEqual(self.status.OK, response.status)
+        self.assertEqual(logging.DEBUG, self._logger.level)
+
+    def test_log_level_set_invalid(self):
+        """Verifies an unknown level is rejected and leaves the logger as is.
+        """
+        response = self.make_request(
+            logger='test_admin_handlers', level='LOUD')
+
+        self.assertEqual(self.status.INVALID_LEVEL, response.status)
+        self.assertEqual(self._original_level, self._logger.level)
+
+
+class TestAdminMetricsGetRequests(ClientHandlerTestCase):
+    def setUp(self):
+        self.initialize(
+            handlers.AdminMetricsGetRequest(),
+            admin_pb2.AdminMetricsGetRequest,
+            admin_pb2.AdminMetricsGetResponse,
+        )
+
+    def tearDown(self):
+        metrics.init_metrics()
+
+    def test_metrics_get_not_enabled(self):
+        """Verifies NOT_ENABLED is sent when there is no metrics registry.
+        """
+        metrics.init_metrics()
+
+        response = self.make_request()
+
+        self.assertEqual(self.status.NOT_ENABLED, response.status)
+
+    def test_metrics_get(self):
+        """Verifies the registry's numeric fields are sent for each metric.
+        """
+        metrics.init_metrics(registry=MockRegistry({
+            'publisher.chain_head,host=test': {'value': 'abc'},
+            'gossip.peers,host=test': {'value': 3},
+        }))
+
+        response = self.make_request()
+
+        self.assertEqual(self.status.OK, response.status)
+        self.assertEqual(
+            ['gossip.peers,host=test', 'publisher.chain_head,host=test'],
+            [metric.name for metric in response.metrics])
+        self.assertEqual({'value': 3.0}, dict(response.metrics[0].fields))
+        self.assertEqual({}, dict(response.metrics[1].fields))
+
+
+class TestAdminPublishingRequests(ClientHandlerTestCase):
+    def setUp(self):
+        self._journal = MockJournal()
+
+    def test_publishing_pause(self):
+        """Verifies the journal is paused with the requested timeout.
+        """
+        self.initialize(
+            handlers.AdminPublishingPauseRequest(self._journal),
+            admin_pb2.AdminPublishingPauseRequest,
+            admin_pb2.AdminPublishingPauseResponse,
+        )
+
+        response = self.make_request(timeout=60)
+
+        self.assertEqual(self.status.OK, response.status)
+        self.assertEqual(60, self._journal.paused_for)
+
+    def test_publishing_pause_invalid_timeout(self):
+        """Verifies a timeout longer than the maximum is refused, without
+        pausing the journal.
+        """
+        self.initialize(
+            handlers.AdminPublishingPauseRequest(self._journal),
+            admin_pb2.AdminPublishingPauseRequest,
+            admin_pb2.AdminPublishingPauseResponse,
+        )
+
+        response = self.make_request(timeout=2 ** 64 - 1)
+
+        self.assertEqual(self.status.INVALID_TIMEOUT, response.status)
+        self.assertIsNone(self._journal.paused_for)
+
+    def test_publishing_resume(self):
+        """Verifies the journal is resumed.
+        """
+        self._journal.pause_publishing(60)
+        self.initialize(
+            handlers.AdminPublishingResumeRequest(self._journal),
+            admin_pb2.AdminPublishingResumeRequest,
+            admin_pb2.AdminPublishingResumeResponse,
+        )
+
+        response = self.make_request()
+
+        self.assertEqual(self.status.OK, response.status)
+        self.assertIsNone(self._journal.paused_for)
+
+
+class TestAdminConsensusEnginesGetRequests(ClientHandlerTestCase):
+    def setUp(self):
+        Engine = admin_pb2.AdminConsensusEnginesGetResponse.Engine
+        self._engines = [
+            Engine(connection_id='abc', name='pbft', version='1.0',
+                   active=True, missed_pings=0, responsive=True),
+            Engine(connection_id='def', name='poet', version='0.1',
+                   active=False, missed_pings=4, responsive=False),
+        ]
+        self.initialize(
+            handlers.AdminConsensusEnginesGetRequest(
+                MockConsensusRegistry(self._engines)),
+            admin_pb2.AdminConsensusEnginesGetRequest,
+            admin_pb2.AdminConsensusEnginesGetResponse,
+        )
+
+    def test_consensus_engines_get(self):
+        """Verifies the registered engines are returned with their status.
+        """
+        response = self.make_request()
+
+        self.assertEqual(self.status.OK, response.status)
+        self.assertEqual(self._engines, list(response.engines))
+
+
+class TestAdminForksGetRequests(ClientHandlerTestCase):
+    def setUp(self):
+        self._journal = MockJournal()
+        self._fork_tracker = MockForkTracker([
+            Fork(head_id='d2', head_block_num=3, fork_point_id='a',
+                 length=3),
+        ])
+        self.initialize(
+            handlers.AdminForksGetRequest(self._fork_tracker, self._journal),
+            admin_pb2.AdminForksGetRequest,
+            admin_pb2.AdminForksGetResponse,
+        )
+
+    def test_forks_get(self):
+        """Verifies the forks off the chain head are returned with the
+        validation status of their heads.
+        """
+        self._journal.chain_head = Block(header_signature='c')
+
+        response = self.make_request()
+
+        self.assertEqual(self.status.OK, response.status)
+        self.assertEqual('c', response.chain_head_id)
+        self.assertEqual('c', self._fork_tracker.chain_head_id)
+        self.assertEqual(
+            [admin_pb2.AdminForksGetResponse.Fork(
+                head_id='d2', head_block_num=3, fork_point_id='a', length=3,
+                head_status='Invalid')],
+            list(response.forks))
+
+    def test_forks_get_no_chain_head(self):
+        """Verifies NOT_READY is sent when there is no chain head yet.
+        """
+        response = self.make_request()
+
+        self.assertEqual(self.status.NOT_READY, response.status)
+
+
+class MockJournal:
+    def __init__(self):
+        self.paused_for = None
+        self.chain_head = None
+
+    def pause_publishing(self, timeout=0):
+        self.paused_for = timeout
+
+    def resume_publishing(self):
+        self.paused_for = None
+
+    def block_validation_result(self, block_id):
+        return BlockStatus.Invalid
+
+
+class MockRegistry:
+    def __init__(self, dump):
+        self._dump = dump
+
+    def dump_metrics(self):
+        return self._dump
+
+
+class MockConsensusRegistry:
+    def __init__(self, engines):
+        self._engines = engines
+
+    def get_engines(self):
+        return self._engines
+
+
+class MockForkTracker:
+    def __init__(self, forks):
+        self._forks = forks
+        self.chain_head_id = None
+
+    def forks(self, chain_head_id):
+        self.chain_head_id = chain_head_id
+        return self._forks
@@ -0,0 +1,181 @@
+# Copyright 2018 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#     http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+
+import os
+import shutil
+import tempfile
+
+import sawtooth_validator.state.client_handlers as handlers
+from sawtooth_validator.database.native_lmdb import NativeLmdbDatabase
+from sawtooth_validator.protobuf import client_identity_pb2
+from sawtooth_validator.protobuf.identity_pb2 import Policy
+from sawtooth_validator.protobuf.identity_pb2 import PolicyList
+from sawtooth_validator.protobuf.identity_pb2 import Role
+from sawtooth_validator.protobuf.identity_pb2 import RoleList
+from sawtooth_validator.state.identity_view import _create_policy_address
+from sawtooth_validator.state.identity_view import _create_role_address
+from sawtooth_validator.state.merkle import MerkleDatabase
+from test_client_request_handlers.base_case import ClientHandlerTestCase
+from test_client_request_handlers.mocks import MockBlockStore
+
+
+def make_identity_db_and_store(base_dir):
+    """Creates a database with a single state root, holding:
+        * policy "allow_a" - permits key 'a', denies everything else
+        * policy "default" - denies key 'b', permits everything else
+        * role "transactor" - using policy "allow_a"
+    """
+    database = NativeLmdbDatabase(
+        os.path.join(base_dir, 'client_handlers_identity_db.lmdb'),
+        indexes=MerkleDatabase.create_index_configuration(),
+        _size=10 * 1024 * 1024)
+    store = MockBlockStore(size=0)
+
+    allow_a = Policy(name='allow_a', entries=[
+        Policy.Entry(type=Policy.PERMIT_KEY, key='a'),
+        Policy.Entry(type=Policy.DENY_KEY, key='*')])
+    default = Policy(name='default', entries=[
+        Policy.Entry(type=Policy.DENY_KEY, key='b'),
+        Policy.Entry(type=Policy.PERMIT_KEY, key='*')])
+    transactor = Role(name='transactor', policy_name='allow_a')
+
+    data = {
+        _create_policy_address('allow_a'):
+            PolicyList(policies=[allow_a]).SerializeToString(),
+        _create_policy_address('default'):
+            PolicyList(policies=[default]).SerializeToString(),
+        _create_role_address('transactor'):
+            RoleList(roles=[transactor]).SerializeToString(),
+    }
+
+    root = MerkleDatabase(database).update(data, virtual=False)
+    store.add_block('1', root)
+
+    return database, store, [root]
+
+
+class TestIdentityPolicyListRequests(ClientHandlerTestCase):
+    def setUp(self):
+        self._temp_dir = tempfile.mkdtemp()
+        db, store, roots = make_identity_db_and_store(self._temp_dir)
+        self.initialize(
+            handlers.IdentityPolicyListRequest(db, store),
+            client_identity_pb2.ClientIdentityPolicyListRequest,
+            client_identity_pb2.ClientIdentityPolicyListResponse,
+            store=store,
+            roots=roots)
+
+    def tearDown(self):
+        shutil.rmtree(self._temp_dir)
+
+    def test_policy_list_request(self):
+        """Verifies requests for the identity policies work properly.
+
+        Expects to find:
+            - a status of OK
+            - the latest state_root
+            - the policies "allow_a" and "default", sorted by name
+            - the role "transactor"
+        """
+        response = self.make_request()
+
+        self.assertEqual(self.status.OK, response.status)
+        self.assertEqual(self.roots[0], response.state_root)
+        self.assertEqual(
+            ['allow_a', 'default'], [p.name for p in response.policies])
+        self.assertEqual(['transactor'], [r.name for r in response.roles])
+
+    def test_policy_list_bad_root(self):
+        """Verifies requests with a malformed state root fail properly.
+        """
+        response = self.make_request(state_root='bad')
+
+        self.assertEqual(self.status.INVALID_ROOT, response.status)
+        self.assertFalse(response.policies)
+
+    def test_policy_list_no_genesis(self):
+        """Verifies requests fail properly without a genesis block.
+        """
+        self.break_genesis()
+        response = self.make_request()
+
+        self.assertEqual(self.status.NOT_READY, response.status)
+
+
+class TestIdentityEvaluateRequests(ClientHandlerTestCase):
+    def setUp(self):
+        self._temp_dir = tempfile.mkdtemp()
+        db, store, roots = make_identity_db_and_store(self._temp_dir)
+        self.initialize(
+            handlers.IdentityEvaluateRequest(db, store),
+            client_identity_pb2.ClientIdentityEvaluateRequest,
+            client_identity_pb2.ClientIdentityEvaluateResponse,
+            store=store,
+            roots=roots)
+
+    def tearDown(self):
+        shutil.rmtree(self._temp_dir)
+
+    def test_evaluate_set_role(self):
+        """Verifies a key is evaluated against the policy of a set role.
+
+        Expects "transactor" to resolve to the policy "allow_a", which
+        permits 'a' and denies 'c'.
+        """
+        response = self.make_request(role='transactor', public_key='a')
+
+        self.assertEqual(self.status.OK, response.status)
+        self.assertEqual(self.roots[0], response.state_root)
+        self.assertTrue(response.allowed)
+        self.assertEqual('transactor', response.role)
+        self.assertEqual('allow_a', response.policy_name)
+        self.assertEqual('a', response.matched_entry.key)
+
+        response = self.make_request(role='transactor', public_key='c')
+
+        self.assertEqual(self.status.OK, response.status)
+        self.assertFalse(response.allowed)
+        self.assertEqual('*', response.matched_entry.key)
+
+    def test_evaluate_falls_back_to_parent_role(self):
+        """Verifies an unset role falls back to its parent role.
+        """
+        response = self.make_request(
+            role='transactor.batch_signer', public_key='c')
+
+        self.assertEqual(self.status.OK, response.status)
+        self.assertFalse(response.allowed)
+        self.assertEqual('transactor', response.role)
+        self.assertEqual('allow_a', response.policy_name)
+
+    def test_evaluate_falls_back_to_default(self):
+        """Verifies an unset role without set parents uses the default policy.
+        """
+        response = self.make_request(role='network', public_key='b')
+
+        self.assertEqual(self.status.OK, response.status)
+        self.assertFalse(response.allowed)
+        self.assertEqual('default', response.role)
+        self.assertEqual('default', response.policy_name)
+
+        response = self.make_request(role='network', public_key='c')
+
+        self.assertTrue(response.allowed)
+
+    def test_evaluate_missing_public_key(self):
+        """Verifies requests without a public key fail properly.
+        """
+        response = self.make_request(role='transactor')
+
+        self.assertEqual(self.status.INVALID_REQUEST, response.status)
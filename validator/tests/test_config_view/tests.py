@@ -51,7 +51,15 @@ class TestSettingsView(unittest.TestCase):
             TestSettingsView._address('my.setting.list'):
                 TestSettingsView._setting_entry('my.setting.list', '10,11,12'),
             TestSettingsView._address('my.other.list'):
-                TestSettingsView._setting_entry('my.other.list', '13;14;15')
+                TestSettingsView._setting_entry('my.other.list', '13;14;15'),
+            TestSettingsView._address('my.negative'):
+                TestSettingsView._setting_entry('my.negative', '-1'),
+            TestSettingsView._address('my.flag'):
+                TestSettingsView._setting_entry('my.flag', 'Yes'),
+            TestSettingsView._address('my.bad.flag'):
+                TestSettingsView._setting_entry('my.bad.flag', 'maybe'),
+            TestSettingsView._address('other.setting'):
+                TestSettingsView._setting_entry('other.setting', 'other')
         }, virtual=False)
 
     def tearDown(self):
@@ -157,6 +165,60 @@ class TestSettingsView(unittest.TestCase):
             [10, 11, 12],
             settings_view.get_setting_list('my.setting.list', value_type=int))
 
+    def test_get_setting_u64(self):
+        """Verifies the correct operation of get_setting_u64() by using it to
+        get the config setting stored as "my.setting" as the int 10, and to
+        return the default value for unknown settings and for values which
+        are not unsigned integers.
+        """
+        settings_view = self._settings_view_factory.create_settings_view(
+            self._current_root_hash)
+
+        self.assertEqual(10, settings_view.get_setting_u64('my.setting'))
+        self.assertEqual(
+            5, settings_view.get_setting_u64('non-existant.setting', 5))
+        self.assertEqual(5, settings_view.get_setting_u64('my.negative', 5))
+        self.assertEqual(
+            5, settings_view.get_setting_u64('my.setting.list', 5))
+
+    def test_get_setting_bool(self):
+        """Verifies the correct operation of get_setting_bool() by using it to
+        get the config setting stored as "my.flag" as True, and to return the
+        default value for unknown settings and for values which are not
+        booleans.
+        """
+        settings_view = self._settings_view_factory.create_settings_view(
+            self._current_root_hash)
+
+        self.assertTrue(settings_view.get_setting_bool('my.flag'))
+        self.assertFalse(
+            settings_view.get_setting_bool('non-existant.setting', False))
+        self.assertFalse(settings_view.get_setting_bool('my.bad.flag', False))
+
+    def test_get_settings(self):
+        """Verifies the correct operation of get_settings() by using it to
+        get every setting whose key starts with "my.", and no others.
+        """
+        settings_view = self._settings_view_factory.create_settings_view(
+            self._current_root_hash)
+
+        self.assertEqual(
+            {
+                'my.setting': '10',
+                'my.setting.list': '10,11,12',
+                'my.other.list': '13;14;15',
+                'my.negative': '-1',
+                'my.flag': 'Yes',
+                'my.bad.flag': 'maybe',
+            },
+            settings_view.get_settings('my.'))
+
+        self.assertEqual(
+            {'my.setting': '10', 'my.setting.list': '10,11,12'},
+            settings_view.get_settings('my.setting'))
+
+        self.assertEqual({}, settings_view.get_settings('sawtooth.'))
+
     @staticmethod
     def _address(key):
         return '000000' + _key_to_address(key)
@@ -3,6 +3,7 @@ from unittest.mock import Mock
 
 from sawtooth_validator.consensus import handlers
 from sawtooth_validator.consensus.proxy import ConsensusProxy
+from sawtooth_validator.consensus.proxy import MAX_REPLAY_COMMITS
 from sawtooth_validator.consensus.proxy import UnknownBlock
 from sawtooth_validator.consensus.registry import ConsensusRegistry, EngineInfo
 from sawtooth_validator.protobuf.block_pb2 import BlockHeader
@@ -185,6 +186,21 @@ class TestHandlers(unittest.TestCase):
         self.assertEqual(response.status, handler.response_class.OK)
         self.mock_proxy.chain_head_get.assert_called_with()
 
+    def test_consensus_replay_commits_handler(self):
+        self.mock_proxy.replay_commits.return_value = ['bb', 'cc']
+        handler = handlers.ConsensusReplayCommitsHandler(self.mock_proxy)
+        request_class = handler.request_class
+        request = request_class()
+        request.block_id = b"test"
+        request.max_count = 2
+        result = handler.handle('mock-id', request.SerializeToString())
+        response = result.message_out
+        self.assertEqual(response.status, handler.response_class.OK)
+        self.assertEqual(response.replayed, 2)
+        self.assertEqual(response.last_block_id, bytes([0xcc]))
+        self.mock_proxy.replay_commits.assert_called_with(
+            request.block_id, 2)
+
     def test_consensus_settings_get_handler(self):
         self.mock_proxy.settings_get.return_value = [('key', 'value')]
         handler = handlers.ConsensusSettingsGetHandler(self.mock_proxy)
@@ -216,6 +232,7 @@ class TestProxy(unittest.TestCase):
 
     def setUp(self):
         self._mock_block_manager = MockBlockManager()
+        self._mock_block_store = Mock()
         self._mock_journal = Mock()
         self._mock_gossip = MockGossip()
         self._mock_identity_signer = MockIdentitySigner()
@@ -225,6 +242,7 @@ class TestProxy(unittest.TestCase):
         self._consensus_notifier = Mock()
         self._proxy = ConsensusProxy(
             block_manager=self._mock_block_manager,
+            block_store=self._mock_block_store,
             journal=self._mock_journal,
             gossip=self._mock_gossip,
             identity_signer=self._mock_identity_signer,
@@ -354,6 +372,41 @@ class TestProxy(unittest.TestCase):
             self._proxy.chain_head_get(),
             chain_head)
 
+    def test_replay_commits(self):
+        """Test that the commits after a block are replayed oldest first, a
+        page at a time, and that an unknown block is refused.
+        """
+        self._proxy._block_store = MockBlockStore(
+            ['aa', 'bb', 'cc', 'dd', 'ee'])
+        notify = self._consensus_notifier.notify_block_commit
+
+        self.assertEqual(
+            self._proxy.replay_commits(bytes([0xaa]), 2), ['bb', 'cc'])
+        self.assertEqual(
+            [call[0][0] for call in notify.call_args_list], ['bb', 'cc'])
+
+        notify.reset_mock()
+        self.assertEqual(
+            self._proxy.replay_commits(bytes([0xcc]), 2), ['dd', 'ee'])
+        self.assertEqual(self._proxy.replay_commits(bytes([0xee]), 2), [])
+        self.assertEqual(
+            [call[0][0] for call in notify.call_args_list], ['dd', 'ee'])
+
+        with self.assertRaises(UnknownBlock):
+            self._proxy.replay_commits(bytes([0xff]), 2)
+
+    def test_replay_commits_limit(self):
+        """Test that no more than MAX_REPLAY_COMMITS commits are replayed for
+        one request, whatever count is asked for.
+        """
+        block_ids = ['{:04x}'.format(i) for i in range(MAX_REPLAY_COMMITS + 2)]
+        self._proxy._block_store = MockBlockStore(block_ids)
+
+        for max_count in (0, MAX_REPLAY_COMMITS + 1):
+            self.assertEqual(
+                self._proxy.replay_commits(bytes(2), max_count),
+                block_ids[1:MAX_REPLAY_COMMITS + 1])
+
     @unittest.skip("Test will have to be rethought due to removal of "
                    "blockwrapper from proxy")
     def test_settings_get(self):
@@ -456,6 +509,29 @@ class MockBlockManager:
         self._cache[key] = value
 
 
+class MockBlockStore:
+    """A block store holding a single chain of blocks, given by id in block
+    number order.
+    """
+
+    def __init__(self, block_ids):
+        self._blocks = [
+            Mock(header_signature=block_id, block_num=block_num)
+            for block_num, block_id in enumerate(block_ids)
+        ]
+
+    def __getitem__(self, block_id):
+        for block in self._blocks:
+            if block.header_signature == block_id:
+                return block
+        raise KeyError(block_id)
+
+    def get_block_iter(self, start_block=None, reverse=True):
+        if reverse:
+            return iter(self._blocks[start_block.block_num::-1])
+        return iter(self._blocks[start_block.block_num:])
+
+
 class MockConsensusRegistry(Mock):
     def get_active_engine_info(self):
         return EngineInfo('mock-id', 'mock-name', 'mock-version',
@@ -253,7 +253,8 @@ class ClientEventsSubscribeValidationHandlerTest(unittest.TestCase):
                 event_type="test_event",
                 filters=[
                     FILTER_FACTORY.create(key="test", match_string="test")])],
-            "0" * 128)
+            "0" * 128,
+            acknowledged=False)
         self.assertEqual(HandlerStatus.RETURN_AND_PASS, response.status)
         self.assertEqual(client_event_pb2.ClientEventsSubscribeResponse.OK,
                          response.message_out.status)
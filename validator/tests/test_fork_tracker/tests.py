@@ -0,0 +1,123 @@
+# Copyright 2018 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#     http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+# ------------------------------------------------------------------------------
+
+import unittest
+
+from sawtooth_validator.journal.block_manager import UnknownBlock
+from sawtooth_validator.journal.block_wrapper import NULL_BLOCK_IDENTIFIER
+from sawtooth_validator.journal.fork_tracker import Fork
+from sawtooth_validator.journal.fork_tracker import ForkTracker
+from sawtooth_validator.protobuf import block_pb2
+
+
+class TestForkTracker(unittest.TestCase):
+    def setUp(self):
+        self.block_manager = MockBlockManager()
+        self.fork_tracker = ForkTracker(self.block_manager)
+
+    def receive(self, block_num, block_id, previous_block_id):
+        self.block_manager.put(_build_block(
+            block_num, block_id, previous_block_id))
+        self.fork_tracker.block_received(block_id)
+
+    def test_forks(self):
+        """
+        Test that each branch off the current chain is listed with where it
+        branches from, longest first, and that the current chain is not.
+        """
+        self.receive(0, 'A', NULL_BLOCK_IDENTIFIER)
+        self.receive(1, 'B', 'A')
+        self.receive(2, 'C', 'B')
+        self.receive(1, 'B2', 'A')
+        self.receive(2, 'C2', 'B2')
+        self.receive(3, 'D2', 'C2')
+        self.receive(2, 'C3', 'B')
+
+        self.assertEqual(
+            [Fork(head_id='D2', head_block_num=3, fork_point_id='A',
+                  length=3),
+             Fork(head_id='C3', head_block_num=2, fork_point_id='B',
+                  length=1)],
+            self.fork_tracker.forks('C'))
+
+        self.assertEqual(
+            [Fork(head_id='C', head_block_num=2, fork_point_id='A',
+                  length=2),
+             Fork(head_id='C3', head_block_num=2, fork_point_id='A',
+                  length=2)],
+            self.fork_tracker.forks('D2'))
+
+    def test_dropped_forks(self):
+        """
+        Test that forks the block manager no longer holds are not listed.
+        """
+        self.receive(0, 'A', NULL_BLOCK_IDENTIFIER)
+        self.receive(1, 'B', 'A')
+        self.receive(1, 'B2', 'A')
+
+        self.block_manager.drop('B2')
+
+        self.assertEqual([], self.fork_tracker.forks('B'))
+
+
+class MockBlockManager:
+    """Holds blocks by id, and finds the blocks on one branch but not another
+    as the block manager does.
+    """
+
+    def __init__(self):
+        self._blocks = {}
+
+    def put(self, block):
+        self._blocks[block.header_signature] = block
+
+    def drop(self, block_id):
+        del self._blocks[block_id]
+
+    def get(self, block_ids):
+        return iter([self._blocks[block_id] for block_id in block_ids])
+
+    def __contains__(self, block_id):
+        return block_id in self._blocks
+
+    def branch_diff(self, tip, exclude):
+        if tip not in self._blocks or exclude not in self._blocks:
+            raise UnknownBlock()
+
+        excluded = set(self._branch(exclude))
+        diff = []
+        for block_id in self._branch(tip):
+            if block_id in excluded:
+                break
+            diff.append(self._blocks[block_id])
+        return iter(diff)
+
+    def _branch(self, tip):
+        block_id = tip
+        while block_id in self._blocks:
+            yield block_id
+            header = block_pb2.BlockHeader()
+            header.ParseFromString(self._blocks[block_id].header)
+            block_id = header.previous_block_id
+
+
+def _build_block(block_num, block_id, previous_block_id):
+    header = block_pb2.BlockHeader(
+        block_num=block_num,
+        previous_block_id=previous_block_id)
+
+    return block_pb2.Block(
+        header_signature=block_id,
+        header=header.SerializeToString())
@@ -166,6 +166,76 @@ class TestSawtoothMerkleTrie(unittest.TestCase):
         self.assertEqual([("010202", {"my_data": 2})],
                          list(iter(self.trie.leaves('0102'))))
 
+    def test_merkle_trie_proof(self):
+        new_root = self.update({
+            "010101": {"my_data": 1},
+            "010202": {"my_data": 2},
+            "010303": {"my_data": 3}
+        }, [], virtual=False)
+        self.set_merkle_root(new_root)
+
+        value, proof = self.trie.get_with_proof("010202")
+        self.assertEqual({"my_data": 2}, value)
+
+        self.assertTrue(
+            MerkleDatabase.verify_proof(new_root, "010202", value, proof))
+        self.assertFalse(
+            MerkleDatabase.verify_proof(
+                new_root, "010202", {"my_data": 3}, proof))
+        self.assertFalse(
+            MerkleDatabase.verify_proof(new_root, "010303", value, proof))
+
+        with self.assertRaises(KeyError):
+            self.trie.get_with_proof("010404")
+
+    def test_merkle_trie_leaves_page(self):
+        new_root = self.update({
+            "010303": {"my_data": 3},
+            "010101": {"my_data": 1},
+            "020101": {"my_data": 4},
+            "010202": {"my_data": 2}
+        }, [], virtual=False)
+        self.set_merkle_root(new_root)
+
+        leaves, next_address = self.trie.leaves_page("01", limit=2)
+        self.assertEqual(
+            [("010101", {"my_data": 1}), ("010202", {"my_data": 2})],
+            leaves)
+        self.assertEqual("010303", next_address)
+
+        leaves, next_address = self.trie.leaves_page(
+            "01", start_address=next_address, limit=2)
+        self.assertEqual([("010303", {"my_data": 3})], leaves)
+        self.assertIsNone(next_address)
+
+        leaves, next_address = self.trie.leaves_page("03")
+        self.assertEqual([], leaves)
+        self.assertIsNone(next_address)
+
+    def test_merkle_trie_diff(self):
+        first_root = self.update({
+            "010101": {"my_data": 1},
+            "010202": {"my_data": 2},
+            "020101": {"my_data": 3}
+        }, [], virtual=False)
+        self.set_merkle_root(first_root)
+
+        second_root = self.update({
+            "010202": {"my_data": 4},
+            "030101": {"my_data": 5}
+        }, ["020101"], virtual=False)
+
+        self.assertEqual(
+            [("010202", {"my_data": 2}, {"my_data": 4}),
+             ("020101", {"my_data": 3}, None),
+             ("030101", None, {"my_data": 5})],
+            MerkleDatabase.diff(self.lmdb, first_root, second_root))
+        self.assertEqual(
+            [], MerkleDatabase.diff(self.lmdb, first_root, first_root))
+
+        with self.assertRaises(KeyError):
+            MerkleDatabase.diff(self.lmdb, first_root, "00" * 32)
+
     # assertions
     def assert_value_at_address(self, address, value, ishash=False):
         self.assertEqual(
@@ -0,0 +1,115 @@
+# Copyright 2018 Intel Corporation
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#     http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+# ------------------------------------------------------------------------------
+import unittest
+
+from sawtooth_validator.gossip.peer_scores import PeerScoreboard
+from sawtooth_validator.gossip.peer_scores import MAXIMUM_SCORE
+from sawtooth_validator.gossip.peer_scores import INVALID_BLOCK_PENALTY
+from sawtooth_validator.gossip.peer_scores import MALFORMED_MESSAGE_PENALTY
+
+
+class TestPeerScoreboard(unittest.TestCase):
+    def setUp(self):
+        self.now = 0.0
+        self.banned = []
+        # Connections conn_a and conn_a2 are both to peer_a
+        self.public_keys = {'conn_a': 'peer_a', 'conn_a2': 'peer_a'}
+        self.scoreboard = PeerScoreboard(
+            self.public_keys.get,
+            on_ban=self.banned.append,
+            half_life=100,
+            deprioritize_threshold=50,
+            ban_threshold=0,
+            ban_duration=60,
+            clock=lambda: self.now)
+
+    def test_penalties_decay(self):
+        """
+        Test that penalties lower a peer's score, and that half of the lost
+        points recover every half life.
+        """
+        self.scoreboard.record_malformed_message('conn_a')
+        self.assertEqual(
+            MAXIMUM_SCORE - MALFORMED_MESSAGE_PENALTY,
+            self.scoreboard.score('conn_a'))
+
+        self.now = 100.0
+        self.assertAlmostEqual(
+            MAXIMUM_SCORE - MALFORMED_MESSAGE_PENALTY / 2,
+            self.scoreboard.score('conn_a'))
+
+    def test_scores_follow_public_key(self):
+        """
+        Test that a peer keeps its score when it reconnects.
+        """
+        self.scoreboard.record_malformed_message('conn_a')
+        self.assertEqual(
+            MAXIMUM_SCORE - MALFORMED_MESSAGE_PENALTY,
+            self.scoreboard.score('conn_a2'))
+        self.assertEqual(MAXIMUM_SCORE, self.scoreboard.score('conn_b'))
+
+    def test_invalid_block(self):
+        """
+        Test that the peer that signed and sent a block is penalized when the
+        block is found to be invalid, and that unknown blocks are ignored.
+        """
+        self.scoreboard.record_block_source('block', 'peer_a', 'conn_a')
+        self.scoreboard.record_invalid_block('block')
+        self.scoreboard.record_invalid_block('unknown_block')
+
+        self.assertEqual(
+            MAXIMUM_SCORE - INVALID_BLOCK_PENALTY,
+            self.scoreboard.score('conn_a'))
+
+    def test_relayed_invalid_block(self):
+        """
+        Test that a peer relaying invalid blocks signed by another validator
+        is neither penalized nor banned.
+        """
+        for block_id in ['block_1', 'block_2', 'block_3']:
+            self.scoreboard.record_block_source(block_id, 'peer_c', 'conn_a')
+            self.scoreboard.record_invalid_block(block_id)
+
+        self.assertEqual(MAXIMUM_SCORE, self.scoreboard.score('conn_a'))
+        self.assertFalse(self.scoreboard.is_banned('conn_a'))
+        self.assertEqual([], self.banned)
+
+    def test_deprioritize_and_ban(self):
+        """
+        Test that low scoring peers are sent gossip last, and that peers
+        reaching the ban threshold are banned until the ban ends.
+        """
+        self.scoreboard.record_block_source('block_1', 'peer_a', 'conn_a')
+        self.scoreboard.record_invalid_block('block_1')
+        self.scoreboard.record_block_source('block_2', 'peer_a', 'conn_a')
+        self.scoreboard.record_invalid_block('block_2')
+
+        self.assertTrue(self.scoreboard.is_deprioritized('conn_a'))
+        self.assertEqual(
+            ['conn_b', 'conn_a'],
+            self.scoreboard.prioritize(['conn_a', 'conn_b']))
+
+        self.scoreboard.record_block_source('block_3', 'peer_a', 'conn_a')
+        self.scoreboard.record_invalid_block('block_3')
+
+        self.assertEqual(['conn_a'], self.banned)
+        self.assertTrue(self.scoreboard.is_banned('conn_a2'))
+        self.assertEqual(['conn_b'], self.scoreboard.prioritize(
+            ['conn_a', 'conn_b']))
+        self.assertEqual(
+            [('peer_a', 50, 60)], self.scoreboard.scores())
+
+        self.now = 60.0
+        self.assertFalse(self.scoreboard.is_banned('conn_a'))
@@ -31,9 +31,11 @@ from sawtooth_validator.protobuf.block_pb2 import Block
 from sawtooth_validator.protobuf.events_pb2 import Event
 from sawtooth_validator.protobuf.transaction_receipt_pb2 import \
     TransactionReceipt
+from sawtooth_validator.protobuf.transaction_receipt_pb2 import StateChange
 from sawtooth_validator.journal.block_wrapper import BlockWrapper
 from sawtooth_validator.gossip.permission_verifier import PermissionVerifier
 from sawtooth_validator.gossip.permission_verifier import IdentityCache
+from sawtooth_validator.gossip.permission_verifier import evaluate_policy
 from sawtooth_validator.gossip.identity_observer import IdentityObserver
 from test_permission_verifier.mocks import MockIdentityViewFactory
 from test_permission_verifier.mocks import make_policy
@@ -175,6 +177,62 @@ class TestPermissionVerifier(unittest.TestCase):
         allowed = self.permission_verifier.is_batch_signer_authorized(batch)
         self.assertFalse(allowed)
 
+    def test_transactor_role_key_prefix(self):
+        """
+        Test that policy entries match keys exactly, as block validation
+        does, rather than by prefix or pattern.
+            1. Set policy to permit the signing key's prefix. Batch should be
+               rejected.
+            2. Set policy to deny the signing key's prefix, before permitting
+               all. Batch should be allowed.
+            3. Set policy to permit a pattern the signing key matches. Batch
+               should be rejected.
+        """
+        prefix = self.public_key[:6]
+        self._identity_view_factory.add_policy(
+            "policy1", ["PERMIT_KEY " + prefix + "*"])
+        self._identity_view_factory.add_role("transactor", "policy1")
+        batch = self._create_batches(1, 1)[0]
+        allowed = self.permission_verifier.is_batch_signer_authorized(batch)
+        self.assertFalse(allowed)
+
+        self._identity_cache.forked()
+        self._identity_view_factory.add_policy(
+            "policy1", ["DENY_KEY " + prefix + "*", "PERMIT_KEY *"])
+        self._identity_view_factory.add_role("transactor", "policy1")
+        batch = self._create_batches(1, 1)[0]
+        allowed = self.permission_verifier.is_batch_signer_authorized(batch)
+        self.assertTrue(allowed)
+
+        self._identity_cache.forked()
+        self._identity_view_factory.add_policy(
+            "policy1", ["PERMIT_KEY " + self.public_key[:-1] + "?"])
+        self._identity_view_factory.add_role("transactor", "policy1")
+        batch = self._create_batches(1, 1)[0]
+        allowed = self.permission_verifier.is_batch_signer_authorized(batch)
+        self.assertFalse(allowed)
+
+    def test_evaluate_policy(self):
+        """
+        Test that the first entry matching a key decides whether the key is
+        permitted, and that no entry is returned when none match.
+        """
+        policy = make_policy(
+            "policy1",
+            ["DENY_KEY 02ab", "PERMIT_KEY 02cd", "DENY_KEY 02*"])
+
+        entry = evaluate_policy("02ab", policy)
+        self.assertEqual(entry.key, "02ab")
+
+        entry = evaluate_policy("02cd", policy)
+        self.assertEqual(entry.key, "02cd")
+
+        self.assertIsNone(evaluate_policy("02ef", policy))
+
+        policy = make_policy("policy2", ["DENY_KEY 02ab", "PERMIT_KEY *"])
+        entry = evaluate_policy("02ef", policy)
+        self.assertEqual(entry.key, "*")
+
     def test_transactor_batch_signer(self):
         """
         Test that role: "transactor.batch_signer" is checked properly.
@@ -498,6 +556,30 @@ class TestIdentityObserver(unittest.TestCase):
             self._identity_cache.get_policy("policy1", lambda: "state_root"),
             identity_view.get_policy("policy1"))
 
+    def test_identity_write_without_event(self):
+        """
+        Test that if a transaction writes to the identity namespace without
+        an "identity/update" event, every value in the cache is invalidated,
+        and that writes elsewhere invalidate nothing.
+        """
+        block1 = self.create_block()
+        self._identity_obsever.chain_update(block1, [])
+        self._identity_cache.get_role("network", lambda: "state_root")
+        self._identity_cache.get_policy("policy1", lambda: "state_root")
+
+        block2 = self.create_block("abcdef1234567890")
+        receipt = TransactionReceipt(state_changes=[
+            StateChange(address="1cf126" + "0" * 64)])
+        self._identity_obsever.chain_update(block2, [receipt])
+        self.assertNotEqual(self._identity_cache["network"], None)
+        self.assertNotEqual(self._identity_cache["policy1"], None)
+
+        receipt = TransactionReceipt(state_changes=[
+            StateChange(address="00001d" + "0" * 64)])
+        self._identity_obsever.chain_update(block2, [receipt])
+        self.assertEqual(self._identity_cache["network"], None)
+        self.assertEqual(self._identity_cache["policy1"], None)
+
     def test_fork(self):
         """
         Test that if there is a fork, all values in the cache will be
@@ -623,3 +705,38 @@ class TestIdentityCache(unittest.TestCase):
         self.assertEqual(
             self._identity_cache.get_role("network", lambda: "state_root"),
             identity_view.get_role("network"))
+
+    def test_state_root_cache(self):
+        """
+        Test that values read from state are cached by state root.
+            1. Read a policy at a state root, then change it. Reading at the
+               same state root should return the cached policy.
+            2. Reading at a new state root should return the changed policy.
+            3. Reading at a third state root evicts the first, so reading at
+               the first state root again returns the changed policy.
+        """
+        identity_cache = IdentityCache(
+            self._identity_view_factory, state_root_cache_size=2)
+
+        self._identity_view_factory.add_policy("policy1", ["PERMIT_KEY key"])
+        original = identity_cache.get_policy(
+            "policy1", lambda: "root1", from_state=True)
+
+        self._identity_view_factory.add_policy("policy1", ["DENY_KEY key"])
+        self.assertEqual(
+            identity_cache.get_policy(
+                "policy1", lambda: "root1", from_state=True),
+            original)
+
+        changed = identity_cache.get_policy(
+            "policy1", lambda: "root2", from_state=True)
+        self.assertNotEqual(changed, original)
+
+        identity_cache.get_policy("policy1", lambda: "root3", from_state=True)
+        self.assertEqual(
+            identity_cache.get_policy(
+                "policy1", lambda: "root1", from_state=True),
+            changed)
+
+        # Values read from state are not cached for the chain head
+        self.assertEqual(len(identity_cache), 0)
@@ -30,6 +30,9 @@ from sawtooth_validator.protobuf.client_receipt_pb2 import \
 from sawtooth_validator.protobuf.client_receipt_pb2 import \
     ClientReceiptGetResponse
 from sawtooth_validator.protobuf.events_pb2 import Event
+from sawtooth_validator.protobuf.block_pb2 import Block
+from sawtooth_validator.protobuf.block_pb2 import BlockHeader
+from sawtooth_validator.journal.block_wrapper import BlockWrapper
 
 
 class ReceiptStoreTest(unittest.TestCase):
@@ -88,6 +91,220 @@ class ReceiptStoreTest(unittest.TestCase):
         with self.assertRaises(KeyError):
             receipt_store.get('unknown')
 
+    def test_list_by_block(self):
+        """Tests that the receipts of a committed block can be listed in
+        pages, in the order of the block's transactions.
+        """
+        receipt_store = TransactionReceiptStore(DictDatabase())
+
+        receipts = [
+            TransactionReceipt(transaction_id='txn{}'.format(i))
+            for i in range(5)
+        ]
+        receipt_store.chain_update(_make_block('block1', 1), receipts[:3])
+        receipt_store.chain_update(_make_block('block2', 2), receipts[3:])
+
+        page, start = receipt_store.list_by_block('block1', limit=2)
+        self.assertEqual(receipts[:2], page)
+        self.assertIsNotNone(start)
+
+        page, start = receipt_store.list_by_block(
+            'block1', start=start, limit=2)
+        self.assertEqual(receipts[2:3], page)
+        self.assertIsNone(start)
+
+        page, start = receipt_store.list_by_block('block2')
+        self.assertEqual(receipts[3:], page)
+        self.assertIsNone(start)
+
+        page, start = receipt_store.list_by_block('unknown')
+        self.assertEqual([], page)
+        self.assertIsNone(start)
+
+    def test_list_by_address_prefix(self):
+        """Tests that the receipts of committed transactions can be listed by
+        the prefix of the addresses they changed, and that a receipt is only
+        listed once per page.
+        """
+        receipt_store = TransactionReceiptStore(DictDatabase())
+
+        receipts = [
+            TransactionReceipt(
+                transaction_id='txn0',
+                state_changes=[
+                    StateChange(address='a10000', type=StateChange.SET),
+                    StateChange(address='a10001', type=StateChange.SET),
+                ]),
+            TransactionReceipt(
+                transaction_id='txn1',
+                state_changes=[
+                    StateChange(address='a10002', type=StateChange.DELETE),
+                ]),
+            TransactionReceipt(
+                transaction_id='txn2',
+                state_changes=[
+                    StateChange(address='b10000', type=StateChange.SET),
+                ]),
+        ]
+        receipt_store.chain_update(_make_block('block1'), receipts)
+
+        page, start = receipt_store.list_by_address_prefix('a1')
+        self.assertEqual(receipts[:2], page)
+        self.assertIsNone(start)
+
+        page, start = receipt_store.list_by_address_prefix('a1', limit=2)
+        self.assertEqual(receipts[:1], page)
+        page, start = receipt_store.list_by_address_prefix(
+            'a1', start=start, limit=2)
+        self.assertEqual(receipts[1:2], page)
+        self.assertIsNone(start)
+
+        page, _ = receipt_store.list_by_address_prefix('b10000')
+        self.assertEqual(receipts[2:], page)
+
+        with self.assertRaises(ValueError):
+            receipt_store.list_by_address_prefix('b1', start='a1')
+
+    def test_fork_switch(self):
+        """Tests that committing a block deletes the receipts of the blocks
+        at or above its height, which were committed on an abandoned fork.
+        """
+        receipt_store = TransactionReceiptStore(DictDatabase())
+
+        for block_id, block_num, txn_id in [
+                ('block1', 1, 'txn1'),
+                ('block2a', 2, 'txn2a'),
+                ('block3a', 3, 'txn3a'),
+                ('block2b', 2, 'txn2b'),
+        ]:
+            receipt_store.chain_update(
+                _make_block(block_id, block_num),
+                [TransactionReceipt(
+                    transaction_id=txn_id,
+                    state_changes=[StateChange(address='a10000')])])
+
+        receipt_store.get('txn1')
+        receipt_store.get('txn2b')
+        for txn_id in ('txn2a', 'txn3a'):
+            with self.assertRaises(KeyError):
+                receipt_store.get(txn_id)
+        for block_id in ('block2a', 'block3a'):
+            page, _ = receipt_store.list_by_block(block_id)
+            self.assertEqual([], page)
+
+        page, _ = receipt_store.list_by_address_prefix('a1')
+        self.assertEqual(
+            ['txn1', 'txn2b'], [receipt.transaction_id for receipt in page])
+
+        # Committing the same block again keeps its receipts
+        receipt_store.chain_update(
+            _make_block('block2b', 2),
+            [TransactionReceipt(transaction_id='txn2b')])
+        receipt_store.get('txn2b')
+
+    def test_fork_switch_keeps_shared_transactions(self):
+        """Tests that when the chain switches to a fork which commits a
+        transaction that was also committed on the abandoned fork, the
+        transaction's receipt is kept, and listed for the new fork's block.
+        """
+        receipt_store = TransactionReceiptStore(DictDatabase())
+
+        def commit(block_id, block_num, previous_block_id, txn_ids):
+            receipt_store.chain_update(
+                _make_block(block_id, block_num, previous_block_id),
+                [TransactionReceipt(
+                    transaction_id=txn_id,
+                    state_changes=[StateChange(address='a10000')])
+                 for txn_id in txn_ids])
+
+        commit('block1', 1, '', ['txn1'])
+        commit('block2a', 2, 'block1', ['shared', 'txn2a'])
+        commit('block3a', 3, 'block2a', ['txn3a'])
+
+        commit('block2b', 2, 'block1', ['txn2b'])
+        commit('block3b', 3, 'block2b', ['shared'])
+
+        self.assertEqual('shared', receipt_store.get('shared').transaction_id)
+        for txn_id in ('txn2a', 'txn3a'):
+            with self.assertRaises(KeyError):
+                receipt_store.get(txn_id)
+
+        page, _ = receipt_store.list_by_block('block3b')
+        self.assertEqual(['shared'], [r.transaction_id for r in page])
+        page, _ = receipt_store.list_by_block('block2a')
+        self.assertEqual([], page)
+
+        page, _ = receipt_store.list_by_address_prefix('a1')
+        self.assertEqual(
+            ['shared', 'txn1', 'txn2b'],
+            [receipt.transaction_id for receipt in page])
+
+    def test_prune_by_blocks(self):
+        """Tests that pruning keeps only the receipts of the most recent
+        blocks, and removes the pruned receipts from the indexes.
+        """
+        receipt_store = TransactionReceiptStore(
+            DictDatabase(), retention_blocks=2)
+
+        for block_num in range(4):
+            receipt_store.chain_update(
+                _make_block('block{}'.format(block_num), block_num),
+                [TransactionReceipt(
+                    transaction_id='txn{}'.format(block_num),
+                    state_changes=[StateChange(address='a10000')])])
+
+        self.assertEqual(2, receipt_store.prune())
+        self.assertEqual(0, receipt_store.prune())
+
+        for block_num in range(2):
+            with self.assertRaises(KeyError):
+                receipt_store.get('txn{}'.format(block_num))
+            page, _ = receipt_store.list_by_block('block{}'.format(block_num))
+            self.assertEqual([], page)
+
+        receipt_store.get('txn2')
+        receipt_store.get('txn3')
+
+        page, _ = receipt_store.list_by_address_prefix('a1')
+        self.assertEqual(
+            ['txn2', 'txn3'], [receipt.transaction_id for receipt in page])
+
+    def test_prune_by_count(self):
+        """Tests that pruning keeps at least the configured number of the
+        most recent receipts, pruning whole blocks.
+        """
+        receipt_store = TransactionReceiptStore(
+            DictDatabase(), retention_count=3)
+
+        for block_num in range(3):
+            receipt_store.chain_update(
+                _make_block('block{}'.format(block_num), block_num),
+                [TransactionReceipt(
+                    transaction_id='txn{}-{}'.format(block_num, i))
+                 for i in range(2)])
+
+        self.assertEqual(2, receipt_store.prune())
+
+        page, _ = receipt_store.list_by_block('block0')
+        self.assertEqual([], page)
+        for block_num in (1, 2):
+            page, _ = receipt_store.list_by_block('block{}'.format(block_num))
+            self.assertEqual(2, len(page))
+
+    def test_no_retention(self):
+        """Tests that receipts are never pruned if no retention policy is
+        configured.
+        """
+        receipt_store = TransactionReceiptStore(DictDatabase())
+
+        for block_num in range(3):
+            receipt_store.chain_update(
+                _make_block('block{}'.format(block_num), block_num),
+                [TransactionReceipt(transaction_id='txn{}'.format(block_num))])
+
+        self.assertEqual(0, receipt_store.prune())
+        receipt_store.get('txn0')
+
 
 class TransactionReceiptGetRequestHandlerTest(unittest.TestCase):
     def test_get_receipts(self):
@@ -120,3 +337,11 @@ class TransactionReceiptGetRequestHandlerTest(unittest.TestCase):
         self.assertEqual(HandlerStatus.RETURN, response.status)
         self.assertEqual(ClientReceiptGetResponse.NO_RESOURCE,
                          response.message_out.status)
+
+
+def _make_block(block_id, block_num=0, previous_block_id=''):
+    return BlockWrapper(Block(
+        header_signature=block_id,
+        header=BlockHeader(
+            block_num=block_num,
+            previous_block_id=previous_block_id).SerializeToString()))